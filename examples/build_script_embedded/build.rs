@@ -0,0 +1,61 @@
+use postgresql_embedded::blocking::{setup_for_build, BuildSetupOptions};
+use postgresql_embedded::Result;
+use std::path::PathBuf;
+
+/// Environment variable that opts in to starting a database for the build; without it, or when
+/// the setup fails (e.g. offline), the build continues with an empty list of columns
+const OPT_IN: &str = "BUILD_SCRIPT_EMBEDDED_DATABASE";
+
+/// Generate the column names of the `users` table from a database started for the build
+fn main() -> Result<()> {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-env-changed={OPT_IN}");
+    println!("cargo::rustc-check-cfg=cfg(build_database)");
+
+    let columns = if std::env::var_os(OPT_IN).is_some() {
+        match generate_columns() {
+            Ok(columns) => {
+                println!("cargo:rustc-cfg=build_database");
+                columns
+            }
+            Err(error) => {
+                println!("cargo:warning=Skipping the build database: {error}");
+                Vec::new()
+            }
+        }
+    } else {
+        println!("cargo:warning=Skipping the build database; set {OPT_IN}=1 to start it");
+        Vec::new()
+    };
+
+    let out_dir = PathBuf::from(std::env::var("OUT_DIR").expect("OUT_DIR"));
+    std::fs::write(
+        out_dir.join("columns.rs"),
+        format!(
+            "pub const USER_COLUMNS: &[&str] = &[{}];\n",
+            columns.join(", ")
+        ),
+    )?;
+    Ok(())
+}
+
+/// Start a database, create the `users` table and get its quoted column names
+fn generate_columns() -> Result<Vec<String>> {
+    let database = setup_for_build(BuildSetupOptions::default())?;
+    let postgresql = database.postgresql();
+    postgresql.run_sql(
+        database.database_name(),
+        "CREATE TABLE users (id integer PRIMARY KEY, name text NOT NULL, email text)",
+    )?;
+    let columns = postgresql.run_sql(
+        database.database_name(),
+        "SELECT column_name FROM information_schema.columns \
+         WHERE table_name = 'users' ORDER BY ordinal_position",
+    )?;
+    database.emit_rustc_env("BUILD_DATABASE_URL");
+    Ok(columns
+        .rows
+        .iter()
+        .map(|row| format!("{:?}", row[0]))
+        .collect())
+}
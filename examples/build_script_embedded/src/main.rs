@@ -0,0 +1,23 @@
+include!(concat!(env!("OUT_DIR"), "/columns.rs"));
+
+fn main() {
+    match option_env!("BUILD_DATABASE_URL") {
+        Some(url) => println!("Database used by the build: {url}"),
+        None => println!("The build did not start a database"),
+    }
+    println!("Columns of the users table: {}", USER_COLUMNS.join(", "));
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_main() {
+        #[cfg(build_database)]
+        assert_eq!(&["id", "name", "email"], USER_COLUMNS);
+        #[cfg(not(build_database))]
+        assert!(USER_COLUMNS.is_empty());
+        main();
+    }
+}
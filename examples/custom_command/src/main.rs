@@ -0,0 +1,62 @@
+use postgresql_embedded::{CommandBuilder, PostgreSQL, Result};
+use std::ffi::{OsStr, OsString};
+use std::path::PathBuf;
+
+/// Builder for a program shipped next to the PostgreSQL binaries; pg_isready stands in for a
+/// custom tool. The connection settings are applied by the server, so the builder only needs
+/// the program name and its own arguments.
+#[derive(Debug, Default)]
+struct ReadyCheckBuilder {
+    program_dir: Option<PathBuf>,
+    timeout: Option<u32>,
+}
+
+impl ReadyCheckBuilder {
+    fn timeout(mut self, seconds: u32) -> Self {
+        self.timeout = Some(seconds);
+        self
+    }
+}
+
+impl CommandBuilder for ReadyCheckBuilder {
+    fn get_program(&self) -> &'static OsStr {
+        "pg_isready".as_ref()
+    }
+
+    fn get_program_dir(&self) -> &Option<PathBuf> {
+        &self.program_dir
+    }
+
+    fn get_args(&self) -> Vec<OsString> {
+        let mut args = Vec::new();
+        if let Some(timeout) = self.timeout {
+            args.push("--timeout".into());
+            args.push(timeout.to_string().into());
+        }
+        args
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let mut postgresql = PostgreSQL::default();
+    postgresql.setup().await?;
+    postgresql.start().await?;
+
+    let (stdout, _stderr) = postgresql
+        .run_command(ReadyCheckBuilder::default().timeout(5))
+        .await?;
+    println!("{}", stdout.trim());
+
+    postgresql.stop().await
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_main() -> Result<()> {
+        main()
+    }
+}
@@ -5,30 +5,34 @@ use crate::error::Error::{AssetHashNotFound, AssetNotFound, ReleaseNotFound, Une
 use crate::error::Result;
 use crate::github::{Asset, Release};
 use crate::version::Version;
-use crate::Error::ArchiveHashMismatch;
+use crate::Error::{ArchiveHashMismatch, PathCollision, PathTooLong};
 use bytes::Bytes;
 use flate2::bufread::GzDecoder;
 use http::Extensions;
 use human_bytes::human_bytes;
 use num_format::{Locale, ToFormattedString};
 use regex::Regex;
-use reqwest::{header, Request, Response};
+use reqwest::{header, Request, Response, StatusCode};
 use reqwest_middleware::{ClientBuilder, ClientWithMiddleware, Middleware, Next};
 use reqwest_retry::policies::ExponentialBackoff;
 use reqwest_retry::RetryTransientMiddleware;
 use reqwest_tracing::TracingMiddleware;
 use sha2::{Digest, Sha256};
-use std::fs::{create_dir_all, remove_dir_all, remove_file, rename, File};
-use std::io::{copy, BufReader, Cursor};
+use std::collections::HashMap;
+use std::fs::{create_dir_all, remove_dir_all, remove_file, rename, File, OpenOptions};
+use std::io::{copy, BufReader, Cursor, Write};
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::sync::Arc;
 use std::thread::sleep;
-use std::time::Duration;
+use std::time::{Duration, Instant, SystemTime};
 use tar::Archive;
 use tracing::{debug, instrument, warn};
 
 const GITHUB_API_VERSION_HEADER: &str = "X-GitHub-Api-Version";
 const GITHUB_API_VERSION: &str = "2022-11-28";
+/// Number of attempts to download an archive when the transfer is interrupted
+const DOWNLOAD_ATTEMPTS: usize = 3;
 
 lazy_static! {
     static ref GITHUB_TOKEN: Option<String> = match std::env::var("GITHUB_TOKEN") {
@@ -253,14 +257,22 @@ pub async fn get_archive_for_target<S: AsRef<str>>(
     );
 
     debug!("Downloading archive {}", asset.browser_download_url);
-    let request = client.get(&asset.browser_download_url);
-    let response = request.send().await?.error_for_status()?;
-    let archive: Bytes = response.bytes().await?;
+    let download_dir = std::env::temp_dir().join(DOWNLOAD_DIR);
+    create_dir_all(&download_dir)?;
+    let part_file = PartFile::acquire(&download_dir, &asset.name)?;
+    let archive = download(
+        &client,
+        &asset.browser_download_url,
+        part_file.path(),
+        part_file.lock(),
+    )
+    .await?;
     debug!(
         "Archive {} downloaded: {}",
         asset.browser_download_url,
         human_bytes(archive.len() as f64)
     );
+    remove_file(part_file.path())?;
 
     let mut hasher = Sha256::new();
     hasher.update(&archive);
@@ -273,58 +285,384 @@ pub async fn get_archive_for_target<S: AsRef<str>>(
     Ok((asset_version, archive))
 }
 
-/// Acquires a lock file in the [out_dir](Path) to prevent multiple processes from extracting the
-/// archive at the same time.
-#[instrument(level = "debug")]
-fn acquire_lock(out_dir: &Path) -> Result<PathBuf> {
-    let lock_file = out_dir.join("postgresql-archive.lock");
+/// File an archive is downloaded to
+#[derive(Debug)]
+enum PartFile {
+    /// Part file of the asset shared by all processes, so that a download interrupted in one
+    /// process is resumed by the next one; the lock is held while downloading into it
+    Shared(PathBuf, LockFile),
+    /// Part file of this download only, used while another process downloads into the shared
+    /// part file; removed when dropped
+    Private(tempfile::TempPath),
+}
 
-    if lock_file.is_file() {
-        let metadata = lock_file.metadata()?;
-        let created = metadata.created()?;
+impl PartFile {
+    /// Get the part file to download the asset to in the download directory: the shared part
+    /// file when its lock is free, or a private part file otherwise
+    fn acquire(download_dir: &Path, asset_name: &str) -> Result<Self> {
+        let path = download_dir.join(format!("{asset_name}.part"));
+        let lock_file = download_dir.join(format!("{asset_name}.part.lock"));
+        if let Some(lock) = try_acquire_lock(&lock_file)? {
+            return Ok(PartFile::Shared(path, lock));
+        }
+        debug!("Archive {asset_name} is downloaded by another process; downloading separately");
+        let path = tempfile::Builder::new()
+            .prefix(&format!("{asset_name}."))
+            .suffix(".part")
+            .tempfile_in(download_dir)?
+            .into_temp_path();
+        Ok(PartFile::Private(path))
+    }
 
-        if created.elapsed()?.as_secs() > 300 {
-            warn!(
-                "Stale lock file detected; removing file to attempt process recovery: {}",
-                lock_file.to_string_lossy()
-            );
-            remove_file(&lock_file)?;
+    /// Get the path of the part file
+    fn path(&self) -> &Path {
+        match self {
+            PartFile::Shared(path, _) => path,
+            PartFile::Private(path) => path,
+        }
+    }
+
+    /// Get the lock held on the shared part file
+    fn lock(&self) -> Option<&LockFile> {
+        match self {
+            PartFile::Shared(_, lock) => Some(lock),
+            PartFile::Private(_) => None,
         }
     }
+}
+
+/// Get the total size of the file from the `Content-Range` header of a response to a range
+/// request that cannot be satisfied; e.g. `bytes */400000`
+fn unsatisfied_range_size(response: &Response) -> Option<u64> {
+    response
+        .headers()
+        .get(header::CONTENT_RANGE)?
+        .to_str()
+        .ok()?
+        .strip_prefix("bytes */")?
+        .parse()
+        .ok()
+}
+
+/// Downloads the file at the url to the [part_file](Path) and returns its contents. If the part
+/// file exists (e.g. a previous download was interrupted), or the transfer is interrupted, the
+/// download is resumed from the end of the part file with a range request. When the server does
+/// not support range requests, the file is downloaded again from the start. The
+/// [lock](LockFile) held on the part file, if any, is [refreshed](LockFile::refresh) while
+/// downloading so that it is not considered stale.
+#[instrument(level = "debug", skip(client, lock))]
+async fn download(
+    client: &ClientWithMiddleware,
+    url: &str,
+    part_file: &Path,
+    lock: Option<&LockFile>,
+) -> Result<Bytes> {
+    let mut attempt = 1;
+    let mut refreshed = Instant::now();
+    loop {
+        let offset = part_file.metadata().map_or(0, |metadata| metadata.len());
+        let mut request = client.get(url);
+        if offset > 0 {
+            debug!("Resuming download of {url} from byte {offset}");
+            request = request.header(header::RANGE, format!("bytes={offset}-"));
+        }
+        let response = request.send().await?;
+
+        // The part file is complete, or larger than the file, e.g. the file was replaced
+        if response.status() == StatusCode::RANGE_NOT_SATISFIABLE {
+            if unsatisfied_range_size(&response) == Some(offset) {
+                debug!("Download of {url} is already complete");
+                break;
+            }
+            debug!("Restarting download of {url}; range not satisfiable");
+            remove_file(part_file)?;
+            continue;
+        }
+        let mut response = response.error_for_status()?;
+        let mut file = if response.status() == StatusCode::PARTIAL_CONTENT {
+            OpenOptions::new().append(true).open(part_file)?
+        } else {
+            if offset > 0 {
+                debug!("Restarting download of {url}; range requests not supported");
+            }
+            File::create(part_file)?
+        };
+
+        let result = async {
+            while let Some(chunk) = response.chunk().await? {
+                file.write_all(&chunk)?;
+                if let Some(lock) = lock.filter(|_| refreshed.elapsed() >= LOCK_REFRESH_INTERVAL) {
+                    lock.refresh()?;
+                    refreshed = Instant::now();
+                }
+            }
+            Ok::<(), crate::Error>(())
+        }
+        .await;
+        match result {
+            Ok(()) => break,
+            Err(error) if attempt < DOWNLOAD_ATTEMPTS => {
+                warn!("Download of {url} interrupted; resuming: {error}");
+                attempt += 1;
+            }
+            Err(error) => return Err(error),
+        }
+    }
+
+    Ok(Bytes::from(std::fs::read(part_file)?))
+}
+
+/// Name of the lock file held while an archive is extracted
+const ARCHIVE_LOCK_FILE: &str = "postgresql-archive.lock";
+
+/// Directory of the temporary directory that archives are downloaded to
+const DOWNLOAD_DIR: &str = "postgresql-archive-downloads";
+
+/// Interval at which a lock held during a long operation, e.g. a download, is refreshed so that
+/// it is not considered stale
+const LOCK_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Number of seconds after which a lock file is considered stale, e.g. because the process that
+/// held it was killed; acquiring a lock waits at most this long
+const LOCK_TIMEOUT_SECS: u64 = 300;
+
+/// Lock file held by [acquire_lock]; the lock is released by removing the file when the value
+/// is dropped
+#[derive(Debug)]
+pub struct LockFile {
+    path: PathBuf,
+}
+
+impl LockFile {
+    /// Get the path of the lock file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Update the modification time of the lock file, so that a lock held for a long time is
+    /// not considered stale by other processes
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the lock file cannot be updated.
+    pub fn refresh(&self) -> Result<()> {
+        OpenOptions::new()
+            .write(true)
+            .open(&self.path)?
+            .set_modified(SystemTime::now())?;
+        Ok(())
+    }
+}
+
+impl Drop for LockFile {
+    fn drop(&mut self) {
+        debug!("Removing lock file: {}", self.path.to_string_lossy());
+        let _ = remove_file(&self.path);
+    }
+}
 
+/// Acquires the [lock_file](Path) to prevent multiple processes from working on the same files
+/// at the same time, e.g. extracting an archive. The lock file is created exclusively; while
+/// another process holds it, acquiring the lock waits for up to five minutes, sleeping the
+/// current thread. A lock file older than five minutes is considered stale and is removed.
+///
+/// # Errors
+///
+/// Returns an error if the lock cannot be acquired within five minutes.
+#[instrument(level = "debug")]
+pub fn acquire_lock(lock_file: &Path) -> Result<LockFile> {
     debug!(
         "Attempting to acquire lock: {}",
         lock_file.to_string_lossy()
     );
 
-    for _ in 0..30 {
-        let lock = std::fs::OpenOptions::new()
-            .create(true)
-            .truncate(true)
+    for _ in 0..=LOCK_TIMEOUT_SECS {
+        if let Some(lock) = try_acquire_lock(lock_file)? {
+            return Ok(lock);
+        }
+        sleep(Duration::from_secs(1));
+    }
+
+    Err(Unexpected(format!(
+        "Failed to acquire lock: {}",
+        lock_file.to_string_lossy()
+    )))
+}
+
+/// Acquires the [lock_file](Path) if no other process holds it, removing a stale lock file
+/// first; returns `None` when the lock is held
+fn try_acquire_lock(lock_file: &Path) -> Result<Option<LockFile>> {
+    for _ in 0..2 {
+        let lock = OpenOptions::new()
             .write(true)
-            .open(&lock_file);
+            .create_new(true)
+            .open(lock_file);
 
         match lock {
             Ok(_) => {
                 debug!("Lock acquired: {}", lock_file.to_string_lossy());
-                return Ok(lock_file);
+                return Ok(Some(LockFile {
+                    path: lock_file.to_path_buf(),
+                }));
             }
-            Err(error) => {
-                warn!("unable to acquire lock: {error}");
-                sleep(Duration::from_secs(1));
+            Err(error) if error.kind() == std::io::ErrorKind::AlreadyExists => {
+                let stale = lock_file
+                    .metadata()
+                    .and_then(|metadata| metadata.modified())
+                    .ok()
+                    .and_then(|modified| modified.elapsed().ok())
+                    .is_some_and(|elapsed| elapsed.as_secs() > LOCK_TIMEOUT_SECS);
+                if !stale {
+                    return Ok(None);
+                }
+                warn!(
+                    "Stale lock file detected; removing file to attempt process recovery: {}",
+                    lock_file.to_string_lossy()
+                );
+                let _ = remove_file(lock_file);
             }
+            Err(error) => return Err(error.into()),
+        }
+    }
+    Ok(None)
+}
+
+/// Maximum length in bytes of a path on the platform
+#[cfg(windows)]
+const MAX_PATH_LENGTH: usize = 259;
+/// Maximum length in bytes of a path on the platform
+#[cfg(target_os = "macos")]
+const MAX_PATH_LENGTH: usize = 1023;
+/// Maximum length in bytes of a path on the platform
+#[cfg(not(any(windows, target_os = "macos")))]
+const MAX_PATH_LENGTH: usize = 4095;
+/// Maximum length in bytes of a file name on common filesystems
+const MAX_NAME_LENGTH: usize = 255;
+
+/// How entries of an archive whose paths differ only by case are extracted onto a
+/// case-insensitive filesystem (e.g. APFS on macOS or NTFS on Windows), where they would
+/// otherwise silently overwrite each other. Directories differing only by case are merged.
+///
+/// Collisions between time zone files (`share/**/timezone/**`) are always resolved by keeping
+/// the canonical entry: a regular file is kept over a link, and the first regular file over
+/// later ones. PostgreSQL looks up time zone files case-insensitively, so the dropped name
+/// still resolves to the kept file.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CollisionPolicy {
+    /// Fail before anything is extracted with a [PathCollision](crate::Error::PathCollision)
+    /// error naming the colliding entries
+    #[default]
+    Error,
+    /// Extract the later entry with a numeric suffix, e.g. `Foo~1`
+    Rename,
+}
+
+/// Paths excluded by the [runtime](ExtractFilter::runtime) filter: headers and build support
+/// for extensions, documentation and message translations
+const RUNTIME_EXCLUDED: [&str; 6] = [
+    "include",
+    "lib/pgxs",
+    "lib/pkgconfig",
+    "share/doc",
+    "share/locale",
+    "share/man",
+];
+
+/// Predicate of an [ExtractFilter]
+type EntryPredicate = Arc<dyn Fn(&Path) -> bool + Send + Sync>;
+
+/// Predicate over the paths of archive entries, relative to the root of the installation (e.g.
+/// `bin/initdb` or `share/locale/de`), selecting the entries that are extracted. The parent
+/// directories of an extracted entry are created even when the filter does not select them.
+/// The default filter extracts every entry.
+#[derive(Clone, Default)]
+pub struct ExtractFilter {
+    predicate: Option<EntryPredicate>,
+}
+
+impl ExtractFilter {
+    /// Create a filter extracting the entries whose paths match the predicate
+    pub fn new<F: Fn(&Path) -> bool + Send + Sync + 'static>(predicate: F) -> Self {
+        Self {
+            predicate: Some(Arc::new(predicate)),
+        }
+    }
+
+    /// Filter extracting everything needed to run the server (initdb, postgres and pg_ctl) and
+    /// the client tools, including the libraries, extensions, time zones and text search data;
+    /// the headers and build support for extensions, documentation and message translations
+    /// are not extracted.
+    pub fn runtime() -> Self {
+        Self::new(|path| {
+            !RUNTIME_EXCLUDED
+                .iter()
+                .any(|excluded| path.starts_with(excluded))
+        })
+    }
+
+    /// Whether the entry with the path is extracted
+    pub fn matches(&self, path: &Path) -> bool {
+        match &self.predicate {
+            Some(predicate) => predicate(path),
+            None => true,
+        }
+    }
+}
+
+impl std::fmt::Debug for ExtractFilter {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.predicate {
+            Some(_) => formatter.write_str("ExtractFilter(predicate)"),
+            None => formatter.write_str("ExtractFilter(all)"),
+        }
+    }
+}
+
+/// Filters are equal when both extract every entry or they share the same predicate
+impl PartialEq for ExtractFilter {
+    fn eq(&self, other: &Self) -> bool {
+        match (&self.predicate, &other.predicate) {
+            (Some(predicate), Some(other)) => Arc::ptr_eq(predicate, other),
+            (None, None) => true,
+            _ => false,
         }
     }
+}
+
+impl Eq for ExtractFilter {}
 
-    Err(Unexpected("Failed to acquire lock".to_string()))
+/// Options for [extract_with_options]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ExtractOptions {
+    /// How entries whose paths differ only by case are extracted onto a case-insensitive
+    /// filesystem
+    pub collision_policy: CollisionPolicy,
+    /// Whether the filesystem of the output directory ignores case; detected when not set
+    pub case_insensitive: Option<bool>,
+    /// Entries that are extracted; every entry is extracted by default
+    pub filter: ExtractFilter,
 }
 
-/// Extracts the compressed tar [bytes](Bytes) to the [out_dir](Path).
+/// Extracts the compressed tar [bytes](Bytes) to the [out_dir](Path) with the default
+/// [options](ExtractOptions); see [extract_with_options].
 #[instrument(skip(bytes))]
 pub async fn extract(bytes: &Bytes, out_dir: &Path) -> Result<()> {
-    let input = BufReader::new(Cursor::new(bytes));
-    let decoder = GzDecoder::new(input);
-    let mut archive = Archive::new(decoder);
+    extract_with_options(bytes, out_dir, &ExtractOptions::default()).await
+}
+
+/// Extracts the compressed tar [bytes](Bytes) to the [out_dir](Path). The destination of every
+/// entry is planned before anything is written: entries that would collide on a
+/// case-insensitive filesystem are handled according to the
+/// [collision policy](ExtractOptions::collision_policy), and a path exceeding the limit of the
+/// platform fails the extraction with a [PathTooLong](crate::Error::PathTooLong) error naming
+/// it. Once extracted, the files that collided are checked to hold their own contents. Only the
+/// entries selected by the [filter](ExtractOptions::filter) are planned and extracted.
+#[instrument(skip(bytes))]
+pub async fn extract_with_options(
+    bytes: &Bytes,
+    out_dir: &Path,
+    options: &ExtractOptions,
+) -> Result<()> {
     let mut files = 0;
     let mut extracted_bytes = 0;
 
@@ -337,7 +675,7 @@ pub async fn extract(bytes: &Bytes, out_dir: &Path) -> Result<()> {
     };
     create_dir_all(parent_dir)?;
 
-    let lock_file = acquire_lock(parent_dir)?;
+    let _lock = acquire_lock(&parent_dir.join(ARCHIVE_LOCK_FILE))?;
     // If the directory already exists, then the archive has already been
     // extracted by another process.
     if out_dir.exists() {
@@ -345,33 +683,37 @@ pub async fn extract(bytes: &Bytes, out_dir: &Path) -> Result<()> {
             "Directory already exists {}; skipping extraction: ",
             out_dir.to_string_lossy()
         );
-        remove_file(&lock_file)?;
         return Ok(());
     }
 
     let extract_dir = tempfile::tempdir_in(parent_dir)?.into_path();
+    let plan = match plan_extraction(bytes, &extract_dir, out_dir, options) {
+        Ok(plan) => plan,
+        Err(error) => {
+            remove_dir_all(&extract_dir)?;
+            return Err(error);
+        }
+    };
     debug!("Extracting archive to {}", extract_dir.to_string_lossy());
 
-    for archive_entry in archive.entries()? {
+    let input = BufReader::new(Cursor::new(bytes));
+    let decoder = GzDecoder::new(input);
+    let mut archive = Archive::new(decoder);
+    for (archive_entry, destination) in archive.entries()?.zip(&plan.destinations) {
         let mut entry = archive_entry?;
+        let Some(destination) = destination else {
+            continue;
+        };
         let entry_header = entry.header();
         let entry_type = entry_header.entry_type();
         let entry_size = entry_header.size()?;
         #[cfg(unix)]
         let file_mode = entry_header.mode()?;
-
-        let entry_header_path = entry_header.path()?.to_path_buf();
-        let prefix = match entry_header_path.components().next() {
-            Some(component) => component.as_os_str().to_str().unwrap_or_default(),
-            None => {
-                return Err(Unexpected(
-                    "Failed to get file header path prefix".to_string(),
-                ));
-            }
-        };
-        let stripped_entry_header_path = entry_header_path.strip_prefix(prefix)?.to_path_buf();
-        let mut entry_name = extract_dir.to_path_buf();
-        entry_name.push(stripped_entry_header_path);
+        let entry_name = extract_dir.join(destination);
+        // The parent directory is not extracted when the filter does not select it
+        if let Some(parent) = entry_name.parent() {
+            create_dir_all(parent)?;
+        }
 
         if entry_type.is_dir() || entry_name.is_dir() {
             create_dir_all(&entry_name)?;
@@ -395,6 +737,7 @@ pub async fn extract(bytes: &Bytes, out_dir: &Path) -> Result<()> {
             }
         }
     }
+    plan.verify(&extract_dir)?;
 
     if out_dir.exists() {
         debug!(
@@ -412,11 +755,6 @@ pub async fn extract(bytes: &Bytes, out_dir: &Path) -> Result<()> {
         rename(extract_dir, out_dir)?;
     }
 
-    if lock_file.is_file() {
-        debug!("Removing lock file: {}", lock_file.to_string_lossy());
-        remove_file(lock_file)?;
-    }
-
     debug!(
         "Extracting {} files totalling {}",
         files.to_formatted_string(&Locale::en),
@@ -426,6 +764,304 @@ pub async fn extract(bytes: &Bytes, out_dir: &Path) -> Result<()> {
     Ok(())
 }
 
+/// Kind of an archive entry
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum EntryKind {
+    Directory,
+    File,
+    Symlink,
+    /// Entries that are not extracted, e.g. hard links
+    Other,
+}
+
+/// Entry of an archive, with the path stripped of the top-level directory
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct ArchiveEntry {
+    path: PathBuf,
+    kind: EntryKind,
+    size: u64,
+}
+
+/// Destination of every entry of an archive, planned before anything is extracted
+#[derive(Debug, Default)]
+struct ExtractionPlan {
+    /// Entries of the archive, in the order of the archive
+    entries: Vec<ArchiveEntry>,
+    /// Path of every entry relative to the output directory; `None` for skipped entries
+    destinations: Vec<Option<PathBuf>>,
+    /// Entries extracted although their path collided with another entry
+    collisions: Vec<usize>,
+}
+
+impl ExtractionPlan {
+    /// Spot-check the files that collided with another entry: each must have the size of its
+    /// own entry, and time zone files must be in the TZif format read by PostgreSQL
+    fn verify(&self, extract_dir: &Path) -> Result<()> {
+        for index in &self.collisions {
+            let entry = &self.entries[*index];
+            let Some(destination) = &self.destinations[*index] else {
+                continue;
+            };
+            if entry.kind != EntryKind::File {
+                continue;
+            }
+            let path = extract_dir.join(destination);
+            let contents = std::fs::read(&path)?;
+            if contents.len() as u64 != entry.size
+                || (is_timezone_entry(&entry.path) && !contents.starts_with(b"TZif"))
+            {
+                return Err(Unexpected(format!(
+                    "extracted file [{}] does not hold the contents of archive entry [{}]",
+                    path.to_string_lossy(),
+                    entry.path.to_string_lossy()
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Plan the extraction of the compressed tar [bytes](Bytes) to a temporary directory that is
+/// renamed to the output directory once extracted
+fn plan_extraction(
+    bytes: &Bytes,
+    extract_dir: &Path,
+    out_dir: &Path,
+    options: &ExtractOptions,
+) -> Result<ExtractionPlan> {
+    let case_insensitive = match options.case_insensitive {
+        Some(case_insensitive) => case_insensitive,
+        None => is_case_insensitive(extract_dir)?,
+    };
+    if case_insensitive {
+        debug!(
+            "Extracting to case-insensitive filesystem {}",
+            extract_dir.to_string_lossy()
+        );
+    }
+    let root_length = extract_dir.as_os_str().len().max(out_dir.as_os_str().len());
+    plan_entries(
+        read_entries(bytes)?,
+        case_insensitive,
+        options.collision_policy,
+        &options.filter,
+        root_length,
+    )
+}
+
+/// Read the entries of the compressed tar [bytes](Bytes) without extracting them
+fn read_entries(bytes: &Bytes) -> Result<Vec<ArchiveEntry>> {
+    let input = BufReader::new(Cursor::new(bytes));
+    let decoder = GzDecoder::new(input);
+    let mut archive = Archive::new(decoder);
+    let mut entries = Vec::new();
+    for archive_entry in archive.entries()? {
+        let entry = archive_entry?;
+        let entry_header = entry.header();
+        let entry_type = entry_header.entry_type();
+        let entry_header_path = entry.path()?.to_path_buf();
+        let prefix = match entry_header_path.components().next() {
+            Some(component) => component.as_os_str().to_str().unwrap_or_default(),
+            None => {
+                return Err(Unexpected(
+                    "Failed to get file header path prefix".to_string(),
+                ));
+            }
+        };
+        let kind = if entry_type.is_dir() {
+            EntryKind::Directory
+        } else if entry_type.is_file() {
+            EntryKind::File
+        } else if entry_type.is_symlink() {
+            EntryKind::Symlink
+        } else {
+            EntryKind::Other
+        };
+        entries.push(ArchiveEntry {
+            path: entry_header_path.strip_prefix(prefix)?.to_path_buf(),
+            kind,
+            size: entry_header.size()?,
+        });
+    }
+    Ok(entries)
+}
+
+/// Plan the destination of every entry relative to the output directory, whose path is
+/// `root_length` bytes long
+fn plan_entries(
+    entries: Vec<ArchiveEntry>,
+    case_insensitive: bool,
+    policy: CollisionPolicy,
+    filter: &ExtractFilter,
+    root_length: usize,
+) -> Result<ExtractionPlan> {
+    let key = |path: &Path| {
+        let path = path.to_string_lossy();
+        if case_insensitive {
+            path.to_lowercase()
+        } else {
+            path.into_owned()
+        }
+    };
+    let mut plan = ExtractionPlan::default();
+    // Entry occupying each destination, by the key of the destination
+    let mut occupied: HashMap<String, usize> = HashMap::new();
+    // Destination of each directory, which differs when directories are merged
+    let mut directories: HashMap<PathBuf, PathBuf> = HashMap::new();
+
+    for (index, entry) in entries.iter().enumerate() {
+        let (Some(name), EntryKind::Directory | EntryKind::File | EntryKind::Symlink) =
+            (entry.path.file_name(), entry.kind)
+        else {
+            let destination = match entry.kind {
+                EntryKind::Other => None,
+                _ => Some(PathBuf::new()),
+            };
+            plan.destinations.push(destination);
+            continue;
+        };
+        if !filter.matches(&entry.path) {
+            plan.destinations.push(None);
+            continue;
+        }
+        let parent = entry
+            .path
+            .parent()
+            .map(|parent| resolve_directory(&directories, parent))
+            .unwrap_or_default();
+        let mut destination = parent.join(name);
+
+        match occupied.get(&key(&destination)).copied() {
+            Some(other) if entries[other].path == entry.path => {}
+            Some(other)
+                if entry.kind == EntryKind::Directory
+                    && entries[other].kind == EntryKind::Directory =>
+            {
+                debug!(
+                    "Merging directory {} into {}",
+                    entry.path.to_string_lossy(),
+                    entries[other].path.to_string_lossy()
+                );
+                if let Some(Some(existing)) = plan.destinations.get(other) {
+                    directories.insert(entry.path.clone(), existing.clone());
+                }
+                plan.destinations.push(None);
+                continue;
+            }
+            Some(other)
+                if is_timezone_entry(&entry.path)
+                    && entry.kind != EntryKind::Directory
+                    && entries[other].kind != EntryKind::Directory =>
+            {
+                if entry.kind == EntryKind::File && entries[other].kind != EntryKind::File {
+                    debug!(
+                        "Extracting time zone file {} instead of {}",
+                        entry.path.to_string_lossy(),
+                        entries[other].path.to_string_lossy()
+                    );
+                    plan.destinations[other] = None;
+                    plan.collisions.retain(|collision| *collision != other);
+                } else {
+                    debug!(
+                        "Skipping time zone file {} colliding with {}",
+                        entry.path.to_string_lossy(),
+                        entries[other].path.to_string_lossy()
+                    );
+                    plan.destinations.push(None);
+                    plan.collisions.push(other);
+                    continue;
+                }
+                plan.collisions.push(index);
+            }
+            Some(other) => match policy {
+                CollisionPolicy::Error => {
+                    return Err(PathCollision {
+                        path: entry.path.to_string_lossy().to_string(),
+                        existing: entries[other].path.to_string_lossy().to_string(),
+                    });
+                }
+                CollisionPolicy::Rename => {
+                    let name = name.to_string_lossy();
+                    destination = (1..)
+                        .map(|suffix| parent.join(format!("{name}~{suffix}")))
+                        .find(|destination| !occupied.contains_key(&key(destination)))
+                        .unwrap_or(destination);
+                    warn!(
+                        "Extracting {} as {} to avoid a collision with {}",
+                        entry.path.to_string_lossy(),
+                        destination.to_string_lossy(),
+                        entries[other].path.to_string_lossy()
+                    );
+                    plan.collisions.push(other);
+                    plan.collisions.push(index);
+                }
+            },
+            None => {}
+        }
+
+        check_path_length(root_length, &destination)?;
+        if entry.kind == EntryKind::Directory {
+            directories.insert(entry.path.clone(), destination.clone());
+        }
+        occupied.insert(key(&destination), index);
+        plan.destinations.push(Some(destination));
+    }
+
+    plan.collisions.sort_unstable();
+    plan.collisions.dedup();
+    plan.entries = entries;
+    Ok(plan)
+}
+
+/// Get the destination of the directory, following the directories that were merged
+fn resolve_directory(directories: &HashMap<PathBuf, PathBuf>, directory: &Path) -> PathBuf {
+    if let Some(destination) = directories.get(directory) {
+        return destination.clone();
+    }
+    match (directory.parent(), directory.file_name()) {
+        (Some(parent), Some(name)) => resolve_directory(directories, parent).join(name),
+        _ => directory.to_path_buf(),
+    }
+}
+
+/// Check that the destination, relative to a directory whose path is `root_length` bytes long,
+/// and each of its names are within the limits of the platform
+fn check_path_length(root_length: usize, destination: &Path) -> Result<()> {
+    let length = root_length + 1 + destination.as_os_str().len();
+    if length > MAX_PATH_LENGTH {
+        return Err(PathTooLong {
+            path: destination.to_string_lossy().to_string(),
+            limit: MAX_PATH_LENGTH,
+        });
+    }
+    if let Some(name) = destination.iter().find(|name| name.len() > MAX_NAME_LENGTH) {
+        return Err(PathTooLong {
+            path: name.to_string_lossy().to_string(),
+            limit: MAX_NAME_LENGTH,
+        });
+    }
+    Ok(())
+}
+
+/// Check whether the entry is a time zone file, e.g. `share/timezone/America/New_York`
+fn is_timezone_entry(path: &Path) -> bool {
+    let mut names = path.iter();
+    names.any(|name| name == "share") && names.any(|name| name == "timezone")
+}
+
+/// Check whether the filesystem of the directory ignores case, by looking up a probe file
+/// under a name in a different case
+fn is_case_insensitive(dir: &Path) -> Result<bool> {
+    let probe = tempfile::Builder::new()
+        .prefix(".Case-Probe")
+        .tempfile_in(dir)?;
+    let Some(name) = probe.path().file_name() else {
+        return Ok(false);
+    };
+    let swapped = name.to_string_lossy().to_uppercase();
+    Ok(dir.join(swapped).exists())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -475,4 +1111,408 @@ mod tests {
         assert!(result.is_err());
         Ok(())
     }
+
+    /// Serve the body over HTTP for the given number of requests and return the url and the
+    /// `Range` header of each request. The first response is interrupted after `interrupt_at`
+    /// bytes of the body; range requests are answered with partial content if `ranges` is set.
+    fn serve(
+        body: Vec<u8>,
+        requests: usize,
+        interrupt_at: Option<usize>,
+        ranges: bool,
+    ) -> Result<(String, std::thread::JoinHandle<Vec<Option<String>>>)> {
+        use std::io::Read;
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+        let url = format!("http://{}/archive.tar.gz", listener.local_addr()?);
+        let handle = std::thread::spawn(move || {
+            let mut ranges_requested = Vec::new();
+            for (index, stream) in listener.incoming().take(requests).enumerate() {
+                let mut stream = stream.expect("stream");
+                let mut request = Vec::new();
+                let mut buffer = [0u8; 1024];
+                while !request.ends_with(b"\r\n\r\n") {
+                    let length = stream.read(&mut buffer).expect("request");
+                    if length == 0 {
+                        break;
+                    }
+                    request.extend_from_slice(&buffer[..length]);
+                }
+                let range = String::from_utf8_lossy(&request).lines().find_map(|line| {
+                    let (name, value) = line.split_once(':')?;
+                    name.eq_ignore_ascii_case("range")
+                        .then(|| value.trim().to_string())
+                });
+                let offset = range
+                    .as_deref()
+                    .filter(|_| ranges)
+                    .and_then(|range| range.strip_prefix("bytes="))
+                    .and_then(|range| range.trim_end_matches('-').parse::<usize>().ok());
+                ranges_requested.push(range);
+
+                if offset.is_some_and(|offset| offset >= body.len()) {
+                    let header = format!(
+                        "HTTP/1.1 416 Range Not Satisfiable\r\nContent-Range: bytes */{}\r\n\
+                         Content-Length: 0\r\nConnection: close\r\n\r\n",
+                        body.len()
+                    );
+                    let _ = stream.write_all(header.as_bytes());
+                    continue;
+                }
+                let (status, start) = match offset {
+                    Some(offset) => ("206 Partial Content", offset),
+                    None => ("200 OK", 0),
+                };
+                let header = format!(
+                    "HTTP/1.1 {status}\r\nContent-Length: {}\r\nAccept-Ranges: {}\r\n\
+                     Connection: close\r\n\r\n",
+                    body.len() - start,
+                    if ranges { "bytes" } else { "none" }
+                );
+                let end = match interrupt_at {
+                    Some(interrupt_at) if index == 0 => interrupt_at,
+                    _ => body.len(),
+                };
+                let _ = stream.write_all(header.as_bytes());
+                let _ = stream.write_all(&body[start..end]);
+            }
+            ranges_requested
+        });
+        Ok((url, handle))
+    }
+
+    fn archive_body() -> Vec<u8> {
+        (0..100_000u32).flat_map(u32::to_le_bytes).collect()
+    }
+
+    #[test(tokio::test)]
+    async fn test_download_resumes_interrupted_transfer() -> Result<()> {
+        let body = archive_body();
+        let (url, server) = serve(body.clone(), 2, Some(150_000), true)?;
+        let temp_dir = tempfile::tempdir()?;
+        let part_file = temp_dir.path().join("archive.tar.gz.part");
+
+        let archive = download(&reqwest_client(), &url, &part_file, None).await?;
+
+        assert_eq!(body, archive.to_vec());
+        let ranges = server.join().expect("server");
+        assert_eq!(vec![None, Some("bytes=150000-".to_string())], ranges);
+        Ok(())
+    }
+
+    #[test(tokio::test)]
+    async fn test_download_resumes_part_file() -> Result<()> {
+        let body = archive_body();
+        let (url, server) = serve(body.clone(), 1, None, true)?;
+        let temp_dir = tempfile::tempdir()?;
+        let part_file = temp_dir.path().join("archive.tar.gz.part");
+        std::fs::write(&part_file, &body[..1000])?;
+
+        let archive = download(&reqwest_client(), &url, &part_file, None).await?;
+
+        assert_eq!(body, archive.to_vec());
+        let ranges = server.join().expect("server");
+        assert_eq!(vec![Some("bytes=1000-".to_string())], ranges);
+        Ok(())
+    }
+
+    #[test(tokio::test)]
+    async fn test_download_complete_part_file() -> Result<()> {
+        let body = archive_body();
+        let (url, server) = serve(body.clone(), 1, None, true)?;
+        let temp_dir = tempfile::tempdir()?;
+        let part_file = temp_dir.path().join("archive.tar.gz.part");
+        std::fs::write(&part_file, &body)?;
+
+        let archive = download(&reqwest_client(), &url, &part_file, None).await?;
+
+        assert_eq!(body, archive.to_vec());
+        let ranges = server.join().expect("server");
+        assert_eq!(vec![Some(format!("bytes={}-", body.len()))], ranges);
+        Ok(())
+    }
+
+    #[test]
+    fn test_part_file() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let shared = PartFile::acquire(temp_dir.path(), "archive.tar.gz")?;
+        assert_eq!(temp_dir.path().join("archive.tar.gz.part"), shared.path());
+        assert!(shared.lock().is_some());
+
+        // While the shared part file is locked, downloads use private part files
+        let private = PartFile::acquire(temp_dir.path(), "archive.tar.gz")?;
+        assert_ne!(shared.path(), private.path());
+        assert!(private.lock().is_none());
+        let private_path = private.path().to_path_buf();
+        drop(private);
+        assert!(!private_path.exists());
+
+        drop(shared);
+        let shared = PartFile::acquire(temp_dir.path(), "archive.tar.gz")?;
+        assert!(shared.lock().is_some());
+        Ok(())
+    }
+
+    #[test(tokio::test)]
+    async fn test_download_without_range_support() -> Result<()> {
+        let body = archive_body();
+        let (url, server) = serve(body.clone(), 2, Some(150_000), false)?;
+        let temp_dir = tempfile::tempdir()?;
+        let part_file = temp_dir.path().join("archive.tar.gz.part");
+
+        let archive = download(&reqwest_client(), &url, &part_file, None).await?;
+
+        // The range request is ignored, so the archive is downloaded again from the start
+        assert_eq!(body, archive.to_vec());
+        let ranges = server.join().expect("server");
+        assert_eq!(vec![None, Some("bytes=150000-".to_string())], ranges);
+        Ok(())
+    }
+
+    #[test]
+    fn test_acquire_lock() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let lock_file = temp_dir.path().join("test.lock");
+        {
+            let lock = acquire_lock(&lock_file)?;
+            assert_eq!(lock_file, lock.path());
+            assert!(lock_file.exists());
+        }
+        assert!(!lock_file.exists());
+
+        // A stale lock file is removed
+        let stale = File::create(&lock_file)?;
+        stale.set_modified(
+            std::time::SystemTime::now() - Duration::from_secs(LOCK_TIMEOUT_SECS + 60),
+        )?;
+        drop(stale);
+        let lock = acquire_lock(&lock_file)?;
+        assert!(lock_file.exists());
+
+        // A refreshed lock is not stale
+        File::options()
+            .write(true)
+            .open(&lock_file)?
+            .set_modified(SystemTime::now() - Duration::from_secs(LOCK_TIMEOUT_SECS + 60))?;
+        lock.refresh()?;
+        assert!(try_acquire_lock(&lock_file)?.is_none());
+        Ok(())
+    }
+
+    /// Path, contents and link target of an entry of a test archive
+    type TestEntry<'a> = (&'a str, Option<&'a [u8]>, Option<&'a str>);
+
+    /// Build a compressed tar archive of entries under the `postgresql` directory; entries with
+    /// contents are files, and entries with a target are symbolic links
+    fn tar_gz(entries: &[TestEntry]) -> Result<Bytes> {
+        let encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        let mut builder = tar::Builder::new(encoder);
+        for (path, contents, target) in entries {
+            let mut header = tar::Header::new_gnu();
+            header.set_mode(0o644);
+            let path = format!("postgresql/{path}");
+            match (contents, target) {
+                (Some(contents), _) => {
+                    header.set_size(contents.len() as u64);
+                    header.set_cksum();
+                    builder.append_data(&mut header, path, *contents)?;
+                }
+                (None, Some(target)) => {
+                    header.set_entry_type(tar::EntryType::Symlink);
+                    header.set_size(0);
+                    builder.append_link(&mut header, path, target)?;
+                }
+                (None, None) => {
+                    header.set_entry_type(tar::EntryType::Directory);
+                    header.set_mode(0o755);
+                    header.set_size(0);
+                    header.set_cksum();
+                    builder.append_data(&mut header, path, std::io::empty())?;
+                }
+            }
+        }
+        Ok(Bytes::from(builder.into_inner()?.finish()?))
+    }
+
+    fn plan(
+        archive: &Bytes,
+        case_insensitive: bool,
+        policy: CollisionPolicy,
+    ) -> Result<ExtractionPlan> {
+        plan_entries(
+            read_entries(archive)?,
+            case_insensitive,
+            policy,
+            &ExtractFilter::default(),
+            0,
+        )
+    }
+
+    fn destinations(plan: &ExtractionPlan) -> Vec<Option<String>> {
+        plan.destinations
+            .iter()
+            .map(|destination| {
+                destination
+                    .as_ref()
+                    .map(|destination| destination.to_string_lossy().replace('\\', "/"))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_plan_case_collision() -> Result<()> {
+        let archive = tar_gz(&[
+            ("include", None, None),
+            ("include/Foo.h", Some(b"upper"), None),
+            ("INCLUDE", None, None),
+            ("INCLUDE/foo.h", Some(b"lower"), None),
+        ])?;
+
+        // No collision on a case-sensitive filesystem
+        let extraction = plan(&archive, false, CollisionPolicy::Error)?;
+        assert!(extraction.collisions.is_empty());
+
+        let error = plan(&archive, true, CollisionPolicy::Error).expect_err("collision");
+        assert!(matches!(
+            error,
+            PathCollision { ref path, ref existing }
+                if path == "INCLUDE/foo.h" && existing == "include/Foo.h"
+        ));
+
+        // Directories are merged and the colliding file is renamed
+        let extraction = plan(&archive, true, CollisionPolicy::Rename)?;
+        assert_eq!(
+            vec![
+                Some("include".to_string()),
+                Some("include/Foo.h".to_string()),
+                None,
+                Some("include/foo.h~1".to_string()),
+            ],
+            destinations(&extraction)
+        );
+        assert_eq!(vec![1, 3], extraction.collisions);
+        Ok(())
+    }
+
+    #[test]
+    fn test_plan_timezone_collision() -> Result<()> {
+        let archive = tar_gz(&[
+            ("share/timezone/America", None, None),
+            ("share/timezone/America/Atka", None, Some("Adak")),
+            ("share/timezone/America/ATKA", Some(b"TZif2"), None),
+            ("share/timezone/America/atka", Some(b"TZif3"), None),
+        ])?;
+
+        // The regular file is kept over the link and the first regular file over later ones
+        let extraction = plan(&archive, true, CollisionPolicy::Error)?;
+        assert_eq!(
+            vec![
+                Some("share/timezone/America".to_string()),
+                None,
+                Some("share/timezone/America/ATKA".to_string()),
+                None,
+            ],
+            destinations(&extraction)
+        );
+        assert_eq!(vec![2], extraction.collisions);
+        Ok(())
+    }
+
+    #[test]
+    fn test_plan_path_too_long() -> Result<()> {
+        let name = "x".repeat(MAX_NAME_LENGTH + 1);
+        let archive = tar_gz(&[(name.as_str(), Some(b"data"), None)])?;
+        let error = plan(&archive, false, CollisionPolicy::Error).expect_err("path too long");
+        assert!(matches!(error, PathTooLong { ref path, .. } if *path == name));
+
+        let archive = tar_gz(&[("data", Some(b"data"), None)])?;
+        let error = plan_entries(
+            read_entries(&archive)?,
+            false,
+            CollisionPolicy::Error,
+            &ExtractFilter::default(),
+            MAX_PATH_LENGTH,
+        )
+        .expect_err("path too long");
+        assert!(matches!(
+            error,
+            PathTooLong { ref path, limit } if path == "data" && limit == MAX_PATH_LENGTH
+        ));
+        Ok(())
+    }
+
+    #[test(tokio::test)]
+    async fn test_extract_case_collision() -> Result<()> {
+        let archive = tar_gz(&[
+            ("lib", None, None),
+            ("lib/Data", Some(b"upper"), None),
+            ("lib/data", Some(b"lower"), None),
+        ])?;
+        let temp_dir = tempfile::tempdir()?;
+        let case_insensitive = is_case_insensitive(temp_dir.path())?;
+
+        let out_dir = temp_dir.path().join("error");
+        let result = extract(&archive, &out_dir).await;
+        if case_insensitive {
+            assert!(matches!(result, Err(PathCollision { .. })));
+            assert!(!out_dir.exists());
+        } else {
+            result?;
+            assert_eq!(b"lower".to_vec(), std::fs::read(out_dir.join("lib/data"))?);
+        }
+
+        let out_dir = temp_dir.path().join("rename");
+        let options = ExtractOptions {
+            collision_policy: CollisionPolicy::Rename,
+            ..Default::default()
+        };
+        extract_with_options(&archive, &out_dir, &options).await?;
+        assert_eq!(b"upper".to_vec(), std::fs::read(out_dir.join("lib/Data"))?);
+        let renamed = if case_insensitive {
+            "lib/data~1"
+        } else {
+            "lib/data"
+        };
+        assert_eq!(b"lower".to_vec(), std::fs::read(out_dir.join(renamed))?);
+        assert!(!temp_dir.path().join(ARCHIVE_LOCK_FILE).exists());
+        Ok(())
+    }
+
+    #[test(tokio::test)]
+    async fn test_extract_filter() -> Result<()> {
+        let archive = tar_gz(&[
+            ("bin", None, None),
+            ("bin/initdb", Some(b"initdb"), None),
+            ("include", None, None),
+            ("include/postgres.h", Some(b"header"), None),
+            ("share", None, None),
+            ("share/postgres.bki", Some(b"bki"), None),
+            ("share/locale", None, None),
+            ("share/locale/de", None, None),
+            ("share/locale/de/postgres.mo", Some(b"mo"), None),
+        ])?;
+        let temp_dir = tempfile::tempdir()?;
+
+        let out_dir = temp_dir.path().join("runtime");
+        let options = ExtractOptions {
+            filter: ExtractFilter::runtime(),
+            ..Default::default()
+        };
+        extract_with_options(&archive, &out_dir, &options).await?;
+        assert!(out_dir.join("bin/initdb").is_file());
+        assert!(out_dir.join("share/postgres.bki").is_file());
+        assert!(!out_dir.join("include").exists());
+        assert!(!out_dir.join("share/locale").exists());
+
+        // Parent directories of the selected entries are created
+        let out_dir = temp_dir.path().join("custom");
+        let options = ExtractOptions {
+            filter: ExtractFilter::new(|path| path.extension().is_some_and(|ext| ext == "mo")),
+            ..Default::default()
+        };
+        extract_with_options(&archive, &out_dir, &options).await?;
+        assert!(out_dir.join("share/locale/de/postgres.mo").is_file());
+        assert!(!out_dir.join("bin").exists());
+        Ok(())
+    }
 }
@@ -48,3 +48,15 @@ pub fn extract(bytes: &Bytes, out_dir: &Path) -> crate::Result<()> {
         .handle()
         .block_on(async move { crate::extract(bytes, out_dir).await })
 }
+
+/// Extracts the compressed tar [bytes](Bytes) to the [out_dir](Path) with the
+/// [options](crate::ExtractOptions).
+pub fn extract_with_options(
+    bytes: &Bytes,
+    out_dir: &Path,
+    options: &crate::ExtractOptions,
+) -> crate::Result<()> {
+    RUNTIME
+        .handle()
+        .block_on(async move { crate::extract_with_options(bytes, out_dir, options).await })
+}
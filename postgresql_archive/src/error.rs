@@ -22,6 +22,13 @@ pub enum Error {
     /// Parse error
     #[error(transparent)]
     ParseError(anyhow::Error),
+    /// Error when two entries of an archive would overwrite each other on a case-insensitive
+    /// filesystem, e.g. APFS on macOS or NTFS on Windows
+    #[error("archive entry [{path}] collides with [{existing}] on a case-insensitive filesystem")]
+    PathCollision { path: String, existing: String },
+    /// Error when the path an archive entry is extracted to exceeds the limit of the platform
+    #[error("path [{path}] exceeds the limit of {limit} bytes")]
+    PathTooLong { path: String, limit: usize },
     /// Release not found
     #[error("release not found for version [{0}]")]
     ReleaseNotFound(String),
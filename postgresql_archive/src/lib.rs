@@ -112,9 +112,14 @@ mod archive;
 pub mod blocking;
 mod error;
 mod github;
+mod source;
 mod version;
 
-pub use archive::{extract, get_archive, get_archive_for_target, get_version};
+pub use archive::{
+    acquire_lock, extract, extract_with_options, get_archive, get_archive_for_target, get_version,
+    CollisionPolicy, ExtractFilter, ExtractOptions, LockFile,
+};
 pub use error::{Error, Result};
+pub use source::{ArchiveSource, GitHubSource, TARGET};
 #[allow(deprecated)]
 pub use version::{Version, LATEST, V12, V13, V14, V15, V16};
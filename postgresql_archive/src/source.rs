@@ -0,0 +1,61 @@
+//! Sources of PostgreSQL installation archives
+use crate::error::Result;
+use crate::version::Version;
+use crate::{get_archive_for_target, get_version};
+use async_trait::async_trait;
+use bytes::Bytes;
+use std::fmt::Debug;
+use tracing::instrument;
+
+/// [Target](https://doc.rust-lang.org/nightly/rustc/platform-support.html) of the current platform
+pub const TARGET: &str = target_triple::TARGET;
+
+/// Source of PostgreSQL installation archives. The default [GitHubSource] downloads the archives
+/// over HTTP; other sources (e.g. an S3 bucket, a directory or archives held in memory) can be
+/// used to install PostgreSQL where GitHub is not reachable.
+#[async_trait]
+pub trait ArchiveSource: Debug + Send + Sync {
+    /// Gets the version of PostgreSQL for the specified [version](Version). If the version minor
+    /// or release is not specified, then the latest matching version provided by the source is
+    /// returned.
+    async fn get_version(&self, version: &Version) -> Result<Version>;
+
+    /// Fetches the compressed tar archive for a fully specified [version](Version) of PostgreSQL
+    /// and [target](https://doc.rust-lang.org/nightly/rustc/platform-support.html); the archive
+    /// can be extracted with [extract](crate::extract).
+    async fn fetch(&self, version: &Version, target: &str) -> Result<Bytes>;
+}
+
+/// Downloads archives from the releases of
+/// [theseus-rs/postgresql-binaries](https://github.com/theseus-rs/postgresql-binaries) over
+/// HTTP, verifying the archive hashes
+#[derive(Clone, Copy, Debug, Default)]
+pub struct GitHubSource;
+
+#[async_trait]
+impl ArchiveSource for GitHubSource {
+    #[instrument(level = "debug")]
+    async fn get_version(&self, version: &Version) -> Result<Version> {
+        get_version(version).await
+    }
+
+    #[instrument(level = "debug")]
+    async fn fetch(&self, version: &Version, target: &str) -> Result<Bytes> {
+        let (_version, bytes) = get_archive_for_target(version, target).await?;
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_log::test;
+
+    #[test(tokio::test)]
+    async fn test_github_source_version_not_found() {
+        let version = Version::new(1, Some(0), Some(0));
+        let source: Box<dyn ArchiveSource> = Box::new(GitHubSource);
+        assert!(source.get_version(&version).await.is_err());
+        assert!(source.fetch(&version, TARGET).await.is_err());
+    }
+}
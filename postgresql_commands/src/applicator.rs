@@ -0,0 +1,261 @@
+use crate::describe::CommandOption;
+use crate::passfile::PassFile;
+use crate::traits::{fmt_redacted, CommandBuilder, Settings};
+use std::ffi::{OsStr, OsString};
+use std::fmt::{Debug, Formatter};
+use std::path::{Path, PathBuf};
+
+/// Applies [Settings] to any [CommandBuilder], including builders defined outside of this crate.
+/// The connection settings are passed with the libpq environment variables (`PGHOST`, `PGPORT`,
+/// `PGUSER` and `PGPASSWORD`) followed by the [additional variables](Settings::get_envs) of the
+/// settings (e.g. `PGSSLMODE`), so they apply to any program built on libpq without knowing its
+/// arguments. The program is resolved in the binary directory of the settings unless the builder
+/// has its own program directory.
+#[derive(Clone)]
+pub struct SettingsApplicator {
+    program_dir: PathBuf,
+    envs: Vec<(OsString, OsString)>,
+}
+
+impl SettingsApplicator {
+    /// Create a new [SettingsApplicator] from [Settings]
+    pub fn new(settings: &dyn Settings) -> Self {
+        let mut envs: Vec<(OsString, OsString)> = vec![
+            ("PGHOST".into(), settings.get_host()),
+            ("PGPORT".into(), settings.get_port().to_string().into()),
+            ("PGUSER".into(), settings.get_username()),
+            ("PGPASSWORD".into(), settings.get_password()),
+        ];
+        envs.extend(settings.get_envs());
+        Self {
+            program_dir: settings.get_binary_dir(),
+            envs,
+        }
+    }
+
+    /// Binary directory of the settings
+    pub fn program_dir(&self) -> &Path {
+        &self.program_dir
+    }
+
+    /// Environment variables applied to builders
+    pub fn envs(&self) -> &[(OsString, OsString)] {
+        &self.envs
+    }
+
+    /// Apply the settings to the builder; environment variables set by the builder take
+    /// precedence over those of the settings.
+    pub fn apply<B: CommandBuilder>(&self, builder: B) -> WithSettings<B> {
+        let program_dir = builder
+            .get_program_dir()
+            .clone()
+            .or_else(|| Some(self.program_dir.clone()));
+        WithSettings {
+            builder,
+            program_dir,
+            envs: self.envs.clone(),
+        }
+    }
+}
+
+impl Debug for SettingsApplicator {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> std::fmt::Result {
+        let envs: Vec<(&OsString, OsString)> = self
+            .envs
+            .iter()
+            .map(|(key, value)| (key, crate::traits::redact_env(key, value)))
+            .collect();
+        formatter
+            .debug_struct("SettingsApplicator")
+            .field("program_dir", &self.program_dir)
+            .field("envs", &envs)
+            .finish()
+    }
+}
+
+/// [CommandBuilder] with [Settings] applied by a [SettingsApplicator]
+#[derive(Clone)]
+pub struct WithSettings<B> {
+    builder: B,
+    program_dir: Option<PathBuf>,
+    envs: Vec<(OsString, OsString)>,
+}
+
+impl<B> WithSettings<B> {
+    /// Builder the settings are applied to
+    pub fn inner(&self) -> &B {
+        &self.builder
+    }
+}
+
+impl<B: CommandBuilder> Debug for WithSettings<B> {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> std::fmt::Result {
+        fmt_redacted(self, "WithSettings", formatter)
+    }
+}
+
+impl<B: CommandBuilder> CommandBuilder for WithSettings<B> {
+    /// Get the program name
+    fn get_program(&self) -> &'static OsStr {
+        self.builder.get_program()
+    }
+
+    /// Location of the program binary
+    fn get_program_dir(&self) -> &Option<PathBuf> {
+        &self.program_dir
+    }
+
+    /// Get the arguments for the command
+    fn get_args(&self) -> Vec<OsString> {
+        self.builder.get_args()
+    }
+
+    /// Get the options of the program
+    fn get_options(&self) -> &'static [CommandOption] {
+        self.builder.get_options()
+    }
+
+    /// Get the environment variables for the command
+    fn get_envs(&self) -> Vec<(OsString, OsString)> {
+        let builder_envs = self.builder.get_envs();
+        let mut envs: Vec<(OsString, OsString)> = self
+            .envs
+            .iter()
+            .filter(|(key, _)| {
+                !builder_envs
+                    .iter()
+                    .any(|(builder_key, _)| builder_key == key)
+            })
+            .cloned()
+            .collect();
+        envs.extend(builder_envs);
+        envs
+    }
+
+    /// Get the temporary password file used by the command
+    fn get_passfile(&self) -> Option<PassFile> {
+        self.builder.get_passfile()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traits::CommandToString;
+    use crate::TestSettings;
+    use test_log::test;
+
+    /// Builder for a program that is not part of this crate
+    #[derive(Debug, Default)]
+    struct BackupAgentBuilder {
+        program_dir: Option<PathBuf>,
+        target: Option<OsString>,
+        ssl_mode: Option<OsString>,
+    }
+
+    impl CommandBuilder for BackupAgentBuilder {
+        fn get_program(&self) -> &'static OsStr {
+            "backup_agent".as_ref()
+        }
+
+        fn get_program_dir(&self) -> &Option<PathBuf> {
+            &self.program_dir
+        }
+
+        fn get_args(&self) -> Vec<OsString> {
+            let mut args = Vec::new();
+            if let Some(target) = &self.target {
+                args.push("--target".into());
+                args.push(target.into());
+            }
+            args
+        }
+
+        fn get_envs(&self) -> Vec<(OsString, OsString)> {
+            match &self.ssl_mode {
+                Some(ssl_mode) => vec![("PGSSLMODE".into(), ssl_mode.into())],
+                None => Vec::new(),
+            }
+        }
+    }
+
+    struct TlsSettings;
+
+    impl Settings for TlsSettings {
+        fn get_binary_dir(&self) -> PathBuf {
+            TestSettings.get_binary_dir()
+        }
+
+        fn get_host(&self) -> OsString {
+            TestSettings.get_host()
+        }
+
+        fn get_port(&self) -> u16 {
+            TestSettings.get_port()
+        }
+
+        fn get_username(&self) -> OsString {
+            TestSettings.get_username()
+        }
+
+        fn get_password(&self) -> OsString {
+            TestSettings.get_password()
+        }
+
+        fn get_envs(&self) -> Vec<(OsString, OsString)> {
+            vec![("PGSSLMODE".into(), "require".into())]
+        }
+    }
+
+    #[test]
+    fn test_apply() {
+        let builder = BackupAgentBuilder {
+            target: Some("/backups".into()),
+            ..Default::default()
+        };
+        let command = SettingsApplicator::new(&TestSettings)
+            .apply(builder)
+            .build();
+
+        assert_eq!(
+            r#"PGHOST="localhost" PGPASSWORD="password" PGPORT="5432" PGUSER="postgres" "./backup_agent" "--target" "/backups""#,
+            command.to_command_string()
+        );
+    }
+
+    #[test]
+    fn test_apply_builder_precedence() {
+        let builder = BackupAgentBuilder {
+            program_dir: Some("/opt/agent".into()),
+            ssl_mode: Some("disable".into()),
+            ..Default::default()
+        };
+        let applicator = SettingsApplicator::new(&TlsSettings);
+        let with_settings = applicator.apply(builder);
+
+        assert_eq!(
+            PathBuf::from("/opt/agent").join("backup_agent"),
+            with_settings.get_program_file()
+        );
+        let envs = with_settings.get_envs();
+        let ssl_modes: Vec<&OsString> = envs
+            .iter()
+            .filter(|(key, _)| key == "PGSSLMODE")
+            .map(|(_, value)| value)
+            .collect();
+        assert_eq!(vec!["disable"], ssl_modes);
+        assert_eq!(Some("disable".into()), with_settings.inner().ssl_mode);
+    }
+
+    #[test]
+    fn test_redacted() {
+        let applicator = SettingsApplicator::new(&TestSettings);
+        let with_settings = applicator.apply(BackupAgentBuilder::default());
+        let debug = format!("{applicator:?} {with_settings:?}");
+        let display = with_settings.to_redacted_command_string();
+
+        assert!(!debug.contains(r#""password""#));
+        assert!(debug.contains("PGPASSWORD"));
+        assert!(display.contains(r#"PGPASSWORD="********""#));
+    }
+}
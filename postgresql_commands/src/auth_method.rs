@@ -0,0 +1,125 @@
+use crate::error::{Error, Result};
+use std::ffi::OsStr;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// Client authentication method, as written in `pg_hba.conf` and passed to the
+/// [initdb](crate::initdb::InitDbBuilder) `--auth` options
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum PgAuthMethod {
+    /// Allow the connection unconditionally
+    Trust,
+    /// Require an MD5-hashed password (`md5`); uses SCRAM when the password is stored as a
+    /// SCRAM secret
+    Md5,
+    /// Require a clear-text password (`password`)
+    Password,
+    /// Require a SCRAM-SHA-256 password exchange (`scram-sha-256`)
+    ScramSha256,
+    /// Authenticate with GSSAPI (`gss`)
+    Gss,
+    /// Authenticate with SSPI; Windows only (`sspi`)
+    Sspi,
+    /// Use the operating system user name reported by the ident server of the client (`ident`)
+    Ident,
+    /// Use the operating system user name of the client; local connections only (`peer`)
+    Peer,
+    /// Authenticate with an LDAP server (`ldap`)
+    Ldap,
+    /// Authenticate with a RADIUS server (`radius`)
+    Radius,
+    /// Authenticate with an SSL client certificate (`cert`)
+    Cert,
+    /// Authenticate with Pluggable Authentication Modules (`pam`)
+    Pam,
+}
+
+impl PgAuthMethod {
+    /// Get the keyword of the authentication method; e.g. `scram-sha-256`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PgAuthMethod::Trust => "trust",
+            PgAuthMethod::Md5 => "md5",
+            PgAuthMethod::Password => "password",
+            PgAuthMethod::ScramSha256 => "scram-sha-256",
+            PgAuthMethod::Gss => "gss",
+            PgAuthMethod::Sspi => "sspi",
+            PgAuthMethod::Ident => "ident",
+            PgAuthMethod::Peer => "peer",
+            PgAuthMethod::Ldap => "ldap",
+            PgAuthMethod::Radius => "radius",
+            PgAuthMethod::Cert => "cert",
+            PgAuthMethod::Pam => "pam",
+        }
+    }
+}
+
+impl AsRef<OsStr> for PgAuthMethod {
+    fn as_ref(&self) -> &OsStr {
+        self.as_str().as_ref()
+    }
+}
+
+impl Display for PgAuthMethod {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> std::fmt::Result {
+        formatter.write_str(self.as_str())
+    }
+}
+
+impl FromStr for PgAuthMethod {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        let method = match value {
+            "trust" => PgAuthMethod::Trust,
+            "md5" => PgAuthMethod::Md5,
+            "password" => PgAuthMethod::Password,
+            "scram-sha-256" => PgAuthMethod::ScramSha256,
+            "gss" => PgAuthMethod::Gss,
+            "sspi" => PgAuthMethod::Sspi,
+            "ident" => PgAuthMethod::Ident,
+            "peer" => PgAuthMethod::Peer,
+            "ldap" => PgAuthMethod::Ldap,
+            "radius" => PgAuthMethod::Radius,
+            "cert" => PgAuthMethod::Cert,
+            "pam" => PgAuthMethod::Pam,
+            _ => {
+                return Err(Error::ParseError(format!(
+                    "invalid authentication method {value}"
+                )))
+            }
+        };
+        Ok(method)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_auth_method() -> Result<()> {
+        let methods = [
+            PgAuthMethod::Trust,
+            PgAuthMethod::Md5,
+            PgAuthMethod::Password,
+            PgAuthMethod::ScramSha256,
+            PgAuthMethod::Gss,
+            PgAuthMethod::Sspi,
+            PgAuthMethod::Ident,
+            PgAuthMethod::Peer,
+            PgAuthMethod::Ldap,
+            PgAuthMethod::Radius,
+            PgAuthMethod::Cert,
+            PgAuthMethod::Pam,
+        ];
+        for method in methods {
+            assert_eq!(method, PgAuthMethod::from_str(&method.to_string())?);
+            assert_eq!(OsStr::new(method.as_str()), method.as_ref());
+        }
+        assert_eq!("scram-sha-256", PgAuthMethod::ScramSha256.to_string());
+        assert!(PgAuthMethod::from_str("crypt").is_err());
+        Ok(())
+    }
+}
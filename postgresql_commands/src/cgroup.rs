@@ -0,0 +1,114 @@
+//! Run commands in a control group (cgroup v2) on Linux, e.g. to exercise the behavior of a
+//! command under a memory limit or CPU throttling. The cgroup must be created and configured
+//! (e.g. `memory.max`) beforehand by a user with permission to move processes into it.
+
+use std::io::Write;
+use std::path::PathBuf;
+
+/// Place the child process of a command into a cgroup when it is spawned
+pub trait CgroupCommand {
+    /// Place the child process into the cgroup directory (e.g. `/sys/fs/cgroup/restore`) by
+    /// writing to its `cgroup.procs` file before the program is executed; spawning the command
+    /// fails when the process cannot be moved. Processes started by the child inherit the cgroup.
+    fn cgroup<P: Into<PathBuf>>(&mut self, cgroup: P) -> &mut Self;
+}
+
+/// Move the current process into the cgroup; called in the child process after it is forked.
+/// Opening a short path does not allocate, so this is safe to call between fork and exec.
+fn join_cgroup(procs: &PathBuf) -> std::io::Result<()> {
+    // Writing 0 moves the writing process
+    std::fs::OpenOptions::new()
+        .write(true)
+        .open(procs)?
+        .write_all(b"0")
+}
+
+impl CgroupCommand for std::process::Command {
+    fn cgroup<P: Into<PathBuf>>(&mut self, cgroup: P) -> &mut Self {
+        use std::os::unix::process::CommandExt;
+        let procs = cgroup.into().join("cgroup.procs");
+        // SAFETY: the closure only opens and writes to a file, which is async-signal-safe
+        unsafe { self.pre_exec(move || join_cgroup(&procs)) }
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl CgroupCommand for tokio::process::Command {
+    fn cgroup<P: Into<PathBuf>>(&mut self, cgroup: P) -> &mut Self {
+        let procs = cgroup.into().join("cgroup.procs");
+        // SAFETY: the closure only opens and writes to a file, which is async-signal-safe
+        unsafe { self.pre_exec(move || join_cgroup(&procs)) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Result;
+    use std::path::Path;
+    use std::process::Command;
+    use test_log::test;
+
+    /// Create a cgroup for the test in the cgroup v2 hierarchy; `None` when cgroup v2 is not
+    /// mounted or the process is not permitted to create cgroups
+    fn create_cgroup(name: &str) -> Option<PathBuf> {
+        let mounts = std::fs::read_to_string("/proc/mounts").ok()?;
+        let root = mounts.lines().find_map(|line| {
+            let mut fields = line.split_whitespace();
+            let mount_point = fields.nth(1)?;
+            (fields.next()? == "cgroup2").then(|| PathBuf::from(mount_point))
+        })?;
+        let cgroup = root.join(format!("{name}_{}", std::process::id()));
+        std::fs::create_dir(&cgroup).ok()?;
+        Some(cgroup)
+    }
+
+    fn procs(cgroup: &Path) -> Result<Vec<u32>> {
+        Ok(std::fs::read_to_string(cgroup.join("cgroup.procs"))?
+            .lines()
+            .filter_map(|pid| pid.trim().parse().ok())
+            .collect())
+    }
+
+    #[test]
+    fn test_cgroup() -> Result<()> {
+        let Some(cgroup) = create_cgroup("postgresql_commands_std") else {
+            return Ok(());
+        };
+        let mut child = Command::new("sleep").arg("30").cgroup(&cgroup).spawn()?;
+        let result = procs(&cgroup);
+        let _ = child.kill();
+        let _ = child.wait();
+        let _ = std::fs::remove_dir(&cgroup);
+
+        assert_eq!(vec![child.id()], result?);
+        Ok(())
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test(tokio::test)]
+    async fn test_tokio_cgroup() -> Result<()> {
+        let Some(cgroup) = create_cgroup("postgresql_commands_tokio") else {
+            return Ok(());
+        };
+        let mut child = tokio::process::Command::new("sleep")
+            .arg("30")
+            .cgroup(&cgroup)
+            .spawn()?;
+        let id = child.id();
+        let result = procs(&cgroup);
+        let _ = child.kill().await;
+        let _ = std::fs::remove_dir(&cgroup);
+
+        assert_eq!(id.into_iter().collect::<Vec<u32>>(), result?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_cgroup_missing() {
+        let result = Command::new("true")
+            .cgroup("/nonexistent/postgresql_commands")
+            .spawn();
+        assert!(result.is_err());
+    }
+}
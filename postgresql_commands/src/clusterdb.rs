@@ -1,11 +1,31 @@
+use crate::describe::CommandOption::{self, Flag, Value};
+use crate::passfile::{self, PassFile};
 use crate::traits::CommandBuilder;
 use crate::Settings;
 use std::convert::AsRef;
 use std::ffi::{OsStr, OsString};
 use std::path::PathBuf;
 
+/// Options of clusterdb, for [describe](CommandBuilder::describe)
+const OPTIONS: &[CommandOption] = &[
+    Flag("--all"),
+    Value("--dbname"),
+    Flag("--echo"),
+    Flag("--quiet"),
+    Value("--table"),
+    Flag("--verbose"),
+    Flag("--version"),
+    Flag("--help"),
+    Value("--host"),
+    Value("--port"),
+    Value("--username"),
+    Flag("--no-password"),
+    Flag("--password"),
+    Value("--maintenance-db"),
+];
+
 /// clusterdb clusters all previously clustered tables in a database.
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Default)]
 pub struct ClusterDbBuilder {
     program_dir: Option<PathBuf>,
     all: bool,
@@ -22,9 +42,12 @@ pub struct ClusterDbBuilder {
     no_password: bool,
     password: bool,
     pg_password: Option<OsString>,
+    pg_passfile: Option<PassFile>,
     maintenance_db: Option<OsString>,
 }
 
+impl_redacted_fmt!(ClusterDbBuilder);
+
 impl ClusterDbBuilder {
     /// Create a new [ClusterDbBuilder]
     pub fn new() -> Self {
@@ -131,6 +154,20 @@ impl ClusterDbBuilder {
         self
     }
 
+    /// user password, passed in a temporary password file (`PGPASSFILE`) instead of `PGPASSWORD`
+    pub fn pg_password_via_passfile<S: AsRef<OsStr>>(mut self, pg_password: S) -> Self {
+        self.pg_password = None;
+        self.pg_passfile = passfile::create(pg_password);
+        self
+    }
+
+    /// password file (`PGPASSFILE`) used instead of `PGPASSWORD`
+    pub fn pg_passfile(mut self, pg_passfile: PassFile) -> Self {
+        self.pg_password = None;
+        self.pg_passfile = Some(pg_passfile);
+        self
+    }
+
     /// Alternate maintenance database
     pub fn maintenance_db<S: AsRef<OsStr>>(mut self, db: S) -> Self {
         self.maintenance_db = Some(db.as_ref().to_os_string());
@@ -218,16 +255,28 @@ impl CommandBuilder for ClusterDbBuilder {
         args
     }
 
+    /// Get the options of clusterdb
+    fn get_options(&self) -> &'static [CommandOption] {
+        OPTIONS
+    }
+
     /// Get the environment variables for the command
     fn get_envs(&self) -> Vec<(OsString, OsString)> {
         let mut envs: Vec<(OsString, OsString)> = Vec::new();
 
-        if let Some(password) = &self.pg_password {
+        if let Some(passfile) = &self.pg_passfile {
+            envs.push(("PGPASSFILE".into(), passfile.path().into()));
+        } else if let Some(password) = &self.pg_password {
             envs.push(("PGPASSWORD".into(), password.into()));
         }
 
         envs
     }
+
+    /// Get the temporary password file used by the command
+    fn get_passfile(&self) -> Option<PassFile> {
+        self.pg_passfile.clone()
+    }
 }
 
 #[cfg(test)]
@@ -257,7 +306,7 @@ mod tests {
 
     #[test]
     fn test_builder() {
-        let command = ClusterDbBuilder::new()
+        let builder = ClusterDbBuilder::new()
             .all()
             .dbname("dbname")
             .echo()
@@ -272,12 +321,24 @@ mod tests {
             .no_password()
             .password()
             .pg_password("password")
-            .maintenance_db("postgres")
-            .build();
+            .maintenance_db("postgres");
+        crate::describe::assert_options_described(&builder);
+        let command = builder.build();
 
         assert_eq!(
             r#"PGPASSWORD="password" "clusterdb" "--all" "--dbname" "dbname" "--echo" "--quiet" "--table" "table" "--verbose" "--version" "--help" "--host" "localhost" "--port" "5432" "--username" "postgres" "--no-password" "--password" "--maintenance-db" "postgres""#,
             command.to_command_string()
         );
     }
+
+    #[test]
+    fn test_builder_redacted() {
+        let builder = ClusterDbBuilder::from(&TestSettings).pg_password("hunter2");
+        let debug = format!("{builder:?}");
+        let display = builder.to_string();
+
+        assert!(!debug.contains("hunter2"));
+        assert!(!display.contains("hunter2"));
+        assert!(display.contains(r#"PGPASSWORD="********""#));
+    }
 }
@@ -1,11 +1,37 @@
+use crate::describe::CommandOption::{self, Flag, Value};
+use crate::passfile::{self, PassFile};
 use crate::traits::CommandBuilder;
 use crate::Settings;
 use std::convert::AsRef;
 use std::ffi::{OsStr, OsString};
 use std::path::PathBuf;
 
+/// Options of createdb, for [describe](CommandBuilder::describe)
+const OPTIONS: &[CommandOption] = &[
+    Value("--tablespace"),
+    Flag("--echo"),
+    Value("--encoding"),
+    Value("--locale"),
+    Value("--lc-collate"),
+    Value("--lc-ctype"),
+    Value("--icu-locale"),
+    Value("--icu-rules"),
+    Value("--locale-provider"),
+    Value("--owner"),
+    Value("--strategy"),
+    Value("--template"),
+    Flag("--version"),
+    Flag("--help"),
+    Value("--host"),
+    Value("--port"),
+    Value("--username"),
+    Flag("--no-password"),
+    Flag("--password"),
+    Value("--maintenance-db"),
+];
+
 /// createdb creates a PostgreSQL database.
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Default)]
 pub struct CreateDbBuilder {
     program_dir: Option<PathBuf>,
     tablespace: Option<OsString>,
@@ -28,11 +54,14 @@ pub struct CreateDbBuilder {
     no_password: bool,
     password: bool,
     pg_password: Option<OsString>,
+    pg_passfile: Option<PassFile>,
     maintenance_db: Option<OsString>,
     dbname: Option<OsString>,
     description: Option<OsString>,
 }
 
+impl_redacted_fmt!(CreateDbBuilder);
+
 impl CreateDbBuilder {
     /// Create a new [CreateDbBuilder]
     pub fn new() -> Self {
@@ -175,6 +204,20 @@ impl CreateDbBuilder {
         self
     }
 
+    /// user password, passed in a temporary password file (`PGPASSFILE`) instead of `PGPASSWORD`
+    pub fn pg_password_via_passfile<S: AsRef<OsStr>>(mut self, pg_password: S) -> Self {
+        self.pg_password = None;
+        self.pg_passfile = passfile::create(pg_password);
+        self
+    }
+
+    /// password file (`PGPASSFILE`) used instead of `PGPASSWORD`
+    pub fn pg_passfile(mut self, pg_passfile: PassFile) -> Self {
+        self.pg_password = None;
+        self.pg_passfile = Some(pg_passfile);
+        self
+    }
+
     /// Alternate maintenance database
     pub fn maintenance_db<S: AsRef<OsStr>>(mut self, db: S) -> Self {
         self.maintenance_db = Some(db.as_ref().to_os_string());
@@ -315,16 +358,28 @@ impl CommandBuilder for CreateDbBuilder {
         args
     }
 
+    /// Get the options of createdb
+    fn get_options(&self) -> &'static [CommandOption] {
+        OPTIONS
+    }
+
     /// Get the environment variables for the command
     fn get_envs(&self) -> Vec<(OsString, OsString)> {
         let mut envs: Vec<(OsString, OsString)> = Vec::new();
 
-        if let Some(password) = &self.pg_password {
+        if let Some(passfile) = &self.pg_passfile {
+            envs.push(("PGPASSFILE".into(), passfile.path().into()));
+        } else if let Some(password) = &self.pg_password {
             envs.push(("PGPASSWORD".into(), password.into()));
         }
 
         envs
     }
+
+    /// Get the temporary password file used by the command
+    fn get_passfile(&self) -> Option<PassFile> {
+        self.pg_passfile.clone()
+    }
 }
 
 #[cfg(test)]
@@ -354,7 +409,7 @@ mod tests {
 
     #[test]
     fn test_builder() {
-        let command = CreateDbBuilder::new()
+        let builder = CreateDbBuilder::new()
             .tablespace("pg_default")
             .echo()
             .encoding("UTF8")
@@ -377,12 +432,24 @@ mod tests {
             .pg_password("password")
             .maintenance_db("postgres")
             .dbname("testdb")
-            .description("Test Database")
-            .build();
+            .description("Test Database");
+        crate::describe::assert_options_described(&builder);
+        let command = builder.build();
 
         assert_eq!(
             r#"PGPASSWORD="password" "createdb" "--tablespace" "pg_default" "--echo" "--encoding" "UTF8" "--locale" "en_US.UTF-8" "--lc-collate" "en_US.UTF-8" "--lc-ctype" "en_US.UTF-8" "--icu-locale" "en_US" "--icu-rules" "standard" "--locale-provider" "icu" "--owner" "postgres" "--strategy" "wal_log" "--template" "template0" "--version" "--help" "--host" "localhost" "--port" "5432" "--username" "postgres" "--no-password" "--password" "--maintenance-db" "postgres" "testdb" "Test Database""#,
             command.to_command_string()
         );
     }
+
+    #[test]
+    fn test_builder_redacted() {
+        let builder = CreateDbBuilder::from(&TestSettings).pg_password("hunter2");
+        let debug = format!("{builder:?}");
+        let display = builder.to_string();
+
+        assert!(!debug.contains("hunter2"));
+        assert!(!display.contains("hunter2"));
+        assert!(display.contains(r#"PGPASSWORD="********""#));
+    }
 }
@@ -1,11 +1,46 @@
+use crate::describe::CommandOption::{self, Flag, Value};
+use crate::passfile::{self, PassFile};
 use crate::traits::CommandBuilder;
 use crate::Settings;
 use std::convert::AsRef;
 use std::ffi::{OsStr, OsString};
 use std::path::PathBuf;
 
+/// Options of createuser, for [describe](CommandBuilder::describe)
+const OPTIONS: &[CommandOption] = &[
+    Value("--with-admin"),
+    Value("--connection-limit"),
+    Flag("--createdb"),
+    Flag("--no-createdb"),
+    Flag("--echo"),
+    Value("--member-of"),
+    Flag("--inherit"),
+    Flag("--no-inherit"),
+    Flag("--login"),
+    Flag("--no-login"),
+    Value("--with-member"),
+    Flag("--pwprompt"),
+    Flag("--createrole"),
+    Flag("--no-createrole"),
+    Flag("--superuser"),
+    Flag("--no-superuser"),
+    Value("--valid-until"),
+    Flag("--version"),
+    Flag("--interactive"),
+    Flag("--bypassrls"),
+    Flag("--no-bypassrls"),
+    Flag("--replication"),
+    Flag("--no-replication"),
+    Flag("--help"),
+    Value("--host"),
+    Value("--port"),
+    Value("--username"),
+    Flag("--no-password"),
+    Flag("--password"),
+];
+
 /// createuser creates a new PostgreSQL role.
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Default)]
 pub struct CreateUserBuilder {
     program_dir: Option<PathBuf>,
     with_admin: Option<OsString>,
@@ -38,8 +73,11 @@ pub struct CreateUserBuilder {
     no_password: bool,
     password: bool,
     pg_password: Option<OsString>,
+    pg_passfile: Option<PassFile>,
 }
 
+impl_redacted_fmt!(CreateUserBuilder);
+
 impl CreateUserBuilder {
     /// Create a new [CreateUserBuilder]
     pub fn new() -> Self {
@@ -241,6 +279,20 @@ impl CreateUserBuilder {
         self.pg_password = Some(pg_password.as_ref().to_os_string());
         self
     }
+
+    /// user password, passed in a temporary password file (`PGPASSFILE`) instead of `PGPASSWORD`
+    pub fn pg_password_via_passfile<S: AsRef<OsStr>>(mut self, pg_password: S) -> Self {
+        self.pg_password = None;
+        self.pg_passfile = passfile::create(pg_password);
+        self
+    }
+
+    /// password file (`PGPASSFILE`) used instead of `PGPASSWORD`
+    pub fn pg_passfile(mut self, pg_passfile: PassFile) -> Self {
+        self.pg_password = None;
+        self.pg_passfile = Some(pg_passfile);
+        self
+    }
 }
 
 impl CommandBuilder for CreateUserBuilder {
@@ -385,16 +437,28 @@ impl CommandBuilder for CreateUserBuilder {
         args
     }
 
+    /// Get the options of createuser
+    fn get_options(&self) -> &'static [CommandOption] {
+        OPTIONS
+    }
+
     /// Get the environment variables for the command
     fn get_envs(&self) -> Vec<(OsString, OsString)> {
         let mut envs: Vec<(OsString, OsString)> = Vec::new();
 
-        if let Some(password) = &self.pg_password {
+        if let Some(passfile) = &self.pg_passfile {
+            envs.push(("PGPASSFILE".into(), passfile.path().into()));
+        } else if let Some(password) = &self.pg_password {
             envs.push(("PGPASSWORD".into(), password.into()));
         }
 
         envs
     }
+
+    /// Get the temporary password file used by the command
+    fn get_passfile(&self) -> Option<PassFile> {
+        self.pg_passfile.clone()
+    }
 }
 
 #[cfg(test)]
@@ -424,7 +488,7 @@ mod tests {
 
     #[test]
     fn test_builder() {
-        let command = CreateUserBuilder::new()
+        let builder = CreateUserBuilder::new()
             .with_admin("admin")
             .connection_limit(10)
             .createdb()
@@ -454,12 +518,24 @@ mod tests {
             .username("username")
             .no_password()
             .password()
-            .pg_password("password")
-            .build();
+            .pg_password("password");
+        crate::describe::assert_options_described(&builder);
+        let command = builder.build();
 
         assert_eq!(
             r#"PGPASSWORD="password" "createuser" "--with-admin" "admin" "--connection-limit" "10" "--createdb" "--no-createdb" "--echo" "--member-of" "member" "--inherit" "--no-inherit" "--login" "--no-login" "--with-member" "member" "--pwprompt" "--createrole" "--no-createrole" "--superuser" "--no-superuser" "--valid-until" "2021-12-31" "--version" "--interactive" "--bypassrls" "--no-bypassrls" "--replication" "--no-replication" "--help" "--host" "localhost" "--port" "5432" "--username" "username" "--no-password" "--password""#,
             command.to_command_string()
         );
     }
+
+    #[test]
+    fn test_builder_redacted() {
+        let builder = CreateUserBuilder::from(&TestSettings).pg_password("hunter2");
+        let debug = format!("{builder:?}");
+        let display = builder.to_string();
+
+        assert!(!debug.contains("hunter2"));
+        assert!(!display.contains("hunter2"));
+        assert!(display.contains(r#"PGPASSWORD="********""#));
+    }
 }
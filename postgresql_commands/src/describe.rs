@@ -0,0 +1,133 @@
+//! Description of the configured state of a builder for debugging, without running the
+//! command; see [CommandBuilder::describe](crate::CommandBuilder::describe).
+
+use std::ffi::OsString;
+
+/// Key of the arguments that are not options of the program; e.g. the database name of psql
+pub const ARGUMENT: &str = "argument";
+
+/// Option of a program emitted by a builder
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CommandOption {
+    /// Option without a value; e.g. `--verbose`
+    Flag(&'static str),
+    /// Option followed by a value; e.g. `--dbname test`
+    Value(&'static str),
+}
+
+impl CommandOption {
+    /// Get the name of the option; e.g. `--dbname`
+    #[must_use]
+    pub fn name(&self) -> &'static str {
+        match self {
+            CommandOption::Flag(name) | CommandOption::Value(name) => name,
+        }
+    }
+}
+
+/// Describe the arguments of a command as (option, value) pairs; flags have an empty value and
+/// arguments that are not one of the options are listed as [ARGUMENT].
+pub fn describe_args(args: &[OsString], options: &[CommandOption]) -> Vec<(&'static str, String)> {
+    let mut description = Vec::new();
+    let mut args = args.iter().map(|arg| arg.to_string_lossy().into_owned());
+    while let Some(arg) = args.next() {
+        let option = options.iter().find(|option| {
+            arg.strip_prefix(option.name())
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('='))
+        });
+        let entry = match option {
+            Some(option) => match arg[option.name().len()..].strip_prefix('=') {
+                Some(value) => (option.name(), value.to_string()),
+                None => match option {
+                    CommandOption::Flag(name) => (*name, String::new()),
+                    CommandOption::Value(name) => (*name, args.next().unwrap_or_default()),
+                },
+            },
+            None => (ARGUMENT, arg),
+        };
+        description.push(entry);
+    }
+    description
+}
+
+/// Assert that every option emitted by the builder is one of its
+/// [options](crate::CommandBuilder::get_options) with the right kind, so that
+/// [describe](crate::CommandBuilder::describe) lists it as an option; used by the tests of the
+/// builders with every option set
+#[cfg(test)]
+pub(crate) fn assert_options_described<B: crate::CommandBuilder>(builder: &B) {
+    let options = builder.get_options();
+    let args = builder.get_args();
+    let mut args = args.iter().map(|arg| arg.to_string_lossy().into_owned());
+    while let Some(arg) = args.next() {
+        if !arg.starts_with('-') {
+            continue;
+        }
+        let name = arg.split_once('=').map_or(arg.as_str(), |(name, _)| name);
+        let option = options.iter().find(|option| option.name() == name);
+        match option {
+            Some(CommandOption::Value(_)) if !arg.contains('=') => {
+                let value = args.next();
+                assert!(
+                    value.as_ref().is_some_and(|value| !value.starts_with("--")),
+                    "{name} is declared with a value but is followed by {value:?}"
+                );
+            }
+            Some(_) => {}
+            None => panic!("{name} is missing from the options of the builder"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::describe::CommandOption::{Flag, Value};
+
+    const OPTIONS: &[CommandOption] = &[Value("--dbname"), Flag("--clean"), Value("-c")];
+
+    fn args(args: &[&str]) -> Vec<OsString> {
+        args.iter().map(OsString::from).collect()
+    }
+
+    #[test]
+    fn test_describe_args() {
+        let description = describe_args(
+            &args(&["--clean", "--dbname", "test", "-c", "a=b", "archive.dump"]),
+            OPTIONS,
+        );
+        assert_eq!(
+            vec![
+                ("--clean", String::new()),
+                ("--dbname", "test".to_string()),
+                ("-c", "a=b".to_string()),
+                (ARGUMENT, "archive.dump".to_string()),
+            ],
+            description
+        );
+    }
+
+    #[test]
+    fn test_describe_args_attached_value() {
+        let description = describe_args(&args(&["--dbname=test", "--dbnames"]), OPTIONS);
+        assert_eq!(
+            vec![
+                ("--dbname", "test".to_string()),
+                (ARGUMENT, "--dbnames".to_string()),
+            ],
+            description
+        );
+    }
+
+    #[test]
+    fn test_describe_args_unknown_options() {
+        let description = describe_args(&args(&["--verbose", "test"]), &[]);
+        assert_eq!(
+            vec![
+                (ARGUMENT, "--verbose".to_string()),
+                (ARGUMENT, "test".to_string()),
+            ],
+            description
+        );
+    }
+}
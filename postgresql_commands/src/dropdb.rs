@@ -1,10 +1,28 @@
+use crate::describe::CommandOption::{self, Flag, Value};
+use crate::passfile::{self, PassFile};
 use crate::traits::CommandBuilder;
 use crate::Settings;
 use std::ffi::{OsStr, OsString};
 use std::path::PathBuf;
 
+/// Options of dropdb, for [describe](CommandBuilder::describe)
+const OPTIONS: &[CommandOption] = &[
+    Flag("--echo"),
+    Flag("--force"),
+    Flag("--interactive"),
+    Flag("--version"),
+    Flag("--if-exists"),
+    Flag("--help"),
+    Value("--host"),
+    Value("--port"),
+    Value("--username"),
+    Flag("--no-password"),
+    Flag("--password"),
+    Value("--maintenance-db"),
+];
+
 /// dropdb removes a PostgreSQL database.
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Default)]
 pub struct DropDbBuilder {
     program_dir: Option<PathBuf>,
     echo: bool,
@@ -19,10 +37,13 @@ pub struct DropDbBuilder {
     no_password: bool,
     password: bool,
     pg_password: Option<OsString>,
+    pg_passfile: Option<PassFile>,
     maintenance_db: Option<OsString>,
     dbname: Option<OsString>,
 }
 
+impl_redacted_fmt!(DropDbBuilder);
+
 impl DropDbBuilder {
     /// Create a new [DropDbBuilder]
     pub fn new() -> Self {
@@ -117,6 +138,20 @@ impl DropDbBuilder {
         self
     }
 
+    /// user password, passed in a temporary password file (`PGPASSFILE`) instead of `PGPASSWORD`
+    pub fn pg_password_via_passfile<S: AsRef<OsStr>>(mut self, pg_password: S) -> Self {
+        self.pg_password = None;
+        self.pg_passfile = passfile::create(pg_password);
+        self
+    }
+
+    /// password file (`PGPASSFILE`) used instead of `PGPASSWORD`
+    pub fn pg_passfile(mut self, pg_passfile: PassFile) -> Self {
+        self.pg_password = None;
+        self.pg_passfile = Some(pg_passfile);
+        self
+    }
+
     /// Alternate maintenance database
     pub fn maintenance_db<S: AsRef<OsStr>>(mut self, db: S) -> Self {
         self.maintenance_db = Some(db.as_ref().to_os_string());
@@ -204,16 +239,28 @@ impl CommandBuilder for DropDbBuilder {
         args
     }
 
+    /// Get the options of dropdb
+    fn get_options(&self) -> &'static [CommandOption] {
+        OPTIONS
+    }
+
     /// Get the environment variables for the command
     fn get_envs(&self) -> Vec<(OsString, OsString)> {
         let mut envs: Vec<(OsString, OsString)> = Vec::new();
 
-        if let Some(password) = &self.pg_password {
+        if let Some(passfile) = &self.pg_passfile {
+            envs.push(("PGPASSFILE".into(), passfile.path().into()));
+        } else if let Some(password) = &self.pg_password {
             envs.push(("PGPASSWORD".into(), password.into()));
         }
 
         envs
     }
+
+    /// Get the temporary password file used by the command
+    fn get_passfile(&self) -> Option<PassFile> {
+        self.pg_passfile.clone()
+    }
 }
 
 #[cfg(test)]
@@ -243,7 +290,7 @@ mod tests {
 
     #[test]
     fn test_builder() {
-        let command = DropDbBuilder::new()
+        let builder = DropDbBuilder::new()
             .echo()
             .force()
             .interactive()
@@ -257,12 +304,24 @@ mod tests {
             .password()
             .pg_password("password")
             .maintenance_db("postgres")
-            .dbname("dbname")
-            .build();
+            .dbname("dbname");
+        crate::describe::assert_options_described(&builder);
+        let command = builder.build();
 
         assert_eq!(
             r#"PGPASSWORD="password" "dropdb" "--echo" "--force" "--interactive" "--version" "--if-exists" "--help" "--host" "localhost" "--port" "5432" "--username" "postgres" "--no-password" "--password" "--maintenance-db" "postgres" "dbname""#,
             command.to_command_string()
         );
     }
+
+    #[test]
+    fn test_builder_redacted() {
+        let builder = DropDbBuilder::from(&TestSettings).pg_password("hunter2");
+        let debug = format!("{builder:?}");
+        let display = builder.to_string();
+
+        assert!(!debug.contains("hunter2"));
+        assert!(!display.contains("hunter2"));
+        assert!(display.contains(r#"PGPASSWORD="********""#));
+    }
 }
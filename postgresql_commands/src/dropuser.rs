@@ -1,11 +1,27 @@
+use crate::describe::CommandOption::{self, Flag, Value};
+use crate::passfile::{self, PassFile};
 use crate::traits::CommandBuilder;
 use crate::Settings;
 use std::convert::AsRef;
 use std::ffi::{OsStr, OsString};
 use std::path::PathBuf;
 
+/// Options of dropuser, for [describe](CommandBuilder::describe)
+const OPTIONS: &[CommandOption] = &[
+    Flag("--echo"),
+    Flag("--interactive"),
+    Flag("--version"),
+    Flag("--if-exists"),
+    Flag("--help"),
+    Value("--host"),
+    Value("--port"),
+    Value("--username"),
+    Flag("--no-password"),
+    Flag("--password"),
+];
+
 /// dropuser removes a PostgreSQL role.
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Default)]
 pub struct DropUserBuilder {
     program_dir: Option<PathBuf>,
     echo: bool,
@@ -19,8 +35,11 @@ pub struct DropUserBuilder {
     no_password: bool,
     password: bool,
     pg_password: Option<OsString>,
+    pg_passfile: Option<PassFile>,
 }
 
+impl_redacted_fmt!(DropUserBuilder);
+
 impl DropUserBuilder {
     /// Create a new [DropUserBuilder]
     pub fn new() -> Self {
@@ -108,6 +127,20 @@ impl DropUserBuilder {
         self.pg_password = Some(pg_password.as_ref().to_os_string());
         self
     }
+
+    /// user password, passed in a temporary password file (`PGPASSFILE`) instead of `PGPASSWORD`
+    pub fn pg_password_via_passfile<S: AsRef<OsStr>>(mut self, pg_password: S) -> Self {
+        self.pg_password = None;
+        self.pg_passfile = passfile::create(pg_password);
+        self
+    }
+
+    /// password file (`PGPASSFILE`) used instead of `PGPASSWORD`
+    pub fn pg_passfile(mut self, pg_passfile: PassFile) -> Self {
+        self.pg_password = None;
+        self.pg_passfile = Some(pg_passfile);
+        self
+    }
 }
 
 impl CommandBuilder for DropUserBuilder {
@@ -171,16 +204,28 @@ impl CommandBuilder for DropUserBuilder {
         args
     }
 
+    /// Get the options of dropuser
+    fn get_options(&self) -> &'static [CommandOption] {
+        OPTIONS
+    }
+
     /// Get the environment variables for the command
     fn get_envs(&self) -> Vec<(OsString, OsString)> {
         let mut envs: Vec<(OsString, OsString)> = Vec::new();
 
-        if let Some(password) = &self.pg_password {
+        if let Some(passfile) = &self.pg_passfile {
+            envs.push(("PGPASSFILE".into(), passfile.path().into()));
+        } else if let Some(password) = &self.pg_password {
             envs.push(("PGPASSWORD".into(), password.into()));
         }
 
         envs
     }
+
+    /// Get the temporary password file used by the command
+    fn get_passfile(&self) -> Option<PassFile> {
+        self.pg_passfile.clone()
+    }
 }
 
 #[cfg(test)]
@@ -210,7 +255,7 @@ mod tests {
 
     #[test]
     fn test_builder() {
-        let command = DropUserBuilder::new()
+        let builder = DropUserBuilder::new()
             .echo()
             .interactive()
             .version()
@@ -221,12 +266,24 @@ mod tests {
             .username("postgres")
             .no_password()
             .password()
-            .pg_password("password")
-            .build();
+            .pg_password("password");
+        crate::describe::assert_options_described(&builder);
+        let command = builder.build();
 
         assert_eq!(
             r#"PGPASSWORD="password" "dropuser" "--echo" "--interactive" "--version" "--if-exists" "--help" "--host" "localhost" "--port" "5432" "--username" "postgres" "--no-password" "--password""#,
             command.to_command_string()
         );
     }
+
+    #[test]
+    fn test_builder_redacted() {
+        let builder = DropUserBuilder::from(&TestSettings).pg_password("hunter2");
+        let debug = format!("{builder:?}");
+        let display = builder.to_string();
+
+        assert!(!debug.contains("hunter2"));
+        assert!(!display.contains("hunter2"));
+        assert!(display.contains(r#"PGPASSWORD="********""#));
+    }
 }
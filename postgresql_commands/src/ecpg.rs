@@ -1,9 +1,26 @@
+use crate::describe::CommandOption::{self, Flag, Value};
 use crate::traits::CommandBuilder;
 use crate::Settings;
 use std::convert::AsRef;
 use std::ffi::{OsStr, OsString};
 use std::path::PathBuf;
 
+/// Options of ecpg, for [describe](CommandBuilder::describe)
+const OPTIONS: &[CommandOption] = &[
+    Flag("-c"),
+    Value("-C"),
+    Value("-D"),
+    Flag("-h"),
+    Flag("-i"),
+    Value("-I"),
+    Value("-o"),
+    Value("-r"),
+    Flag("--regression"),
+    Flag("-t"),
+    Flag("--version"),
+    Flag("--help"),
+];
+
 /// ecpg is the PostgreSQL embedded SQL preprocessor for C programs.
 #[derive(Clone, Debug, Default)]
 pub struct EcpgBuilder {
@@ -182,6 +199,11 @@ impl CommandBuilder for EcpgBuilder {
 
         args
     }
+
+    /// Get the options of ecpg
+    fn get_options(&self) -> &'static [CommandOption] {
+        OPTIONS
+    }
 }
 
 #[cfg(test)]
@@ -207,7 +229,7 @@ mod tests {
     }
     #[test]
     fn test_builder() {
-        let command = EcpgBuilder::new()
+        let builder = EcpgBuilder::new()
             .c()
             .compatibility_mode("mode")
             .symbol("symbol")
@@ -219,8 +241,9 @@ mod tests {
             .regression()
             .autocommit()
             .version()
-            .help()
-            .build();
+            .help();
+        crate::describe::assert_options_described(&builder);
+        let command = builder.build();
 
         assert_eq!(
             r#""ecpg" "-c" "-C" "mode" "-D" "symbol" "-h" "-i" "-I" "directory" "-o" "outfile" "-r" "behavior" "--regression" "-t" "--version" "--help""#,
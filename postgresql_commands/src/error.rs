@@ -1,18 +1,82 @@
+use crate::traits::{redact_arg, redact_env, CommandToString};
+use std::ffi::OsString;
+use std::fmt::{Display, Formatter};
+use std::path::PathBuf;
+
 /// PostgreSQL command result type
 pub type Result<T, E = Error> = core::result::Result<T, E>;
 
+/// Invocation of a command with secrets redacted; displayed as a command line that can be used
+/// to reproduce the command.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CommandInvocation {
+    /// Program executed
+    pub program: PathBuf,
+    /// Arguments of the command, with passwords in connection strings redacted
+    pub args: Vec<OsString>,
+    /// Environment variables set for the command, with secret values redacted
+    pub envs: Vec<(OsString, OsString)>,
+}
+
+/// Create a [`CommandInvocation`] from a [standard Command](std::process::Command)
+impl From<&std::process::Command> for CommandInvocation {
+    fn from(command: &std::process::Command) -> Self {
+        Self {
+            program: PathBuf::from(command.get_program()),
+            args: command.get_args().map(redact_arg).collect(),
+            envs: command
+                .get_envs()
+                .filter_map(|(key, value)| {
+                    value.map(|value| (key.to_os_string(), redact_env(key, value)))
+                })
+                .collect(),
+        }
+    }
+}
+
+impl Display for CommandInvocation {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut command = std::process::Command::new(&self.program);
+        command.args(&self.args);
+        command.envs(self.envs.iter().map(|(key, value)| (key, value)));
+        formatter.write_str(&command.to_command_string())
+    }
+}
+
 /// PostgreSQL command errors
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
+    /// Error when a command is cancelled before it exits
+    #[error("Command cancelled: {invocation}")]
+    CancelledError { invocation: CommandInvocation },
     /// Error when a command fails
-    #[error("Command error: stdout={stdout}; stderr={stderr}")]
-    CommandError { stdout: String, stderr: String },
+    #[error("Command error: {invocation}; stdout={stdout}; stderr={stderr}")]
+    CommandError {
+        invocation: CommandInvocation,
+        stdout: String,
+        stderr: String,
+    },
     /// Error when IO operations fail
     #[error(transparent)]
     IoError(anyhow::Error),
+    /// Error when the output of a command could not be parsed
+    #[error("Parse error: {0}")]
+    ParseError(String),
     /// Error when a command fails to execute before the timeout is reached
     #[error(transparent)]
     TimeoutError(anyhow::Error),
+    /// Error when an option is not supported by the version of the program
+    #[error(
+        "Option {option} requires version {required} or later; the program is version {actual}"
+    )]
+    UnsupportedOptionError {
+        option: String,
+        required: u64,
+        actual: u64,
+    },
+    /// Error when a builder is configured with an invalid combination of options
+    #[error("Validation error: {0}")]
+    ValidationError(String),
 }
 
 /// Convert [standard IO errors](std::io::Error) to a [embedded errors](Error::IoError)
@@ -38,7 +102,7 @@ mod test {
 
     #[test]
     fn test_from_io_error() {
-        let io_error = std::io::Error::new(std::io::ErrorKind::Other, "test");
+        let io_error = std::io::Error::other("test");
         let error = Error::from(io_error);
         assert_eq!(error.to_string(), "test");
     }
@@ -1,16 +1,63 @@
+use crate::auth_method::PgAuthMethod;
+use crate::describe::CommandOption::{self, Flag, Value};
+use crate::option_version::OptionVersion;
+use crate::sync_method::SyncMethod;
 use crate::traits::CommandBuilder;
 use crate::Settings;
 use std::convert::AsRef;
 use std::ffi::{OsStr, OsString};
 use std::path::PathBuf;
 
+/// Options of initdb that are only supported from a major version
+const OPTION_VERSIONS: &[OptionVersion] = &[OptionVersion::new("--sync-method", 17)];
+
+/// Options of initdb, for [describe](CommandBuilder::describe)
+const OPTIONS: &[CommandOption] = &[
+    Value("--auth"),
+    Value("--auth-host"),
+    Value("--auth-local"),
+    Value("--pgdata"),
+    Value("--encoding"),
+    Flag("--allow-group-access"),
+    Value("--icu-locale"),
+    Value("--icu-rules"),
+    Flag("--data-checksums"),
+    Value("--locale"),
+    Value("--lc-collate"),
+    Value("--lc-ctype"),
+    Value("--lc-messages"),
+    Value("--lc-monetary"),
+    Value("--lc-numeric"),
+    Value("--lc-time"),
+    Flag("--no-locale"),
+    Value("--locale-provider"),
+    Value("--pwfile"),
+    Value("--text-search-config"),
+    Value("--username"),
+    Flag("--pwprompt"),
+    Value("--waldir"),
+    Value("--wal-segsize"),
+    Value("--set"),
+    Flag("--debug"),
+    Flag("--discard-caches"),
+    Value("--directory"),
+    Flag("--no-clean"),
+    Flag("--no-sync"),
+    Value("--sync-method"),
+    Flag("--no-instructions"),
+    Flag("--show"),
+    Flag("--sync-only"),
+    Flag("--version"),
+    Flag("--help"),
+];
+
 /// initdb initializes a PostgreSQL database cluster.
 #[derive(Clone, Debug, Default)]
 pub struct InitDbBuilder {
     program_dir: Option<PathBuf>,
-    auth: Option<OsString>,
-    auth_host: Option<OsString>,
-    auth_local: Option<OsString>,
+    auth: Option<PgAuthMethod>,
+    auth_host: Option<PgAuthMethod>,
+    auth_local: Option<PgAuthMethod>,
     pgdata: Option<PathBuf>,
     encoding: Option<OsString>,
     allow_group_access: bool,
@@ -38,6 +85,7 @@ pub struct InitDbBuilder {
     directory: Option<OsString>,
     no_clean: bool,
     no_sync: bool,
+    sync_method: Option<SyncMethod>,
     no_instructions: bool,
     show: bool,
     sync_only: bool,
@@ -65,20 +113,20 @@ impl InitDbBuilder {
     }
 
     /// Set the default authentication method for local connections
-    pub fn auth<S: AsRef<OsStr>>(mut self, auth: S) -> Self {
-        self.auth = Some(auth.as_ref().to_os_string());
+    pub fn auth(mut self, auth: PgAuthMethod) -> Self {
+        self.auth = Some(auth);
         self
     }
 
     /// Set the default authentication method for local TCP/IP connections
-    pub fn auth_host<S: AsRef<OsStr>>(mut self, auth_host: S) -> Self {
-        self.auth_host = Some(auth_host.as_ref().to_os_string());
+    pub fn auth_host(mut self, auth_host: PgAuthMethod) -> Self {
+        self.auth_host = Some(auth_host);
         self
     }
 
     /// Set the default authentication method for local-socket connections
-    pub fn auth_local<S: AsRef<OsStr>>(mut self, auth_local: S) -> Self {
-        self.auth_local = Some(auth_local.as_ref().to_os_string());
+    pub fn auth_local(mut self, auth_local: PgAuthMethod) -> Self {
+        self.auth_local = Some(auth_local);
         self
     }
 
@@ -244,6 +292,12 @@ impl InitDbBuilder {
         self
     }
 
+    /// Set method for syncing files to disk; requires PostgreSQL 17
+    pub fn sync_method(mut self, sync_method: SyncMethod) -> Self {
+        self.sync_method = Some(sync_method);
+        self
+    }
+
     /// Do not print instructions for next steps
     pub fn no_instructions(mut self) -> Self {
         self.no_instructions = true;
@@ -292,17 +346,17 @@ impl CommandBuilder for InitDbBuilder {
 
         if let Some(auth) = &self.auth {
             args.push("--auth".into());
-            args.push(auth.into());
+            args.push(auth.as_str().into());
         }
 
         if let Some(auth_host) = &self.auth_host {
             args.push("--auth-host".into());
-            args.push(auth_host.into());
+            args.push(auth_host.as_str().into());
         }
 
         if let Some(auth_local) = &self.auth_local {
             args.push("--auth-local".into());
-            args.push(auth_local.into());
+            args.push(auth_local.as_str().into());
         }
 
         if let Some(pgdata) = &self.pgdata {
@@ -432,6 +486,11 @@ impl CommandBuilder for InitDbBuilder {
             args.push("--no-sync".into());
         }
 
+        if let Some(sync_method) = &self.sync_method {
+            args.push("--sync-method".into());
+            args.push(sync_method.as_str().into());
+        }
+
         if self.no_instructions {
             args.push("--no-instructions".into());
         }
@@ -454,6 +513,16 @@ impl CommandBuilder for InitDbBuilder {
 
         args
     }
+
+    /// Get the options of initdb
+    fn get_options(&self) -> &'static [CommandOption] {
+        OPTIONS
+    }
+
+    /// Get the options of initdb that are only supported from a major version
+    fn get_option_versions(&self) -> &'static [OptionVersion] {
+        OPTION_VERSIONS
+    }
 }
 
 #[cfg(test)]
@@ -483,10 +552,10 @@ mod tests {
 
     #[test]
     fn test_builder() {
-        let command = InitDbBuilder::new()
-            .auth("md5")
-            .auth_host("md5")
-            .auth_local("md5")
+        let builder = InitDbBuilder::new()
+            .auth(PgAuthMethod::Md5)
+            .auth_host(PgAuthMethod::ScramSha256)
+            .auth_local(PgAuthMethod::Peer)
             .pgdata("pgdata")
             .encoding("UTF8")
             .allow_group_access()
@@ -518,12 +587,37 @@ mod tests {
             .show()
             .sync_only()
             .version()
-            .help()
-            .build();
+            .help();
+        crate::describe::assert_options_described(&builder);
+        let command = builder.build();
 
         assert_eq!(
-            r#""initdb" "--auth" "md5" "--auth-host" "md5" "--auth-local" "md5" "--pgdata" "pgdata" "--encoding" "UTF8" "--allow-group-access" "--icu-locale" "en_US" "--icu-rules" "phonebook" "--data-checksums" "--locale" "en_US" "--lc-collate" "en_US" "--lc-ctype" "en_US" "--lc-messages" "en_US" "--lc-monetary" "en_US" "--lc-numeric" "en_US" "--lc-time" "en_US" "--no-locale" "--locale-provider" "icu" "--pwfile" ".pwfile" "--text-search-config" "english" "--username" "postgres" "--pwprompt" "--waldir" "waldir" "--wal-segsize" "1" "--set" "timezone=UTC" "--debug" "--discard-caches" "--directory" "directory" "--no-clean" "--no-sync" "--no-instructions" "--show" "--sync-only" "--version" "--help""#,
+            r#""initdb" "--auth" "md5" "--auth-host" "scram-sha-256" "--auth-local" "peer" "--pgdata" "pgdata" "--encoding" "UTF8" "--allow-group-access" "--icu-locale" "en_US" "--icu-rules" "phonebook" "--data-checksums" "--locale" "en_US" "--lc-collate" "en_US" "--lc-ctype" "en_US" "--lc-messages" "en_US" "--lc-monetary" "en_US" "--lc-numeric" "en_US" "--lc-time" "en_US" "--no-locale" "--locale-provider" "icu" "--pwfile" ".pwfile" "--text-search-config" "english" "--username" "postgres" "--pwprompt" "--waldir" "waldir" "--wal-segsize" "1" "--set" "timezone=UTC" "--debug" "--discard-caches" "--directory" "directory" "--no-clean" "--no-sync" "--no-instructions" "--show" "--sync-only" "--version" "--help""#,
             command.to_command_string()
         );
     }
+
+    #[test]
+    fn test_builder_sync_method() -> crate::Result<()> {
+        let builder = || {
+            InitDbBuilder::new()
+                .pgdata("pgdata")
+                .sync_method(SyncMethod::Syncfs)
+        };
+
+        assert_eq!(
+            r#""initdb" "--pgdata" "pgdata" "--sync-method" "syncfs""#,
+            builder().build().to_command_string()
+        );
+        assert_eq!(
+            r#""initdb" "--pgdata" "pgdata" "--sync-method" "syncfs""#,
+            builder().build_for_version(17)?.to_command_string()
+        );
+        assert!(matches!(
+            builder().build_for_version(16),
+            Err(crate::Error::UnsupportedOptionError { option, required: 17, actual: 16 })
+                if option == "--sync-method"
+        ));
+        Ok(())
+    }
 }
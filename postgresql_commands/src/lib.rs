@@ -1,20 +1,49 @@
 #![allow(async_fn_in_trait)]
-#![forbid(unsafe_code)]
+#![deny(unsafe_code)]
 
 //! Command builders for interacting with PostgreSQL via CLI.
 //!
 //! The commands are implemented as builders, which can be used to construct a
 //! [standard Command](std::process::Command) or [tokio Command](tokio::process::Command).
 
+/// Implement [`Debug`](std::fmt::Debug) and [`Display`](std::fmt::Display) for a command
+/// builder that carries credentials; both renderings redact secrets so that builders can safely
+/// be logged.
+macro_rules! impl_redacted_fmt {
+    ($builder:ident) => {
+        impl std::fmt::Debug for $builder {
+            fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                crate::traits::fmt_redacted(self, stringify!($builder), formatter)
+            }
+        }
+
+        impl std::fmt::Display for $builder {
+            fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                let command = crate::traits::CommandBuilder::to_redacted_command_string(self);
+                formatter.write_str(&command)
+            }
+        }
+    };
+}
+
+pub mod applicator;
+pub mod auth_method;
+#[cfg(target_os = "linux")]
+#[allow(unsafe_code)]
+pub mod cgroup;
 pub mod clusterdb;
 pub mod createdb;
 pub mod createuser;
+pub mod describe;
 pub mod dropdb;
 pub mod dropuser;
 pub mod ecpg;
 pub mod error;
 pub mod initdb;
+pub mod lsn;
 pub mod oid2name;
+pub mod option_version;
+pub mod passfile;
 pub mod pg_amcheck;
 pub mod pg_archivecleanup;
 pub mod pg_basebackup;
@@ -37,13 +66,28 @@ pub mod pg_verifybackup;
 pub mod pg_waldump;
 pub mod pgbench;
 pub mod postgres;
+pub mod progress;
 pub mod psql;
+pub mod qualified_name;
 pub mod reindexdb;
+pub mod sync_method;
+pub mod toc;
 pub mod traits;
 pub mod vacuumdb;
 pub mod vacuumlo;
 
-pub use error::{Error, Result};
+pub use applicator::{SettingsApplicator, WithSettings};
+pub use auth_method::PgAuthMethod;
+#[cfg(target_os = "linux")]
+pub use cgroup::CgroupCommand;
+pub use describe::CommandOption;
+pub use error::{CommandInvocation, Error, Result};
+pub use lsn::Lsn;
+pub use option_version::OptionVersion;
+pub use passfile::PassFile;
+pub use progress::{CancellationToken, ProgressEvent};
+pub use qualified_name::QualifiedName;
+pub use sync_method::SyncMethod;
 #[cfg(test)]
 pub use traits::TestSettings;
 pub use traits::{AsyncCommandExecutor, CommandBuilder, CommandExecutor, Settings};
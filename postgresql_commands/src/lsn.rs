@@ -0,0 +1,90 @@
+use crate::error::{Error, Result};
+use std::ffi::OsString;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// Write-ahead log location (log sequence number); written as two hexadecimal numbers
+/// separated by a slash, the high and low 32 bits of the location, e.g. `0/16E5678`
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Lsn(pub u64);
+
+impl Lsn {
+    /// Create a [Lsn] from the high and low 32 bits of the location
+    pub fn new(high: u32, low: u32) -> Self {
+        Self((u64::from(high) << 32) | u64::from(low))
+    }
+
+    /// High 32 bits of the location
+    pub fn high(&self) -> u32 {
+        (self.0 >> 32) as u32
+    }
+
+    /// Low 32 bits of the location
+    pub fn low(&self) -> u32 {
+        self.0 as u32
+    }
+}
+
+/// Parse a [Lsn] from the `high/low` format, e.g. `0/16E5678`
+impl FromStr for Lsn {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        let parse = |part: &str| u32::from_str_radix(part, 16).ok();
+        match value.split_once('/') {
+            Some((high, low)) => match (parse(high), parse(low)) {
+                (Some(high), Some(low)) => Ok(Self::new(high, low)),
+                _ => Err(Error::ParseError(format!("Invalid LSN: {value}"))),
+            },
+            None => Err(Error::ParseError(format!("Invalid LSN: {value}"))),
+        }
+    }
+}
+
+/// Format a [Lsn] in the `high/low` format used by PostgreSQL, e.g. `0/16E5678`
+impl Display for Lsn {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(formatter, "{:X}/{:X}", self.high(), self.low())
+    }
+}
+
+impl From<u64> for Lsn {
+    fn from(value: u64) -> Self {
+        Self(value)
+    }
+}
+
+impl From<Lsn> for OsString {
+    fn from(lsn: Lsn) -> Self {
+        lsn.to_string().into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() -> Result<()> {
+        let lsn: Lsn = "0/16E5678".parse()?;
+        assert_eq!(Lsn(0x16E_5678), lsn);
+        assert_eq!(Lsn::new(0xA, 0x1b), "a/1B".parse()?);
+        assert_eq!(0xA, Lsn::new(0xA, 0x1b).high());
+        assert_eq!(0x1b, Lsn::new(0xA, 0x1b).low());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        for value in ["", "16E5678", "0/", "/1", "0/G", "0/100000000", "0/1/2"] {
+            assert!(value.parse::<Lsn>().is_err(), "{value}");
+        }
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!("0/16E5678", Lsn(0x16E_5678).to_string());
+        assert_eq!("FFFFFFFF/0", Lsn::new(u32::MAX, 0).to_string());
+        assert_eq!(OsString::from("1/0"), OsString::from(Lsn::new(1, 0)));
+    }
+}
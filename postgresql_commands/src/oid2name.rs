@@ -1,9 +1,28 @@
+use crate::describe::CommandOption::{self, Flag, Value};
 use crate::traits::CommandBuilder;
 use crate::Settings;
 use std::convert::AsRef;
 use std::ffi::{OsStr, OsString};
 use std::path::PathBuf;
 
+/// Options of oid2name, for [describe](CommandBuilder::describe)
+const OPTIONS: &[CommandOption] = &[
+    Value("--filenode"),
+    Flag("--indexes"),
+    Value("--oid"),
+    Flag("--quiet"),
+    Flag("--tablespaces"),
+    Flag("--system-objects"),
+    Value("--table"),
+    Flag("--version"),
+    Flag("--extended"),
+    Flag("--help"),
+    Value("--dbname"),
+    Value("--host"),
+    Value("--port"),
+    Value("--username"),
+];
+
 /// oid2name helps to examine the file structure used by PostgreSQL.
 #[derive(Clone, Debug, Default)]
 pub struct Oid2NameBuilder {
@@ -210,6 +229,11 @@ impl CommandBuilder for Oid2NameBuilder {
 
         args
     }
+
+    /// Get the options of oid2name
+    fn get_options(&self) -> &'static [CommandOption] {
+        OPTIONS
+    }
 }
 
 #[cfg(test)]
@@ -239,7 +263,7 @@ mod tests {
 
     #[test]
     fn test_builder() {
-        let command = Oid2NameBuilder::new()
+        let builder = Oid2NameBuilder::new()
             .filenode("filenode")
             .indexes()
             .oid("oid")
@@ -253,8 +277,9 @@ mod tests {
             .dbname("dbname")
             .host("localhost")
             .port(5432)
-            .username("username")
-            .build();
+            .username("username");
+        crate::describe::assert_options_described(&builder);
+        let command = builder.build();
 
         assert_eq!(
             r#""oid2name" "--filenode" "filenode" "--indexes" "--oid" "oid" "--quiet" "--tablespaces" "--system-objects" "--table" "table" "--version" "--extended" "--help" "--dbname" "dbname" "--host" "localhost" "--port" "5432" "--username" "username""#,
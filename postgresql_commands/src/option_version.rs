@@ -0,0 +1,217 @@
+//! Options that are only supported by some major versions of the PostgreSQL programs. Each
+//! builder declares the options it emits that were introduced after the oldest supported major
+//! version, so that [CommandBuilder::build_checked](crate::CommandBuilder::build_checked) can
+//! adapt or reject the arguments for the version of the program that is run.
+
+use crate::error::{Error, Result};
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{Mutex, OnceLock};
+use tracing::debug;
+
+/// Option of a program that is supported from a major version of PostgreSQL
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OptionVersion {
+    /// Long form of the option; e.g. `--filter`
+    pub option: &'static str,
+    /// First major version of PostgreSQL supporting the option
+    pub since: u64,
+    /// Documented spelling of the option in the versions before `since`, if any; e.g.
+    /// `--blobs` for `--large-objects`
+    pub older_spelling: Option<&'static str>,
+}
+
+impl OptionVersion {
+    /// Create a new [OptionVersion] for an option without an older spelling
+    pub const fn new(option: &'static str, since: u64) -> Self {
+        Self {
+            option,
+            since,
+            older_spelling: None,
+        }
+    }
+
+    /// Create a new [OptionVersion] for an option that was spelled differently before `since`
+    pub const fn renamed(option: &'static str, since: u64, older_spelling: &'static str) -> Self {
+        Self {
+            option,
+            since,
+            older_spelling: Some(older_spelling),
+        }
+    }
+
+    /// Check if the argument is this option, either on its own or with an attached value
+    /// (e.g. `--filter=filters.txt`)
+    fn matches(&self, arg: &str) -> bool {
+        arg.strip_prefix(self.option)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('='))
+    }
+}
+
+/// Adapt the arguments of a command for the major version of the program; options that are not
+/// supported by the version are replaced with their older spelling, or rejected with
+/// [Error::UnsupportedOptionError] when there is none.
+pub fn check_args(
+    args: Vec<OsString>,
+    option_versions: &[OptionVersion],
+    major_version: u64,
+) -> Result<Vec<OsString>> {
+    args.into_iter()
+        .map(|arg| {
+            let Some(value) = arg.to_str().filter(|value| value.starts_with("--")) else {
+                return Ok(arg);
+            };
+            let Some(option_version) = option_versions
+                .iter()
+                .find(|option_version| option_version.matches(value))
+            else {
+                return Ok(arg);
+            };
+            if major_version >= option_version.since {
+                return Ok(arg);
+            }
+            match option_version.older_spelling {
+                Some(older_spelling) => Ok(OsString::from(format!(
+                    "{older_spelling}{}",
+                    &value[option_version.option.len()..]
+                ))),
+                None => Err(Error::UnsupportedOptionError {
+                    option: option_version.option.to_string(),
+                    required: option_version.since,
+                    actual: major_version,
+                }),
+            }
+        })
+        .collect()
+}
+
+/// Get the major version of the program by running it with `--version`; the version of each
+/// program file is cached for the life of the process.
+pub fn program_major_version(program_file: &Path) -> Result<u64> {
+    static VERSIONS: OnceLock<Mutex<HashMap<PathBuf, u64>>> = OnceLock::new();
+    let versions = VERSIONS.get_or_init(Mutex::default);
+    if let Some(version) = versions
+        .lock()
+        .ok()
+        .and_then(|versions| versions.get(program_file).copied())
+    {
+        return Ok(version);
+    }
+
+    let output = Command::new(program_file).arg("--version").output()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let Some(version) = parse_major_version(&stdout) else {
+        return Err(Error::ParseError(format!(
+            "unable to determine the version of {}: {}",
+            program_file.to_string_lossy(),
+            stdout.trim()
+        )));
+    };
+    debug!(
+        "Program {} is version {version}",
+        program_file.to_string_lossy()
+    );
+    if let Ok(mut versions) = versions.lock() {
+        versions.insert(program_file.to_path_buf(), version);
+    }
+    Ok(version)
+}
+
+/// Parse the major version from the first version number in the `--version` output of a
+/// program; e.g. `15` from `pg_dump (PostgreSQL) 15.18`
+pub(crate) fn parse_major_version(output: &str) -> Option<u64> {
+    let version = output
+        .lines()
+        .next()?
+        .split_whitespace()
+        .find(|token| token.starts_with(|c: char| c.is_ascii_digit()))?;
+    let major = version
+        .split(|c: char| !c.is_ascii_digit())
+        .next()
+        .filter(|major| !major.is_empty())?;
+    major.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OPTION_VERSIONS: &[OptionVersion] = &[
+        OptionVersion::new("--filter", 17),
+        OptionVersion::renamed("--large-objects", 16, "--blobs"),
+    ];
+
+    fn args(args: &[&str]) -> Vec<OsString> {
+        args.iter().map(OsString::from).collect()
+    }
+
+    #[test]
+    fn test_check_args_supported() -> Result<()> {
+        let supported = args(&["--filter", "filters.txt", "--large-objects"]);
+        assert_eq!(
+            supported.clone(),
+            check_args(supported, OPTION_VERSIONS, 17)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_check_args_older_spelling() -> Result<()> {
+        assert_eq!(
+            args(&["--blobs", "--dbname", "shop"]),
+            check_args(
+                args(&["--large-objects", "--dbname", "shop"]),
+                OPTION_VERSIONS,
+                15
+            )?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_check_args_unsupported() {
+        let result = check_args(args(&["--filter=filters.txt"]), OPTION_VERSIONS, 16);
+        assert!(matches!(
+            result,
+            Err(Error::UnsupportedOptionError { option, required: 17, actual: 16 }) if option == "--filter"
+        ));
+        assert!(check_args(args(&["--filters"]), OPTION_VERSIONS, 16).is_ok());
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn test_program_major_version() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let program = temp_dir.path().join("pg_dump");
+        std::fs::write(&program, "#!/bin/sh\necho 'pg_dump (PostgreSQL) 15.18'\n")?;
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&program, std::fs::Permissions::from_mode(0o755))?;
+        }
+
+        assert_eq!(15, program_major_version(&program)?);
+        // The version is cached, so the program is not run again
+        std::fs::remove_file(&program)?;
+        assert_eq!(15, program_major_version(&program)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_major_version() {
+        assert_eq!(
+            Some(15),
+            parse_major_version("pg_dump (PostgreSQL) 15.18\n")
+        );
+        assert_eq!(
+            Some(17),
+            parse_major_version("pg_restore (PostgreSQL) 17devel")
+        );
+        assert_eq!(
+            Some(16),
+            parse_major_version("psql (PostgreSQL) 16.4 (Debian 16.4-1.pgdg120+1)")
+        );
+        assert_eq!(None, parse_major_version("unknown"));
+    }
+}
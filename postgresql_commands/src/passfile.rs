@@ -0,0 +1,86 @@
+use crate::error::Result;
+use std::ffi::OsStr;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
+use tempfile::NamedTempFile;
+use tracing::warn;
+
+/// Temporary password file (`.pgpass`) passed to a command with the `PGPASSFILE` environment
+/// variable, for environments where passing passwords with `PGPASSWORD` is not permitted. The
+/// file is created in the temporary directory, is only accessible by the current user, and is
+/// removed when the last clone of the [`PassFile`] is dropped; keep the pass file of a builder
+/// (see [`CommandBuilder::get_passfile`](crate::CommandBuilder::get_passfile)) alive until the
+/// command exits.
+#[derive(Clone, Debug)]
+pub struct PassFile {
+    file: Arc<NamedTempFile>,
+}
+
+impl PassFile {
+    /// Create a password file that provides the password for any host, port, database and user
+    pub fn new<S: AsRef<OsStr>>(password: S) -> Result<Self> {
+        let mut file = tempfile::Builder::new().prefix(".pgpass").tempfile()?;
+        let password = escape(&password.as_ref().to_string_lossy());
+        writeln!(file, "*:*:*:*:{password}")?;
+        file.flush()?;
+        Ok(Self {
+            file: Arc::new(file),
+        })
+    }
+
+    /// Path of the password file
+    pub fn path(&self) -> &Path {
+        self.file.path()
+    }
+}
+
+/// Create a password file for a builder; when the file cannot be created, no password is
+/// provided rather than falling back to `PGPASSWORD`, so authentication fails.
+pub(crate) fn create<S: AsRef<OsStr>>(password: S) -> Option<PassFile> {
+    match PassFile::new(password) {
+        Ok(passfile) => Some(passfile),
+        Err(error) => {
+            warn!("Failed to create password file: {error}");
+            None
+        }
+    }
+}
+
+/// Escape a `.pgpass` field; backslashes and colons are escaped with a backslash
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace(':', "\\:")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_log::test;
+
+    #[test]
+    fn test_escape() {
+        assert_eq!("password", escape("password"));
+        assert_eq!(r"pass\:word\\", escape(r"pass:word\"));
+    }
+
+    #[test]
+    fn test_passfile() -> Result<()> {
+        let passfile = PassFile::new("pass:word")?;
+        let path = passfile.path().to_path_buf();
+        assert_eq!("*:*:*:*:pass\\:word\n", std::fs::read_to_string(&path)?);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path)?.permissions().mode();
+            assert_eq!(0o600, mode & 0o777);
+        }
+
+        let clone = passfile.clone();
+        drop(passfile);
+        assert!(path.exists());
+        drop(clone);
+        assert!(!path.exists());
+        Ok(())
+    }
+}
@@ -1,11 +1,53 @@
-use crate::traits::CommandBuilder;
+use crate::describe::CommandOption::{self, Flag, Value};
+use crate::error::{Error, Result};
+use crate::passfile::{self, PassFile};
+use crate::traits::{CommandBuilder, CommandExecutor};
 use crate::Settings;
 use std::convert::AsRef;
 use std::ffi::{OsStr, OsString};
 use std::path::PathBuf;
 
+/// Options of pg_amcheck, for [describe](CommandBuilder::describe)
+const OPTIONS: &[CommandOption] = &[
+    Flag("--all"),
+    Value("--database"),
+    Value("--exclude-database"),
+    Value("--index"),
+    Value("--exclude-index"),
+    Value("--relation"),
+    Value("--exclude-relation"),
+    Value("--schema"),
+    Value("--exclude-schema"),
+    Value("--table"),
+    Value("--exclude-table"),
+    Flag("--no-dependent-indexes"),
+    Flag("--no-dependent-toast"),
+    Flag("--no-strict-names"),
+    Flag("--exclude-toast-pointers"),
+    Flag("--on-error-stop"),
+    Value("--skip"),
+    Value("--startblock"),
+    Value("--endblock"),
+    Flag("--heapallindexed"),
+    Flag("--parent-check"),
+    Flag("--rootdescend"),
+    Value("--host"),
+    Value("--port"),
+    Value("--username"),
+    Flag("--no-password"),
+    Flag("--password"),
+    Value("--maintenance-db"),
+    Flag("--echo"),
+    Value("--jobs"),
+    Flag("--progress"),
+    Flag("--verbose"),
+    Flag("--version"),
+    Flag("--install-missing"),
+    Flag("--help"),
+];
+
 /// pg_amcheck checks objects in a PostgreSQL database for corruption.
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Default)]
 pub struct PgAmCheckBuilder {
     program_dir: Option<PathBuf>,
     all: bool,
@@ -36,9 +78,10 @@ pub struct PgAmCheckBuilder {
     no_password: bool,
     password: bool,
     pg_password: Option<OsString>,
+    pg_passfile: Option<PassFile>,
     maintenance_db: Option<OsString>,
     echo: bool,
-    jobs: Option<OsString>,
+    jobs: Option<u32>,
     progress: bool,
     verbose: bool,
     version: bool,
@@ -46,6 +89,30 @@ pub struct PgAmCheckBuilder {
     help: bool,
 }
 
+impl_redacted_fmt!(PgAmCheckBuilder);
+
+/// Corruption reported by pg_amcheck for a relation
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AmcheckCorruption {
+    /// Qualified name of the corrupt relation; e.g. `postgres.public.table`
+    pub relation: String,
+    /// Block containing the corruption, when reported
+    pub block_number: Option<u64>,
+    /// Item (line pointer offset) within the block containing the corruption, when reported
+    pub item_number: Option<u64>,
+    /// Description of the corruption
+    pub message: String,
+    /// Additional detail for corruptions reported as errors by amcheck
+    pub detail: Option<String>,
+}
+
+/// Corruptions reported by pg_amcheck; see [parse_pg_amcheck_output]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AmcheckReport {
+    /// Corruptions in the order reported
+    pub corruptions: Vec<AmcheckCorruption>,
+}
+
 impl PgAmCheckBuilder {
     /// Create a new [PgAmCheckBuilder]
     pub fn new() -> Self {
@@ -236,6 +303,20 @@ impl PgAmCheckBuilder {
         self
     }
 
+    /// user password, passed in a temporary password file (`PGPASSFILE`) instead of `PGPASSWORD`
+    pub fn pg_password_via_passfile<S: AsRef<OsStr>>(mut self, pg_password: S) -> Self {
+        self.pg_password = None;
+        self.pg_passfile = passfile::create(pg_password);
+        self
+    }
+
+    /// password file (`PGPASSFILE`) used instead of `PGPASSWORD`
+    pub fn pg_passfile(mut self, pg_passfile: PassFile) -> Self {
+        self.pg_password = None;
+        self.pg_passfile = Some(pg_passfile);
+        self
+    }
+
     /// alternate maintenance database
     pub fn maintenance_db<S: AsRef<OsStr>>(mut self, maintenance_db: S) -> Self {
         self.maintenance_db = Some(maintenance_db.as_ref().to_os_string());
@@ -249,8 +330,8 @@ impl PgAmCheckBuilder {
     }
 
     /// use this many concurrent connections to the server
-    pub fn jobs<S: AsRef<OsStr>>(mut self, jobs: S) -> Self {
-        self.jobs = Some(jobs.as_ref().to_os_string());
+    pub fn jobs(mut self, jobs: u32) -> Self {
+        self.jobs = Some(jobs);
         self
     }
 
@@ -283,6 +364,119 @@ impl PgAmCheckBuilder {
         self.help = true;
         self
     }
+
+    /// Check for corruption with verbose output and report the corruptions found. pg_amcheck
+    /// exits with a failure when corruption is found; the corruptions are reported rather than
+    /// returned as an error.
+    pub fn execute_and_report(&self) -> Result<AmcheckReport> {
+        self.execute_and_report_with(|builder| builder.build().execute())
+    }
+
+    /// Check for corruption, using the given function to execute the command
+    fn execute_and_report_with<F>(&self, execute: F) -> Result<AmcheckReport>
+    where
+        F: FnOnce(Self) -> Result<(String, String)>,
+    {
+        match execute(self.clone().verbose()) {
+            Ok((stdout, stderr)) => Ok(parse_pg_amcheck_output(&stdout, &stderr)),
+            Err(Error::CommandError {
+                invocation,
+                stdout,
+                stderr,
+            }) => {
+                let report = parse_pg_amcheck_output(&stdout, &stderr);
+                if report.corruptions.is_empty() {
+                    Err(Error::CommandError {
+                        invocation,
+                        stdout,
+                        stderr,
+                    })
+                } else {
+                    Ok(report)
+                }
+            }
+            Err(error) => Err(error),
+        }
+    }
+}
+
+/// Parse the corruptions reported by pg_amcheck. Each corruption is reported with a header line
+/// naming the relation and, for heap tables, the location of the corruption; e.g.
+/// `heap table "postgres.public.t", block 0, offset 1:`. The header is followed by an indented
+/// message, which is an error (with optional detail) when amcheck raised an error. Progress
+/// messages (e.g. from `--verbose`) are ignored.
+pub fn parse_pg_amcheck_output(stdout: &str, stderr: &str) -> AmcheckReport {
+    let mut report = AmcheckReport::default();
+    // Whether the lines belong to the query printed after an error with `--verbose`
+    let mut in_query = false;
+
+    for line in stdout.lines().chain(stderr.lines()) {
+        if let Some(corruption) = parse_header(line) {
+            report.corruptions.push(corruption);
+            in_query = false;
+            continue;
+        }
+        if in_query {
+            continue;
+        }
+        if line.starts_with("query was: ") {
+            in_query = true;
+            continue;
+        }
+        let Some(corruption) = report.corruptions.last_mut() else {
+            continue;
+        };
+
+        let line = line.trim();
+        if let Some(detail) = line.strip_prefix("DETAIL:") {
+            corruption.detail = Some(detail.trim().to_string());
+        } else if ["HINT:", "CONTEXT:", "LOCATION:"]
+            .iter()
+            .any(|prefix| line.starts_with(prefix))
+        {
+            continue;
+        } else if corruption.message.is_empty() && !line.is_empty() {
+            let message = line.strip_prefix("ERROR:").map_or(line, |message| {
+                let message = message.trim_start();
+                // Verbose errors are prefixed with the SQLSTATE; e.g. `XX002: `
+                match message.split_once(": ") {
+                    Some((code, message))
+                        if code.len() == 5 && code.chars().all(|c| c.is_ascii_alphanumeric()) =>
+                    {
+                        message
+                    }
+                    _ => message,
+                }
+            });
+            corruption.message = message.to_string();
+        }
+    }
+
+    report
+}
+
+/// Parse a corruption header line; e.g. `btree index "postgres.public.t_pkey":`
+fn parse_header(line: &str) -> Option<AmcheckCorruption> {
+    let line = line.strip_suffix(':')?;
+    let (kind, rest) = line.split_once(" \"")?;
+    if kind.is_empty() || !kind.chars().all(|c| c.is_ascii_lowercase() || c == ' ') {
+        return None;
+    }
+    let (relation, location) = rest.split_once('"')?;
+
+    let mut corruption = AmcheckCorruption {
+        relation: relation.to_string(),
+        ..Default::default()
+    };
+    for part in location.split(", ").filter(|part| !part.is_empty()) {
+        match part.split_once(' ') {
+            Some(("block", block)) => corruption.block_number = block.parse().ok(),
+            Some(("offset", offset)) => corruption.item_number = offset.parse().ok(),
+            Some(("attribute", _)) => {}
+            _ => return None,
+        }
+    }
+    Some(corruption)
 }
 
 impl CommandBuilder for PgAmCheckBuilder {
@@ -435,7 +629,7 @@ impl CommandBuilder for PgAmCheckBuilder {
 
         if let Some(jobs) = &self.jobs {
             args.push("--jobs".into());
-            args.push(jobs.into());
+            args.push(jobs.to_string().into());
         }
 
         if self.progress {
@@ -461,16 +655,28 @@ impl CommandBuilder for PgAmCheckBuilder {
         args
     }
 
+    /// Get the options of pg_amcheck
+    fn get_options(&self) -> &'static [CommandOption] {
+        OPTIONS
+    }
+
     /// Get the environment variables for the command
     fn get_envs(&self) -> Vec<(OsString, OsString)> {
         let mut envs: Vec<(OsString, OsString)> = Vec::new();
 
-        if let Some(password) = &self.pg_password {
+        if let Some(passfile) = &self.pg_passfile {
+            envs.push(("PGPASSFILE".into(), passfile.path().into()));
+        } else if let Some(password) = &self.pg_password {
             envs.push(("PGPASSWORD".into(), password.into()));
         }
 
         envs
     }
+
+    /// Get the temporary password file used by the command
+    fn get_passfile(&self) -> Option<PassFile> {
+        self.pg_passfile.clone()
+    }
 }
 
 #[cfg(test)]
@@ -499,7 +705,7 @@ mod tests {
     }
     #[test]
     fn test_builder() {
-        let command = PgAmCheckBuilder::new()
+        let builder = PgAmCheckBuilder::new()
             .all()
             .database("database")
             .exclude_database("exclude_database")
@@ -530,17 +736,117 @@ mod tests {
             .pg_password("password")
             .maintenance_db("maintenance_db")
             .echo()
-            .jobs("jobs")
+            .jobs(4)
             .progress()
             .verbose()
             .version()
             .install_missing()
-            .help()
-            .build();
+            .help();
+        crate::describe::assert_options_described(&builder);
+        let command = builder.build();
 
         assert_eq!(
-            r#"PGPASSWORD="password" "pg_amcheck" "--all" "--database" "database" "--exclude-database" "exclude_database" "--index" "index" "--exclude-index" "exclude_index" "--relation" "relation" "--exclude-relation" "exclude_relation" "--schema" "schema" "--exclude-schema" "exclude_schema" "--table" "table" "--exclude-table" "exclude_table" "--no-dependent-indexes" "--no-dependent-toast" "--no-strict-names" "--exclude-toast-pointers" "--on-error-stop" "--skip" "skip" "--startblock" "start_block" "--endblock" "end_block" "--heapallindexed" "--parent-check" "--rootdescend" "--host" "localhost" "--port" "5432" "--username" "username" "--no-password" "--password" "--maintenance-db" "maintenance_db" "--echo" "--jobs" "jobs" "--progress" "--verbose" "--version" "--install-missing" "--help""#,
+            r#"PGPASSWORD="password" "pg_amcheck" "--all" "--database" "database" "--exclude-database" "exclude_database" "--index" "index" "--exclude-index" "exclude_index" "--relation" "relation" "--exclude-relation" "exclude_relation" "--schema" "schema" "--exclude-schema" "exclude_schema" "--table" "table" "--exclude-table" "exclude_table" "--no-dependent-indexes" "--no-dependent-toast" "--no-strict-names" "--exclude-toast-pointers" "--on-error-stop" "--skip" "skip" "--startblock" "start_block" "--endblock" "end_block" "--heapallindexed" "--parent-check" "--rootdescend" "--host" "localhost" "--port" "5432" "--username" "username" "--no-password" "--password" "--maintenance-db" "maintenance_db" "--echo" "--jobs" "4" "--progress" "--verbose" "--version" "--install-missing" "--help""#,
             command.to_command_string()
         );
     }
+
+    #[test]
+    fn test_builder_redacted() {
+        let builder = PgAmCheckBuilder::from(&TestSettings).pg_password("hunter2");
+        let debug = format!("{builder:?}");
+        let display = builder.to_string();
+
+        assert!(!debug.contains("hunter2"));
+        assert!(!display.contains("hunter2"));
+        assert!(display.contains(r#"PGPASSWORD="********""#));
+    }
+
+    const CORRUPT_STDOUT: &str = r#"btree index "postgres.public.corrupt_t_pkey":
+    ERROR:  XX002: item order invariant violated for index "corrupt_t_pkey"
+    DETAIL:  Lower index tid=(1,2) (points to heap tid=(0,2)) higher index tid=(1,3) (points to heap tid=(0,3)) page lsn=0/3863BC0.
+    LOCATION:  bt_target_page_check, verify_nbtree.c:1532
+query was: SELECT "pg_catalog".bt_index_parent_check(index := c.oid, heapallindexed := false, rootdescend := false)
+FROM pg_catalog.pg_class c, pg_catalog.pg_index i WHERE c.oid = 16447 AND c.oid = i.indexrelid
+heap table "postgres.public.corrupt_t", block 0, offset 1:
+    xmin 4294967280 precedes oldest valid transaction ID 0:716
+heap table "postgres.public.corrupt_t", block 3, offset 7, attribute 2:
+    toast value 16390 not found in toast table
+"#;
+
+    const VERBOSE_STDERR: &str = r#"pg_amcheck: including database "postgres"
+pg_amcheck: checking btree index "postgres.public.corrupt_t_pkey"
+pg_amcheck: checking heap table "postgres.public.corrupt_t"
+"#;
+
+    #[test]
+    fn test_parse_pg_amcheck_output() {
+        let report = parse_pg_amcheck_output(CORRUPT_STDOUT, VERBOSE_STDERR);
+        assert_eq!(
+            vec![
+                AmcheckCorruption {
+                    relation: "postgres.public.corrupt_t_pkey".to_string(),
+                    block_number: None,
+                    item_number: None,
+                    message: r#"item order invariant violated for index "corrupt_t_pkey""#.to_string(),
+                    detail: Some("Lower index tid=(1,2) (points to heap tid=(0,2)) higher index tid=(1,3) (points to heap tid=(0,3)) page lsn=0/3863BC0.".to_string()),
+                },
+                AmcheckCorruption {
+                    relation: "postgres.public.corrupt_t".to_string(),
+                    block_number: Some(0),
+                    item_number: Some(1),
+                    message: "xmin 4294967280 precedes oldest valid transaction ID 0:716".to_string(),
+                    detail: None,
+                },
+                AmcheckCorruption {
+                    relation: "postgres.public.corrupt_t".to_string(),
+                    block_number: Some(3),
+                    item_number: Some(7),
+                    message: "toast value 16390 not found in toast table".to_string(),
+                    detail: None,
+                },
+            ],
+            report.corruptions
+        );
+    }
+
+    #[test]
+    fn test_parse_pg_amcheck_output_no_corruption() {
+        let report = parse_pg_amcheck_output("", VERBOSE_STDERR);
+        assert!(report.corruptions.is_empty());
+    }
+
+    #[test]
+    fn test_execute_and_report() -> Result<()> {
+        let mut command = String::new();
+        let report = PgAmCheckBuilder::new()
+            .database("postgres")
+            .execute_and_report_with(|builder| {
+                command = builder.build().to_command_string();
+                Err(Error::CommandError {
+                    invocation: Default::default(),
+                    stdout: CORRUPT_STDOUT.to_string(),
+                    stderr: VERBOSE_STDERR.to_string(),
+                })
+            })?;
+
+        assert_eq!(
+            r#""pg_amcheck" "--database" "postgres" "--verbose""#,
+            command
+        );
+        assert_eq!(3, report.corruptions.len());
+        Ok(())
+    }
+
+    #[test]
+    fn test_execute_and_report_command_error() {
+        let result = PgAmCheckBuilder::new().execute_and_report_with(|_| {
+            Err(Error::CommandError {
+                invocation: Default::default(),
+                stdout: String::new(),
+                stderr: "pg_amcheck: error: connection to server failed".to_string(),
+            })
+        });
+        assert!(matches!(result, Err(Error::CommandError { .. })));
+    }
 }
@@ -1,9 +1,19 @@
+use crate::describe::CommandOption::{self, Flag, Value};
 use crate::traits::CommandBuilder;
 use crate::Settings;
 use std::convert::AsRef;
 use std::ffi::{OsStr, OsString};
 use std::path::PathBuf;
 
+/// Options of pg_archivecleanup, for [describe](CommandBuilder::describe)
+const OPTIONS: &[CommandOption] = &[
+    Flag("-d"),
+    Flag("-n"),
+    Flag("--version"),
+    Value("-x"),
+    Flag("--help"),
+];
+
 /// pg_archivecleanup removes older WAL files from PostgreSQL archives.
 #[derive(Clone, Debug, Default)]
 pub struct PgArchiveCleanupBuilder {
@@ -123,6 +133,11 @@ impl CommandBuilder for PgArchiveCleanupBuilder {
 
         args
     }
+
+    /// Get the options of pg_archivecleanup
+    fn get_options(&self) -> &'static [CommandOption] {
+        OPTIONS
+    }
 }
 
 #[cfg(test)]
@@ -149,15 +164,16 @@ mod tests {
 
     #[test]
     fn test_builder() {
-        let command = PgArchiveCleanupBuilder::new()
+        let builder = PgArchiveCleanupBuilder::new()
             .debug()
             .dry_run()
             .version()
             .ext("partial")
             .help()
             .archive_location("archive_location")
-            .oldest_kept_wal_file("000000010000000000000001")
-            .build();
+            .oldest_kept_wal_file("000000010000000000000001");
+        crate::describe::assert_options_described(&builder);
+        let command = builder.build();
 
         assert_eq!(
             r#""pg_archivecleanup" "-d" "-n" "--version" "-x" "partial" "--help" "archive_location" "000000010000000000000001""#,
@@ -1,8 +1,27 @@
+use crate::describe::CommandOption::{self, Flag, Value};
+use crate::error::{CommandInvocation, Error, Result};
 use crate::traits::CommandBuilder;
 use crate::Settings;
 use std::convert::AsRef;
 use std::ffi::{OsStr, OsString};
+use std::io::{BufRead, BufReader, Read};
 use std::path::PathBuf;
+use std::process::Stdio;
+use tracing::debug;
+
+/// Options of pg_checksums, for [describe](CommandBuilder::describe)
+const OPTIONS: &[CommandOption] = &[
+    Value("--pgdata"),
+    Flag("--check"),
+    Flag("--disable"),
+    Flag("--enable"),
+    Value("--filenode"),
+    Flag("--no-sync"),
+    Flag("--progress"),
+    Flag("--verbose"),
+    Flag("--version"),
+    Flag("--help"),
+];
 
 /// pg_checksums enables, disables, or verifies data checksums in a PostgreSQL database cluster.
 #[derive(Clone, Debug, Default)]
@@ -96,6 +115,108 @@ impl PgChecksumsBuilder {
         self.help = true;
         self
     }
+
+    /// Execute the command with progress reporting enabled, invoking the callback for each
+    /// progress line reported by pg_checksums, and return the result parsed from the final
+    /// output. The result is returned even if the command exits with a non-zero status (e.g.
+    /// when checksum mismatches are found) as long as the summary could be parsed.
+    pub fn execute_with_progress<F: Fn(ChecksumProgress)>(
+        mut self,
+        callback: F,
+    ) -> Result<ChecksumResult> {
+        self.progress = true;
+        let mut command = self.build();
+        command.stdout(Stdio::piped()).stderr(Stdio::piped());
+        debug!("Executing command: {}", CommandInvocation::from(&command));
+
+        let mut child = command.spawn()?;
+        let mut stdout = child.stdout.take().expect("stdout");
+        let stdout_reader = std::thread::spawn(move || {
+            let mut output = String::new();
+            stdout.read_to_string(&mut output).map(|_| output)
+        });
+
+        let mut stderr = String::new();
+        let mut reader = BufReader::new(child.stderr.take().expect("stderr"));
+        let mut buffer = Vec::new();
+        // Progress lines are terminated with a carriage return when stderr is a terminal and a
+        // newline otherwise; split on carriage returns and handle newlines per line.
+        while reader.read_until(b'\r', &mut buffer)? > 0 {
+            let chunk = String::from_utf8_lossy(&buffer).into_owned();
+            for line in chunk.split(['\r', '\n']) {
+                if let Some(progress) = parse_progress(line) {
+                    callback(progress);
+                }
+            }
+            stderr.push_str(&chunk);
+            buffer.clear();
+        }
+
+        let status = child.wait()?;
+        let stdout = match stdout_reader.join() {
+            Ok(result) => result?,
+            Err(_) => return Err(Error::IoError(anyhow::anyhow!("failed to read stdout"))),
+        };
+
+        match parse_result(&stdout) {
+            Some(result) => Ok(result),
+            None if status.success() => Err(Error::ParseError(format!(
+                "checksum result not found in output: {stdout}"
+            ))),
+            None => Err(Error::CommandError {
+                invocation: CommandInvocation::from(&command),
+                stdout,
+                stderr,
+            }),
+        }
+    }
+}
+
+/// Progress reported by pg_checksums; e.g. `340/1000 MB (34%) computed`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ChecksumProgress {
+    pub processed_mb: u64,
+    pub total_mb: u64,
+    pub percentage: u8,
+}
+
+/// Result of a pg_checksums operation
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ChecksumResult {
+    /// Number of blocks scanned
+    pub blocks_processed: u64,
+    /// Number of blocks with a checksum mismatch; only reported when checking checksums
+    pub blocks_checksum_mismatch: Option<u64>,
+}
+
+/// Parse a pg_checksums progress line; e.g. `340/1000 MB (34%) computed`
+pub fn parse_progress(line: &str) -> Option<ChecksumProgress> {
+    let line = line.trim().strip_suffix("computed")?.trim_end();
+    let (sizes, percentage) = line.split_once(" MB (")?;
+    let (processed_mb, total_mb) = sizes.split_once('/')?;
+    let percentage = percentage.strip_suffix("%)")?;
+
+    Some(ChecksumProgress {
+        processed_mb: processed_mb.trim().parse().ok()?,
+        total_mb: total_mb.trim().parse().ok()?,
+        percentage: percentage.trim().parse().ok()?,
+    })
+}
+
+/// Parse the summary output of pg_checksums
+pub fn parse_result(output: &str) -> Option<ChecksumResult> {
+    let value = |name: &str| -> Option<u64> {
+        output.lines().find_map(|line| {
+            line.trim()
+                .strip_prefix(name)
+                .and_then(|value| value.trim().parse().ok())
+        })
+    };
+
+    Some(ChecksumResult {
+        blocks_processed: value("Blocks scanned:")?,
+        blocks_checksum_mismatch: value("Bad checksums:"),
+    })
 }
 
 impl CommandBuilder for PgChecksumsBuilder {
@@ -157,6 +278,11 @@ impl CommandBuilder for PgChecksumsBuilder {
 
         args
     }
+
+    /// Get the options of pg_checksums
+    fn get_options(&self) -> &'static [CommandOption] {
+        OPTIONS
+    }
 }
 
 #[cfg(test)]
@@ -181,9 +307,97 @@ mod tests {
         assert_eq!(r#""./pg_checksums""#, command.to_command_string())
     }
 
+    #[test]
+    fn test_parse_progress() {
+        assert_eq!(
+            Some(ChecksumProgress {
+                processed_mb: 340,
+                total_mb: 1000,
+                percentage: 34
+            }),
+            parse_progress("340/1000 MB (34%) computed")
+        );
+        assert_eq!(
+            Some(ChecksumProgress {
+                processed_mb: 21,
+                total_mb: 21,
+                percentage: 100
+            }),
+            parse_progress("  21/21 MB (100%) computed\r")
+        );
+        assert_eq!(None, parse_progress("pg_checksums: syncing data directory"));
+        assert_eq!(None, parse_progress("x/21 MB (100%) computed"));
+    }
+
+    #[test]
+    fn test_parse_result_check() {
+        let output = "Checksum operation completed\n\
+                      Files scanned:   947\n\
+                      Blocks scanned:  2807\n\
+                      Bad checksums:  3\n\
+                      Data checksum version: 1\n";
+        assert_eq!(
+            Some(ChecksumResult {
+                blocks_processed: 2807,
+                blocks_checksum_mismatch: Some(3)
+            }),
+            parse_result(output)
+        );
+    }
+
+    #[test]
+    fn test_parse_result_enable() {
+        let output = "Checksum operation completed\n\
+                      Files scanned:   947\n\
+                      Blocks scanned:  2807\n\
+                      Files written:  778\n\
+                      Blocks written: 2807\n\
+                      Checksums enabled in cluster\n";
+        assert_eq!(
+            Some(ChecksumResult {
+                blocks_processed: 2807,
+                blocks_checksum_mismatch: None
+            }),
+            parse_result(output)
+        );
+        assert_eq!(None, parse_result("Checksums disabled in cluster"));
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn test_execute_with_progress() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let program = temp_dir.path().join("pg_checksums");
+        std::fs::write(
+            &program,
+            "#!/bin/sh\n\
+             printf '0/21 MB (0%%) computed\\r' >&2\n\
+             printf '21/21 MB (100%%) computed\\n' >&2\n\
+             echo 'Checksum operation completed'\n\
+             echo 'Blocks scanned:  2807'\n\
+             echo 'Bad checksums:  1'\n\
+             exit 1\n",
+        )?;
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&program, std::fs::Permissions::from_mode(0o755))?;
+        }
+
+        let progress = std::sync::Mutex::new(Vec::new());
+        let result = PgChecksumsBuilder::new()
+            .program_dir(temp_dir.path())
+            .check()
+            .execute_with_progress(|value| progress.lock().unwrap().push(value.percentage))?;
+
+        assert_eq!(vec![0, 100], *progress.lock().unwrap());
+        assert_eq!(2807, result.blocks_processed);
+        assert_eq!(Some(1), result.blocks_checksum_mismatch);
+        Ok(())
+    }
+
     #[test]
     fn test_builder() {
-        let command = PgChecksumsBuilder::new()
+        let builder = PgChecksumsBuilder::new()
             .pgdata("pgdata")
             .check()
             .disable()
@@ -193,8 +407,9 @@ mod tests {
             .progress()
             .verbose()
             .version()
-            .help()
-            .build();
+            .help();
+        crate::describe::assert_options_described(&builder);
+        let command = builder.build();
 
         assert_eq!(
             r#""pg_checksums" "--pgdata" "pgdata" "--check" "--disable" "--enable" "--filenode" "12345" "--no-sync" "--progress" "--verbose" "--version" "--help""#,
@@ -1,9 +1,38 @@
+use crate::describe::CommandOption::{self, Flag, Value};
 use crate::traits::CommandBuilder;
 use crate::Settings;
 use std::convert::AsRef;
 use std::ffi::{OsStr, OsString};
 use std::path::PathBuf;
 
+/// Options of pg_config, for [describe](CommandBuilder::describe)
+const OPTIONS: &[CommandOption] = &[
+    Value("--bindir"),
+    Value("--docdir"),
+    Value("--htmldir"),
+    Value("--includedir"),
+    Value("--pkgincludedir"),
+    Value("--includedir-server"),
+    Value("--libdir"),
+    Value("--pkglibdir"),
+    Value("--localedir"),
+    Value("--mandir"),
+    Value("--sharedir"),
+    Value("--sysconfdir"),
+    Value("--pgxs"),
+    Flag("--configure"),
+    Flag("--cc"),
+    Flag("--cppflags"),
+    Flag("--cflags"),
+    Flag("--cflags_sl"),
+    Flag("--ldflags"),
+    Flag("--ldflags_ex"),
+    Flag("--ldflags_sl"),
+    Flag("--libs"),
+    Flag("--version"),
+    Flag("--help"),
+];
+
 /// pg_config provides information about the installed version of PostgreSQL.
 #[derive(Clone, Debug, Default)]
 pub struct PgConfigBuilder {
@@ -322,6 +351,11 @@ impl CommandBuilder for PgConfigBuilder {
 
         args
     }
+
+    /// Get the options of pg_config
+    fn get_options(&self) -> &'static [CommandOption] {
+        OPTIONS
+    }
 }
 
 #[cfg(test)]
@@ -348,7 +382,7 @@ mod tests {
 
     #[test]
     fn test_builder() {
-        let command = PgConfigBuilder::new()
+        let builder = PgConfigBuilder::new()
             .bindir("bindir")
             .docdir("docdir")
             .htmldir("htmldir")
@@ -372,8 +406,9 @@ mod tests {
             .ldflags_sl()
             .libs()
             .version()
-            .help()
-            .build();
+            .help();
+        crate::describe::assert_options_described(&builder);
+        let command = builder.build();
 
         assert_eq!(
             r#""pg_config" "--bindir" "bindir" "--docdir" "docdir" "--htmldir" "htmldir" "--includedir" "includedir" "--pkgincludedir" "pkgincludedir" "--includedir-server" "includedir_server" "--libdir" "libdir" "--pkglibdir" "pkglibdir" "--localedir" "localedir" "--mandir" "mandir" "--sharedir" "sharedir" "--sysconfdir" "sysconfdir" "--pgxs" "pgxs" "--configure" "--cc" "--cppflags" "--cflags" "--cflags_sl" "--ldflags" "--ldflags_ex" "--ldflags_sl" "--libs" "--version" "--help""#,
@@ -1,8 +1,12 @@
+use crate::describe::CommandOption::{self, Flag, Value};
 use crate::traits::CommandBuilder;
 use crate::Settings;
 use std::ffi::{OsStr, OsString};
 use std::path::PathBuf;
 
+/// Options of pg_controldata, for [describe](CommandBuilder::describe)
+const OPTIONS: &[CommandOption] = &[Value("--pgdata"), Flag("--version"), Flag("--help")];
+
 /// pg_controldata displays control information of a PostgreSQL database cluster.
 #[derive(Clone, Debug, Default)]
 pub struct PgControlDataBuilder {
@@ -78,6 +82,11 @@ impl CommandBuilder for PgControlDataBuilder {
 
         args
     }
+
+    /// Get the options of pg_controldata
+    fn get_options(&self) -> &'static [CommandOption] {
+        OPTIONS
+    }
 }
 
 #[cfg(test)]
@@ -103,11 +112,12 @@ mod tests {
     }
     #[test]
     fn test_builder() {
-        let command = PgControlDataBuilder::new()
+        let builder = PgControlDataBuilder::new()
             .pgdata("pgdata")
             .version()
-            .help()
-            .build();
+            .help();
+        crate::describe::assert_options_described(&builder);
+        let command = builder.build();
 
         assert_eq!(
             r#""pg_controldata" "--pgdata" "pgdata" "--version" "--help""#,
@@ -1,3 +1,4 @@
+use crate::describe::CommandOption::{self, Flag, Value};
 use crate::traits::CommandBuilder;
 use crate::Settings;
 use std::convert::AsRef;
@@ -5,6 +6,22 @@ use std::ffi::{OsStr, OsString};
 use std::fmt::Display;
 use std::path::PathBuf;
 
+/// Options of pg_ctl, for [describe](CommandBuilder::describe)
+const OPTIONS: &[CommandOption] = &[
+    Value("--pgdata"),
+    Flag("--silent"),
+    Value("--timeout"),
+    Flag("--version"),
+    Flag("--wait"),
+    Flag("--no-wait"),
+    Flag("--help"),
+    Flag("--core-files"),
+    Value("--log"),
+    Value("-o"),
+    Value("-p"),
+    Value("--mode"),
+];
+
 /// pg_ctl is a utility to initialize, start, stop, or control a PostgreSQL server.
 #[derive(Clone, Debug, Default)]
 pub struct PgCtlBuilder {
@@ -18,7 +35,7 @@ pub struct PgCtlBuilder {
     no_wait: bool,
     help: bool,
     core_files: bool,
-    log: Option<PathBuf>,
+    log_file: Option<PathBuf>,
     options: Option<OsString>,
     path_to_postgres: Option<OsString>,
     shutdown_mode: Option<ShutdownMode>,
@@ -142,9 +159,14 @@ impl PgCtlBuilder {
         self
     }
 
-    /// write (or append) server log to FILENAME
-    pub fn log<P: Into<PathBuf>>(mut self, log: P) -> Self {
-        self.log = Some(log.into());
+    /// write (or append) server log to FILENAME; same as [log_file](Self::log_file)
+    pub fn log<P: Into<PathBuf>>(self, log: P) -> Self {
+        self.log_file(log)
+    }
+
+    /// write (or append) server log to the file; e.g. a path derived from the data directory
+    pub fn log_file<P: Into<PathBuf>>(mut self, log_file: P) -> Self {
+        self.log_file = Some(log_file.into());
         self
     }
 
@@ -232,9 +254,9 @@ impl CommandBuilder for PgCtlBuilder {
             args.push("--core-files".into());
         }
 
-        if let Some(log) = &self.log {
+        if let Some(log_file) = &self.log_file {
             args.push("--log".into());
-            args.push(log.into());
+            args.push(log_file.as_os_str().into());
         }
 
         if let Some(options) = &self.options {
@@ -262,6 +284,11 @@ impl CommandBuilder for PgCtlBuilder {
 
         args
     }
+
+    /// Get the options of pg_ctl
+    fn get_options(&self) -> &'static [CommandOption] {
+        OPTIONS
+    }
 }
 
 #[cfg(test)]
@@ -308,7 +335,7 @@ mod tests {
 
     #[test]
     fn test_builder() {
-        let command = PgCtlBuilder::new()
+        let builder = PgCtlBuilder::new()
             .mode(Mode::Start)
             .pgdata("pgdata")
             .silent()
@@ -323,12 +350,30 @@ mod tests {
             .path_to_postgres("path_to_postgres")
             .shutdown_mode(ShutdownMode::Smart)
             .signal("HUP")
-            .pid("12345")
-            .build();
+            .pid("12345");
+        crate::describe::assert_options_described(&builder);
+        let command = builder.build();
 
         assert_eq!(
             r#""pg_ctl" "start" "--pgdata" "pgdata" "--silent" "--timeout" "60" "--version" "--wait" "--no-wait" "--help" "--core-files" "--log" "log" "-o" "-c log_connections=on" "-p" "path_to_postgres" "--mode" "smart" "HUP" "12345""#,
             command.to_command_string()
         );
     }
+
+    #[test]
+    fn test_builder_log_file() {
+        let pgdata = PathBuf::from("data dir").join("pgdata");
+        let command = PgCtlBuilder::new()
+            .mode(Mode::Start)
+            .pgdata(&pgdata)
+            .log_file(pgdata.join("start up.log"))
+            .build();
+
+        let log_file = pgdata.join("start up.log").to_string_lossy().to_string();
+        let pgdata = pgdata.to_string_lossy().to_string();
+        assert_eq!(
+            format!(r#""pg_ctl" "start" "--pgdata" {pgdata:?} "--log" {log_file:?}"#),
+            command.to_command_string()
+        );
+    }
 }
@@ -1,11 +1,142 @@
-use crate::traits::CommandBuilder;
+use crate::describe::CommandOption::{self, Flag, Value};
+use crate::error::{Error, Result};
+use crate::option_version::OptionVersion;
+use crate::passfile::{self, PassFile};
+use crate::progress::{self, CancellationToken, ProgressEvent};
+use crate::psql::PsqlBuilder;
+use crate::qualified_name::QualifiedName;
+use crate::traits::{CommandBuilder, CommandExecutor};
 use crate::Settings;
 use std::convert::AsRef;
 use std::ffi::{OsStr, OsString};
 use std::path::PathBuf;
+use tracing::warn;
+
+/// Separator used between the columns of the rows returned by [PgDumpBuilder::list_objects]
+/// (ASCII unit separator)
+const FIELD_SEPARATOR: char = '\u{1f}';
+/// Separator used between the rows returned by [PgDumpBuilder::list_objects] (ASCII record
+/// separator)
+const RECORD_SEPARATOR: char = '\u{1e}';
+
+/// Condition selecting the objects that are dumped, given the namespace `n` and the column with
+/// the oid of the object: objects outside of the system schemas that do not belong to an
+/// extension
+fn dumped_object_condition(oid: &str) -> String {
+    format!(
+        "n.nspname NOT IN ('pg_catalog', 'information_schema') \
+        AND n.nspname NOT LIKE 'pg\\_toast%' AND n.nspname NOT LIKE 'pg\\_temp\\_%' \
+        AND NOT EXISTS (SELECT 1 FROM pg_catalog.pg_depend d WHERE d.objid = {oid} AND d.deptype = 'e')"
+    )
+}
+
+/// Build the query listing the tables, views and functions that are dumped
+fn list_objects_query() -> String {
+    format!(
+        "SELECT 'table', n.nspname, c.relname, \
+            coalesce(s.n_live_tup, greatest(c.reltuples, 0)::bigint)::text, \
+            pg_catalog.pg_total_relation_size(c.oid) \
+        FROM pg_catalog.pg_class c \
+        JOIN pg_catalog.pg_namespace n ON n.oid = c.relnamespace \
+        LEFT JOIN pg_catalog.pg_stat_user_tables s ON s.relid = c.oid \
+        WHERE c.relkind IN ('r', 'p') AND {relations} \
+        UNION ALL \
+        SELECT CASE c.relkind WHEN 'm' THEN 'materialized view' ELSE 'view' END, n.nspname, \
+            c.relname, NULL, NULL \
+        FROM pg_catalog.pg_class c \
+        JOIN pg_catalog.pg_namespace n ON n.oid = c.relnamespace \
+        WHERE c.relkind IN ('v', 'm') AND {relations} \
+        UNION ALL \
+        SELECT 'function', n.nspname, p.proname, \
+            pg_catalog.pg_get_function_identity_arguments(p.oid), NULL \
+        FROM pg_catalog.pg_proc p \
+        JOIN pg_catalog.pg_namespace n ON n.oid = p.pronamespace \
+        WHERE {functions} \
+        ORDER BY 1, 2, 3, 4",
+        relations = dumped_object_condition("c.oid"),
+        functions = dumped_object_condition("p.oid"),
+    )
+}
+
+/// Options of pg_dump introduced after PostgreSQL 13
+const OPTION_VERSIONS: &[OptionVersion] = &[
+    OptionVersion::new("--no-toast-compression", 14),
+    OptionVersion::new("--no-table-access-method", 15),
+    OptionVersion::renamed("--large-objects", 16, "--blobs"),
+    OptionVersion::renamed("--no-large-objects", 16, "--no-blobs"),
+    OptionVersion::new("--exclude-table-and-children", 16),
+    OptionVersion::new("--exclude-table-data-and-children", 16),
+    OptionVersion::new("--table-and-children", 16),
+    OptionVersion::new("--filter", 17),
+];
+
+/// Options of pg_dump, for [describe](CommandBuilder::describe)
+const OPTIONS: &[CommandOption] = &[
+    Flag("--data-only"),
+    Flag("--large-objects"),
+    Flag("--no-large-objects"),
+    Flag("--clean"),
+    Flag("--create"),
+    Value("--extension"),
+    Value("--encoding"),
+    Value("--file"),
+    Value("--format"),
+    Value("--jobs"),
+    Value("--schema"),
+    Value("--exclude-schema"),
+    Flag("--no-owner"),
+    Flag("--no-reconnect"),
+    Flag("--schema-only"),
+    Value("--superuser"),
+    Value("--table"),
+    Value("--exclude-table"),
+    Flag("--verbose"),
+    Flag("--version"),
+    Flag("--no-privileges"),
+    Value("--compression"),
+    Flag("--binary-upgrade"),
+    Flag("--column-inserts"),
+    Flag("--attribute-inserts"),
+    Flag("--disable-dollar-quoting"),
+    Flag("--disable-triggers"),
+    Flag("--enable-row-security"),
+    Value("--exclude-table-data-and-children"),
+    Value("--extra-float-digits"),
+    Flag("--if-exists"),
+    Value("--include-foreign-data"),
+    Flag("--inserts"),
+    Flag("--load-via-partition-root"),
+    Value("--lock-wait-timeout"),
+    Flag("--no-comments"),
+    Flag("--no-publications"),
+    Flag("--no-security-labels"),
+    Flag("--no-subscriptions"),
+    Flag("--no-table-access-method"),
+    Flag("--no-tablespaces"),
+    Flag("--no-toast-compression"),
+    Flag("--no-unlogged-table-data"),
+    Flag("--on-conflict-do-nothing"),
+    Flag("--quote-all-identifiers"),
+    Value("--rows-per-insert"),
+    Value("--section"),
+    Flag("--serializable-deferrable"),
+    Value("--snapshot"),
+    Flag("--strict-names"),
+    Value("--table-and-children"),
+    Value("--filter"),
+    Flag("--use-set-session-authorization"),
+    Flag("--help"),
+    Value("--dbname"),
+    Value("--host"),
+    Value("--port"),
+    Value("--username"),
+    Flag("--no-password"),
+    Flag("--password"),
+    Value("--role"),
+];
 
 /// pg_dump dumps a database as a text file or to other formats.
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Default)]
 pub struct PgDumpBuilder {
     program_dir: Option<PathBuf>,
     data_only: bool,
@@ -17,14 +148,16 @@ pub struct PgDumpBuilder {
     encoding: Option<OsString>,
     file: Option<OsString>,
     format: Option<OsString>,
-    jobs: Option<OsString>,
+    jobs: Option<u32>,
     schema: Option<OsString>,
+    schemas: Vec<OsString>,
     exclude_schema: Option<OsString>,
     no_owner: bool,
     no_reconnect: bool,
     schema_only: bool,
     superuser: Option<OsString>,
     table: Option<OsString>,
+    tables: Vec<OsString>,
     exclude_table: Option<OsString>,
     verbose: bool,
     version: bool,
@@ -39,7 +172,7 @@ pub struct PgDumpBuilder {
     exclude_table_data_and_children: Option<OsString>,
     extra_float_digits: Option<OsString>,
     if_exists: bool,
-    include_foreign_data: Option<OsString>,
+    include_foreign_data: Vec<OsString>,
     inserts: bool,
     load_via_partition_root: bool,
     lock_wait_timeout: Option<u16>,
@@ -59,6 +192,7 @@ pub struct PgDumpBuilder {
     snapshot: Option<OsString>,
     strict_names: bool,
     table_and_children: Option<OsString>,
+    filter: Option<PathBuf>,
     use_set_session_authorization: bool,
     help: bool,
     dbname: Option<OsString>,
@@ -68,9 +202,13 @@ pub struct PgDumpBuilder {
     no_password: bool,
     password: bool,
     pg_password: Option<OsString>,
+    pg_passfile: Option<PassFile>,
     role: Option<OsString>,
+    target_version: Option<u64>,
 }
 
+impl_redacted_fmt!(PgDumpBuilder);
+
 impl PgDumpBuilder {
     /// Create a new [PgDumpBuilder]
     pub fn new() -> Self {
@@ -148,8 +286,8 @@ impl PgDumpBuilder {
     }
 
     /// Use this many parallel jobs to dump
-    pub fn jobs<S: AsRef<OsStr>>(mut self, jobs: S) -> Self {
-        self.jobs = Some(jobs.as_ref().to_os_string());
+    pub fn jobs(mut self, jobs: u32) -> Self {
+        self.jobs = Some(jobs);
         self
     }
 
@@ -159,6 +297,133 @@ impl PgDumpBuilder {
         self
     }
 
+    /// Dump data for the schemas whose names match the SQL `LIKE` pattern (e.g. `tenant_%`)
+    /// only. The matching schemas are looked up in `pg_catalog.pg_namespace` with psql, using
+    /// the connection options of this builder, and each one is added as a `--schema` argument
+    /// matching exactly that name (see [QualifiedName::to_pattern]), with
+    /// [strict_names](Self::strict_names) set. System schemas never match. An error is
+    /// returned when no schema matches, rather than dumping the entire database.
+    pub fn schemas_matching<S: AsRef<str>>(self, pattern: S) -> Result<Self> {
+        self.schemas_matching_with(pattern.as_ref(), |psql| {
+            let _passfile = psql.get_passfile();
+            let (stdout, _stderr) = psql.build().execute()?;
+            Ok(stdout)
+        })
+    }
+
+    /// Add the schemas matching the pattern, using the given function to execute the psql query
+    fn schemas_matching_with<F>(mut self, pattern: &str, query: F) -> Result<Self>
+    where
+        F: FnOnce(PsqlBuilder) -> Result<String>,
+    {
+        let stdout = query(self.schemas_query(pattern))?;
+        let schemas: Vec<OsString> = stdout
+            .lines()
+            .map(str::trim)
+            .filter(|schema| !schema.is_empty())
+            .map(|schema| OsString::from(QualifiedName::new(schema).to_pattern()))
+            .collect();
+        if schemas.is_empty() {
+            return Err(Error::ValidationError(format!(
+                "no schemas match pattern {pattern}"
+            )));
+        }
+
+        self.schemas.extend(schemas);
+        Ok(self.strict_names())
+    }
+
+    /// Build the psql command that lists the names of the schemas matching the pattern, other
+    /// than the system schemas
+    fn schemas_query(&self, pattern: &str) -> PsqlBuilder {
+        let sql = format!(
+            "SELECT nspname FROM pg_catalog.pg_namespace WHERE nspname LIKE '{}' AND nspname !~ '^pg_' AND nspname <> 'information_schema' ORDER BY nspname",
+            pattern.replace('\'', "''")
+        );
+        self.query(sql)
+    }
+
+    /// Execute the dump with verbose output enabled, invoking the callback with a
+    /// [ProgressEvent::Dump] for each table whose data is dumped. pg_dump does not report the
+    /// number of tables up front; use [list_objects](Self::list_objects) to estimate the size of
+    /// the dump. The dump is killed when the token is cancelled; the partial output is not
+    /// removed.
+    pub fn execute_with_progress<F: Fn(ProgressEvent)>(
+        self,
+        callback: F,
+        cancel: &CancellationToken,
+    ) -> Result<DumpProgress> {
+        let mut dump_progress = DumpProgress::default();
+        progress::execute_builder_with_cancellation(self.verbose(), cancel, |line| {
+            if dump_progress.update(line) {
+                callback(ProgressEvent::Dump(dump_progress.clone()));
+            }
+        })?;
+        Ok(dump_progress)
+    }
+
+    /// List the tables, views and functions in the database, with the estimated number of rows
+    /// and the total size (including indexes and TOAST data) of each table, to predict the size
+    /// and duration of a dump before starting it. The objects are queried with psql, using the
+    /// connection options of this builder. All objects outside of the system schemas are listed,
+    /// except for objects that belong to extensions; the schema and table selection options of
+    /// this builder are not applied.
+    pub fn list_objects(&self) -> Result<DumpObjectList> {
+        self.list_objects_with(|psql| {
+            let _passfile = psql.get_passfile();
+            let (stdout, _stderr) = psql.build().execute()?;
+            Ok(stdout)
+        })
+    }
+
+    /// List the objects in the database, using the given function to execute the psql query
+    fn list_objects_with<F>(&self, query: F) -> Result<DumpObjectList>
+    where
+        F: FnOnce(PsqlBuilder) -> Result<String>,
+    {
+        let psql = self
+            .query(list_objects_query())
+            .field_separator(FIELD_SEPARATOR.to_string())
+            .record_separator(RECORD_SEPARATOR.to_string());
+        let stdout = query(psql)?;
+        parse_dump_objects(&stdout)
+    }
+
+    /// Build a psql command that executes the query using the connection options of this builder
+    fn query<S: AsRef<OsStr>>(&self, sql: S) -> PsqlBuilder {
+        let mut psql = PsqlBuilder::new()
+            .command(sql)
+            .tuples_only()
+            .no_align()
+            .no_psqlrc();
+
+        if let Some(program_dir) = &self.program_dir {
+            psql = psql.program_dir(program_dir);
+        }
+        if let Some(dbname) = &self.dbname {
+            psql = psql.dbname(dbname);
+        }
+        if let Some(host) = &self.host {
+            psql = psql.host(host);
+        }
+        if let Some(port) = self.port {
+            psql = psql.port(port);
+        }
+        if let Some(username) = &self.username {
+            psql = psql.username(username);
+        }
+        if self.no_password {
+            psql = psql.no_password();
+        }
+        if let Some(pg_passfile) = &self.pg_passfile {
+            psql = psql.pg_passfile(pg_passfile.clone());
+        }
+        if let Some(pg_password) = &self.pg_password {
+            psql = psql.pg_password(pg_password);
+        }
+        psql
+    }
+
     /// Do not output commands to set ownership of objects to match the original database
     pub fn exclude_schema<S: AsRef<OsStr>>(mut self, exclude_schema: S) -> Self {
         self.exclude_schema = Some(exclude_schema.as_ref().to_os_string());
@@ -201,6 +466,36 @@ impl PgDumpBuilder {
         self
     }
 
+    /// Dump data for the table with exactly this name only; see [QualifiedName::to_pattern]
+    pub fn qualified_table(self, table: &QualifiedName) -> Self {
+        self.table(table.to_pattern())
+    }
+
+    /// Dump data for the tables with exactly these names only, in addition to the
+    /// [table](Self::table); see [QualifiedName::to_pattern]
+    pub fn qualified_tables<'a, I>(mut self, tables: I) -> Self
+    where
+        I: IntoIterator<Item = &'a QualifiedName>,
+    {
+        self.tables.extend(
+            tables
+                .into_iter()
+                .map(|table| OsString::from(table.to_pattern())),
+        );
+        self
+    }
+
+    /// Do not dump the table with exactly this name; see [QualifiedName::to_pattern]
+    pub fn exclude_qualified_table(self, table: &QualifiedName) -> Self {
+        self.exclude_table(table.to_pattern())
+    }
+
+    /// Dump data for the table with exactly this name only, failing if the table does not exist
+    /// rather than dumping nothing
+    pub fn strict_table(self, table: &QualifiedName) -> Self {
+        self.qualified_table(table).strict_names()
+    }
+
     /// Enable verbose mode
     pub fn verbose(mut self) -> Self {
         self.verbose = true;
@@ -226,6 +521,10 @@ impl PgDumpBuilder {
     }
 
     /// Dump data in a format suitable for binary upgrade
+    ///
+    /// **This option is for use by in-place upgrade utilities such as `pg_upgrade` only.** It
+    /// should not be used in normal dump workflows, and its behavior may change between
+    /// PostgreSQL versions without notice.
     pub fn binary_upgrade(mut self) -> Self {
         self.binary_upgrade = true;
         self
@@ -283,9 +582,11 @@ impl PgDumpBuilder {
         self
     }
 
-    /// Include foreign-data wrappers in the dump
+    /// Include the data of foreign tables on foreign servers whose name matches the pattern
+    /// (`pg_catalog.pg_foreign_server.srvname`, e.g. `archive_*`); may be given multiple times
     pub fn include_foreign_data<S: AsRef<OsStr>>(mut self, include_foreign_data: S) -> Self {
-        self.include_foreign_data = Some(include_foreign_data.as_ref().to_os_string());
+        self.include_foreign_data
+            .push(include_foreign_data.as_ref().to_os_string());
         self
     }
 
@@ -295,7 +596,9 @@ impl PgDumpBuilder {
         self
     }
 
-    /// Load data via the partition root table
+    /// Load data via the partition root table; the COPY or INSERT statements of a partition
+    /// target the root of its partitioning hierarchy, so that rows are routed to the right
+    /// partition when the data is reloaded into partitions with different boundaries
     pub fn load_via_partition_root(mut self) -> Self {
         self.load_via_partition_root = true;
         self
@@ -343,7 +646,7 @@ impl PgDumpBuilder {
         self
     }
 
-    /// Do not output TOAST table compression
+    /// Do not output TOAST table compression (PostgreSQL 14+)
     pub fn no_toast_compression(mut self) -> Self {
         self.no_toast_compression = true;
         self
@@ -403,6 +706,12 @@ impl PgDumpBuilder {
         self
     }
 
+    /// Read the objects to include or exclude from the filter file (PostgreSQL 17 and later)
+    pub fn filter<P: Into<PathBuf>>(mut self, filter: P) -> Self {
+        self.filter = Some(filter.into());
+        self
+    }
+
     /// Use SET SESSION AUTHORIZATION commands instead of ALTER OWNER
     pub fn use_set_session_authorization(mut self) -> Self {
         self.use_set_session_authorization = true;
@@ -457,11 +766,53 @@ impl PgDumpBuilder {
         self
     }
 
+    /// user password, passed in a temporary password file (`PGPASSFILE`) instead of `PGPASSWORD`
+    pub fn pg_password_via_passfile<S: AsRef<OsStr>>(mut self, pg_password: S) -> Self {
+        self.pg_password = None;
+        self.pg_passfile = passfile::create(pg_password);
+        self
+    }
+
+    /// password file (`PGPASSFILE`) used instead of `PGPASSWORD`
+    pub fn pg_passfile(mut self, pg_passfile: PassFile) -> Self {
+        self.pg_password = None;
+        self.pg_passfile = Some(pg_passfile);
+        self
+    }
+
     /// Specifies a role name to be used to create the dump
     pub fn role<S: AsRef<OsStr>>(mut self, rolename: S) -> Self {
         self.role = Some(rolename.as_ref().to_os_string());
         self
     }
+
+    /// Major version of the pg_dump program; options not supported by this version are omitted
+    /// from the arguments. When not set, all configured options are emitted.
+    pub fn target_version(mut self, major_version: u64) -> Self {
+        self.target_version = Some(major_version);
+        self
+    }
+
+    /// Check if the target version supports an option introduced in the given major version
+    fn supports(&self, major_version: u64) -> bool {
+        self.target_version
+            .is_none_or(|target_version| target_version >= major_version)
+    }
+
+    /// Validate the combination of options. A warning is emitted when `--load-via-partition-root`
+    /// is used with `--schema-only`, since no data is dumped for the option to apply to; the
+    /// option applies to all formats, including plain text.
+    pub fn validate(&self) -> Result<()> {
+        if self.load_via_partition_root_ignored() {
+            warn!("--load-via-partition-root has no effect with --schema-only");
+        }
+        Ok(())
+    }
+
+    /// Check if `--load-via-partition-root` is set but no data is dumped
+    fn load_via_partition_root_ignored(&self) -> bool {
+        self.load_via_partition_root && self.schema_only
+    }
 }
 
 impl CommandBuilder for PgDumpBuilder {
@@ -521,7 +872,7 @@ impl CommandBuilder for PgDumpBuilder {
 
         if let Some(jobs) = &self.jobs {
             args.push("--jobs".into());
-            args.push(jobs.into());
+            args.push(jobs.to_string().into());
         }
 
         if let Some(schema) = &self.schema {
@@ -529,6 +880,11 @@ impl CommandBuilder for PgDumpBuilder {
             args.push(schema.into());
         }
 
+        for schema in &self.schemas {
+            args.push("--schema".into());
+            args.push(schema.into());
+        }
+
         if let Some(exclude_schema) = &self.exclude_schema {
             args.push("--exclude-schema".into());
             args.push(exclude_schema.into());
@@ -556,6 +912,11 @@ impl CommandBuilder for PgDumpBuilder {
             args.push(table.into());
         }
 
+        for table in &self.tables {
+            args.push("--table".into());
+            args.push(table.into());
+        }
+
         if let Some(exclude_table) = &self.exclude_table {
             args.push("--exclude-table".into());
             args.push(exclude_table.into());
@@ -616,7 +977,7 @@ impl CommandBuilder for PgDumpBuilder {
             args.push("--if-exists".into());
         }
 
-        if let Some(include_foreign_data) = &self.include_foreign_data {
+        for include_foreign_data in &self.include_foreign_data {
             args.push("--include-foreign-data".into());
             args.push(include_foreign_data.into());
         }
@@ -658,7 +1019,7 @@ impl CommandBuilder for PgDumpBuilder {
             args.push("--no-tablespaces".into());
         }
 
-        if self.no_toast_compression {
+        if self.no_toast_compression && self.supports(14) {
             args.push("--no-toast-compression".into());
         }
 
@@ -702,6 +1063,11 @@ impl CommandBuilder for PgDumpBuilder {
             args.push(table_and_children.into());
         }
 
+        if let Some(filter) = &self.filter {
+            args.push("--filter".into());
+            args.push(filter.into());
+        }
+
         if self.use_set_session_authorization {
             args.push("--use-set-session-authorization".into());
         }
@@ -746,16 +1112,170 @@ impl CommandBuilder for PgDumpBuilder {
         args
     }
 
+    /// Get the options of pg_dump
+    fn get_options(&self) -> &'static [CommandOption] {
+        OPTIONS
+    }
+
+    /// Get the options of pg_dump that are only supported from a major version
+    fn get_option_versions(&self) -> &'static [OptionVersion] {
+        OPTION_VERSIONS
+    }
+
     /// Get the environment variables for the command
     fn get_envs(&self) -> Vec<(OsString, OsString)> {
         let mut envs: Vec<(OsString, OsString)> = Vec::new();
 
-        if let Some(password) = &self.pg_password {
+        if let Some(passfile) = &self.pg_passfile {
+            envs.push(("PGPASSFILE".into(), passfile.path().into()));
+        } else if let Some(password) = &self.pg_password {
             envs.push(("PGPASSWORD".into(), password.into()));
         }
 
         envs
     }
+
+    /// Get the temporary password file used by the command
+    fn get_passfile(&self) -> Option<PassFile> {
+        self.pg_passfile.clone()
+    }
+}
+
+/// Coarse progress of a dump; the number of tables whose data has been dumped, based on the
+/// verbose output of pg_dump
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DumpProgress {
+    /// Number of tables whose data has been dumped
+    pub tables_dumped: usize,
+    /// Table most recently dumped, as reported by pg_dump; not set before the first table
+    pub table: Option<String>,
+}
+
+impl DumpProgress {
+    /// Update the progress from a line of the verbose output of pg_dump; returns whether the
+    /// line reported a table being dumped
+    pub fn update(&mut self, line: &str) -> bool {
+        match parse_dumped_table(line) {
+            Some(table) => {
+                self.tables_dumped += 1;
+                self.table = Some(table);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Parse the table from a verbose pg_dump line reporting the data of a table being dumped; e.g.
+/// `pg_dump: dumping contents of table "public.users"`
+pub fn parse_dumped_table(line: &str) -> Option<String> {
+    let table = line
+        .trim()
+        .strip_prefix("pg_dump: dumping contents of table ")?;
+    Some(table.trim_matches('"').to_string())
+}
+
+/// Objects that are included in a dump; see [PgDumpBuilder::list_objects]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DumpObjectList {
+    /// Tables, including partitioned tables and partitions
+    pub tables: Vec<TableInfo>,
+    /// Views and materialized views
+    pub views: Vec<ViewInfo>,
+    /// Functions and procedures
+    pub functions: Vec<FunctionInfo>,
+}
+
+impl DumpObjectList {
+    /// Estimated number of rows in all tables
+    pub fn estimated_rows(&self) -> u64 {
+        self.tables.iter().map(|table| table.estimated_rows).sum()
+    }
+
+    /// Total size of all tables in bytes
+    pub fn total_bytes(&self) -> u64 {
+        self.tables.iter().map(|table| table.total_bytes).sum()
+    }
+}
+
+/// Table included in a dump
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TableInfo {
+    /// Schema containing the table
+    pub schema: String,
+    /// Name of the table
+    pub name: String,
+    /// Estimated number of rows, from the statistics collector (`pg_stat_user_tables`) or, when
+    /// not available, the last vacuum or analyze
+    pub estimated_rows: u64,
+    /// Total size of the table in bytes, including indexes and TOAST data
+    /// (`pg_total_relation_size()`)
+    pub total_bytes: u64,
+}
+
+/// View included in a dump
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ViewInfo {
+    /// Schema containing the view
+    pub schema: String,
+    /// Name of the view
+    pub name: String,
+    /// Whether the view is a materialized view, whose data is dumped as well
+    pub materialized: bool,
+}
+
+/// Function included in a dump
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FunctionInfo {
+    /// Schema containing the function
+    pub schema: String,
+    /// Name of the function
+    pub name: String,
+    /// Arguments identifying the function among functions of the same name; e.g.
+    /// `a integer, b text`
+    pub arguments: String,
+}
+
+/// Parse the output of the [query listing the objects](list_objects_query)
+fn parse_dump_objects(stdout: &str) -> Result<DumpObjectList> {
+    let parse_number = |value: &str| {
+        value
+            .parse::<u64>()
+            .map_err(|error| Error::ParseError(format!("invalid number {value}: {error}")))
+    };
+
+    let mut objects = DumpObjectList::default();
+    for row in stdout.trim_end_matches('\n').split(RECORD_SEPARATOR) {
+        let row = row.trim_start_matches('\n');
+        if row.is_empty() {
+            continue;
+        }
+        let columns: Vec<&str> = row.split(FIELD_SEPARATOR).collect();
+        let [kind, schema, name, detail, size] = columns.as_slice() else {
+            return Err(Error::ParseError(format!("unexpected object row {row:?}")));
+        };
+        let (schema, name) = (schema.to_string(), name.to_string());
+        match *kind {
+            "table" => objects.tables.push(TableInfo {
+                schema,
+                name,
+                estimated_rows: parse_number(detail)?,
+                total_bytes: parse_number(size)?,
+            }),
+            "view" | "materialized view" => objects.views.push(ViewInfo {
+                schema,
+                name,
+                materialized: *kind == "materialized view",
+            }),
+            "function" => objects.functions.push(FunctionInfo {
+                schema,
+                name,
+                arguments: detail.to_string(),
+            }),
+            _ => return Err(Error::ParseError(format!("unexpected object kind {kind}"))),
+        }
+    }
+    Ok(objects)
 }
 
 #[cfg(test)]
@@ -784,8 +1304,22 @@ mod tests {
     }
 
     #[test]
-    fn test_builder() {
+    fn test_builder_qualified_table() {
+        let table = QualifiedName::qualified("my schema", "Weird.Table");
         let command = PgDumpBuilder::new()
+            .strict_table(&table)
+            .exclude_qualified_table(&QualifiedName::new("Audit"))
+            .build();
+
+        assert_eq!(
+            r#""pg_dump" "--table" "\"my schema\".\"Weird.Table\"" "--exclude-table" "\"Audit\"" "--strict-names""#,
+            command.to_command_string()
+        );
+    }
+
+    #[test]
+    fn test_builder() {
+        let builder = PgDumpBuilder::new()
             .data_only()
             .large_objects()
             .no_large_objects()
@@ -795,7 +1329,7 @@ mod tests {
             .encoding("UTF8")
             .file("file")
             .format("format")
-            .jobs("jobs")
+            .jobs(4)
             .schema("schema")
             .exclude_schema("exclude_schema")
             .no_owner()
@@ -846,11 +1380,354 @@ mod tests {
             .no_password()
             .password()
             .pg_password("password")
-            .role("role")
+            .role("role");
+        crate::describe::assert_options_described(&builder);
+        let command = builder.build();
+        assert_eq!(
+            r#"PGPASSWORD="password" "pg_dump" "--data-only" "--large-objects" "--no-large-objects" "--clean" "--create" "--extension" "extension" "--encoding" "UTF8" "--file" "file" "--format" "format" "--jobs" "4" "--schema" "schema" "--exclude-schema" "exclude_schema" "--no-owner" "--no-reconnect" "--schema-only" "--superuser" "superuser" "--table" "table" "--exclude-table" "exclude_table" "--verbose" "--version" "--no-privileges" "--compression" "compression" "--binary-upgrade" "--column-inserts" "--attribute-inserts" "--disable-dollar-quoting" "--disable-triggers" "--enable-row-security" "--exclude-table-data-and-children" "exclude_table_data_and_children" "--extra-float-digits" "extra_float_digits" "--if-exists" "--include-foreign-data" "include_foreign_data" "--inserts" "--load-via-partition-root" "--lock-wait-timeout" "10" "--no-comments" "--no-publications" "--no-security-labels" "--no-subscriptions" "--no-table-access-method" "--no-tablespaces" "--no-toast-compression" "--no-unlogged-table-data" "--on-conflict-do-nothing" "--quote-all-identifiers" "--rows-per-insert" "100" "--section" "section" "--serializable-deferrable" "--snapshot" "snapshot" "--strict-names" "--table-and-children" "table_and_children" "--use-set-session-authorization" "--help" "--dbname" "dbname" "--host" "localhost" "--port" "5432" "--username" "postgres" "--no-password" "--password" "--role" "role""#,
+            command.to_command_string()
+        );
+    }
+
+    #[test]
+    fn test_builder_environment_specific_options() {
+        let command = PgDumpBuilder::new().build();
+        assert_eq!(r#""pg_dump""#, command.to_command_string());
+
+        let command = PgDumpBuilder::new().no_toast_compression().build();
+        assert_eq!(
+            r#""pg_dump" "--no-toast-compression""#,
+            command.to_command_string()
+        );
+
+        let command = PgDumpBuilder::new().no_table_access_method().build();
+        assert_eq!(
+            r#""pg_dump" "--no-table-access-method""#,
+            command.to_command_string()
+        );
+
+        let command = PgDumpBuilder::new().no_tablespaces().build();
+        assert_eq!(
+            r#""pg_dump" "--no-tablespaces""#,
+            command.to_command_string()
+        );
+    }
+
+    #[test]
+    fn test_builder_no_toast_compression_target_version() {
+        let command = PgDumpBuilder::new()
+            .no_toast_compression()
+            .no_tablespaces()
+            .target_version(13)
+            .build();
+        assert_eq!(
+            r#""pg_dump" "--no-tablespaces""#,
+            command.to_command_string()
+        );
+
+        let command = PgDumpBuilder::new()
+            .no_toast_compression()
+            .target_version(14)
+            .build();
+        assert_eq!(
+            r#""pg_dump" "--no-toast-compression""#,
+            command.to_command_string()
+        );
+    }
+
+    #[test]
+    fn test_builder_schemas_matching() -> Result<()> {
+        let command = PgDumpBuilder::from(&TestSettings)
+            .dbname("tenants")
+            .schemas_matching_with("tenant_%", |psql| {
+                assert_eq!(
+                    r#"PGPASSWORD="password" "./psql" "--command" "SELECT nspname FROM pg_catalog.pg_namespace WHERE nspname LIKE 'tenant_%' AND nspname !~ '^pg_' AND nspname <> 'information_schema' ORDER BY nspname" "--dbname" "tenants" "--no-psqlrc" "--no-align" "--tuples-only" "--host" "localhost" "--port" "5432" "--username" "postgres""#,
+                    psql.build().to_command_string()
+                );
+                Ok("tenant_001\nTenant.002\n\n".to_string())
+            })?
+            .build();
+
+        assert_eq!(
+            r#"PGPASSWORD="password" "./pg_dump" "--schema" "\"tenant_001\"" "--schema" "\"Tenant.002\"" "--strict-names" "--dbname" "tenants" "--host" "localhost" "--port" "5432" "--username" "postgres""#,
+            command.to_command_string()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_builder_qualified_tables() {
+        let tables = [
+            QualifiedName::qualified("sales", "orders"),
+            QualifiedName::qualified("my schema", "Items"),
+        ];
+        let command = PgDumpBuilder::new()
+            .qualified_tables(&tables)
+            .strict_names()
             .build();
         assert_eq!(
-            r#"PGPASSWORD="password" "pg_dump" "--data-only" "--large-objects" "--no-large-objects" "--clean" "--create" "--extension" "extension" "--encoding" "UTF8" "--file" "file" "--format" "format" "--jobs" "jobs" "--schema" "schema" "--exclude-schema" "exclude_schema" "--no-owner" "--no-reconnect" "--schema-only" "--superuser" "superuser" "--table" "table" "--exclude-table" "exclude_table" "--verbose" "--version" "--no-privileges" "--compression" "compression" "--binary-upgrade" "--column-inserts" "--attribute-inserts" "--disable-dollar-quoting" "--disable-triggers" "--enable-row-security" "--exclude-table-data-and-children" "exclude_table_data_and_children" "--extra-float-digits" "extra_float_digits" "--if-exists" "--include-foreign-data" "include_foreign_data" "--inserts" "--load-via-partition-root" "--lock-wait-timeout" "10" "--no-comments" "--no-publications" "--no-security-labels" "--no-subscriptions" "--no-table-access-method" "--no-tablespaces" "--no-toast-compression" "--no-unlogged-table-data" "--on-conflict-do-nothing" "--quote-all-identifiers" "--rows-per-insert" "100" "--section" "section" "--serializable-deferrable" "--snapshot" "snapshot" "--strict-names" "--table-and-children" "table_and_children" "--use-set-session-authorization" "--help" "--dbname" "dbname" "--host" "localhost" "--port" "5432" "--username" "postgres" "--no-password" "--password" "--role" "role""#,
+            r#""pg_dump" "--table" "\"sales\".\"orders\"" "--table" "\"my schema\".\"Items\"" "--strict-names""#,
             command.to_command_string()
         );
     }
+
+    #[test]
+    fn test_builder_include_foreign_data() {
+        let command = PgDumpBuilder::new()
+            .include_foreign_data("remote")
+            .include_foreign_data("archive_*")
+            .build();
+        assert_eq!(
+            r#""pg_dump" "--include-foreign-data" "remote" "--include-foreign-data" "archive_*""#,
+            command.to_command_string()
+        );
+    }
+
+    #[test]
+    fn test_builder_load_via_partition_root() {
+        let command = PgDumpBuilder::new()
+            .format("plain")
+            .load_via_partition_root()
+            .build();
+        assert_eq!(
+            r#""pg_dump" "--format" "plain" "--load-via-partition-root""#,
+            command.to_command_string()
+        );
+    }
+
+    #[test]
+    fn test_validate_load_via_partition_root() {
+        let builder = PgDumpBuilder::new().load_via_partition_root();
+        assert!(!builder.load_via_partition_root_ignored());
+        assert!(!builder
+            .clone()
+            .format("plain")
+            .load_via_partition_root_ignored());
+        assert!(builder.validate().is_ok());
+
+        let builder = builder.schema_only();
+        assert!(builder.load_via_partition_root_ignored());
+        assert!(builder.validate().is_ok());
+        assert!(!PgDumpBuilder::new()
+            .schema_only()
+            .load_via_partition_root_ignored());
+    }
+
+    #[test]
+    fn test_builder_schemas_matching_escapes_pattern() {
+        let command = PgDumpBuilder::new().schemas_query("it's_%").build();
+        assert!(command
+            .to_command_string()
+            .contains("nspname LIKE 'it''s_%'"));
+    }
+
+    #[test]
+    fn test_builder_schemas_matching_no_match() {
+        let result = PgDumpBuilder::new().schemas_matching_with("tenant_%", |_| Ok(String::new()));
+        assert!(matches!(result, Err(Error::ValidationError(_))));
+    }
+
+    #[test]
+    fn test_builder_list_objects() -> Result<()> {
+        let row = |columns: &[&str]| columns.join(&FIELD_SEPARATOR.to_string());
+        let stdout = [
+            row(&["function", "public", "add", "a integer, b integer", ""]),
+            row(&["materialized view", "public", "totals", "", ""]),
+            row(&["table", "public", "orders", "1000", "262144"]),
+            row(&["table", "sales", "items", "24", "16384"]),
+            row(&["view", "sales", "recent", "", ""]),
+        ]
+        .join(&RECORD_SEPARATOR.to_string())
+            + "\n";
+
+        let objects = PgDumpBuilder::from(&TestSettings)
+            .dbname("shop")
+            .list_objects_with(|psql| {
+                let command = psql.build().to_command_string();
+                assert!(command.starts_with(r#"PGPASSWORD="password" "./psql" "--command" "SELECT 'table'"#));
+                assert!(command.ends_with(r#""--dbname" "shop" "--no-psqlrc" "--no-align" "--field-separator" "\u{1f}" "--record-separator" "\u{1e}" "--tuples-only" "--host" "localhost" "--port" "5432" "--username" "postgres""#));
+                Ok(stdout)
+            })?;
+
+        assert_eq!(
+            vec![
+                TableInfo {
+                    schema: "public".to_string(),
+                    name: "orders".to_string(),
+                    estimated_rows: 1000,
+                    total_bytes: 262144,
+                },
+                TableInfo {
+                    schema: "sales".to_string(),
+                    name: "items".to_string(),
+                    estimated_rows: 24,
+                    total_bytes: 16384,
+                },
+            ],
+            objects.tables
+        );
+        assert_eq!(
+            vec![
+                ViewInfo {
+                    schema: "public".to_string(),
+                    name: "totals".to_string(),
+                    materialized: true,
+                },
+                ViewInfo {
+                    schema: "sales".to_string(),
+                    name: "recent".to_string(),
+                    materialized: false,
+                },
+            ],
+            objects.views
+        );
+        assert_eq!(
+            vec![FunctionInfo {
+                schema: "public".to_string(),
+                name: "add".to_string(),
+                arguments: "a integer, b integer".to_string(),
+            }],
+            objects.functions
+        );
+        assert_eq!(1024, objects.estimated_rows());
+        assert_eq!(278528, objects.total_bytes());
+        Ok(())
+    }
+
+    #[test]
+    fn test_builder_list_objects_empty() -> Result<()> {
+        let objects = PgDumpBuilder::new().list_objects_with(|_| Ok("\n".to_string()))?;
+        assert_eq!(DumpObjectList::default(), objects);
+        Ok(())
+    }
+
+    #[test]
+    fn test_builder_list_objects_invalid() {
+        let result =
+            PgDumpBuilder::new().list_objects_with(|_| Ok("table\u{1f}public".to_string()));
+        assert!(matches!(result, Err(Error::ParseError(_))));
+    }
+
+    #[test]
+    fn test_build_for_version() -> Result<()> {
+        let builder = || {
+            PgDumpBuilder::new()
+                .large_objects()
+                .table_and_children("orders")
+                .dbname("shop")
+        };
+
+        assert_eq!(
+            r#""pg_dump" "--large-objects" "--table-and-children" "orders" "--dbname" "shop""#,
+            builder().build_for_version(17)?.to_command_string()
+        );
+        assert!(matches!(
+            builder().build_for_version(15),
+            Err(Error::UnsupportedOptionError { option, required: 16, actual: 15 })
+                if option == "--table-and-children"
+        ));
+
+        let command = PgDumpBuilder::new()
+            .large_objects()
+            .no_table_access_method()
+            .build_for_version(15)?;
+        assert_eq!(
+            r#""pg_dump" "--blobs" "--no-table-access-method""#,
+            command.to_command_string()
+        );
+
+        let filter = || PgDumpBuilder::new().filter("filters.txt");
+        assert_eq!(
+            r#""pg_dump" "--filter" "filters.txt""#,
+            filter().build_for_version(17)?.to_command_string()
+        );
+        assert!(matches!(
+            filter().build_for_version(15),
+            Err(Error::UnsupportedOptionError { option, required: 17, actual: 15 })
+                if option == "--filter"
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_builder_redacted() {
+        let builder = PgDumpBuilder::from(&TestSettings).pg_password("hunter2");
+        let debug = format!("{builder:?}");
+        let display = builder.to_string();
+
+        assert!(!debug.contains("hunter2"));
+        assert!(!display.contains("hunter2"));
+        assert!(display.contains(r#"PGPASSWORD="********""#));
+    }
+
+    const VERBOSE_OUTPUT: &str = "pg_dump: last built-in OID is 16383
+pg_dump: reading extensions
+pg_dump: dumping contents of table \"public.accounts\"
+pg_dump: dumping contents of table \"public.events\"
+pg_dump: saving database definition
+";
+
+    #[test]
+    fn test_parse_dumped_table() {
+        assert_eq!(
+            Some("public.accounts".to_string()),
+            parse_dumped_table("pg_dump: dumping contents of table \"public.accounts\"")
+        );
+        assert_eq!(
+            Some("public.accounts".to_string()),
+            parse_dumped_table("pg_dump: dumping contents of table public.accounts")
+        );
+        assert_eq!(None, parse_dumped_table("pg_dump: reading extensions"));
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn test_execute_with_progress() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        std::fs::write(temp_dir.path().join("verbose.txt"), VERBOSE_OUTPUT)?;
+        let program = temp_dir.path().join("pg_dump");
+        std::fs::write(
+            &program,
+            format!(
+                "#!/bin/sh\n\
+                 case \"$*\" in\n\
+                 *--verbose*) cat '{}/verbose.txt' >&2 ;;\n\
+                 *) exit 1 ;;\n\
+                 esac\n",
+                temp_dir.path().to_string_lossy()
+            ),
+        )?;
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&program, std::fs::Permissions::from_mode(0o755))?;
+        }
+
+        let events = std::sync::Mutex::new(Vec::new());
+        let dump_progress = PgDumpBuilder::new()
+            .program_dir(temp_dir.path())
+            .file("dump.backup")
+            .execute_with_progress(
+                |event| events.lock().unwrap().push(event),
+                &CancellationToken::new(),
+            )?;
+
+        let tables: Vec<(usize, Option<String>)> = events
+            .into_inner()
+            .unwrap()
+            .into_iter()
+            .filter_map(|event| match event {
+                ProgressEvent::Dump(progress) => Some((progress.tables_dumped, progress.table)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            vec![
+                (1, Some("public.accounts".to_string())),
+                (2, Some("public.events".to_string()))
+            ],
+            tables
+        );
+        assert_eq!(2, dump_progress.tables_dumped);
+        Ok(())
+    }
 }
@@ -1,11 +1,63 @@
+use crate::describe::CommandOption::{self, Flag, Value};
+use crate::passfile::{self, PassFile};
 use crate::traits::CommandBuilder;
 use crate::Settings;
 use std::convert::AsRef;
 use std::ffi::{OsStr, OsString};
 use std::path::PathBuf;
 
+/// Options of pg_dumpall, for [describe](CommandBuilder::describe)
+const OPTIONS: &[CommandOption] = &[
+    Value("--file"),
+    Flag("--verbose"),
+    Flag("--version"),
+    Value("--lock-wait-timeout"),
+    Flag("--help"),
+    Flag("--data-only"),
+    Flag("--clean"),
+    Value("--encoding"),
+    Flag("--globals-only"),
+    Flag("--no-owner"),
+    Flag("--roles-only"),
+    Flag("--schema-only"),
+    Value("--superuser"),
+    Flag("--tablespaces-only"),
+    Flag("--no-privileges"),
+    Flag("--binary-upgrade"),
+    Flag("--column-inserts"),
+    Flag("--disable-dollar-quoting"),
+    Flag("--disable-triggers"),
+    Value("--exclude-database"),
+    Value("--extra-float-digits"),
+    Flag("--if-exists"),
+    Flag("--inserts"),
+    Flag("--load-via-partition-root"),
+    Flag("--no-comments"),
+    Flag("--no-publications"),
+    Flag("--no-role-passwords"),
+    Flag("--no-security-labels"),
+    Flag("--no-subscriptions"),
+    Flag("--no-sync"),
+    Flag("--no-table-access-method"),
+    Flag("--no-tablespaces"),
+    Flag("--no-toast-compression"),
+    Flag("--no-unlogged-table-data"),
+    Flag("--on-conflict-do-nothing"),
+    Flag("--quote-all-identifiers"),
+    Value("--rows-per-insert"),
+    Flag("--use-set-session-authorization"),
+    Value("--dbname"),
+    Value("--host"),
+    Value("--database"),
+    Value("--port"),
+    Value("--username"),
+    Flag("--no-password"),
+    Flag("--password"),
+    Value("--role"),
+];
+
 /// pg_dumpall extracts a PostgreSQL database cluster into an SQL script file.
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Default)]
 pub struct PgDumpAllBuilder {
     program_dir: Option<PathBuf>,
     file: Option<OsString>,
@@ -54,9 +106,12 @@ pub struct PgDumpAllBuilder {
     no_password: bool,
     password: bool,
     pg_password: Option<OsString>,
+    pg_passfile: Option<PassFile>,
     role: Option<OsString>,
 }
 
+impl_redacted_fmt!(PgDumpAllBuilder);
+
 impl PgDumpAllBuilder {
     /// Create a new [PgDumpAllBuilder]
     pub fn new() -> Self {
@@ -355,6 +410,20 @@ impl PgDumpAllBuilder {
         self
     }
 
+    /// user password, passed in a temporary password file (`PGPASSFILE`) instead of `PGPASSWORD`
+    pub fn pg_password_via_passfile<S: AsRef<OsStr>>(mut self, pg_password: S) -> Self {
+        self.pg_password = None;
+        self.pg_passfile = passfile::create(pg_password);
+        self
+    }
+
+    /// password file (`PGPASSFILE`) used instead of `PGPASSWORD`
+    pub fn pg_passfile(mut self, pg_passfile: PassFile) -> Self {
+        self.pg_password = None;
+        self.pg_passfile = Some(pg_passfile);
+        self
+    }
+
     /// role name to use in the dump
     pub fn role<S: AsRef<OsStr>>(mut self, role: S) -> Self {
         self.role = Some(role.as_ref().to_os_string());
@@ -577,16 +646,28 @@ impl CommandBuilder for PgDumpAllBuilder {
         args
     }
 
+    /// Get the options of pg_dumpall
+    fn get_options(&self) -> &'static [CommandOption] {
+        OPTIONS
+    }
+
     /// Get the environment variables for the command
     fn get_envs(&self) -> Vec<(OsString, OsString)> {
         let mut envs: Vec<(OsString, OsString)> = Vec::new();
 
-        if let Some(password) = &self.pg_password {
+        if let Some(passfile) = &self.pg_passfile {
+            envs.push(("PGPASSFILE".into(), passfile.path().into()));
+        } else if let Some(password) = &self.pg_password {
             envs.push(("PGPASSWORD".into(), password.into()));
         }
 
         envs
     }
+
+    /// Get the temporary password file used by the command
+    fn get_passfile(&self) -> Option<PassFile> {
+        self.pg_passfile.clone()
+    }
 }
 
 #[cfg(test)]
@@ -616,7 +697,7 @@ mod tests {
 
     #[test]
     fn test_builder() {
-        let command = PgDumpAllBuilder::new()
+        let builder = PgDumpAllBuilder::new()
             .file("dump.sql")
             .verbose()
             .version()
@@ -663,12 +744,24 @@ mod tests {
             .no_password()
             .password()
             .pg_password("password")
-            .role("postgres")
-            .build();
+            .role("postgres");
+        crate::describe::assert_options_described(&builder);
+        let command = builder.build();
 
         assert_eq!(
             r#"PGPASSWORD="password" "pg_dumpall" "--file" "dump.sql" "--verbose" "--version" "--lock-wait-timeout" "10" "--help" "--data-only" "--clean" "--encoding" "UTF8" "--globals-only" "--no-owner" "--roles-only" "--schema-only" "--superuser" "postgres" "--tablespaces-only" "--no-privileges" "--binary-upgrade" "--column-inserts" "--disable-dollar-quoting" "--disable-triggers" "--exclude-database" "exclude" "--extra-float-digits" "2" "--if-exists" "--inserts" "--load-via-partition-root" "--no-comments" "--no-publications" "--no-role-passwords" "--no-security-labels" "--no-subscriptions" "--no-sync" "--no-table-access-method" "--no-tablespaces" "--no-toast-compression" "--no-unlogged-table-data" "--on-conflict-do-nothing" "--quote-all-identifiers" "--rows-per-insert" "1000" "--use-set-session-authorization" "--dbname" "postgres" "--host" "localhost" "--database" "postgres" "--port" "5432" "--username" "postgres" "--no-password" "--password" "--role" "postgres""#,
             command.to_command_string()
         );
     }
+
+    #[test]
+    fn test_builder_redacted() {
+        let builder = PgDumpAllBuilder::from(&TestSettings).pg_password("hunter2");
+        let debug = format!("{builder:?}");
+        let display = builder.to_string();
+
+        assert!(!debug.contains("hunter2"));
+        assert!(!display.contains("hunter2"));
+        assert!(display.contains(r#"PGPASSWORD="********""#));
+    }
 }
@@ -1,9 +1,22 @@
+use crate::describe::CommandOption::{self, Flag, Value};
 use crate::traits::CommandBuilder;
 use crate::Settings;
 use std::convert::AsRef;
 use std::ffi::{OsStr, OsString};
 use std::path::PathBuf;
 
+/// Options of pg_isready, for [describe](CommandBuilder::describe)
+const OPTIONS: &[CommandOption] = &[
+    Value("--dbname"),
+    Flag("--quiet"),
+    Flag("--version"),
+    Flag("--help"),
+    Value("--host"),
+    Value("--port"),
+    Value("--timeout"),
+    Value("--username"),
+];
+
 /// pg_isready issues a connection check to a PostgreSQL database.
 #[derive(Clone, Debug, Default)]
 pub struct PgIsReadyBuilder {
@@ -142,6 +155,11 @@ impl CommandBuilder for PgIsReadyBuilder {
 
         args
     }
+
+    /// Get the options of pg_isready
+    fn get_options(&self) -> &'static [CommandOption] {
+        OPTIONS
+    }
 }
 
 #[cfg(test)]
@@ -171,7 +189,7 @@ mod tests {
 
     #[test]
     fn test_builder() {
-        let command = PgIsReadyBuilder::new()
+        let builder = PgIsReadyBuilder::new()
             .dbname("postgres")
             .quiet()
             .version()
@@ -179,8 +197,9 @@ mod tests {
             .host("localhost")
             .port(5432)
             .timeout(3)
-            .username("postgres")
-            .build();
+            .username("postgres");
+        crate::describe::assert_options_described(&builder);
+        let command = builder.build();
 
         assert_eq!(
             r#""pg_isready" "--dbname" "postgres" "--quiet" "--version" "--help" "--host" "localhost" "--port" "5432" "--timeout" "3" "--username" "postgres""#,
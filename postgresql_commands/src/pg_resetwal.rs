@@ -1,9 +1,28 @@
+use crate::describe::CommandOption::{self, Flag, Value};
 use crate::traits::CommandBuilder;
 use crate::Settings;
 use std::convert::AsRef;
 use std::ffi::{OsStr, OsString};
 use std::path::PathBuf;
 
+/// Options of pg_resetwal, for [describe](CommandBuilder::describe)
+const OPTIONS: &[CommandOption] = &[
+    Value("--commit-timestamp-ids"),
+    Value("--pgdata"),
+    Value("--epoch"),
+    Flag("--force"),
+    Value("--next-wal-file"),
+    Value("--multixact-ids"),
+    Flag("--dry-run"),
+    Value("--next-oid"),
+    Value("--multixact-offset"),
+    Value("--oldest-transaction-id"),
+    Flag("--version"),
+    Value("--next-transaction-id"),
+    Value("--wal-segsize"),
+    Flag("--help"),
+];
+
 /// pg_resetwal resets the PostgreSQL write-ahead log.
 #[derive(Clone, Debug, Default)]
 pub struct PgResetWalBuilder {
@@ -209,6 +228,11 @@ impl CommandBuilder for PgResetWalBuilder {
 
         args
     }
+
+    /// Get the options of pg_resetwal
+    fn get_options(&self) -> &'static [CommandOption] {
+        OPTIONS
+    }
 }
 
 #[cfg(test)]
@@ -235,7 +259,7 @@ mod tests {
 
     #[test]
     fn test_builder() {
-        let command = PgResetWalBuilder::new()
+        let builder = PgResetWalBuilder::new()
             .commit_timestamp_ids("1", "2")
             .pgdata("pgdata")
             .epoch("epoch")
@@ -249,8 +273,9 @@ mod tests {
             .version()
             .next_transaction_id("next_transaction_id")
             .wal_segsize("wal_segsize")
-            .help()
-            .build();
+            .help();
+        crate::describe::assert_options_described(&builder);
+        let command = builder.build();
 
         assert_eq!(
             r#""pg_resetwal" "--commit-timestamp-ids" "1,2" "--pgdata" "pgdata" "--epoch" "epoch" "--force" "--next-wal-file" "next_wal_file" "--multixact-ids" "3,4" "--dry-run" "--next-oid" "next_oid" "--multixact-offset" "multixact_offset" "--oldest-transaction-id" "oldest_transaction_id" "--version" "--next-transaction-id" "next_transaction_id" "--wal-segsize" "wal_segsize" "--help""#,
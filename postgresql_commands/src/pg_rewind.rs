@@ -1,10 +1,28 @@
+use crate::describe::CommandOption::{self, Flag, Value};
 use crate::traits::CommandBuilder;
 use crate::Settings;
 use std::convert::AsRef;
 use std::ffi::{OsStr, OsString};
 use std::path::PathBuf;
 
-#[derive(Clone, Debug, Default)]
+/// Options of pg_rewind, for [describe](CommandBuilder::describe)
+const OPTIONS: &[CommandOption] = &[
+    Flag("--restore-target-wal"),
+    Value("--target-pgdata"),
+    Value("--source-pgdata"),
+    Value("--source-server"),
+    Flag("--dry-run"),
+    Flag("--no-sync"),
+    Flag("--progress"),
+    Flag("--write-recovery-conf"),
+    Value("--config-file"),
+    Flag("--debug"),
+    Flag("--no-ensure-shutdown"),
+    Flag("--version"),
+    Flag("--help"),
+];
+
+#[derive(Clone, Default)]
 pub struct PgRewindBuilder {
     program_dir: Option<PathBuf>,
     restore_target_wal: bool,
@@ -22,6 +40,8 @@ pub struct PgRewindBuilder {
     help: bool,
 }
 
+impl_redacted_fmt!(PgRewindBuilder);
+
 impl PgRewindBuilder {
     /// Create a new [PgRewindBuilder]
     pub fn new() -> Self {
@@ -191,6 +211,11 @@ impl CommandBuilder for PgRewindBuilder {
 
         args
     }
+
+    /// Get the options of pg_rewind
+    fn get_options(&self) -> &'static [CommandOption] {
+        OPTIONS
+    }
 }
 
 #[cfg(test)]
@@ -217,7 +242,7 @@ mod tests {
 
     #[test]
     fn test_builder() {
-        let command = PgRewindBuilder::new()
+        let builder = PgRewindBuilder::new()
             .restore_target_wal()
             .target_pgdata("target_pgdata")
             .source_pgdata("source_pgdata")
@@ -230,12 +255,25 @@ mod tests {
             .debug()
             .no_ensure_shutdown()
             .version()
-            .help()
-            .build();
+            .help();
+        crate::describe::assert_options_described(&builder);
+        let command = builder.build();
 
         assert_eq!(
             r#""pg_rewind" "--restore-target-wal" "--target-pgdata" "target_pgdata" "--source-pgdata" "source_pgdata" "--source-server" "source_server" "--dry-run" "--no-sync" "--progress" "--write-recovery-conf" "--config-file" "config_file" "--debug" "--no-ensure-shutdown" "--version" "--help""#,
             command.to_command_string()
         );
     }
+
+    #[test]
+    fn test_builder_redacted() {
+        let builder =
+            PgRewindBuilder::new().source_server("host=localhost user=postgres password='hunter2'");
+        let debug = format!("{builder:?}");
+        let display = builder.to_string();
+
+        assert!(!debug.contains("hunter2"));
+        assert!(!display.contains("hunter2"));
+        assert!(display.contains("password=********"));
+    }
 }
@@ -1,9 +1,13 @@
+use crate::describe::CommandOption::{self, Value};
 use crate::traits::CommandBuilder;
 use crate::Settings;
 use std::convert::AsRef;
 use std::ffi::{OsStr, OsString};
 use std::path::PathBuf;
 
+/// Options of pg_test_fsync, for [describe](CommandBuilder::describe)
+const OPTIONS: &[CommandOption] = &[Value("-f"), Value("-s")];
+
 /// determine fastest wal_sync_method for PostgreSQL
 #[derive(Clone, Debug, Default)]
 pub struct PgTestFsyncBuilder {
@@ -69,6 +73,11 @@ impl CommandBuilder for PgTestFsyncBuilder {
 
         args
     }
+
+    /// Get the options of pg_test_fsync
+    fn get_options(&self) -> &'static [CommandOption] {
+        OPTIONS
+    }
 }
 
 #[cfg(test)]
@@ -95,10 +104,11 @@ mod tests {
 
     #[test]
     fn test_builder() {
-        let command = PgTestFsyncBuilder::new()
+        let builder = PgTestFsyncBuilder::new()
             .filename("filename")
-            .secs_per_test(10)
-            .build();
+            .secs_per_test(10);
+        crate::describe::assert_options_described(&builder);
+        let command = builder.build();
 
         assert_eq!(
             r#""pg_test_fsync" "-f" "filename" "-s" "10""#,
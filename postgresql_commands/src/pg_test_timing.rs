@@ -1,9 +1,13 @@
+use crate::describe::CommandOption::{self, Value};
 use crate::traits::CommandBuilder;
 use crate::Settings;
 use std::convert::AsRef;
 use std::ffi::{OsStr, OsString};
 use std::path::PathBuf;
 
+/// Options of pg_test_timing, for [describe](CommandBuilder::describe)
+const OPTIONS: &[CommandOption] = &[Value("-d")];
+
 /// pg_test_timing tests the timing of a PostgreSQL instance.
 #[derive(Clone, Debug, Default)]
 pub struct PgTestTimingBuilder {
@@ -57,6 +61,11 @@ impl CommandBuilder for PgTestTimingBuilder {
 
         args
     }
+
+    /// Get the options of pg_test_timing
+    fn get_options(&self) -> &'static [CommandOption] {
+        OPTIONS
+    }
 }
 
 #[cfg(test)]
@@ -83,7 +92,9 @@ mod tests {
 
     #[test]
     fn test_builder() {
-        let command = PgTestTimingBuilder::new().duration("10").build();
+        let builder = PgTestTimingBuilder::new().duration("10");
+        crate::describe::assert_options_described(&builder);
+        let command = builder.build();
 
         assert_eq!(r#""pg_test_timing" "-d" "10""#, command.to_command_string());
     }
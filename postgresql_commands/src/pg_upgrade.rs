@@ -1,9 +1,35 @@
-use crate::traits::CommandBuilder;
+use crate::describe::CommandOption::{self, Flag, Value};
+use crate::error::{Error, Result};
+use crate::traits::{CommandBuilder, CommandExecutor};
 use crate::Settings;
 use std::convert::AsRef;
 use std::ffi::{OsStr, OsString};
 use std::path::PathBuf;
 
+/// Options of pg_upgrade, for [describe](CommandBuilder::describe)
+const OPTIONS: &[CommandOption] = &[
+    Value("--old-bindir"),
+    Value("--new-bindir"),
+    Flag("--check"),
+    Value("--old-datadir"),
+    Value("--new-datadir"),
+    Value("--jobs"),
+    Flag("--link"),
+    Flag("--no-sync"),
+    Value("--old-options"),
+    Value("--new-options"),
+    Value("--old-port"),
+    Value("--new-port"),
+    Flag("--retain"),
+    Value("--socketdir"),
+    Value("--username"),
+    Flag("--verbose"),
+    Flag("--version"),
+    Flag("--clone"),
+    Flag("--copy"),
+    Flag("--help"),
+];
+
 /// pg_upgrade upgrades a PostgreSQL cluster to a different major version.
 #[derive(Clone, Debug, Default)]
 pub struct PgUpgradeBuilder {
@@ -13,7 +39,7 @@ pub struct PgUpgradeBuilder {
     check: bool,
     old_datadir: Option<OsString>,
     new_datadir: Option<OsString>,
-    jobs: Option<OsString>,
+    jobs: Option<u32>,
     link: bool,
     no_sync: bool,
     old_options: Option<OsString>,
@@ -30,6 +56,18 @@ pub struct PgUpgradeBuilder {
     help: bool,
 }
 
+/// Result of checking whether two clusters can be upgraded; see
+/// [execute_check](PgUpgradeBuilder::execute_check)
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CompatibilityReport {
+    /// Whether pg_upgrade reported the clusters as compatible
+    pub compatible: bool,
+    /// Checks reported as warnings or notices, which do not prevent the upgrade
+    pub warnings: Vec<String>,
+    /// Checks that failed, and other failures reported by pg_upgrade, with their explanation
+    pub errors: Vec<String>,
+}
+
 impl PgUpgradeBuilder {
     /// Create a new [PgUpgradeBuilder]
     pub fn new() -> Self {
@@ -78,8 +116,8 @@ impl PgUpgradeBuilder {
     }
 
     /// number of simultaneous processes or threads to use
-    pub fn jobs<S: AsRef<OsStr>>(mut self, jobs: S) -> Self {
-        self.jobs = Some(jobs.as_ref().to_os_string());
+    pub fn jobs(mut self, jobs: u32) -> Self {
+        self.jobs = Some(jobs);
         self
     }
 
@@ -166,6 +204,122 @@ impl PgUpgradeBuilder {
         self.help = true;
         self
     }
+
+    /// Check the binary compatibility of the old and new clusters without changing any data,
+    /// and report the outcome of the consistency checks. Incompatible clusters are reported
+    /// through [CompatibilityReport::errors] rather than as an error.
+    pub fn execute_check(&self) -> Result<CompatibilityReport> {
+        self.execute_check_with(|builder| builder.build().execute())
+    }
+
+    /// Check if the old cluster can be upgraded to the new cluster; see
+    /// [execute_check](Self::execute_check)
+    pub fn is_compatible(&self) -> Result<bool> {
+        Ok(self.execute_check()?.compatible)
+    }
+
+    /// Check the compatibility of the clusters, using the given function to execute the command
+    fn execute_check_with<F>(&self, execute: F) -> Result<CompatibilityReport>
+    where
+        F: FnOnce(Self) -> Result<(String, String)>,
+    {
+        match execute(self.clone().check()) {
+            Ok((stdout, _stderr)) => Ok(parse_compatibility_report(&stdout)),
+            // pg_upgrade exits with a failure when a check fails
+            Err(Error::CommandError {
+                invocation,
+                stdout,
+                stderr,
+            }) => {
+                let report = parse_compatibility_report(&stdout);
+                if report.errors.is_empty() {
+                    Err(Error::CommandError {
+                        invocation,
+                        stdout,
+                        stderr,
+                    })
+                } else {
+                    Ok(report)
+                }
+            }
+            Err(error) => Err(error),
+        }
+    }
+}
+
+/// Warning or error reported by pg_upgrade while it is being parsed
+struct Finding {
+    is_error: bool,
+    message: String,
+    explained: bool,
+}
+
+impl Finding {
+    fn new(is_error: bool, message: String, explained: bool) -> Self {
+        Self {
+            is_error,
+            message,
+            explained,
+        }
+    }
+}
+
+/// Parse the output of `pg_upgrade --check`. Each check is reported on a line such as
+/// `Checking cluster versions   ok`; a failed check (`fatal`) is followed by a paragraph
+/// explaining the failure, while other failures are reported as a paragraph on their own.
+fn parse_compatibility_report(stdout: &str) -> CompatibilityReport {
+    let mut report = CompatibilityReport::default();
+    let mut clusters_compatible = false;
+    let mut current: Option<Finding> = None;
+
+    let mut flush = |current: &mut Option<Finding>| {
+        if let Some(finding) = current.take() {
+            if finding.is_error {
+                report.errors.push(finding.message);
+            } else {
+                report.warnings.push(finding.message);
+            }
+        }
+    };
+
+    for line in stdout.lines() {
+        let line = line.trim();
+        if line == "*Clusters are compatible*" {
+            clusters_compatible = true;
+        } else if let Some(check) = line.strip_prefix("Checking ") {
+            flush(&mut current);
+            let (name, status) = check
+                .rsplit_once(char::is_whitespace)
+                .unwrap_or((check, ""));
+            let name = format!("Checking {}", name.trim());
+            match status {
+                "fatal" => current = Some(Finding::new(true, name, false)),
+                "warning" | "notice" => current = Some(Finding::new(false, name, false)),
+                _ => {}
+            }
+        } else if line.is_empty() {
+            if current.as_ref().is_some_and(|finding| finding.explained) {
+                flush(&mut current);
+            }
+        } else if line == "Failure, exiting" {
+            flush(&mut current);
+        } else if line == "Performing Consistency Checks" || line.chars().all(|c| c == '-') {
+            continue;
+        } else if let Some(finding) = current.as_mut() {
+            finding
+                .message
+                .push_str(if finding.explained { " " } else { ": " });
+            finding.message.push_str(line);
+            finding.explained = true;
+        } else {
+            let is_error = !line.to_ascii_uppercase().starts_with("WARNING");
+            current = Some(Finding::new(is_error, line.to_string(), true));
+        }
+    }
+    flush(&mut current);
+
+    report.compatible = clusters_compatible && report.errors.is_empty();
+    report
 }
 
 impl CommandBuilder for PgUpgradeBuilder {
@@ -209,7 +363,7 @@ impl CommandBuilder for PgUpgradeBuilder {
 
         if let Some(jobs) = &self.jobs {
             args.push("--jobs".into());
-            args.push(jobs.into());
+            args.push(jobs.to_string().into());
         }
 
         if self.link {
@@ -276,6 +430,11 @@ impl CommandBuilder for PgUpgradeBuilder {
 
         args
     }
+
+    /// Get the options of pg_upgrade
+    fn get_options(&self) -> &'static [CommandOption] {
+        OPTIONS
+    }
 }
 
 #[cfg(test)]
@@ -302,13 +461,13 @@ mod tests {
 
     #[test]
     fn test_builder() {
-        let command = PgUpgradeBuilder::new()
+        let builder = PgUpgradeBuilder::new()
             .old_bindir("old")
             .new_bindir("new")
             .check()
             .old_datadir("old_data")
             .new_datadir("new_data")
-            .jobs("10")
+            .jobs(10)
             .link()
             .no_sync()
             .old_options("old")
@@ -322,12 +481,109 @@ mod tests {
             .version()
             .clone()
             .copy()
-            .help()
-            .build();
+            .help();
+        crate::describe::assert_options_described(&builder);
+        let command = builder.build();
 
         assert_eq!(
             r#""pg_upgrade" "--old-bindir" "old" "--new-bindir" "new" "--check" "--old-datadir" "old_data" "--new-datadir" "new_data" "--jobs" "10" "--link" "--no-sync" "--old-options" "old" "--new-options" "new" "--old-port" "5432" "--new-port" "5433" "--retain" "--socketdir" "socket" "--username" "user" "--verbose" "--version" "--clone" "--copy" "--help""#,
             command.to_command_string()
         );
     }
+
+    const CHECKS: &str = "\
+Performing Consistency Checks
+-----------------------------
+Checking cluster versions                                   ok
+Checking database user is the install user                  ok
+Checking for prepared transactions                          ok
+";
+
+    #[test]
+    fn test_execute_check_compatible() -> Result<()> {
+        let mut command = String::new();
+        let report = PgUpgradeBuilder::new()
+            .old_datadir("old_data")
+            .new_datadir("new_data")
+            .execute_check_with(|builder| {
+                command = builder.build().to_command_string();
+                let stdout = format!(
+                    "{CHECKS}Checking for extension updates                              notice\n\n\
+Your installation contains extensions that should be updated\n\
+with the ALTER EXTENSION command.\n\n\
+*Clusters are compatible*\n"
+                );
+                Ok((stdout, String::new()))
+            })?;
+
+        assert_eq!(
+            r#""pg_upgrade" "--check" "--old-datadir" "old_data" "--new-datadir" "new_data""#,
+            command
+        );
+        assert!(report.compatible);
+        assert_eq!(
+            vec!["Checking for extension updates: Your installation contains extensions that should be updated with the ALTER EXTENSION command.".to_string()],
+            report.warnings
+        );
+        assert!(report.errors.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_execute_check_fatal() -> Result<()> {
+        let report = PgUpgradeBuilder::new().execute_check_with(|_| {
+            Err(Error::CommandError {
+                invocation: Default::default(),
+                stdout: format!(
+                    "{CHECKS}Checking for presence of required libraries                 fatal\n\n\
+Your installation references loadable libraries that are missing from the\n\
+new installation.\n\n\
+Failure, exiting\n"
+                ),
+                stderr: String::new(),
+            })
+        })?;
+
+        assert!(!report.compatible);
+        assert!(report.warnings.is_empty());
+        assert_eq!(
+            vec!["Checking for presence of required libraries: Your installation references loadable libraries that are missing from the new installation.".to_string()],
+            report.errors
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_execute_check_failure() -> Result<()> {
+        let report = PgUpgradeBuilder::new().execute_check_with(|_| {
+            Err(Error::CommandError {
+                invocation: Default::default(),
+                stdout: format!(
+                    "{CHECKS}\n\
+encodings for database \"template1\" do not match:  old \"SQL_ASCII\", new \"LATIN1\"\n\
+Failure, exiting\n"
+                ),
+                stderr: String::new(),
+            })
+        })?;
+
+        assert!(!report.compatible);
+        assert_eq!(
+            vec![r#"encodings for database "template1" do not match:  old "SQL_ASCII", new "LATIN1""#.to_string()],
+            report.errors
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_execute_check_command_error() {
+        let result = PgUpgradeBuilder::new().execute_check_with(|_| {
+            Err(Error::CommandError {
+                invocation: Default::default(),
+                stdout: String::new(),
+                stderr: "pg_upgrade: could not execute".to_string(),
+            })
+        });
+        assert!(matches!(result, Err(Error::CommandError { .. })));
+    }
 }
@@ -1,9 +1,25 @@
-use crate::traits::CommandBuilder;
+use crate::describe::CommandOption::{self, Flag, Value};
+use crate::error::{Error, Result};
+use crate::traits::{CommandBuilder, CommandExecutor};
 use crate::Settings;
 use std::convert::AsRef;
 use std::ffi::{OsStr, OsString};
 use std::path::PathBuf;
 
+/// Options of pg_verifybackup, for [describe](CommandBuilder::describe)
+const OPTIONS: &[CommandOption] = &[
+    Flag("--exit-on-error"),
+    Value("--ignore"),
+    Value("--manifest-path"),
+    Flag("--no-parse-wal"),
+    Flag("--progress"),
+    Flag("--quiet"),
+    Flag("--skip-checksums"),
+    Value("--wal-directory"),
+    Flag("--version"),
+    Flag("--help"),
+];
+
 /// pg_verifybackup verifies a backup against the backup manifest.
 #[derive(Clone, Debug, Default)]
 pub struct PgVerifyBackupBuilder {
@@ -18,6 +34,26 @@ pub struct PgVerifyBackupBuilder {
     wal_directory: Option<OsString>,
     version: bool,
     help: bool,
+    backup_directory: Option<OsString>,
+}
+
+/// Error reported by pg_verifybackup
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct VerifyError {
+    /// File the error refers to, when the message names one; relative to the backup directory
+    /// for files in the backup
+    pub path: Option<String>,
+    /// Error message, without the program name prefix
+    pub message: String,
+}
+
+/// Result of verifying a backup; see [parse_pg_verifybackup_output]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct VerifyResult {
+    /// Whether the backup was verified successfully
+    pub success: bool,
+    /// Errors reported while verifying the backup
+    pub errors: Vec<VerifyError>,
 }
 
 impl PgVerifyBackupBuilder {
@@ -96,6 +132,62 @@ impl PgVerifyBackupBuilder {
         self.help = true;
         self
     }
+
+    /// backup directory to verify
+    pub fn backup_directory<S: AsRef<OsStr>>(mut self, backup_directory: S) -> Self {
+        self.backup_directory = Some(backup_directory.as_ref().to_os_string());
+        self
+    }
+
+    /// Verify the backup and parse the result. A failed verification is reported through
+    /// [VerifyResult::success] and [VerifyResult::errors] rather than as an error, so that
+    /// callers can decide which errors are acceptable.
+    pub fn execute_and_parse(&self) -> Result<VerifyResult> {
+        self.execute_and_parse_with(|builder| builder.build().execute())
+    }
+
+    /// Verify the backup, using the given function to execute the command
+    fn execute_and_parse_with<F>(&self, execute: F) -> Result<VerifyResult>
+    where
+        F: FnOnce(Self) -> Result<(String, String)>,
+    {
+        match execute(self.clone()) {
+            Ok((_stdout, stderr)) => Ok(parse_pg_verifybackup_output(0, &stderr)),
+            // The exit code is not reported by the executor; any failure exits with 1
+            Err(Error::CommandError { stderr, .. }) => Ok(parse_pg_verifybackup_output(1, &stderr)),
+            Err(error) => Err(error),
+        }
+    }
+}
+
+/// Parse the result of pg_verifybackup from its exit code and standard error. Errors are
+/// reported one per line as `pg_verifybackup: error: <message>`; the path of the file an
+/// error refers to is the first quoted value of the message, e.g. `"base/1/1259" is present on
+/// disk but not in the manifest`. Errors reported by pg_waldump, which is used to verify the WAL,
+/// are included.
+pub fn parse_pg_verifybackup_output(exit_code: i32, stderr: &str) -> VerifyResult {
+    let errors = stderr
+        .lines()
+        .filter_map(|line| {
+            let (program, message) = line.split_once(": error: ")?;
+            if program.contains(char::is_whitespace) {
+                return None;
+            }
+            let path = message
+                .split_once('"')
+                .and_then(|(_, rest)| rest.split_once('"'))
+                .map(|(path, _)| path.to_string());
+            Some(VerifyError {
+                path,
+                message: message.trim().to_string(),
+            })
+        })
+        .collect();
+
+    VerifyResult {
+        success: exit_code == 0,
+        errors,
+    }
 }
 
 impl CommandBuilder for PgVerifyBackupBuilder {
@@ -156,8 +248,17 @@ impl CommandBuilder for PgVerifyBackupBuilder {
             args.push("--help".into());
         }
 
+        if let Some(backup_directory) = &self.backup_directory {
+            args.push(backup_directory.into());
+        }
+
         args
     }
+
+    /// Get the options of pg_verifybackup
+    fn get_options(&self) -> &'static [CommandOption] {
+        OPTIONS
+    }
 }
 
 #[cfg(test)]
@@ -184,7 +285,7 @@ mod tests {
 
     #[test]
     fn test_builder() {
-        let command = PgVerifyBackupBuilder::new()
+        let builder = PgVerifyBackupBuilder::new()
             .exit_on_error()
             .ignore("ignore")
             .manifest_path("manifest-path")
@@ -195,11 +296,86 @@ mod tests {
             .wal_directory("wal_directory")
             .version()
             .help()
-            .build();
+            .backup_directory("backup");
+        crate::describe::assert_options_described(&builder);
+        let command = builder.build();
 
         assert_eq!(
-            r#""pg_verifybackup" "--exit-on-error" "--ignore" "ignore" "--manifest-path" "manifest-path" "--no-parse-wal" "--progress" "--quiet" "--skip-checksums" "--wal-directory" "wal_directory" "--version" "--help""#,
+            r#""pg_verifybackup" "--exit-on-error" "--ignore" "ignore" "--manifest-path" "manifest-path" "--no-parse-wal" "--progress" "--quiet" "--skip-checksums" "--wal-directory" "wal_directory" "--version" "--help" "backup""#,
             command.to_command_string()
         );
     }
+
+    const CORRUPT_STDERR: &str = r#"pg_verifybackup: error: "PG_VERSION" has size 5 on disk but size 3 in the manifest
+pg_verifybackup: error: "extra_file" is present on disk but not in the manifest
+pg_verifybackup: error: checksum mismatch for file "base/1/1259"
+pg_verifybackup: error: WAL parsing failed for timeline 1
+pg_waldump: error: could not find file "000000010000000000000002": No such file or directory
+"#;
+
+    #[test]
+    fn test_parse_pg_verifybackup_output() {
+        let result = parse_pg_verifybackup_output(1, CORRUPT_STDERR);
+        assert!(!result.success);
+        assert_eq!(
+            vec![
+                VerifyError {
+                    path: Some("PG_VERSION".to_string()),
+                    message: r#""PG_VERSION" has size 5 on disk but size 3 in the manifest"#
+                        .to_string(),
+                },
+                VerifyError {
+                    path: Some("extra_file".to_string()),
+                    message: r#""extra_file" is present on disk but not in the manifest"#
+                        .to_string(),
+                },
+                VerifyError {
+                    path: Some("base/1/1259".to_string()),
+                    message: r#"checksum mismatch for file "base/1/1259""#.to_string(),
+                },
+                VerifyError {
+                    path: None,
+                    message: "WAL parsing failed for timeline 1".to_string(),
+                },
+                VerifyError {
+                    path: Some("000000010000000000000002".to_string()),
+                    message: r#"could not find file "000000010000000000000002": No such file or directory"#
+                        .to_string(),
+                },
+            ],
+            result.errors
+        );
+    }
+
+    #[test]
+    fn test_parse_pg_verifybackup_output_success() {
+        let result = parse_pg_verifybackup_output(0, "");
+        assert!(result.success);
+        assert!(result.errors.is_empty());
+    }
+
+    #[test]
+    fn test_execute_and_parse() -> Result<()> {
+        let mut command = String::new();
+        let result = PgVerifyBackupBuilder::new()
+            .backup_directory("backup")
+            .execute_and_parse_with(|builder| {
+                command = builder.build().to_command_string();
+                Err(Error::CommandError {
+                    invocation: Default::default(),
+                    stdout: String::new(),
+                    stderr: CORRUPT_STDERR.to_string(),
+                })
+            })?;
+
+        assert_eq!(r#""pg_verifybackup" "backup""#, command);
+        assert!(!result.success);
+        assert_eq!(5, result.errors.len());
+
+        let result = PgVerifyBackupBuilder::new().execute_and_parse_with(|_| {
+            Ok(("backup successfully verified\n".to_string(), String::new()))
+        })?;
+        assert!(result.success);
+        Ok(())
+    }
 }
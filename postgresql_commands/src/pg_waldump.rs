@@ -1,16 +1,40 @@
+use crate::describe::CommandOption::{self, Flag, Value};
+use crate::lsn::Lsn;
 use crate::traits::CommandBuilder;
 use crate::Settings;
 use std::convert::AsRef;
 use std::ffi::{OsStr, OsString};
 use std::path::PathBuf;
 
+/// Options of pg_waldump, for [describe](CommandBuilder::describe)
+const OPTIONS: &[CommandOption] = &[
+    Flag("--bkp-details"),
+    Value("--block"),
+    Value("--end"),
+    Flag("--follow"),
+    Value("--fork"),
+    Value("--limit"),
+    Value("--path"),
+    Flag("--quiet"),
+    Value("--rmgr"),
+    Value("--relation"),
+    Value("--start"),
+    Value("--timeline"),
+    Flag("--version"),
+    Flag("--fullpage"),
+    Value("--xid"),
+    Value("--stats"),
+    Value("--save-fullpage"),
+    Flag("--help"),
+];
+
 /// pg_waldump decodes and displays PostgreSQL write-ahead logs for debugging.
 #[derive(Clone, Debug, Default)]
 pub struct PgWalDumpBuilder {
     program_dir: Option<PathBuf>,
     backkup_details: bool,
     block: Option<OsString>,
-    end: Option<OsString>,
+    end: Option<Lsn>,
     follow: bool,
     fork: Option<OsString>,
     limit: Option<OsString>,
@@ -18,7 +42,7 @@ pub struct PgWalDumpBuilder {
     quiet: bool,
     rmgr: Option<OsString>,
     relation: Option<OsString>,
-    start: Option<OsString>,
+    start: Option<Lsn>,
     timeline: Option<OsString>,
     version: bool,
     fullpage: bool,
@@ -58,8 +82,8 @@ impl PgWalDumpBuilder {
     }
 
     /// stop reading at WAL location RECPTR
-    pub fn end<S: AsRef<OsStr>>(mut self, end: S) -> Self {
-        self.end = Some(end.as_ref().to_os_string());
+    pub fn end(mut self, end: Lsn) -> Self {
+        self.end = Some(end);
         self
     }
 
@@ -106,8 +130,8 @@ impl PgWalDumpBuilder {
     }
 
     /// start reading at WAL location RECPTR
-    pub fn start<S: AsRef<OsStr>>(mut self, start: S) -> Self {
-        self.start = Some(start.as_ref().to_os_string());
+    pub fn start(mut self, start: Lsn) -> Self {
+        self.start = Some(start);
         self
     }
 
@@ -154,6 +178,47 @@ impl PgWalDumpBuilder {
     }
 }
 
+/// Write-ahead log record, as displayed by pg_waldump
+#[derive(Clone, Debug, PartialEq)]
+pub struct WalRecord {
+    /// Location of the record
+    pub lsn: Lsn,
+    /// Resource manager of the record; e.g. `Heap`, `Btree`, `Transaction` or `XLOG`
+    pub rmgr: String,
+    /// Record type, the first word of the description; e.g. `INSERT` or `COMMIT`
+    pub record_type: String,
+    /// Transaction that wrote the record; not set for records without a transaction
+    pub transaction_id: Option<u32>,
+    /// Total length of the record in bytes, including full page images
+    pub length: u32,
+}
+
+/// Parse a record line of pg_waldump output; e.g.
+/// `rmgr: Heap len (rec/tot): 59/ 59, tx: 735, lsn: 0/01A2F4D0, prev 0/01A2F498, desc: INSERT off 12`.
+/// The `prev` field is written with a colon by some versions of pg_waldump.
+pub fn parse_wal_record(line: &str) -> Option<WalRecord> {
+    let line = line.trim().strip_prefix("rmgr:")?;
+    let (rmgr, line) = line.split_once("len (rec/tot):")?;
+    let mut fields = line.splitn(5, ',').map(str::trim);
+    let (_record_length, length) = fields.next()?.split_once('/')?;
+    let transaction_id: u32 = fields.next()?.strip_prefix("tx:")?.trim().parse().ok()?;
+    let lsn = fields.next()?.strip_prefix("lsn:")?.trim().parse().ok()?;
+    fields.next()?.strip_prefix("prev")?;
+    let record_type = fields
+        .next()?
+        .strip_prefix("desc:")?
+        .split_whitespace()
+        .next()?;
+
+    Some(WalRecord {
+        lsn,
+        rmgr: rmgr.trim().to_string(),
+        record_type: record_type.to_string(),
+        transaction_id: (transaction_id != 0).then_some(transaction_id),
+        length: length.trim().parse().ok()?,
+    })
+}
+
 impl CommandBuilder for PgWalDumpBuilder {
     /// Get the program name
     fn get_program(&self) -> &'static OsStr {
@@ -180,7 +245,7 @@ impl CommandBuilder for PgWalDumpBuilder {
 
         if let Some(end) = &self.end {
             args.push("--end".into());
-            args.push(end.into());
+            args.push(end.to_string().into());
         }
 
         if self.follow {
@@ -218,7 +283,7 @@ impl CommandBuilder for PgWalDumpBuilder {
 
         if let Some(start) = &self.start {
             args.push("--start".into());
-            args.push(start.into());
+            args.push(start.to_string().into());
         }
 
         if let Some(timeline) = &self.timeline {
@@ -255,6 +320,11 @@ impl CommandBuilder for PgWalDumpBuilder {
 
         args
     }
+
+    /// Get the options of pg_waldump
+    fn get_options(&self) -> &'static [CommandOption] {
+        OPTIONS
+    }
 }
 
 #[cfg(test)]
@@ -281,10 +351,10 @@ mod tests {
 
     #[test]
     fn test_builder() {
-        let command = PgWalDumpBuilder::new()
+        let builder = PgWalDumpBuilder::new()
             .backup_details()
             .block("block")
-            .end("end")
+            .end(Lsn::new(0, 0x16E_5678))
             .follow()
             .fork("fork")
             .limit("limit")
@@ -292,19 +362,57 @@ mod tests {
             .quiet()
             .rmgr("rmgr")
             .relation("relation")
-            .start("start")
+            .start(Lsn::new(0, 0x16E_5678))
             .timeline("timeline")
             .version()
             .fullpage()
             .xid("xid")
             .stats("stats")
             .save_fullpage("save_fullpage")
-            .help()
-            .build();
+            .help();
+        crate::describe::assert_options_described(&builder);
+        let command = builder.build();
 
         assert_eq!(
-            r#""pg_waldump" "--bkp-details" "--block" "block" "--end" "end" "--follow" "--fork" "fork" "--limit" "limit" "--path" "path" "--quiet" "--rmgr" "rmgr" "--relation" "relation" "--start" "start" "--timeline" "timeline" "--version" "--fullpage" "--xid" "xid" "--stats" "stats" "--save-fullpage" "save_fullpage" "--help""#,
+            r#""pg_waldump" "--bkp-details" "--block" "block" "--end" "0/16E5678" "--follow" "--fork" "fork" "--limit" "limit" "--path" "path" "--quiet" "--rmgr" "rmgr" "--relation" "relation" "--start" "0/16E5678" "--timeline" "timeline" "--version" "--fullpage" "--xid" "xid" "--stats" "stats" "--save-fullpage" "save_fullpage" "--help""#,
             command.to_command_string()
         );
     }
+
+    #[test]
+    fn test_parse_wal_record() {
+        let output = "rmgr: Standby     len (rec/tot):     50/    50, tx:          0, lsn: 0/09000028, prev 0/08000100, desc: RUNNING_XACTS nextXid 748 latestCompletedXid 747 oldestRunningXid 748
+rmgr: Heap        len (rec/tot):     59/  8199, tx:        735, lsn: 0/01A2F4D0, prev 0/01A2F498, desc: INSERT off 12 flags 0x00, blkref #0: rel 1663/5/16384 blk 0 FPW
+rmgr: Transaction len (rec/tot):     34/    34, tx:        735, lsn: 0/01A2F510, prev: 0/01A2F4D0, desc: COMMIT 2024-10-15 13:50:19.123456 UTC
+pg_waldump: error: error in WAL record at 0/A000028: invalid record length at 0/A000060: wanted 24, got 0";
+        let records: Vec<WalRecord> = output.lines().filter_map(parse_wal_record).collect();
+
+        assert_eq!(
+            vec![
+                WalRecord {
+                    lsn: Lsn::new(0, 0x0900_0028),
+                    rmgr: "Standby".to_string(),
+                    record_type: "RUNNING_XACTS".to_string(),
+                    transaction_id: None,
+                    length: 50,
+                },
+                WalRecord {
+                    lsn: Lsn::new(0, 0x01A2_F4D0),
+                    rmgr: "Heap".to_string(),
+                    record_type: "INSERT".to_string(),
+                    transaction_id: Some(735),
+                    length: 8199,
+                },
+                WalRecord {
+                    lsn: Lsn::new(0, 0x01A2_F510),
+                    rmgr: "Transaction".to_string(),
+                    record_type: "COMMIT".to_string(),
+                    transaction_id: Some(735),
+                    length: 34,
+                },
+            ],
+            records
+        );
+        assert_eq!(None, parse_wal_record("rmgr: Heap len (rec/tot): 59"));
+    }
 }
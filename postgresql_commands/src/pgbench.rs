@@ -1,11 +1,61 @@
+use crate::describe::CommandOption::{self, Flag, Value};
+use crate::passfile::{self, PassFile};
 use crate::traits::CommandBuilder;
 use crate::Settings;
 use std::convert::AsRef;
 use std::ffi::{OsStr, OsString};
 use std::path::PathBuf;
 
+/// Options of pgbench, for [describe](CommandBuilder::describe)
+const OPTIONS: &[CommandOption] = &[
+    Flag("--initialize"),
+    Value("--init-steps"),
+    Value("--fillfactor"),
+    Flag("--no-vacuum"),
+    Flag("--quiet"),
+    Value("--scale"),
+    Flag("--foreign-keys"),
+    Value("--index-tablespace"),
+    Value("--partition-method"),
+    Value("--partitions"),
+    Value("--tablespace"),
+    Flag("--unlogged-tables"),
+    Value("--builtin"),
+    Value("--file"),
+    Flag("--skip-some-updates"),
+    Flag("--select-only"),
+    Value("--client"),
+    Flag("--connect"),
+    Value("--define"),
+    Value("--jobs"),
+    Flag("--log"),
+    Value("--latency-limit"),
+    Value("--protocol"),
+    Value("--progress"),
+    Flag("--report-per-command"),
+    Value("--rate"),
+    Value("--transactions"),
+    Value("--time"),
+    Flag("--vacuum-all"),
+    Value("--aggregate-interval"),
+    Flag("--failures-detailed"),
+    Value("--log-prefix"),
+    Value("--max-tries"),
+    Flag("--progress-timestamp"),
+    Value("--random-seed"),
+    Value("--sampling-rate"),
+    Value("--show-script"),
+    Flag("--verbose-errors"),
+    Flag("--debug"),
+    Value("--host"),
+    Value("--port"),
+    Value("--username"),
+    Flag("--version"),
+    Flag("--help"),
+];
+
 /// pgbench is a benchmarking tool for PostgreSQL.
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Default)]
 pub struct PgBenchBuilder {
     program_dir: Option<PathBuf>,
     initialize: bool,
@@ -52,10 +102,15 @@ pub struct PgBenchBuilder {
     host: Option<OsString>,
     port: Option<u16>,
     username: Option<OsString>,
+    pg_password: Option<OsString>,
+    pg_passfile: Option<PassFile>,
     version: bool,
     help: bool,
+    dbname: Option<OsString>,
 }
 
+impl_redacted_fmt!(PgBenchBuilder);
+
 impl PgBenchBuilder {
     /// Create a new [PgBenchBuilder]
     pub fn new() -> Self {
@@ -341,6 +396,26 @@ impl PgBenchBuilder {
         self
     }
 
+    /// user password
+    pub fn pg_password<S: AsRef<OsStr>>(mut self, pg_password: S) -> Self {
+        self.pg_password = Some(pg_password.as_ref().to_os_string());
+        self
+    }
+
+    /// user password, passed in a temporary password file (`PGPASSFILE`) instead of `PGPASSWORD`
+    pub fn pg_password_via_passfile<S: AsRef<OsStr>>(mut self, pg_password: S) -> Self {
+        self.pg_password = None;
+        self.pg_passfile = passfile::create(pg_password);
+        self
+    }
+
+    /// password file (`PGPASSFILE`) used instead of `PGPASSWORD`
+    pub fn pg_passfile(mut self, pg_passfile: PassFile) -> Self {
+        self.pg_password = None;
+        self.pg_passfile = Some(pg_passfile);
+        self
+    }
+
     /// output version information, then exit
     pub fn version(mut self) -> Self {
         self.version = true;
@@ -352,6 +427,12 @@ impl PgBenchBuilder {
         self.help = true;
         self
     }
+
+    /// database name to benchmark
+    pub fn dbname<S: AsRef<OsStr>>(mut self, dbname: S) -> Self {
+        self.dbname = Some(dbname.as_ref().to_os_string());
+        self
+    }
 }
 
 impl CommandBuilder for PgBenchBuilder {
@@ -581,8 +662,35 @@ impl CommandBuilder for PgBenchBuilder {
             args.push("--help".into());
         }
 
+        if let Some(dbname) = &self.dbname {
+            args.push(dbname.into());
+        }
+
         args
     }
+
+    /// Get the options of pgbench
+    fn get_options(&self) -> &'static [CommandOption] {
+        OPTIONS
+    }
+
+    /// Get the environment variables for the command
+    fn get_envs(&self) -> Vec<(OsString, OsString)> {
+        let mut envs: Vec<(OsString, OsString)> = Vec::new();
+
+        if let Some(passfile) = &self.pg_passfile {
+            envs.push(("PGPASSFILE".into(), passfile.path().into()));
+        } else if let Some(password) = &self.pg_password {
+            envs.push(("PGPASSWORD".into(), password.into()));
+        }
+
+        envs
+    }
+
+    /// Get the temporary password file used by the command
+    fn get_passfile(&self) -> Option<PassFile> {
+        self.pg_passfile.clone()
+    }
 }
 
 #[cfg(test)]
@@ -612,7 +720,7 @@ mod tests {
 
     #[test]
     fn test_builder() {
-        let command = PgBenchBuilder::new()
+        let builder = PgBenchBuilder::new()
             .initialize()
             .init_steps("steps")
             .fill_factor(10)
@@ -657,13 +765,27 @@ mod tests {
             .host("localhost")
             .port(5432)
             .username("username")
+            .pg_password("password")
             .version()
             .help()
-            .build();
+            .dbname("dbname");
+        crate::describe::assert_options_described(&builder);
+        let command = builder.build();
 
         assert_eq!(
-            r#""pgbench" "--initialize" "--init-steps" "steps" "--fillfactor" "10" "--no-vacuum" "--quiet" "--scale" "10" "--foreign-keys" "--index-tablespace" "tablespace" "--partition-method" "method" "--partitions" "10" "--tablespace" "tablespace" "--unlogged-tables" "--builtin" "name" "--file" "filename" "--skip-some-updates" "--select-only" "--client" "10" "--connect" "--define" "var" "--jobs" "10" "--log" "--latency-limit" "10" "--protocol" "protocol" "--no-vacuum" "--progress" "10" "--report-per-command" "--rate" "10" "--scale" "10" "--transactions" "10" "--time" "10" "--vacuum-all" "--aggregate-interval" "10" "--failures-detailed" "--log-prefix" "prefix" "--max-tries" "10" "--progress-timestamp" "--random-seed" "seed" "--sampling-rate" "10" "--show-script" "name" "--verbose-errors" "--debug" "--host" "localhost" "--port" "5432" "--username" "username" "--version" "--help""#,
+            r#"PGPASSWORD="password" "pgbench" "--initialize" "--init-steps" "steps" "--fillfactor" "10" "--no-vacuum" "--quiet" "--scale" "10" "--foreign-keys" "--index-tablespace" "tablespace" "--partition-method" "method" "--partitions" "10" "--tablespace" "tablespace" "--unlogged-tables" "--builtin" "name" "--file" "filename" "--skip-some-updates" "--select-only" "--client" "10" "--connect" "--define" "var" "--jobs" "10" "--log" "--latency-limit" "10" "--protocol" "protocol" "--no-vacuum" "--progress" "10" "--report-per-command" "--rate" "10" "--scale" "10" "--transactions" "10" "--time" "10" "--vacuum-all" "--aggregate-interval" "10" "--failures-detailed" "--log-prefix" "prefix" "--max-tries" "10" "--progress-timestamp" "--random-seed" "seed" "--sampling-rate" "10" "--show-script" "name" "--verbose-errors" "--debug" "--host" "localhost" "--port" "5432" "--username" "username" "--version" "--help" "dbname""#,
             command.to_command_string()
         );
     }
+
+    #[test]
+    fn test_builder_redacted() {
+        let builder = PgBenchBuilder::from(&TestSettings).pg_password("hunter2");
+        let debug = format!("{builder:?}");
+        let display = builder.to_string();
+
+        assert!(!debug.contains("hunter2"));
+        assert!(!display.contains("hunter2"));
+        assert!(display.contains(r#"PGPASSWORD="********""#));
+    }
 }
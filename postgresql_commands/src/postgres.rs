@@ -1,8 +1,42 @@
+use crate::describe::CommandOption::{self, Flag, Value};
 use crate::traits::CommandBuilder;
 use crate::Settings;
 use std::ffi::{OsStr, OsString};
 use std::path::PathBuf;
 
+/// Options of postgres, for [describe](CommandBuilder::describe)
+const OPTIONS: &[CommandOption] = &[
+    Value("-B"),
+    Value("-c"),
+    Value("-C"),
+    Value("-d"),
+    Value("-D"),
+    Flag("-e"),
+    Flag("-F"),
+    Value("-h"),
+    Flag("-i"),
+    Value("-k"),
+    Value("-N"),
+    Value("-p"),
+    Flag("-s"),
+    Value("-S"),
+    Flag("--version"),
+    Flag("--describe-config"),
+    Flag("--help"),
+    Value("-f"),
+    Flag("-O"),
+    Flag("-P"),
+    Value("-t"),
+    Flag("-T"),
+    Value("-W"),
+    Flag("--single"),
+    Flag("-E"),
+    Flag("-j"),
+    Value("-r"),
+    Flag("--boot"),
+    Flag("--check"),
+];
+
 /// postgres is the PostgreSQL server.
 #[derive(Clone, Debug, Default)]
 pub struct PostgresBuilder {
@@ -403,6 +437,11 @@ impl CommandBuilder for PostgresBuilder {
 
         args
     }
+
+    /// Get the options of postgres
+    fn get_options(&self) -> &'static [CommandOption] {
+        OPTIONS
+    }
 }
 
 #[cfg(test)]
@@ -432,7 +471,7 @@ mod tests {
 
     #[test]
     fn test_builder() {
-        let command = PostgresBuilder::new()
+        let builder = PostgresBuilder::new()
             .n_buffers(100)
             .runtime_param("name", "value")
             .print_runtime_param("name")
@@ -463,8 +502,9 @@ mod tests {
             .no_newline_delimiter()
             .output_file("output_file")
             .bootstrapping_mode()
-            .check_mode()
-            .build();
+            .check_mode();
+        crate::describe::assert_options_described(&builder);
+        let command = builder.build();
 
         assert_eq!(
             r#""postgres" "-B" "100" "-c" "name=value" "-C" "name" "-d" "3" "-D" "data_dir" "-e" "-F" "-h" "localhost" "-i" "-k" "socket_location" "-N" "100" "-p" "5432" "-s" "-S" "100" "--version" "--describe-config" "--help" "-f" "type" "-O" "-P" "-t" "timings" "-T" "-W" "10" "--single" "dbname" "-d" "3" "-E" "-j" "-r" "output_file" "--boot" "--check""#,
@@ -0,0 +1,208 @@
+use crate::error::{CommandInvocation, Error, Result};
+use crate::pg_basebackup::BaseBackupProgress;
+use crate::pg_dump::DumpProgress;
+use crate::pg_restore::RestoreProgress;
+use crate::traits::CommandBuilder;
+use std::io::{BufReader, Read};
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::debug;
+
+/// Interval at which a running command is checked for cancellation
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Progress reported by a long-running command
+#[derive(Clone, Debug, PartialEq)]
+pub enum ProgressEvent {
+    /// Progress of pg_restore, derived from its verbose output
+    Restore(RestoreProgress),
+    /// Progress of pg_basebackup, parsed from its `--progress` output
+    BaseBackup(BaseBackupProgress),
+    /// Progress of pg_dump, derived from its verbose output
+    Dump(DumpProgress),
+}
+
+/// Token used to cancel a running command; clones share the same state, so a clone can be
+/// cancelled from another thread while the command runs.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    /// Create a new [CancellationToken]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Request cancellation of the commands using this token
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    /// Check if cancellation has been requested
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+/// Execute the command, invoking the callback for each line written to stderr as it is written;
+/// lines may be terminated with a newline or a carriage return (progress lines written to a
+/// terminal). The child process is killed and [Error::CancelledError] is returned when the token
+/// is cancelled before the command exits. Returns the stdout and stderr of the command.
+pub fn execute_with_cancellation<F: FnMut(&str)>(
+    command: &mut Command,
+    cancel: &CancellationToken,
+    mut callback: F,
+) -> Result<(String, String)> {
+    command.stdout(Stdio::piped()).stderr(Stdio::piped());
+    debug!("Executing command: {}", CommandInvocation::from(&*command));
+
+    let mut child = command.spawn()?;
+    let mut stdout = child.stdout.take().expect("stdout");
+    let stdout_reader = std::thread::spawn(move || {
+        let mut output = String::new();
+        stdout.read_to_string(&mut output).map(|_| output)
+    });
+
+    let stderr = child.stderr.take().expect("stderr");
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        let mut line = Vec::new();
+        for byte in BufReader::new(stderr).bytes() {
+            let Ok(byte) = byte else { break };
+            line.push(byte);
+            if byte == b'\r' || byte == b'\n' {
+                if sender
+                    .send(String::from_utf8_lossy(&line).into_owned())
+                    .is_err()
+                {
+                    return;
+                }
+                line.clear();
+            }
+        }
+        if !line.is_empty() {
+            let _ = sender.send(String::from_utf8_lossy(&line).into_owned());
+        }
+    });
+
+    let mut stderr = String::new();
+    let status = loop {
+        if cancel.is_cancelled() {
+            return Err(kill(&mut child, command));
+        }
+        match receiver.recv_timeout(POLL_INTERVAL) {
+            Ok(line) => {
+                callback(line.trim_end_matches(['\r', '\n']));
+                stderr.push_str(&line);
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => match child.try_wait()? {
+                Some(status) => break status,
+                None => std::thread::sleep(POLL_INTERVAL),
+            },
+        }
+    };
+
+    let stdout = match stdout_reader.join() {
+        Ok(result) => result?,
+        Err(_) => return Err(Error::IoError(anyhow::anyhow!("failed to read stdout"))),
+    };
+
+    if status.success() {
+        Ok((stdout, stderr))
+    } else {
+        Err(Error::CommandError {
+            invocation: CommandInvocation::from(&*command),
+            stdout,
+            stderr,
+        })
+    }
+}
+
+/// Build and execute the command with [execute_with_cancellation]. Messages are forced to
+/// English since the output is parsed; `LC_ALL` is removed because it takes precedence over
+/// `LC_MESSAGES`.
+pub fn execute_builder_with_cancellation<B: CommandBuilder, F: FnMut(&str)>(
+    builder: B,
+    cancel: &CancellationToken,
+    callback: F,
+) -> Result<(String, String)> {
+    let _passfile = builder.get_passfile();
+    let mut command = builder.build();
+    command.env_remove("LC_ALL").env("LC_MESSAGES", "C");
+    execute_with_cancellation(&mut command, cancel, callback)
+}
+
+/// Kill a cancelled child process and wait for it to exit
+fn kill(child: &mut Child, command: &Command) -> Error {
+    let invocation = CommandInvocation::from(command);
+    debug!("Cancelling command: {invocation}");
+    if let Err(error) = child.kill().and_then(|()| child.wait().map(|_| ())) {
+        return Error::IoError(error.into());
+    }
+    Error::CancelledError { invocation }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_log::test;
+
+    #[test]
+    fn test_cancellation_token() {
+        let token = CancellationToken::new();
+        let clone = token.clone();
+        assert!(!token.is_cancelled());
+        clone.cancel();
+        assert!(token.is_cancelled());
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn test_execute_with_cancellation() -> Result<()> {
+        let mut command = Command::new("sh");
+        command.args([
+            "-c",
+            "printf 'one\\rtwo\\n' >&2; echo out; printf 'three' >&2",
+        ]);
+        let mut lines = Vec::new();
+        let (stdout, stderr) =
+            execute_with_cancellation(&mut command, &CancellationToken::new(), |line| {
+                lines.push(line.to_string());
+            })?;
+
+        assert_eq!(vec!["one", "two", "three"], lines);
+        assert_eq!("out\n", stdout);
+        assert_eq!("one\rtwo\nthree", stderr);
+        Ok(())
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn test_execute_with_cancellation_error() {
+        let mut command = Command::new("sh");
+        command.args(["-c", "echo failed >&2; exit 1"]);
+        let error = execute_with_cancellation(&mut command, &CancellationToken::new(), |_| {})
+            .expect_err("command fails");
+        assert!(matches!(error, Error::CommandError { stderr, .. } if stderr == "failed\n"));
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn test_execute_with_cancellation_cancelled() {
+        let mut command = Command::new("sh");
+        command.args(["-c", "echo started >&2; sleep 30"]);
+        let cancel = CancellationToken::new();
+        let start = std::time::Instant::now();
+        let error = execute_with_cancellation(&mut command, &cancel, |_| cancel.cancel())
+            .expect_err("command cancelled");
+
+        assert!(matches!(error, Error::CancelledError { .. }));
+        assert!(start.elapsed() < Duration::from_secs(10));
+    }
+}
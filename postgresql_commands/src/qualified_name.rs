@@ -0,0 +1,74 @@
+use std::fmt::{Display, Formatter};
+
+/// Name of a database object, optionally qualified by its schema. Names are used exactly as
+/// written: case is preserved, and dots or quotes are part of the name rather than separators.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct QualifiedName {
+    /// Schema of the object; objects in any schema match when not set
+    pub schema: Option<String>,
+    /// Name of the object
+    pub name: String,
+}
+
+impl QualifiedName {
+    /// Create a [QualifiedName] that is not qualified by a schema
+    pub fn new<S: Into<String>>(name: S) -> Self {
+        Self {
+            schema: None,
+            name: name.into(),
+        }
+    }
+
+    /// Create a [QualifiedName] qualified by the schema
+    pub fn qualified<S: Into<String>, N: Into<String>>(schema: S, name: N) -> Self {
+        Self {
+            schema: Some(schema.into()),
+            name: name.into(),
+        }
+    }
+
+    /// Pattern matching exactly this name, in the syntax used by the `--table` and `--schema`
+    /// options of pg_dump and psql: each component is enclosed in double quotes, with double
+    /// quotes in the name doubled, so that case, dots and wildcard characters are matched
+    /// literally; e.g. `"my schema"."Weird.Table"`.
+    pub fn to_pattern(&self) -> String {
+        match &self.schema {
+            Some(schema) => format!("{}.{}", quote(schema), quote(&self.name)),
+            None => quote(&self.name),
+        }
+    }
+}
+
+/// Display the name as a quoted SQL identifier; e.g. `"my schema"."Weird.Table"`
+impl Display for QualifiedName {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> std::fmt::Result {
+        formatter.write_str(&self.to_pattern())
+    }
+}
+
+/// Enclose a name in double quotes, doubling embedded double quotes
+fn quote(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_pattern() {
+        assert_eq!(r#""users""#, QualifiedName::new("users").to_pattern());
+        assert_eq!(
+            r#""my schema"."Weird.Table""#,
+            QualifiedName::qualified("my schema", "Weird.Table").to_pattern()
+        );
+        assert_eq!(
+            r#""a""b"."*?""#,
+            QualifiedName::qualified(r#"a"b"#, "*?").to_pattern()
+        );
+        assert_eq!(
+            r#""public"."Users""#,
+            QualifiedName::qualified("public", "Users").to_string()
+        );
+    }
+}
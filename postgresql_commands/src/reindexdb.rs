@@ -1,10 +1,36 @@
+use crate::describe::CommandOption::{self, Flag, Value};
+use crate::passfile::{self, PassFile};
 use crate::traits::CommandBuilder;
 use crate::Settings;
 use std::ffi::{OsStr, OsString};
 use std::path::PathBuf;
 
+/// Options of reindexdb, for [describe](CommandBuilder::describe)
+const OPTIONS: &[CommandOption] = &[
+    Flag("--all"),
+    Flag("--concurrently"),
+    Value("--dbname"),
+    Flag("--echo"),
+    Value("--index"),
+    Value("--jobs"),
+    Flag("--quiet"),
+    Flag("--system"),
+    Value("--schema"),
+    Value("--table"),
+    Value("--tablespace"),
+    Flag("--verbose"),
+    Flag("--version"),
+    Flag("--help"),
+    Value("--host"),
+    Value("--port"),
+    Value("--username"),
+    Flag("--no-password"),
+    Flag("--password"),
+    Value("--maintenance-db"),
+];
+
 /// reindexdb reindexes a PostgreSQL database.
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Default)]
 pub struct ReindexDbBuilder {
     program_dir: Option<PathBuf>,
     all: bool,
@@ -27,9 +53,12 @@ pub struct ReindexDbBuilder {
     no_password: bool,
     password: bool,
     pg_password: Option<OsString>,
+    pg_passfile: Option<PassFile>,
     maintenance_db: Option<OsString>,
 }
 
+impl_redacted_fmt!(ReindexDbBuilder);
+
 impl ReindexDbBuilder {
     /// Create a new [ReindexDbBuilder]
     pub fn new() -> Self {
@@ -172,6 +201,20 @@ impl ReindexDbBuilder {
         self
     }
 
+    /// user password, passed in a temporary password file (`PGPASSFILE`) instead of `PGPASSWORD`
+    pub fn pg_password_via_passfile<S: AsRef<OsStr>>(mut self, pg_password: S) -> Self {
+        self.pg_password = None;
+        self.pg_passfile = passfile::create(pg_password);
+        self
+    }
+
+    /// password file (`PGPASSFILE`) used instead of `PGPASSWORD`
+    pub fn pg_passfile(mut self, pg_passfile: PassFile) -> Self {
+        self.pg_password = None;
+        self.pg_passfile = Some(pg_passfile);
+        self
+    }
+
     /// alternate maintenance database
     pub fn maintenance_db<S: AsRef<OsStr>>(mut self, maintenance_db: S) -> Self {
         self.maintenance_db = Some(maintenance_db.as_ref().to_os_string());
@@ -287,16 +330,28 @@ impl CommandBuilder for ReindexDbBuilder {
         args
     }
 
+    /// Get the options of reindexdb
+    fn get_options(&self) -> &'static [CommandOption] {
+        OPTIONS
+    }
+
     /// Get the environment variables for the command
     fn get_envs(&self) -> Vec<(OsString, OsString)> {
         let mut envs: Vec<(OsString, OsString)> = Vec::new();
 
-        if let Some(password) = &self.pg_password {
+        if let Some(passfile) = &self.pg_passfile {
+            envs.push(("PGPASSFILE".into(), passfile.path().into()));
+        } else if let Some(password) = &self.pg_password {
             envs.push(("PGPASSWORD".into(), password.into()));
         }
 
         envs
     }
+
+    /// Get the temporary password file used by the command
+    fn get_passfile(&self) -> Option<PassFile> {
+        self.pg_passfile.clone()
+    }
 }
 
 #[cfg(test)]
@@ -326,7 +381,7 @@ mod tests {
 
     #[test]
     fn test_builder() {
-        let command = ReindexDbBuilder::new()
+        let builder = ReindexDbBuilder::new()
             .all()
             .concurrently()
             .dbname("dbname")
@@ -347,12 +402,24 @@ mod tests {
             .no_password()
             .password()
             .pg_password("password")
-            .maintenance_db("maintenance-db")
-            .build();
+            .maintenance_db("maintenance-db");
+        crate::describe::assert_options_described(&builder);
+        let command = builder.build();
 
         assert_eq!(
             r#"PGPASSWORD="password" "reindexdb" "--all" "--concurrently" "--dbname" "dbname" "--echo" "--index" "index" "--jobs" "1" "--quiet" "--system" "--schema" "schema" "--table" "table" "--tablespace" "tablespace" "--verbose" "--version" "--help" "--host" "localhost" "--port" "5432" "--username" "username" "--no-password" "--password" "--maintenance-db" "maintenance-db""#,
             command.to_command_string()
         );
     }
+
+    #[test]
+    fn test_builder_redacted() {
+        let builder = ReindexDbBuilder::from(&TestSettings).pg_password("hunter2");
+        let debug = format!("{builder:?}");
+        let display = builder.to_string();
+
+        assert!(!debug.contains("hunter2"));
+        assert!(!display.contains("hunter2"));
+        assert!(display.contains(r#"PGPASSWORD="********""#));
+    }
 }
@@ -0,0 +1,66 @@
+use crate::error::{Error, Result};
+use std::ffi::OsStr;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// Method used to synchronize files to disk, passed to the `--sync-method` option of
+/// [initdb](crate::initdb::InitDbBuilder) and [pg_basebackup](crate::pg_basebackup::PgBaseBackupBuilder);
+/// requires PostgreSQL 17 or later
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SyncMethod {
+    /// Recursively open and synchronize each file (`fsync`); the default
+    Fsync,
+    /// Synchronize each file system containing the files with a single `syncfs` call
+    /// (`syncfs`); Linux only. Much faster on large clusters, but also synchronizes unrelated
+    /// files on the same file systems.
+    Syncfs,
+}
+
+impl SyncMethod {
+    /// Get the keyword of the sync method; e.g. `syncfs`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SyncMethod::Fsync => "fsync",
+            SyncMethod::Syncfs => "syncfs",
+        }
+    }
+}
+
+impl AsRef<OsStr> for SyncMethod {
+    fn as_ref(&self) -> &OsStr {
+        self.as_str().as_ref()
+    }
+}
+
+impl Display for SyncMethod {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> std::fmt::Result {
+        formatter.write_str(self.as_str())
+    }
+}
+
+impl FromStr for SyncMethod {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value {
+            "fsync" => Ok(SyncMethod::Fsync),
+            "syncfs" => Ok(SyncMethod::Syncfs),
+            _ => Err(Error::ParseError(format!("invalid sync method {value}"))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sync_method() -> Result<()> {
+        for method in [SyncMethod::Fsync, SyncMethod::Syncfs] {
+            assert_eq!(method, SyncMethod::from_str(&method.to_string())?);
+            assert_eq!(OsStr::new(method.as_str()), method.as_ref());
+        }
+        assert!(SyncMethod::from_str("fdatasync").is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,515 @@
+//! Table of contents (TOC) of a pg_dump archive, as listed by `pg_restore --list --verbose`,
+//! with the dependencies between the entries. A TOC can be [reordered](reorder) and written
+//! to a file for `pg_restore --use-list`, which restores the entries in the order of the list.
+
+use crate::error::{Error, Result};
+use crate::pg_restore::Section;
+use crate::QualifiedName;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::fmt::{Display, Formatter};
+
+/// Object types of TOC entries that consist of more than one word; the longest types are
+/// listed first, so that e.g. `MATERIALIZED VIEW DATA` is not parsed as `MATERIALIZED VIEW`
+const MULTI_WORD_TYPES: [&str; 27] = [
+    "PUBLICATION TABLES IN SCHEMA",
+    "TEXT SEARCH CONFIGURATION",
+    "TEXT SEARCH DICTIONARY",
+    "MATERIALIZED VIEW DATA",
+    "FOREIGN DATA WRAPPER",
+    "TEXT SEARCH TEMPLATE",
+    "PROCEDURAL LANGUAGE",
+    "DATABASE PROPERTIES",
+    "TEXT SEARCH PARSER",
+    "SUBSCRIPTION TABLE",
+    "SEQUENCE OWNED BY",
+    "MATERIALIZED VIEW",
+    "PUBLICATION TABLE",
+    "OPERATOR FAMILY",
+    "CHECK CONSTRAINT",
+    "STATISTICS DATA",
+    "OPERATOR CLASS",
+    "SECURITY LABEL",
+    "FOREIGN TABLE",
+    "EVENT TRIGGER",
+    "FK CONSTRAINT",
+    "ACCESS METHOD",
+    "ROW SECURITY",
+    "SEQUENCE SET",
+    "INDEX ATTACH",
+    "LARGE OBJECT",
+    "TABLE DATA",
+];
+
+/// Object types restored in the [data](Section::Data) section
+const DATA_TYPES: [&str; 5] = [
+    "TABLE DATA",
+    "SEQUENCE SET",
+    "BLOB",
+    "BLOBS",
+    "LARGE OBJECT",
+];
+
+/// Object types restored in the [post-data](Section::PostData) section
+const POST_DATA_TYPES: [&str; 15] = [
+    "CHECK CONSTRAINT",
+    "CONSTRAINT",
+    "EVENT TRIGGER",
+    "FK CONSTRAINT",
+    "INDEX",
+    "INDEX ATTACH",
+    "MATERIALIZED VIEW DATA",
+    "POLICY",
+    "PUBLICATION",
+    "PUBLICATION TABLE",
+    "PUBLICATION TABLES IN SCHEMA",
+    "ROW SECURITY",
+    "RULE",
+    "STATISTICS",
+    "TRIGGER",
+];
+
+/// Entry of the [table of contents](Toc) of an archive; e.g.
+/// `215; 1259 16386 TABLE public accounts postgres`
+#[derive(Clone, Debug, PartialEq)]
+pub struct TocEntry {
+    /// Identifier of the entry in the archive
+    pub dump_id: u32,
+    /// OID of the system catalog the object is stored in; `0` for entries that are not
+    /// objects, e.g. table data
+    pub catalog_oid: u32,
+    /// OID of the object
+    pub object_oid: u32,
+    /// Type of the entry; e.g. `TABLE`, `TABLE DATA` or `FK CONSTRAINT`
+    pub object_type: String,
+    /// Schema of the object; `None` for objects that are not in a schema
+    pub schema: Option<String>,
+    /// Name of the object; e.g. `accounts` or `accounts accounts_pkey` for a constraint
+    pub name: String,
+    /// Owner of the object; `None` for objects without an owner
+    pub owner: Option<String>,
+    /// Identifiers of the entries that must be restored before this entry
+    pub dependencies: Vec<u32>,
+}
+
+impl TocEntry {
+    /// Section in which the entry is restored; entries such as comments and privileges that
+    /// are not in a section are restored with the object they belong to and are reported in
+    /// the [pre-data](Section::PreData) section
+    pub fn section(&self) -> Section {
+        let object_type = self.object_type.as_str();
+        if DATA_TYPES.contains(&object_type) {
+            Section::Data
+        } else if POST_DATA_TYPES.contains(&object_type) {
+            Section::PostData
+        } else {
+            Section::PreData
+        }
+    }
+
+    /// Table whose data the entry restores; `None` unless the entry is `TABLE DATA`
+    pub fn data_table(&self) -> Option<QualifiedName> {
+        if self.object_type != "TABLE DATA" {
+            return None;
+        }
+        let name = self.name.clone();
+        Some(match &self.schema {
+            Some(schema) => QualifiedName::qualified(schema, name),
+            None => QualifiedName::new(name),
+        })
+    }
+}
+
+/// Display the entry as a line of the listing, which `pg_restore --use-list` accepts
+impl Display for TocEntry {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            formatter,
+            "{}; {} {} {} {} {} {}",
+            self.dump_id,
+            self.catalog_oid,
+            self.object_oid,
+            self.object_type,
+            self.schema.as_deref().unwrap_or("-"),
+            self.name,
+            self.owner.as_deref().unwrap_or_default()
+        )
+    }
+}
+
+/// Table of contents of an archive, parsed from the output of
+/// `pg_restore --list --verbose`; see [PgRestoreBuilder::list](crate::pg_restore::PgRestoreBuilder::list)
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Toc {
+    /// Entries of the archive, in the order in which they are restored
+    pub entries: Vec<TocEntry>,
+}
+
+impl Toc {
+    /// Parse the listing of an archive. Entry lines start with the dump identifier followed by
+    /// a semicolon; with `--verbose`, the dependencies of an entry are listed on the following
+    /// comment line, e.g. `;\tdepends on: 215 216`. Other comment lines are ignored.
+    pub fn parse(list: &str) -> Result<Self> {
+        let mut entries: Vec<TocEntry> = Vec::new();
+        for line in list.lines() {
+            let line = line.trim_end_matches('\r');
+            if let Some(comment) = line.strip_prefix(';') {
+                let Some(dependencies) = comment.trim_start().strip_prefix("depends on:") else {
+                    continue;
+                };
+                let Some(entry) = entries.last_mut() else {
+                    return Err(Error::ParseError(format!(
+                        "dependencies before the first entry: {line}"
+                    )));
+                };
+                for dependency in dependencies.split_whitespace() {
+                    match dependency.parse() {
+                        Ok(dependency) => entry.dependencies.push(dependency),
+                        Err(error) => {
+                            return Err(Error::ParseError(format!(
+                                "invalid dependency {dependency}: {error}"
+                            )))
+                        }
+                    }
+                }
+            } else if !line.trim().is_empty() {
+                entries.push(parse_entry(line)?);
+            }
+        }
+        Ok(Self { entries })
+    }
+
+    /// Get the listing of the entries for `pg_restore --use-list`, with the dependencies of
+    /// every entry on the following comment line, so that the listing can be parsed again
+    pub fn to_list(&self) -> String {
+        let mut list = String::new();
+        for entry in &self.entries {
+            list.push_str(&format!("{entry}\n"));
+            if !entry.dependencies.is_empty() {
+                let dependencies: Vec<String> =
+                    entry.dependencies.iter().map(ToString::to_string).collect();
+                list.push_str(&format!(";\tdepends on: {}\n", dependencies.join(" ")));
+            }
+        }
+        list
+    }
+}
+
+/// Parse an entry line of the listing, e.g. `3340; 2606 16395 CONSTRAINT public accounts
+/// accounts_pkey postgres`; the name may contain spaces, the owner is empty for objects
+/// without an owner and the schema is `-` for objects that are not in a schema
+fn parse_entry(line: &str) -> Result<TocEntry> {
+    let invalid = || Error::ParseError(format!("invalid TOC entry: {line}"));
+    let (dump_id, rest) = line.split_once(';').ok_or_else(invalid)?;
+    let mut fields = rest.trim_start().splitn(3, ' ');
+    let dump_id = dump_id.trim().parse().map_err(|_| invalid())?;
+    let catalog_oid = fields
+        .next()
+        .and_then(|oid| oid.parse().ok())
+        .ok_or_else(invalid)?;
+    let object_oid = fields
+        .next()
+        .and_then(|oid| oid.parse().ok())
+        .ok_or_else(invalid)?;
+    let rest = fields.next().ok_or_else(invalid)?;
+
+    let object_type = MULTI_WORD_TYPES
+        .into_iter()
+        .find(|object_type| {
+            rest.strip_prefix(*object_type)
+                .is_some_and(|rest| rest.starts_with(' '))
+        })
+        .or_else(|| rest.split(' ').next())
+        .ok_or_else(invalid)?;
+    let rest = rest[object_type.len()..]
+        .strip_prefix(' ')
+        .ok_or_else(invalid)?;
+    let (schema, rest) = rest.split_once(' ').ok_or_else(invalid)?;
+    let (name, owner) = rest.rsplit_once(' ').ok_or_else(invalid)?;
+
+    Ok(TocEntry {
+        dump_id,
+        catalog_oid,
+        object_oid,
+        object_type: object_type.to_string(),
+        schema: (schema != "-").then(|| schema.to_string()),
+        name: name.to_string(),
+        owner: (!owner.is_empty()).then(|| owner.to_string()),
+        dependencies: Vec::new(),
+    })
+}
+
+/// Order in which the entries of a [Toc] are [reordered](reorder). Every strategy restores an
+/// entry only after the entries it depends on, and every strategy other than
+/// [Original](ReorderStrategy::Original) keeps the sections in order.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum ReorderStrategy {
+    /// Order of the archive
+    #[default]
+    Original,
+    /// Alphabetical order of the schema, name and type of the entries
+    Alphabetical,
+    /// Table data in descending order of the size of the tables in bytes, e.g. as reported by
+    /// `pg_table_size` on the database that was dumped, so that a parallel restore starts
+    /// loading the largest tables first; tables without a size are restored last, and other
+    /// entries keep the order of the archive
+    LargestDataFirst(HashMap<QualifiedName, u64>),
+}
+
+/// Reorder the entries of the table of contents according to the strategy. The entries are
+/// sorted topologically: an entry is placed after every entry it depends on; among the entries
+/// that can be placed next, the strategy decides. Dependencies on entries that are not in the
+/// table of contents are ignored. A [ParseError](Error::ParseError) is returned when the
+/// dependencies form a cycle.
+pub fn reorder(toc: &Toc, strategy: &ReorderStrategy) -> Result<Toc> {
+    let entries = &toc.entries;
+    let positions: HashMap<u32, usize> = entries
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| (entry.dump_id, index))
+        .collect();
+    let dependencies: Vec<Vec<usize>> = entries
+        .iter()
+        .map(|entry| {
+            let mut dependencies: Vec<usize> = entry
+                .dependencies
+                .iter()
+                .filter_map(|dump_id| positions.get(dump_id).copied())
+                .collect();
+            dependencies.sort_unstable();
+            dependencies.dedup();
+            dependencies
+        })
+        .collect();
+
+    // Comments and privileges are not in a section and follow the object they belong to
+    let mut sections: Vec<Option<usize>> = vec![None; entries.len()];
+    fn section_rank(
+        index: usize,
+        entries: &[TocEntry],
+        dependencies: &[Vec<usize>],
+        sections: &mut [Option<usize>],
+        visiting: &mut [bool],
+    ) -> usize {
+        if let Some(rank) = sections[index] {
+            return rank;
+        }
+        let entry = &entries[index];
+        let own = match entry.section() {
+            Section::PreData => 0,
+            Section::Data => 1,
+            Section::PostData => 2,
+        };
+        visiting[index] = true;
+        let mut rank = own;
+        if matches!(
+            entry.object_type.as_str(),
+            "ACL" | "COMMENT" | "SECURITY LABEL"
+        ) {
+            for dependency in &dependencies[index] {
+                if !visiting[*dependency] {
+                    rank = rank.max(section_rank(
+                        *dependency,
+                        entries,
+                        dependencies,
+                        sections,
+                        visiting,
+                    ));
+                }
+            }
+        }
+        visiting[index] = false;
+        sections[index] = Some(rank);
+        rank
+    }
+    let mut visiting = vec![false; entries.len()];
+    let ranks: Vec<usize> = (0..entries.len())
+        .map(|index| section_rank(index, entries, &dependencies, &mut sections, &mut visiting))
+        .collect();
+
+    let mut order: Vec<usize> = (0..entries.len()).collect();
+    match strategy {
+        ReorderStrategy::Original => {}
+        ReorderStrategy::Alphabetical => order.sort_by(|left, right| {
+            let key = |index: usize| {
+                let entry = &entries[index];
+                (
+                    ranks[index],
+                    entry.schema.as_deref().unwrap_or_default(),
+                    entry.name.as_str(),
+                    entry.object_type.as_str(),
+                    index,
+                )
+            };
+            key(*left).cmp(&key(*right))
+        }),
+        ReorderStrategy::LargestDataFirst(sizes) => order.sort_by_key(|index| {
+            let entry = &entries[*index];
+            let size = entry
+                .data_table()
+                .map(|table| sizes.get(&table).map_or(0, |size| size.saturating_add(1)));
+            (ranks[*index], Reverse(size.unwrap_or(0)), *index)
+        }),
+    }
+    let mut priorities = vec![0; entries.len()];
+    for (priority, index) in order.into_iter().enumerate() {
+        priorities[index] = priority;
+    }
+
+    // Kahn's algorithm, placing the entry with the highest priority among those whose
+    // dependencies have all been placed
+    let mut remaining: Vec<usize> = dependencies.iter().map(Vec::len).collect();
+    let mut dependents: Vec<Vec<usize>> = vec![Vec::new(); entries.len()];
+    for (index, dependencies) in dependencies.iter().enumerate() {
+        for dependency in dependencies {
+            dependents[*dependency].push(index);
+        }
+    }
+    let mut ready: BinaryHeap<Reverse<(usize, usize)>> = remaining
+        .iter()
+        .enumerate()
+        .filter(|(_, remaining)| **remaining == 0)
+        .map(|(index, _)| Reverse((priorities[index], index)))
+        .collect();
+    let mut reordered = Vec::with_capacity(entries.len());
+    while let Some(Reverse((_, index))) = ready.pop() {
+        reordered.push(entries[index].clone());
+        for dependent in &dependents[index] {
+            remaining[*dependent] -= 1;
+            if remaining[*dependent] == 0 {
+                ready.push(Reverse((priorities[*dependent], *dependent)));
+            }
+        }
+    }
+
+    if reordered.len() != entries.len() {
+        let cycle: Vec<String> = remaining
+            .iter()
+            .enumerate()
+            .filter(|(_, remaining)| **remaining > 0)
+            .map(|(index, _)| entries[index].dump_id.to_string())
+            .collect();
+        return Err(Error::ParseError(format!(
+            "dependency cycle between TOC entries {}",
+            cycle.join(", ")
+        )));
+    }
+    Ok(Toc { entries: reordered })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIST: &str = ";
+; Archive created at 2024-01-01 00:00:00 UTC
+;     dbname: source
+;
+; Selected TOC Entries:
+;
+215; 1259 16386 TABLE public accounts postgres
+216; 1259 16392 TABLE public events postgres
+;	depends on: 215
+3340; 0 16386 TABLE DATA public accounts postgres
+;	depends on: 215
+3341; 0 16392 TABLE DATA public events postgres
+;	depends on: 216
+3190; 2606 16391 CONSTRAINT public accounts accounts_pkey postgres
+;	depends on: 215
+3192; 2606 16398 FK CONSTRAINT public events events_account_id_fkey postgres
+;	depends on: 3190 216
+3342; 0 0 COMMENT - SCHEMA public \n";
+
+    #[test]
+    fn test_parse() -> Result<()> {
+        let toc = Toc::parse(LIST)?;
+        assert_eq!(7, toc.entries.len());
+        assert_eq!(
+            TocEntry {
+                dump_id: 3340,
+                catalog_oid: 0,
+                object_oid: 16386,
+                object_type: "TABLE DATA".to_string(),
+                schema: Some("public".to_string()),
+                name: "accounts".to_string(),
+                owner: Some("postgres".to_string()),
+                dependencies: vec![215],
+            },
+            toc.entries[2]
+        );
+        assert_eq!(Section::Data, toc.entries[2].section());
+        assert_eq!(
+            Some(QualifiedName::qualified("public", "accounts")),
+            toc.entries[2].data_table()
+        );
+
+        let constraint = &toc.entries[4];
+        assert_eq!("CONSTRAINT", constraint.object_type);
+        assert_eq!("accounts accounts_pkey", constraint.name);
+        assert_eq!(Section::PostData, constraint.section());
+        assert_eq!("FK CONSTRAINT", toc.entries[5].object_type);
+        assert_eq!(vec![3190, 216], toc.entries[5].dependencies);
+
+        let comment = &toc.entries[6];
+        assert_eq!(None, comment.schema);
+        assert_eq!("SCHEMA public", comment.name);
+        assert_eq!(None, comment.owner);
+
+        assert_eq!(
+            "3190; 2606 16391 CONSTRAINT public accounts accounts_pkey postgres",
+            constraint.to_string()
+        );
+        assert_eq!(toc, Toc::parse(&toc.to_list())?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!(Toc::parse("215; TABLE public accounts postgres").is_err());
+        assert!(Toc::parse(";\tdepends on: 215").is_err());
+    }
+
+    fn dump_ids(toc: &Toc) -> Vec<u32> {
+        toc.entries.iter().map(|entry| entry.dump_id).collect()
+    }
+
+    #[test]
+    fn test_reorder() -> Result<()> {
+        let toc = Toc::parse(LIST)?;
+        assert_eq!(
+            dump_ids(&toc),
+            dump_ids(&reorder(&toc, &ReorderStrategy::Original)?)
+        );
+
+        let sizes = HashMap::from([(QualifiedName::qualified("public", "events"), 1 << 30)]);
+        let reordered = reorder(&toc, &ReorderStrategy::LargestDataFirst(sizes))?;
+        assert_eq!(
+            vec![215, 216, 3342, 3341, 3340, 3190, 3192],
+            dump_ids(&reordered)
+        );
+
+        let reordered = reorder(&toc, &ReorderStrategy::Alphabetical)?;
+        assert_eq!(
+            vec![3342, 215, 216, 3340, 3341, 3190, 3192],
+            dump_ids(&reordered)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_reorder_respects_dependencies() -> Result<()> {
+        // The data of events depends on accounts, so it cannot move before it
+        let mut toc = Toc::parse(LIST)?;
+        toc.entries[3].dependencies.push(3340);
+        let sizes = HashMap::from([(QualifiedName::qualified("public", "events"), 1 << 30)]);
+        let reordered = reorder(&toc, &ReorderStrategy::LargestDataFirst(sizes))?;
+        assert_eq!(
+            vec![215, 216, 3342, 3340, 3341, 3190, 3192],
+            dump_ids(&reordered)
+        );
+
+        toc.entries[0].dependencies.push(3192);
+        assert!(reorder(&toc, &ReorderStrategy::Original).is_err());
+        Ok(())
+    }
+}
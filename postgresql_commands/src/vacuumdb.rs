@@ -1,11 +1,50 @@
+use crate::describe::CommandOption::{self, Flag, Value};
+use crate::passfile::{self, PassFile};
 use crate::traits::CommandBuilder;
 use crate::Settings;
 use std::convert::AsRef;
 use std::ffi::{OsStr, OsString};
 use std::path::PathBuf;
 
+/// Options of vacuumdb, for [describe](CommandBuilder::describe)
+const OPTIONS: &[CommandOption] = &[
+    Flag("--all"),
+    Value("--buffer-usage-limit"),
+    Value("--dbname"),
+    Flag("--disable-page-skipping"),
+    Flag("--echo"),
+    Flag("--full"),
+    Flag("--freeze"),
+    Flag("--force-index-cleanup"),
+    Value("--jobs"),
+    Value("--min-mxid-age"),
+    Value("--min-xid-age"),
+    Flag("--no-index-cleanup"),
+    Flag("--no-process-main"),
+    Flag("--no-process-toast"),
+    Flag("--no-truncate"),
+    Value("--schema"),
+    Value("--exclude-schema"),
+    Value("--parallel"),
+    Flag("--quiet"),
+    Flag("--skip-locked"),
+    Value("--table"),
+    Flag("--verbose"),
+    Flag("--version"),
+    Flag("--analyze"),
+    Flag("--analyze-only"),
+    Flag("--analyze-in-stages"),
+    Flag("--help"),
+    Value("--host"),
+    Value("--port"),
+    Value("--username"),
+    Flag("--no-password"),
+    Flag("--password"),
+    Value("--maintenance-db"),
+];
+
 /// vacuumdb cleans and analyzes a PostgreSQL database.
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Default)]
 pub struct VacuumDbBuilder {
     program_dir: Option<PathBuf>,
     all: bool,
@@ -41,9 +80,12 @@ pub struct VacuumDbBuilder {
     no_password: bool,
     password: bool,
     pg_password: Option<OsString>,
+    pg_passfile: Option<PassFile>,
     maintenance_db: Option<OsString>,
 }
 
+impl_redacted_fmt!(VacuumDbBuilder);
+
 /// vacuumdb cleans and analyzes a PostgreSQL database.
 impl VacuumDbBuilder {
     /// Create a new [VacuumDbBuilder]
@@ -265,6 +307,20 @@ impl VacuumDbBuilder {
         self
     }
 
+    /// user password, passed in a temporary password file (`PGPASSFILE`) instead of `PGPASSWORD`
+    pub fn pg_password_via_passfile<S: AsRef<OsStr>>(mut self, pg_password: S) -> Self {
+        self.pg_password = None;
+        self.pg_passfile = passfile::create(pg_password);
+        self
+    }
+
+    /// password file (`PGPASSFILE`) used instead of `PGPASSWORD`
+    pub fn pg_passfile(mut self, pg_passfile: PassFile) -> Self {
+        self.pg_password = None;
+        self.pg_passfile = Some(pg_passfile);
+        self
+    }
+
     /// alternate maintenance database
     pub fn maintenance_db<S: AsRef<OsStr>>(mut self, maintenance_db: S) -> Self {
         self.maintenance_db = Some(maintenance_db.as_ref().to_os_string());
@@ -435,16 +491,28 @@ impl CommandBuilder for VacuumDbBuilder {
         args
     }
 
+    /// Get the options of vacuumdb
+    fn get_options(&self) -> &'static [CommandOption] {
+        OPTIONS
+    }
+
     /// Get the environment variables for the command
     fn get_envs(&self) -> Vec<(OsString, OsString)> {
         let mut envs: Vec<(OsString, OsString)> = Vec::new();
 
-        if let Some(password) = &self.pg_password {
+        if let Some(passfile) = &self.pg_passfile {
+            envs.push(("PGPASSFILE".into(), passfile.path().into()));
+        } else if let Some(password) = &self.pg_password {
             envs.push(("PGPASSWORD".into(), password.into()));
         }
 
         envs
     }
+
+    /// Get the temporary password file used by the command
+    fn get_passfile(&self) -> Option<PassFile> {
+        self.pg_passfile.clone()
+    }
 }
 
 #[cfg(test)]
@@ -474,7 +542,7 @@ mod tests {
 
     #[test]
     fn test_builder() {
-        let command = VacuumDbBuilder::new()
+        let builder = VacuumDbBuilder::new()
             .all()
             .buffer_usage_limit("buffer_usage_limit")
             .dbname("dbname")
@@ -508,12 +576,24 @@ mod tests {
             .no_password()
             .password()
             .pg_password("password")
-            .maintenance_db("maintenance_db")
-            .build();
+            .maintenance_db("maintenance_db");
+        crate::describe::assert_options_described(&builder);
+        let command = builder.build();
 
         assert_eq!(
             r#"PGPASSWORD="password" "vacuumdb" "--all" "--buffer-usage-limit" "buffer_usage_limit" "--dbname" "dbname" "--disable-page-skipping" "--echo" "--full" "--freeze" "--force-index-cleanup" "--jobs" "1" "--min-mxid-age" "min_mxid_age" "--min-xid-age" "min_xid_age" "--no-index-cleanup" "--no-process-main" "--no-process-toast" "--no-truncate" "--schema" "schema" "--exclude-schema" "exclude_schema" "--parallel" "1" "--quiet" "--skip-locked" "--table" "table" "--verbose" "--version" "--analyze" "--analyze-only" "--analyze-in-stages" "--help" "--host" "localhost" "--port" "5432" "--username" "username" "--no-password" "--password" "--maintenance-db" "maintenance_db""#,
             command.to_command_string()
         );
     }
+
+    #[test]
+    fn test_builder_redacted() {
+        let builder = VacuumDbBuilder::from(&TestSettings).pg_password("hunter2");
+        let debug = format!("{builder:?}");
+        let display = builder.to_string();
+
+        assert!(!debug.contains("hunter2"));
+        assert!(!display.contains("hunter2"));
+        assert!(display.contains(r#"PGPASSWORD="********""#));
+    }
 }
@@ -1,11 +1,27 @@
+use crate::describe::CommandOption::{self, Flag, Value};
+use crate::passfile::{self, PassFile};
 use crate::traits::CommandBuilder;
 use crate::Settings;
 use std::convert::AsRef;
 use std::ffi::{OsStr, OsString};
 use std::path::PathBuf;
 
+/// Options of vacuumlo, for [describe](CommandBuilder::describe)
+const OPTIONS: &[CommandOption] = &[
+    Value("--limit"),
+    Flag("--dry-run"),
+    Flag("--verbose"),
+    Flag("--version"),
+    Flag("--help"),
+    Value("--host"),
+    Value("--port"),
+    Value("--username"),
+    Flag("--no-password"),
+    Flag("--password"),
+];
+
 /// vacuumlo removes unreferenced large objects from databases.
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Default)]
 pub struct VacuumLoBuilder {
     program_dir: Option<PathBuf>,
     limit: Option<usize>,
@@ -19,8 +35,11 @@ pub struct VacuumLoBuilder {
     no_password: bool,
     password: bool,
     pg_password: Option<OsString>,
+    pg_passfile: Option<PassFile>,
 }
 
+impl_redacted_fmt!(VacuumLoBuilder);
+
 impl VacuumLoBuilder {
     /// Create a new [VacuumLoBuilder]
     pub fn new() -> Self {
@@ -108,6 +127,20 @@ impl VacuumLoBuilder {
         self.pg_password = Some(pg_password.as_ref().to_os_string());
         self
     }
+
+    /// user password, passed in a temporary password file (`PGPASSFILE`) instead of `PGPASSWORD`
+    pub fn pg_password_via_passfile<S: AsRef<OsStr>>(mut self, pg_password: S) -> Self {
+        self.pg_password = None;
+        self.pg_passfile = passfile::create(pg_password);
+        self
+    }
+
+    /// password file (`PGPASSFILE`) used instead of `PGPASSWORD`
+    pub fn pg_passfile(mut self, pg_passfile: PassFile) -> Self {
+        self.pg_password = None;
+        self.pg_passfile = Some(pg_passfile);
+        self
+    }
 }
 
 impl CommandBuilder for VacuumLoBuilder {
@@ -172,16 +205,28 @@ impl CommandBuilder for VacuumLoBuilder {
         args
     }
 
+    /// Get the options of vacuumlo
+    fn get_options(&self) -> &'static [CommandOption] {
+        OPTIONS
+    }
+
     /// Get the environment variables for the command
     fn get_envs(&self) -> Vec<(OsString, OsString)> {
         let mut envs: Vec<(OsString, OsString)> = Vec::new();
 
-        if let Some(password) = &self.pg_password {
+        if let Some(passfile) = &self.pg_passfile {
+            envs.push(("PGPASSFILE".into(), passfile.path().into()));
+        } else if let Some(password) = &self.pg_password {
             envs.push(("PGPASSWORD".into(), password.into()));
         }
 
         envs
     }
+
+    /// Get the temporary password file used by the command
+    fn get_passfile(&self) -> Option<PassFile> {
+        self.pg_passfile.clone()
+    }
 }
 
 #[cfg(test)]
@@ -211,7 +256,7 @@ mod tests {
 
     #[test]
     fn test_builder() {
-        let command = VacuumLoBuilder::new()
+        let builder = VacuumLoBuilder::new()
             .limit(100)
             .dry_run()
             .verbose()
@@ -222,12 +267,44 @@ mod tests {
             .username("postgres")
             .no_password()
             .password()
-            .pg_password("password")
-            .build();
+            .pg_password("password");
+        crate::describe::assert_options_described(&builder);
+        let command = builder.build();
 
         assert_eq!(
             r#"PGPASSWORD="password" "vacuumlo" "--limit" "100" "--dry-run" "--verbose" "--version" "--help" "--host" "localhost" "--port" "5432" "--username" "postgres" "--no-password" "--password""#,
             command.to_command_string()
         );
     }
+
+    #[test]
+    fn test_builder_redacted() {
+        let builder = VacuumLoBuilder::from(&TestSettings).pg_password("hunter2");
+        let debug = format!("{builder:?}");
+        let display = builder.to_string();
+
+        assert!(!debug.contains("hunter2"));
+        assert!(!display.contains("hunter2"));
+        assert!(display.contains(r#"PGPASSWORD="********""#));
+    }
+
+    #[test]
+    fn test_builder_pg_password_via_passfile() -> crate::Result<()> {
+        let builder = VacuumLoBuilder::from(&TestSettings).pg_password_via_passfile("hunter2");
+        let passfile = builder.get_passfile().expect("passfile");
+        let path = passfile.path().to_path_buf();
+        let envs = builder.get_envs();
+
+        assert_eq!(
+            vec![(OsString::from("PGPASSFILE"), path.clone().into_os_string())],
+            envs
+        );
+        assert!(std::fs::read_to_string(&path)?.contains("hunter2"));
+
+        drop(builder);
+        assert!(path.exists());
+        drop(passfile);
+        assert!(!path.exists());
+        Ok(())
+    }
 }
@@ -0,0 +1,41 @@
+//! Planner statistics gathered after bulk loads and restores; see
+//! [analyze](crate::PostgreSQL::analyze) and
+//! [Settings::analyze_after_load](crate::Settings::analyze_after_load)
+
+use postgresql_commands::QualifiedName;
+use std::time::Duration;
+
+/// Report of the tables analyzed in a database, e.g. at the end of a restore
+#[derive(Clone, Debug, PartialEq)]
+pub struct AnalyzeReport {
+    /// Database that was analyzed
+    pub database: String,
+    /// Tables that were analyzed
+    pub tables: Vec<QualifiedName>,
+    /// Time taken to analyze the tables
+    pub duration: Duration,
+}
+
+/// Build the `ANALYZE` statement for the tables; the names are quoted, so case, dots and
+/// quotes are part of the name
+pub(crate) fn analyze_statement(tables: &[QualifiedName]) -> String {
+    let tables: Vec<String> = tables.iter().map(ToString::to_string).collect();
+    format!("ANALYZE {}", tables.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_analyze_statement() {
+        let tables = [
+            QualifiedName::new("items"),
+            QualifiedName::qualified("my schema", "Weird.Table"),
+        ];
+        assert_eq!(
+            r#"ANALYZE "items", "my schema"."Weird.Table""#,
+            analyze_statement(&tables)
+        );
+    }
+}
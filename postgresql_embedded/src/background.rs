@@ -0,0 +1,40 @@
+//! Quiescing of background activity for stable benchmarks; see
+//! [quiesce_background_activity](crate::PostgreSQL::quiesce_background_activity)
+
+use std::time::Duration;
+
+/// Query counting the vacuums in progress: autovacuum workers, and sessions running `VACUUM`
+pub(crate) const ACTIVE_VACUUMS_QUERY: &str = "SELECT count(*) FROM pg_catalog.pg_stat_activity \
+    WHERE backend_type = 'autovacuum worker' \
+    OR (state = 'active' AND query ~* '^\\s*vacuum\\M' AND pid <> pg_backend_pid())";
+
+/// Options for [quiesce_background_activity](crate::PostgreSQL::quiesce_background_activity)
+#[derive(Clone, Debug, PartialEq)]
+pub struct QuiesceOptions {
+    /// Turn autovacuum off with `ALTER SYSTEM` and a configuration reload until
+    /// [restore_background_activity](crate::PostgreSQL::restore_background_activity) is called
+    pub disable_autovacuum: bool,
+    /// Issue a `CHECKPOINT`, so that no checkpoint is due during the measurement
+    pub force_checkpoint: bool,
+    /// Wait until no autovacuum worker or `VACUUM` is running
+    pub wait_for_no_active_vacuum: bool,
+    /// Maximum time to wait for running vacuums to finish
+    pub timeout: Duration,
+    /// Allow autovacuum to be turned off on an instance that is not
+    /// [temporary](crate::Settings::temporary); autovacuum stays off for the data directory if
+    /// it is not restored
+    pub force: bool,
+}
+
+/// Default implementation for [`QuiesceOptions`]
+impl Default for QuiesceOptions {
+    fn default() -> Self {
+        Self {
+            disable_autovacuum: true,
+            force_checkpoint: true,
+            wait_for_no_active_vacuum: true,
+            timeout: Duration::from_secs(30),
+            force: false,
+        }
+    }
+}
@@ -0,0 +1,256 @@
+use crate::error::Error::BackupError;
+use crate::error::Result;
+use postgresql_commands::pg_basebackup::PgBaseBackupBuilder;
+use postgresql_commands::pg_verifybackup::{PgVerifyBackupBuilder, VerifyResult};
+use postgresql_commands::{CommandBuilder, CommandExecutor, Settings};
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tracing::{debug, instrument};
+
+/// Where a base backup is compressed
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CompressionLocation {
+    /// The client compresses the backup, which is written in tar format
+    #[default]
+    Client,
+    /// The server compresses the backup before sending it; the client decompresses it, so the
+    /// backup is written in plain format
+    Server,
+}
+
+/// Compression method of a base backup
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CompressionMethod {
+    /// gzip
+    Gzip,
+    /// LZ4
+    Lz4,
+    /// Zstandard
+    Zstd,
+}
+
+/// Compression of a base backup, passed to `pg_basebackup --compress`; requires PostgreSQL 15 or
+/// later. E.g. `server-zstd:level=3`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CompressionSpec {
+    /// Where the backup is compressed
+    pub location: CompressionLocation,
+    /// Compression method
+    pub method: CompressionMethod,
+    /// Compression level; the default level of the method is used when not set
+    pub level: Option<u32>,
+}
+
+impl CompressionSpec {
+    /// Compress the backup on the client with the method
+    pub fn client(method: CompressionMethod) -> Self {
+        Self {
+            location: CompressionLocation::Client,
+            method,
+            level: None,
+        }
+    }
+
+    /// Compress the backup on the server with the method
+    pub fn server(method: CompressionMethod) -> Self {
+        Self {
+            location: CompressionLocation::Server,
+            method,
+            level: None,
+        }
+    }
+
+    /// Use the compression level
+    pub fn level(mut self, level: u32) -> Self {
+        self.level = Some(level);
+        self
+    }
+}
+
+impl Display for CompressionSpec {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let location = match self.location {
+            CompressionLocation::Client => "client",
+            CompressionLocation::Server => "server",
+        };
+        let method = match self.method {
+            CompressionMethod::Gzip => "gzip",
+            CompressionMethod::Lz4 => "lz4",
+            CompressionMethod::Zstd => "zstd",
+        };
+        write!(f, "{location}-{method}")?;
+        if let Some(level) = self.level {
+            write!(f, ":level={level}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Outcome of a [PhysicalBackupWorkflow]
+#[derive(Clone, Debug, PartialEq)]
+pub struct BackupReport {
+    /// Directory the backup was written to
+    pub path: PathBuf,
+    /// Time taken by pg_basebackup
+    pub backup_duration: Duration,
+    /// Result of pg_verifybackup; `None` when the backup was not verified
+    pub verify_result: Option<VerifyResult>,
+}
+
+/// Take a base backup with pg_basebackup and optionally verify it with pg_verifybackup right
+/// away. A backup compressed on the [client](CompressionLocation::Client) is written in tar
+/// format, which pg_verifybackup only verifies from PostgreSQL 18 on; compress on the
+/// [server](CompressionLocation::Server) to verify the backup with earlier versions.
+///
+/// The commands are run synchronously; use `tokio::task::spawn_blocking` to run the workflow
+/// from async code.
+pub struct PhysicalBackupWorkflow<'a> {
+    settings: &'a dyn Settings,
+    directory: Option<PathBuf>,
+    verify_after: bool,
+    compression: Option<CompressionSpec>,
+}
+
+impl<'a> PhysicalBackupWorkflow<'a> {
+    /// Create a new [PhysicalBackupWorkflow] for the server with the settings
+    pub fn new(settings: &'a dyn Settings) -> Self {
+        Self {
+            settings,
+            directory: None,
+            verify_after: false,
+            compression: None,
+        }
+    }
+
+    /// Directory to write the backup to; it must not exist or be empty
+    pub fn backup_to(&mut self, directory: &Path) -> &mut Self {
+        self.directory = Some(directory.to_path_buf());
+        self
+    }
+
+    /// Verify the backup with pg_verifybackup after it has been taken
+    pub fn verify_after(&mut self, verify_after: bool) -> &mut Self {
+        self.verify_after = verify_after;
+        self
+    }
+
+    /// Compress the backup
+    pub fn compress(&mut self, compression: CompressionSpec) -> &mut Self {
+        self.compression = Some(compression);
+        self
+    }
+
+    /// Get the pg_basebackup command of the workflow
+    fn pg_basebackup(&self, directory: &Path) -> PgBaseBackupBuilder {
+        let mut pg_basebackup = PgBaseBackupBuilder::from(self.settings)
+            .pgdata(directory)
+            .checkpoint("fast");
+        if let Some(compression) = &self.compression {
+            let format = match compression.location {
+                CompressionLocation::Client => "tar",
+                CompressionLocation::Server => "plain",
+            };
+            pg_basebackup = pg_basebackup
+                .format(format)
+                .compress(compression.to_string());
+        }
+        pg_basebackup
+    }
+
+    /// Take the backup and verify it when [verify_after](Self::verify_after) is set. A failed
+    /// verification is reported in [BackupReport::verify_result] rather than as an error.
+    #[instrument(skip(self))]
+    pub fn execute(&self) -> Result<BackupReport> {
+        let Some(directory) = &self.directory else {
+            return Err(BackupError(anyhow::anyhow!(
+                "backup directory not set; see PhysicalBackupWorkflow::backup_to"
+            )));
+        };
+
+        debug!("Taking base backup to {}", directory.to_string_lossy());
+        let start = Instant::now();
+        if let Err(error) = self.pg_basebackup(directory).build().execute() {
+            return Err(BackupError(error.into()));
+        }
+        let backup_duration = start.elapsed();
+        debug!(
+            "Took base backup to {} in {backup_duration:?}",
+            directory.to_string_lossy()
+        );
+
+        let verify_result = if self.verify_after {
+            let verify_result = PgVerifyBackupBuilder::from(self.settings)
+                .backup_directory(directory)
+                .execute_and_parse()
+                .map_err(|error| BackupError(error.into()))?;
+            debug!(
+                "Verified base backup {}: success={}",
+                directory.to_string_lossy(),
+                verify_result.success
+            );
+            Some(verify_result)
+        } else {
+            None
+        };
+
+        Ok(BackupReport {
+            path: directory.clone(),
+            backup_duration,
+            verify_result,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Settings;
+
+    #[test]
+    fn test_compression_spec() {
+        assert_eq!(
+            "client-gzip",
+            CompressionSpec::client(CompressionMethod::Gzip).to_string()
+        );
+        assert_eq!(
+            "server-zstd:level=3",
+            CompressionSpec::server(CompressionMethod::Zstd)
+                .level(3)
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn test_pg_basebackup() {
+        let settings = Settings::default();
+        let directory = Path::new("backup");
+        let mut workflow = PhysicalBackupWorkflow::new(&settings);
+        let command = workflow
+            .pg_basebackup(directory)
+            .to_redacted_command_string();
+        assert!(command.contains(r#""--pgdata" "backup""#));
+        assert!(!command.contains("--compress"));
+
+        workflow.compress(CompressionSpec::server(CompressionMethod::Lz4));
+        let command = workflow
+            .pg_basebackup(directory)
+            .to_redacted_command_string();
+        assert!(command.contains(r#""--format" "plain""#));
+        assert!(command.contains(r#""--compress" "server-lz4""#));
+
+        workflow.compress(CompressionSpec::client(CompressionMethod::Gzip));
+        let command = workflow
+            .pg_basebackup(directory)
+            .to_redacted_command_string();
+        assert!(command.contains(r#""--format" "tar""#));
+        assert!(command.contains(r#""--compress" "client-gzip""#));
+    }
+
+    #[test]
+    fn test_execute_without_directory() {
+        let settings = Settings::default();
+        let result = PhysicalBackupWorkflow::new(&settings).execute();
+        assert!(matches!(result, Err(BackupError(_))));
+    }
+}
@@ -0,0 +1,121 @@
+use std::time::Duration;
+
+/// Number of connections left available for other clients when running statements concurrently
+pub(crate) const CONNECTION_HEADROOM: usize = 3;
+
+/// Result of a SQL statement
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct QueryResult {
+    /// Rows returned by the statement; each row is a vector of column values, with `NULL`
+    /// values returned as empty strings
+    pub rows: Vec<Vec<String>>,
+    /// Notices and warnings raised by the server while the statement ran, e.g. by
+    /// `RAISE NOTICE`, without the severity prefix; only collected by
+    /// [query_with_notices](crate::PostgreSQL::query_with_notices)
+    pub notices: Vec<String>,
+}
+
+/// Severities of the server messages collected as notices
+const NOTICE_SEVERITIES: [&str; 3] = ["NOTICE:", "WARNING:", "INFO:"];
+/// Prefixes of the lines psql prints after a server message to give further details
+const DETAIL_PREFIXES: [&str; 4] = ["DETAIL:", "HINT:", "CONTEXT:", "LOCATION:"];
+
+/// Parse the notices and warnings printed by psql on stderr; e.g. `NOTICE:  hello` or
+/// `psql:<stdin>:1: WARNING:  careful`. The detail, hint and context lines of a message are
+/// skipped, and other lines continue the message, e.g. for messages spanning several lines.
+pub(crate) fn parse_notices(stderr: &str) -> Vec<String> {
+    let mut notices: Vec<String> = Vec::new();
+    let mut in_notice = false;
+    for line in stderr.lines() {
+        // psql prefixes messages with the location when the statements are read from a file
+        let message = match line
+            .strip_prefix("psql:")
+            .and_then(|line| line.split_once(": "))
+        {
+            Some((_location, message)) => message,
+            None => line,
+        };
+        if let Some(notice) = NOTICE_SEVERITIES
+            .iter()
+            .find_map(|severity| message.strip_prefix(severity))
+        {
+            notices.push(notice.trim_start().to_string());
+            in_notice = true;
+        } else if DETAIL_PREFIXES
+            .iter()
+            .any(|prefix| message.starts_with(prefix))
+        {
+            in_notice = false;
+        } else if let (true, Some(notice)) = (in_notice, notices.last_mut()) {
+            notice.push('\n');
+            notice.push_str(line);
+        }
+    }
+    notices
+}
+
+/// Options for running a batch of SQL statements with
+/// [run_sql_batch](crate::PostgreSQL::run_sql_batch)
+#[derive(Clone, Debug, PartialEq)]
+pub struct BatchOptions {
+    /// Maximum number of statements executed concurrently; the concurrency is further limited
+    /// by the connections available on the server. Without the `tokio` feature the statements
+    /// run one after another.
+    pub max_concurrency: usize,
+    /// Skip the statements that have not been started once a statement fails
+    pub stop_on_first_error: bool,
+    /// Timeout for each statement; the [settings](crate::Settings) timeout is used when not set.
+    /// Requires the `tokio` feature.
+    pub per_statement_timeout: Option<Duration>,
+}
+
+/// Default implementation for [`BatchOptions`]
+impl Default for BatchOptions {
+    fn default() -> Self {
+        Self {
+            max_concurrency: 4,
+            stop_on_first_error: false,
+            per_statement_timeout: None,
+        }
+    }
+}
+
+/// Determine the number of concurrent sessions; the requested concurrency is limited by the
+/// available connections, less [`CONNECTION_HEADROOM`], and is always at least one.
+pub(crate) fn concurrency(max_concurrency: usize, available_connections: Option<usize>) -> usize {
+    let concurrency = match available_connections {
+        Some(available_connections) => {
+            max_concurrency.min(available_connections.saturating_sub(CONNECTION_HEADROOM))
+        }
+        None => max_concurrency,
+    };
+    concurrency.max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_concurrency() {
+        assert_eq!(8, concurrency(8, None));
+        assert_eq!(8, concurrency(8, Some(100)));
+        assert_eq!(2, concurrency(8, Some(5)));
+        assert_eq!(1, concurrency(8, Some(2)));
+        assert_eq!(1, concurrency(0, None));
+    }
+
+    #[test]
+    fn test_parse_notices() {
+        let stderr = "NOTICE:  hello\npsql:<stdin>:3: WARNING:  careful\nDETAIL:  details\nINFO:  first line\nsecond line\nHINT:  hint\n";
+        assert_eq!(
+            vec![
+                "hello".to_string(),
+                "careful".to_string(),
+                "first line\nsecond line".to_string()
+            ],
+            parse_notices(stderr)
+        );
+        assert!(parse_notices("").is_empty());
+    }
+}
@@ -0,0 +1,169 @@
+use crate::error::Error::BenchmarkError;
+use crate::error::Result;
+use std::time::Duration;
+
+/// Time allowed for initializing each unit of [scale](BenchOptions::scale) of the pgbench
+/// tables, in addition to the [settings](crate::Settings) timeout
+pub(crate) const INITIALIZE_TIME_PER_SCALE: Duration = Duration::from_secs(2);
+
+/// Options for running a pgbench benchmark with [benchmark](crate::PostgreSQL::benchmark)
+#[derive(Clone, Debug, PartialEq)]
+pub struct BenchOptions {
+    /// Database to benchmark; the pgbench tables are (re)created in this database. The
+    /// [maintenance database](crate::Settings::maintenance_database) is used when not set.
+    pub database_name: Option<String>,
+    /// Scale factor used to initialize the pgbench tables; each unit of scale adds 100,000 rows
+    /// to the `pgbench_accounts` table
+    pub scale: usize,
+    /// Number of concurrent clients
+    pub clients: usize,
+    /// Number of pgbench worker threads
+    pub jobs: usize,
+    /// Duration of the run; pgbench only supports whole seconds, with a minimum of one second
+    pub duration: Duration,
+}
+
+/// Default implementation for [`BenchOptions`]
+impl Default for BenchOptions {
+    fn default() -> Self {
+        Self {
+            database_name: None,
+            scale: 1,
+            clients: 1,
+            jobs: 1,
+            duration: Duration::from_secs(10),
+        }
+    }
+}
+
+/// Result of a pgbench run, parsed from the pgbench output
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BenchResult {
+    /// Transactions per second, excluding the initial connection time
+    pub tps: f64,
+    /// Average transaction latency
+    pub latency_avg: Duration,
+    /// Standard deviation of the transaction latency; only reported by pgbench when progress
+    /// reporting or rate limiting is enabled
+    pub latency_stddev: Option<Duration>,
+    /// Number of transactions processed
+    pub transactions: u64,
+    /// Number of failed transactions, as reported by PostgreSQL 15+
+    pub failed_transactions: Option<u64>,
+}
+
+/// Parse the result of a pgbench run from its standard output
+pub(crate) fn parse_bench_result(stdout: &str) -> Result<BenchResult> {
+    let mut tps = None;
+    let mut latency_avg = None;
+    let mut latency_stddev = None;
+    let mut transactions = None;
+    let mut failed_transactions = None;
+
+    for line in stdout.lines() {
+        if let Some(value) = line.strip_prefix("tps = ") {
+            // Prior to PostgreSQL 14, pgbench reports the tps both including and excluding the
+            // connection time; the latter is reported last and takes precedence
+            tps = first_word(value).and_then(|value| value.parse::<f64>().ok());
+        } else if let Some(value) = line.strip_prefix("latency average = ") {
+            latency_avg = parse_milliseconds(value);
+        } else if let Some(value) = line.strip_prefix("latency stddev = ") {
+            latency_stddev = parse_milliseconds(value);
+        } else if let Some(value) = line.strip_prefix("number of transactions actually processed: ")
+        {
+            transactions = value
+                .split('/')
+                .next()
+                .and_then(|value| value.trim().parse::<u64>().ok());
+        } else if let Some(value) = line.strip_prefix("number of failed transactions: ") {
+            failed_transactions = first_word(value).and_then(|value| value.parse::<u64>().ok());
+        }
+    }
+
+    match (tps, latency_avg, transactions) {
+        (Some(tps), Some(latency_avg), Some(transactions)) => Ok(BenchResult {
+            tps,
+            latency_avg,
+            latency_stddev,
+            transactions,
+            failed_transactions,
+        }),
+        _ => Err(BenchmarkError(anyhow::anyhow!(
+            "Unable to parse pgbench output: {stdout}"
+        ))),
+    }
+}
+
+/// Get the first whitespace separated word of the value
+fn first_word(value: &str) -> Option<&str> {
+    value.split_whitespace().next()
+}
+
+/// Parse a value in milliseconds; e.g. `0.462 ms`
+fn parse_milliseconds(value: &str) -> Option<Duration> {
+    first_word(value)
+        .and_then(|value| value.parse::<f64>().ok())
+        .map(|milliseconds| Duration::from_secs_f64(milliseconds / 1000.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_bench_result() -> Result<()> {
+        let stdout = "\
+pgbench (15.18 (Debian 15.18-0+deb12u1))
+starting vacuum...end.
+transaction type: <builtin: TPC-B (sort of)>
+scaling factor: 1
+query mode: simple
+number of clients: 2
+number of threads: 1
+maximum number of tries: 1
+duration: 2 s
+number of transactions actually processed: 8649
+number of failed transactions: 0 (0.000%)
+latency average = 0.462 ms
+initial connection time = 5.574 ms
+tps = 4329.084500 (without initial connection time)
+";
+        let result = parse_bench_result(stdout)?;
+        assert_eq!(4329.0845, result.tps);
+        assert_eq!(Duration::from_micros(462), result.latency_avg);
+        assert_eq!(None, result.latency_stddev);
+        assert_eq!(8649, result.transactions);
+        assert_eq!(Some(0), result.failed_transactions);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_bench_result_excluding_connections() -> Result<()> {
+        let stdout = "\
+pgbench (13.14)
+transaction type: <builtin: TPC-B (sort of)>
+scaling factor: 1
+query mode: simple
+number of clients: 4
+number of threads: 2
+duration: 10 s
+number of transactions actually processed: 16180
+latency average = 2.472 ms
+latency stddev = 1.250 ms
+tps = 1618.021000 (including connections establishing)
+tps = 1620.330000 (excluding connections establishing)
+";
+        let result = parse_bench_result(stdout)?;
+        assert_eq!(1620.33, result.tps);
+        assert_eq!(Duration::from_micros(2472), result.latency_avg);
+        assert_eq!(Some(Duration::from_micros(1250)), result.latency_stddev);
+        assert_eq!(16180, result.transactions);
+        assert_eq!(None, result.failed_transactions);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_bench_result_invalid() {
+        assert!(parse_bench_result("pgbench: error: connection failed").is_err());
+    }
+}
@@ -0,0 +1,267 @@
+//! Database for build scripts, e.g. to check SQL queries at compile time; see
+//! [setup_for_build]
+
+use crate::blocking::PostgreSQL;
+use crate::crash::{kill_process, postmaster_pid, process_exists};
+use crate::{Result, Settings};
+use postgresql_archive::Version;
+use std::fs::remove_dir_all;
+use std::path::{Path, PathBuf};
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+use tracing::{debug, warn};
+
+/// Password of the build databases; the server only listens on the loopback interface and the
+/// URL is stable across builds, so changes to it do not trigger recompilation
+const BUILD_PASSWORD: &str = "build";
+
+/// Maximum time to wait for the server of an orphaned build database to exit once killed
+const ORPHAN_KILL_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Options for [setup_for_build]
+#[derive(Clone, Debug, PartialEq)]
+pub struct BuildSetupOptions {
+    /// Name of the database created for the build
+    pub database_name: String,
+    /// Version of PostgreSQL; the [default version](crate::PostgreSQL::default_version) when
+    /// not set
+    pub version: Option<Version>,
+    /// Directory where PostgreSQL is installed and reused across builds, including clean
+    /// builds; the user-level [installation directory](Settings::installation_dir) (e.g.
+    /// `~/.theseus/postgresql`) when not set. Avoid directories under `target/`, which are
+    /// removed by `cargo clean`.
+    pub cache_dir: Option<PathBuf>,
+    /// Directory holding the data directories of the build databases; `build` next to the
+    /// default installation directory (e.g. `~/.theseus/build`) when not set
+    pub run_dir: Option<PathBuf>,
+    /// Port of the server; a free port is used when `0`
+    pub port: u16,
+    /// Report a failed setup to cargo with `cargo:warning` lines; nothing is written to the
+    /// build output otherwise
+    pub emit_warnings: bool,
+}
+
+/// Default implementation for [`BuildSetupOptions`]
+impl Default for BuildSetupOptions {
+    fn default() -> Self {
+        Self {
+            database_name: "build".to_string(),
+            version: None,
+            cache_dir: None,
+            run_dir: None,
+            port: 0,
+            emit_warnings: true,
+        }
+    }
+}
+
+/// Database started by [setup_for_build]. The server is stopped and its data directory removed
+/// when the value is dropped; when the build script is killed before that, the next
+/// [setup_for_build] stops the orphaned server and removes its data directory.
+#[derive(Debug)]
+pub struct BuildDatabase {
+    postgresql: Option<PostgreSQL>,
+    database_name: String,
+    url: String,
+    run_dir: PathBuf,
+}
+
+impl BuildDatabase {
+    /// Get the connection URL of the database
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Get the name of the database
+    pub fn database_name(&self) -> &str {
+        &self.database_name
+    }
+
+    /// Get the server, e.g. to run migrations before the queries are checked
+    pub fn postgresql(&self) -> &PostgreSQL {
+        self.postgresql.as_ref().expect("server")
+    }
+
+    /// Set the environment variable to the connection URL when compiling the crate, by printing
+    /// a `cargo:rustc-env` instruction; e.g. `DATABASE_URL` for the sqlx query macros
+    pub fn emit_rustc_env(&self, name: &str) {
+        println!("cargo:rustc-env={name}={}", self.url);
+    }
+}
+
+impl Drop for BuildDatabase {
+    fn drop(&mut self) {
+        // Dropping the temporary server stops it and removes its data directory
+        drop(self.postgresql.take());
+        if let Err(error) = remove_dir_all(&self.run_dir) {
+            warn!(
+                "Failed to remove build database directory {}: {error}",
+                self.run_dir.to_string_lossy()
+            );
+        }
+    }
+}
+
+/// Install, start and create a database for a build script, synchronously and without writing
+/// to the build output. PostgreSQL is installed once in a user-level
+/// [cache directory](BuildSetupOptions::cache_dir) and reused by later builds. The server is
+/// temporary: it is stopped when the returned [BuildDatabase] is dropped, and servers left
+/// running by build scripts that were killed are cleaned up first.
+///
+/// ```no_run
+/// // build.rs
+/// use postgresql_embedded::blocking::{setup_for_build, BuildSetupOptions};
+///
+/// fn main() -> postgresql_embedded::Result<()> {
+///     let database = setup_for_build(BuildSetupOptions::default())?;
+///     database.postgresql().run_sql(database.database_name(), "CREATE TABLE t (id int)")?;
+///     database.emit_rustc_env("DATABASE_URL");
+///     Ok(())
+/// }
+/// ```
+///
+/// # Errors
+///
+/// Returns an error if PostgreSQL cannot be installed or started; the error is also reported
+/// with `cargo:warning` lines when [emit_warnings](BuildSetupOptions::emit_warnings) is set.
+pub fn setup_for_build(options: BuildSetupOptions) -> Result<BuildDatabase> {
+    let result = setup(&options);
+    if let Err(error) = &result {
+        if options.emit_warnings {
+            println!("cargo:warning=PostgreSQL setup for the build failed");
+            for line in error.to_string().lines() {
+                println!("cargo:warning={line}");
+            }
+        }
+    }
+    result
+}
+
+fn setup(options: &BuildSetupOptions) -> Result<BuildDatabase> {
+    let mut settings = Settings::default();
+    let default_installation_dir = settings.installation_dir.clone();
+    // The data directory and password file created by the default settings are not used
+    let _ = remove_dir_all(&settings.data_dir);
+    if let Some(password_dir) = settings.password_file.parent() {
+        let _ = remove_dir_all(password_dir);
+    }
+
+    if let Some(cache_dir) = &options.cache_dir {
+        settings.installation_dir.clone_from(cache_dir);
+    }
+    let run_dir = match &options.run_dir {
+        Some(run_dir) => run_dir.clone(),
+        None => default_run_dir(&default_installation_dir),
+    };
+    std::fs::create_dir_all(&run_dir)?;
+    remove_orphans(&run_dir);
+
+    // The directory is named after the process, so that it is known to be orphaned once the
+    // process is gone
+    let directory = tempfile::Builder::new()
+        .prefix(&format!("{}-", std::process::id()))
+        .tempdir_in(&run_dir)?
+        .into_path();
+    settings.data_dir = directory.join("data");
+    settings.password_file = directory.join(".pgpass");
+    settings.password = BUILD_PASSWORD.to_string();
+    settings.port = options.port;
+    settings.temporary = true;
+    settings.version = options.version;
+
+    let mut build_database = BuildDatabase {
+        postgresql: None,
+        database_name: options.database_name.clone(),
+        url: String::new(),
+        run_dir: directory,
+    };
+    let mut postgresql = PostgreSQL::from(settings);
+    postgresql.setup()?;
+    postgresql.start()?;
+    let postgresql = build_database.postgresql.insert(postgresql);
+    if !postgresql.database_exists(&options.database_name)? {
+        postgresql.create_database(&options.database_name)?;
+    }
+    build_database.url = postgresql.settings().url(&options.database_name);
+    debug!(
+        "Started build database {} in {}",
+        options.database_name,
+        build_database.run_dir.to_string_lossy()
+    );
+    Ok(build_database)
+}
+
+/// Get the default [run directory](BuildSetupOptions::run_dir) for the installation directory
+fn default_run_dir(installation_dir: &Path) -> PathBuf {
+    installation_dir.with_file_name("build")
+}
+
+/// Stop the servers and remove the data directories of build databases whose build script is
+/// no longer running, e.g. because it was killed
+fn remove_orphans(run_dir: &Path) {
+    let Ok(entries) = std::fs::read_dir(run_dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let Some(Ok(owner)) = name.split('-').next().map(str::parse::<u32>) else {
+            continue;
+        };
+        if process_exists(owner) {
+            continue;
+        }
+
+        let directory = entry.path();
+        warn!(
+            "Removing orphaned build database {}",
+            directory.to_string_lossy()
+        );
+        if let Ok(pid) = postmaster_pid(&directory.join("data")) {
+            if process_exists(pid) {
+                debug!("Killing orphaned postmaster {pid}");
+                let _ = kill_process(pid);
+                let started = Instant::now();
+                while process_exists(pid) && started.elapsed() < ORPHAN_KILL_TIMEOUT {
+                    sleep(Duration::from_millis(100));
+                }
+            }
+        }
+        if let Err(error) = remove_dir_all(&directory) {
+            warn!(
+                "Failed to remove orphaned build database {}: {error}",
+                directory.to_string_lossy()
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_run_dir() {
+        assert_eq!(
+            PathBuf::from("/home/user/.theseus/build"),
+            default_run_dir(Path::new("/home/user/.theseus/postgresql"))
+        );
+    }
+
+    #[test]
+    fn test_remove_orphans() -> Result<()> {
+        let run_dir = tempfile::tempdir()?;
+        // No process has the largest process id
+        let orphan = run_dir.path().join(format!("{}-orphan", u32::MAX));
+        let owned = run_dir.path().join(format!("{}-owned", std::process::id()));
+        let unrelated = run_dir.path().join("unrelated");
+        for directory in [&orphan, &owned, &unrelated] {
+            std::fs::create_dir_all(directory.join("data"))?;
+        }
+
+        remove_orphans(run_dir.path());
+        assert!(!orphan.exists());
+        assert!(owned.exists());
+        assert!(unrelated.exists());
+        Ok(())
+    }
+}
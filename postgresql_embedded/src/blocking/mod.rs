@@ -1,6 +1,24 @@
-use crate::{Result, Settings, Status};
+mod build_script;
+
+pub use build_script::{setup_for_build, BuildDatabase, BuildSetupOptions};
+
+use crate::{
+    AnalyzeReport, BatchOptions, BenchOptions, BenchResult, BoundAddress, CancellationToken,
+    ClusterParams, ConnectionInfo, ConnectionInfoHandle, CrashMode, CreateDatabaseOptions,
+    DiskUsage, EnvFileFormat, FunctionInfo, IndexInfo, InstanceDescription, LowDiskEvent,
+    LowDiskWatcher, Operation, OperationOutcome, PreparedTransaction, ProgressEvent, ProxyHandle,
+    QueryResult, QuiesceOptions, QuiescedDataDir, Readiness, RecoveryInfo, ReorderStrategy,
+    ResetOptions, ResetReport, Result, ServerInfo, SessionPoolStats, Settings, Status,
+    TableDescription, TableInfo, TablespaceInfo, TeardownMode, TeardownReport, Toc, Tool, ToolInfo,
+    ToolInventory, WalUsage,
+};
 use lazy_static::lazy_static;
-use postgresql_archive::Version;
+use postgresql_archive::{ArchiveSource, ExtractFilter, Version};
+use postgresql_commands::{CommandBuilder, QualifiedName};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
 use tokio::runtime::Runtime;
 
 lazy_static! {
@@ -13,6 +31,15 @@ pub struct PostgreSQL {
     inner: crate::postgresql::PostgreSQL,
 }
 
+/// Create a new [`PostgreSQL`] instance for the [version](Settings::version) of the settings
+impl From<Settings> for PostgreSQL {
+    fn from(settings: Settings) -> Self {
+        Self {
+            inner: crate::postgresql::PostgreSQL::from(settings),
+        }
+    }
+}
+
 /// PostgreSQL server methods
 impl PostgreSQL {
     /// Create a new [`crate::postgresql::PostgreSQL`] instance
@@ -22,6 +49,30 @@ impl PostgreSQL {
         }
     }
 
+    /// Use the [source](ArchiveSource) to resolve the version and fetch the installation archive
+    /// instead of downloading it from GitHub
+    pub fn with_archive_source(self, archive_source: Arc<dyn ArchiveSource>) -> Self {
+        Self {
+            inner: self.inner.with_archive_source(archive_source),
+        }
+    }
+
+    /// Extract only the entries of the installation archive selected by the
+    /// [filter](ExtractFilter)
+    pub fn with_extract_filter(self, extract_filter: ExtractFilter) -> Self {
+        Self {
+            inner: self.inner.with_extract_filter(extract_filter),
+        }
+    }
+
+    /// Apply the pg_dump output at the path to the maintenance database after the server is
+    /// first started
+    pub fn with_dump<P: Into<PathBuf>>(self, path: P) -> Result<Self> {
+        Ok(Self {
+            inner: self.inner.with_dump(path)?,
+        })
+    }
+
     /// Get the [status](Status) of the PostgreSQL server
     pub fn status(&self) -> Status {
         self.inner.status()
@@ -37,6 +88,49 @@ impl PostgreSQL {
         self.inner.settings()
     }
 
+    /// Get the path of the Unix-domain socket of the server
+    pub fn socket_path(&self) -> Option<PathBuf> {
+        self.inner.socket_path()
+    }
+
+    /// Get the inventory of the programs in the installation, which is built by setup
+    pub fn inventory(&self) -> &ToolInventory {
+        self.inner.inventory()
+    }
+
+    /// Get the information of a program the caller depends on; an error is returned if the
+    /// program is not present in the installation
+    pub fn require_tool(&self, tool: Tool) -> Result<&ToolInfo> {
+        self.inner.require_tool(tool)
+    }
+
+    /// Get the current endpoint of the server
+    pub fn connection_info(&self) -> ConnectionInfo {
+        self.inner.connection_info()
+    }
+
+    /// Get a handle that always reflects the current endpoint of the server; subscribers are
+    /// notified when the server is started on a different endpoint.
+    pub fn connection_info_handle(&self) -> ConnectionInfoHandle {
+        self.inner.connection_info_handle()
+    }
+
+    /// Describe the server for external tooling; see [InstanceDescription].
+    pub fn describe(&self) -> InstanceDescription {
+        self.inner.describe()
+    }
+
+    /// Get the path the description of the server is written to on every lifecycle transition
+    pub fn description_path(&self) -> PathBuf {
+        self.inner.description_path()
+    }
+
+    /// Write the description of the server as JSON to the path; the file is replaced atomically.
+    #[cfg(feature = "serde")]
+    pub fn write_description<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        self.inner.write_description(path)
+    }
+
     /// Set up the database by extracting the archive and initializing the database.
     /// If the installation directory already exists, the archive will not be extracted.
     /// If the data directory already exists, the database will not be initialized.
@@ -54,6 +148,13 @@ impl PostgreSQL {
             .block_on(async move { self.inner.start().await })
     }
 
+    /// Check whether the server accepts connections, without authenticating.
+    pub fn ping(&self) -> Result<Readiness> {
+        RUNTIME
+            .handle()
+            .block_on(async move { self.inner.ping().await })
+    }
+
     /// Stop the database gracefully (smart mode) and wait for the shutdown to complete.
     pub fn stop(&self) -> Result<()> {
         RUNTIME
@@ -61,6 +162,48 @@ impl PostgreSQL {
             .block_on(async move { self.inner.stop().await })
     }
 
+    /// Stop the server and wait until the data directory is safe to archive or copy
+    pub fn stop_and_quiesce(&mut self) -> Result<QuiescedDataDir<'_>> {
+        RUNTIME
+            .handle()
+            .block_on(async move { self.inner.stop_and_quiesce().await })
+    }
+
+    /// Stop the server at the end of a test as quickly as the lifecycle allows, and remove the
+    /// data directory of a temporary instance
+    pub fn teardown(self, mode: Option<TeardownMode>) -> Result<TeardownReport> {
+        RUNTIME
+            .handle()
+            .block_on(async move { self.inner.teardown(mode).await })
+    }
+
+    /// Crash the server on purpose to verify that an application survives crash recovery. Only
+    /// a server started by this instance can be crashed.
+    pub fn crash(&mut self, mode: CrashMode) -> Result<()> {
+        RUNTIME
+            .handle()
+            .block_on(async move { self.inner.crash(mode).await })
+    }
+
+    /// Bring the server back up after a crash and return the crash recovery details parsed from
+    /// the server log.
+    pub fn recover(&mut self) -> Result<RecoveryInfo> {
+        RUNTIME
+            .handle()
+            .block_on(async move { self.inner.recover().await })
+    }
+
+    /// Write a connection environment file for attaching external client tools to the server.
+    /// The file is rewritten every time the server is started.
+    pub fn write_env_file<P: AsRef<Path>>(&self, path: P, format: EnvFileFormat) -> Result<()> {
+        self.inner.write_env_file(path, format)
+    }
+
+    /// Run an interactive psql session connected to the server, for quick manual inspection.
+    pub fn exec_psql_interactive(&self) -> Result<()> {
+        self.inner.exec_psql_interactive()
+    }
+
     /// Create a new database with the given name.
     pub fn create_database<S: AsRef<str>>(&self, database_name: S) -> Result<()> {
         RUNTIME
@@ -68,6 +211,20 @@ impl PostgreSQL {
             .block_on(async move { self.inner.create_database(database_name).await })
     }
 
+    /// Create a new database with the given name and provision it with the options; the
+    /// database is dropped if the provisioning fails
+    pub fn create_database_with_options<S: AsRef<str>>(
+        &self,
+        database_name: S,
+        options: &CreateDatabaseOptions,
+    ) -> Result<()> {
+        RUNTIME.handle().block_on(async move {
+            self.inner
+                .create_database_with_options(database_name, options)
+                .await
+        })
+    }
+
     /// Check if a database with the given name exists.
     pub fn database_exists<S: AsRef<str>>(&self, database_name: S) -> Result<bool> {
         RUNTIME
@@ -81,6 +238,608 @@ impl PostgreSQL {
             .handle()
             .block_on(async move { self.inner.drop_database(database_name).await })
     }
+
+    /// Create a physical replication slot with the given name. Nothing is done if the slot
+    /// already exists.
+    pub fn create_physical_slot<S: AsRef<str>>(&self, slot_name: S) -> Result<()> {
+        RUNTIME
+            .handle()
+            .block_on(async move { self.inner.create_physical_slot(slot_name).await })
+    }
+
+    /// Create a logical replication slot with the given name in the database, decoding changes
+    /// with the output plugin. Nothing is done if the slot already exists.
+    pub fn create_logical_slot<S: AsRef<str>, N: AsRef<str>, P: AsRef<str>>(
+        &self,
+        database_name: S,
+        slot_name: N,
+        plugin: P,
+    ) -> Result<()> {
+        RUNTIME.handle().block_on(async move {
+            self.inner
+                .create_logical_slot(database_name, slot_name, plugin)
+                .await
+        })
+    }
+
+    /// Drop the replication slot with the given name. Nothing is done if the slot does not exist.
+    pub fn drop_slot<S: AsRef<str>>(&self, slot_name: S) -> Result<()> {
+        RUNTIME
+            .handle()
+            .block_on(async move { self.inner.drop_slot(slot_name).await })
+    }
+
+    /// Drop all user-created roles, except for the roles in the `keep` list.
+    /// Returns the names of the dropped roles.
+    pub fn reset_roles(&self, keep: &[&str]) -> Result<Vec<String>> {
+        RUNTIME
+            .handle()
+            .block_on(async move { self.inner.reset_roles(keep).await })
+    }
+
+    /// Rewind this (target) server to the live source server; the target is configured to
+    /// follow the source as a standby.
+    pub fn rewind_from(&mut self, source: &PostgreSQL) -> Result<()> {
+        RUNTIME
+            .handle()
+            .block_on(async move { self.inner.rewind_from(&source.inner).await })
+    }
+
+    /// Execute the SQL against the given database and return the resulting rows.
+    pub fn run_sql<S: AsRef<str>, Q: AsRef<str>>(
+        &self,
+        database_name: S,
+        sql: Q,
+    ) -> Result<QueryResult> {
+        RUNTIME
+            .handle()
+            .block_on(async move { self.inner.run_sql(database_name, sql).await })
+    }
+
+    /// Execute the SQL against the given database in a new psql process rather than on a
+    /// reused psql session, and return the resulting rows.
+    pub fn run_sql_in_new_session<S: AsRef<str>, Q: AsRef<str>>(
+        &self,
+        database_name: S,
+        sql: Q,
+    ) -> Result<QueryResult> {
+        RUNTIME
+            .handle()
+            .block_on(async move { self.inner.run_sql_in_new_session(database_name, sql).await })
+    }
+
+    /// Get the statistics of the psql sessions used by the SQL helpers
+    pub fn session_pool_stats(&self) -> SessionPoolStats {
+        self.inner.session_pool_stats()
+    }
+
+    /// Execute the SQL against the given database and return the resulting rows along with the
+    /// notices and warnings raised by the server.
+    pub fn query_with_notices<S: AsRef<str>, Q: AsRef<str>>(
+        &self,
+        database_name: S,
+        sql: Q,
+    ) -> Result<QueryResult> {
+        RUNTIME
+            .handle()
+            .block_on(async move { self.inner.query_with_notices(database_name, sql).await })
+    }
+
+    /// Terminate the sessions that have been idle in a transaction for at least `older_than`
+    pub fn terminate_idle_in_transaction(&self, older_than: Duration) -> Result<Vec<u32>> {
+        RUNTIME
+            .handle()
+            .block_on(async move { self.inner.terminate_idle_in_transaction(older_than).await })
+    }
+
+    /// Create a tablespace with the given name; when no location is given, the tablespace is
+    /// placed in a directory managed by the crate. Returns the location of the tablespace.
+    pub fn create_tablespace<S: AsRef<str>>(
+        &self,
+        name: S,
+        location: Option<PathBuf>,
+    ) -> Result<PathBuf> {
+        RUNTIME
+            .handle()
+            .block_on(async move { self.inner.create_tablespace(name, location).await })
+    }
+
+    /// Drop the tablespace with the given name, first moving its contents to the `force_move_to`
+    /// tablespace when given
+    pub fn drop_tablespace<S: AsRef<str>>(
+        &self,
+        name: S,
+        force_move_to: Option<&str>,
+    ) -> Result<()> {
+        RUNTIME
+            .handle()
+            .block_on(async move { self.inner.drop_tablespace(name, force_move_to).await })
+    }
+
+    /// List the tablespaces of the server
+    pub fn list_tablespaces(&self) -> Result<Vec<TablespaceInfo>> {
+        RUNTIME
+            .handle()
+            .block_on(async move { self.inner.list_tablespaces().await })
+    }
+
+    /// List the tables in the given database; all schemas except the system schemas are listed
+    /// unless a schema is given
+    pub fn list_tables<S: AsRef<str>>(
+        &self,
+        database_name: S,
+        schema: Option<&str>,
+    ) -> Result<Vec<TableInfo>> {
+        RUNTIME
+            .handle()
+            .block_on(async move { self.inner.list_tables(database_name, schema).await })
+    }
+
+    /// Describe the columns, indexes and constraints of a table in the given database
+    pub fn describe_table<S: AsRef<str>, T: AsRef<str>>(
+        &self,
+        database_name: S,
+        table: T,
+    ) -> Result<TableDescription> {
+        RUNTIME
+            .handle()
+            .block_on(async move { self.inner.describe_table(database_name, table).await })
+    }
+
+    /// List the indexes in the given database; all schemas except the system schemas are listed
+    /// unless a schema is given
+    pub fn list_indexes<S: AsRef<str>>(
+        &self,
+        database_name: S,
+        schema: Option<&str>,
+    ) -> Result<Vec<IndexInfo>> {
+        RUNTIME
+            .handle()
+            .block_on(async move { self.inner.list_indexes(database_name, schema).await })
+    }
+
+    /// List the functions in the given database; all schemas except the system schemas are
+    /// listed unless a schema is given
+    pub fn list_functions<S: AsRef<str>>(
+        &self,
+        database_name: S,
+        schema: Option<&str>,
+    ) -> Result<Vec<FunctionInfo>> {
+        RUNTIME
+            .handle()
+            .block_on(async move { self.inner.list_functions(database_name, schema).await })
+    }
+
+    /// Vacuum the table in the given database and wait until the vacuum is reflected in the
+    /// cumulative statistics.
+    pub fn wait_for_vacuum<S: AsRef<str>, T: AsRef<str>>(
+        &self,
+        database_name: S,
+        table: T,
+        timeout: Duration,
+    ) -> Result<()> {
+        RUNTIME.handle().block_on(async move {
+            self.inner
+                .wait_for_vacuum(database_name, table, timeout)
+                .await
+        })
+    }
+
+    /// Execute independent SQL statements against the given database with bounded concurrency.
+    /// Returns one result per statement, in the order of the statements.
+    pub fn run_sql_batch<S: AsRef<str>>(
+        &self,
+        database_name: S,
+        statements: Vec<String>,
+        options: BatchOptions,
+    ) -> Vec<Result<QueryResult>> {
+        RUNTIME.handle().block_on(async move {
+            self.inner
+                .run_sql_batch(database_name, statements, options)
+                .await
+        })
+    }
+
+    /// Run a command built by any [CommandBuilder], with the settings of the server applied.
+    pub fn run_command<B: CommandBuilder>(&self, command_builder: B) -> Result<(String, String)> {
+        RUNTIME
+            .handle()
+            .block_on(async move { self.inner.run_command(command_builder).await })
+    }
+
+    /// Back up the database to a file in the pg_dump custom archive format.
+    pub fn backup<S: AsRef<str>, P: Into<PathBuf>>(&self, database_name: S, path: P) -> Result<()> {
+        RUNTIME
+            .handle()
+            .block_on(async move { self.inner.backup(database_name, path).await })
+    }
+
+    /// Back up a single table of the database to a file in the pg_dump custom archive format.
+    pub fn backup_table<S: AsRef<str>, P: Into<PathBuf>>(
+        &self,
+        database_name: S,
+        table: &QualifiedName,
+        path: P,
+    ) -> Result<()> {
+        RUNTIME
+            .handle()
+            .block_on(async move { self.inner.backup_table(database_name, table, path).await })
+    }
+
+    /// Back up the tables selected by a query to a file in the pg_dump custom archive format.
+    pub fn backup_tables_from_query<S, Q, P>(
+        &self,
+        database_name: S,
+        query: Q,
+        path: P,
+    ) -> Result<Vec<QualifiedName>>
+    where
+        S: AsRef<str>,
+        Q: AsRef<str>,
+        P: Into<PathBuf>,
+    {
+        RUNTIME.handle().block_on(async move {
+            self.inner
+                .backup_tables_from_query(database_name, query, path)
+                .await
+        })
+    }
+
+    /// Produce a deterministic snapshot of the schema of the database for golden tests
+    pub fn schema_snapshot<S: AsRef<str>>(&self, database_name: S) -> Result<String> {
+        RUNTIME
+            .handle()
+            .block_on(async move { self.inner.schema_snapshot(database_name).await })
+    }
+
+    /// Restore a single table from a pg_dump archive into the database, analyzing it afterward
+    /// when requested.
+    pub fn restore_table<S: AsRef<str>, P: Into<PathBuf>>(
+        &self,
+        database_name: S,
+        table: &QualifiedName,
+        path: P,
+        analyze: Option<bool>,
+    ) -> Result<Option<AnalyzeReport>> {
+        RUNTIME.handle().block_on(async move {
+            self.inner
+                .restore_table(database_name, table, path, analyze)
+                .await
+        })
+    }
+
+    /// Restore the database from a pg_dump archive, replacing the objects of the archive when
+    /// the database exists and creating the database when it does not.
+    pub fn restore_database<S: AsRef<str>, P: Into<PathBuf>>(
+        &self,
+        database_name: S,
+        path: P,
+        analyze: Option<bool>,
+    ) -> Result<Option<AnalyzeReport>> {
+        RUNTIME.handle().block_on(async move {
+            self.inner
+                .restore_database(database_name, path, analyze)
+                .await
+        })
+    }
+
+    /// Restore the database from a pg_dump archive like
+    /// [restore_database](Self::restore_database), in the order chosen by the strategy.
+    pub fn restore_database_ordered<S: AsRef<str>, P: Into<PathBuf>>(
+        &self,
+        database_name: S,
+        path: P,
+        strategy: &ReorderStrategy,
+        analyze: Option<bool>,
+    ) -> Result<Option<AnalyzeReport>> {
+        RUNTIME.handle().block_on(async move {
+            self.inner
+                .restore_database_ordered(database_name, path, strategy, analyze)
+                .await
+        })
+    }
+
+    /// List the table of contents of a pg_dump archive, including the dependencies between its
+    /// entries.
+    pub fn list_archive<P: Into<PathBuf>>(&self, path: P) -> Result<Toc> {
+        RUNTIME
+            .handle()
+            .block_on(async move { self.inner.list_archive(path).await })
+    }
+
+    /// Get the size in bytes of the tables in the given database.
+    pub fn table_sizes<S: AsRef<str>>(
+        &self,
+        database_name: S,
+    ) -> Result<HashMap<QualifiedName, u64>> {
+        RUNTIME
+            .handle()
+            .block_on(async move { self.inner.table_sizes(database_name).await })
+    }
+
+    /// Back up the database to a file in the pg_dump custom archive format, reporting progress
+    /// to the callback and stopping when the token is cancelled.
+    pub fn dump<S, P, F>(
+        &self,
+        database_name: S,
+        path: P,
+        progress: F,
+        cancel: &CancellationToken,
+    ) -> Result<()>
+    where
+        S: AsRef<str>,
+        P: Into<PathBuf>,
+        F: Fn(ProgressEvent) + Send + 'static,
+    {
+        RUNTIME
+            .handle()
+            .block_on(async move { self.inner.dump(database_name, path, progress, cancel).await })
+    }
+
+    /// Restore the database from a pg_dump archive, reporting progress to the callback and
+    /// stopping when the token is cancelled; the database is analyzed afterward when requested.
+    pub fn restore<S, P, F>(
+        &self,
+        database_name: S,
+        path: P,
+        analyze: Option<bool>,
+        progress: F,
+        cancel: &CancellationToken,
+    ) -> Result<Option<AnalyzeReport>>
+    where
+        S: AsRef<str>,
+        P: Into<PathBuf>,
+        F: Fn(ProgressEvent) + Send + 'static,
+    {
+        RUNTIME.handle().block_on(async move {
+            self.inner
+                .restore(database_name, path, analyze, progress, cancel)
+                .await
+        })
+    }
+
+    /// Remove the data of all user tables in the given database while preserving the schema
+    pub fn reset_data<S: AsRef<str>>(
+        &self,
+        database_name: S,
+        options: &ResetOptions,
+    ) -> Result<ResetReport> {
+        RUNTIME
+            .handle()
+            .block_on(async move { self.inner.reset_data(database_name, options).await })
+    }
+
+    /// Gather planner statistics for the tables in the given database; every table is analyzed
+    /// when no tables are given.
+    pub fn analyze<S: AsRef<str>>(
+        &self,
+        database_name: S,
+        tables: &[QualifiedName],
+    ) -> Result<AnalyzeReport> {
+        RUNTIME
+            .handle()
+            .block_on(async move { self.inner.analyze(database_name, tables).await })
+    }
+
+    /// Take a base backup of the server into the directory, reporting progress to the callback
+    /// and stopping when the token is cancelled.
+    pub fn base_backup<P, F>(
+        &self,
+        directory: P,
+        progress: F,
+        cancel: &CancellationToken,
+    ) -> Result<()>
+    where
+        P: Into<PathBuf>,
+        F: Fn(ProgressEvent) + Send + 'static,
+    {
+        RUNTIME
+            .handle()
+            .block_on(async move { self.inner.base_backup(directory, progress, cancel).await })
+    }
+
+    /// Apply a lifecycle [operation](Operation) to the server; the operation is delegated to the
+    /// corresponding method.
+    pub fn apply(&mut self, operation: Operation) -> OperationOutcome {
+        RUNTIME
+            .handle()
+            .block_on(async move { self.inner.apply(operation).await })
+    }
+
+    /// Get the disk space consumed by the server; the sizes of the databases and the largest
+    /// relations are only reported when the server is started.
+    pub fn disk_usage(&self) -> Result<DiskUsage> {
+        RUNTIME
+            .handle()
+            .block_on(async move { self.inner.disk_usage().await })
+    }
+
+    /// Get the sockets the server listens on
+    pub fn bound_addresses(&self) -> Result<Vec<BoundAddress>> {
+        RUNTIME
+            .handle()
+            .block_on(async move { self.inner.bound_addresses().await })
+    }
+
+    /// List the transactions prepared for two-phase commit that were never committed or rolled
+    /// back
+    pub fn orphaned_prepared_transactions(&self) -> Result<Vec<PreparedTransaction>> {
+        RUNTIME
+            .handle()
+            .block_on(async move { self.inner.orphaned_prepared_transactions().await })
+    }
+
+    /// Roll back every prepared transaction, returning the transactions that were rolled back
+    pub fn rollback_all_prepared(&self) -> Result<Vec<PreparedTransaction>> {
+        RUNTIME
+            .handle()
+            .block_on(async move { self.inner.rollback_all_prepared().await })
+    }
+
+    /// Issue a `CHECKPOINT` and return once it is complete
+    pub fn checkpoint(&self) -> Result<()> {
+        RUNTIME
+            .handle()
+            .block_on(async move { self.inner.checkpoint().await })
+    }
+
+    /// Get the compile-time parameters of the cluster
+    pub fn cluster_params(&self) -> Result<ClusterParams> {
+        RUNTIME
+            .handle()
+            .block_on(async move { self.inner.cluster_params().await })
+    }
+
+    /// Get the live value of the configuration parameter (GUC) with `SHOW`.
+    pub fn get_guc<S: AsRef<str>>(&self, name: S) -> Result<String> {
+        RUNTIME
+            .handle()
+            .block_on(async move { self.inner.get_guc(name).await })
+    }
+
+    /// Check that the live value of the configuration parameter (GUC) is the expected value.
+    pub fn assert_guc<S: AsRef<str>, V: AsRef<str>>(&self, name: S, expected: V) -> Result<()> {
+        RUNTIME
+            .handle()
+            .block_on(async move { self.inner.assert_guc(name, expected).await })
+    }
+
+    /// Run the operation with autovacuum turned off; the prior setting is restored afterward,
+    /// also when the operation panics.
+    pub fn with_autovacuum_paused<F, T>(&self, operation: F) -> Result<T>
+    where
+        F: FnOnce(&Self) -> T,
+    {
+        let pause = RUNTIME
+            .handle()
+            .block_on(async { self.inner.pause_autovacuum().await })?;
+        let result = operation(self);
+        RUNTIME.handle().block_on(async { pause.restore().await })?;
+        Ok(result)
+    }
+
+    /// Quiesce background activity before a benchmark: turn autovacuum off, checkpoint and
+    /// wait for running vacuums, depending on the options
+    pub fn quiesce_background_activity(&self, options: &QuiesceOptions) -> Result<()> {
+        RUNTIME
+            .handle()
+            .block_on(async move { self.inner.quiesce_background_activity(options).await })
+    }
+
+    /// Restore the autovacuum setting changed by quiesce_background_activity
+    pub fn restore_background_activity(&self) -> Result<()> {
+        RUNTIME
+            .handle()
+            .block_on(async move { self.inner.restore_background_activity().await })
+    }
+
+    /// Get the write-ahead log retained by the server
+    pub fn wal_usage(&self) -> Result<WalUsage> {
+        RUNTIME
+            .handle()
+            .block_on(async move { self.inner.wal_usage().await })
+    }
+
+    /// Drop the replication slots that have been inactive for at least `older_than`
+    pub fn prune_inactive_replication_slots(&self, older_than: Duration) -> Result<Vec<String>> {
+        RUNTIME.handle().block_on(async move {
+            self.inner
+                .prune_inactive_replication_slots(older_than)
+                .await
+        })
+    }
+
+    /// Watch the available disk space on the filesystem of the data directory; the callback is
+    /// invoked when the available space drops below the threshold (in bytes).
+    pub fn low_disk_watcher<F>(&self, threshold_bytes: u64, callback: F) -> LowDiskWatcher
+    where
+        F: Fn(LowDiskEvent) + Send + 'static,
+    {
+        self.inner.low_disk_watcher(threshold_bytes, callback)
+    }
+
+    /// Start a TCP proxy in front of the server that records the SQL statements sent by clients
+    pub fn statement_logging_proxy(&self) -> Result<ProxyHandle> {
+        self.inner.statement_logging_proxy()
+    }
+
+    /// Start a TCP proxy in front of the server that injects faults into the traffic
+    #[cfg(feature = "fault-injection")]
+    pub fn fault_injecting_proxy(&self) -> Result<crate::FaultInjectingProxy> {
+        self.inner.fault_injecting_proxy()
+    }
+
+    /// Run a pgbench benchmark; the pgbench tables are initialized at the given scale, then the
+    /// default workload is run for the given duration and number of clients.
+    pub fn benchmark(&self, options: BenchOptions) -> Result<BenchResult> {
+        RUNTIME
+            .handle()
+            .block_on(async move { self.inner.benchmark(options).await })
+    }
+}
+
+/// Verify that a connection can be established with the given settings by authenticating and
+/// running a query. Returns information about the server.
+pub fn verify_connection(settings: &dyn postgresql_commands::Settings) -> Result<ServerInfo> {
+    RUNTIME
+        .handle()
+        .block_on(async move { crate::verify_connection(settings).await })
+}
+
+/// Template database built from a directory of SQL migrations
+#[derive(Clone, Debug)]
+pub struct MigratedTemplate {
+    inner: crate::MigratedTemplate,
+}
+
+/// Migrated template methods
+impl MigratedTemplate {
+    /// Create a new [`MigratedTemplate`] for the given migrations directory
+    pub fn new<P: Into<PathBuf>>(migrations_dir: P) -> Self {
+        Self {
+            inner: crate::MigratedTemplate::new(migrations_dir),
+        }
+    }
+
+    /// Set the prefix used for the template database names
+    pub fn prefix<S: AsRef<str>>(self, prefix: S) -> Self {
+        Self {
+            inner: self.inner.prefix(prefix),
+        }
+    }
+
+    /// Analyze the template database before it is marked as a template
+    pub fn analyze(self, analyze: bool) -> Self {
+        Self {
+            inner: self.inner.analyze(analyze),
+        }
+    }
+
+    /// Get the name of the template database for the current migration set
+    pub fn template_name(&self) -> Result<String> {
+        self.inner.template_name()
+    }
+
+    /// Ensure that the template database for the current migration set exists, building it if
+    /// necessary, and drop the templates of stale migration sets.
+    pub fn setup(&self, postgresql: &PostgreSQL) -> Result<String> {
+        RUNTIME
+            .handle()
+            .block_on(async move { self.inner.setup(&postgresql.inner).await })
+    }
+
+    /// Create a new database with the given name as a clone of the template database.
+    pub fn create_database<S: AsRef<str>>(
+        &self,
+        postgresql: &PostgreSQL,
+        database_name: S,
+    ) -> Result<()> {
+        RUNTIME.handle().block_on(async move {
+            self.inner
+                .create_database(&postgresql.inner, database_name)
+                .await
+        })
+    }
 }
 
 #[cfg(test)]
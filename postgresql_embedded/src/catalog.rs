@@ -0,0 +1,533 @@
+//! Catalog queries describing database objects, equivalent to the psql `\d` meta-commands. The
+//! queries only use catalog columns and functions available in every supported PostgreSQL
+//! version, so the results do not depend on the psql output format.
+
+use crate::sql::quote_literal;
+use postgresql_commands::QualifiedName;
+use std::collections::HashMap;
+
+/// Separator used between the elements of list columns (e.g. the columns of an index)
+const LIST_SEPARATOR: char = '\u{1d}';
+
+/// Table (or partitioned table); see [list_tables](crate::PostgreSQL::list_tables)
+#[derive(Clone, Debug, PartialEq)]
+pub struct TableInfo {
+    /// Schema containing the table
+    pub schema: String,
+    /// Name of the table
+    pub name: String,
+    /// Owner of the table
+    pub owner: String,
+    /// Persistence of the table
+    pub persistence: Persistence,
+    /// Estimated number of rows, as of the last vacuum or analyze; `None` if the table has not
+    /// been vacuumed or analyzed yet (PostgreSQL 13 reports `0` instead)
+    pub approximate_rows: Option<u64>,
+}
+
+/// Persistence of a table
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Persistence {
+    /// Regular table
+    Permanent,
+    /// Table that is not written to the write-ahead log (`UNLOGGED`)
+    Unlogged,
+    /// Table that is dropped at the end of the session (`TEMPORARY`)
+    Temporary,
+}
+
+/// Columns, indexes and constraints of a table; see
+/// [describe_table](crate::PostgreSQL::describe_table)
+#[derive(Clone, Debug, PartialEq)]
+pub struct TableDescription {
+    /// Schema containing the table
+    pub schema: String,
+    /// Name of the table
+    pub name: String,
+    /// Columns of the table, in the order of their definition
+    pub columns: Vec<ColumnInfo>,
+    /// Indexes on the table, ordered by name
+    pub indexes: Vec<IndexInfo>,
+    /// Constraints on the table, including foreign keys, ordered by name
+    pub constraints: Vec<ConstraintInfo>,
+    /// Foreign keys referencing other tables, ordered by name
+    pub foreign_keys: Vec<ForeignKeyInfo>,
+}
+
+/// Column of a table
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColumnInfo {
+    /// Name of the column
+    pub name: String,
+    /// Data type of the column, including the type modifier; e.g. `character varying(20)`
+    pub data_type: String,
+    /// Whether the column accepts null values
+    pub nullable: bool,
+    /// Default expression of the column; e.g. `nextval('t_id_seq'::regclass)`
+    pub default: Option<String>,
+}
+
+/// Index on a table; see [list_indexes](crate::PostgreSQL::list_indexes)
+#[derive(Clone, Debug, PartialEq)]
+pub struct IndexInfo {
+    /// Schema containing the index
+    pub schema: String,
+    /// Table the index is defined on
+    pub table: String,
+    /// Name of the index
+    pub name: String,
+    /// Key columns or expressions of the index, quoted as needed
+    pub columns: Vec<String>,
+    /// Whether the index is unique
+    pub unique: bool,
+    /// Whether the index backs the primary key
+    pub primary: bool,
+    /// Predicate of a partial index; e.g. `(active = true)`
+    pub predicate: Option<String>,
+    /// `CREATE INDEX` statement that defines the index
+    pub definition: String,
+}
+
+/// Constraint on a table
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConstraintInfo {
+    /// Name of the constraint
+    pub name: String,
+    /// Kind of the constraint
+    pub kind: ConstraintKind,
+    /// Definition of the constraint; e.g. `CHECK (price > 0)`
+    pub definition: String,
+}
+
+/// Kind of a constraint
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConstraintKind {
+    /// `CHECK` constraint
+    Check,
+    /// `EXCLUDE` constraint
+    Exclusion,
+    /// `FOREIGN KEY` constraint
+    ForeignKey,
+    /// `PRIMARY KEY` constraint
+    PrimaryKey,
+    /// `UNIQUE` constraint
+    Unique,
+    /// Other constraint kinds, identified by their `pg_constraint.contype` code
+    Other(String),
+}
+
+/// Foreign key referencing another table
+#[derive(Clone, Debug, PartialEq)]
+pub struct ForeignKeyInfo {
+    /// Name of the foreign key constraint
+    pub name: String,
+    /// Referencing columns
+    pub columns: Vec<String>,
+    /// Schema containing the referenced table
+    pub referenced_schema: String,
+    /// Referenced table
+    pub referenced_table: String,
+    /// Referenced columns, in the order of the referencing columns
+    pub referenced_columns: Vec<String>,
+    /// Action when a referenced row is updated; e.g. `NO ACTION` or `CASCADE`
+    pub on_update: String,
+    /// Action when a referenced row is deleted; e.g. `NO ACTION` or `CASCADE`
+    pub on_delete: String,
+}
+
+/// Function, procedure or aggregate; see [list_functions](crate::PostgreSQL::list_functions)
+#[derive(Clone, Debug, PartialEq)]
+pub struct FunctionInfo {
+    /// Schema containing the function
+    pub schema: String,
+    /// Name of the function
+    pub name: String,
+    /// Arguments identifying the function; e.g. `a integer, b text`
+    pub arguments: String,
+    /// Result type of the function; `None` for procedures
+    pub result_type: Option<String>,
+    /// Kind of the function
+    pub kind: FunctionKind,
+    /// Language the function is implemented in; e.g. `sql` or `plpgsql`
+    pub language: String,
+}
+
+/// Kind of a function
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FunctionKind {
+    /// Normal function
+    Function,
+    /// Procedure, executed with `CALL`
+    Procedure,
+    /// Aggregate function
+    Aggregate,
+    /// Window function
+    Window,
+}
+
+/// Condition on the schema `n.nspname`; without a schema, the system schemas are excluded
+pub(crate) fn schema_condition(schema: Option<&str>) -> String {
+    match schema {
+        Some(schema) => format!("n.nspname = {}", quote_literal(schema)),
+        None => "n.nspname <> 'information_schema' AND n.nspname NOT LIKE 'pg\\_%'".to_string(),
+    }
+}
+
+/// Query listing the tables in the schema
+pub(crate) fn tables_query(schema: Option<&str>) -> String {
+    format!(
+        "SELECT n.nspname, c.relname, pg_catalog.pg_get_userbyid(c.relowner), c.relpersistence, \
+         c.reltuples::bigint \
+         FROM pg_catalog.pg_class c JOIN pg_catalog.pg_namespace n ON n.oid = c.relnamespace \
+         WHERE c.relkind IN ('r', 'p') AND {} ORDER BY 1, 2",
+        schema_condition(schema)
+    )
+}
+
+/// Parse the rows of the [tables query](tables_query)
+pub(crate) fn parse_tables(rows: Vec<Vec<String>>) -> Vec<TableInfo> {
+    rows.into_iter()
+        .filter_map(|row| match row.as_slice() {
+            [schema, name, owner, persistence, reltuples] => Some(TableInfo {
+                schema: schema.clone(),
+                name: name.clone(),
+                owner: owner.clone(),
+                persistence: match persistence.as_str() {
+                    "u" => Persistence::Unlogged,
+                    "t" => Persistence::Temporary,
+                    _ => Persistence::Permanent,
+                },
+                // reltuples is -1 until the table is vacuumed or analyzed
+                approximate_rows: reltuples.parse().ok(),
+            }),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Query getting the size of the tables, excluding indexes and including TOAST data
+pub(crate) fn table_sizes_query() -> String {
+    format!(
+        "SELECT n.nspname, c.relname, pg_catalog.pg_table_size(c.oid) \
+         FROM pg_catalog.pg_class c JOIN pg_catalog.pg_namespace n ON n.oid = c.relnamespace \
+         WHERE c.relkind IN ('r', 'p') AND {} ORDER BY 1, 2",
+        schema_condition(None)
+    )
+}
+
+/// Parse the rows of the [table sizes query](table_sizes_query)
+pub(crate) fn parse_table_sizes(rows: Vec<Vec<String>>) -> HashMap<QualifiedName, u64> {
+    rows.into_iter()
+        .filter_map(|row| match row.as_slice() {
+            [schema, name, bytes] => Some((
+                QualifiedName::qualified(schema.as_str(), name.as_str()),
+                bytes.parse().ok()?,
+            )),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Query resolving the (possibly schema qualified) table name to its schema and name
+pub(crate) fn table_query(relation: &str) -> String {
+    format!(
+        "SELECT n.nspname, c.relname \
+         FROM pg_catalog.pg_class c JOIN pg_catalog.pg_namespace n ON n.oid = c.relnamespace \
+         WHERE c.oid = {relation}::regclass"
+    )
+}
+
+/// Query describing the columns of the table
+pub(crate) fn columns_query(relation: &str) -> String {
+    format!(
+        "SELECT a.attname, pg_catalog.format_type(a.atttypid, a.atttypmod), NOT a.attnotnull, \
+         pg_catalog.pg_get_expr(d.adbin, d.adrelid) \
+         FROM pg_catalog.pg_attribute a \
+         LEFT JOIN pg_catalog.pg_attrdef d ON d.adrelid = a.attrelid AND d.adnum = a.attnum \
+         WHERE a.attrelid = {relation}::regclass AND a.attnum > 0 AND NOT a.attisdropped \
+         ORDER BY a.attnum"
+    )
+}
+
+/// Parse the rows of the [columns query](columns_query)
+pub(crate) fn parse_columns(rows: Vec<Vec<String>>) -> Vec<ColumnInfo> {
+    rows.into_iter()
+        .filter_map(|row| match row.as_slice() {
+            [name, data_type, nullable, default] => Some(ColumnInfo {
+                name: name.clone(),
+                data_type: data_type.clone(),
+                nullable: nullable == "t",
+                default: non_empty(default),
+            }),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Query listing the indexes matching the condition on the index `c`, table `t` and namespace
+/// `n` of the index
+fn indexes_query(condition: &str) -> String {
+    format!(
+        "SELECT n.nspname, t.relname, c.relname, i.indisunique, i.indisprimary, \
+         array_to_string(ARRAY(SELECT pg_catalog.pg_get_indexdef(i.indexrelid, k, true) \
+         FROM generate_series(1, i.indnkeyatts) AS k ORDER BY k), {separator}), \
+         pg_catalog.pg_get_expr(i.indpred, i.indrelid, true), \
+         pg_catalog.pg_get_indexdef(i.indexrelid) \
+         FROM pg_catalog.pg_index i \
+         JOIN pg_catalog.pg_class c ON c.oid = i.indexrelid \
+         JOIN pg_catalog.pg_class t ON t.oid = i.indrelid \
+         JOIN pg_catalog.pg_namespace n ON n.oid = c.relnamespace \
+         WHERE {condition} ORDER BY 1, 2, 3",
+        separator = quote_literal(LIST_SEPARATOR.to_string())
+    )
+}
+
+/// Query listing the indexes in the schema
+pub(crate) fn schema_indexes_query(schema: Option<&str>) -> String {
+    indexes_query(&schema_condition(schema))
+}
+
+/// Query listing the indexes on the table
+pub(crate) fn table_indexes_query(relation: &str) -> String {
+    indexes_query(&format!("i.indrelid = {relation}::regclass"))
+}
+
+/// Parse the rows of an indexes query
+pub(crate) fn parse_indexes(rows: Vec<Vec<String>>) -> Vec<IndexInfo> {
+    rows.into_iter()
+        .filter_map(|row| match row.as_slice() {
+            [schema, table, name, unique, primary, columns, predicate, definition] => {
+                Some(IndexInfo {
+                    schema: schema.clone(),
+                    table: table.clone(),
+                    name: name.clone(),
+                    columns: parse_list(columns),
+                    unique: unique == "t",
+                    primary: primary == "t",
+                    predicate: non_empty(predicate),
+                    definition: definition.clone(),
+                })
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Query listing the constraints on the table
+pub(crate) fn constraints_query(relation: &str) -> String {
+    format!(
+        "SELECT conname, contype, pg_catalog.pg_get_constraintdef(oid, true) \
+         FROM pg_catalog.pg_constraint WHERE conrelid = {relation}::regclass ORDER BY 1"
+    )
+}
+
+/// Parse the rows of the [constraints query](constraints_query)
+pub(crate) fn parse_constraints(rows: Vec<Vec<String>>) -> Vec<ConstraintInfo> {
+    rows.into_iter()
+        .filter_map(|row| match row.as_slice() {
+            [name, kind, definition] => Some(ConstraintInfo {
+                name: name.clone(),
+                kind: match kind.as_str() {
+                    "c" => ConstraintKind::Check,
+                    "x" => ConstraintKind::Exclusion,
+                    "f" => ConstraintKind::ForeignKey,
+                    "p" => ConstraintKind::PrimaryKey,
+                    "u" => ConstraintKind::Unique,
+                    kind => ConstraintKind::Other(kind.to_string()),
+                },
+                definition: definition.clone(),
+            }),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Query listing the foreign keys of the table
+pub(crate) fn foreign_keys_query(relation: &str) -> String {
+    let columns = |keys: &str, relation: &str| {
+        format!(
+            "array_to_string(ARRAY(SELECT a.attname FROM unnest(c.{keys}) WITH ORDINALITY AS k(attnum, position) \
+             JOIN pg_catalog.pg_attribute a ON a.attrelid = c.{relation} AND a.attnum = k.attnum \
+             ORDER BY k.position), {})",
+            quote_literal(LIST_SEPARATOR.to_string())
+        )
+    };
+    format!(
+        "SELECT c.conname, {}, n.nspname, r.relname, {}, c.confupdtype, c.confdeltype \
+         FROM pg_catalog.pg_constraint c \
+         JOIN pg_catalog.pg_class r ON r.oid = c.confrelid \
+         JOIN pg_catalog.pg_namespace n ON n.oid = r.relnamespace \
+         WHERE c.conrelid = {relation}::regclass AND c.contype = 'f' ORDER BY 1",
+        columns("conkey", "conrelid"),
+        columns("confkey", "confrelid"),
+    )
+}
+
+/// Parse the rows of the [foreign keys query](foreign_keys_query)
+pub(crate) fn parse_foreign_keys(rows: Vec<Vec<String>>) -> Vec<ForeignKeyInfo> {
+    rows.into_iter()
+        .filter_map(|row| match row.as_slice() {
+            [name, columns, referenced_schema, referenced_table, referenced_columns, on_update, on_delete] => {
+                Some(ForeignKeyInfo {
+                    name: name.clone(),
+                    columns: parse_list(columns),
+                    referenced_schema: referenced_schema.clone(),
+                    referenced_table: referenced_table.clone(),
+                    referenced_columns: parse_list(referenced_columns),
+                    on_update: foreign_key_action(on_update),
+                    on_delete: foreign_key_action(on_delete),
+                })
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Convert a foreign key action code (`pg_constraint.confupdtype` and `confdeltype`) to SQL
+fn foreign_key_action(code: &str) -> String {
+    match code {
+        "r" => "RESTRICT",
+        "c" => "CASCADE",
+        "n" => "SET NULL",
+        "d" => "SET DEFAULT",
+        _ => "NO ACTION",
+    }
+    .to_string()
+}
+
+/// Query listing the functions in the schema
+pub(crate) fn functions_query(schema: Option<&str>) -> String {
+    format!(
+        "SELECT n.nspname, p.proname, pg_catalog.pg_get_function_identity_arguments(p.oid), \
+         pg_catalog.pg_get_function_result(p.oid), p.prokind, l.lanname \
+         FROM pg_catalog.pg_proc p \
+         JOIN pg_catalog.pg_namespace n ON n.oid = p.pronamespace \
+         JOIN pg_catalog.pg_language l ON l.oid = p.prolang \
+         WHERE {} ORDER BY 1, 2, 3",
+        schema_condition(schema)
+    )
+}
+
+/// Parse the rows of the [functions query](functions_query)
+pub(crate) fn parse_functions(rows: Vec<Vec<String>>) -> Vec<FunctionInfo> {
+    rows.into_iter()
+        .filter_map(|row| match row.as_slice() {
+            [schema, name, arguments, result_type, kind, language] => Some(FunctionInfo {
+                schema: schema.clone(),
+                name: name.clone(),
+                arguments: arguments.clone(),
+                result_type: non_empty(result_type),
+                kind: match kind.as_str() {
+                    "p" => FunctionKind::Procedure,
+                    "a" => FunctionKind::Aggregate,
+                    "w" => FunctionKind::Window,
+                    _ => FunctionKind::Function,
+                },
+                language: language.clone(),
+            }),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Parse a list column; an empty value is an empty list
+fn parse_list(value: &str) -> Vec<String> {
+    if value.is_empty() {
+        return Vec::new();
+    }
+    value.split(LIST_SEPARATOR).map(str::to_string).collect()
+}
+
+/// Convert a nullable value, which psql outputs as an empty string, to an option
+fn non_empty(value: &str) -> Option<String> {
+    if value.is_empty() {
+        None
+    } else {
+        Some(value.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schema_condition() {
+        assert_eq!("n.nspname = 'it''s'", schema_condition(Some("it's")));
+        assert!(schema_condition(None).contains("information_schema"));
+    }
+
+    #[test]
+    fn test_parse_tables() {
+        let rows = vec![
+            vec!["public", "a", "postgres", "p", "-1"],
+            vec!["public", "b", "postgres", "u", "42"],
+            vec!["pg_temp_3", "c", "postgres", "t", "0"],
+            vec!["invalid"],
+        ]
+        .into_iter()
+        .map(|row| row.into_iter().map(str::to_string).collect())
+        .collect();
+
+        let tables = parse_tables(rows);
+        assert_eq!(3, tables.len());
+        assert_eq!(Persistence::Permanent, tables[0].persistence);
+        assert_eq!(None, tables[0].approximate_rows);
+        assert_eq!(Persistence::Unlogged, tables[1].persistence);
+        assert_eq!(Some(42), tables[1].approximate_rows);
+        assert_eq!(Persistence::Temporary, tables[2].persistence);
+    }
+
+    #[test]
+    fn test_parse_table_sizes() {
+        let rows = vec![
+            vec!["public", "a", "8192"],
+            vec!["public", "b", "invalid"],
+            vec!["invalid"],
+        ]
+        .into_iter()
+        .map(|row| row.into_iter().map(str::to_string).collect())
+        .collect();
+
+        let sizes = parse_table_sizes(rows);
+        assert_eq!(
+            HashMap::from([(QualifiedName::qualified("public", "a"), 8192)]),
+            sizes
+        );
+    }
+
+    #[test]
+    fn test_parse_foreign_keys() {
+        let row = vec![
+            "orders_fkey".to_string(),
+            format!("customer_id{LIST_SEPARATOR}region"),
+            "sales".to_string(),
+            "customers".to_string(),
+            format!("id{LIST_SEPARATOR}region"),
+            "a".to_string(),
+            "c".to_string(),
+        ];
+
+        assert_eq!(
+            vec![ForeignKeyInfo {
+                name: "orders_fkey".to_string(),
+                columns: vec!["customer_id".to_string(), "region".to_string()],
+                referenced_schema: "sales".to_string(),
+                referenced_table: "customers".to_string(),
+                referenced_columns: vec!["id".to_string(), "region".to_string()],
+                on_update: "NO ACTION".to_string(),
+                on_delete: "CASCADE".to_string(),
+            }],
+            parse_foreign_keys(vec![row])
+        );
+    }
+
+    #[test]
+    fn test_parse_list() {
+        assert!(parse_list("").is_empty());
+        assert_eq!(
+            vec!["a".to_string(), "lower(b)".to_string()],
+            parse_list(&format!("a{LIST_SEPARATOR}lower(b)"))
+        );
+    }
+}
@@ -0,0 +1,113 @@
+//! Compile-time parameters of a cluster, which are fixed when PostgreSQL is built or the cluster
+//! is initialized
+
+use crate::error::{Error::ClusterParamsError, Result};
+
+/// Compile-time parameters of a cluster; see [cluster_params](crate::PostgreSQL::cluster_params)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ClusterParams {
+    /// Size of a disk block in bytes (`block_size`); `8192` for a standard build
+    pub block_size: u32,
+    /// Size of a WAL disk block in bytes (`wal_block_size`); `8192` for a standard build
+    pub wal_block_size: u32,
+    /// Size of a WAL segment file in bytes (`wal_segment_size`); `16777216` unless the cluster
+    /// was initialized with a different `--wal-segsize`
+    pub wal_segment_size: u64,
+    /// Maximum length of an identifier in bytes (`max_identifier_length`); `63` for a standard
+    /// build
+    pub max_identifier_length: u32,
+}
+
+/// Parameters queried from `pg_settings` when the server is started
+pub(crate) const SETTINGS_QUERY: &str = "SELECT name, setting FROM pg_settings \
+    WHERE name IN ('block_size', 'wal_block_size', 'wal_segment_size', 'max_identifier_length')";
+
+/// Parse the rows of parameter names and values returned by [SETTINGS_QUERY]; the values are in
+/// bytes
+pub(crate) fn parse_settings(rows: &[Vec<String>]) -> Result<ClusterParams> {
+    let setting = |name: &str| {
+        rows.iter()
+            .find_map(|row| match row.as_slice() {
+                [row_name, value] if row_name == name => Some(value.as_str()),
+                _ => None,
+            })
+            .ok_or_else(|| ClusterParamsError(anyhow::anyhow!("setting {name} not found")))
+    };
+    Ok(ClusterParams {
+        block_size: parse_number(setting("block_size")?)?,
+        wal_block_size: parse_number(setting("wal_block_size")?)?,
+        wal_segment_size: parse_number(setting("wal_segment_size")?)?,
+        max_identifier_length: parse_number(setting("max_identifier_length")?)?,
+    })
+}
+
+/// Parse the output of pg_controldata. The maximum identifier length is reported as the size of
+/// the name type (`NAMEDATALEN`), which includes the terminating null byte.
+pub(crate) fn parse_control_data(output: &str) -> Result<ClusterParams> {
+    let value = |label: &str| {
+        output
+            .lines()
+            .find_map(|line| {
+                let (line_label, value) = line.split_once(':')?;
+                (line_label.trim() == label).then(|| value.trim())
+            })
+            .ok_or_else(|| ClusterParamsError(anyhow::anyhow!("{label} not found")))
+    };
+    let name_length: u32 = parse_number(value("Maximum length of identifiers")?)?;
+    Ok(ClusterParams {
+        block_size: parse_number(value("Database block size")?)?,
+        wal_block_size: parse_number(value("WAL block size")?)?,
+        wal_segment_size: parse_number(value("Bytes per WAL segment")?)?,
+        max_identifier_length: name_length.saturating_sub(1),
+    })
+}
+
+fn parse_number<T: std::str::FromStr>(value: &str) -> Result<T> {
+    value
+        .parse()
+        .map_err(|_| ClusterParamsError(anyhow::anyhow!("invalid number {value}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STANDARD: ClusterParams = ClusterParams {
+        block_size: 8192,
+        wal_block_size: 8192,
+        wal_segment_size: 16_777_216,
+        max_identifier_length: 63,
+    };
+
+    #[test]
+    fn test_parse_settings() -> Result<()> {
+        let rows: Vec<Vec<String>> = [
+            ["block_size", "8192"],
+            ["max_identifier_length", "63"],
+            ["wal_block_size", "8192"],
+            ["wal_segment_size", "16777216"],
+        ]
+        .iter()
+        .map(|row| row.iter().map(|value| value.to_string()).collect())
+        .collect();
+        assert_eq!(STANDARD, parse_settings(&rows)?);
+        assert!(parse_settings(&rows[1..]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_control_data() -> Result<()> {
+        let output = "pg_control version number:            1300
+Catalog version number:               202209061
+Database block size:                  8192
+Blocks per segment of large relation: 131072
+WAL block size:                       8192
+Bytes per WAL segment:                16777216
+Maximum length of identifiers:        64
+Maximum columns in an index:          32
+";
+        assert_eq!(STANDARD, parse_control_data(output)?);
+        assert!(parse_control_data("WAL block size: 8192").is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,255 @@
+//! Helpers for writing server configuration parameters to `postgresql.conf`
+
+use crate::error::Result;
+use crate::settings::Settings;
+use std::fs;
+use tracing::warn;
+
+/// Comment appended to every line of `postgresql.conf` written by this crate
+const MANAGED_MARKER: &str = "# managed by postgresql_embedded";
+
+/// Quote a configuration parameter value for `postgresql.conf`
+pub(crate) fn quote_value<S: AsRef<str>>(value: S) -> String {
+    let escaped = value.as_ref().replace('\\', "\\\\").replace('\'', "''");
+    format!("'{escaped}'")
+}
+
+/// Render the contents of `postgresql.conf` with the given parameters. Lines written by a
+/// previous call are replaced, so the managed parameters always reflect the latest settings.
+pub(crate) fn render(contents: &str, parameters: &[(&str, String)]) -> String {
+    let mut rendered = String::new();
+    for line in contents.lines() {
+        if !line.ends_with(MANAGED_MARKER) {
+            rendered.push_str(line);
+            rendered.push('\n');
+        }
+    }
+    for (name, value) in parameters {
+        rendered.push_str(&format!(
+            "{name} = {} {MANAGED_MARKER}\n",
+            quote_value(value)
+        ));
+    }
+    rendered
+}
+
+/// Get the value of a parameter set in `postgresql.conf` contents; lines written by this crate
+/// are ignored. When the parameter is set more than once, the last value is returned.
+pub(crate) fn parameter_value(contents: &str, name: &str) -> Option<String> {
+    contents
+        .lines()
+        .rev()
+        .filter(|line| !line.ends_with(MANAGED_MARKER))
+        .find_map(|line| {
+            let (key, value) = line
+                .trim()
+                .split_once(|c: char| c == '=' || c.is_whitespace())?;
+            if !key.eq_ignore_ascii_case(name) {
+                return None;
+            }
+            let value = value.trim_start();
+            let value = value.strip_prefix('=').unwrap_or(value).trim_start();
+            Some(unquote_value(value))
+        })
+}
+
+/// Unquote a configuration parameter value; any trailing comment is removed
+fn unquote_value(value: &str) -> String {
+    let Some(quoted) = value.strip_prefix('\'') else {
+        return value
+            .split(|c: char| c == '#' || c.is_whitespace())
+            .next()
+            .unwrap_or_default()
+            .to_string();
+    };
+
+    let mut unquoted = String::new();
+    let mut chars = quoted.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' if chars.peek() == Some(&'\'') => {
+                unquoted.push('\'');
+                chars.next();
+            }
+            '\'' => break,
+            '\\' => unquoted.extend(chars.next()),
+            c => unquoted.push(c),
+        }
+    }
+    unquoted
+}
+
+/// Append the libraries to a comma separated list of libraries; libraries already in the list
+/// are not added again.
+pub(crate) fn append_libraries(existing: &str, libraries: &[String]) -> String {
+    let mut merged: Vec<&str> = existing
+        .split(',')
+        .map(str::trim)
+        .filter(|library| !library.is_empty())
+        .collect();
+    for library in libraries {
+        if !merged.contains(&library.as_str()) {
+            merged.push(library);
+        }
+    }
+    merged.join(",")
+}
+
+/// Write the configuration parameters of the settings to the `postgresql.conf` file in the data
+/// directory. The [preload libraries](Settings::preload_libraries) are appended to any
+/// `shared_preload_libraries` already configured in the file, and `unix_socket_directories` is
+/// set to the [server socket directory](Settings::server_socket_dir) when there is one.
+pub(crate) fn write(settings: &Settings) -> Result<()> {
+    let path = settings.data_dir.join("postgresql.conf");
+    let contents = fs::read_to_string(&path)?;
+    let mut parameters = settings.configuration();
+    if let Some(socket_dir) = settings.server_socket_dir() {
+        parameters.push((
+            "unix_socket_directories",
+            socket_dir.to_string_lossy().to_string(),
+        ));
+    }
+    if !settings.preload_libraries.is_empty() {
+        let existing = parameter_value(&contents, "shared_preload_libraries").unwrap_or_default();
+        parameters.push((
+            "shared_preload_libraries",
+            append_libraries(&existing, &settings.preload_libraries),
+        ));
+
+        let auto_conf =
+            fs::read_to_string(settings.data_dir.join("postgresql.auto.conf")).unwrap_or_default();
+        if parameter_value(&auto_conf, "shared_preload_libraries").is_some() {
+            warn!("shared_preload_libraries is set with ALTER SYSTEM and overrides the preload libraries");
+        }
+    }
+
+    let rendered = render(&contents, &parameters);
+    if rendered != contents {
+        fs::write(&path, rendered)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::{IpVersion, SocketDirMode};
+
+    #[test]
+    fn test_quote_value() {
+        assert_eq!("'%m [%p] '", quote_value("%m [%p] "));
+        assert_eq!(r"'it''s \\'", quote_value(r"it's \"));
+    }
+
+    #[test]
+    fn test_render_replaces_managed_lines() {
+        let contents = "max_connections = 100\n";
+        let parameters = [("log_line_prefix", "%m ".to_string())];
+        let rendered = render(contents, &parameters);
+        assert_eq!(
+            format!("max_connections = 100\nlog_line_prefix = '%m ' {MANAGED_MARKER}\n"),
+            rendered
+        );
+
+        let parameters = [("log_line_prefix", "%p ".to_string())];
+        assert_eq!(
+            format!("max_connections = 100\nlog_line_prefix = '%p ' {MANAGED_MARKER}\n"),
+            render(&rendered, &parameters)
+        );
+        assert_eq!(contents, render(&rendered, &[]));
+    }
+
+    #[test]
+    fn test_parameter_value() {
+        let contents = format!(
+            "#shared_preload_libraries = ''\t# (change requires restart)\n\
+            shared_preload_libraries = 'auto_explain'\t# comment\n\
+            log_min_messages=warning\n\
+            shared_preload_libraries_other = 'other'\n\
+            shared_preload_libraries = 'managed' {MANAGED_MARKER}\n"
+        );
+        assert_eq!(
+            Some("auto_explain".to_string()),
+            parameter_value(&contents, "shared_preload_libraries")
+        );
+        assert_eq!(
+            Some("warning".to_string()),
+            parameter_value(&contents, "log_min_messages")
+        );
+        assert_eq!(None, parameter_value(&contents, "log_line_prefix"));
+        assert_eq!(
+            Some("it's".to_string()),
+            parameter_value("name 'it''s'", "name")
+        );
+    }
+
+    #[test]
+    fn test_append_libraries() {
+        let libraries = vec!["pg_stat_statements".to_string(), "auto_explain".to_string()];
+        assert_eq!(
+            "pg_stat_statements,auto_explain",
+            append_libraries("", &libraries)
+        );
+        assert_eq!(
+            "auto_explain,pg_stat_statements",
+            append_libraries("auto_explain", &libraries)
+        );
+        assert_eq!(
+            "timescaledb,pg_stat_statements,auto_explain",
+            append_libraries(" timescaledb , ", &libraries)
+        );
+    }
+
+    #[test]
+    fn test_write_appends_preload_libraries() -> Result<()> {
+        let data_dir = tempfile::tempdir()?;
+        let path = data_dir.path().join("postgresql.conf");
+        fs::write(&path, "shared_preload_libraries = 'auto_explain'\n")?;
+        let settings = Settings {
+            data_dir: data_dir.path().to_path_buf(),
+            host_ip_version: IpVersion::Either,
+            socket_dir_mode: SocketDirMode::ServerDefault,
+            preload_libraries: vec!["pg_stat_statements".to_string()],
+            ..Default::default()
+        };
+
+        write(&settings)?;
+        write(&settings)?;
+        let contents = fs::read_to_string(&path)?;
+        assert_eq!(
+            format!("shared_preload_libraries = 'auto_explain'\nshared_preload_libraries = 'auto_explain,pg_stat_statements' {MANAGED_MARKER}\n"),
+            contents
+        );
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_socket_dir() -> Result<()> {
+        let data_dir = tempfile::tempdir()?;
+        let path = data_dir.path().join("postgresql.conf");
+        fs::write(&path, "")?;
+        let mut settings = Settings {
+            data_dir: data_dir.path().to_path_buf(),
+            host_ip_version: IpVersion::Either,
+            ..Default::default()
+        };
+
+        write(&settings)?;
+        let socket_dir = settings
+            .server_socket_dir()
+            .expect("managed socket directory");
+        assert_eq!(
+            format!(
+                "unix_socket_directories = '{}' {MANAGED_MARKER}\n",
+                socket_dir.to_string_lossy()
+            ),
+            fs::read_to_string(&path)?
+        );
+
+        settings.socket_dir_mode = SocketDirMode::ServerDefault;
+        write(&settings)?;
+        assert_eq!("", fs::read_to_string(&path)?);
+        Ok(())
+    }
+}
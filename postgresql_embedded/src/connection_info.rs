@@ -0,0 +1,146 @@
+use crate::settings::{url_host, Settings};
+use postgresql_commands::traits::REDACTED;
+use std::fmt::{Debug, Formatter};
+#[cfg(feature = "tokio")]
+use tokio::sync::watch;
+
+/// Endpoint of a running PostgreSQL server. The password is redacted from the debug output.
+#[derive(Clone, PartialEq)]
+pub struct ConnectionInfo {
+    /// PostgreSQL host
+    pub host: String,
+    /// PostgreSQL port
+    pub port: u16,
+    /// PostgreSQL user name
+    pub username: String,
+    /// PostgreSQL password
+    pub password: String,
+    /// Port the server was expected to use, when it was taken at start and the server fell back
+    /// to a new [port](Self::port); e.g. the port persisted by the previous start
+    pub previous_port: Option<u16>,
+}
+
+impl Debug for ConnectionInfo {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> std::fmt::Result {
+        formatter
+            .debug_struct("ConnectionInfo")
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field("username", &self.username)
+            .field("password", &REDACTED)
+            .field("previous_port", &self.previous_port)
+            .finish()
+    }
+}
+
+impl ConnectionInfo {
+    /// Return the PostgreSQL URL for the given database name.
+    pub fn url<S: AsRef<str>>(&self, database_name: S) -> String {
+        format!(
+            "postgresql://{}:{}@{}:{}/{}",
+            self.username,
+            self.password,
+            url_host(&self.host),
+            self.port,
+            database_name.as_ref()
+        )
+    }
+}
+
+impl From<&Settings> for ConnectionInfo {
+    fn from(settings: &Settings) -> Self {
+        Self {
+            host: settings.effective_host().to_string(),
+            port: settings.port,
+            username: settings.username.clone(),
+            password: settings.password.clone(),
+            previous_port: None,
+        }
+    }
+}
+
+/// Handle that always reflects the current endpoint of a PostgreSQL server. The endpoint is
+/// updated every time the server is started, so long-lived applications can rebuild their
+/// connection pools when the server restarts on a different port.
+#[cfg(feature = "tokio")]
+#[derive(Clone, Debug)]
+pub struct ConnectionInfoHandle {
+    receiver: watch::Receiver<ConnectionInfo>,
+}
+
+#[cfg(feature = "tokio")]
+impl ConnectionInfoHandle {
+    /// Create a new [`ConnectionInfoHandle`] for the given receiver
+    pub(crate) fn new(receiver: watch::Receiver<ConnectionInfo>) -> Self {
+        Self { receiver }
+    }
+
+    /// Get the current endpoint
+    pub fn current(&self) -> ConnectionInfo {
+        self.receiver.borrow().clone()
+    }
+
+    /// Return the PostgreSQL URL of the current endpoint for the given database name.
+    pub fn url<S: AsRef<str>>(&self, database_name: S) -> String {
+        self.receiver.borrow().url(database_name)
+    }
+
+    /// Subscribe to endpoint changes; the receiver is notified every time the endpoint changes.
+    pub fn subscribe(&self) -> watch::Receiver<ConnectionInfo> {
+        self.receiver.clone()
+    }
+
+    /// Wait for the endpoint to change and return the new endpoint. Returns `None` when the
+    /// server has been dropped and the endpoint can no longer change.
+    pub async fn changed(&mut self) -> Option<ConnectionInfo> {
+        match self.receiver.changed().await {
+            Ok(()) => Some(self.receiver.borrow_and_update().clone()),
+            Err(_) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_connection_info_url() {
+        let settings = Settings {
+            port: 5432,
+            password: "password".to_string(),
+            ..Default::default()
+        };
+        let connection_info = ConnectionInfo::from(&settings);
+        assert_eq!(settings.url("test"), connection_info.url("test"));
+    }
+
+    #[test]
+    fn test_connection_info_debug_redacts_password() {
+        let settings = Settings {
+            password: "secret-password".to_string(),
+            ..Default::default()
+        };
+        let debug = format!("{:?}", ConnectionInfo::from(&settings));
+        assert!(!debug.contains("secret-password"));
+        assert!(debug.contains(REDACTED));
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_connection_info_handle_changed() {
+        let settings = Settings::default();
+        let (sender, receiver) = watch::channel(ConnectionInfo::from(&settings));
+        let mut handle = ConnectionInfoHandle::new(receiver);
+        assert_eq!(settings.port, handle.current().port);
+
+        let mut changed = ConnectionInfo::from(&settings);
+        changed.port = 5433;
+        sender.send_replace(changed.clone());
+        assert_eq!(Some(changed), handle.changed().await);
+        assert_eq!(5433, handle.current().port);
+
+        drop(sender);
+        assert_eq!(None, handle.changed().await);
+    }
+}
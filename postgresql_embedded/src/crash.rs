@@ -0,0 +1,209 @@
+use crate::error::Error::CrashError;
+use crate::error::Result;
+use postgresql_commands::Lsn;
+use std::path::Path;
+#[cfg(not(unix))]
+use std::process::Command;
+use std::time::Duration;
+
+/// Unclean action used to crash a PostgreSQL server with [crash](crate::PostgreSQL::crash)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CrashMode {
+    /// Stop the server in immediate mode; all server processes are terminated without a
+    /// shutdown checkpoint
+    ImmediateShutdown,
+    /// Kill the postmaster with `SIGKILL`; the remaining server processes exit when they detect
+    /// that the postmaster is gone
+    KillPostmaster,
+    /// Kill one client backend with `SIGKILL`; the postmaster terminates the other server
+    /// processes and performs crash recovery on its own
+    KillRandomBackend,
+}
+
+/// Details of a crash recovery, parsed from the server log
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RecoveryInfo {
+    /// Location where redo started; e.g. `0/1523F48`. Not set when redo was not required.
+    pub redo_start_lsn: Option<Lsn>,
+    /// Location where redo ended; e.g. `0/1540F58`. Not set when redo was not required.
+    pub redo_end_lsn: Option<Lsn>,
+    /// Time spent in redo, as reported by PostgreSQL 14+
+    pub duration: Option<Duration>,
+}
+
+/// Parse the recovery details from the server log lines written during recovery
+pub(crate) fn parse_recovery_info(log: &str) -> RecoveryInfo {
+    let mut recovery_info = RecoveryInfo::default();
+    for line in log.lines() {
+        if let Some((_, rest)) = line.split_once("redo starts at ") {
+            recovery_info.redo_start_lsn = parse_lsn(rest);
+        } else if let Some((_, rest)) = line.split_once("redo done at ") {
+            recovery_info.redo_end_lsn = parse_lsn(rest);
+            recovery_info.duration = line
+                .split_once("elapsed: ")
+                .and_then(|(_, elapsed)| elapsed.split_whitespace().next())
+                .and_then(|seconds| seconds.parse::<f64>().ok())
+                .map(Duration::from_secs_f64);
+        }
+    }
+    recovery_info
+}
+
+/// Parse the location at the start of the log message
+fn parse_lsn(message: &str) -> Option<Lsn> {
+    message.split_whitespace().next()?.parse().ok()
+}
+
+/// Read the process id of the postmaster from the `postmaster.pid` file in the data directory
+pub(crate) fn postmaster_pid(data_dir: &Path) -> Result<u32> {
+    let contents = std::fs::read_to_string(data_dir.join("postmaster.pid"))?;
+    match contents.lines().next().map(|pid| pid.trim().parse::<u32>()) {
+        Some(Ok(pid)) => Ok(pid),
+        _ => Err(CrashError(anyhow::anyhow!(
+            "Invalid postmaster.pid file in {}",
+            data_dir.to_string_lossy()
+        ))),
+    }
+}
+
+/// Remove a lock file (e.g. `postmaster.pid`) if it is owned by the given process id
+pub(crate) fn remove_lock_file(lock_file: &Path, pid: u32) -> Result<()> {
+    let Ok(contents) = std::fs::read_to_string(lock_file) else {
+        return Ok(());
+    };
+    if contents.lines().next().map(str::trim) == Some(pid.to_string().as_str()) {
+        std::fs::remove_file(lock_file)?;
+    }
+    Ok(())
+}
+
+/// Forcefully kill the process with the given process id
+#[cfg(unix)]
+pub(crate) fn kill_process(pid: u32) -> Result<()> {
+    rustix::process::kill_process(unix_pid(pid)?, rustix::process::Signal::Kill)
+        .map_err(|error| CrashError(anyhow::anyhow!("Failed to kill process {pid}: {error}")))
+}
+
+/// Forcefully kill the process with the given process id
+#[cfg(not(unix))]
+pub(crate) fn kill_process(pid: u32) -> Result<()> {
+    let output = Command::new("taskkill")
+        .args(["/F", "/PID", &pid.to_string()])
+        .output()?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(CrashError(anyhow::anyhow!(
+            "Failed to kill process {pid}: {}",
+            String::from_utf8_lossy(&output.stderr)
+        )))
+    }
+}
+
+/// Convert the process id to a Unix process id; `0` and ids out of range refer to process
+/// groups rather than a process and are rejected
+#[cfg(unix)]
+fn unix_pid(pid: u32) -> Result<rustix::process::Pid> {
+    i32::try_from(pid)
+        .ok()
+        .and_then(rustix::process::Pid::from_raw)
+        .ok_or_else(|| CrashError(anyhow::anyhow!("Invalid process id {pid}")))
+}
+
+/// Check if the process with the given process id is running; zombie processes are not
+/// considered to be running.
+pub(crate) fn process_exists(pid: u32) -> bool {
+    #[cfg(target_os = "linux")]
+    {
+        std::fs::read_to_string(format!("/proc/{pid}/stat")).is_ok_and(|stat| {
+            // The state follows the command name, which is enclosed in parentheses
+            stat.rsplit_once(')')
+                .and_then(|(_, rest)| rest.split_whitespace().next())
+                .is_some_and(|state| state != "Z")
+        })
+    }
+    #[cfg(all(unix, not(target_os = "linux")))]
+    {
+        // Signal 0 checks that the process exists; a process owned by another user exists but
+        // cannot be signalled
+        unix_pid(pid).is_ok_and(|pid| match rustix::process::test_kill_process(pid) {
+            Ok(()) => true,
+            Err(error) => error == rustix::io::Errno::PERM,
+        })
+    }
+    #[cfg(target_os = "windows")]
+    {
+        Command::new("tasklist")
+            .args(["/FI", &format!("PID eq {pid}"), "/NH"])
+            .output()
+            .is_ok_and(|output| String::from_utf8_lossy(&output.stdout).contains(&pid.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_recovery_info() {
+        let log = "\
+2024-01-01 00:00:00.000 UTC [100] LOG:  database system was not properly shut down; automatic recovery in progress
+2024-01-01 00:00:00.001 UTC [100] LOG:  redo starts at 0/1523F48
+2024-01-01 00:00:00.002 UTC [100] LOG:  invalid record length at 0/1540F90: wanted 24, got 0
+2024-01-01 00:00:00.003 UTC [100] LOG:  redo done at 0/1540F58 system usage: CPU: user: 0.00 s, system: 0.00 s, elapsed: 0.25 s
+2024-01-01 00:00:00.004 UTC [1] LOG:  database system is ready to accept connections
+";
+        let recovery_info = parse_recovery_info(log);
+        assert_eq!(Some(Lsn::new(0, 0x152_3F48)), recovery_info.redo_start_lsn);
+        assert_eq!(Some(Lsn::new(0, 0x154_0F58)), recovery_info.redo_end_lsn);
+        assert_eq!(Some(Duration::from_millis(250)), recovery_info.duration);
+    }
+
+    #[test]
+    fn test_parse_recovery_info_redo_not_required() {
+        let log =
+            "LOG:  redo is not required\nLOG:  database system is ready to accept connections\n";
+        assert_eq!(RecoveryInfo::default(), parse_recovery_info(log));
+    }
+
+    #[test]
+    fn test_parse_recovery_info_without_elapsed() {
+        let log = "LOG:  redo starts at 0/16B3B98\nLOG:  redo done at 0/16B3C80\n";
+        let recovery_info = parse_recovery_info(log);
+        assert_eq!(Some(Lsn::new(0, 0x16B_3B98)), recovery_info.redo_start_lsn);
+        assert_eq!(Some(Lsn::new(0, 0x16B_3C80)), recovery_info.redo_end_lsn);
+        assert_eq!(None, recovery_info.duration);
+    }
+
+    #[test]
+    fn test_remove_lock_file() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let lock_file = temp_dir.path().join("postmaster.pid");
+        std::fs::write(&lock_file, "42\n/tmp/data\n")?;
+
+        remove_lock_file(&lock_file, 7)?;
+        assert!(lock_file.exists());
+        remove_lock_file(&lock_file, 42)?;
+        assert!(!lock_file.exists());
+        remove_lock_file(&lock_file, 42)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_process_exists() {
+        assert!(process_exists(std::process::id()));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_kill_process() -> Result<()> {
+        let mut child = std::process::Command::new("sleep").arg("30").spawn()?;
+        assert!(process_exists(child.id()));
+        kill_process(child.id())?;
+        child.wait()?;
+        assert!(!process_exists(child.id()));
+        assert!(kill_process(0).is_err());
+        Ok(())
+    }
+}
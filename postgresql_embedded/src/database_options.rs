@@ -0,0 +1,220 @@
+//! Declarative provisioning of a database right after it is created; see
+//! [create_database_with_options](crate::PostgreSQL::create_database_with_options)
+
+use crate::error::{Error::CreateDatabaseError, Result};
+use crate::sql::{quote_identifier, quote_literal};
+
+/// Provisioning applied to a database by
+/// [create_database_with_options](crate::PostgreSQL::create_database_with_options). The
+/// statements are applied in a single transaction, in the order of the fields: revocations,
+/// schemas, settings and default privileges.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CreateDatabaseOptions {
+    /// Privileges revoked after creation; e.g. `CREATE` on schema `public` from `PUBLIC`
+    pub revocations: Vec<Revocation>,
+    /// Schemas to create
+    pub schemas: Vec<SchemaSpec>,
+    /// Schemas of the database-level `search_path`; not set when empty
+    pub search_path: Vec<String>,
+    /// Other database-level configuration parameters (`ALTER DATABASE ... SET`) as name and
+    /// value; the value is passed as a string literal, e.g. `("statement_timeout", "5s")`
+    pub settings: Vec<(String, String)>,
+    /// Default privileges (`ALTER DEFAULT PRIVILEGES`) for objects created later
+    pub default_privileges: Vec<DefaultPrivileges>,
+}
+
+/// Schema created by [CreateDatabaseOptions]
+#[derive(Clone, Debug, PartialEq)]
+pub struct SchemaSpec {
+    /// Name of the schema
+    pub name: String,
+    /// Role owning the schema; the bootstrap superuser when not set
+    pub owner: Option<String>,
+}
+
+/// Privileges revoked by [CreateDatabaseOptions]; e.g.
+/// `REVOKE CREATE ON SCHEMA "public" FROM PUBLIC`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Revocation {
+    /// Privileges to revoke; e.g. `CREATE` or `ALL PRIVILEGES`
+    pub privileges: Vec<String>,
+    /// Type of the object; e.g. `SCHEMA` or `DATABASE`
+    pub object_type: String,
+    /// Name of the object
+    pub object: String,
+    /// Role the privileges are revoked from; `PUBLIC` for every role
+    pub role: String,
+}
+
+/// Default privileges granted by [CreateDatabaseOptions]; e.g.
+/// `ALTER DEFAULT PRIVILEGES FOR ROLE "app" IN SCHEMA "app" GRANT SELECT ON TABLES TO "reader"`
+#[derive(Clone, Debug, PartialEq)]
+pub struct DefaultPrivileges {
+    /// Role whose future objects are affected; the bootstrap superuser when not set
+    pub for_role: Option<String>,
+    /// Schema whose future objects are affected; every schema when not set
+    pub schema: Option<String>,
+    /// Privileges to grant; e.g. `SELECT` or `ALL`
+    pub privileges: Vec<String>,
+    /// Type of the objects; e.g. `TABLES`, `SEQUENCES` or `FUNCTIONS`
+    pub object_type: String,
+    /// Role the privileges are granted to; `PUBLIC` for every role
+    pub grantee: String,
+}
+
+/// Build the statements provisioning the database. Names and roles are quoted; privileges and
+/// object types are keywords, which may only contain letters and spaces.
+pub(crate) fn provisioning_statements(
+    database_name: &str,
+    options: &CreateDatabaseOptions,
+) -> Result<Vec<String>> {
+    let database = quote_identifier(database_name);
+    let mut statements = Vec::new();
+    for revocation in &options.revocations {
+        statements.push(format!(
+            "REVOKE {} ON {} {} FROM {}",
+            keywords(&revocation.privileges)?,
+            keyword(&revocation.object_type)?,
+            quote_identifier(&revocation.object),
+            quote_role(&revocation.role)
+        ));
+    }
+    for schema in &options.schemas {
+        let mut statement = format!("CREATE SCHEMA {}", quote_identifier(&schema.name));
+        if let Some(owner) = &schema.owner {
+            statement.push_str(&format!(" AUTHORIZATION {}", quote_identifier(owner)));
+        }
+        statements.push(statement);
+    }
+    if !options.search_path.is_empty() {
+        let search_path: Vec<String> = options.search_path.iter().map(quote_identifier).collect();
+        statements.push(format!(
+            "ALTER DATABASE {database} SET search_path = {}",
+            search_path.join(", ")
+        ));
+    }
+    for (name, value) in &options.settings {
+        statements.push(format!(
+            "ALTER DATABASE {database} SET {} = {}",
+            quote_identifier(name),
+            quote_literal(value)
+        ));
+    }
+    for default_privileges in &options.default_privileges {
+        let mut statement = "ALTER DEFAULT PRIVILEGES".to_string();
+        if let Some(role) = &default_privileges.for_role {
+            statement.push_str(&format!(" FOR ROLE {}", quote_identifier(role)));
+        }
+        if let Some(schema) = &default_privileges.schema {
+            statement.push_str(&format!(" IN SCHEMA {}", quote_identifier(schema)));
+        }
+        statement.push_str(&format!(
+            " GRANT {} ON {} TO {}",
+            keywords(&default_privileges.privileges)?,
+            keyword(&default_privileges.object_type)?,
+            quote_role(&default_privileges.grantee)
+        ));
+        statements.push(statement);
+    }
+    Ok(statements)
+}
+
+/// Check that the keyword only contains letters and spaces, e.g. `ALL PRIVILEGES`, and return
+/// it in upper case
+fn keyword(keyword: &str) -> Result<String> {
+    let keyword = keyword.trim();
+    if keyword.is_empty() || !keyword.chars().all(|c| c.is_ascii_alphabetic() || c == ' ') {
+        return Err(CreateDatabaseError(anyhow::anyhow!(
+            "Invalid keyword: {keyword:?}"
+        )));
+    }
+    Ok(keyword.to_ascii_uppercase())
+}
+
+/// Check and join a list of privilege keywords
+fn keywords(keywords: &[String]) -> Result<String> {
+    if keywords.is_empty() {
+        return Err(CreateDatabaseError(anyhow::anyhow!("No privileges given")));
+    }
+    let keywords = keywords
+        .iter()
+        .map(|privilege| keyword(privilege))
+        .collect::<Result<Vec<String>>>()?;
+    Ok(keywords.join(", "))
+}
+
+/// Quote a role, except for the `PUBLIC` pseudo-role
+fn quote_role(role: &str) -> String {
+    if role.eq_ignore_ascii_case("public") {
+        "PUBLIC".to_string()
+    } else {
+        quote_identifier(role)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_provisioning_statements() -> Result<()> {
+        let options = CreateDatabaseOptions {
+            revocations: vec![Revocation {
+                privileges: vec!["create".to_string()],
+                object_type: "schema".to_string(),
+                object: "public".to_string(),
+                role: "public".to_string(),
+            }],
+            schemas: vec![SchemaSpec {
+                name: "app".to_string(),
+                owner: Some("App \"Owner\"".to_string()),
+            }],
+            search_path: vec!["app".to_string(), "public".to_string()],
+            settings: vec![("statement_timeout".to_string(), "5s".to_string())],
+            default_privileges: vec![DefaultPrivileges {
+                for_role: Some("app".to_string()),
+                schema: Some("app".to_string()),
+                privileges: vec!["SELECT".to_string(), "INSERT".to_string()],
+                object_type: "TABLES".to_string(),
+                grantee: "reader".to_string(),
+            }],
+        };
+        assert_eq!(
+            vec![
+                r#"REVOKE CREATE ON SCHEMA "public" FROM PUBLIC"#,
+                r#"CREATE SCHEMA "app" AUTHORIZATION "App ""Owner""""#,
+                r#"ALTER DATABASE "my db" SET search_path = "app", "public""#,
+                r#"ALTER DATABASE "my db" SET "statement_timeout" = '5s'"#,
+                r#"ALTER DEFAULT PRIVILEGES FOR ROLE "app" IN SCHEMA "app" GRANT SELECT, INSERT ON TABLES TO "reader""#,
+            ],
+            provisioning_statements("my db", &options)?
+        );
+        assert!(provisioning_statements("test", &CreateDatabaseOptions::default())?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_provisioning_statements_invalid_keyword() {
+        let options = CreateDatabaseOptions {
+            revocations: vec![Revocation {
+                privileges: vec!["CREATE ON SCHEMA public FROM PUBLIC; DROP".to_string()],
+                object_type: "SCHEMA".to_string(),
+                object: "public".to_string(),
+                role: "PUBLIC".to_string(),
+            }],
+            ..Default::default()
+        };
+        assert!(provisioning_statements("test", &options).is_err());
+
+        let options = CreateDatabaseOptions {
+            revocations: vec![Revocation {
+                privileges: Vec::new(),
+                object_type: "SCHEMA".to_string(),
+                object: "public".to_string(),
+                role: "PUBLIC".to_string(),
+            }],
+            ..Default::default()
+        };
+        assert!(provisioning_statements("test", &options).is_err());
+    }
+}
@@ -0,0 +1,193 @@
+//! Machine-readable description of a server for external tooling, such as deployment scripts
+//! that need to discover the server managed by an application. With the `serde` feature, the
+//! description is written as JSON next to the data directory on every lifecycle transition.
+
+use crate::postgresql::Status;
+use crate::settings::data_dir_sibling;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Version of the [InstanceDescription] schema
+pub const DESCRIPTION_SCHEMA_VERSION: u32 = 1;
+
+/// Description of a server; see [describe](crate::PostgreSQL::describe).
+///
+/// The schema is stable: fields are only ever added, never removed, renamed or changed in
+/// meaning, so readers should ignore unknown fields. Fields added in later versions are optional
+/// and default to `None` when reading an older description. An incompatible change increments
+/// the [schema version](Self::schema_version). Timestamps are in milliseconds since the Unix
+/// epoch.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InstanceDescription {
+    /// Version of the schema of the description; see [DESCRIPTION_SCHEMA_VERSION]
+    pub schema_version: u32,
+    /// PostgreSQL version; e.g. `16.4.0`
+    pub version: String,
+    /// Status of the server
+    pub status: Status,
+    /// Installation directory of PostgreSQL
+    pub installation_dir: PathBuf,
+    /// Data directory of the server
+    pub data_dir: PathBuf,
+    /// Host clients connect to; see [effective_host](crate::Settings::effective_host)
+    pub host: String,
+    /// Port of the server
+    pub port: u16,
+    /// User clients connect as
+    pub username: String,
+    /// Password of the user; only included when
+    /// [describe_secrets](crate::Settings::describe_secrets) is set
+    pub password: Option<String>,
+    /// Whether the data directory is removed when the server is dropped
+    pub temporary: bool,
+    /// Process id of the postmaster; `None` when the server is not started
+    pub pid: Option<u32>,
+    /// Time the server was started; `None` when the server is not started
+    pub started_at: Option<u64>,
+    /// Time of the last successful backup taken by this instance
+    pub last_backup_at: Option<u64>,
+    /// Time the description was created
+    pub updated_at: u64,
+}
+
+/// Get the path the description of the server with the given data directory is written to; e.g.
+/// `/tmp/data.description.json` for `/tmp/data`
+pub(crate) fn description_path(data_dir: &Path) -> PathBuf {
+    data_dir_sibling(data_dir, ".description.json")
+}
+
+/// Get the number of milliseconds since the Unix epoch
+pub(crate) fn unix_millis(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_millis() as u64)
+}
+
+/// Read the process id and start time (in milliseconds since the Unix epoch) of the postmaster
+/// from the `postmaster.pid` file in the data directory
+pub(crate) fn read_postmaster(data_dir: &Path) -> Option<(u32, u64)> {
+    let contents = std::fs::read_to_string(data_dir.join("postmaster.pid")).ok()?;
+    let mut lines = contents.lines();
+    let pid = lines.next()?.trim().parse().ok()?;
+    let started_at = lines.nth(1)?.trim().parse::<u64>().ok()?;
+    Some((pid, started_at * 1000))
+}
+
+/// Write the description as JSON to the path. The description is written to a temporary file in
+/// the same directory, which is only accessible by the current user, and then renamed, so readers
+/// never observe a partially written description.
+#[cfg(feature = "serde")]
+pub(crate) fn write(description: &InstanceDescription, path: &Path) -> crate::Result<()> {
+    use crate::error::Error::DescriptionError;
+    use std::io::Write;
+
+    let directory = match path.parent() {
+        Some(directory) if !directory.as_os_str().is_empty() => directory,
+        _ => Path::new("."),
+    };
+    let json =
+        serde_json::to_vec_pretty(description).map_err(|error| DescriptionError(error.into()))?;
+    let mut file = tempfile::NamedTempFile::new_in(directory)?;
+    file.write_all(&json)?;
+    file.flush()?;
+    file.persist(path)
+        .map_err(|error| DescriptionError(error.into()))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn description() -> InstanceDescription {
+        InstanceDescription {
+            schema_version: DESCRIPTION_SCHEMA_VERSION,
+            version: "16.4.0".to_string(),
+            status: Status::Started,
+            installation_dir: PathBuf::from("/opt/postgresql/16.4.0"),
+            data_dir: PathBuf::from("/tmp/data"),
+            host: "127.0.0.1".to_string(),
+            port: 5432,
+            username: "postgres".to_string(),
+            password: None,
+            temporary: true,
+            pid: Some(42),
+            started_at: Some(1_700_000_000_000),
+            last_backup_at: None,
+            updated_at: 1_700_000_001_000,
+        }
+    }
+
+    #[test]
+    fn test_description_path() {
+        assert_eq!(
+            PathBuf::from("/tmp/data.description.json"),
+            description_path(Path::new("/tmp/data"))
+        );
+    }
+
+    #[test]
+    fn test_unix_millis() {
+        assert_eq!(0, unix_millis(UNIX_EPOCH));
+        assert_eq!(
+            1_500,
+            unix_millis(UNIX_EPOCH + std::time::Duration::from_millis(1_500))
+        );
+    }
+
+    #[test]
+    fn test_read_postmaster() -> crate::Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        assert_eq!(None, read_postmaster(temp_dir.path()));
+        std::fs::write(
+            temp_dir.path().join("postmaster.pid"),
+            "42\n/tmp/data\n1700000000\n5432\n/tmp\nlocalhost\n",
+        )?;
+        assert_eq!(
+            Some((42, 1_700_000_000_000)),
+            read_postmaster(temp_dir.path())
+        );
+        Ok(())
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serialize_description() -> anyhow::Result<()> {
+        let description = description();
+        let json = serde_json::to_string(&description)?;
+        assert_eq!(
+            r#"{"schema_version":1,"version":"16.4.0","status":"Started","installation_dir":"/opt/postgresql/16.4.0","data_dir":"/tmp/data","host":"127.0.0.1","port":5432,"username":"postgres","password":null,"temporary":true,"pid":42,"started_at":1700000000000,"last_backup_at":null,"updated_at":1700000001000}"#,
+            json
+        );
+        assert_eq!(description, serde_json::from_str(&json)?);
+        Ok(())
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_deserialize_unknown_fields() -> anyhow::Result<()> {
+        let json = serde_json::to_value(description())?;
+        let mut object = json.as_object().cloned().unwrap_or_default();
+        object.insert("added_later".to_string(), serde_json::Value::Bool(true));
+        let description: InstanceDescription =
+            serde_json::from_value(serde_json::Value::Object(object))?;
+        assert_eq!(super::tests::description(), description);
+        Ok(())
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_write() -> anyhow::Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let path = temp_dir.path().join("data.description.json");
+        write(&description(), &path)?;
+        let mut updated = description();
+        updated.status = Status::Stopped;
+        write(&updated, &path)?;
+
+        let json = std::fs::read_to_string(&path)?;
+        assert_eq!(updated, serde_json::from_str(&json)?);
+        assert_eq!(1, std::fs::read_dir(temp_dir.path())?.count());
+        Ok(())
+    }
+}
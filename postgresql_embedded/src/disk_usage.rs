@@ -0,0 +1,269 @@
+use crate::error::Error::DiskUsageError;
+use crate::error::Result;
+use crate::telemetry;
+use std::fs::read_dir;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, RecvTimeoutError, Sender};
+use std::thread::JoinHandle;
+use std::time::Duration;
+use tracing::{debug, warn};
+
+/// Maximum number of relations reported in [`DiskUsage::largest_relations`]
+pub(crate) const LARGEST_RELATIONS_LIMIT: usize = 10;
+
+/// Interval at which the [`LowDiskWatcher`] checks the available disk space
+pub(crate) const DISK_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Disk space consumed by a PostgreSQL server; see [disk_usage](crate::PostgreSQL::disk_usage)
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DiskUsage {
+    /// Size of the installation directory in bytes
+    pub installation_bytes: u64,
+    /// Size of the data directory in bytes; includes the write-ahead log unless `pg_wal` is a
+    /// symbolic link to another location
+    pub data_dir_bytes: u64,
+    /// Size of the write-ahead log directory (`pg_wal`) in bytes
+    pub wal_bytes: u64,
+    /// Size of each database in bytes, largest first
+    pub per_database: Vec<(String, u64)>,
+    /// Largest user relations across all databases, largest first; the size includes indexes
+    /// and TOAST data
+    pub largest_relations: Vec<RelationSize>,
+}
+
+/// Size of a relation (table or materialized view)
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RelationSize {
+    /// Database containing the relation
+    pub database: String,
+    /// Schema containing the relation
+    pub schema: String,
+    /// Name of the relation
+    pub name: String,
+    /// Total size of the relation in bytes, including indexes and TOAST data
+    pub bytes: u64,
+}
+
+/// Event fired by a [`LowDiskWatcher`] when the available disk space drops below the threshold
+#[derive(Clone, Debug, PartialEq)]
+pub struct LowDiskEvent {
+    /// Path whose filesystem is being watched
+    pub path: PathBuf,
+    /// Available disk space in bytes
+    pub available_bytes: u64,
+    /// Threshold in bytes
+    pub threshold_bytes: u64,
+}
+
+/// Watches the available disk space of a filesystem on a background thread; see
+/// [low_disk_watcher](crate::PostgreSQL::low_disk_watcher). The watcher stops when dropped.
+#[derive(Debug)]
+pub struct LowDiskWatcher {
+    stop: Option<Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl LowDiskWatcher {
+    /// Start watching the filesystem of the path. The available space is checked immediately
+    /// and then every `interval`; the callback is invoked when the available space drops below
+    /// the threshold, and again only after the available space has recovered above it.
+    pub(crate) fn start<F>(
+        path: PathBuf,
+        threshold_bytes: u64,
+        interval: Duration,
+        callback: F,
+    ) -> Self
+    where
+        F: Fn(LowDiskEvent) + Send + 'static,
+    {
+        let (stop, stopped) = channel::<()>();
+        let span = telemetry::background_span("postgresql.low_disk_watcher");
+        let thread = std::thread::spawn(move || {
+            let _span = span.entered();
+            let mut below_threshold = false;
+            loop {
+                match available_space(&path) {
+                    Ok(available_bytes) if available_bytes < threshold_bytes => {
+                        if !below_threshold {
+                            debug!("Low disk space: {available_bytes} bytes available");
+                            callback(LowDiskEvent {
+                                path: path.clone(),
+                                available_bytes,
+                                threshold_bytes,
+                            });
+                        }
+                        below_threshold = true;
+                    }
+                    Ok(_) => below_threshold = false,
+                    Err(error) => warn!("Unable to determine available disk space: {error}"),
+                }
+
+                match stopped.recv_timeout(interval) {
+                    Err(RecvTimeoutError::Timeout) => {}
+                    _ => break,
+                }
+            }
+        });
+
+        Self {
+            stop: Some(stop),
+            thread: Some(thread),
+        }
+    }
+}
+
+impl Drop for LowDiskWatcher {
+    fn drop(&mut self) {
+        // Dropping the sender wakes the watcher thread, which then exits
+        drop(self.stop.take());
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Get the total size in bytes of the regular files in the directory and its subdirectories;
+/// symbolic links are not followed. Files and directories that disappear during the walk (e.g.
+/// WAL segments that are recycled by the server) are ignored.
+pub(crate) fn directory_size(path: &Path) -> Result<u64> {
+    let entries = match read_dir(path) {
+        Ok(entries) => entries,
+        Err(error) if error.kind() == ErrorKind::NotFound => return Ok(0),
+        Err(error) => return Err(error.into()),
+    };
+
+    let mut size = 0;
+    for entry in entries {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(error) if error.kind() == ErrorKind::NotFound => continue,
+            Err(error) => return Err(error.into()),
+        };
+        let file_type = match entry.file_type() {
+            Ok(file_type) => file_type,
+            Err(error) if error.kind() == ErrorKind::NotFound => continue,
+            Err(error) => return Err(error.into()),
+        };
+
+        if file_type.is_dir() {
+            size += directory_size(&entry.path())?;
+        } else if file_type.is_file() {
+            match entry.metadata() {
+                Ok(metadata) => size += metadata.len(),
+                Err(error) if error.kind() == ErrorKind::NotFound => {}
+                Err(error) => return Err(error.into()),
+            }
+        }
+    }
+    Ok(size)
+}
+
+/// Get the available disk space in bytes on the filesystem containing the path; the space
+/// available to unprivileged users is reported
+#[cfg(unix)]
+pub(crate) fn available_space(path: &Path) -> Result<u64> {
+    let statvfs = rustix::fs::statvfs(path).map_err(|error| {
+        DiskUsageError(anyhow::anyhow!(
+            "Failed to determine available disk space for {}: {error}",
+            path.to_string_lossy()
+        ))
+    })?;
+    Ok(statvfs.f_bavail.saturating_mul(statvfs.f_frsize))
+}
+
+/// Get the available disk space in bytes on the drive containing the path
+#[cfg(not(unix))]
+pub(crate) fn available_space(path: &Path) -> Result<u64> {
+    let output = std::process::Command::new("powershell")
+        .args(["-NoProfile", "-Command"])
+        .arg(format!(
+            "(Get-Item -LiteralPath '{}').PSDrive.Free",
+            path.to_string_lossy().replace('\'', "''")
+        ))
+        .output()?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    match stdout.trim().parse::<u64>().ok() {
+        Some(available) if output.status.success() => Ok(available),
+        _ => Err(DiskUsageError(anyhow::anyhow!(
+            "Failed to determine available disk space for {}: {}",
+            path.to_string_lossy(),
+            String::from_utf8_lossy(&output.stderr)
+        ))),
+    }
+}
+
+/// Merge the relations of multiple databases, keeping the largest relations
+pub(crate) fn largest_relations(mut relations: Vec<RelationSize>) -> Vec<RelationSize> {
+    relations.sort_by_key(|relation| std::cmp::Reverse(relation.bytes));
+    relations.truncate(LARGEST_RELATIONS_LIMIT);
+    relations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{create_dir_all, write};
+
+    #[test]
+    fn test_directory_size() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        write(temp_dir.path().join("a"), [0u8; 100])?;
+        create_dir_all(temp_dir.path().join("b").join("c"))?;
+        write(temp_dir.path().join("b").join("c").join("d"), [0u8; 50])?;
+
+        assert_eq!(150, directory_size(temp_dir.path())?);
+        assert_eq!(0, directory_size(&temp_dir.path().join("missing"))?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_available_space() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        assert!(available_space(temp_dir.path())? > 0);
+        assert!(available_space(&temp_dir.path().join("missing")).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_largest_relations() {
+        let relations = (0..20)
+            .map(|bytes| RelationSize {
+                name: format!("relation_{bytes}"),
+                bytes,
+                ..Default::default()
+            })
+            .collect();
+        let relations = largest_relations(relations);
+        assert_eq!(LARGEST_RELATIONS_LIMIT, relations.len());
+        assert_eq!(19, relations[0].bytes);
+        assert_eq!(10, relations[9].bytes);
+    }
+
+    #[test]
+    fn test_low_disk_watcher() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let (sender, receiver) = channel();
+        let watcher = LowDiskWatcher::start(
+            temp_dir.path().to_path_buf(),
+            u64::MAX,
+            Duration::from_millis(10),
+            move |event| {
+                let _ = sender.send(event);
+            },
+        );
+
+        let event = receiver
+            .recv_timeout(Duration::from_secs(10))
+            .map_err(|error| DiskUsageError(error.into()))?;
+        assert_eq!(temp_dir.path(), event.path);
+        assert_eq!(u64::MAX, event.threshold_bytes);
+        assert!(event.available_bytes < u64::MAX);
+
+        // The event is fired once while the available space remains below the threshold
+        assert!(receiver.recv_timeout(Duration::from_millis(100)).is_err());
+        drop(watcher);
+        Ok(())
+    }
+}
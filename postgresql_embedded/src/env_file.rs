@@ -0,0 +1,295 @@
+use crate::error::Result;
+use crate::settings::Settings;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Default service name used for [`EnvFileFormat::PgServiceConf`] files
+pub const DEFAULT_SERVICE_NAME: &str = "postgresql_embedded";
+
+/// Format of a generated connection environment file
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+pub enum EnvFileFormat {
+    /// `.env` file syntax; e.g. `PGHOST="localhost"`
+    DotEnv,
+    /// POSIX shell syntax; e.g. `export PGHOST='localhost'`
+    Shell,
+    /// [pg_service.conf](https://www.postgresql.org/docs/current/libpq-pgservice.html) stanza
+    PgServiceConf,
+}
+
+/// Connection environment file for attaching external client tools (e.g. psql, GUI clients or
+/// shell scripts) to the PostgreSQL server. The file contains the host, port, user and database
+/// and, only when explicitly requested, the password.
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+pub struct EnvFile {
+    path: PathBuf,
+    format: EnvFileFormat,
+    database_name: String,
+    service_name: String,
+    include_password: bool,
+}
+
+impl EnvFile {
+    /// Create a new [`EnvFile`] written to the given path in the given format
+    pub fn new<P: Into<PathBuf>>(path: P, format: EnvFileFormat) -> Self {
+        Self {
+            path: path.into(),
+            format,
+            database_name: "postgres".to_string(),
+            service_name: DEFAULT_SERVICE_NAME.to_string(),
+            include_password: false,
+        }
+    }
+
+    /// Set the database name; defaults to `postgres`
+    pub fn database_name<S: AsRef<str>>(mut self, database_name: S) -> Self {
+        self.database_name = database_name.as_ref().to_string();
+        self
+    }
+
+    /// Set the service name used for [`EnvFileFormat::PgServiceConf`] files
+    pub fn service_name<S: AsRef<str>>(mut self, service_name: S) -> Self {
+        self.service_name = service_name.as_ref().to_string();
+        self
+    }
+
+    /// Include the password in the file; the password is omitted by default
+    pub fn include_password(mut self) -> Self {
+        self.include_password = true;
+        self
+    }
+
+    /// Get the path of the file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Get the format of the file
+    pub fn format(&self) -> EnvFileFormat {
+        self.format
+    }
+
+    /// Render the contents of the file for the given settings
+    pub fn render(&self, settings: &Settings) -> String {
+        let port = settings.port.to_string();
+        let mut variables = vec![
+            ("PGHOST", "host", settings.effective_host()),
+            ("PGPORT", "port", port.as_str()),
+            ("PGUSER", "user", settings.username.as_str()),
+            ("PGDATABASE", "dbname", self.database_name.as_str()),
+        ];
+        if self.include_password {
+            variables.push(("PGPASSWORD", "password", settings.password.as_str()));
+        }
+
+        let mut contents = String::new();
+        if self.format == EnvFileFormat::PgServiceConf {
+            contents.push_str(&format!("[{}]\n", self.service_name));
+        }
+        for (variable, keyword, value) in variables {
+            let line = match self.format {
+                EnvFileFormat::DotEnv => format!("{variable}={}\n", double_quote(value)),
+                EnvFileFormat::Shell => format!("export {variable}={}\n", single_quote(value)),
+                EnvFileFormat::PgServiceConf => format!("{keyword}={value}\n"),
+            };
+            contents.push_str(&line);
+        }
+        contents
+    }
+
+    /// Write the file for the given settings. The contents are written to a temporary file in
+    /// the same directory, which is only accessible by the current user from the moment it is
+    /// created, and then renamed, so readers never observe a partially written file. On Unix,
+    /// the file stays only readable by the owner when the password is included.
+    pub fn write(&self, settings: &Settings) -> Result<()> {
+        let directory = match self.path.parent() {
+            Some(directory) if !directory.as_os_str().is_empty() => directory,
+            _ => Path::new("."),
+        };
+        fs::create_dir_all(directory)?;
+        let mut file = tempfile::NamedTempFile::new_in(directory)?;
+        file.write_all(self.render(settings).as_bytes())?;
+        file.flush()?;
+
+        #[cfg(unix)]
+        if !self.include_password {
+            use std::os::unix::fs::PermissionsExt;
+            file.as_file()
+                .set_permissions(fs::Permissions::from_mode(0o644))?;
+        }
+
+        file.persist(&self.path).map_err(|error| error.error)?;
+        Ok(())
+    }
+}
+
+/// Replace the section with the given name in the contents of a pg_service.conf file with the
+/// new section, or append the new section when there is none. The other sections, and any
+/// comments outside of the replaced section, are kept as they are.
+pub(crate) fn merge_service_section(contents: &str, name: &str, section: &str) -> String {
+    let header = format!("[{name}]");
+    let is_header = |line: &str| {
+        let line = line.trim();
+        line.starts_with('[') && line.ends_with(']')
+    };
+
+    let mut merged = String::new();
+    let mut replaced = false;
+    let mut in_section = false;
+    for line in contents.lines() {
+        if is_header(line) {
+            in_section = line.trim() == header;
+            if in_section && !replaced {
+                merged.push_str(section);
+                replaced = true;
+            }
+        }
+        // Blank lines separating the replaced section from the next one are kept
+        if !in_section || line.trim().is_empty() {
+            merged.push_str(line);
+            merged.push('\n');
+        }
+    }
+    if !replaced {
+        if !merged.is_empty() && !merged.ends_with("\n\n") {
+            merged.push('\n');
+        }
+        merged.push_str(section);
+    }
+    merged
+}
+
+/// Quote a value for a `.env` file
+fn double_quote(value: &str) -> String {
+    let escaped = value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('$', "\\$")
+        .replace('\n', "\\n");
+    format!("\"{escaped}\"")
+}
+
+/// Quote a value for a POSIX shell
+fn single_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_log::test;
+
+    fn settings() -> Settings {
+        Settings {
+            host: "localhost".to_string(),
+            port: 5432,
+            username: "postgres".to_string(),
+            password: "pa$s'word".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_dot_env() {
+        let env_file = EnvFile::new(".env", EnvFileFormat::DotEnv).database_name("test");
+        assert_eq!(
+            "PGHOST=\"127.0.0.1\"\nPGPORT=\"5432\"\nPGUSER=\"postgres\"\nPGDATABASE=\"test\"\n",
+            env_file.render(&settings())
+        );
+
+        let env_file = env_file.include_password();
+        assert!(env_file
+            .render(&settings())
+            .ends_with("PGPASSWORD=\"pa\\$s'word\"\n"));
+    }
+
+    #[test]
+    fn test_shell() {
+        let env_file = EnvFile::new("env.sh", EnvFileFormat::Shell);
+        assert_eq!(
+            "export PGHOST='127.0.0.1'\nexport PGPORT='5432'\nexport PGUSER='postgres'\nexport PGDATABASE='postgres'\n",
+            env_file.render(&settings())
+        );
+
+        let env_file = env_file.include_password();
+        assert!(env_file
+            .render(&settings())
+            .ends_with("export PGPASSWORD='pa$s'\\''word'\n"));
+    }
+
+    #[test]
+    fn test_pg_service_conf() {
+        let env_file = EnvFile::new("pg_service.conf", EnvFileFormat::PgServiceConf)
+            .service_name("test_service")
+            .database_name("test");
+        assert_eq!(
+            "[test_service]\nhost=127.0.0.1\nport=5432\nuser=postgres\ndbname=test\n",
+            env_file.render(&settings())
+        );
+
+        let env_file = env_file.include_password();
+        assert!(env_file
+            .render(&settings())
+            .ends_with("password=pa$s'word\n"));
+    }
+
+    #[test]
+    fn test_merge_service_section() {
+        let section = "[test]\nhost=127.0.0.1\nport=5432\n";
+        assert_eq!(section, merge_service_section("", "test", section));
+
+        let contents = "# services\n[other]\nhost=db\n";
+        assert_eq!(
+            "# services\n[other]\nhost=db\n\n[test]\nhost=127.0.0.1\nport=5432\n",
+            merge_service_section(contents, "test", section)
+        );
+
+        let contents = "[test]\nhost=old\nport=1\n\n[other]\nhost=db\n";
+        assert_eq!(
+            "[test]\nhost=127.0.0.1\nport=5432\n\n[other]\nhost=db\n",
+            merge_service_section(contents, "test", section)
+        );
+    }
+
+    #[test]
+    fn test_password_excluded_by_default() {
+        for format in [
+            EnvFileFormat::DotEnv,
+            EnvFileFormat::Shell,
+            EnvFileFormat::PgServiceConf,
+        ] {
+            let env_file = EnvFile::new("file", format);
+            assert!(!env_file.render(&settings()).contains("pa$s"));
+        }
+    }
+
+    #[test]
+    fn test_write() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let path = temp_dir.path().join("connection.env");
+        let env_file = EnvFile::new(&path, EnvFileFormat::DotEnv).include_password();
+        env_file.write(&settings())?;
+
+        assert_eq!(env_file.render(&settings()), fs::read_to_string(&path)?);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path)?.permissions().mode();
+            assert_eq!(0o600, mode & 0o777);
+        }
+
+        // The file is replaced, and without the password it is readable by others
+        let env_file = EnvFile::new(&path, EnvFileFormat::Shell);
+        env_file.write(&settings())?;
+        assert_eq!(env_file.render(&settings()), fs::read_to_string(&path)?);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path)?.permissions().mode();
+            assert_eq!(0o644, mode & 0o777);
+        }
+        assert_eq!(1, fs::read_dir(temp_dir.path())?.count());
+        Ok(())
+    }
+}
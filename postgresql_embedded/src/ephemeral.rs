@@ -0,0 +1,170 @@
+//! One-liner throwaway databases for quick scripts, examples and doctests; see [ephemeral]
+
+use crate::batch::QueryResult;
+use crate::error::Error::{ArchiveError, EphemeralError};
+use crate::error::{Error, Result};
+use crate::postgresql::PostgreSQL;
+use crate::settings::Settings;
+use postgresql_archive::TARGET;
+use tracing::debug;
+
+/// Name of the database created for an [EphemeralDatabase]
+pub const EPHEMERAL_DATABASE: &str = "ephemeral";
+
+/// Database started by [ephemeral] or [ephemeral_with]. The server is temporary: it is stopped
+/// and its data directory removed when the value is dropped, or by [close](Self::close).
+#[derive(Debug)]
+pub struct EphemeralDatabase {
+    postgresql: PostgreSQL,
+    database_name: String,
+    url: String,
+}
+
+impl EphemeralDatabase {
+    /// Get the connection URL of the database
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Get the name of the database; [`EPHEMERAL_DATABASE`]
+    pub fn database_name(&self) -> &str {
+        &self.database_name
+    }
+
+    /// Get the server, for everything beyond running SQL in the database
+    pub fn postgresql(&self) -> &PostgreSQL {
+        &self.postgresql
+    }
+
+    /// Run SQL statements in the database and return the rows of the last statement; see
+    /// [run_sql](PostgreSQL::run_sql)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a statement fails.
+    pub async fn run_sql<Q: AsRef<str>>(&self, sql: Q) -> Result<QueryResult> {
+        self.postgresql.run_sql(&self.database_name, sql).await
+    }
+
+    /// Stop the server and remove its data directory. Dropping the database does the same, but
+    /// any error stopping the server is ignored.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the server cannot be stopped; the data directory is removed
+    /// regardless.
+    pub async fn close(self) -> Result<()> {
+        self.postgresql.stop().await
+    }
+}
+
+/// Start a throwaway database with settings suited to scripts and CI and no configuration: a
+/// free port, a temporary data directory and `fsync` turned off. PostgreSQL is installed on
+/// the first use and reused afterwards.
+///
+#[cfg_attr(feature = "server-doctests", doc = "```")]
+#[cfg_attr(not(feature = "server-doctests"), doc = "```no_run")]
+/// # #[tokio::main]
+/// # async fn main() -> postgresql_embedded::Result<()> {
+/// let db = postgresql_embedded::ephemeral().await?;
+/// db.run_sql("CREATE TABLE users (id int)").await?;
+/// let result = db.run_sql("SELECT count(*) FROM users").await?;
+/// assert_eq!(vec![vec!["0".to_string()]], result.rows);
+/// assert!(db.url().ends_with("/ephemeral"));
+/// db.close().await
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// Returns an error if PostgreSQL cannot be installed or started, e.g. when no PostgreSQL
+/// binaries are available for the platform.
+pub async fn ephemeral() -> Result<EphemeralDatabase> {
+    ephemeral_with(|_| {}).await
+}
+
+/// Start a throwaway database like [ephemeral], after the closure changed the settings; e.g.
+/// to select the version or set a configuration parameter.
+///
+#[cfg_attr(feature = "server-doctests", doc = "```")]
+#[cfg_attr(not(feature = "server-doctests"), doc = "```no_run")]
+/// # #[tokio::main]
+/// # async fn main() -> postgresql_embedded::Result<()> {
+/// let db = postgresql_embedded::ephemeral_with(|settings| {
+///     settings.max_connections = Some(20);
+/// })
+/// .await?;
+/// let result = db.run_sql("SHOW max_connections").await?;
+/// assert_eq!(vec![vec!["20".to_string()]], result.rows);
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// Returns an error if PostgreSQL cannot be installed or started, e.g. when no PostgreSQL
+/// binaries are available for the platform.
+pub async fn ephemeral_with<F>(configure: F) -> Result<EphemeralDatabase>
+where
+    F: FnOnce(&mut Settings),
+{
+    let mut settings = Settings::default().data_dir_prefix(EPHEMERAL_DATABASE);
+    settings.fsync = Some(false);
+    configure(&mut settings);
+
+    let mut postgresql = PostgreSQL::from(settings);
+    postgresql.setup().await.map_err(describe_setup_error)?;
+    postgresql.start().await?;
+    postgresql.create_database(EPHEMERAL_DATABASE).await?;
+    let url = postgresql.settings().url(EPHEMERAL_DATABASE);
+    debug!(
+        "Started ephemeral database in {}",
+        postgresql.settings().data_dir.to_string_lossy()
+    );
+    Ok(EphemeralDatabase {
+        postgresql,
+        database_name: EPHEMERAL_DATABASE.to_string(),
+        url,
+    })
+}
+
+/// Explain a setup error caused by the lack of PostgreSQL binaries for the platform; other
+/// errors are returned unchanged
+fn describe_setup_error(error: Error) -> Error {
+    match error {
+        ArchiveError(postgresql_archive::Error::AssetNotFound(asset)) => {
+            EphemeralError(anyhow::anyhow!(
+                "No PostgreSQL binaries are available for this platform ({TARGET}): {asset} is \
+                 not published; select a version that is published for the platform with \
+                 Settings::version, or point Settings::installation_dir at an existing \
+                 installation"
+            ))
+        }
+        error => error,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_describe_setup_error() {
+        let asset = format!("postgresql-16.4.0-{TARGET}.tar.gz");
+        let error = describe_setup_error(ArchiveError(postgresql_archive::Error::AssetNotFound(
+            asset.clone(),
+        )));
+        assert!(matches!(error, EphemeralError(_)));
+        let message = error.to_string();
+        assert!(message.contains(TARGET));
+        assert!(message.contains(&asset));
+
+        let error = describe_setup_error(ArchiveError(postgresql_archive::Error::ReleaseNotFound(
+            "16".to_string(),
+        )));
+        assert!(matches!(
+            error,
+            ArchiveError(postgresql_archive::Error::ReleaseNotFound(_))
+        ));
+    }
+}
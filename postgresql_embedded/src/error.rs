@@ -6,12 +6,39 @@ pub type Result<T, E = Error> = core::result::Result<T, E>;
 /// Errors that can occur when using PostgreSQL embedded
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
+    /// Error when the tables of a database could not be analyzed
+    #[error(transparent)]
+    AnalyzeError(anyhow::Error),
     /// Error when PostgreSQL archive operations fail
     #[error(transparent)]
     ArchiveError(postgresql_archive::Error),
+    /// Error when background activity could not be quiesced or restored
+    #[error(transparent)]
+    BackgroundActivityError(anyhow::Error),
+    /// Error when a database could not be backed up
+    #[error(transparent)]
+    BackupError(anyhow::Error),
+    /// Error when an operation is cancelled
+    #[error(transparent)]
+    CancelledError(anyhow::Error),
+    /// Error when a pgbench benchmark fails
+    #[error(transparent)]
+    BenchmarkError(anyhow::Error),
+    /// Error when database objects could not be listed or described
+    #[error(transparent)]
+    CatalogError(anyhow::Error),
+    /// Error when a checkpoint could not be completed
+    #[error(transparent)]
+    CheckpointError(anyhow::Error),
+    /// Error when the compile-time parameters of a cluster could not be determined
+    #[error(transparent)]
+    ClusterParamsError(anyhow::Error),
     /// Error when a command fails
     #[error("Command error: stdout={stdout}; stderr={stderr}")]
     CommandError { stdout: String, stderr: String },
+    /// Error when the server could not be crashed or recovered
+    #[error(transparent)]
+    CrashError(anyhow::Error),
     /// Error when the database could not be created
     #[error(transparent)]
     CreateDatabaseError(anyhow::Error),
@@ -27,15 +54,100 @@ pub enum Error {
     /// Error when the database could not be stopped
     #[error(transparent)]
     DatabaseStopError(anyhow::Error),
+    /// Error when the description of the server could not be written
+    #[error(transparent)]
+    DescriptionError(anyhow::Error),
+    /// Error when the disk usage could not be determined
+    #[error(transparent)]
+    DiskUsageError(anyhow::Error),
     /// Error when the database could not be dropped
     #[error(transparent)]
     DropDatabaseError(anyhow::Error),
+    /// Error when an [ephemeral](crate::ephemeral) database could not be started
+    #[error(transparent)]
+    EphemeralError(anyhow::Error),
+    /// Error when a configuration parameter could not be read, changed or has an unexpected
+    /// value
+    #[error(transparent)]
+    GucError(anyhow::Error),
     /// Error when an invalid URL is provided
     #[error("Invalid URL: {url}; {message}")]
     InvalidUrl { url: String, message: String },
     /// Error when IO operations fail
     #[error(transparent)]
     IoError(anyhow::Error),
+    /// Error when the addresses the server listens on could not be determined
+    #[error(transparent)]
+    ListenerError(anyhow::Error),
+    /// Error when prepared transactions could not be listed or rolled back
+    #[error(transparent)]
+    PreparedTransactionError(anyhow::Error),
+    /// Error when a stopped server could not be confirmed to have fully exited
+    #[error(transparent)]
+    QuiesceError(anyhow::Error),
+    /// Error when the readiness of the server could not be determined
+    #[error(transparent)]
+    ReadinessError(anyhow::Error),
+    /// Error when a replication slot could not be managed
+    #[error(transparent)]
+    ReplicationSlotError(anyhow::Error),
+    /// Error when the data of a database could not be reset
+    #[error(transparent)]
+    ResetError(anyhow::Error),
+    /// Error when a database could not be restored
+    #[error(transparent)]
+    RestoreError(anyhow::Error),
+    /// Error when a server could not be rewound
+    #[error(transparent)]
+    RewindError(anyhow::Error),
+    /// Error when roles could not be managed
+    #[error(transparent)]
+    RoleError(anyhow::Error),
+    /// Error when the seed could not be applied
+    #[error(transparent)]
+    SeedError(anyhow::Error),
+    /// Error when sessions could not be terminated
+    #[error(transparent)]
+    SessionError(anyhow::Error),
+    /// Error when the server could not be started because its Unix-domain socket or the socket
+    /// lock file is in use by another server; see
+    /// [socket_path](crate::PostgreSQL::socket_path)
+    #[error(transparent)]
+    SocketConflictError(anyhow::Error),
+    /// Error when the Unix-domain socket path is longer than the platform limit; see
+    /// [socket_dir](crate::Settings::socket_dir)
+    #[error("{0}")]
+    SocketPathError(String),
+    /// Error when a SQL statement fails
+    #[error(transparent)]
+    SqlError(anyhow::Error),
+    /// Error when a tablespace could not be created, dropped or listed
+    #[error(transparent)]
+    TablespaceError(anyhow::Error),
+    /// Error when the server could not be started because its TCP/IP port is in use by another
+    /// listener
+    #[error(transparent)]
+    TcpPortConflictError(anyhow::Error),
+    /// Error when a migrated template database could not be prepared
+    #[error(transparent)]
+    TemplateError(anyhow::Error),
+    /// Error when a program is not present in the installation; see
+    /// [require_tool](crate::PostgreSQL::require_tool)
+    #[error("{0}")]
+    ToolNotFoundError(String),
+    /// Error when a destructive operation is refused because the connection does not reach the
+    /// server managed by this instance; see [managed_only](crate::Settings::managed_only)
+    #[error(transparent)]
+    UnmanagedServerError(anyhow::Error),
+    /// Error when a table could not be vacuumed
+    #[error(transparent)]
+    VacuumError(anyhow::Error),
+    /// Error when a connection could not be verified
+    #[error(transparent)]
+    VerifyError(anyhow::Error),
+    /// Error when the write-ahead log usage could not be determined
+    #[error(transparent)]
+    WalError(anyhow::Error),
 }
 
 /// Convert PostgreSQL [archive errors](postgresql_archive::Error) to an [embedded errors](Error::ArchiveError)
@@ -74,7 +186,7 @@ mod test {
 
     #[test]
     fn test_from_io_error() {
-        let io_error = std::io::Error::new(std::io::ErrorKind::Other, "test");
+        let io_error = std::io::Error::other("test");
         let error = Error::from(io_error);
         assert_eq!(error.to_string(), "test");
     }
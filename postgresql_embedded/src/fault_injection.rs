@@ -0,0 +1,251 @@
+//! TCP proxy that injects faults between clients and the server, to test how an application
+//! copes with latency, slow links and dropped connections without an external tool such as
+//! toxiproxy; see [fault_injecting_proxy](crate::PostgreSQL::fault_injecting_proxy)
+
+use crate::connection_info::ConnectionInfo;
+use crate::error::Result;
+use crate::proxy::{lock, Connection, Listener};
+use crate::settings::Settings;
+use rand::Rng;
+use std::io::{Read, Write};
+use std::net::Shutdown;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::debug;
+
+/// Maximum number of bytes forwarded at once
+const BUFFER_SIZE: usize = 16 * 1024;
+
+/// Faults applied to the traffic of the proxied connections
+#[derive(Clone, Copy, Debug, Default)]
+struct Faults {
+    latency: Duration,
+    jitter: Duration,
+    bytes_per_sec: Option<u64>,
+}
+
+impl Faults {
+    /// Delay before forwarding a chunk of traffic; the latency plus a random jitter
+    fn delay(&self) -> Duration {
+        if self.jitter.is_zero() {
+            self.latency
+        } else {
+            self.latency + rand::thread_rng().gen_range(Duration::ZERO..=self.jitter)
+        }
+    }
+
+    /// Maximum number of bytes to forward at once, so that a bandwidth limit is applied
+    /// smoothly
+    fn chunk_size(&self) -> usize {
+        self.bytes_per_sec.map_or(BUFFER_SIZE, |bytes_per_sec| {
+            usize::try_from(bytes_per_sec).map_or(BUFFER_SIZE, |bytes| bytes.clamp(1, BUFFER_SIZE))
+        })
+    }
+
+    /// Time needed to transfer the bytes within the bandwidth limit
+    fn transfer_time(&self, bytes: usize) -> Duration {
+        match self.bytes_per_sec {
+            Some(bytes_per_sec) if bytes_per_sec > 0 => {
+                Duration::from_secs_f64(bytes as f64 / bytes_per_sec as f64)
+            }
+            _ => Duration::ZERO,
+        }
+    }
+}
+
+/// State shared by the proxy handle and its threads
+#[derive(Debug, Default)]
+struct State {
+    faults: Mutex<Faults>,
+    drop_new_connections: AtomicBool,
+}
+
+/// TCP proxy that forwards client connections to a PostgreSQL server and injects faults into
+/// the traffic. The faults are applied independently to each direction of a connection
+/// (client to server and server to client) and can be changed while connections are open.
+/// The proxy stops and closes the proxied connections when dropped.
+#[derive(Debug)]
+pub struct FaultInjectingProxy {
+    settings: Settings,
+    connection_info: ConnectionInfo,
+    state: Arc<State>,
+    listener: Listener,
+}
+
+impl FaultInjectingProxy {
+    /// Start a proxy for the server on a free port of the server host
+    pub(crate) fn start(settings: &Settings) -> Result<Self> {
+        let state = Arc::new(State::default());
+        let listener = {
+            let state = state.clone();
+            Listener::start(
+                settings,
+                "postgresql.fault_injecting_proxy",
+                move |accepted| {
+                    if state.drop_new_connections.load(Ordering::SeqCst) {
+                        debug!("Dropping new connection");
+                        return accepted.client.shutdown(Shutdown::Both);
+                    }
+                    proxy_connection(accepted.connect()?, &state)
+                },
+            )?
+        };
+        let settings = Settings {
+            port: listener.address().port(),
+            ..settings.clone()
+        };
+        let connection_info = ConnectionInfo::from(&settings);
+        debug!("Fault injecting proxy listening on {}", listener.address());
+
+        Ok(Self {
+            settings,
+            connection_info,
+            state,
+            listener,
+        })
+    }
+
+    /// Settings of the server with the port of the proxy, for code that connects with
+    /// [Settings]; clients connect to the proxy in place of the server
+    pub fn settings(&self) -> &Settings {
+        &self.settings
+    }
+
+    /// Endpoint of the proxy; clients connect to the proxy in place of the server
+    pub fn connection_info(&self) -> &ConnectionInfo {
+        &self.connection_info
+    }
+
+    /// Return the URL of the proxy for the given database name.
+    pub fn url<S: AsRef<str>>(&self, database_name: S) -> String {
+        self.connection_info.url(database_name)
+    }
+
+    /// Delay the traffic in each direction by the latency plus a random jitter of up to
+    /// `jitter`; a query therefore takes at least twice the latency longer. Use
+    /// [Duration::ZERO] for both to remove the delay.
+    pub fn set_latency(&self, latency: Duration, jitter: Duration) {
+        debug!("Setting latency to {latency:?} with jitter {jitter:?}");
+        let mut faults = lock(&self.state.faults);
+        faults.latency = latency;
+        faults.jitter = jitter;
+    }
+
+    /// Close new client connections right after accepting them, as a server that is
+    /// unreachable would; open connections are not affected.
+    pub fn drop_new_connections(&self, drop: bool) {
+        debug!("Dropping new connections: {drop}");
+        self.state
+            .drop_new_connections
+            .store(drop, Ordering::SeqCst);
+    }
+
+    /// Close all open connections; clients see the connection closed unexpectedly, e.g. in the
+    /// middle of a transaction. New connections are accepted as usual.
+    pub fn reset_existing_connections(&self) {
+        let connections = self.listener.close_connections();
+        debug!("Reset {connections} connections");
+    }
+
+    /// Limit the bandwidth in each direction to the number of bytes per second; `None` removes
+    /// the limit.
+    pub fn limit_bandwidth(&self, bytes_per_sec: Option<u64>) {
+        debug!("Limiting bandwidth to {bytes_per_sec:?} bytes per second");
+        lock(&self.state.faults).bytes_per_sec = bytes_per_sec;
+    }
+}
+
+/// Forward a client connection to the server on background threads, one per direction
+fn proxy_connection(connection: Connection, state: &Arc<State>) -> std::io::Result<()> {
+    let forward_with_faults = |state: Arc<State>| {
+        move |from: &mut std::net::TcpStream, to: &mut std::net::TcpStream| {
+            if let Err(error) = forward(from, to, &state) {
+                debug!("Connection closed: {error}");
+            }
+        }
+    };
+    connection.forward(
+        forward_with_faults(state.clone()),
+        forward_with_faults(state.clone()),
+    )
+}
+
+/// Copy the traffic from one stream to the other until the end of the stream, applying the
+/// current faults to each chunk
+fn forward<R, W>(from: &mut R, to: &mut W, state: &State) -> std::io::Result<()>
+where
+    R: Read,
+    W: Write,
+{
+    let mut buffer = vec![0u8; BUFFER_SIZE];
+    loop {
+        let faults = *lock(&state.faults);
+        let length = from.read(&mut buffer[..faults.chunk_size()])?;
+        if length == 0 {
+            return Ok(());
+        }
+        std::thread::sleep(faults.delay());
+        to.write_all(&buffer[..length])?;
+        std::thread::sleep(faults.transfer_time(length));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use std::time::Instant;
+
+    #[test]
+    fn test_faults_default() {
+        let faults = Faults::default();
+        assert_eq!(Duration::ZERO, faults.delay());
+        assert_eq!(BUFFER_SIZE, faults.chunk_size());
+        assert_eq!(Duration::ZERO, faults.transfer_time(BUFFER_SIZE));
+    }
+
+    #[test]
+    fn test_faults_delay() {
+        let faults = Faults {
+            latency: Duration::from_millis(10),
+            jitter: Duration::from_millis(5),
+            ..Default::default()
+        };
+        for _ in 0..100 {
+            let delay = faults.delay();
+            assert!(delay >= Duration::from_millis(10));
+            assert!(delay <= Duration::from_millis(15));
+        }
+    }
+
+    #[test]
+    fn test_faults_bandwidth() {
+        let faults = Faults {
+            bytes_per_sec: Some(1000),
+            ..Default::default()
+        };
+        assert_eq!(1000, faults.chunk_size());
+        assert_eq!(Duration::from_millis(500), faults.transfer_time(500));
+        let faults = Faults {
+            bytes_per_sec: Some(1_000_000),
+            ..Default::default()
+        };
+        assert_eq!(BUFFER_SIZE, faults.chunk_size());
+    }
+
+    #[test]
+    fn test_forward() -> std::io::Result<()> {
+        let state = State::default();
+        lock(&state.faults).latency = Duration::from_millis(20);
+        let input = vec![1u8; BUFFER_SIZE + 1];
+        let mut output = Vec::new();
+
+        let start = Instant::now();
+        forward(&mut Cursor::new(input.clone()), &mut output, &state)?;
+        // The input is forwarded in two chunks, each delayed by the latency
+        assert!(start.elapsed() >= Duration::from_millis(40));
+        assert_eq!(input, output);
+        Ok(())
+    }
+}
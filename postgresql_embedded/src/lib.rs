@@ -39,6 +39,20 @@
 //! }
 //! ```
 //!
+//! ### Ephemeral database
+//!
+//! A throwaway database for quick scripts, with a free port, a temporary data directory and
+//! `fsync` turned off; the server is stopped and removed when the database is dropped.
+//! ```no_run
+//! # #[tokio::main]
+//! # async fn main() -> postgresql_embedded::Result<()> {
+//! let db = postgresql_embedded::ephemeral().await?;
+//! db.run_sql("SELECT 1").await?;
+//! println!("{}", db.url());
+//! # Ok(())
+//! # }
+//! ```
+//!
 //! ### Synchronous API
 //! ```no_run
 //! #[cfg(feature = "blocking")] {
@@ -57,6 +71,19 @@
 //! }
 //! ```
 //!
+//! ### Build scripts
+//!
+//! A database can be started in a `build.rs`, e.g. for compile-time checked queries, with
+//! `blocking::setup_for_build`; see the `build_script_embedded` example.
+//! ```no_run
+//! #[cfg(feature = "blocking")] {
+//! use postgresql_embedded::blocking::{setup_for_build, BuildSetupOptions};
+//!
+//! let database = setup_for_build(BuildSetupOptions::default()).unwrap();
+//! database.emit_rustc_env("DATABASE_URL");
+//! }
+//! ```
+//!
 //! ## Information
 //!
 //! During the build process, when the `bundled` feature is enabled, the PostgreSQL binaries are
@@ -81,15 +108,51 @@
 //!
 //! The following features are available:
 //!
-//! | Name       | Description                                               | Default? |
-//! |------------|-----------------------------------------------------------|----------|
-//! | `bundled`  | Bundles the PostgreSQL archive into the resulting binary  | No      |
-//! | `blocking` | Enables the blocking API; requires `tokio`                | No       |
-//! | `tokio`    | Enables using tokio for async                             | No       |
+//! | Name              | Description                                               | Default? |
+//! |-------------------|-----------------------------------------------------------|----------|
+//! | `bundled`         | Bundles the PostgreSQL archive into the resulting binary  | No       |
+//! | `blocking`        | Enables the blocking API; requires `tokio`                | No       |
+//! | `fault-injection` | Enables the fault injecting proxy                         | No       |
+//! | `otel`            | Emits OpenTelemetry-compatible lifecycle spans            | No       |
+//! | `serde`           | Enables serializing operations, outcomes and descriptions | No       |
+//! | `server-doctests` | Runs the doc examples that start a server                 | No       |
+//! | `tokio`           | Enables using tokio for async                             | No       |
+//!
+//! ## OpenTelemetry
+//!
+//! With the `otel` feature, the lifecycle operations emit spans with attribute names following
+//! the OpenTelemetry semantic conventions. With a bridge such as `tracing-opentelemetry`, the
+//! spans appear as children of the span that is current when the operation is called. The span
+//! names, hierarchy and attribute names are a stable contract:
+//!
+//! ```text
+//! postgresql.setup            db.system, postgresql.version, postgresql.data_dir,
+//! │                           postgresql.cache_hit
+//! ├── postgresql.install      postgresql.version, postgresql.installation_dir,
+//! │                           postgresql.cache_hit, postgresql.download.bytes
+//! └── postgresql.initialize   postgresql.data_dir
+//! postgresql.start            db.system, server.address, server.port, postgresql.data_dir,
+//!                             postgresql.previous_port
+//! postgresql.stop             postgresql.data_dir
+//! postgresql.background       otel.name
+//! ```
+//!
+//! `postgresql.install` is only emitted when the version is not known to be installed, and
+//! `postgresql.initialize` only when the data directory is not initialized yet.
+//! `postgresql.previous_port` is only recorded when the previous port of a dynamic port server
+//! was taken and the server fell back to a new port. The completion
+//! of the `download`, `extract`, `initialize`, `start` and `stop` phases is recorded as a
+//! `phase completed` event, i.e. a span event, with the `postgresql.phase` and
+//! `postgresql.duration_ms` attributes. Background tasks, e.g. the
+//! [low disk watcher](PostgreSQL::low_disk_watcher) and the proxies, run in a
+//! `postgresql.background` span named with `otel.name`, e.g. `postgresql.low_disk_watcher`,
+//! which is a child of the span current when the task is started.
 //!
 //! ## Safety
 //!
-//! These crates use `#![forbid(unsafe_code)]` to ensure everything is implemented in 100% safe Rust.
+//! These crates use `#![forbid(unsafe_code)]` to ensure everything is implemented in 100% safe Rust,
+//! with the exception of the Linux-only `cgroup` module of `postgresql_commands`, which places child
+//! processes into a cgroup with `pre_exec` and is the only place where unsafe code is allowed.
 //!
 //! ## License
 //!
@@ -109,12 +172,102 @@
 #![forbid(unsafe_code)]
 #![allow(dead_code)]
 
+mod analyze;
+mod background;
+mod backup_workflow;
+mod batch;
+mod benchmark;
 #[cfg(feature = "blocking")]
 pub mod blocking;
+mod catalog;
+mod cluster_params;
+mod configuration;
+mod connection_info;
+mod crash;
+mod database_options;
+mod description;
+mod disk_usage;
+mod env_file;
+mod ephemeral;
 mod error;
+#[cfg(feature = "fault-injection")]
+mod fault_injection;
+mod listener;
+mod managed;
+mod matrix;
+mod operation;
 mod postgresql;
+mod prepared_transaction;
+mod probe;
+mod proxy;
+mod quiesce;
+mod reset;
+mod schema_snapshot;
+mod scoped_files;
+mod seed;
+mod server_log;
+mod session_pool;
 mod settings;
+mod socket;
+mod sql;
+mod tablespace;
+mod teardown;
+mod telemetry;
+mod template;
+mod tools;
+mod verify;
+mod wal;
 
+pub use analyze::AnalyzeReport;
+pub use background::QuiesceOptions;
+pub use backup_workflow::{
+    BackupReport, CompressionLocation, CompressionMethod, CompressionSpec, PhysicalBackupWorkflow,
+};
+pub use batch::{BatchOptions, QueryResult};
+pub use benchmark::{BenchOptions, BenchResult};
+pub use catalog::{
+    ColumnInfo, ConstraintInfo, ConstraintKind, ForeignKeyInfo, FunctionInfo, FunctionKind,
+    IndexInfo, Persistence, TableDescription, TableInfo,
+};
+pub use cluster_params::ClusterParams;
+pub use connection_info::ConnectionInfo;
+#[cfg(feature = "tokio")]
+pub use connection_info::ConnectionInfoHandle;
+pub use crash::{CrashMode, RecoveryInfo};
+pub use database_options::{CreateDatabaseOptions, DefaultPrivileges, Revocation, SchemaSpec};
+pub use description::{InstanceDescription, DESCRIPTION_SCHEMA_VERSION};
+pub use disk_usage::{DiskUsage, LowDiskEvent, LowDiskWatcher, RelationSize};
+pub use env_file::{EnvFile, EnvFileFormat};
+pub use ephemeral::{ephemeral, ephemeral_with, EphemeralDatabase, EPHEMERAL_DATABASE};
 pub use error::{Error, Result};
+#[cfg(feature = "fault-injection")]
+pub use fault_injection::FaultInjectingProxy;
+pub use listener::BoundAddress;
+pub use matrix::{MatrixOutcome, MatrixReport, MatrixResult, MatrixRunner};
+pub use operation::{Operation, OperationOutcome, OperationOutput};
 pub use postgresql::{PostgreSQL, Status};
-pub use settings::Settings;
+pub use postgresql_commands::pg_basebackup::BaseBackupProgress;
+pub use postgresql_commands::pg_dump::DumpProgress;
+pub use postgresql_commands::pg_restore::{RestoreProgress, RestoreProgressEvent};
+pub use postgresql_commands::pg_verifybackup::VerifyResult;
+pub use postgresql_commands::toc::{ReorderStrategy, Toc, TocEntry};
+pub use postgresql_commands::{
+    CancellationToken, CommandBuilder, Lsn, ProgressEvent, QualifiedName, SettingsApplicator,
+};
+pub use prepared_transaction::PreparedTransaction;
+pub use probe::{Readiness, ReadinessProbe};
+pub use proxy::{LoggedStatement, ProxyHandle};
+pub use quiesce::QuiescedDataDir;
+pub use reset::{ResetOptions, ResetReport};
+pub use seed::Seed;
+pub use session_pool::SessionPoolStats;
+pub use settings::{
+    IpVersion, Settings, SocketDirMode, WalLevel, FALLBACK_MAINTENANCE_DATABASE,
+    STRUCTURED_LOG_LINE_PREFIX,
+};
+pub use tablespace::TablespaceInfo;
+pub use teardown::{TeardownMode, TeardownReport};
+pub use template::MigratedTemplate;
+pub use tools::{Tool, ToolInfo, ToolInventory, ToolVersion};
+pub use verify::{verify_connection, ServerInfo};
+pub use wal::{SlotRetention, WalUsage};
@@ -0,0 +1,228 @@
+//! Addresses the server listens on. The port of a started server is read from `postmaster.pid`,
+//! and the sockets are derived from the `listen_addresses`, `port` and
+//! `unix_socket_directories` settings of the server.
+
+use crate::error::{Error::DatabaseStartError, Result};
+use std::fmt::{Display, Formatter};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, ToSocketAddrs};
+use std::path::{Path, PathBuf};
+
+/// Line of `postmaster.pid` containing the port of the server (1-based)
+const PORT_LINE: usize = 4;
+
+/// Socket the server listens on; see [bound_addresses](crate::PostgreSQL::bound_addresses)
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum BoundAddress {
+    /// TCP/IP socket
+    Tcp(SocketAddr),
+    /// Unix-domain socket file; e.g. `/tmp/.s.PGSQL.5432`
+    Unix(PathBuf),
+}
+
+impl Display for BoundAddress {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BoundAddress::Tcp(address) => write!(formatter, "{address}"),
+            BoundAddress::Unix(path) => write!(formatter, "{}", path.to_string_lossy()),
+        }
+    }
+}
+
+/// Settings of the listening sockets queried from `pg_settings`
+pub(crate) const SETTINGS_QUERY: &str = "SELECT name, setting FROM pg_settings \
+    WHERE name IN ('listen_addresses', 'port', 'unix_socket_directories')";
+
+/// Read the port the server is bound to from the `postmaster.pid` file in the data directory
+pub(crate) fn bound_port(data_dir: &Path) -> Result<u16> {
+    let contents = std::fs::read_to_string(data_dir.join("postmaster.pid"))?;
+    match contents
+        .lines()
+        .nth(PORT_LINE - 1)
+        .map(|port| port.trim().parse::<u16>())
+    {
+        Some(Ok(port)) => Ok(port),
+        _ => Err(DatabaseStartError(anyhow::anyhow!(
+            "Invalid port in postmaster.pid file in {}",
+            data_dir.to_string_lossy()
+        ))),
+    }
+}
+
+/// Conflict with another server that made a start fail, with the log line reporting it
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum PortConflict {
+    /// The TCP/IP port is in use by another listener
+    Tcp(String),
+    /// The Unix-domain socket or its lock file is in use by another server with the same port
+    /// and socket directory
+    Socket(String),
+}
+
+/// Find the conflict reported by the server log written by a failed start. The server creates
+/// its TCP/IP sockets before its Unix-domain sockets, so the first conflict reported is the one
+/// that made the start fail.
+pub(crate) fn port_conflict(log: &str, port: u16) -> Option<PortConflict> {
+    let lock_file = format!(".s.PGSQL.{port}.lock\" already exists");
+    log.lines().find_map(|line| {
+        let message = line.trim().to_string();
+        if line.contains(&lock_file)
+            || (line.contains("could not bind Unix address")
+                && line.contains("Address already in use"))
+        {
+            Some(PortConflict::Socket(message))
+        } else if line.contains("Address already in use") {
+            Some(PortConflict::Tcp(message))
+        } else {
+            None
+        }
+    })
+}
+
+/// Parse the rows of setting names and values returned by [SETTINGS_QUERY]. Host names in
+/// `listen_addresses` (e.g. `localhost`) are resolved, and `*` is reported as the IPv4 and IPv6
+/// wildcard addresses.
+pub(crate) fn parse_bound_addresses(rows: &[Vec<String>]) -> Vec<BoundAddress> {
+    let setting = |name: &str| {
+        rows.iter()
+            .find_map(|row| match row.as_slice() {
+                [key, value] if key == name => Some(value.as_str()),
+                _ => None,
+            })
+            .unwrap_or_default()
+    };
+    let Ok(port) = setting("port").parse::<u16>() else {
+        return Vec::new();
+    };
+
+    let mut addresses = Vec::new();
+    for host in split_list(setting("listen_addresses")) {
+        let resolved: Vec<SocketAddr> = match host {
+            "*" => vec![
+                SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), port),
+                SocketAddr::new(IpAddr::V6(Ipv6Addr::UNSPECIFIED), port),
+            ],
+            host => (host, port)
+                .to_socket_addrs()
+                .map(Iterator::collect)
+                .unwrap_or_default(),
+        };
+        for address in resolved {
+            let address = BoundAddress::Tcp(address);
+            if !addresses.contains(&address) {
+                addresses.push(address);
+            }
+        }
+    }
+    for directory in split_list(setting("unix_socket_directories")) {
+        addresses.push(BoundAddress::Unix(
+            PathBuf::from(directory).join(format!(".s.PGSQL.{port}")),
+        ));
+    }
+    addresses
+}
+
+/// Split a comma separated list setting, ignoring empty entries
+fn split_list(value: &str) -> impl Iterator<Item = &str> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows(listen_addresses: &str, unix_socket_directories: &str) -> Vec<Vec<String>> {
+        [
+            ("listen_addresses", listen_addresses),
+            ("port", "5432"),
+            ("unix_socket_directories", unix_socket_directories),
+        ]
+        .iter()
+        .map(|(name, value)| vec![name.to_string(), value.to_string()])
+        .collect()
+    }
+
+    #[test]
+    fn test_bound_port() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        std::fs::write(
+            temp_dir.path().join("postmaster.pid"),
+            "42\n/tmp/data\n1700000000\n54321\n/tmp\nlocalhost\n",
+        )?;
+        assert_eq!(54321, bound_port(temp_dir.path())?);
+
+        std::fs::write(temp_dir.path().join("postmaster.pid"), "42\n")?;
+        assert!(bound_port(temp_dir.path()).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_port_conflict() {
+        let tcp = "LOG:  could not bind IPv4 address \"127.0.0.1\": Address already in use";
+        assert_eq!(
+            Some(PortConflict::Tcp(tcp.to_string())),
+            port_conflict(
+                &format!("{tcp}\nFATAL:  could not create any TCP/IP sockets\n"),
+                5432
+            )
+        );
+        let lock_file = "FATAL:  lock file \"/tmp/.s.PGSQL.5432.lock\" already exists";
+        assert_eq!(
+            Some(PortConflict::Socket(lock_file.to_string())),
+            port_conflict(lock_file, 5432)
+        );
+        let socket =
+            "LOG:  could not bind Unix address \"/tmp/.s.PGSQL.5432\": Address already in use";
+        assert_eq!(
+            Some(PortConflict::Socket(socket.to_string())),
+            port_conflict(socket, 5432)
+        );
+        assert_eq!(
+            None,
+            port_conflict(
+                "FATAL:  lock file \"/tmp/.s.PGSQL.5433.lock\" already exists",
+                5432
+            )
+        );
+        assert_eq!(
+            None,
+            port_conflict("FATAL:  could not access directory \"/tmp/data\"", 5432)
+        );
+    }
+
+    #[test]
+    fn test_parse_bound_addresses() {
+        assert_eq!(
+            vec![
+                BoundAddress::Tcp("127.0.0.1:5432".parse().expect("address")),
+                BoundAddress::Tcp("[::1]:5432".parse().expect("address")),
+                BoundAddress::Unix(PathBuf::from("/tmp/.s.PGSQL.5432")),
+                BoundAddress::Unix(PathBuf::from("/var/run/postgresql/.s.PGSQL.5432")),
+            ],
+            parse_bound_addresses(&rows("127.0.0.1, ::1", "/tmp, /var/run/postgresql"))
+        );
+        assert_eq!(
+            vec![
+                BoundAddress::Tcp("0.0.0.0:5432".parse().expect("address")),
+                BoundAddress::Tcp("[::]:5432".parse().expect("address")),
+            ],
+            parse_bound_addresses(&rows("*", ""))
+        );
+        assert!(parse_bound_addresses(&rows("", "")).is_empty());
+        assert!(parse_bound_addresses(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_bound_address_display() {
+        assert_eq!(
+            "127.0.0.1:5432",
+            BoundAddress::Tcp("127.0.0.1:5432".parse().expect("address")).to_string()
+        );
+        assert_eq!(
+            "/tmp/.s.PGSQL.5432",
+            BoundAddress::Unix(PathBuf::from("/tmp/.s.PGSQL.5432")).to_string()
+        );
+    }
+}
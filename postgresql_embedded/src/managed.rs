@@ -0,0 +1,32 @@
+//! Verification that a connection reaches the server managed by an instance, for the
+//! [managed_only](crate::Settings::managed_only) safety mode
+
+/// Query the system identifier of the server a connection reaches
+pub(crate) const SYSTEM_IDENTIFIER_QUERY: &str =
+    "SELECT system_identifier FROM pg_control_system()";
+
+/// Get the system identifier from the output of pg_controldata
+pub(crate) fn parse_system_identifier(control_data: &str) -> Option<&str> {
+    control_data.lines().find_map(|line| {
+        let (label, value) = line.split_once(':')?;
+        (label.trim() == "Database system identifier").then(|| value.trim())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_system_identifier() {
+        let control_data = "pg_control version number:            1300\n\
+            Catalog version number:               202209061\n\
+            Database system identifier:           7358104279419582751\n\
+            Database cluster state:               in production\n";
+        assert_eq!(
+            Some("7358104279419582751"),
+            parse_system_identifier(control_data)
+        );
+        assert_eq!(None, parse_system_identifier(""));
+    }
+}
@@ -0,0 +1,319 @@
+//! Run the same workload against several versions of PostgreSQL installed side by side; see
+//! [MatrixRunner]
+
+use crate::error::{Error, Result};
+use crate::postgresql::PostgreSQL;
+use crate::settings::Settings;
+use futures_util::stream::{self, StreamExt};
+use postgresql_archive::{ArchiveSource, Version};
+use std::future::Future;
+use std::sync::Arc;
+use tracing::{debug, instrument, warn};
+
+/// Outcome of the workload for one version requirement of a [MatrixRunner]
+#[derive(Debug)]
+pub enum MatrixOutcome<T> {
+    /// The workload completed with the value
+    Completed(T),
+    /// Setting up or starting the server failed, or the workload returned the error
+    Failed(Error),
+    /// No installation archive exists for the version on this platform; the reason is given
+    Skipped(String),
+}
+
+/// Result of the workload for one version requirement of a [MatrixRunner]
+#[derive(Debug)]
+pub struct MatrixResult<T> {
+    /// Version requirement; e.g. `16`
+    pub requirement: Version,
+    /// Version the requirement resolved to; e.g. `16.4.0`. Not set when the version was skipped
+    /// or could not be installed.
+    pub version: Option<Version>,
+    /// Outcome of the workload
+    pub outcome: MatrixOutcome<T>,
+}
+
+/// Results of a [MatrixRunner], in the order of the version requirements
+#[derive(Debug)]
+pub struct MatrixReport<T> {
+    /// Result for every version requirement
+    pub results: Vec<MatrixResult<T>>,
+}
+
+impl<T> MatrixReport<T> {
+    /// Get the versions the workload completed on, with the values returned by the workload
+    pub fn completed(&self) -> Vec<(Version, &T)> {
+        self.results
+            .iter()
+            .filter_map(|result| match &result.outcome {
+                MatrixOutcome::Completed(value) => {
+                    Some((result.version.unwrap_or(result.requirement), value))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Get the version requirements that failed, with the errors
+    pub fn failed(&self) -> Vec<(Version, &Error)> {
+        self.results
+            .iter()
+            .filter_map(|result| match &result.outcome {
+                MatrixOutcome::Failed(error) => Some((result.requirement, error)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Get the version requirements skipped because no installation archive exists for the
+    /// platform
+    pub fn skipped(&self) -> Vec<Version> {
+        self.results
+            .iter()
+            .filter(|result| matches!(result.outcome, MatrixOutcome::Skipped(_)))
+            .map(|result| result.requirement)
+            .collect()
+    }
+
+    /// Check that no version requirement failed; skipped versions are not failures
+    pub fn is_success(&self) -> bool {
+        self.failed().is_empty()
+    }
+}
+
+/// Runs the same workload against several versions of PostgreSQL, e.g. to run a test suite
+/// against PostgreSQL 14, 15, 16 and 17. Every version is installed in the installation
+/// directory of the [settings](MatrixRunner::settings), which acts as a cache shared by all
+/// versions; a requirement without a minor or release version prefers the latest matching
+/// installed version, so no network access is needed once the versions are installed.
+///
+/// Every version runs on a temporary server with its own data directory and a random port, so
+/// several versions can run concurrently; see [max_concurrency](MatrixRunner::max_concurrency).
+#[derive(Clone, Debug)]
+pub struct MatrixRunner {
+    versions: Vec<Version>,
+    settings: Settings,
+    archive_source: Option<Arc<dyn ArchiveSource>>,
+    max_concurrency: usize,
+}
+
+impl MatrixRunner {
+    /// Create a new [`MatrixRunner`] for the version requirements; e.g. `[V15, V16]`
+    pub fn new(versions: Vec<Version>) -> Self {
+        Self {
+            versions,
+            settings: Settings::default(),
+            archive_source: None,
+            max_concurrency: 1,
+        }
+    }
+
+    /// Use the settings for every server. The installation directory is the root of the
+    /// installation cache, e.g. `~/.theseus/postgresql`; the version, data directory, password
+    /// file and port are chosen per server, and the servers are always temporary.
+    pub fn settings(mut self, settings: Settings) -> Self {
+        self.settings = settings;
+        self
+    }
+
+    /// Use the [source](ArchiveSource) to resolve the versions and fetch the installation
+    /// archives; see [PostgreSQL::with_archive_source]
+    pub fn with_archive_source(mut self, archive_source: Arc<dyn ArchiveSource>) -> Self {
+        self.archive_source = Some(archive_source);
+        self
+    }
+
+    /// Set the maximum number of versions run concurrently; defaults to `1`, which runs the
+    /// versions one after the other. Requirements resolving to the same version that is not
+    /// installed yet should not run concurrently, as both would install it.
+    pub fn max_concurrency(mut self, max_concurrency: usize) -> Self {
+        self.max_concurrency = max_concurrency.max(1);
+        self
+    }
+
+    /// Get the version requirements
+    pub fn versions(&self) -> &[Version] {
+        &self.versions
+    }
+
+    /// Set up and start a server for every version requirement and run the workload with it.
+    /// The workload owns the server, which is stopped and removed once the workload drops it.
+    /// Versions without an installation archive for the platform are skipped; failures of one
+    /// version do not stop the other versions.
+    #[instrument(skip(self, workload))]
+    pub async fn run<F, Fut, T>(&self, workload: F) -> MatrixReport<T>
+    where
+        F: Fn(PostgreSQL) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let installed = match self.settings.installed_versions() {
+            Ok(installed) => installed,
+            Err(error) => {
+                warn!("Unable to determine the installed versions: {error}");
+                Vec::new()
+            }
+        };
+        let concurrency = self.max_concurrency.min(self.versions.len()).max(1);
+        let mut results: Vec<Option<MatrixResult<T>>> =
+            self.versions.iter().map(|_| None).collect();
+        let mut completed = stream::iter(self.versions.iter().copied().enumerate())
+            .map(|(index, requirement)| {
+                let version = resolve(requirement, &installed);
+                let workload = &workload;
+                async move {
+                    (
+                        index,
+                        self.run_version(requirement, version, workload).await,
+                    )
+                }
+            })
+            .buffer_unordered(concurrency);
+        while let Some((index, result)) = completed.next().await {
+            results[index] = Some(result);
+        }
+
+        MatrixReport {
+            results: results.into_iter().flatten().collect(),
+        }
+    }
+
+    /// Set up and start a server for the version and run the workload with it
+    async fn run_version<F, Fut, T>(
+        &self,
+        requirement: Version,
+        version: Version,
+        workload: &F,
+    ) -> MatrixResult<T>
+    where
+        F: Fn(PostgreSQL) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut postgresql = PostgreSQL::from(self.server_settings(version));
+        if let Some(archive_source) = &self.archive_source {
+            postgresql = postgresql.with_archive_source(archive_source.clone());
+        }
+
+        debug!("Setting up PostgreSQL {version} for requirement {requirement}");
+        if let Err(error) = postgresql.setup().await {
+            let outcome = match error {
+                Error::ArchiveError(
+                    error @ (postgresql_archive::Error::ReleaseNotFound(_)
+                    | postgresql_archive::Error::AssetNotFound(_)),
+                ) => {
+                    debug!("Skipping PostgreSQL {requirement}: {error}");
+                    MatrixOutcome::Skipped(error.to_string())
+                }
+                error => MatrixOutcome::Failed(error),
+            };
+            return MatrixResult {
+                requirement,
+                version: None,
+                outcome,
+            };
+        }
+
+        let version = *postgresql.version();
+        let outcome = match postgresql.start().await {
+            Ok(()) => match workload(postgresql).await {
+                Ok(value) => MatrixOutcome::Completed(value),
+                Err(error) => MatrixOutcome::Failed(error),
+            },
+            Err(error) => MatrixOutcome::Failed(error),
+        };
+        MatrixResult {
+            requirement,
+            version: Some(version),
+            outcome,
+        }
+    }
+
+    /// Get the settings of the temporary server for the version
+    fn server_settings(&self, version: Version) -> Settings {
+        let server = Settings::new();
+        Settings {
+            version: Some(version),
+            data_dir: server.data_dir,
+            password_file: server.password_file,
+            port: 0,
+            temporary: true,
+            ..self.settings.clone()
+        }
+    }
+}
+
+/// Resolve a version requirement to the latest matching installed version; requirements
+/// without a matching installation are resolved when the server is set up.
+fn resolve(requirement: Version, installed: &[Version]) -> Version {
+    if requirement.minor.is_some() && requirement.release.is_some() {
+        return requirement;
+    }
+    installed
+        .iter()
+        .rev()
+        .find(|version| requirement.matches(version))
+        .copied()
+        .unwrap_or(requirement)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve() {
+        let installed = [
+            Version::new(15, Some(7), Some(0)),
+            Version::new(15, Some(8), Some(0)),
+            Version::new(16, Some(4), Some(0)),
+        ];
+        assert_eq!(
+            Version::new(15, Some(8), Some(0)),
+            resolve(Version::new(15, None, None), &installed)
+        );
+        assert_eq!(
+            Version::new(15, Some(7), Some(0)),
+            resolve(Version::new(15, Some(7), None), &installed)
+        );
+        assert_eq!(
+            Version::new(14, None, None),
+            resolve(Version::new(14, None, None), &installed)
+        );
+        assert_eq!(
+            Version::new(16, Some(3), Some(0)),
+            resolve(Version::new(16, Some(3), Some(0)), &installed)
+        );
+    }
+
+    #[test]
+    fn test_matrix_report() {
+        let report = MatrixReport {
+            results: vec![
+                MatrixResult {
+                    requirement: Version::new(15, None, None),
+                    version: Some(Version::new(15, Some(8), Some(0))),
+                    outcome: MatrixOutcome::Completed(1),
+                },
+                MatrixResult {
+                    requirement: Version::new(13, None, None),
+                    version: None,
+                    outcome: MatrixOutcome::Skipped("release not found".to_string()),
+                },
+                MatrixResult {
+                    requirement: Version::new(16, None, None),
+                    version: Some(Version::new(16, Some(4), Some(0))),
+                    outcome: MatrixOutcome::Completed(2),
+                },
+            ],
+        };
+        assert_eq!(
+            vec![
+                (Version::new(15, Some(8), Some(0)), &1),
+                (Version::new(16, Some(4), Some(0)), &2)
+            ],
+            report.completed()
+        );
+        assert_eq!(vec![Version::new(13, None, None)], report.skipped());
+        assert!(report.failed().is_empty());
+        assert!(report.is_success());
+    }
+}
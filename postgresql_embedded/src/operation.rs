@@ -0,0 +1,110 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Lifecycle operation applied to a PostgreSQL server with [apply](crate::PostgreSQL::apply).
+/// Each operation corresponds to a method of [`PostgreSQL`](crate::PostgreSQL), allowing
+/// operations to be retried, rate limited, observed, or journaled uniformly.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Operation {
+    /// Install and initialize the server; see [setup](crate::PostgreSQL::setup)
+    Setup,
+    /// Start the server; see [start](crate::PostgreSQL::start)
+    Start,
+    /// Stop the server; see [stop](crate::PostgreSQL::stop)
+    Stop,
+    /// Create a database; see [create_database](crate::PostgreSQL::create_database)
+    CreateDatabase { database_name: String },
+    /// Check if a database exists; see [database_exists](crate::PostgreSQL::database_exists)
+    DatabaseExists { database_name: String },
+    /// Drop a database; see [drop_database](crate::PostgreSQL::drop_database)
+    DropDatabase { database_name: String },
+    /// Back up a database; see [backup](crate::PostgreSQL::backup)
+    Backup {
+        database_name: String,
+        path: PathBuf,
+    },
+    /// Create a physical replication slot; see
+    /// [create_physical_slot](crate::PostgreSQL::create_physical_slot)
+    CreatePhysicalSlot { slot_name: String },
+    /// Drop a replication slot; see [drop_slot](crate::PostgreSQL::drop_slot)
+    DropSlot { slot_name: String },
+    /// Drop the roles that are not kept; see [reset_roles](crate::PostgreSQL::reset_roles)
+    ResetRoles { keep: Vec<String> },
+    /// Execute SQL against a database; see [run_sql](crate::PostgreSQL::run_sql)
+    RunSql { database_name: String, sql: String },
+}
+
+/// Output of a successful [`Operation`]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OperationOutput {
+    /// The operation completed without producing a value
+    Completed,
+    /// Whether the database exists; produced by [`Operation::DatabaseExists`]
+    Exists(bool),
+    /// Names of the dropped roles; produced by [`Operation::ResetRoles`]
+    Roles(Vec<String>),
+    /// Rows returned by the SQL; produced by [`Operation::RunSql`]
+    Rows(Vec<Vec<String>>),
+}
+
+/// Outcome of applying an [`Operation`]. Failures are captured as error messages, so that
+/// outcomes can be journaled.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OperationOutcome {
+    /// Applied operation
+    pub operation: Operation,
+    /// Output of the operation, or the error message when the operation failed
+    pub output: std::result::Result<OperationOutput, String>,
+    /// Time taken to apply the operation
+    pub duration: Duration,
+}
+
+impl OperationOutcome {
+    /// Check if the operation succeeded
+    pub fn is_success(&self) -> bool {
+        self.output.is_ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_success() {
+        let outcome = OperationOutcome {
+            operation: Operation::Start,
+            output: Ok(OperationOutput::Completed),
+            duration: Duration::from_millis(10),
+        };
+        assert!(outcome.is_success());
+
+        let outcome = OperationOutcome {
+            output: Err("failed".to_string()),
+            ..outcome
+        };
+        assert!(!outcome.is_success());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serialize_outcome() -> anyhow::Result<()> {
+        let outcome = OperationOutcome {
+            operation: Operation::DatabaseExists {
+                database_name: "test".to_string(),
+            },
+            output: Ok(OperationOutput::Exists(true)),
+            duration: Duration::from_millis(10),
+        };
+        let json = serde_json::to_string(&outcome)?;
+        assert_eq!(
+            r#"{"operation":{"DatabaseExists":{"database_name":"test"}},"output":{"Ok":{"Exists":true}},"duration":{"secs":0,"nanos":10000000}}"#,
+            json
+        );
+        assert_eq!(outcome, serde_json::from_str(&json)?);
+        Ok(())
+    }
+}
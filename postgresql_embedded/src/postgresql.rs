@@ -1,28 +1,103 @@
-use crate::error::Error::{DatabaseInitializationError, DatabaseStartError, DatabaseStopError};
+use crate::analyze::{self, AnalyzeReport};
+use crate::background::{self, QuiesceOptions};
+use crate::batch::{self, BatchOptions, QueryResult};
+use crate::benchmark::{self, BenchOptions, BenchResult};
+use crate::catalog::{self, FunctionInfo, IndexInfo, TableDescription, TableInfo};
+use crate::cluster_params::{self, ClusterParams};
+use crate::configuration;
+use crate::connection_info::ConnectionInfo;
+#[cfg(feature = "tokio")]
+use crate::connection_info::ConnectionInfoHandle;
+use crate::crash::{self, CrashMode, RecoveryInfo};
+use crate::database_options::{self, CreateDatabaseOptions};
+use crate::description::{self, InstanceDescription, DESCRIPTION_SCHEMA_VERSION};
+use crate::disk_usage::{self, DiskUsage, LowDiskEvent, LowDiskWatcher, RelationSize};
+use crate::env_file::{EnvFile, EnvFileFormat};
+use crate::error::Error::{
+    AnalyzeError, BackgroundActivityError, BackupError, BenchmarkError, CancelledError,
+    CatalogError, CheckpointError, ClusterParamsError, CrashError, DatabaseInitializationError,
+    DatabaseStartError, DatabaseStopError, DiskUsageError, GucError, ListenerError,
+    PreparedTransactionError, QuiesceError, ReadinessError, ResetError, RestoreError, RewindError,
+    SeedError, SessionError, SocketConflictError, TablespaceError, TcpPortConflictError,
+    ToolNotFoundError, UnmanagedServerError, VacuumError, WalError,
+};
 use crate::error::Result;
-use crate::settings::{Settings, BOOTSTRAP_SUPERUSER};
-use postgresql_archive::{extract, get_archive};
-use postgresql_archive::{get_version, Version};
+#[cfg(feature = "fault-injection")]
+use crate::fault_injection::FaultInjectingProxy;
+use crate::listener::{self, BoundAddress, PortConflict};
+use crate::managed;
+use crate::operation::{Operation, OperationOutcome, OperationOutput};
+use crate::prepared_transaction::{self, PreparedTransaction};
+use crate::probe::{self, Readiness, ReadinessProbe};
+use crate::proxy::ProxyHandle;
+use crate::quiesce::{self, QuiescedDataDir};
+use crate::reset::{self, ResetOptions, ResetReport};
+use crate::schema_snapshot;
+use crate::scoped_files::{self, ScopedFiles};
+use crate::seed::{Seed, SEED_APPLIED, SEED_FILE, SEED_PENDING};
+use crate::server_log::ServerLogForwarder;
+use crate::session_pool::{self, Checkout, Session, SessionPool, SessionPoolStats};
+use crate::settings::{
+    Settings, SocketDirMode, WalLevel, BOOTSTRAP_SUPERUSER, FALLBACK_MAINTENANCE_DATABASE,
+};
+use crate::socket;
+use crate::tablespace::{self, TablespaceInfo};
+use crate::teardown::{self, TeardownMode, TeardownReport};
+use crate::telemetry;
+use crate::tools::{Tool, ToolInfo, ToolInventory};
+use crate::wal::{self, SlotRetention, WalUsage};
+use futures_util::stream::{self, StreamExt};
+use postgresql_archive::{
+    extract_with_options, ArchiveSource, ExtractFilter, ExtractOptions, GitHubSource, Version,
+    TARGET,
+};
 use postgresql_commands::initdb::InitDbBuilder;
+use postgresql_commands::pg_basebackup::PgBaseBackupBuilder;
+use postgresql_commands::pg_controldata::PgControlDataBuilder;
 use postgresql_commands::pg_ctl::Mode::{Start, Stop};
 use postgresql_commands::pg_ctl::PgCtlBuilder;
-use postgresql_commands::pg_ctl::ShutdownMode::Fast;
+use postgresql_commands::pg_ctl::ShutdownMode::{Fast, Immediate};
+use postgresql_commands::pg_dump::{DumpProgress, PgDumpBuilder};
+use postgresql_commands::pg_isready::PgIsReadyBuilder;
+use postgresql_commands::pg_restore::PgRestoreBuilder;
+use postgresql_commands::pg_rewind::PgRewindBuilder;
+use postgresql_commands::pgbench::PgBenchBuilder;
+use postgresql_commands::progress::{self, CancellationToken, ProgressEvent};
 use postgresql_commands::psql::PsqlBuilder;
+use postgresql_commands::toc::{self, ReorderStrategy, Toc};
+use postgresql_commands::vacuumdb::VacuumDbBuilder;
 #[cfg(feature = "tokio")]
 use postgresql_commands::AsyncCommandExecutor;
+#[cfg(target_os = "linux")]
+use postgresql_commands::CgroupCommand;
 use postgresql_commands::CommandBuilder;
 #[cfg(not(feature = "tokio"))]
 use postgresql_commands::CommandExecutor;
-use std::fs::{remove_dir_all, remove_file};
-use std::io::prelude::*;
+use postgresql_commands::PgAuthMethod;
+use postgresql_commands::QualifiedName;
+use postgresql_commands::SettingsApplicator;
+use std::collections::HashMap;
+use std::fs::{create_dir_all, metadata, read, read_to_string, remove_dir_all, remove_file, write};
+use std::future::Future;
 use std::net::TcpListener;
 #[cfg(feature = "bundled")]
 use std::ops::Deref;
+use std::path::{Path, PathBuf};
 #[cfg(feature = "bundled")]
 use std::str::FromStr;
-use tracing::{debug, instrument};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::RecvTimeoutError;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+#[cfg(feature = "tokio")]
+use tokio::sync::watch;
+use tracing::{debug, instrument, warn};
 
-use crate::Error::{CreateDatabaseError, DatabaseExistsError, DropDatabaseError};
+use crate::sql::{quote_identifier, quote_literal};
+use crate::Error::{
+    CreateDatabaseError, DatabaseExistsError, DropDatabaseError, ReplicationSlotError, RoleError,
+    SqlError,
+};
 
 #[cfg(feature = "bundled")]
 lazy_static::lazy_static! {
@@ -37,8 +112,31 @@ lazy_static::lazy_static! {
 #[cfg(feature = "bundled")]
 pub(crate) const ARCHIVE: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/postgresql.tar.gz"));
 
+/// Separator used between columns of query results (ASCII unit separator)
+pub(crate) const FIELD_SEPARATOR: char = '\u{1f}';
+/// Separator used between rows of query results (ASCII record separator)
+const RECORD_SEPARATOR: char = '\u{1e}';
+/// Log message written by the server once it is ready to accept connections
+const READY_MESSAGE: &str = "database system is ready to accept connections";
+/// Maximum time to wait for server processes to exit and for crash recovery to complete
+const RECOVERY_TIMEOUT: Duration = Duration::from_secs(60);
+/// File in the data directory used to persist the port of persistent servers across restarts
+const PORT_FILE: &str = "postgresql_embedded.port";
+/// Number of attempts to start a server with a dynamic port when the selected port is in use
+const START_ATTEMPTS: u32 = 3;
+/// Maximum time to wait for a configuration reload to take effect
+const RELOAD_TIMEOUT: Duration = Duration::from_secs(10);
+/// Maximum time spent removing the partial output of a cancelled command, while the processes
+/// it started exit
+const CLEANUP_TIMEOUT: Duration = Duration::from_secs(5);
+/// Maximum time the drop of a cancelled operation waits for the operation to return; longer
+/// than [`CLEANUP_TIMEOUT`] so that the partial output is normally removed before the drop
+/// returns
+const CANCEL_TIMEOUT: Duration = Duration::from_secs(10);
+
 /// PostgreSQL status
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Status {
     /// Archive not installed
     NotInstalled,
@@ -55,13 +153,47 @@ pub enum Status {
 pub struct PostgreSQL {
     version: Version,
     settings: Settings,
+    dynamic_port: bool,
+    started: bool,
+    crash_log_offset: Option<u64>,
+    archive_source: Arc<dyn ArchiveSource>,
+    extract_filter: ExtractFilter,
+    last_backup_at: Arc<Mutex<Option<u64>>>,
+    autovacuum_restore: Arc<Mutex<Option<(String, String)>>>,
+    inventory: ToolInventory,
+    session_pool: Arc<SessionPool>,
+    server_log: Arc<Mutex<Option<ServerLogForwarder>>>,
+    env_files: Arc<Mutex<Vec<EnvFile>>>,
+    previous_port: Option<u16>,
+    #[cfg(feature = "tokio")]
+    connection_info: Arc<watch::Sender<ConnectionInfo>>,
 }
 
 /// PostgreSQL server methods
 impl PostgreSQL {
     /// Create a new [`PostgreSQL`] instance
     pub fn new(version: Version, settings: Settings) -> Self {
-        let mut postgresql = PostgreSQL { version, settings };
+        let dynamic_port = settings.port == 0;
+        #[cfg(feature = "tokio")]
+        let (connection_info, _) = watch::channel(ConnectionInfo::from(&settings));
+        let mut postgresql = PostgreSQL {
+            version,
+            settings,
+            dynamic_port,
+            started: false,
+            crash_log_offset: None,
+            archive_source: Arc::new(GitHubSource),
+            extract_filter: ExtractFilter::default(),
+            last_backup_at: Arc::default(),
+            autovacuum_restore: Arc::default(),
+            inventory: ToolInventory::default(),
+            session_pool: Arc::default(),
+            server_log: Arc::default(),
+            env_files: Arc::default(),
+            previous_port: None,
+            #[cfg(feature = "tokio")]
+            connection_info: Arc::new(connection_info),
+        };
 
         // If the minor and release version are set, append the version to the installation directory
         // to avoid conflicts with other versions.  This will also facilitate setting the status
@@ -80,6 +212,38 @@ impl PostgreSQL {
         postgresql
     }
 
+    /// Use the [source](ArchiveSource) to resolve the version and fetch the installation archive
+    /// instead of downloading it from GitHub; the bundled archive is still preferred when the
+    /// `bundled` feature is enabled and the versions match.
+    pub fn with_archive_source(mut self, archive_source: Arc<dyn ArchiveSource>) -> Self {
+        self.archive_source = archive_source;
+        self
+    }
+
+    /// Extract only the entries of the installation archive selected by the
+    /// [filter](ExtractFilter), e.g. [ExtractFilter::runtime] to leave out the documentation,
+    /// message translations and extension headers; every entry is extracted by default. The
+    /// filter only applies when the version is not installed yet, so a filtered installation
+    /// should use its own [installation directory](Settings::installation_dir).
+    pub fn with_extract_filter(mut self, extract_filter: ExtractFilter) -> Self {
+        self.extract_filter = extract_filter;
+        self
+    }
+
+    /// Apply the pg_dump output at the path to the
+    /// [maintenance database](Settings::maintenance_database) after the server is first
+    /// started, by setting the [seed](Settings::seed). Plain SQL dumps are executed with psql,
+    /// and custom, tar or directory format archives are restored with pg_restore; the format
+    /// is detected with [Seed::from_dump].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the dump cannot be read.
+    pub fn with_dump<P: Into<PathBuf>>(mut self, path: P) -> Result<Self> {
+        self.settings.seed = Some(Seed::from_dump(path)?);
+        Ok(self)
+    }
+
     /// Get the default version used if not otherwise specified
     pub fn default_version() -> Version {
         #[cfg(feature = "bundled")]
@@ -117,6 +281,127 @@ impl PostgreSQL {
         &self.settings
     }
 
+    /// Get the path of the Unix-domain socket of the server, in the
+    /// [server socket directory](Settings::server_socket_dir) on the port of the server; e.g.
+    /// `/tmp/pg-sock-3fa8c2d91b0e/.s.PGSQL.5432`. `None` when the directory compiled into
+    /// PostgreSQL is used, when the port is not selected yet, and on platforms without
+    /// Unix-domain sockets.
+    pub fn socket_path(&self) -> Option<PathBuf> {
+        if self.settings.port == 0 {
+            return None;
+        }
+        let socket_dir = self.settings.server_socket_dir()?;
+        Some(socket::socket_path(&socket_dir, self.settings.port))
+    }
+
+    /// Get the [inventory](ToolInventory) of the programs in the installation, which is built
+    /// by [setup](Self::setup); the inventory is empty before.
+    pub fn inventory(&self) -> &ToolInventory {
+        &self.inventory
+    }
+
+    /// Get the information of a program the caller depends on, e.g. before offering a feature
+    /// that runs it. An error is returned if the program is not present in the installation,
+    /// or if the installation has not been [set up](Self::setup) yet.
+    pub fn require_tool(&self, tool: Tool) -> Result<&ToolInfo> {
+        match self.inventory.get(tool) {
+            Some(info) if info.present => Ok(info),
+            Some(info) => Err(ToolNotFoundError(format!(
+                "{tool} is not present in this installation: {}",
+                info.path.to_string_lossy()
+            ))),
+            None => Err(ToolNotFoundError(format!(
+                "{tool} is unknown until the installation is set up"
+            ))),
+        }
+    }
+
+    /// Get the current endpoint of the server; the
+    /// [previous port](ConnectionInfo::previous_port) is set when the last start fell back to a
+    /// new port
+    pub fn connection_info(&self) -> ConnectionInfo {
+        ConnectionInfo {
+            previous_port: self.previous_port,
+            ..ConnectionInfo::from(&self.settings)
+        }
+    }
+
+    /// Get a handle that always reflects the current endpoint of the server; subscribers are
+    /// notified when the server is started on a different endpoint.
+    #[cfg(feature = "tokio")]
+    pub fn connection_info_handle(&self) -> ConnectionInfoHandle {
+        ConnectionInfoHandle::new(self.connection_info.subscribe())
+    }
+
+    /// Describe the server for external tooling; see [InstanceDescription]. The password is only
+    /// included when [describe_secrets](Settings::describe_secrets) is set.
+    pub fn describe(&self) -> InstanceDescription {
+        let status = self.status();
+        let postmaster = if status == Status::Started {
+            description::read_postmaster(&self.settings.data_dir)
+        } else {
+            None
+        };
+        let last_backup_at = self
+            .last_backup_at
+            .lock()
+            .map_or(None, |last_backup_at| *last_backup_at);
+        InstanceDescription {
+            schema_version: DESCRIPTION_SCHEMA_VERSION,
+            version: self.version.to_string(),
+            status,
+            installation_dir: self.settings.installation_dir.clone(),
+            data_dir: self.settings.data_dir.clone(),
+            host: self.settings.effective_host().to_string(),
+            port: self.settings.port,
+            username: self.settings.username.clone(),
+            password: self
+                .settings
+                .describe_secrets
+                .then(|| self.settings.password.clone()),
+            temporary: self.settings.temporary,
+            pid: postmaster.map(|(pid, _)| pid),
+            started_at: postmaster.map(|(_, started_at)| started_at),
+            last_backup_at,
+            updated_at: description::unix_millis(SystemTime::now()),
+        }
+    }
+
+    /// Get the path the [description](Self::describe) of the server is written to on every
+    /// lifecycle transition with the `serde` feature; e.g. `/tmp/data.description.json` for the
+    /// data directory `/tmp/data`. The file is removed along with the data directory of
+    /// temporary servers.
+    pub fn description_path(&self) -> PathBuf {
+        description::description_path(&self.settings.data_dir)
+    }
+
+    /// Write the [description](Self::describe) of the server as JSON to the path; the file is
+    /// replaced atomically.
+    #[cfg(feature = "serde")]
+    pub fn write_description<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        description::write(&self.describe(), path.as_ref())
+    }
+
+    /// Write the description to the [description path](Self::description_path) after a
+    /// lifecycle transition; a failure is logged rather than failing the transition.
+    fn update_description(&self) {
+        #[cfg(feature = "serde")]
+        if let Err(error) = self.write_description(self.description_path()) {
+            warn!(
+                "Failed to write the description of database {}: {error}",
+                self.settings.data_dir.to_string_lossy()
+            );
+        }
+    }
+
+    /// Record a successful backup in the description of the server
+    fn record_backup(&self) {
+        if let Ok(mut last_backup_at) = self.last_backup_at.lock() {
+            *last_backup_at = Some(description::unix_millis(SystemTime::now()));
+        }
+        self.update_description();
+    }
+
     /// Check if the PostgreSQL server is installed
     fn is_installed(&self) -> bool {
         if self.version.minor.is_none() || self.version.release.is_none() {
@@ -141,33 +426,78 @@ impl PostgreSQL {
     /// Set up the database by extracting the archive and initializing the database.
     /// If the installation directory already exists, the archive will not be extracted.
     /// If the data directory already exists, the database will not be initialized.
-    #[instrument]
+    /// If the [socket directory](Settings::socket_dir) makes the Unix-domain socket path longer
+    /// than the platform limit, an error is returned before anything is installed.
+    ///
+    /// Setup is cancellation safe: when the future is dropped before it completes, e.g. by a
+    /// timeout, no partial installation is left behind since the archive is extracted to a
+    /// temporary directory that is renamed into place, and a running initdb is killed and the
+    /// partially initialized data directory is removed (or emptied, when it existed before)
+    /// before the drop returns. Setup can then be run again. Only initdb is cancelled: dropping
+    /// the future while the archive is downloaded abandons the download, which has written
+    /// nothing yet, and an extraction that has started is not interrupted but completes before
+    /// the future can be dropped.
+    #[cfg_attr(not(feature = "otel"), instrument)]
+    #[cfg_attr(
+        feature = "otel",
+        instrument(
+            name = "postgresql.setup",
+            skip(self),
+            fields(
+                db.system = telemetry::DB_SYSTEM,
+                postgresql.version = tracing::field::Empty,
+                postgresql.data_dir = %self.settings.data_dir.display(),
+                postgresql.cache_hit = tracing::field::Empty,
+            )
+        )
+    )]
     pub async fn setup(&mut self) -> Result<()> {
-        if !self.is_installed() {
-            self.install().await?;
-        }
+        self.settings.check_socket_path()?;
+        let cache_hit = if self.is_installed() {
+            true
+        } else {
+            !self.install().await?
+        };
+        telemetry::record("postgresql.cache_hit", cache_hit);
+        telemetry::record("postgresql.version", tracing::field::display(&self.version));
+        self.inventory = ToolInventory::scan(&self.settings.binary_dir());
 
         if !self.is_initialized() {
             self.initialize().await?;
         }
 
+        self.update_description();
         Ok(())
     }
 
-    /// Install the PostgreSQL server from the archive. If the version minor and/or release are not set,
+    /// Install the PostgreSQL server from the archive fetched from the
+    /// [archive source](PostgreSQL::with_archive_source). If the version minor and/or release are not set,
     /// the latest version will be determined dynamically during the installation process. If the archive
     /// hash does not match the expected hash, an error will be returned. If the installation directory
     /// already exists, the archive will not be extracted. If the archive is not found, an error will be
-    /// returned.
-    #[instrument]
-    async fn install(&mut self) -> Result<()> {
+    /// returned. Returns whether the archive was extracted.
+    #[cfg_attr(not(feature = "otel"), instrument)]
+    #[cfg_attr(
+        feature = "otel",
+        instrument(
+            name = "postgresql.install",
+            skip(self),
+            fields(
+                postgresql.version = tracing::field::Empty,
+                postgresql.installation_dir = tracing::field::Empty,
+                postgresql.cache_hit = tracing::field::Empty,
+                postgresql.download.bytes = tracing::field::Empty,
+            )
+        )
+    )]
+    async fn install(&mut self) -> Result<bool> {
         debug!("Starting installation process for version {}", self.version);
 
         // If the minor and release version are not set, determine the latest version and update the
         // version and installation directory accordingly. This is an optimization to avoid downloading
         // the archive if the latest version is already installed.
         if self.version.minor.is_none() || self.version.release.is_none() {
-            let version = get_version(&self.version).await?;
+            let version = self.archive_source.get_version(&self.version).await?;
             self.version = version;
             self.settings.installation_dir = self
                 .settings
@@ -175,27 +505,39 @@ impl PostgreSQL {
                 .join(self.version.to_string());
         }
 
+        telemetry::record("postgresql.version", tracing::field::display(&self.version));
+        telemetry::record(
+            "postgresql.installation_dir",
+            tracing::field::display(self.settings.installation_dir.display()),
+        );
         if self.settings.installation_dir.exists() {
             debug!("Installation directory already exists");
-            return Ok(());
+            telemetry::record("postgresql.cache_hit", true);
+            return Ok(false);
         }
+        telemetry::record("postgresql.cache_hit", false);
 
         #[cfg(feature = "bundled")]
         // If the requested version is the same as the version of the bundled archive, use the bundled
         // archive. This avoids downloading the archive in environments where internet access is
         // restricted or undesirable.
-        let (version, bytes) = if ARCHIVE_VERSION.deref() == &self.version {
+        let bytes = if ARCHIVE_VERSION.deref() == &self.version {
             debug!("Using bundled installation archive");
-            (self.version, bytes::Bytes::copy_from_slice(ARCHIVE))
+            bytes::Bytes::copy_from_slice(ARCHIVE)
         } else {
-            get_archive(&self.version).await?
+            self.fetch_archive().await?
         };
 
         #[cfg(not(feature = "bundled"))]
-        let (version, bytes) = { get_archive(&self.version).await? };
+        let bytes = self.fetch_archive().await?;
 
-        self.version = version;
-        extract(&bytes, &self.settings.installation_dir).await?;
+        let extract_started = Instant::now();
+        let options = ExtractOptions {
+            filter: self.extract_filter.clone(),
+            ..Default::default()
+        };
+        extract_with_options(&bytes, &self.settings.installation_dir, &options).await?;
+        telemetry::phase_completed("extract", extract_started);
 
         debug!(
             "Installed PostgreSQL version {} to {}",
@@ -203,85 +545,496 @@ impl PostgreSQL {
             self.settings.installation_dir.to_string_lossy()
         );
 
-        Ok(())
+        Ok(true)
+    }
+
+    /// Fetch the installation archive from the [archive source](PostgreSQL::with_archive_source)
+    async fn fetch_archive(&self) -> Result<bytes::Bytes> {
+        let started = Instant::now();
+        let bytes = self.archive_source.fetch(&self.version, TARGET).await?;
+        telemetry::record("postgresql.download.bytes", bytes.len() as u64);
+        telemetry::phase_completed("download", started);
+        Ok(bytes)
     }
 
     /// Initialize the database in the data directory. This will create the necessary files and
     /// directories to start the database.
-    #[instrument]
+    #[cfg_attr(not(feature = "otel"), instrument)]
+    #[cfg_attr(
+        feature = "otel",
+        instrument(
+            name = "postgresql.initialize",
+            skip(self),
+            fields(postgresql.data_dir = %self.settings.data_dir.display())
+        )
+    )]
     async fn initialize(&mut self) -> Result<()> {
+        let started = Instant::now();
         if !self.settings.password_file.exists() {
-            let mut file = std::fs::File::create(&self.settings.password_file)?;
-            file.write_all(self.settings.password.as_bytes())?;
+            scoped_files::write_private(
+                &self.settings.password_file,
+                self.settings.password.as_bytes(),
+            )?;
         }
+        let mut files =
+            ScopedFiles::new(&self.settings.data_dir, self.settings.keep_files_on_failure);
+        let pwfile = files.create("pwfile", self.settings.password.as_bytes())?;
 
         debug!(
             "Initializing database {}",
             self.settings.data_dir.to_string_lossy()
         );
 
-        let initdb = InitDbBuilder::from(&self.settings)
+        let mut initdb = InitDbBuilder::from(&self.settings)
             .pgdata(&self.settings.data_dir)
             .username(BOOTSTRAP_SUPERUSER)
-            .auth("password")
-            .pwfile(&self.settings.password_file)
+            .auth(PgAuthMethod::Password)
+            .pwfile(&pwfile)
             .encoding("UTF8");
+        if !self.settings.inherit_locale {
+            initdb = initdb.locale(&self.settings.locale).lc_messages("C");
+        }
+        if let Err(error) = self.check_tool(&initdb) {
+            files.fail();
+            return Err(DatabaseInitializationError(error.into()));
+        }
 
-        match self.execute_command(initdb).await {
+        let data_dir = self.settings.data_dir.clone();
+        let existed = data_dir.exists();
+        let passfile = initdb.get_passfile();
+        let mut command = initdb.build();
+        self.set_locale_environment(&mut command);
+        self.set_cgroup(&mut command);
+        let result = self
+            .run_cancel_safe(move |cancel| {
+                let _passfile = passfile;
+                let result = progress::execute_with_cancellation(&mut command, cancel, |_| {});
+                if matches!(
+                    result,
+                    Err(postgresql_commands::Error::CancelledError { .. })
+                ) {
+                    debug!(
+                        "Removing partially initialized database {}",
+                        data_dir.to_string_lossy()
+                    );
+                    let started = Instant::now();
+                    while !remove_partial_output(&data_dir, existed)
+                        && started.elapsed() < CLEANUP_TIMEOUT
+                    {
+                        std::thread::sleep(Duration::from_millis(100));
+                    }
+                }
+                result
+            })
+            .await;
+
+        match result {
             Ok((_stdout, _stderr)) => {
                 debug!(
                     "Initialized database {}",
                     self.settings.data_dir.to_string_lossy()
                 );
+                if self.settings.seed.is_some() {
+                    write(self.settings.data_dir.join(SEED_FILE), SEED_PENDING)?;
+                }
+                telemetry::phase_completed("initialize", started);
                 Ok(())
             }
-            Err(error) => Err(DatabaseInitializationError(error.into())),
+            Err(error) => {
+                files.fail();
+                Err(cancellable_error(error, DatabaseInitializationError))
+            }
         }
     }
 
     /// Start the database and wait for the startup to complete.
-    /// If the port is set to `0`, the database will be started on a random port.
-    #[instrument]
+    /// If the port is set to `0`, the database will be started on a random port. Restarts prefer
+    /// the previously selected port, which is persisted in the data directory for servers that
+    /// are not temporary; a new random port is selected when the previous port is taken, or when
+    /// the server fails to start because another process took the port in the meantime. Such a
+    /// fallback is reported by the [previous port](ConnectionInfo::previous_port) of the
+    /// [connection info](Self::connection_info). The port the server is bound to is read back
+    /// from `postmaster.pid` before returning, so the [settings](Self::settings) always report
+    /// the actual port.
+    #[cfg_attr(not(feature = "otel"), instrument)]
+    #[cfg_attr(
+        feature = "otel",
+        instrument(
+            name = "postgresql.start",
+            skip(self),
+            fields(
+                db.system = telemetry::DB_SYSTEM,
+                server.address = %self.settings.effective_host(),
+                server.port = tracing::field::Empty,
+                postgresql.previous_port = tracing::field::Empty,
+                postgresql.data_dir = %self.settings.data_dir.display(),
+            )
+        )
+    )]
     pub async fn start(&mut self) -> Result<()> {
-        if self.settings.port == 0 {
-            let listener = TcpListener::bind(("0.0.0.0", 0))?;
-            self.settings.port = listener.local_addr()?.port();
+        let started = Instant::now();
+        let previous_port = if self.dynamic_port {
+            let previous_port = self.previous_port_to_reuse();
+            self.settings.port = select_port(previous_port)?;
+            previous_port
+        } else {
+            None
+        };
+
+        configuration::write(&self.settings)?;
+        let start_log = self.settings.data_dir.join("start.log");
+        let server_log = self
+            .settings
+            .trace_server_logs
+            .then(|| ServerLogForwarder::start(start_log.clone(), self.start_log_len()));
+        let mut attempt = 1;
+        loop {
+            debug!(
+                "Starting database {} on port {}",
+                self.settings.data_dir.to_string_lossy(),
+                self.settings.port
+            );
+            let log_offset = metadata(&start_log)
+                .map(|metadata| metadata.len())
+                .unwrap_or(0);
+            if let Some(socket_dir) = self.settings.server_socket_dir() {
+                create_dir_all(&socket_dir)?;
+                if socket::remove_stale_files(&socket_dir, self.settings.port)? {
+                    warn!(
+                        "Removed the stale socket files of port {} in {}",
+                        self.settings.port,
+                        socket_dir.to_string_lossy()
+                    );
+                }
+            }
+            let options = format!("-F -p {}", self.settings.port);
+            let pg_ctl = PgCtlBuilder::from(&self.settings)
+                .mode(Start)
+                .pgdata(&self.settings.data_dir)
+                .log_file(&start_log)
+                .options(options)
+                .wait();
+
+            match self.execute_command(pg_ctl).await {
+                Ok((_stdout, _stderr)) => break,
+                Err(error) => {
+                    let log = read(&start_log)
+                        .ok()
+                        .and_then(|log| log.get(log_offset as usize..).map(<[u8]>::to_vec))
+                        .map(|log| String::from_utf8_lossy(&log).into_owned())
+                        .unwrap_or_default();
+                    let port = self.settings.port;
+                    let conflict = match listener::port_conflict(&log, port) {
+                        Some(PortConflict::Tcp(message)) => TcpPortConflictError(anyhow::anyhow!(
+                            "TCP/IP port {port} is in use: {message}"
+                        )),
+                        Some(PortConflict::Socket(message)) => {
+                            SocketConflictError(anyhow::anyhow!(
+                                "Unix-domain socket of port {port} is in use: {message}"
+                            ))
+                        }
+                        None => return Err(DatabaseStartError(error.into())),
+                    };
+                    if !self.dynamic_port || attempt >= START_ATTEMPTS {
+                        return Err(conflict);
+                    }
+                    warn!("{conflict}; retrying with a new port");
+                    self.settings.port = random_port()?;
+                    attempt += 1;
+                }
+            }
+        }
+        self.started = true;
+        if let Some(server_log) = server_log {
+            self.replace_server_log(Some(server_log));
         }
 
+        let port = listener::bound_port(&self.settings.data_dir)?;
+        if port != self.settings.port {
+            warn!(
+                "Database bound to port {port} instead of port {}",
+                self.settings.port
+            );
+            self.settings.port = port;
+        }
+        self.previous_port = previous_port.filter(|previous_port| *previous_port != port);
+        if let Some(previous_port) = self.previous_port {
+            telemetry::record("postgresql.previous_port", previous_port);
+        }
         debug!(
-            "Starting database {} on port {}",
+            "Started database {} on port {}",
             self.settings.data_dir.to_string_lossy(),
             self.settings.port
         );
-        let start_log = self.settings.data_dir.join("start.log");
-        let options = format!("-F -p {}", self.settings.port);
-        let pg_ctl = PgCtlBuilder::from(&self.settings)
-            .mode(Start)
-            .pgdata(&self.settings.data_dir)
-            .log(start_log)
-            .options(options)
-            .wait();
+        self.create_preload_extensions().await?;
+        self.apply_default_text_search_config().await?;
+        if let Err(error) = self.apply_seed().await {
+            if let Err(stop_error) = self.stop().await {
+                warn!("Failed to stop database after the seed failed: {stop_error}");
+            }
+            return Err(error);
+        }
 
-        match self.execute_command(pg_ctl).await {
+        if !self.settings.temporary {
+            write(
+                self.settings.data_dir.join(PORT_FILE),
+                self.settings.port.to_string(),
+            )?;
+        }
+        for env_file in &self.settings.env_files {
+            env_file.write(&self.settings)?;
+        }
+        if let Ok(env_files) = self.env_files.lock() {
+            for env_file in env_files.iter() {
+                env_file.write(&self.settings)?;
+            }
+        }
+        #[cfg(feature = "tokio")]
+        {
+            let connection_info = self.connection_info();
+            self.connection_info.send_if_modified(|current| {
+                if *current == connection_info {
+                    return false;
+                }
+                *current = connection_info;
+                true
+            });
+        }
+
+        self.update_description();
+        telemetry::record("server.port", u64::from(self.settings.port));
+        telemetry::phase_completed("start", started);
+        Ok(())
+    }
+
+    /// Create the extensions provided by the [preload libraries](Settings::preload_libraries) in
+    /// the maintenance database; libraries that do not provide an extension (e.g.
+    /// `auto_explain`) are skipped.
+    async fn create_preload_extensions(&self) -> Result<()> {
+        if self.settings.preload_libraries.is_empty() {
+            return Ok(());
+        }
+
+        let libraries = self
+            .settings
+            .preload_libraries
+            .iter()
+            .map(quote_literal)
+            .collect::<Vec<String>>()
+            .join(", ");
+        let sql = format!("SELECT name FROM pg_available_extensions WHERE name IN ({libraries})");
+        let extensions = match self.maintenance_query(sql).await {
+            Ok(rows) => rows.into_iter().flatten().collect::<Vec<String>>(),
+            Err(error) => return Err(DatabaseStartError(error.into())),
+        };
+
+        for extension in extensions {
+            debug!("Creating extension {extension}");
+            let sql = format!(
+                "CREATE EXTENSION IF NOT EXISTS {}",
+                quote_identifier(&extension)
+            );
+            if let Err(error) = self.maintenance_query(sql).await {
+                return Err(DatabaseStartError(error.into()));
+            }
+        }
+        Ok(())
+    }
+
+    /// Set the [default text search configuration](Settings::default_text_search_config) of the
+    /// existing databases; template databases are skipped, since the setting is not copied to
+    /// the databases created from them.
+    async fn apply_default_text_search_config(&self) -> Result<()> {
+        if self.settings.default_text_search_config.is_none() {
+            return Ok(());
+        }
+
+        let sql = "SELECT datname FROM pg_database WHERE datallowconn AND NOT datistemplate";
+        let database_names = match self.maintenance_query(sql).await {
+            Ok(rows) => rows.into_iter().flatten().collect::<Vec<String>>(),
+            Err(error) => return Err(DatabaseStartError(error.into())),
+        };
+        for database_name in database_names {
+            if let Err(error) = self.set_default_text_search_config(&database_name).await {
+                return Err(DatabaseStartError(error.into()));
+            }
+        }
+        Ok(())
+    }
+
+    /// Set the [default text search configuration](Settings::default_text_search_config) of the
+    /// database, when configured
+    async fn set_default_text_search_config(
+        &self,
+        database_name: &str,
+    ) -> postgresql_commands::Result<()> {
+        let Some(default_text_search_config) = &self.settings.default_text_search_config else {
+            return Ok(());
+        };
+        debug!("Setting the default text search configuration of database {database_name}");
+        let sql = format!(
+            "ALTER DATABASE {} SET default_text_search_config = {}",
+            quote_identifier(database_name),
+            quote_literal(default_text_search_config)
+        );
+        self.maintenance_query(sql).await?;
+        Ok(())
+    }
+
+    /// Apply the [seed](Settings::seed) when it is pending, i.e. on the first start after the
+    /// data directory was initialized. The seed is applied in a single transaction, so a failed
+    /// seed leaves no objects behind and remains pending.
+    async fn apply_seed(&self) -> Result<()> {
+        let Some(seed) = &self.settings.seed else {
+            return Ok(());
+        };
+        let seed_file = self.settings.data_dir.join(SEED_FILE);
+        if read_to_string(&seed_file).map_or(true, |state| state.trim() != SEED_PENDING) {
+            return Ok(());
+        }
+
+        debug!(
+            "Applying seed to database {}",
+            self.settings.data_dir.to_string_lossy()
+        );
+        let psql = || {
+            PsqlBuilder::from(&self.settings)
+                .username(BOOTSTRAP_SUPERUSER)
+                .variable(("ON_ERROR_STOP", "1"))
+                .no_psqlrc()
+                .quiet()
+                .single_transaction()
+        };
+        let result = match seed {
+            Seed::SqlFile(path) => self.execute_maintenance_command(psql().file(path)).await,
+            Seed::SqlString(sql) => self.execute_maintenance_command(psql().command(sql)).await,
+            Seed::DumpArchive(path) => {
+                let pg_restore = PgRestoreBuilder::from(&self.settings)
+                    .username(BOOTSTRAP_SUPERUSER)
+                    .dbname(&self.settings.maintenance_database)
+                    .single_transaction()
+                    .exit_on_error()
+                    .archive(path);
+                self.execute_command(pg_restore).await
+            }
+        };
+
+        match result {
             Ok((_stdout, _stderr)) => {
+                write(&seed_file, SEED_APPLIED)?;
                 debug!(
-                    "Started database {} on port {}",
-                    self.settings.data_dir.to_string_lossy(),
-                    self.settings.port
+                    "Applied seed to database {}",
+                    self.settings.data_dir.to_string_lossy()
                 );
+                if self.settings.analyze_after_load {
+                    // The seed is applied; missing statistics do not warrant failing the start
+                    if let Err(error) = self.analyze(&self.settings.maintenance_database, &[]).await
+                    {
+                        warn!("Failed to analyze the seeded database: {error}");
+                    }
+                }
                 Ok(())
             }
-            Err(error) => Err(DatabaseStartError(error.into())),
+            Err(error) => Err(SeedError(error.into())),
+        }
+    }
+
+    /// Get the port a server started with a dynamic port should reuse: the port of the previous
+    /// start, which is persisted in the data directory for servers that are not temporary
+    fn previous_port_to_reuse(&self) -> Option<u16> {
+        if self.settings.port != 0 {
+            Some(self.settings.port)
+        } else if self.settings.temporary {
+            None
+        } else {
+            read_to_string(self.settings.data_dir.join(PORT_FILE))
+                .ok()
+                .and_then(|port| port.trim().parse::<u16>().ok())
+        }
+    }
+
+    /// Get the sockets the server listens on: the TCP/IP addresses of `listen_addresses` and
+    /// the Unix-domain sockets in `unix_socket_directories`, on the port the server is bound to.
+    /// No addresses are returned when the server is not started.
+    #[instrument(skip(self))]
+    pub async fn bound_addresses(&self) -> Result<Vec<BoundAddress>> {
+        if self.status() != Status::Started {
+            debug!("Server is not started; no addresses are bound");
+            return Ok(Vec::new());
+        }
+
+        match self.maintenance_query(listener::SETTINGS_QUERY).await {
+            Ok(rows) => Ok(listener::parse_bound_addresses(&rows)),
+            Err(error) => Err(ListenerError(error.into())),
+        }
+    }
+
+    /// Check whether the server accepts connections, without authenticating; see [Readiness].
+    /// The [native probe](ReadinessProbe::Native) opens a connection to the server (a
+    /// Unix-domain socket when the host is a directory) and interprets the response to the
+    /// startup message, falling back to pg_isready when the response cannot be classified. The
+    /// [process probe](ReadinessProbe::Process) always runs pg_isready.
+    #[instrument(skip(self))]
+    pub async fn ping(&self) -> Result<Readiness> {
+        if self.settings.readiness_probe == ReadinessProbe::Native {
+            if let Some(readiness) = self.ping_native() {
+                debug!("Database readiness: {readiness:?}");
+                return Ok(readiness);
+            }
+            debug!("Unexpected response to the startup message; falling back to pg_isready");
+        }
+
+        let pg_isready =
+            PgIsReadyBuilder::from(&self.settings).dbname(&self.settings.maintenance_database);
+        let stdout = match self.execute_command(pg_isready).await {
+            Ok((stdout, _stderr)) => stdout,
+            // pg_isready exits with a non-zero code when the server is not accepting connections
+            Err(postgresql_commands::Error::CommandError { stdout, .. }) => stdout,
+            Err(error) => return Err(ReadinessError(error.into())),
+        };
+        match probe::parse_pg_isready_output(&stdout) {
+            Some(readiness) => {
+                debug!("Database readiness: {readiness:?}");
+                Ok(readiness)
+            }
+            None => Err(ReadinessError(anyhow::anyhow!(
+                "Unexpected pg_isready output: {}",
+                stdout.trim()
+            ))),
+        }
+    }
+
+    /// Probe the server with the startup exchange of the wire protocol
+    fn ping_native(&self) -> Option<Readiness> {
+        let host = self.settings.effective_host();
+        let timeout = self.settings.timeout.unwrap_or(Duration::from_secs(5));
+        let user = &self.settings.username;
+        let database = &self.settings.maintenance_database;
+        #[cfg(unix)]
+        if host.starts_with('/') {
+            let socket = Path::new(host).join(format!(".s.PGSQL.{}", self.settings.port));
+            return probe::ping_unix(&socket, user, database, timeout);
         }
+        probe::ping_tcp(host, self.settings.port, user, database, timeout)
     }
 
     /// Stop the database gracefully (smart mode) and wait for the shutdown to complete.
-    #[instrument]
+    #[cfg_attr(not(feature = "otel"), instrument)]
+    #[cfg_attr(
+        feature = "otel",
+        instrument(
+            name = "postgresql.stop",
+            skip(self),
+            fields(postgresql.data_dir = %self.settings.data_dir.display())
+        )
+    )]
     pub async fn stop(&self) -> Result<()> {
+        let started = Instant::now();
         debug!(
             "Stopping database {}",
             self.settings.data_dir.to_string_lossy()
         );
+        self.session_pool.close_idle();
         let pg_ctl = PgCtlBuilder::from(&self.settings)
             .mode(Stop)
             .pgdata(&self.settings.data_dir)
@@ -294,13 +1047,426 @@ impl PostgreSQL {
                     "Stopped database {}",
                     self.settings.data_dir.to_string_lossy()
                 );
+                self.replace_server_log(None);
+                self.update_description();
+                telemetry::phase_completed("stop", started);
                 Ok(())
             }
             Err(error) => Err(DatabaseStopError(error.into())),
         }
     }
 
-    /// Create a new database with the given name.
+    /// Stop the server and wait until the data directory is safe to archive or copy: the
+    /// postmaster and all of its child processes have exited, `postmaster.pid` is gone and
+    /// pg_controldata reports a clean shutdown. A server that is already stopped is only
+    /// checked.
+    ///
+    /// ```no_run
+    /// # async fn example(mut postgresql: postgresql_embedded::PostgreSQL) -> postgresql_embedded::Result<()> {
+    /// let data_dir = postgresql.stop_and_quiesce().await?;
+    /// data_dir.archive_to("/tmp/data.tar.gz")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[instrument(skip(self))]
+    pub async fn stop_and_quiesce(&mut self) -> Result<QuiescedDataDir<'_>> {
+        let data_dir = &self.settings.data_dir;
+        let mut pids = Vec::new();
+        if self.status() == Status::Started {
+            // Collect the process ids first; they are no longer available once stopped
+            if let Ok(pid) = crash::postmaster_pid(data_dir) {
+                pids.push(pid);
+            }
+            match self.maintenance_query(quiesce::SERVER_PIDS_QUERY).await {
+                Ok(rows) => pids.extend(
+                    rows.into_iter()
+                        .flatten()
+                        .filter_map(|pid| pid.parse::<u32>().ok()),
+                ),
+                Err(error) => return Err(QuiesceError(error.into())),
+            }
+            self.stop().await?;
+        }
+
+        let start = Instant::now();
+        while let Some(pid) = pids.iter().copied().find(|pid| crash::process_exists(*pid)) {
+            if start.elapsed() > quiesce::QUIESCE_TIMEOUT {
+                return Err(QuiesceError(anyhow::anyhow!(
+                    "Timed out waiting for server process {pid} to exit"
+                )));
+            }
+            #[cfg(feature = "tokio")]
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            #[cfg(not(feature = "tokio"))]
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        if data_dir.join("postmaster.pid").exists() {
+            return Err(QuiesceError(anyhow::anyhow!(
+                "postmaster.pid still exists in {}",
+                data_dir.to_string_lossy()
+            )));
+        }
+
+        let pg_controldata = PgControlDataBuilder::from(&self.settings).pgdata(data_dir);
+        let control_data = match self.execute_command(pg_controldata).await {
+            Ok((stdout, _stderr)) => stdout,
+            Err(error) => return Err(QuiesceError(error.into())),
+        };
+        match quiesce::parse_cluster_state(&control_data) {
+            Some(quiesce::SHUT_DOWN) => {}
+            state => {
+                return Err(QuiesceError(anyhow::anyhow!(
+                    "Cluster state of {} is {}; expected {}",
+                    data_dir.to_string_lossy(),
+                    state.unwrap_or("unknown"),
+                    quiesce::SHUT_DOWN
+                )))
+            }
+        }
+
+        debug!(
+            "Quiesced database {}",
+            self.settings.data_dir.to_string_lossy()
+        );
+        Ok(QuiescedDataDir::new(&self.settings.data_dir))
+    }
+
+    /// Stop the server at the end of a test as quickly as the [lifecycle](Settings::temporary)
+    /// allows, and remove the data directory of a temporary instance. Without a mode, a
+    /// temporary instance is stopped in [immediate](TeardownMode::Immediate) mode, since a
+    /// shutdown checkpoint would only flush data that is about to be removed, and a persistent
+    /// instance in [fast](TeardownMode::Fast) mode, after a
+    /// [checkpoint](Settings::checkpoint_before_stop) when configured. The time taken by each
+    /// phase is returned in the [report](TeardownReport).
+    #[instrument(skip(self))]
+    pub async fn teardown(self, mode: Option<TeardownMode>) -> Result<TeardownReport> {
+        let mode = mode.unwrap_or(if self.settings.temporary {
+            TeardownMode::Immediate
+        } else {
+            TeardownMode::Fast
+        });
+        let started = self.status() == Status::Started;
+
+        let checkpoint_timeout = match mode {
+            TeardownMode::Fast if started => self.settings.checkpoint_before_stop,
+            _ => None,
+        };
+        let mut checkpoint = None;
+        if let Some(timeout) = checkpoint_timeout {
+            let start = Instant::now();
+            // The checkpoint only shortens the shutdown; the server is stopped regardless
+            if let Err(error) = self
+                .maintenance_query(teardown::checkpoint_statement(timeout))
+                .await
+            {
+                warn!("Failed to checkpoint before stopping the database: {error}");
+            }
+            checkpoint = Some(start.elapsed());
+        }
+
+        let start = Instant::now();
+        if started {
+            let shutdown_mode = match mode {
+                TeardownMode::Fast => Fast,
+                TeardownMode::Immediate => Immediate,
+            };
+            debug!(
+                "Stopping database {} in {shutdown_mode:?} mode",
+                self.settings.data_dir.to_string_lossy()
+            );
+            let pg_ctl = PgCtlBuilder::from(&self.settings)
+                .mode(Stop)
+                .pgdata(&self.settings.data_dir)
+                .shutdown_mode(shutdown_mode)
+                .wait();
+            if let Err(error) = self.execute_command(pg_ctl).await {
+                return Err(DatabaseStopError(error.into()));
+            }
+        }
+        let shutdown = start.elapsed();
+        self.replace_server_log(None);
+
+        let removed = self.settings.temporary;
+        if removed {
+            self.remove_temporary_files();
+        } else {
+            self.update_description();
+        }
+
+        let report = TeardownReport {
+            mode,
+            checkpoint,
+            shutdown,
+            removed,
+        };
+        debug!("Tore down database: {report:?}");
+        Ok(report)
+    }
+
+    /// Remove the data directory and the other files of a temporary instance
+    fn remove_temporary_files(&self) {
+        let _ = remove_dir_all(&self.settings.data_dir);
+        let _ = remove_dir_all(tablespace::managed_dir(&self.settings.data_dir));
+        if self.settings.socket_dir().is_none()
+            && self.settings.socket_dir_mode == SocketDirMode::Managed
+        {
+            let _ = remove_dir_all(socket::managed_dir(&self.settings.data_dir));
+        }
+        let _ = remove_file(self.description_path());
+        if !self.settings.keep_files_on_failure {
+            let _ = remove_dir_all(scoped_files::files_dir(&self.settings.data_dir));
+        }
+        let _ = remove_file(&self.settings.password_file);
+    }
+
+    /// Crash the server on purpose to verify that an application survives crash recovery; see
+    /// [`CrashMode`] for the available unclean actions. As a safety interlock, only a server
+    /// started by this instance can be crashed. Use [recover](Self::recover) to bring the server
+    /// back up.
+    #[instrument(skip(self))]
+    pub async fn crash(&mut self, mode: CrashMode) -> Result<()> {
+        if !self.started || self.status() != Status::Started {
+            return Err(CrashError(anyhow::anyhow!(
+                "Refusing to crash a server that was not started by this instance"
+            )));
+        }
+        self.verify_managed().await?;
+        self.crash_log_offset = Some(self.start_log_len());
+
+        match mode {
+            CrashMode::ImmediateShutdown => {
+                let pg_ctl = PgCtlBuilder::from(&self.settings)
+                    .mode(Stop)
+                    .pgdata(&self.settings.data_dir)
+                    .shutdown_mode(Immediate)
+                    .wait();
+                if let Err(error) = self.execute_command(pg_ctl).await {
+                    return Err(CrashError(error.into()));
+                }
+            }
+            CrashMode::KillPostmaster => {
+                let postmaster_pid = crash::postmaster_pid(&self.settings.data_dir)?;
+                let server_pids = match self
+                    .maintenance_query(
+                        "SELECT pid FROM pg_stat_activity WHERE pid <> pg_backend_pid()",
+                    )
+                    .await
+                {
+                    Ok(rows) => parse_pids(rows),
+                    Err(error) => return Err(CrashError(error.into())),
+                };
+                let socket_directories =
+                    match self.maintenance_query("SHOW unix_socket_directories").await {
+                        Ok(rows) => rows
+                            .into_iter()
+                            .flatten()
+                            .collect::<Vec<String>>()
+                            .join(","),
+                        Err(error) => return Err(CrashError(error.into())),
+                    };
+                debug!("Killing postmaster {postmaster_pid}");
+                crash::kill_process(postmaster_pid)?;
+
+                // The server can only be started again once every server process has exited
+                self.wait_until("server processes to exit", || {
+                    !crash::process_exists(postmaster_pid)
+                        && !server_pids.iter().any(|pid| crash::process_exists(*pid))
+                })
+                .await?;
+
+                // Remove the lock files left behind by the killed postmaster, so that a
+                // postmaster that has exited but not yet been reaped does not block the restart
+                let mut lock_files = vec![self.settings.data_dir.join("postmaster.pid")];
+                for socket_directory in socket_directories.split(',').map(str::trim) {
+                    if !socket_directory.is_empty() && !socket_directory.starts_with('@') {
+                        lock_files.push(socket::lock_file_path(
+                            Path::new(socket_directory),
+                            self.settings.port,
+                        ));
+                    }
+                }
+                for lock_file in lock_files {
+                    crash::remove_lock_file(&lock_file, postmaster_pid)?;
+                }
+            }
+            CrashMode::KillRandomBackend => {
+                let sql = "SELECT pid FROM pg_stat_activity WHERE backend_type = 'client backend' AND pid <> pg_backend_pid() ORDER BY random() LIMIT 1";
+                let backend_pid = match self.maintenance_query(sql).await {
+                    Ok(rows) => parse_pids(rows).into_iter().next(),
+                    Err(error) => return Err(CrashError(error.into())),
+                };
+                let Some(backend_pid) = backend_pid else {
+                    return Err(CrashError(anyhow::anyhow!("No client backend to kill")));
+                };
+                debug!("Killing backend {backend_pid}");
+                crash::kill_process(backend_pid)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Bring the server back up after a [crash](Self::crash) and return the crash recovery
+    /// details parsed from the server log. The server is started again if it is not running;
+    /// after a backend was killed, the server performs crash recovery on its own and this waits
+    /// for the recovery to complete.
+    #[instrument(skip(self))]
+    pub async fn recover(&mut self) -> Result<RecoveryInfo> {
+        let offset = match self.crash_log_offset.take() {
+            Some(offset) => offset,
+            None => self.start_log_len(),
+        };
+        let postmaster_running =
+            crash::postmaster_pid(&self.settings.data_dir).is_ok_and(crash::process_exists);
+        if !postmaster_running {
+            self.start().await?;
+        }
+
+        let mut log = String::new();
+        self.wait_until("crash recovery to complete", || {
+            log = self.read_start_log(offset);
+            log.contains(READY_MESSAGE)
+        })
+        .await?;
+        let recovery_info = crash::parse_recovery_info(&log);
+        debug!("Recovered database: {recovery_info:?}");
+        Ok(recovery_info)
+    }
+
+    /// Replace the [forwarder](Settings::trace_server_logs) of the server log; the previous
+    /// forwarder emits the remaining lines of the log and stops.
+    fn replace_server_log(&self, server_log: Option<ServerLogForwarder>) {
+        let previous = match self.server_log.lock() {
+            Ok(mut current) => std::mem::replace(&mut *current, server_log),
+            Err(_) => None,
+        };
+        // Joining the forwarder thread happens after the lock is released
+        drop(previous);
+    }
+
+    /// Get the length of the server log
+    fn start_log_len(&self) -> u64 {
+        metadata(self.settings.data_dir.join("start.log")).map_or(0, |metadata| metadata.len())
+    }
+
+    /// Read the server log, starting at the given offset
+    fn read_start_log(&self, offset: u64) -> String {
+        let log = read(self.settings.data_dir.join("start.log")).unwrap_or_default();
+        let offset = usize::try_from(offset).unwrap_or(usize::MAX).min(log.len());
+        String::from_utf8_lossy(&log[offset..]).into_owned()
+    }
+
+    /// Wait until the condition is met, checking it periodically for up to
+    /// [`RECOVERY_TIMEOUT`]
+    async fn wait_until<F: FnMut() -> bool>(
+        &self,
+        description: &str,
+        mut condition: F,
+    ) -> Result<()> {
+        let start = Instant::now();
+        while !condition() {
+            if start.elapsed() > RECOVERY_TIMEOUT {
+                return Err(CrashError(anyhow::anyhow!(
+                    "Timed out waiting for {description}"
+                )));
+            }
+            #[cfg(feature = "tokio")]
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            #[cfg(not(feature = "tokio"))]
+            std::thread::sleep(Duration::from_millis(100));
+        }
+        Ok(())
+    }
+
+    /// Rewind this (target) server to the live source server, e.g. after the source was promoted
+    /// from a standby of this server and both servers diverged. The target is stopped if it is
+    /// running, and is configured to follow the source as a standby when it is started again.
+    /// The target must have been initialized with data checksums or run with `wal_log_hints`
+    /// enabled, and must retain the WAL since the last checkpoint before the servers diverged
+    /// (e.g. with [wal_keep_size](Settings::wal_keep_size)).
+    #[instrument(skip(self, source))]
+    pub async fn rewind_from(&mut self, source: &PostgreSQL) -> Result<()> {
+        if self.status() == Status::Started {
+            self.stop().await?;
+        }
+
+        let source_settings = Settings {
+            username: BOOTSTRAP_SUPERUSER.to_string(),
+            ..source.settings.clone()
+        };
+        debug!(
+            "Rewinding database {} from {}:{}",
+            self.settings.data_dir.to_string_lossy(),
+            source_settings.effective_host(),
+            source_settings.port
+        );
+        let pg_rewind = PgRewindBuilder::from(&self.settings)
+            .target_pgdata(&self.settings.data_dir)
+            .source_server(source_settings.maintenance_url())
+            .write_recovery_conf();
+
+        match self.execute_command(pg_rewind).await {
+            Ok((_stdout, _stderr)) => {
+                debug!(
+                    "Rewound database {}",
+                    self.settings.data_dir.to_string_lossy()
+                );
+                Ok(())
+            }
+            Err(postgresql_commands::Error::CommandError { stderr, .. })
+                if stderr.contains("could not find common ancestor")
+                    || stderr.contains("are from different systems") =>
+            {
+                Err(RewindError(anyhow::anyhow!(
+                    "The timelines of the source and target servers are incompatible; the source \
+                     must share its history with the target: {}",
+                    stderr.trim()
+                )))
+            }
+            Err(error) => Err(RewindError(error.into())),
+        }
+    }
+
+    /// Write a connection environment file for attaching external client tools to the server.
+    /// The file is rewritten every time the server is started, so that it stays in sync when the
+    /// port changes across restarts. The password is omitted; configure an [`EnvFile`] that
+    /// [includes the password](EnvFile::include_password) in the
+    /// [env files](Settings::env_files) of the settings to write it.
+    #[instrument(skip(self, path), fields(path = %path.as_ref().display()))]
+    pub fn write_env_file<P: AsRef<Path>>(&self, path: P, format: EnvFileFormat) -> Result<()> {
+        let env_file = EnvFile::new(path.as_ref(), format);
+        env_file.write(&self.settings)?;
+        if let Ok(mut env_files) = self.env_files.lock() {
+            env_files.retain(|registered| registered.path() != env_file.path());
+            env_files.push(env_file);
+        }
+        Ok(())
+    }
+
+    /// Run an interactive psql session connected to the server, for quick manual inspection.
+    /// On Unix, the current process is replaced with psql and this function only returns if psql
+    /// could not be executed. On other platforms, psql is run with the standard input and output
+    /// of the current process and this function returns once psql exits.
+    pub fn exec_psql_interactive(&self) -> Result<()> {
+        let mut command = PsqlBuilder::from(&self.settings).build();
+        self.set_locale_environment(&mut command);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::CommandExt;
+            Err(command.exec().into())
+        }
+
+        #[cfg(not(unix))]
+        {
+            command.status()?;
+            Ok(())
+        }
+    }
+
+    /// Create a new database with the given name. The
+    /// [default text search configuration](Settings::default_text_search_config) is set on the
+    /// new database when configured.
     #[instrument(skip(database_name))]
     pub async fn create_database<S: AsRef<str>>(&self, database_name: S) -> Result<()> {
         debug!(
@@ -309,12 +1475,14 @@ impl PostgreSQL {
             self.settings.host,
             self.settings.port
         );
+        // Idle sessions connected to the template database would prevent it from being copied
+        self.session_pool.close_idle();
         let psql = PsqlBuilder::from(&self.settings)
             .command(format!("CREATE DATABASE \"{}\"", database_name.as_ref()))
             .username(BOOTSTRAP_SUPERUSER)
             .no_psqlrc();
 
-        match self.execute_command(psql).await {
+        match self.execute_maintenance_command(psql).await {
             Ok((_stdout, _stderr)) => {
                 debug!(
                     "Created database {} for {}:{}",
@@ -322,19 +1490,54 @@ impl PostgreSQL {
                     self.settings.host,
                     self.settings.port
                 );
-                Ok(())
             }
-            Err(error) => Err(CreateDatabaseError(error.into())),
+            Err(error) => return Err(CreateDatabaseError(error.into())),
         }
+        self.set_default_text_search_config(database_name.as_ref())
+            .await
+            .map_err(|error| CreateDatabaseError(error.into()))
     }
 
-    /// Check if a database with the given name exists.
-    #[instrument(skip(database_name))]
-    pub async fn database_exists<S: AsRef<str>>(&self, database_name: S) -> Result<bool> {
+    /// Create a new database with the given name and provision it with the
+    /// [options](CreateDatabaseOptions): schemas, database-level settings, default privileges
+    /// and revocations. The provisioning is applied in a single transaction right after the
+    /// database is created; if it fails, the database is dropped so that no partially
+    /// provisioned database is left behind.
+    #[instrument(skip(self, database_name, options))]
+    pub async fn create_database_with_options<S: AsRef<str>>(
+        &self,
+        database_name: S,
+        options: &CreateDatabaseOptions,
+    ) -> Result<()> {
+        let database_name = database_name.as_ref();
+        let statements = database_options::provisioning_statements(database_name, options)?;
+        self.create_database(database_name).await?;
+        if statements.is_empty() {
+            return Ok(());
+        }
+
         debug!(
-            "Checking if database {} exists for {}:{}",
-            database_name.as_ref(),
-            self.settings.host,
+            "Provisioning database {database_name} with {} statements",
+            statements.len()
+        );
+        // The statements are sent in a single query, which runs as one transaction
+        if let Err(error) = self.query(database_name, statements.join(";\n")).await {
+            if let Err(drop_error) = self.drop_database(database_name).await {
+                warn!("Failed to drop database {database_name} after provisioning failed: {drop_error}");
+            }
+            return Err(CreateDatabaseError(error.into()));
+        }
+        debug!("Provisioned database {database_name}");
+        Ok(())
+    }
+
+    /// Check if a database with the given name exists.
+    #[instrument(skip(database_name))]
+    pub async fn database_exists<S: AsRef<str>>(&self, database_name: S) -> Result<bool> {
+        debug!(
+            "Checking if database {} exists for {}:{}",
+            database_name.as_ref(),
+            self.settings.host,
             self.settings.port
         );
         let psql = PsqlBuilder::from(&self.settings)
@@ -346,7 +1549,7 @@ impl PostgreSQL {
             .no_psqlrc()
             .tuples_only();
 
-        match self.execute_command(psql).await {
+        match self.execute_maintenance_command(psql).await {
             Ok((stdout, _stderr)) => match stdout.trim() {
                 "1" => Ok(true),
                 _ => Ok(false),
@@ -358,12 +1561,14 @@ impl PostgreSQL {
     /// Drop a database with the given name.
     #[instrument(skip(database_name))]
     pub async fn drop_database<S: AsRef<str>>(&self, database_name: S) -> Result<()> {
+        self.verify_managed().await?;
         debug!(
             "Dropping database {} for {}:{}",
             database_name.as_ref(),
             self.settings.host,
             self.settings.port
         );
+        self.session_pool.close_idle();
         let psql = PsqlBuilder::from(&self.settings)
             .command(format!(
                 "DROP DATABASE IF EXISTS \"{}\"",
@@ -372,7 +1577,7 @@ impl PostgreSQL {
             .username(BOOTSTRAP_SUPERUSER)
             .no_psqlrc();
 
-        match self.execute_command(psql).await {
+        match self.execute_maintenance_command(psql).await {
             Ok((_stdout, _stderr)) => {
                 debug!(
                     "Dropped database {} for {}:{}",
@@ -386,60 +1591,2436 @@ impl PostgreSQL {
         }
     }
 
-    #[cfg(not(feature = "tokio"))]
-    /// Execute a command and return the stdout and stderr as strings.
-    async fn execute_command<B: CommandBuilder>(
+    /// Produce a deterministic snapshot of the schema of the database for golden tests, from
+    /// `pg_dump --schema-only` without ownership and tablespaces. Lines that vary between runs
+    /// or machines, such as the server version, are removed and the objects are sorted by
+    /// name, type and schema, so the same schema always produces the same snapshot. The
+    /// snapshot is meant for comparison rather than for restoring.
+    #[instrument(skip(self, database_name))]
+    pub async fn schema_snapshot<S: AsRef<str>>(&self, database_name: S) -> Result<String> {
+        debug!(
+            "Taking schema snapshot of database {}",
+            database_name.as_ref()
+        );
+        let pg_dump = PgDumpBuilder::from(&self.settings)
+            .username(BOOTSTRAP_SUPERUSER)
+            .dbname(database_name.as_ref())
+            .schema_only()
+            .no_owner()
+            .no_tablespaces();
+
+        match self.execute_command(pg_dump).await {
+            Ok((stdout, _stderr)) => Ok(schema_snapshot::normalize(&stdout)),
+            Err(error) => Err(BackupError(error.into())),
+        }
+    }
+
+    /// Back up the database to a file in the pg_dump custom archive format; the backup can be
+    /// restored with pg_restore. Use [dump](Self::dump) to report progress or to cancel the
+    /// backup.
+    #[instrument(skip(self, database_name, path))]
+    pub async fn backup<S: AsRef<str>, P: Into<PathBuf>>(
         &self,
-        command_builder: B,
-    ) -> postgresql_commands::Result<(String, String)> {
-        let mut command = command_builder.build();
-        command.execute()
+        database_name: S,
+        path: P,
+    ) -> Result<()> {
+        self.dump(database_name, path, |_| {}, &CancellationToken::new())
+            .await
     }
 
-    #[cfg(feature = "tokio")]
-    /// Execute a command and return the stdout and stderr as strings.
-    #[instrument(level = "debug")]
-    async fn execute_command<B: CommandBuilder>(
+    /// Back up a single table of the database to a file in the pg_dump custom archive format. The
+    /// table name is matched exactly (see [QualifiedName::to_pattern]); an error is returned if
+    /// the table does not exist.
+    #[instrument(skip(self, database_name, path))]
+    pub async fn backup_table<S: AsRef<str>, P: Into<PathBuf>>(
+        &self,
+        database_name: S,
+        table: &QualifiedName,
+        path: P,
+    ) -> Result<()> {
+        let path = path.into();
+        debug!(
+            "Backing up table {table} of database {} to {}",
+            database_name.as_ref(),
+            path.to_string_lossy()
+        );
+        let pg_dump = PgDumpBuilder::from(&self.settings)
+            .username(BOOTSTRAP_SUPERUSER)
+            .dbname(database_name.as_ref())
+            .format("custom")
+            .strict_table(table)
+            .file(&path);
+
+        match self.execute_command(pg_dump).await {
+            Ok((_stdout, _stderr)) => {
+                self.record_backup();
+                Ok(())
+            }
+            Err(error) => Err(BackupError(error.into())),
+        }
+    }
+
+    /// Back up the tables selected by a query to a file in the pg_dump custom archive format,
+    /// e.g. to create a fixture from a subset of the database. The query is executed against the
+    /// database and must return the schema and name of each table; the tables are matched
+    /// exactly. Returns the tables backed up. An error is returned if the query selects no
+    /// tables, since pg_dump would otherwise back up the whole database.
+    #[instrument(skip(self, database_name, query, path))]
+    pub async fn backup_tables_from_query<S, Q, P>(
+        &self,
+        database_name: S,
+        query: Q,
+        path: P,
+    ) -> Result<Vec<QualifiedName>>
+    where
+        S: AsRef<str>,
+        Q: AsRef<str>,
+        P: Into<PathBuf>,
+    {
+        let path = path.into();
+        let rows = match self.query(database_name.as_ref(), query).await {
+            Ok(rows) => rows,
+            Err(error) => return Err(BackupError(error.into())),
+        };
+        let tables = rows
+            .iter()
+            .map(|row| match row.as_slice() {
+                [schema, name] => Ok(QualifiedName::qualified(schema, name)),
+                _ => Err(BackupError(anyhow::anyhow!(
+                    "query must return the schema and name of each table"
+                ))),
+            })
+            .collect::<Result<Vec<QualifiedName>>>()?;
+        if tables.is_empty() {
+            return Err(BackupError(anyhow::anyhow!("query selected no tables")));
+        }
+
+        debug!(
+            "Backing up {} tables of database {} to {}",
+            tables.len(),
+            database_name.as_ref(),
+            path.to_string_lossy()
+        );
+        let pg_dump = PgDumpBuilder::from(&self.settings)
+            .username(BOOTSTRAP_SUPERUSER)
+            .dbname(database_name.as_ref())
+            .format("custom")
+            .qualified_tables(&tables)
+            .strict_names()
+            .file(&path);
+
+        match self.execute_command(pg_dump).await {
+            Ok((_stdout, _stderr)) => {
+                self.record_backup();
+                Ok(tables)
+            }
+            Err(error) => Err(BackupError(error.into())),
+        }
+    }
+
+    /// Restore a single table from a pg_dump archive into the database; the schema of the table
+    /// must exist. The table name is matched exactly; an error is returned if the table is not
+    /// in the archive.
+    ///
+    /// When `analyze` is set, or it is `None` and [analyze_after_load](Settings::analyze_after_load)
+    /// is set, the table is analyzed afterward and the [report](AnalyzeReport) is returned.
+    #[instrument(skip(self, database_name, path))]
+    pub async fn restore_table<S: AsRef<str>, P: Into<PathBuf>>(
+        &self,
+        database_name: S,
+        table: &QualifiedName,
+        path: P,
+        analyze: Option<bool>,
+    ) -> Result<Option<AnalyzeReport>> {
+        let path = path.into();
+        debug!(
+            "Restoring table {table} of database {} from {}",
+            database_name.as_ref(),
+            path.to_string_lossy()
+        );
+        let pg_restore = PgRestoreBuilder::from(&self.settings)
+            .username(BOOTSTRAP_SUPERUSER)
+            .dbname(database_name.as_ref())
+            .strict_table(table)
+            .exit_on_error()
+            .archive(&path);
+
+        if let Err(error) = self.execute_command(pg_restore).await {
+            return Err(RestoreError(error.into()));
+        }
+        self.analyze_after_load(analyze, database_name, std::slice::from_ref(table))
+            .await
+    }
+
+    /// Restore the database from a pg_dump archive in the custom, tar or directory format,
+    /// choosing the pg_restore options based on whether the database exists, so that the
+    /// database ends up with exactly the contents of the archive:
+    ///
+    /// - When the database exists, the objects of the archive are dropped before they are
+    ///   recreated (`--clean --if-exists`); objects that are not in the archive are left
+    ///   untouched. The database itself is not dropped, so its name, owner and database-level
+    ///   settings are kept.
+    /// - When the database does not exist, it is [created](Self::create_database) first and the
+    ///   archive is restored into it; the database is dropped again if the restore fails.
+    ///
+    /// `--create` is never used: it creates the database named in the archive rather than the
+    /// given one, and combined with `--clean` it drops and recreates that whole database. The
+    /// archive is restored in a single transaction that stops at the first error, so a failed
+    /// restore leaves an existing database unchanged.
+    ///
+    /// The entries of the archive are restored in the order of the archive; see
+    /// [restore_database_ordered](Self::restore_database_ordered) to restore them in another
+    /// order.
+    ///
+    /// When `analyze` is set, or it is `None` and [analyze_after_load](Settings::analyze_after_load)
+    /// is set, the database is analyzed afterward and the [report](AnalyzeReport) is returned.
+    #[instrument(skip(self, database_name, path))]
+    pub async fn restore_database<S: AsRef<str>, P: Into<PathBuf>>(
+        &self,
+        database_name: S,
+        path: P,
+        analyze: Option<bool>,
+    ) -> Result<Option<AnalyzeReport>> {
+        self.restore_database_ordered(database_name, path, &ReorderStrategy::Original, analyze)
+            .await
+    }
+
+    /// Restore the database from a pg_dump archive like [restore_database](Self::restore_database),
+    /// restoring the entries of the archive in the order chosen by the strategy; e.g.
+    /// [LargestDataFirst](ReorderStrategy::LargestDataFirst) with the
+    /// [table sizes](Self::table_sizes) of the database that was dumped loads the largest tables
+    /// first. The [table of contents](Self::list_archive) of the archive is
+    /// [reordered](postgresql_commands::toc::reorder) within the dependencies between its
+    /// entries and passed to pg_restore with `--use-list`.
+    #[instrument(skip(self, database_name, path, strategy))]
+    pub async fn restore_database_ordered<S: AsRef<str>, P: Into<PathBuf>>(
+        &self,
+        database_name: S,
+        path: P,
+        strategy: &ReorderStrategy,
+        analyze: Option<bool>,
+    ) -> Result<Option<AnalyzeReport>> {
+        let database_name = database_name.as_ref();
+        let path = path.into();
+        let exists = self.database_exists(database_name).await?;
+        let mut pg_restore = PgRestoreBuilder::from(&self.settings)
+            .username(BOOTSTRAP_SUPERUSER)
+            .dbname(database_name)
+            .single_transaction()
+            .exit_on_error()
+            .archive(&path);
+        // The list file is removed when the files are dropped, after the restore
+        let mut files =
+            ScopedFiles::new(&self.settings.data_dir, self.settings.keep_files_on_failure);
+        if *strategy != ReorderStrategy::Original {
+            let listing = self.list_archive(&path).await?;
+            let listing =
+                toc::reorder(&listing, strategy).map_err(|error| RestoreError(error.into()))?;
+            let use_list = files.create("use_list", listing.to_list().as_bytes())?;
+            pg_restore = pg_restore.use_list(use_list);
+        }
+        if exists {
+            self.verify_managed().await?;
+            debug!(
+                "Restoring existing database {database_name} from {}",
+                path.to_string_lossy()
+            );
+            pg_restore = pg_restore.clean().if_exists();
+        } else {
+            debug!(
+                "Restoring new database {database_name} from {}",
+                path.to_string_lossy()
+            );
+            self.create_database(database_name).await?;
+        }
+
+        if let Err(error) = self.execute_command(pg_restore).await {
+            files.fail();
+            if !exists {
+                if let Err(drop_error) = self.drop_database(database_name).await {
+                    warn!("Failed to drop database {database_name} after the restore failed: {drop_error}");
+                }
+            }
+            return Err(RestoreError(error.into()));
+        }
+        self.analyze_after_load(analyze, database_name, &[]).await
+    }
+
+    /// List the [table of contents](Toc) of a pg_dump archive in the custom, tar or directory
+    /// format, including the dependencies between its entries
+    #[instrument(skip(self, path))]
+    pub async fn list_archive<P: Into<PathBuf>>(&self, path: P) -> Result<Toc> {
+        let path = path.into();
+        let pg_restore = PgRestoreBuilder::from(&self.settings)
+            .list()
+            .verbose()
+            .archive(&path);
+        match self.execute_command(pg_restore).await {
+            Ok((stdout, _stderr)) => {
+                Toc::parse(&stdout).map_err(|error| RestoreError(error.into()))
+            }
+            Err(error) => Err(RestoreError(error.into())),
+        }
+    }
+
+    /// Get the size in bytes of the tables in the given database, excluding indexes and
+    /// including TOAST data (`pg_table_size`); e.g. to restore the largest tables of a dump of
+    /// the database [first](ReorderStrategy::LargestDataFirst)
+    #[instrument(skip(self, database_name))]
+    pub async fn table_sizes<S: AsRef<str>>(
+        &self,
+        database_name: S,
+    ) -> Result<HashMap<QualifiedName, u64>> {
+        let rows = self
+            .catalog_query(database_name, catalog::table_sizes_query())
+            .await?;
+        Ok(catalog::parse_table_sizes(rows))
+    }
+
+    /// Back up the database to a file in the pg_dump custom archive format, invoking the progress
+    /// callback with a [ProgressEvent::Dump] for each table whose data is dumped; see
+    /// [backup](Self::backup). When the token is cancelled, pg_dump is stopped, the partial file
+    /// is removed and [CancelledError](crate::Error::CancelledError) is returned.
+    #[instrument(skip(self, database_name, path, progress, cancel))]
+    pub async fn dump<S, P, F>(
+        &self,
+        database_name: S,
+        path: P,
+        progress: F,
+        cancel: &CancellationToken,
+    ) -> Result<()>
+    where
+        S: AsRef<str>,
+        P: Into<PathBuf>,
+        F: Fn(ProgressEvent) + Send + 'static,
+    {
+        let path = path.into();
+        debug!(
+            "Backing up database {} to {}",
+            database_name.as_ref(),
+            path.to_string_lossy()
+        );
+        let pg_dump = PgDumpBuilder::from(&self.settings)
+            .username(BOOTSTRAP_SUPERUSER)
+            .dbname(database_name.as_ref())
+            .format("custom")
+            .file(&path)
+            .verbose();
+        if let Err(error) = self.check_tool(&pg_dump) {
+            return Err(BackupError(error.into()));
+        }
+        let passfile = pg_dump.get_passfile();
+        let mut command = pg_dump.build();
+        self.set_locale_environment(&mut command);
+        self.set_cgroup(&mut command);
+        // The verbose output is parsed, so the messages must be in English
+        command.env_remove("LC_ALL").env("LC_MESSAGES", "C");
+        let cancel = cancel.clone();
+        let result = run_blocking(move || {
+            let _passfile = passfile;
+            let mut dump_progress = DumpProgress::default();
+            progress::execute_with_cancellation(&mut command, &cancel, |line| {
+                if dump_progress.update(line) {
+                    progress(ProgressEvent::Dump(dump_progress.clone()));
+                }
+            })
+        })
+        .await;
+
+        match result {
+            Ok(_) => {
+                self.record_backup();
+                Ok(())
+            }
+            Err(error) => {
+                if matches!(error, postgresql_commands::Error::CancelledError { .. }) {
+                    remove_partial_output(&path, false);
+                }
+                Err(cancellable_error(error, BackupError))
+            }
+        }
+    }
+
+    /// Restore the database from a pg_dump archive, e.g. one created with
+    /// [backup](Self::backup), invoking the progress callback with a
+    /// [ProgressEvent::Restore] for each archive entry restored. The restore is stopped when
+    /// the token is cancelled, returning [CancelledError](crate::Error::CancelledError);
+    /// objects already restored are not removed.
+    ///
+    /// When `analyze` is set, or it is `None` and [analyze_after_load](Settings::analyze_after_load)
+    /// is set, the database is analyzed afterward and the [report](AnalyzeReport) is returned.
+    #[instrument(skip(self, database_name, path, progress, cancel))]
+    pub async fn restore<S, P, F>(
+        &self,
+        database_name: S,
+        path: P,
+        analyze: Option<bool>,
+        progress: F,
+        cancel: &CancellationToken,
+    ) -> Result<Option<AnalyzeReport>>
+    where
+        S: AsRef<str>,
+        P: Into<PathBuf>,
+        F: Fn(ProgressEvent) + Send + 'static,
+    {
+        let path = path.into();
+        debug!(
+            "Restoring database {} from {}",
+            database_name.as_ref(),
+            path.to_string_lossy()
+        );
+        let pg_restore = PgRestoreBuilder::from(&self.settings)
+            .username(BOOTSTRAP_SUPERUSER)
+            .dbname(database_name.as_ref())
+            .archive(&path);
+        if let Err(error) = self.check_tool(&pg_restore) {
+            return Err(RestoreError(error.into()));
+        }
+        let cancel = cancel.clone();
+        match run_blocking(move || pg_restore.execute_with_progress(progress, &cancel)).await {
+            Ok(restore_progress) => {
+                debug!(
+                    "Restored {} archive entries to database {}",
+                    restore_progress.processed,
+                    database_name.as_ref()
+                );
+            }
+            Err(error) => return Err(cancellable_error(error, RestoreError)),
+        }
+        self.analyze_after_load(analyze, database_name, &[]).await
+    }
+
+    /// Remove the data of all user tables in the given database while preserving the schema,
+    /// which is faster than dropping the database and creating it again, e.g. between tests.
+    /// The tables are discovered from the catalog and truncated with a single `TRUNCATE`;
+    /// tables referenced by the [excluded tables](ResetOptions::exclude_tables) are emptied
+    /// with `DELETE` instead, with the foreign key triggers disabled, so that the excluded
+    /// tables keep their data. The configuration tables of extensions are preserved unless
+    /// [included](ResetOptions::include_extension_config). All tables are reset in a single
+    /// transaction.
+    #[instrument(skip(self, database_name, options))]
+    pub async fn reset_data<S: AsRef<str>>(
+        &self,
+        database_name: S,
+        options: &ResetOptions,
+    ) -> Result<ResetReport> {
+        let database_name = database_name.as_ref();
+        let tables = match self.query(database_name, reset::reset_tables_query()).await {
+            Ok(rows) => reset::parse_reset_tables(rows),
+            Err(error) => return Err(ResetError(error.into())),
+        };
+        let plan = reset::plan_reset(&tables, options);
+        debug!(
+            "Resetting database {database_name}: truncating {} tables, deleting from {} tables and preserving {} tables",
+            plan.truncate.len(),
+            plan.delete.len(),
+            plan.preserve.len()
+        );
+        if !plan.truncate.is_empty() || !plan.delete.is_empty() {
+            let sql = reset::reset_statement(&plan, options);
+            if let Err(error) = self.query(database_name, sql).await {
+                return Err(ResetError(error.into()));
+            }
+        }
+
+        Ok(ResetReport {
+            database: database_name.to_string(),
+            truncated: plan.truncate,
+            deleted: plan.delete,
+            preserved: plan.preserve,
+        })
+    }
+
+    /// Gather planner statistics for the tables in the given database, so that queries run
+    /// right after a bulk load or restore are planned with statistics rather than defaults.
+    /// The tables are analyzed with `ANALYZE`; when no tables are given, every table in the
+    /// database is analyzed with `vacuumdb --analyze-in-stages`.
+    #[instrument(skip(self, database_name, tables))]
+    pub async fn analyze<S: AsRef<str>>(
+        &self,
+        database_name: S,
+        tables: &[QualifiedName],
+    ) -> Result<AnalyzeReport> {
+        let database_name = database_name.as_ref();
+        let start = Instant::now();
+        let tables = if tables.is_empty() {
+            debug!("Analyzing database {database_name}");
+            let vacuumdb = VacuumDbBuilder::from(&self.settings)
+                .username(BOOTSTRAP_SUPERUSER)
+                .dbname(database_name)
+                .analyze_in_stages();
+            if let Err(error) = self.execute_command(vacuumdb).await {
+                return Err(AnalyzeError(error.into()));
+            }
+            self.list_tables(database_name, None)
+                .await?
+                .into_iter()
+                .map(|table| QualifiedName::qualified(table.schema, table.name))
+                .collect()
+        } else {
+            debug!(
+                "Analyzing {} tables of database {database_name}",
+                tables.len()
+            );
+            let sql = analyze::analyze_statement(tables);
+            if let Err(error) = self.query(database_name, sql).await {
+                return Err(AnalyzeError(error.into()));
+            }
+            tables.to_vec()
+        };
+
+        let report = AnalyzeReport {
+            database: database_name.to_string(),
+            tables,
+            duration: start.elapsed(),
+        };
+        debug!(
+            "Analyzed {} tables of database {database_name} in {:?}",
+            report.tables.len(),
+            report.duration
+        );
+        Ok(report)
+    }
+
+    /// Analyze the tables after a load when requested, falling back to
+    /// [analyze_after_load](Settings::analyze_after_load)
+    async fn analyze_after_load<S: AsRef<str>>(
+        &self,
+        analyze: Option<bool>,
+        database_name: S,
+        tables: &[QualifiedName],
+    ) -> Result<Option<AnalyzeReport>> {
+        if !analyze.unwrap_or(self.settings.analyze_after_load) {
+            return Ok(None);
+        }
+        Ok(Some(self.analyze(database_name, tables).await?))
+    }
+
+    /// Take a base backup of the server into the directory with pg_basebackup, invoking the
+    /// progress callback with a [ProgressEvent::BaseBackup] for each progress line reported. The
+    /// directory must not exist or be empty. When the token is cancelled, the backup is stopped,
+    /// the partial backup is removed and [CancelledError](crate::Error::CancelledError) is
+    /// returned.
+    #[instrument(skip(self, directory, progress, cancel))]
+    pub async fn base_backup<P, F>(
+        &self,
+        directory: P,
+        progress: F,
+        cancel: &CancellationToken,
+    ) -> Result<()>
+    where
+        P: Into<PathBuf>,
+        F: Fn(ProgressEvent) + Send + 'static,
+    {
+        let directory = directory.into();
+        debug!("Taking base backup to {}", directory.to_string_lossy());
+        let existed = directory.exists();
+        let pg_basebackup = PgBaseBackupBuilder::from(&self.settings)
+            .username(BOOTSTRAP_SUPERUSER)
+            .pgdata(&directory)
+            .checkpoint("fast");
+        if let Err(error) = self.check_tool(&pg_basebackup) {
+            return Err(BackupError(error.into()));
+        }
+        let cancel = cancel.clone();
+        match run_blocking(move || pg_basebackup.execute_with_progress(progress, &cancel)).await {
+            Ok(()) => {
+                self.record_backup();
+                Ok(())
+            }
+            Err(error) => {
+                if matches!(error, postgresql_commands::Error::CancelledError { .. }) {
+                    remove_partial_output(&directory, existed);
+                }
+                Err(cancellable_error(error, BackupError))
+            }
+        }
+    }
+
+    /// Run a command built by any [CommandBuilder], including builders defined outside of this
+    /// crate, with the settings of the server applied by a [SettingsApplicator]: the program is
+    /// resolved in the binary directory unless the builder sets its own, and the connection is
+    /// passed with the libpq environment variables. The command runs with the configured
+    /// timeout and locale environment; the stdout and stderr are returned.
+    #[instrument(skip(self, command_builder))]
+    pub async fn run_command<B: CommandBuilder>(
         &self,
         command_builder: B,
-    ) -> postgresql_commands::Result<(String, String)> {
-        let mut command = command_builder.build_tokio();
-        command.execute(self.settings.timeout).await
+    ) -> Result<(String, String)> {
+        let command_builder = SettingsApplicator::new(&self.settings).apply(command_builder);
+        debug!(
+            "Running command {}",
+            command_builder.to_redacted_command_string()
+        );
+        match self.execute_command(command_builder).await {
+            Ok(output) => Ok(output),
+            Err(postgresql_commands::Error::CommandError { stdout, stderr, .. }) => {
+                Err(crate::Error::CommandError { stdout, stderr })
+            }
+            Err(error) => Err(crate::Error::IoError(error.into())),
+        }
     }
-}
 
-/// Default PostgreSQL server
-impl Default for PostgreSQL {
-    fn default() -> Self {
-        let version = PostgreSQL::default_version();
-        let settings = Settings::default();
-        Self::new(version, settings)
+    /// Create a physical replication slot with the given name, for use by standbys or
+    /// `pg_receivewal`. Nothing is done if the slot already exists.
+    #[instrument(skip(self, slot_name))]
+    pub async fn create_physical_slot<S: AsRef<str>>(&self, slot_name: S) -> Result<()> {
+        debug!("Creating physical replication slot {}", slot_name.as_ref());
+        let slot_name = quote_literal(slot_name);
+        let sql = format!(
+            "SELECT pg_create_physical_replication_slot({slot_name}) WHERE NOT EXISTS (SELECT 1 FROM pg_replication_slots WHERE slot_name = {slot_name})"
+        );
+        match self.maintenance_query(sql).await {
+            Ok(_) => Ok(()),
+            Err(error) => Err(ReplicationSlotError(error.into())),
+        }
     }
-}
 
-/// Stop the PostgreSQL server and remove the data directory if it is marked as temporary.
-impl Drop for PostgreSQL {
-    fn drop(&mut self) {
+    /// Create a logical replication slot with the given name in the database, decoding changes
+    /// with the output plugin; e.g. `pgoutput` for publications or `test_decoding`. Nothing is
+    /// done if the slot already exists. The server must run with `wal_level = logical`; see
+    /// [wal_level](Settings::wal_level).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the server does not run with `wal_level = logical`, e.g. because it
+    /// has not been restarted since the setting was changed.
+    #[instrument(skip(self, database_name, slot_name, plugin))]
+    pub async fn create_logical_slot<S: AsRef<str>, N: AsRef<str>, P: AsRef<str>>(
+        &self,
+        database_name: S,
+        slot_name: N,
+        plugin: P,
+    ) -> Result<()> {
+        self.require_logical_wal_level().await?;
+        debug!(
+            "Creating logical replication slot {} in database {} with plugin {}",
+            slot_name.as_ref(),
+            database_name.as_ref(),
+            plugin.as_ref()
+        );
+        let slot_name = quote_literal(slot_name);
+        let sql = format!(
+            "SELECT pg_create_logical_replication_slot({slot_name}, {}) WHERE NOT EXISTS (SELECT 1 FROM pg_replication_slots WHERE slot_name = {slot_name})",
+            quote_literal(plugin)
+        );
+        match self.query(database_name, sql).await {
+            Ok(_) => Ok(()),
+            Err(error) => Err(ReplicationSlotError(error.into())),
+        }
+    }
+
+    /// Check that the server runs with `wal_level = logical`, which logical decoding requires
+    async fn require_logical_wal_level(&self) -> Result<()> {
+        let wal_level = self.get_guc("wal_level").await?;
+        if wal_level == WalLevel::Logical.as_str() {
+            return Ok(());
+        }
+        Err(ReplicationSlotError(anyhow::anyhow!(
+            "Logical replication requires wal_level = logical, but the server runs with \
+             wal_level = {wal_level}; set Settings::wal_level to WalLevel::Logical and restart \
+             the server"
+        )))
+    }
+
+    /// Drop the replication slot with the given name. Nothing is done if the slot does not exist.
+    #[instrument(skip(self, slot_name))]
+    pub async fn drop_slot<S: AsRef<str>>(&self, slot_name: S) -> Result<()> {
+        self.verify_managed().await?;
+        debug!("Dropping replication slot {}", slot_name.as_ref());
+        let sql = format!(
+            "SELECT pg_drop_replication_slot(slot_name) FROM pg_replication_slots WHERE slot_name = {}",
+            quote_literal(slot_name)
+        );
+        match self.maintenance_query(sql).await {
+            Ok(_) => Ok(()),
+            Err(error) => Err(ReplicationSlotError(error.into())),
+        }
+    }
+
+    /// List the transactions prepared for two-phase commit in any database that were never
+    /// committed or rolled back, oldest first; e.g. those leaked by a test that panicked
+    /// between `PREPARE TRANSACTION` and `COMMIT PREPARED`. A prepared transaction holds its
+    /// locks until it is resolved, so an empty list is a useful assertion at the end of a test.
+    #[instrument(skip(self))]
+    pub async fn orphaned_prepared_transactions(&self) -> Result<Vec<PreparedTransaction>> {
+        match self
+            .maintenance_query(prepared_transaction::PREPARED_TRANSACTIONS_QUERY)
+            .await
+        {
+            Ok(rows) => Ok(prepared_transaction::parse_prepared_transactions(rows)),
+            Err(error) => Err(PreparedTransactionError(error.into())),
+        }
+    }
+
+    /// Roll back every [prepared transaction](Self::orphaned_prepared_transactions), releasing
+    /// its locks, e.g. for a clean teardown. Each transaction is rolled back in the database it
+    /// was prepared in. The transactions that were rolled back are returned.
+    #[instrument(skip(self))]
+    pub async fn rollback_all_prepared(&self) -> Result<Vec<PreparedTransaction>> {
+        self.verify_managed().await?;
+        let transactions = self.orphaned_prepared_transactions().await?;
+        for transaction in &transactions {
+            debug!(
+                "Rolling back prepared transaction {} in database {}",
+                transaction.gid, transaction.database
+            );
+            let sql = prepared_transaction::rollback_statement(&transaction.gid);
+            if let Err(error) = self.query(&transaction.database, sql).await {
+                return Err(PreparedTransactionError(error.into()));
+            }
+        }
+        Ok(transactions)
+    }
+
+    /// Issue a `CHECKPOINT` and return once it is complete, e.g. before taking a filesystem
+    /// snapshot of the data directory, so that starting the snapshot later has little WAL to
+    /// replay. The checkpoint is requested with the superuser role.
+    #[instrument(skip(self))]
+    pub async fn checkpoint(&self) -> Result<()> {
+        debug!(
+            "Checkpointing database {}",
+            self.settings.data_dir.to_string_lossy()
+        );
+        match self.maintenance_query("CHECKPOINT").await {
+            Ok(_) => Ok(()),
+            Err(error) => Err(CheckpointError(error.into())),
+        }
+    }
+
+    /// Get the compile-time parameters of the cluster: the block size, WAL block size, WAL
+    /// segment size and maximum identifier length. The parameters are queried from the server
+    /// when it is started, and read with pg_controldata otherwise; the cluster must be
+    /// initialized.
+    #[instrument(skip(self))]
+    pub async fn cluster_params(&self) -> Result<ClusterParams> {
         if self.status() == Status::Started {
-            let mut pg_ctl = PgCtlBuilder::from(&self.settings)
-                .mode(Stop)
-                .pgdata(&self.settings.data_dir)
-                .shutdown_mode(Fast)
-                .wait()
-                .build();
+            return match self.maintenance_query(cluster_params::SETTINGS_QUERY).await {
+                Ok(rows) => cluster_params::parse_settings(&rows),
+                Err(error) => Err(ClusterParamsError(error.into())),
+            };
+        }
 
-            let _ = pg_ctl.output();
+        let pg_controldata =
+            PgControlDataBuilder::from(&self.settings).pgdata(&self.settings.data_dir);
+        match self.execute_command(pg_controldata).await {
+            Ok((stdout, _stderr)) => cluster_params::parse_control_data(&stdout),
+            Err(error) => Err(ClusterParamsError(error.into())),
         }
+    }
 
-        if self.settings.temporary {
-            let _ = remove_dir_all(&self.settings.data_dir);
-            let _ = remove_file(&self.settings.password_file);
+    /// Verify that the connection reaches the server managed by this instance when
+    /// [managed_only](Settings::managed_only) is set, by comparing the system identifier
+    /// reported by the server with the one of the data directory read with pg_controldata.
+    /// Called by destructive operations before they change anything.
+    async fn verify_managed(&self) -> Result<()> {
+        if !self.settings.managed_only {
+            return Ok(());
+        }
+
+        let pg_controldata =
+            PgControlDataBuilder::from(&self.settings).pgdata(&self.settings.data_dir);
+        let control_data = match self.execute_command(pg_controldata).await {
+            Ok((stdout, _stderr)) => stdout,
+            Err(error) => return Err(UnmanagedServerError(error.into())),
+        };
+        let Some(managed) = managed::parse_system_identifier(&control_data) else {
+            return Err(UnmanagedServerError(anyhow::anyhow!(
+                "System identifier of {} not found",
+                self.settings.data_dir.to_string_lossy()
+            )));
+        };
+        let connected = match self
+            .maintenance_query(managed::SYSTEM_IDENTIFIER_QUERY)
+            .await
+        {
+            Ok(rows) => rows.into_iter().flatten().next().unwrap_or_default(),
+            Err(error) => return Err(UnmanagedServerError(error.into())),
+        };
+        if connected != managed {
+            return Err(UnmanagedServerError(anyhow::anyhow!(
+                "Refusing to modify the server at {}:{} with system identifier {connected}; the managed data directory {} has system identifier {managed}",
+                self.settings.effective_host(),
+                self.settings.port,
+                self.settings.data_dir.to_string_lossy()
+            )));
+        }
+        debug!("Verified managed server with system identifier {managed}");
+        Ok(())
+    }
+
+    /// Get the live value of the configuration parameter (GUC) with `SHOW`; e.g. `5432` for
+    /// `port`. Unlike the configuration files, this reflects the value actually applied by the
+    /// server, including the command line options and the defaults.
+    #[instrument(skip(self, name))]
+    pub async fn get_guc<S: AsRef<str>>(&self, name: S) -> Result<String> {
+        let sql = show_statement(name.as_ref());
+        match self.maintenance_query(sql).await {
+            Ok(rows) => {
+                rows.into_iter().flatten().next().ok_or_else(|| {
+                    GucError(anyhow::anyhow!("parameter {} not shown", name.as_ref()))
+                })
+            }
+            Err(error) => Err(GucError(error.into())),
         }
     }
-}
 
-#[cfg(test)]
-mod tests {
+    /// Check that the live value of the configuration parameter (GUC) is the expected value; see
+    /// [get_guc](Self::get_guc). The value is compared as shown by the server, including the unit,
+    /// e.g. `128MB` for `shared_buffers`. A [GucError](crate::Error::GucError) describing both
+    /// values is returned on a mismatch.
+    #[instrument(skip(self, name, expected))]
+    pub async fn assert_guc<S: AsRef<str>, V: AsRef<str>>(
+        &self,
+        name: S,
+        expected: V,
+    ) -> Result<()> {
+        let value = self.get_guc(name.as_ref()).await?;
+        if value != expected.as_ref() {
+            return Err(GucError(anyhow::anyhow!(
+                "parameter {} is {value:?}, expected {:?}",
+                name.as_ref(),
+                expected.as_ref()
+            )));
+        }
+        Ok(())
+    }
+
+    /// Run the operation with autovacuum turned off (`ALTER SYSTEM SET autovacuum = off` and a
+    /// configuration reload), e.g. for a deterministic bulk load followed by `ANALYZE`. The
+    /// prior setting is restored and the configuration reloaded afterward, also when the
+    /// operation panics; the server is not restarted. Autovacuum workers that are already
+    /// running are not cancelled.
+    ///
+    /// ```no_run
+    /// # async fn example(postgresql: &postgresql_embedded::PostgreSQL) -> postgresql_embedded::Result<()> {
+    /// postgresql
+    ///     .with_autovacuum_paused(|postgresql| async move {
+    ///         postgresql.run_sql("test", "COPY items FROM '/tmp/items.csv'").await?;
+    ///         postgresql.run_sql("test", "ANALYZE items").await
+    ///     })
+    ///     .await??;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[instrument(skip(self, operation))]
+    pub async fn with_autovacuum_paused<'a, F, Fut, T>(&'a self, operation: F) -> Result<T>
+    where
+        F: FnOnce(&'a Self) -> Fut,
+        Fut: Future<Output = T> + 'a,
+    {
+        let pause = self.pause_autovacuum().await?;
+        let result = operation(self).await;
+        pause.restore().await?;
+        Ok(result)
+    }
+
+    /// Turn autovacuum off until the returned [AutovacuumPause] is restored or dropped
+    pub(crate) async fn pause_autovacuum(&self) -> Result<AutovacuumPause<'_>> {
+        let (restore_sql, previous) = self.autovacuum_restore_sql().await?;
+
+        debug!("Pausing autovacuum");
+        let pause = AutovacuumPause {
+            postgresql: self,
+            restore_sql,
+            previous,
+            restored: false,
+        };
+        self.alter_system("ALTER SYSTEM SET autovacuum = off", "off")
+            .await?;
+        Ok(pause)
+    }
+
+    /// Get the statement restoring the current autovacuum setting, and the current value
+    async fn autovacuum_restore_sql(&self) -> Result<(String, String)> {
+        // Restore the value set with ALTER SYSTEM, if any, rather than the live value, so that
+        // the value from postgresql.conf keeps taking effect
+        let sql = "SELECT setting FROM pg_file_settings WHERE name = 'autovacuum' \
+            AND sourcefile LIKE '%postgresql.auto.conf' ORDER BY seqno DESC LIMIT 1";
+        let restore_sql = match self.maintenance_query(sql).await {
+            Ok(rows) => match rows.into_iter().flatten().next() {
+                Some(setting) => {
+                    format!("ALTER SYSTEM SET autovacuum = {}", quote_literal(setting))
+                }
+                None => "ALTER SYSTEM RESET autovacuum".to_string(),
+            },
+            Err(error) => return Err(GucError(error.into())),
+        };
+        let previous = self.get_guc("autovacuum").await?;
+        Ok((restore_sql, previous))
+    }
+
+    /// Quiesce background activity before a benchmark, so that autovacuum or a checkpoint does
+    /// not kick in during the measurement: depending on the [options](QuiesceOptions),
+    /// autovacuum is turned off with `ALTER SYSTEM` and a configuration reload, a `CHECKPOINT`
+    /// is issued, and the running vacuums are waited for. Use
+    /// [restore_background_activity](Self::restore_background_activity) to turn autovacuum
+    /// back on. As autovacuum would stay off for the data directory if it is not restored,
+    /// turning it off on an instance that is not [temporary](Settings::temporary) is refused
+    /// unless [forced](QuiesceOptions::force).
+    #[instrument(skip(self))]
+    pub async fn quiesce_background_activity(&self, options: &QuiesceOptions) -> Result<()> {
+        if options.disable_autovacuum {
+            if !self.settings.temporary && !options.force {
+                return Err(BackgroundActivityError(anyhow::anyhow!(
+                    "Refusing to turn autovacuum off for the persistent data directory {}",
+                    self.settings.data_dir.to_string_lossy()
+                )));
+            }
+            let restore = self
+                .autovacuum_restore
+                .lock()
+                .map_or(true, |restore| restore.is_none());
+            if restore {
+                let restore_sql = self.autovacuum_restore_sql().await?;
+                debug!("Turning autovacuum off");
+                self.alter_system("ALTER SYSTEM SET autovacuum = off", "off")
+                    .await?;
+                if let Ok(mut restore) = self.autovacuum_restore.lock() {
+                    *restore = Some(restore_sql);
+                }
+            }
+        }
+
+        if options.force_checkpoint {
+            debug!("Checkpointing before quiescing");
+            if let Err(error) = self.maintenance_query("CHECKPOINT").await {
+                return Err(BackgroundActivityError(error.into()));
+            }
+        }
+
+        if options.wait_for_no_active_vacuum {
+            let start = Instant::now();
+            loop {
+                let active = match self
+                    .maintenance_query(background::ACTIVE_VACUUMS_QUERY)
+                    .await
+                {
+                    Ok(rows) => rows.into_iter().flatten().next().unwrap_or_default(),
+                    Err(error) => return Err(BackgroundActivityError(error.into())),
+                };
+                if active == "0" {
+                    break;
+                }
+                if start.elapsed() > options.timeout {
+                    return Err(BackgroundActivityError(anyhow::anyhow!(
+                        "Timed out waiting for {active} vacuums to finish"
+                    )));
+                }
+                #[cfg(feature = "tokio")]
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                #[cfg(not(feature = "tokio"))]
+                std::thread::sleep(Duration::from_millis(100));
+            }
+        }
+        debug!("Quiesced background activity");
+        Ok(())
+    }
+
+    /// Undo [quiesce_background_activity](Self::quiesce_background_activity): the prior
+    /// autovacuum setting is restored and the configuration reloaded. Nothing is done when
+    /// autovacuum was not turned off.
+    #[instrument(skip(self))]
+    pub async fn restore_background_activity(&self) -> Result<()> {
+        let restore = self
+            .autovacuum_restore
+            .lock()
+            .map_or(None, |mut restore| restore.take());
+        let Some((restore_sql, previous)) = restore else {
+            return Ok(());
+        };
+        debug!("Restoring autovacuum");
+        self.alter_system(&restore_sql, &previous).await
+    }
+
+    /// Change the configuration with `ALTER SYSTEM`, reload it and wait until the autovacuum
+    /// setting reported to new sessions is the expected value
+    async fn alter_system(&self, sql: &str, expected: &str) -> Result<()> {
+        if let Err(error) = self.maintenance_query(sql).await {
+            return Err(GucError(error.into()));
+        }
+        if let Err(error) = self.maintenance_query("SELECT pg_reload_conf()").await {
+            return Err(GucError(error.into()));
+        }
+
+        // The reload is signalled asynchronously, so sessions started right away may not see it
+        let start = Instant::now();
+        loop {
+            let value = self.get_guc("autovacuum").await?;
+            if value == expected {
+                return Ok(());
+            }
+            if start.elapsed() > RELOAD_TIMEOUT {
+                return Err(GucError(anyhow::anyhow!(
+                    "Timed out waiting for autovacuum to be {expected}; it is {value}"
+                )));
+            }
+            #[cfg(feature = "tokio")]
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            #[cfg(not(feature = "tokio"))]
+            std::thread::sleep(Duration::from_millis(50));
+        }
+    }
+
+    /// Get the write-ahead log retained by the server: the size of the WAL directory, its oldest
+    /// segment, and the replication slots that pin WAL. The slots are only reported when the
+    /// server is started.
+    #[instrument(skip(self))]
+    pub async fn wal_usage(&self) -> Result<WalUsage> {
+        let wal_dir = self.settings.data_dir.join("pg_wal");
+        let mut wal_usage = WalUsage {
+            wal_bytes: disk_usage::directory_size(&wal_dir)?,
+            oldest_segment: wal::oldest_segment(&wal_dir)?,
+            ..Default::default()
+        };
+        if self.status() != Status::Started {
+            debug!("Server is not started; replication slots are not reported");
+            return Ok(wal_usage);
+        }
+
+        let sql =
+            "SELECT slot_name, active, pg_wal_lsn_diff(pg_current_wal_lsn(), restart_lsn)::bigint \
+            FROM pg_replication_slots WHERE restart_lsn IS NOT NULL ORDER BY 3 DESC, 1";
+        let rows = match self.maintenance_query(sql).await {
+            Ok(rows) => rows,
+            Err(error) => return Err(WalError(error.into())),
+        };
+        wal_usage.slots = rows
+            .into_iter()
+            .filter_map(|row| match row.as_slice() {
+                [slot_name, active, retained_bytes] => Some(SlotRetention {
+                    slot_name: slot_name.clone(),
+                    active: active == "t",
+                    retained_bytes: retained_bytes.parse().unwrap_or_default(),
+                }),
+                _ => None,
+            })
+            .collect();
+        Ok(wal_usage)
+    }
+
+    /// Drop the replication slots that have been inactive for at least `older_than`, so that an
+    /// orphaned slot does not retain write-ahead log indefinitely. A slot is considered inactive
+    /// since its state was last saved in the data directory, i.e. since it was created or last
+    /// advanced. The retained WAL is removed or recycled by the next checkpoint.
+    ///
+    /// Returns the names of the dropped slots.
+    #[instrument(skip(self))]
+    pub async fn prune_inactive_replication_slots(
+        &self,
+        older_than: Duration,
+    ) -> Result<Vec<String>> {
+        self.verify_managed().await?;
+        let sql = "SELECT slot_name, coalesce(pg_wal_lsn_diff(pg_current_wal_lsn(), restart_lsn)::bigint, 0) \
+            FROM pg_replication_slots WHERE NOT active ORDER BY slot_name";
+        let rows = match self.maintenance_query(sql).await {
+            Ok(rows) => rows,
+            Err(error) => return Err(ReplicationSlotError(error.into())),
+        };
+
+        let mut dropped_slots = Vec::new();
+        for row in rows {
+            let [slot_name, retained_bytes] = row.as_slice() else {
+                continue;
+            };
+            let Some(inactive_for) = wal::slot_inactive_for(&self.settings.data_dir, slot_name)
+            else {
+                debug!("Unable to determine how long slot {slot_name} has been inactive");
+                continue;
+            };
+            if inactive_for < older_than {
+                continue;
+            }
+
+            warn!(
+                "Dropping replication slot {slot_name}, inactive for {}s and retaining {retained_bytes} bytes of WAL",
+                inactive_for.as_secs()
+            );
+            self.drop_slot(slot_name).await?;
+            dropped_slots.push(slot_name.clone());
+        }
+        Ok(dropped_slots)
+    }
+
+    /// Drop all user-created roles, except for the roles in the `keep` list. The bootstrap
+    /// superuser, the configured user, and the built-in `pg_*` roles are never dropped. Objects
+    /// owned by a dropped role are reassigned to the bootstrap superuser in every database, and
+    /// any privileges granted to the role are revoked, so that the role can be dropped.
+    ///
+    /// Returns the names of the dropped roles.
+    #[instrument(skip(self))]
+    pub async fn reset_roles(&self, keep: &[&str]) -> Result<Vec<String>> {
+        self.verify_managed().await?;
+        let sql = format!(
+            "SELECT rolname FROM pg_roles WHERE rolname NOT LIKE 'pg\\_%' AND rolname NOT IN ({}, {}) ORDER BY rolname",
+            quote_literal(BOOTSTRAP_SUPERUSER),
+            quote_literal(&self.settings.username)
+        );
+        let roles: Vec<String> = match self.maintenance_query(sql).await {
+            Ok(rows) => rows
+                .into_iter()
+                .filter_map(|row| row.into_iter().next())
+                .filter(|role| !keep.contains(&role.as_str()))
+                .collect(),
+            Err(error) => return Err(RoleError(error.into())),
+        };
+        if roles.is_empty() {
+            return Ok(roles);
+        }
+
+        let databases = match self
+            .maintenance_query(
+                "SELECT datname FROM pg_database WHERE datallowconn ORDER BY datname",
+            )
+            .await
+        {
+            Ok(rows) => rows.into_iter().filter_map(|row| row.into_iter().next()),
+            Err(error) => return Err(RoleError(error.into())),
+        };
+        let role_list = roles
+            .iter()
+            .map(quote_identifier)
+            .collect::<Vec<String>>()
+            .join(", ");
+
+        for database_name in databases {
+            debug!("Reassigning objects owned by {role_list} in database {database_name}");
+            let sql = format!(
+                "REASSIGN OWNED BY {role_list} TO {}; DROP OWNED BY {role_list}",
+                quote_identifier(BOOTSTRAP_SUPERUSER)
+            );
+            if let Err(error) = self.query(&database_name, sql).await {
+                return Err(RoleError(error.into()));
+            }
+        }
+
+        for role in &roles {
+            debug!("Dropping role {role}");
+            let sql = format!("DROP ROLE {}", quote_identifier(role));
+            if let Err(error) = self.maintenance_query(sql).await {
+                return Err(RoleError(error.into()));
+            }
+        }
+
+        Ok(roles)
+    }
+
+    /// Terminate the sessions that have been idle in a transaction for at least `older_than`.
+    /// Such sessions, e.g. leaked by a test that panicked, hold their locks and block statements
+    /// that need conflicting locks (e.g. DDL) in later tests. Sessions that are running a
+    /// statement or are idle outside of a transaction are not affected.
+    ///
+    /// Returns the process ids of the terminated backends.
+    #[instrument(skip(self))]
+    pub async fn terminate_idle_in_transaction(&self, older_than: Duration) -> Result<Vec<u32>> {
+        self.verify_managed().await?;
+        let sql = format!(
+            "SELECT pid, pg_terminate_backend(pid) FROM pg_stat_activity \
+             WHERE state IN ('idle in transaction', 'idle in transaction (aborted)') \
+             AND state_change <= now() - make_interval(secs => {}) \
+             AND pid <> pg_backend_pid() ORDER BY pid",
+            older_than.as_secs_f64()
+        );
+        let rows = match self.maintenance_query(sql).await {
+            Ok(rows) => rows,
+            Err(error) => return Err(SessionError(error.into())),
+        };
+        let pids = parse_pids(
+            rows.into_iter()
+                .filter(|row| row.get(1).is_some_and(|terminated| terminated == "t"))
+                .collect(),
+        );
+        if !pids.is_empty() {
+            warn!("Terminated sessions idle in transaction: {pids:?}");
+        }
+        Ok(pids)
+    }
+
+    /// Create a tablespace with the given name. When no location is given, the tablespace is
+    /// placed in a new directory managed by the crate, next to the data directory; the directory
+    /// is removed when the tablespace is [dropped](Self::drop_tablespace), and along with the
+    /// data directory when the server is temporary. A given location must be an empty directory
+    /// owned by the user running the server; relative locations are resolved against the
+    /// current directory.
+    ///
+    /// Returns the location of the tablespace.
+    #[instrument(skip(self, name))]
+    pub async fn create_tablespace<S: AsRef<str>>(
+        &self,
+        name: S,
+        location: Option<PathBuf>,
+    ) -> Result<PathBuf> {
+        let name = name.as_ref();
+        let managed = location.is_none();
+        let location = match location {
+            Some(location) => location,
+            None if tablespace::is_valid_directory_name(name) => {
+                let location = tablespace::managed_dir(&self.settings.data_dir).join(name);
+                tablespace::create_directory(&location)?;
+                location
+            }
+            None => {
+                return Err(TablespaceError(anyhow::anyhow!(
+                    "tablespace name {name} cannot be used as a directory name"
+                )))
+            }
+        };
+        let location = if location.is_relative() {
+            std::env::current_dir()?.join(location)
+        } else {
+            location
+        };
+
+        debug!(
+            "Creating tablespace {name} in {}",
+            location.to_string_lossy()
+        );
+        let sql = format!(
+            "CREATE TABLESPACE {} LOCATION {}",
+            quote_identifier(name),
+            quote_literal(location.to_string_lossy())
+        );
+        match self.maintenance_query(sql).await {
+            Ok(_) => Ok(location),
+            Err(error) => {
+                if managed {
+                    let _ = remove_dir_all(&location);
+                }
+                Err(TablespaceError(error.into()))
+            }
+        }
+    }
+
+    /// Drop the tablespace with the given name. The tablespace must be empty in every database
+    /// unless `force_move_to` is given, in which case the tables, indexes and materialized views
+    /// in the tablespace, and the databases using it as their default tablespace, are first moved
+    /// to that tablespace (e.g. `pg_default`). The directory of a managed tablespace is removed.
+    #[instrument(skip(self, name))]
+    pub async fn drop_tablespace<S: AsRef<str>>(
+        &self,
+        name: S,
+        force_move_to: Option<&str>,
+    ) -> Result<()> {
+        self.verify_managed().await?;
+        let name = name.as_ref();
+        let tablespace_oid = format!(
+            "(SELECT oid FROM pg_tablespace WHERE spcname = {})",
+            quote_literal(name)
+        );
+        let sql = format!(
+            "SELECT spcname, pg_tablespace_location(oid) FROM pg_tablespace WHERE oid = {tablespace_oid}"
+        );
+        let rows = match self.maintenance_query(sql).await {
+            Ok(rows) => rows,
+            Err(error) => return Err(TablespaceError(error.into())),
+        };
+        let managed_dir = tablespace::managed_dir(&self.settings.data_dir);
+        let Some(tablespace) = tablespace::parse_tablespaces(rows, &managed_dir).pop() else {
+            return Err(TablespaceError(anyhow::anyhow!(
+                "tablespace {name} does not exist"
+            )));
+        };
+
+        let sql = format!(
+            "SELECT datname, dattablespace = {tablespace_oid} FROM pg_database WHERE datallowconn ORDER BY datname"
+        );
+        let databases = match self.maintenance_query(sql).await {
+            Ok(rows) => rows,
+            Err(error) => return Err(TablespaceError(error.into())),
+        };
+        for database in databases {
+            let [database_name, is_default] = database.as_slice() else {
+                continue;
+            };
+            match force_move_to {
+                Some(target) => {
+                    let target = quote_identifier(target);
+                    let source = quote_identifier(name);
+                    if is_default == "t" {
+                        debug!("Moving database {database_name} to tablespace {target}");
+                        let sql = format!(
+                            "ALTER DATABASE {} SET TABLESPACE {target}",
+                            quote_identifier(database_name)
+                        );
+                        if let Err(error) = self.maintenance_query(sql).await {
+                            return Err(TablespaceError(error.into()));
+                        }
+                    }
+                    debug!("Moving relations in database {database_name} to tablespace {target}");
+                    let sql = format!(
+                        "ALTER TABLE ALL IN TABLESPACE {source} SET TABLESPACE {target}; \
+                         ALTER INDEX ALL IN TABLESPACE {source} SET TABLESPACE {target}; \
+                         ALTER MATERIALIZED VIEW ALL IN TABLESPACE {source} SET TABLESPACE {target}"
+                    );
+                    if let Err(error) = self.query(database_name, sql).await {
+                        return Err(TablespaceError(error.into()));
+                    }
+                }
+                None => {
+                    if is_default == "t" {
+                        return Err(TablespaceError(anyhow::anyhow!(
+                            "tablespace {name} is the default tablespace of database {database_name}"
+                        )));
+                    }
+                    let sql = format!(
+                        "SELECT count(*) FROM pg_class WHERE reltablespace = {tablespace_oid}"
+                    );
+                    let relations = match self.query(database_name, sql).await {
+                        Ok(rows) => rows
+                            .first()
+                            .and_then(|row| row.first())
+                            .and_then(|count| count.parse::<u64>().ok())
+                            .unwrap_or_default(),
+                        Err(error) => return Err(TablespaceError(error.into())),
+                    };
+                    if relations > 0 {
+                        return Err(TablespaceError(anyhow::anyhow!(
+                            "tablespace {name} is not empty; {relations} relations remain in database {database_name}"
+                        )));
+                    }
+                }
+            }
+        }
+
+        debug!("Dropping tablespace {name}");
+        let sql = format!("DROP TABLESPACE {}", quote_identifier(name));
+        if let Err(error) = self.maintenance_query(sql).await {
+            return Err(TablespaceError(error.into()));
+        }
+        if let (true, Some(location)) = (tablespace.managed, tablespace.location) {
+            if location.exists() {
+                remove_dir_all(location)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// List the tablespaces of the server, including the built-in `pg_default` and `pg_global`
+    /// tablespaces
+    #[instrument(skip(self))]
+    pub async fn list_tablespaces(&self) -> Result<Vec<TablespaceInfo>> {
+        let sql = "SELECT spcname, pg_tablespace_location(oid) FROM pg_tablespace ORDER BY spcname";
+        match self.maintenance_query(sql).await {
+            Ok(rows) => Ok(tablespace::parse_tablespaces(
+                rows,
+                &tablespace::managed_dir(&self.settings.data_dir),
+            )),
+            Err(error) => Err(TablespaceError(error.into())),
+        }
+    }
+
+    /// List the tables in the given database, like the psql `\dt` meta-command. When a schema
+    /// is given, only the tables in that schema are listed; otherwise the tables in all schemas
+    /// except the system schemas are listed.
+    #[instrument(skip(self, database_name))]
+    pub async fn list_tables<S: AsRef<str>>(
+        &self,
+        database_name: S,
+        schema: Option<&str>,
+    ) -> Result<Vec<TableInfo>> {
+        let rows = self
+            .catalog_query(database_name, catalog::tables_query(schema))
+            .await?;
+        Ok(catalog::parse_tables(rows))
+    }
+
+    /// Describe the columns, indexes and constraints of a table in the given database, like the
+    /// psql `\d` meta-command. The table name may be schema qualified, and is resolved using
+    /// the search path otherwise; names that require quoting must be quoted, e.g.
+    /// `"My Schema"."My Table"`.
+    #[instrument(skip(self, database_name, table))]
+    pub async fn describe_table<S: AsRef<str>, T: AsRef<str>>(
+        &self,
+        database_name: S,
+        table: T,
+    ) -> Result<TableDescription> {
+        let database_name = database_name.as_ref();
+        let relation = quote_literal(table.as_ref());
+        let rows = self
+            .catalog_query(database_name, catalog::table_query(&relation))
+            .await?;
+        let (schema, name) = match rows.into_iter().next().as_deref() {
+            Some([schema, name]) => (schema.to_string(), name.to_string()),
+            _ => {
+                return Err(CatalogError(anyhow::anyhow!(
+                    "Table {relation} does not exist"
+                )))
+            }
+        };
+
+        let columns = self
+            .catalog_query(database_name, catalog::columns_query(&relation))
+            .await?;
+        let indexes = self
+            .catalog_query(database_name, catalog::table_indexes_query(&relation))
+            .await?;
+        let constraints = self
+            .catalog_query(database_name, catalog::constraints_query(&relation))
+            .await?;
+        let foreign_keys = self
+            .catalog_query(database_name, catalog::foreign_keys_query(&relation))
+            .await?;
+
+        Ok(TableDescription {
+            schema,
+            name,
+            columns: catalog::parse_columns(columns),
+            indexes: catalog::parse_indexes(indexes),
+            constraints: catalog::parse_constraints(constraints),
+            foreign_keys: catalog::parse_foreign_keys(foreign_keys),
+        })
+    }
+
+    /// List the indexes in the given database, like the psql `\di` meta-command. When a schema
+    /// is given, only the indexes in that schema are listed; otherwise the indexes in all
+    /// schemas except the system schemas are listed.
+    #[instrument(skip(self, database_name))]
+    pub async fn list_indexes<S: AsRef<str>>(
+        &self,
+        database_name: S,
+        schema: Option<&str>,
+    ) -> Result<Vec<IndexInfo>> {
+        let rows = self
+            .catalog_query(database_name, catalog::schema_indexes_query(schema))
+            .await?;
+        Ok(catalog::parse_indexes(rows))
+    }
+
+    /// List the functions, procedures and aggregates in the given database, like the psql `\df`
+    /// meta-command. When a schema is given, only the functions in that schema are listed;
+    /// otherwise the functions in all schemas except the system schemas are listed.
+    #[instrument(skip(self, database_name))]
+    pub async fn list_functions<S: AsRef<str>>(
+        &self,
+        database_name: S,
+        schema: Option<&str>,
+    ) -> Result<Vec<FunctionInfo>> {
+        let rows = self
+            .catalog_query(database_name, catalog::functions_query(schema))
+            .await?;
+        Ok(catalog::parse_functions(rows))
+    }
+
+    /// Execute a catalog query against the given database and return the resulting rows
+    async fn catalog_query<S: AsRef<str>>(
+        &self,
+        database_name: S,
+        sql: String,
+    ) -> Result<Vec<Vec<String>>> {
+        match self.query(database_name, sql).await {
+            Ok(rows) => Ok(rows),
+            Err(error) => Err(CatalogError(error.into())),
+        }
+    }
+
+    /// Execute the SQL against the given database and return the resulting rows.
+    #[instrument(skip(self, database_name, sql))]
+    pub async fn run_sql<S: AsRef<str>, Q: AsRef<str>>(
+        &self,
+        database_name: S,
+        sql: Q,
+    ) -> Result<QueryResult> {
+        match self.query(database_name, sql).await {
+            Ok(rows) => Ok(QueryResult {
+                rows,
+                notices: Vec::new(),
+            }),
+            Err(error) => Err(SqlError(error.into())),
+        }
+    }
+
+    /// Execute the SQL against the given database in a new psql process rather than on a
+    /// reused [psql session](Settings::psql_sessions), and return the resulting rows. Use it for
+    /// statements that must run in a session of their own, e.g. statements that depend on
+    /// `SET ROLE` or `SET SESSION AUTHORIZATION` side effects of the session, or that must not
+    /// share a backend with other statements.
+    #[instrument(skip(self, database_name, sql))]
+    pub async fn run_sql_in_new_session<S: AsRef<str>, Q: AsRef<str>>(
+        &self,
+        database_name: S,
+        sql: Q,
+    ) -> Result<QueryResult> {
+        let result = self
+            .execute_in_new_session(database_name.as_ref(), sql.as_ref(), self.settings.timeout)
+            .await;
+        match result {
+            Ok((stdout, _stderr)) => Ok(QueryResult {
+                rows: parse_rows(&stdout),
+                notices: Vec::new(),
+            }),
+            Err(error) => Err(SqlError(error.into())),
+        }
+    }
+
+    /// Execute the SQL against the given database and return the resulting rows along with the
+    /// [notices](QueryResult::notices) and warnings raised by the server, e.g. by
+    /// `RAISE NOTICE` in a function. Notices below `client_min_messages` are not sent by the
+    /// server.
+    #[instrument(skip(self, database_name, sql))]
+    pub async fn query_with_notices<S: AsRef<str>, Q: AsRef<str>>(
+        &self,
+        database_name: S,
+        sql: Q,
+    ) -> Result<QueryResult> {
+        let result = self
+            .execute_query(database_name.as_ref(), sql.as_ref(), self.settings.timeout)
+            .await;
+        match result {
+            Ok((stdout, stderr)) => Ok(QueryResult {
+                rows: parse_rows(&stdout),
+                notices: batch::parse_notices(&stderr),
+            }),
+            Err(error) => Err(SqlError(error.into())),
+        }
+    }
+
+    /// Vacuum the table in the given database and wait until the vacuum is reflected in the
+    /// cumulative statistics, i.e. until `last_vacuum` in `pg_stat_all_tables` advances. This
+    /// allows tests to assert on state that depends on a completed vacuum, such as the visibility
+    /// map or the number of dead tuples. The table name may be schema qualified.
+    #[instrument(skip(self, database_name, table))]
+    pub async fn wait_for_vacuum<S: AsRef<str>, T: AsRef<str>>(
+        &self,
+        database_name: S,
+        table: T,
+        timeout: Duration,
+    ) -> Result<()> {
+        let database_name = database_name.as_ref();
+        let start = Instant::now();
+        let relation = quote_literal(table.as_ref());
+        // The regclass output is quoted and schema qualified as needed
+        let sql = format!(
+            "SELECT {relation}::regclass::text, coalesce(last_vacuum::text, '') \
+             FROM pg_catalog.pg_stat_all_tables WHERE relid = {relation}::regclass"
+        );
+        let (table, previous_vacuum) = match self.query(database_name, &sql).await {
+            Ok(rows) => match rows.into_iter().next().as_deref() {
+                Some([table, last_vacuum]) => (table.to_string(), last_vacuum.to_string()),
+                _ => {
+                    return Err(VacuumError(anyhow::anyhow!(
+                        "Table {relation} does not exist"
+                    )))
+                }
+            },
+            Err(error) => return Err(VacuumError(error.into())),
+        };
+
+        debug!("Vacuuming {table} in database {database_name}");
+        let vacuum = format!("VACUUM {table}");
+        if let Err(error) = self
+            .execute_query(database_name, &vacuum, Some(timeout))
+            .await
+        {
+            return Err(VacuumError(error.into()));
+        }
+
+        // Statistics are reported asynchronously, so the vacuum may not be visible immediately
+        loop {
+            let last_vacuum = match self.query(database_name, &sql).await {
+                Ok(rows) => rows.into_iter().flatten().nth(1).unwrap_or_default(),
+                Err(error) => return Err(VacuumError(error.into())),
+            };
+            if !last_vacuum.is_empty() && last_vacuum != previous_vacuum {
+                debug!("Vacuum of {table} completed at {last_vacuum}");
+                return Ok(());
+            }
+            if start.elapsed() > timeout {
+                return Err(VacuumError(anyhow::anyhow!(
+                    "Timed out waiting for the vacuum of {table} to complete"
+                )));
+            }
+            #[cfg(feature = "tokio")]
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            #[cfg(not(feature = "tokio"))]
+            std::thread::sleep(Duration::from_millis(100));
+        }
+    }
+
+    /// Execute independent SQL statements against the given database, with up to
+    /// [max_concurrency](BatchOptions::max_concurrency) statements running concurrently, each in
+    /// its own psql session; see [psql_sessions](Settings::psql_sessions). The concurrency is
+    /// limited by the connections available on the server, leaving headroom for other clients.
+    ///
+    /// Returns one result per statement, in the order of the statements. When
+    /// [stop_on_first_error](BatchOptions::stop_on_first_error) is set, the statements that were
+    /// not started before the first failure are skipped and return an error.
+    ///
+    /// Statements only run concurrently, and the
+    /// [per_statement_timeout](BatchOptions::per_statement_timeout) is only applied, with the
+    /// `tokio` feature; without it, commands run synchronously, so the statements run one after
+    /// another without a timeout.
+    #[instrument(skip(self, database_name, statements))]
+    pub async fn run_sql_batch<S: AsRef<str>>(
+        &self,
+        database_name: S,
+        statements: Vec<String>,
+        options: BatchOptions,
+    ) -> Vec<Result<QueryResult>> {
+        let database_name = database_name.as_ref();
+        let available_connections = match self
+            .maintenance_query(
+                "SELECT current_setting('max_connections')::integer - (SELECT count(*) FROM pg_stat_activity WHERE backend_type = 'client backend')",
+            )
+            .await
+        {
+            Ok(rows) => rows
+                .first()
+                .and_then(|row| row.first())
+                .and_then(|value| value.parse::<usize>().ok()),
+            Err(error) => {
+                warn!("Unable to determine available connections: {error}");
+                None
+            }
+        };
+        let concurrency = batch::concurrency(options.max_concurrency, available_connections);
+        #[cfg(not(feature = "tokio"))]
+        let concurrency = {
+            if options.per_statement_timeout.is_some() {
+                warn!("Statement timeouts require the tokio feature; the timeout is ignored");
+            }
+            concurrency.min(1)
+        };
+        debug!(
+            "Running {} statements with concurrency {concurrency}",
+            statements.len()
+        );
+
+        let timeout = options.per_statement_timeout.or(self.settings.timeout);
+        let failed = AtomicBool::new(false);
+        let mut results: Vec<Option<Result<QueryResult>>> =
+            statements.iter().map(|_| None).collect();
+        let mut completed = stream::iter(0..statements.len())
+            .map(|index| {
+                let statement = &statements[index];
+                let failed = &failed;
+                async move {
+                    if options.stop_on_first_error && failed.load(Ordering::SeqCst) {
+                        return (
+                            index,
+                            Err(SqlError(anyhow::anyhow!(
+                                "Statement {index} skipped after an earlier statement failed"
+                            ))),
+                        );
+                    }
+                    let result = match self.execute_query(database_name, statement, timeout).await {
+                        Ok((stdout, _stderr)) => Ok(QueryResult {
+                            rows: parse_rows(&stdout),
+                            notices: Vec::new(),
+                        }),
+                        Err(error) => {
+                            failed.store(true, Ordering::SeqCst);
+                            Err(SqlError(anyhow::anyhow!(
+                                "Statement {index} failed: {error}"
+                            )))
+                        }
+                    };
+                    (index, result)
+                }
+            })
+            .buffer_unordered(concurrency);
+        while let Some((index, result)) = completed.next().await {
+            results[index] = Some(result);
+        }
+
+        results
+            .into_iter()
+            .enumerate()
+            .map(|(index, result)| {
+                result.unwrap_or_else(|| {
+                    Err(SqlError(anyhow::anyhow!("Statement {index} was not run")))
+                })
+            })
+            .collect()
+    }
+
+    /// Run a pgbench benchmark: the pgbench tables are initialized in the
+    /// [database](BenchOptions::database_name) at the given [scale](BenchOptions::scale), then
+    /// the default TPC-B like workload is run for the given duration and number of clients.
+    /// Intended for quick performance sanity checks rather than rigorous benchmarking.
+    #[instrument(skip(self))]
+    pub async fn benchmark(&self, options: BenchOptions) -> Result<BenchResult> {
+        let database_name = options
+            .database_name
+            .as_deref()
+            .unwrap_or(&self.settings.maintenance_database);
+        let pgbench = PgBenchBuilder::from(&self.settings)
+            .username(BOOTSTRAP_SUPERUSER)
+            .dbname(database_name);
+
+        debug!(
+            "Initializing pgbench tables in database {database_name} at scale {}",
+            options.scale
+        );
+        let initialize = pgbench.clone().initialize().scale(options.scale).quiet();
+        // Initialization takes longer the larger the scale, so the timeout is extended accordingly
+        let scale = u32::try_from(options.scale).unwrap_or(u32::MAX);
+        let timeout = self.settings.timeout.map(|timeout| {
+            timeout.saturating_add(benchmark::INITIALIZE_TIME_PER_SCALE.saturating_mul(scale))
+        });
+        if let Err(error) = self.execute_command_with_timeout(initialize, timeout).await {
+            return Err(BenchmarkError(error.into()));
+        }
+
+        let seconds = usize::try_from(options.duration.as_secs())
+            .unwrap_or(usize::MAX)
+            .max(1);
+        debug!(
+            "Running pgbench with {} clients for {seconds} seconds",
+            options.clients
+        );
+        let run = pgbench
+            .client(options.clients)
+            .jobs(options.jobs)
+            .time(seconds);
+        // The run takes at least the requested duration, so the timeout is extended accordingly
+        let timeout = self
+            .settings
+            .timeout
+            .map(|timeout| timeout + options.duration);
+        match self.execute_command_with_timeout(run, timeout).await {
+            Ok((stdout, _stderr)) => benchmark::parse_bench_result(&stdout),
+            Err(error) => Err(BenchmarkError(error.into())),
+        }
+    }
+
+    /// Get the disk space consumed by the server. The sizes of the installation, data, and WAL
+    /// directories are determined from the filesystem; the sizes of the databases and the largest
+    /// relations are queried from the server, and are only reported when the server is started.
+    #[instrument(skip(self))]
+    pub async fn disk_usage(&self) -> Result<DiskUsage> {
+        let mut disk_usage = DiskUsage {
+            installation_bytes: disk_usage::directory_size(&self.settings.installation_dir)?,
+            data_dir_bytes: disk_usage::directory_size(&self.settings.data_dir)?,
+            wal_bytes: disk_usage::directory_size(&self.settings.data_dir.join("pg_wal"))?,
+            ..Default::default()
+        };
+        if self.status() != Status::Started {
+            debug!("Server is not started; database sizes are not reported");
+            return Ok(disk_usage);
+        }
+
+        let rows = match self
+            .maintenance_query(
+                "SELECT datname, pg_database_size(datname) FROM pg_database WHERE datallowconn ORDER BY 2 DESC, 1",
+            )
+            .await
+        {
+            Ok(rows) => rows,
+            Err(error) => return Err(DiskUsageError(error.into())),
+        };
+        disk_usage.per_database = rows
+            .into_iter()
+            .filter_map(|row| match row.as_slice() {
+                [database_name, bytes] => Some((database_name.clone(), bytes.parse().ok()?)),
+                _ => None,
+            })
+            .collect();
+
+        let mut relations = Vec::new();
+        for (database_name, _) in &disk_usage.per_database {
+            let sql = format!(
+                "SELECT n.nspname, c.relname, pg_total_relation_size(c.oid) \
+                FROM pg_class c JOIN pg_namespace n ON n.oid = c.relnamespace \
+                WHERE c.relkind IN ('r', 'm', 'p') \
+                AND n.nspname NOT IN ('pg_catalog', 'information_schema') \
+                AND n.nspname NOT LIKE 'pg_toast%' \
+                ORDER BY 3 DESC LIMIT {}",
+                disk_usage::LARGEST_RELATIONS_LIMIT
+            );
+            let rows = match self.query(database_name, sql).await {
+                Ok(rows) => rows,
+                Err(error) => return Err(DiskUsageError(error.into())),
+            };
+            relations.extend(rows.into_iter().filter_map(|row| match row.as_slice() {
+                [schema, name, bytes] => Some(RelationSize {
+                    database: database_name.clone(),
+                    schema: schema.clone(),
+                    name: name.clone(),
+                    bytes: bytes.parse().ok()?,
+                }),
+                _ => None,
+            }));
+        }
+        disk_usage.largest_relations = disk_usage::largest_relations(relations);
+
+        Ok(disk_usage)
+    }
+
+    /// Watch the available disk space on the filesystem of the data directory. The callback is
+    /// invoked on a background thread when the available space drops below the threshold (in
+    /// bytes), and again only after the available space has recovered above it. The space is
+    /// checked every ten seconds until the returned watcher is dropped.
+    pub fn low_disk_watcher<F>(&self, threshold_bytes: u64, callback: F) -> LowDiskWatcher
+    where
+        F: Fn(LowDiskEvent) + Send + 'static,
+    {
+        LowDiskWatcher::start(
+            self.settings.data_dir.clone(),
+            threshold_bytes,
+            disk_usage::DISK_CHECK_INTERVAL,
+            callback,
+        )
+    }
+
+    /// Start a TCP proxy in front of the server that records the SQL statements sent by clients,
+    /// to debug the statements an application sends without enabling `log_statement` for the
+    /// whole server. Clients connect to the [proxy endpoint](ProxyHandle::connection_info) in
+    /// place of the server; SSL is declined by the proxy, so clients must not require it. The
+    /// proxy stops when the returned handle is dropped.
+    #[instrument(skip(self))]
+    pub fn statement_logging_proxy(&self) -> Result<ProxyHandle> {
+        ProxyHandle::start(&self.settings)
+    }
+
+    /// Start a TCP proxy in front of the server that injects faults into the traffic (latency,
+    /// limited bandwidth, dropped and reset connections), to test the resilience of an
+    /// application. Clients connect to the [proxy endpoint](FaultInjectingProxy::settings) in
+    /// place of the server; the faults are controlled through the returned handle and the proxy
+    /// stops when it is dropped.
+    #[cfg(feature = "fault-injection")]
+    #[instrument(skip(self))]
+    pub fn fault_injecting_proxy(&self) -> Result<FaultInjectingProxy> {
+        FaultInjectingProxy::start(&self.settings)
+    }
+
+    /// Apply a lifecycle [operation](Operation) to the server; the operation is delegated to the
+    /// corresponding method. This provides a single entry point for orchestration code that
+    /// handles operations uniformly, e.g. to retry, rate limit, or journal them.
+    #[instrument(skip(self))]
+    pub async fn apply(&mut self, operation: Operation) -> OperationOutcome {
+        let start = Instant::now();
+        let output = match &operation {
+            Operation::Setup => self.setup().await.map(|()| OperationOutput::Completed),
+            Operation::Start => self.start().await.map(|()| OperationOutput::Completed),
+            Operation::Stop => self.stop().await.map(|()| OperationOutput::Completed),
+            Operation::CreateDatabase { database_name } => self
+                .create_database(database_name)
+                .await
+                .map(|()| OperationOutput::Completed),
+            Operation::DatabaseExists { database_name } => self
+                .database_exists(database_name)
+                .await
+                .map(OperationOutput::Exists),
+            Operation::DropDatabase { database_name } => self
+                .drop_database(database_name)
+                .await
+                .map(|()| OperationOutput::Completed),
+            Operation::Backup {
+                database_name,
+                path,
+            } => self
+                .backup(database_name, path)
+                .await
+                .map(|()| OperationOutput::Completed),
+            Operation::CreatePhysicalSlot { slot_name } => self
+                .create_physical_slot(slot_name)
+                .await
+                .map(|()| OperationOutput::Completed),
+            Operation::DropSlot { slot_name } => self
+                .drop_slot(slot_name)
+                .await
+                .map(|()| OperationOutput::Completed),
+            Operation::ResetRoles { keep } => {
+                let keep: Vec<&str> = keep.iter().map(String::as_str).collect();
+                self.reset_roles(&keep).await.map(OperationOutput::Roles)
+            }
+            Operation::RunSql { database_name, sql } => self
+                .run_sql(database_name, sql)
+                .await
+                .map(|result| OperationOutput::Rows(result.rows)),
+        };
+
+        OperationOutcome {
+            operation,
+            output: output.map_err(|error| error.to_string()),
+            duration: start.elapsed(),
+        }
+    }
+
+    /// Execute the SQL against the given database and return the resulting rows. Each row is
+    /// returned as a vector of column values; `NULL` values are returned as empty strings.
+    #[instrument(level = "debug", skip(database_name, sql))]
+    pub(crate) async fn query<S: AsRef<str>, Q: AsRef<str>>(
+        &self,
+        database_name: S,
+        sql: Q,
+    ) -> postgresql_commands::Result<Vec<Vec<String>>> {
+        let (stdout, _stderr) = self
+            .execute_query(database_name.as_ref(), sql.as_ref(), self.settings.timeout)
+            .await?;
+        Ok(parse_rows(&stdout))
+    }
+
+    /// Execute a SQL statement against the [maintenance database](Settings::maintenance_database)
+    /// and return the resulting rows; see [query](Self::query).
+    pub(crate) async fn maintenance_query<Q: AsRef<str>>(
+        &self,
+        sql: Q,
+    ) -> postgresql_commands::Result<Vec<Vec<String>>> {
+        let sql = sql.as_ref();
+        let maintenance_database = &self.settings.maintenance_database;
+        let timeout = self.settings.timeout;
+        let result = match self.execute_query(maintenance_database, sql, timeout).await {
+            Err(error) if self.is_missing_maintenance_database(&error) => {
+                warn!(
+                    "Maintenance database {maintenance_database} does not exist; using {FALLBACK_MAINTENANCE_DATABASE}"
+                );
+                self.execute_query(FALLBACK_MAINTENANCE_DATABASE, sql, timeout)
+                    .await
+            }
+            result => result,
+        };
+        let (stdout, _stderr) = result?;
+        Ok(parse_rows(&stdout))
+    }
+
+    /// Execute the SQL against the given database on a [psql session](Settings::psql_sessions)
+    /// and return the stdout and stderr as strings. The SQL runs in a new psql process, as with
+    /// `psql --command`, when the sessions are disabled or all in use, when the SQL contains
+    /// psql meta-commands or variables, or for servers older than PostgreSQL 13. A statement
+    /// that fails because the server closed the connection of a reused session, e.g. after a
+    /// restart, is retried in a new psql process.
+    pub(crate) async fn execute_query(
+        &self,
+        database_name: &str,
+        sql: &str,
+        timeout: Option<Duration>,
+    ) -> postgresql_commands::Result<(String, String)> {
+        let max_sessions = self.settings.psql_sessions;
+        // psql supports \warn, which delimits the stderr of a statement, from version 13
+        let script = if max_sessions == 0 || self.version.major < 13 {
+            None
+        } else {
+            session_pool::script(sql)
+        };
+        let Some(script) = script else {
+            return self
+                .execute_in_new_session(database_name, sql, timeout)
+                .await;
+        };
+        if script.database_level {
+            // Idle sessions keep the database they are connected to in use, which prevents it
+            // from being e.g. dropped, renamed or used as a template
+            self.session_pool.close_idle();
+        }
+
+        let port = self.settings.port;
+        let (session, reused) = match self
+            .session_pool
+            .checkout(database_name, port, max_sessions)
+        {
+            Checkout::Idle(session) => (*session, true),
+            Checkout::Spawn => match self.spawn_session(database_name) {
+                Ok(session) => (session, false),
+                Err(error) => {
+                    self.session_pool.checkin(None);
+                    return Err(error);
+                }
+            },
+            Checkout::Full => {
+                return self
+                    .execute_in_new_session(database_name, sql, timeout)
+                    .await;
+            }
+        };
+        match self.run_session(session, script.text, timeout).await {
+            Err(postgresql_commands::Error::CommandError { stderr, .. })
+                if reused && session_pool::connection_lost(&stderr) =>
+            {
+                debug!("psql session of database {database_name} lost its connection; retrying in a new psql process");
+                self.execute_in_new_session(database_name, sql, timeout)
+                    .await
+            }
+            result => result,
+        }
+    }
+
+    /// Execute the SQL against the given database in a new psql process
+    async fn execute_in_new_session(
+        &self,
+        database_name: &str,
+        sql: &str,
+        timeout: Option<Duration>,
+    ) -> postgresql_commands::Result<(String, String)> {
+        let psql = self.query_command(sql).dbname(database_name);
+        self.execute_command_with_timeout(psql, timeout).await
+    }
+
+    /// Start a psql session connected to the database for the [session pool](SessionPool)
+    fn spawn_session(&self, database_name: &str) -> postgresql_commands::Result<Session> {
+        let psql = self.psql_command().dbname(database_name);
+        self.check_tool(&psql)?;
+        let passfile = psql.get_passfile();
+        let mut command = psql.build();
+        self.set_locale_environment(&mut command);
+        self.set_cgroup(&mut command);
+        Session::spawn(command, database_name, self.settings.port, passfile)
+    }
+
+    /// Run the script on the session and return the session to the pool once it is reset; the
+    /// session is closed instead when the script fails or the session reached the
+    /// [maximum number of uses](Settings::psql_session_max_uses). With the `tokio` feature, the
+    /// psql process is killed when the timeout elapses.
+    async fn run_session(
+        &self,
+        mut session: Session,
+        script: String,
+        timeout: Option<Duration>,
+    ) -> postgresql_commands::Result<(String, String)> {
+        let max_uses = self.settings.psql_session_max_uses;
+        let session_pool = self.session_pool.clone();
+        #[cfg(feature = "tokio")]
+        let child = session.child();
+        let task = run_blocking(move || {
+            let result = session.execute(&script);
+            let reusable = result.is_ok() && session.uses() < max_uses && session.reset();
+            session_pool.checkin(reusable.then_some(session));
+            result
+        });
+
+        #[cfg(feature = "tokio")]
+        if let Some(timeout) = timeout {
+            return match tokio::time::timeout(timeout, task).await {
+                Ok(result) => result,
+                Err(error) => {
+                    session_pool::kill(&child);
+                    Err(error.into())
+                }
+            };
+        }
+        #[cfg(not(feature = "tokio"))]
+        let _ = timeout;
+        task.await
+    }
+
+    /// Get the [statistics](SessionPoolStats) of the [psql sessions](Settings::psql_sessions)
+    /// used by the SQL helpers, e.g. to check that repeated statements reuse the sessions
+    pub fn session_pool_stats(&self) -> SessionPoolStats {
+        self.session_pool.stats()
+    }
+
+    /// Check whether the error reports that the
+    /// [maintenance database](Settings::maintenance_database) does not exist, in which case
+    /// [`FALLBACK_MAINTENANCE_DATABASE`] is used instead
+    fn is_missing_maintenance_database(&self, error: &postgresql_commands::Error) -> bool {
+        let maintenance_database = &self.settings.maintenance_database;
+        match error {
+            postgresql_commands::Error::CommandError { stderr, .. } => {
+                maintenance_database != FALLBACK_MAINTENANCE_DATABASE
+                    && stderr.contains(&format!(
+                        "database \"{maintenance_database}\" does not exist"
+                    ))
+            }
+            _ => false,
+        }
+    }
+
+    /// Build the psql command used to execute a query
+    fn query_command<Q: AsRef<str>>(&self, sql: Q) -> PsqlBuilder {
+        self.psql_command().command(sql.as_ref())
+    }
+
+    /// Build the psql command used to execute queries, without the queries; the results are
+    /// printed unaligned with [`FIELD_SEPARATOR`] and [`RECORD_SEPARATOR`]
+    fn psql_command(&self) -> PsqlBuilder {
+        PsqlBuilder::from(&self.settings)
+            .username(BOOTSTRAP_SUPERUSER)
+            .variable(("ON_ERROR_STOP", "1"))
+            .no_psqlrc()
+            .quiet()
+            .no_align()
+            .tuples_only()
+            .field_separator(FIELD_SEPARATOR.to_string())
+            .record_separator(RECORD_SEPARATOR.to_string())
+    }
+
+    /// Execute a psql command connected to the [maintenance database](Settings::maintenance_database).
+    /// When the maintenance database does not exist, the command is retried against
+    /// [`FALLBACK_MAINTENANCE_DATABASE`].
+    pub(crate) async fn execute_maintenance_command(
+        &self,
+        psql: PsqlBuilder,
+    ) -> postgresql_commands::Result<(String, String)> {
+        let maintenance_database = &self.settings.maintenance_database;
+        let result = self
+            .execute_command(psql.clone().dbname(maintenance_database))
+            .await;
+        match result {
+            Err(error) if self.is_missing_maintenance_database(&error) => {
+                warn!(
+                    "Maintenance database {maintenance_database} does not exist; using {FALLBACK_MAINTENANCE_DATABASE}"
+                );
+                self.execute_command(psql.dbname(FALLBACK_MAINTENANCE_DATABASE))
+                    .await
+            }
+            result => result,
+        }
+    }
+
+    /// Run a blocking operation, passing it a token that is cancelled when the returned future
+    /// is dropped before the operation completes, or when the [timeout](Settings::timeout)
+    /// elapses (with the `tokio` feature). Dropping the future blocks the dropping thread until
+    /// the operation has returned, so that it can kill the processes it started and remove their
+    /// partial output before the caller continues; the wait is bounded by [`CANCEL_TIMEOUT`],
+    /// after which the operation finishes in the background.
+    async fn run_cancel_safe<T, F>(&self, operation: F) -> postgresql_commands::Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&CancellationToken) -> postgresql_commands::Result<T> + Send + 'static,
+    {
+        let cancel = CancellationToken::new();
+        let (done, finished) = std::sync::mpsc::channel::<()>();
+        let guard = CancelOnDrop {
+            cancel: cancel.clone(),
+            finished,
+        };
+        let operation = move || {
+            let _done = done;
+            operation(&cancel)
+        };
+
+        #[cfg(feature = "tokio")]
+        if let Some(timeout) = self.settings.timeout {
+            let result = tokio::time::timeout(timeout, run_blocking(operation)).await;
+            drop(guard);
+            return result?;
+        }
+        let result = run_blocking(operation).await;
+        drop(guard);
+        result
+    }
+
+    /// Execute a command and return the stdout and stderr as strings.
+    pub(crate) async fn execute_command<B: CommandBuilder>(
+        &self,
+        command_builder: B,
+    ) -> postgresql_commands::Result<(String, String)> {
+        self.execute_command_with_timeout(command_builder, self.settings.timeout)
+            .await
+    }
+
+    /// Check that the program of the command is present in the installation before it is run,
+    /// so that a missing program is reported as such rather than as a failure to spawn it.
+    /// Programs outside of the binary directory and installations that were not scanned are
+    /// not checked.
+    fn check_tool<B: CommandBuilder>(
+        &self,
+        command_builder: &B,
+    ) -> postgresql_commands::Result<()> {
+        if command_builder.get_program_dir().as_deref() != Some(self.inventory.binary_dir()) {
+            return Ok(());
+        }
+        let Some(tool) = command_builder
+            .get_program()
+            .to_str()
+            .and_then(Tool::from_program)
+        else {
+            return Ok(());
+        };
+        if self.inventory.is_present(tool) {
+            return Ok(());
+        }
+        Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("{tool} is not present in this installation"),
+        )
+        .into())
+    }
+
+    #[cfg(not(feature = "tokio"))]
+    /// Execute a command and return the stdout and stderr as strings; the timeout is only
+    /// supported with the `tokio` feature.
+    pub(crate) async fn execute_command_with_timeout<B: CommandBuilder>(
+        &self,
+        command_builder: B,
+        _timeout: Option<Duration>,
+    ) -> postgresql_commands::Result<(String, String)> {
+        self.check_tool(&command_builder)?;
+        let _passfile = command_builder.get_passfile();
+        let mut command = command_builder.build();
+        self.set_locale_environment(&mut command);
+        self.set_cgroup(&mut command);
+        command.execute()
+    }
+
+    #[cfg(feature = "tokio")]
+    /// Execute a command with the given timeout and return the stdout and stderr as strings.
+    #[instrument(level = "debug")]
+    pub(crate) async fn execute_command_with_timeout<B: CommandBuilder>(
+        &self,
+        command_builder: B,
+        timeout: Option<Duration>,
+    ) -> postgresql_commands::Result<(String, String)> {
+        self.check_tool(&command_builder)?;
+        let _passfile = command_builder.get_passfile();
+        let mut command = command_builder.build_tokio();
+        if !self.settings.inherit_locale {
+            command
+                .env_remove("LC_ALL")
+                .envs(self.settings.locale_envs());
+        }
+        self.set_cgroup(&mut command);
+        command.execute(timeout).await
+    }
+
+    /// Set the locale environment of the command to the configured locale, unless the locale is
+    /// inherited. `LC_ALL` is removed because it takes precedence over all other categories.
+    fn set_locale_environment(&self, command: &mut std::process::Command) {
+        if !self.settings.inherit_locale {
+            command
+                .env_remove("LC_ALL")
+                .envs(self.settings.locale_envs());
+        }
+    }
+
+    /// Place the command in the configured [cgroup](Settings::cgroup)
+    #[cfg(target_os = "linux")]
+    fn set_cgroup<C: CgroupCommand>(&self, command: &mut C) {
+        if let Some(cgroup) = &self.settings.cgroup {
+            command.cgroup(cgroup);
+        }
+    }
+
+    /// Cgroups are only supported on Linux; the configured [cgroup](Settings::cgroup) is ignored
+    #[cfg(not(target_os = "linux"))]
+    fn set_cgroup<C>(&self, _command: &mut C) {
+        if self.settings.cgroup.is_some() {
+            warn!("Cgroups are only supported on Linux; the cgroup is ignored");
+        }
+    }
+}
+
+/// Cancels a blocking operation when dropped and blocks until the operation has returned, for
+/// at most [`CANCEL_TIMEOUT`]; see [run_cancel_safe](PostgreSQL::run_cancel_safe)
+struct CancelOnDrop {
+    cancel: CancellationToken,
+    /// Disconnected when the operation returns
+    finished: std::sync::mpsc::Receiver<()>,
+}
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        self.cancel.cancel();
+        if let Err(RecvTimeoutError::Timeout) = self.finished.recv_timeout(CANCEL_TIMEOUT) {
+            warn!(
+                "Cancelled operation did not return within {}s; continuing without waiting",
+                CANCEL_TIMEOUT.as_secs()
+            );
+        }
+    }
+}
+
+/// Autovacuum turned off by [with_autovacuum_paused](PostgreSQL::with_autovacuum_paused); the
+/// prior setting is restored when the pause is dropped without being restored, e.g. when the
+/// operation panics
+pub(crate) struct AutovacuumPause<'a> {
+    postgresql: &'a PostgreSQL,
+    restore_sql: String,
+    previous: String,
+    restored: bool,
+}
+
+impl AutovacuumPause<'_> {
+    /// Restore the prior autovacuum setting and reload the configuration
+    pub(crate) async fn restore(mut self) -> Result<()> {
+        debug!("Restoring autovacuum");
+        self.restored = true;
+        self.postgresql
+            .alter_system(&self.restore_sql, &self.previous)
+            .await
+    }
+}
+
+impl Drop for AutovacuumPause<'_> {
+    fn drop(&mut self) {
+        if self.restored {
+            return;
+        }
+        warn!("Restoring autovacuum after the operation did not complete");
+        for sql in [self.restore_sql.as_str(), "SELECT pg_reload_conf()"] {
+            let psql = self
+                .postgresql
+                .query_command(sql)
+                .dbname(&self.postgresql.settings.maintenance_database);
+            let mut command = psql.build();
+            self.postgresql.set_locale_environment(&mut command);
+            if let Err(error) = command.output() {
+                warn!("Failed to restore autovacuum: {error}");
+            }
+        }
+    }
+}
+
+/// Build the `SHOW` statement for the configuration parameter; each part of a qualified name
+/// such as `auto_explain.log_min_duration` is quoted separately.
+fn show_statement(name: &str) -> String {
+    let name = name
+        .split('.')
+        .map(quote_identifier)
+        .collect::<Vec<String>>()
+        .join(".");
+    format!("SHOW {name}")
+}
+
+/// Run a blocking operation, such as a command that reports progress; with the `tokio` feature
+/// the operation runs on the blocking thread pool so that it does not stall the runtime.
+async fn run_blocking<T, F>(operation: F) -> postgresql_commands::Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> postgresql_commands::Result<T> + Send + 'static,
+{
+    #[cfg(feature = "tokio")]
+    {
+        match tokio::task::spawn_blocking(operation).await {
+            Ok(result) => result,
+            Err(error) => Err(postgresql_commands::Error::IoError(error.into())),
+        }
+    }
+    #[cfg(not(feature = "tokio"))]
+    {
+        operation()
+    }
+}
+
+/// Convert a command error to an embedded error, using [CancelledError] when the command was
+/// cancelled
+fn cancellable_error(
+    error: postgresql_commands::Error,
+    convert: fn(anyhow::Error) -> crate::Error,
+) -> crate::Error {
+    match error {
+        postgresql_commands::Error::CancelledError { .. } => CancelledError(error.into()),
+        error => convert(error.into()),
+    }
+}
+
+/// Remove the partial output of a cancelled command; a directory that existed before the command
+/// started is emptied rather than removed. Returns whether the output was removed.
+fn remove_partial_output(path: &Path, existed: bool) -> bool {
+    let result = if path.is_dir() {
+        remove_dir_all(path).and_then(|()| match existed {
+            true => std::fs::create_dir(path),
+            false => Ok(()),
+        })
+    } else if path.exists() {
+        remove_file(path)
+    } else {
+        Ok(())
+    };
+    if let Err(error) = result {
+        warn!(
+            "Failed to remove partial output {}: {error}",
+            path.to_string_lossy()
+        );
+        return false;
+    }
+    true
+}
+
+/// Parse the process ids in the first column of the rows
+fn parse_pids(rows: Vec<Vec<String>>) -> Vec<u32> {
+    rows.into_iter()
+        .filter_map(|row| row.into_iter().next())
+        .filter_map(|pid| pid.parse::<u32>().ok())
+        .collect()
+}
+
+/// Parse the rows of a query executed with [`FIELD_SEPARATOR`] and [`RECORD_SEPARATOR`]
+fn parse_rows(stdout: &str) -> Vec<Vec<String>> {
+    let stdout = stdout.strip_suffix('\n').unwrap_or(stdout);
+    if stdout.is_empty() {
+        return Vec::new();
+    }
+
+    stdout
+        .split(RECORD_SEPARATOR)
+        .map(|row| row.split(FIELD_SEPARATOR).map(str::to_string).collect())
+        .collect()
+}
+
+/// Select the port for a server started with a dynamic port; the previous port is preferred
+/// when it is available
+fn select_port(previous_port: Option<u16>) -> Result<u16> {
+    if let Some(port) = previous_port {
+        if TcpListener::bind(("0.0.0.0", port)).is_ok() {
+            debug!("Reusing previous port {port}");
+            return Ok(port);
+        }
+        warn!("Previous port {port} is not available; selecting a new port");
+    }
+
+    random_port()
+}
+
+/// Select a random available port
+fn random_port() -> Result<u16> {
+    let listener = TcpListener::bind(("0.0.0.0", 0))?;
+    Ok(listener.local_addr()?.port())
+}
+
+/// Default PostgreSQL server
+impl Default for PostgreSQL {
+    fn default() -> Self {
+        Self::from(Settings::default())
+    }
+}
+
+/// Create a new [`PostgreSQL`] instance for the [version](Settings::version) of the settings,
+/// or the [default version](PostgreSQL::default_version) when not set
+impl From<Settings> for PostgreSQL {
+    fn from(settings: Settings) -> Self {
+        let version = settings.version.unwrap_or_else(PostgreSQL::default_version);
+        Self::new(version, settings)
+    }
+}
+
+/// Stop the PostgreSQL server and remove the data directory if it is marked as temporary.
+impl Drop for PostgreSQL {
+    fn drop(&mut self) {
+        if self.status() == Status::Started {
+            self.session_pool.close_idle();
+            let mut pg_ctl = PgCtlBuilder::from(&self.settings)
+                .mode(Stop)
+                .pgdata(&self.settings.data_dir)
+                .shutdown_mode(Fast)
+                .wait()
+                .build();
+            self.set_locale_environment(&mut pg_ctl);
+
+            let _ = pg_ctl.output();
+            self.replace_server_log(None);
+            if !self.settings.temporary {
+                self.update_description();
+            }
+        }
+
+        if self.settings.temporary {
+            self.remove_temporary_files();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_send<T: Send>(_: T) {}
+
+    #[test]
+    fn test_run_sql_batch_is_send() {
+        let postgresql = PostgreSQL::default();
+        assert_send(postgresql.run_sql_batch("postgres", Vec::new(), BatchOptions::default()));
+    }
+
+    #[test]
+    fn test_show_statement() {
+        assert_eq!(r#"SHOW "port""#, super::show_statement("port"));
+        assert_eq!(
+            r#"SHOW "auto_explain"."log_min_duration""#,
+            super::show_statement("auto_explain.log_min_duration")
+        );
+    }
+
     #[test]
     #[cfg(feature = "bundled")]
     fn test_archive_version() {
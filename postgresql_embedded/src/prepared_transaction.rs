@@ -0,0 +1,82 @@
+//! Transactions prepared for two-phase commit that were never committed or rolled back; see
+//! [orphaned_prepared_transactions](crate::PostgreSQL::orphaned_prepared_transactions)
+
+use crate::sql::quote_literal;
+
+/// Query listing the prepared transactions of every database, oldest first
+pub(crate) const PREPARED_TRANSACTIONS_QUERY: &str =
+    "SELECT gid, database, owner, transaction::text, prepared FROM pg_catalog.pg_prepared_xacts \
+     ORDER BY prepared, gid";
+
+/// Transaction prepared with `PREPARE TRANSACTION` that has not been committed or rolled back.
+/// A prepared transaction survives the session and server restarts, and holds its locks until
+/// it is resolved.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PreparedTransaction {
+    /// Global identifier given to `PREPARE TRANSACTION`
+    pub gid: String,
+    /// Database the transaction was prepared in
+    pub database: String,
+    /// Role that prepared the transaction
+    pub owner: String,
+    /// Transaction id
+    pub transaction: u64,
+    /// Time the transaction was prepared, as reported by the server
+    pub prepared_at: String,
+}
+
+/// Parse the rows of the [prepared transactions query](PREPARED_TRANSACTIONS_QUERY)
+pub(crate) fn parse_prepared_transactions(rows: Vec<Vec<String>>) -> Vec<PreparedTransaction> {
+    rows.into_iter()
+        .filter_map(|row| match row.as_slice() {
+            [gid, database, owner, transaction, prepared_at] => Some(PreparedTransaction {
+                gid: gid.clone(),
+                database: database.clone(),
+                owner: owner.clone(),
+                transaction: transaction.parse().ok()?,
+                prepared_at: prepared_at.clone(),
+            }),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Build the statement rolling back the prepared transaction; it must run in the database the
+/// transaction was prepared in
+pub(crate) fn rollback_statement(gid: &str) -> String {
+    format!("ROLLBACK PREPARED {}", quote_literal(gid))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_prepared_transactions() {
+        let rows = vec![
+            vec![
+                "leaked".to_string(),
+                "app".to_string(),
+                "postgres".to_string(),
+                "742".to_string(),
+                "2024-01-01 00:00:00+00".to_string(),
+            ],
+            vec!["invalid".to_string()],
+        ];
+        assert_eq!(
+            vec![PreparedTransaction {
+                gid: "leaked".to_string(),
+                database: "app".to_string(),
+                owner: "postgres".to_string(),
+                transaction: 742,
+                prepared_at: "2024-01-01 00:00:00+00".to_string(),
+            }],
+            parse_prepared_transactions(rows)
+        );
+    }
+
+    #[test]
+    fn test_rollback_statement() {
+        assert_eq!("ROLLBACK PREPARED 'it''s'", rollback_statement("it's"));
+    }
+}
@@ -0,0 +1,288 @@
+//! Readiness probes that classify whether a server accepts connections. The native probe speaks
+//! just enough of the PostgreSQL wire protocol to do so without spawning a process or linking
+//! libpq; the process probe runs pg_isready.
+
+use std::io::{ErrorKind, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::Path;
+use std::time::Duration;
+
+/// Protocol version 3.0 sent in the startup message
+const PROTOCOL_VERSION: i32 = 196_608;
+
+/// SQLSTATE reported while the server is starting up, shutting down or in recovery
+/// (`cannot_connect_now`)
+const CANNOT_CONNECT_NOW: &str = "57P03";
+
+/// Readiness of a server reported by [ping](crate::PostgreSQL::ping)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Readiness {
+    /// The server accepts connections
+    Accepting,
+    /// The server is running but rejects connections, e.g. while it is starting up or
+    /// recovering (SQLSTATE `57P03`)
+    Rejecting,
+    /// No server is listening
+    Absent,
+}
+
+/// Probe used to check the readiness of a server; see [Settings::readiness_probe](crate::Settings::readiness_probe)
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd)]
+pub enum ReadinessProbe {
+    /// Perform the startup exchange of the wire protocol without spawning a process, falling
+    /// back to pg_isready when the response cannot be classified
+    #[default]
+    Native,
+    /// Run pg_isready
+    Process,
+}
+
+/// Probe the server listening on the TCP/IP address. `None` is returned when the response of
+/// the server cannot be classified.
+pub(crate) fn ping_tcp(
+    host: &str,
+    port: u16,
+    user: &str,
+    database: &str,
+    timeout: Duration,
+) -> Option<Readiness> {
+    let Ok(addresses) = (host, port).to_socket_addrs() else {
+        return Some(Readiness::Absent);
+    };
+    for address in addresses {
+        if let Ok(mut stream) = TcpStream::connect_timeout(&address, timeout) {
+            let _ = stream.set_read_timeout(Some(timeout));
+            let _ = stream.set_write_timeout(Some(timeout));
+            return exchange(&mut stream, user, database);
+        }
+    }
+    Some(Readiness::Absent)
+}
+
+/// Probe the server listening on the Unix-domain socket, e.g. `/tmp/.s.PGSQL.5432`. `None` is
+/// returned when the response of the server cannot be classified.
+#[cfg(unix)]
+pub(crate) fn ping_unix(
+    socket: &Path,
+    user: &str,
+    database: &str,
+    timeout: Duration,
+) -> Option<Readiness> {
+    let Ok(mut stream) = std::os::unix::net::UnixStream::connect(socket) else {
+        return Some(Readiness::Absent);
+    };
+    let _ = stream.set_read_timeout(Some(timeout));
+    let _ = stream.set_write_timeout(Some(timeout));
+    exchange(&mut stream, user, database)
+}
+
+/// Send the startup message and classify the first response of the server, like `PQping`: an
+/// authentication request or any error other than `57P03` means the server accepts connections.
+/// The connection is closed without authenticating.
+fn exchange<S: Read + Write>(stream: &mut S, user: &str, database: &str) -> Option<Readiness> {
+    if let Err(error) = stream.write_all(&startup_message(user, database)) {
+        return match error.kind() {
+            ErrorKind::ConnectionReset | ErrorKind::BrokenPipe => Some(Readiness::Absent),
+            _ => None,
+        };
+    }
+
+    let mut header = [0u8; 5];
+    if stream.read_exact(&mut header).is_err() {
+        return None;
+    }
+    let length = i32::from_be_bytes([header[1], header[2], header[3], header[4]]);
+    match header[0] {
+        b'R' | b'v' => Some(Readiness::Accepting),
+        b'E' => {
+            let length = usize::try_from(length).ok()?.checked_sub(4)?;
+            let mut body = vec![0u8; length];
+            stream.read_exact(&mut body).ok()?;
+            match error_code(&body) {
+                Some(CANNOT_CONNECT_NOW) => Some(Readiness::Rejecting),
+                Some(_) => Some(Readiness::Accepting),
+                None => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Build the startup message for protocol version 3.0
+fn startup_message(user: &str, database: &str) -> Vec<u8> {
+    let mut body = PROTOCOL_VERSION.to_be_bytes().to_vec();
+    for (name, value) in [("user", user), ("database", database)] {
+        body.extend_from_slice(name.as_bytes());
+        body.push(0);
+        body.extend_from_slice(value.as_bytes());
+        body.push(0);
+    }
+    body.push(0);
+
+    let length = i32::try_from(body.len() + 4).unwrap_or(i32::MAX);
+    let mut message = length.to_be_bytes().to_vec();
+    message.extend_from_slice(&body);
+    message
+}
+
+/// Get the SQLSTATE (`C` field) of the body of an error response
+fn error_code(body: &[u8]) -> Option<&str> {
+    let mut fields = body;
+    while let Some((&field_type, rest)) = fields.split_first() {
+        if field_type == 0 {
+            break;
+        }
+        let end = rest.iter().position(|&byte| byte == 0)?;
+        if field_type == b'C' {
+            return std::str::from_utf8(&rest[..end]).ok();
+        }
+        fields = &rest[end + 1..];
+    }
+    None
+}
+
+/// Parse the output of pg_isready, e.g. `localhost:5432 - accepting connections`
+pub(crate) fn parse_pg_isready_output(output: &str) -> Option<Readiness> {
+    if output.contains("accepting connections") {
+        Some(Readiness::Accepting)
+    } else if output.contains("rejecting connections") {
+        Some(Readiness::Rejecting)
+    } else if output.contains("no response") {
+        Some(Readiness::Absent)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    /// Stream that records what is written and reads the given response
+    struct MockStream {
+        written: Vec<u8>,
+        response: Cursor<Vec<u8>>,
+    }
+
+    impl MockStream {
+        fn new(response: Vec<u8>) -> Self {
+            Self {
+                written: Vec::new(),
+                response: Cursor::new(response),
+            }
+        }
+    }
+
+    impl Read for MockStream {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.response.read(buf)
+        }
+    }
+
+    impl Write for MockStream {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.written.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn error_response(code: &str) -> Vec<u8> {
+        let mut body = Vec::new();
+        for (field_type, value) in [(b'S', "FATAL"), (b'C', code), (b'M', "message")] {
+            body.push(field_type);
+            body.extend_from_slice(value.as_bytes());
+            body.push(0);
+        }
+        body.push(0);
+        let mut response = vec![b'E'];
+        response.extend_from_slice(&i32::try_from(body.len() + 4).unwrap().to_be_bytes());
+        response.extend_from_slice(&body);
+        response
+    }
+
+    #[test]
+    fn test_startup_message() {
+        let message = startup_message("postgres", "db");
+        let mut expected = 35i32.to_be_bytes().to_vec();
+        expected.extend_from_slice(&PROTOCOL_VERSION.to_be_bytes());
+        expected.extend_from_slice(b"user\0postgres\0database\0db\0\0");
+        assert_eq!(expected, message);
+    }
+
+    #[test]
+    fn test_exchange_authentication_request() {
+        let mut stream = MockStream::new(vec![b'R', 0, 0, 0, 12, 0, 0, 0, 10]);
+        assert_eq!(
+            Some(Readiness::Accepting),
+            exchange(&mut stream, "postgres", "postgres")
+        );
+        assert_eq!(startup_message("postgres", "postgres"), stream.written);
+    }
+
+    #[test]
+    fn test_exchange_cannot_connect_now() {
+        let mut stream = MockStream::new(error_response(CANNOT_CONNECT_NOW));
+        assert_eq!(
+            Some(Readiness::Rejecting),
+            exchange(&mut stream, "postgres", "postgres")
+        );
+    }
+
+    #[test]
+    fn test_exchange_other_error() {
+        // The database does not exist, so the server is up
+        let mut stream = MockStream::new(error_response("3D000"));
+        assert_eq!(
+            Some(Readiness::Accepting),
+            exchange(&mut stream, "postgres", "postgres")
+        );
+    }
+
+    #[test]
+    fn test_exchange_unexpected_response() {
+        let mut stream = MockStream::new(vec![b'N']);
+        assert_eq!(None, exchange(&mut stream, "postgres", "postgres"));
+        let mut stream = MockStream::new(vec![b'Z', 0, 0, 0, 5, b'I']);
+        assert_eq!(None, exchange(&mut stream, "postgres", "postgres"));
+    }
+
+    #[test]
+    fn test_ping_tcp_closed_port() -> std::io::Result<()> {
+        let listener = std::net::TcpListener::bind(("127.0.0.1", 0))?;
+        let port = listener.local_addr()?.port();
+        drop(listener);
+        assert_eq!(
+            Some(Readiness::Absent),
+            ping_tcp(
+                "127.0.0.1",
+                port,
+                "postgres",
+                "postgres",
+                Duration::from_secs(1)
+            )
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_pg_isready_output() {
+        assert_eq!(
+            Some(Readiness::Accepting),
+            parse_pg_isready_output("localhost:5432 - accepting connections\n")
+        );
+        assert_eq!(
+            Some(Readiness::Rejecting),
+            parse_pg_isready_output("localhost:5432 - rejecting connections\n")
+        );
+        assert_eq!(
+            Some(Readiness::Absent),
+            parse_pg_isready_output("localhost:5432 - no response\n")
+        );
+        assert_eq!(None, parse_pg_isready_output(""));
+    }
+}
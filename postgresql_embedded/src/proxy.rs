@@ -0,0 +1,572 @@
+use crate::connection_info::ConnectionInfo;
+use crate::error::Result;
+use crate::settings::Settings;
+use crate::telemetry;
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::io::{copy, ErrorKind, Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::{debug, warn};
+
+/// Request code sent by a client to negotiate SSL encryption
+const SSL_REQUEST_CODE: i32 = 80_877_103;
+/// Request code sent by a client to negotiate GSSAPI encryption
+const GSSENC_REQUEST_CODE: i32 = 80_877_104;
+/// Request code sent by a client to cancel a running query
+const CANCEL_REQUEST_CODE: i32 = 80_877_102;
+/// Maximum length of a startup message accepted by the server
+const MAX_STARTUP_MESSAGE_LENGTH: usize = 10_000;
+
+/// SQL statement sent by a client through a statement logging proxy; see
+/// [statement_logging_proxy](crate::PostgreSQL::statement_logging_proxy)
+#[derive(Clone, Debug, PartialEq)]
+pub struct LoggedStatement {
+    /// Client connection that sent the statement; connections are numbered from 1 in the order
+    /// in which they were accepted
+    pub connection: u64,
+    /// Time at which the statement was received by the proxy
+    pub timestamp: SystemTime,
+    /// SQL text of a simple query, or of a statement prepared with the extended query protocol
+    pub sql: String,
+}
+
+/// Statements captured by a proxy
+type Statements = Arc<Mutex<Vec<LoggedStatement>>>;
+
+/// Listener of a proxy: accepts client connections on a free port of the server host on a
+/// background thread and tracks the open proxied connections, so that they can be closed. The
+/// listener stops and closes the open connections when dropped.
+#[derive(Debug)]
+pub(crate) struct Listener {
+    address: SocketAddr,
+    connections: Arc<Connections>,
+    stopped: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Listener {
+    /// Start listening, calling the handler on the listener thread for each accepted client
+    /// connection
+    pub(crate) fn start<F>(settings: &Settings, span_name: &'static str, handler: F) -> Result<Self>
+    where
+        F: Fn(Accepted) -> std::io::Result<()> + Send + 'static,
+    {
+        let upstream = (settings.effective_host().to_string(), settings.port);
+        let listener = TcpListener::bind((settings.effective_host(), 0))?;
+        let address = listener.local_addr()?;
+
+        let connections = Arc::new(Connections::default());
+        let stopped = Arc::new(AtomicBool::new(false));
+        let thread = {
+            let connections = connections.clone();
+            let stopped = stopped.clone();
+            let span = telemetry::background_span(span_name);
+            std::thread::spawn(move || {
+                let _span = span.entered();
+                for (id, client) in (1..).zip(listener.incoming()) {
+                    if stopped.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    let result = client.and_then(|client| {
+                        handler(Accepted {
+                            id,
+                            client,
+                            upstream: upstream.clone(),
+                            connections: connections.clone(),
+                        })
+                    });
+                    if let Err(error) = result {
+                        warn!("Unable to proxy connection {id}: {error}");
+                    }
+                }
+            })
+        };
+
+        Ok(Self {
+            address,
+            connections,
+            stopped,
+            thread: Some(thread),
+        })
+    }
+
+    /// Address the listener accepts connections on
+    pub(crate) fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// Close all open connections; returns the number of connections closed
+    pub(crate) fn close_connections(&self) -> usize {
+        let streams: Vec<(TcpStream, TcpStream)> = lock(&self.connections.streams)
+            .drain()
+            .map(|(_, streams)| streams)
+            .collect();
+        for (client, server) in &streams {
+            let _ = client.shutdown(Shutdown::Both);
+            let _ = server.shutdown(Shutdown::Both);
+        }
+        streams.len()
+    }
+
+    /// Number of open connections
+    pub(crate) fn open_connections(&self) -> usize {
+        lock(&self.connections.streams).len()
+    }
+}
+
+impl Drop for Listener {
+    fn drop(&mut self) {
+        // Wake the listener thread with a connection, so that it sees the stop flag and exits
+        self.stopped.store(true, Ordering::SeqCst);
+        let _ = TcpStream::connect(self.address);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+        self.close_connections();
+    }
+}
+
+/// Client and server streams of the open connections of a [Listener], by connection id; the
+/// streams are kept to close the connections, and are removed once the forwarding ends
+#[derive(Debug, Default)]
+struct Connections {
+    streams: Mutex<HashMap<u64, (TcpStream, TcpStream)>>,
+}
+
+/// Client connection accepted by a [Listener], not connected to the server yet
+pub(crate) struct Accepted {
+    /// Connection id; connections are numbered from 1 in the order in which they were accepted
+    pub(crate) id: u64,
+    /// Client stream
+    pub(crate) client: TcpStream,
+    upstream: (String, u16),
+    connections: Arc<Connections>,
+}
+
+impl Accepted {
+    /// Connect to the server; the connection is tracked by the listener until both directions
+    /// have been [forwarded](Connection::forward)
+    pub(crate) fn connect(self) -> std::io::Result<Connection> {
+        let server = TcpStream::connect((self.upstream.0.as_str(), self.upstream.1))?;
+        lock(&self.connections.streams)
+            .insert(self.id, (self.client.try_clone()?, server.try_clone()?));
+        Ok(Connection {
+            id: self.id,
+            client: self.client,
+            server,
+            registration: Arc::new(Registration {
+                id: self.id,
+                connections: self.connections,
+            }),
+        })
+    }
+}
+
+/// Client connection of a [Listener] connected to the server
+pub(crate) struct Connection {
+    /// Connection id
+    pub(crate) id: u64,
+    client: TcpStream,
+    server: TcpStream,
+    registration: Arc<Registration>,
+}
+
+impl Connection {
+    /// Forward the traffic of each direction on a background thread, passing the stream to
+    /// read from and the stream to write to; when a direction ends, the stream written to is
+    /// shut down, which ends the other direction too. The connection is no longer tracked once
+    /// both directions have ended.
+    pub(crate) fn forward<C, S>(
+        self,
+        client_to_server: C,
+        server_to_client: S,
+    ) -> std::io::Result<()>
+    where
+        C: FnOnce(&mut TcpStream, &mut TcpStream) + Send + 'static,
+        S: FnOnce(&mut TcpStream, &mut TcpStream) + Send + 'static,
+    {
+        let client_to_server_streams = (self.client.try_clone()?, self.server.try_clone()?);
+        spawn_direction(
+            client_to_server_streams,
+            self.registration.clone(),
+            client_to_server,
+        );
+        spawn_direction(
+            (self.server, self.client),
+            self.registration,
+            server_to_client,
+        );
+        Ok(())
+    }
+}
+
+/// Forward one direction of a connection on a background thread
+fn spawn_direction<F>(streams: (TcpStream, TcpStream), registration: Arc<Registration>, forward: F)
+where
+    F: FnOnce(&mut TcpStream, &mut TcpStream) + Send + 'static,
+{
+    std::thread::spawn(move || {
+        let (mut from, mut to) = streams;
+        forward(&mut from, &mut to);
+        let _ = to.shutdown(Shutdown::Both);
+        drop(registration);
+    });
+}
+
+/// Stops tracking a connection of a [Listener] when dropped, i.e. once both directions have
+/// ended
+struct Registration {
+    id: u64,
+    connections: Arc<Connections>,
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        lock(&self.connections.streams).remove(&self.id);
+    }
+}
+
+/// TCP proxy that forwards client connections to a PostgreSQL server and records the SQL
+/// statements sent by the clients. The proxy stops and closes the proxied connections when
+/// dropped; if the current thread is panicking (e.g. a test assertion failed), the captured
+/// statements are logged.
+#[derive(Debug)]
+pub struct ProxyHandle {
+    connection_info: ConnectionInfo,
+    statements: Statements,
+    listener: Listener,
+}
+
+impl ProxyHandle {
+    /// Start a proxy for the server on a free port of the server host
+    pub(crate) fn start(settings: &Settings) -> Result<Self> {
+        let statements = Statements::default();
+        let listener = {
+            let statements = statements.clone();
+            Listener::start(
+                settings,
+                "postgresql.statement_logging_proxy",
+                move |accepted| proxy_connection(accepted.connect()?, &statements),
+            )?
+        };
+        let connection_info = ConnectionInfo {
+            port: listener.address().port(),
+            ..ConnectionInfo::from(settings)
+        };
+        debug!(
+            "Statement logging proxy listening on {}",
+            listener.address()
+        );
+
+        Ok(Self {
+            connection_info,
+            statements,
+            listener,
+        })
+    }
+
+    /// Endpoint of the proxy; clients connect to the proxy in place of the server
+    pub fn connection_info(&self) -> &ConnectionInfo {
+        &self.connection_info
+    }
+
+    /// Return the URL of the proxy for the given database name.
+    pub fn url<S: AsRef<str>>(&self, database_name: S) -> String {
+        self.connection_info.url(database_name)
+    }
+
+    /// Statements captured so far, in the order in which they were received
+    pub fn statements(&self) -> Vec<LoggedStatement> {
+        lock(&self.statements).clone()
+    }
+
+    /// Captured statements formatted one per line, with the time (in seconds since the Unix
+    /// epoch) and connection that sent each statement
+    pub fn diagnostics(&self) -> String {
+        let mut diagnostics = String::new();
+        for statement in lock(&self.statements).iter() {
+            let timestamp = statement
+                .timestamp
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default();
+            let _ = writeln!(
+                diagnostics,
+                "[{}.{:03}] connection {}: {}",
+                timestamp.as_secs(),
+                timestamp.subsec_millis(),
+                statement.connection,
+                statement.sql
+            );
+        }
+        diagnostics
+    }
+}
+
+impl Drop for ProxyHandle {
+    fn drop(&mut self) {
+        if std::thread::panicking() {
+            warn!("Statements sent through the proxy:\n{}", self.diagnostics());
+        }
+    }
+}
+
+/// Lock the mutex, ignoring poisoning; the protected values are always left consistent
+pub(crate) fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|error| error.into_inner())
+}
+
+/// Forward a client connection to the server on background threads; messages from the server
+/// are copied to the client unchanged, while messages from the client are inspected.
+fn proxy_connection(connection: Connection, statements: &Statements) -> std::io::Result<()> {
+    let id = connection.id;
+    debug!("Proxying connection {id}");
+    let statements = statements.clone();
+    connection.forward(
+        move |client, server| {
+            if let Err(error) = intercept(id, client, server, &statements) {
+                debug!("Connection {id} closed: {error}");
+            }
+        },
+        |server, client| {
+            let _ = copy(server, client);
+        },
+    )
+}
+
+/// Forward the messages of a client to the server, recording the SQL text of `Query` and
+/// `Parse` messages. Only the startup message and the framing of the subsequent messages are
+/// decoded. Encryption requests are declined, since encrypted messages cannot be inspected;
+/// clients then continue unencrypted, unless they require encryption.
+fn intercept<C, S>(
+    connection: u64,
+    client: &mut C,
+    server: &mut S,
+    statements: &Mutex<Vec<LoggedStatement>>,
+) -> std::io::Result<()>
+where
+    C: Read + Write,
+    S: Write,
+{
+    loop {
+        let mut length = [0u8; 4];
+        client.read_exact(&mut length)?;
+        let body_length = usize::try_from(i32::from_be_bytes(length))
+            .ok()
+            .filter(|length| (8..=MAX_STARTUP_MESSAGE_LENGTH).contains(length))
+            .ok_or_else(|| {
+                std::io::Error::new(ErrorKind::InvalidData, "invalid startup message")
+            })?
+            - 4;
+        let mut body = vec![0u8; body_length];
+        client.read_exact(&mut body)?;
+
+        let code = i32::from_be_bytes([body[0], body[1], body[2], body[3]]);
+        if code == SSL_REQUEST_CODE || code == GSSENC_REQUEST_CODE {
+            client.write_all(b"N")?;
+            continue;
+        }
+        server.write_all(&length)?;
+        server.write_all(&body)?;
+        if code == CANCEL_REQUEST_CODE {
+            return Ok(());
+        }
+        break;
+    }
+
+    loop {
+        let mut header = [0u8; 5];
+        match client.read_exact(&mut header) {
+            Ok(()) => {}
+            Err(error) if error.kind() == ErrorKind::UnexpectedEof => return Ok(()),
+            Err(error) => return Err(error),
+        }
+        let tag = header[0];
+        let body_length = usize::try_from(i32::from_be_bytes([
+            header[1], header[2], header[3], header[4],
+        ]))
+        .ok()
+        .and_then(|length| length.checked_sub(4))
+        .ok_or_else(|| std::io::Error::new(ErrorKind::InvalidData, "invalid message length"))?;
+        let mut body = vec![0u8; body_length];
+        client.read_exact(&mut body)?;
+
+        let sql = match tag {
+            b'Q' => Some(read_string(&body)),
+            // Parse messages start with the (possibly empty) name of the prepared statement
+            b'P' => body
+                .iter()
+                .position(|byte| *byte == 0)
+                .map(|end| read_string(&body[end + 1..])),
+            _ => None,
+        };
+        if let Some(sql) = sql.filter(|sql| !sql.is_empty()) {
+            lock(statements).push(LoggedStatement {
+                connection,
+                timestamp: SystemTime::now(),
+                sql,
+            });
+        }
+
+        server.write_all(&header)?;
+        server.write_all(&body)?;
+        if tag == b'X' {
+            return Ok(());
+        }
+    }
+}
+
+/// Read a null terminated string
+fn read_string(bytes: &[u8]) -> String {
+    let end = bytes
+        .iter()
+        .position(|byte| *byte == 0)
+        .unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    /// Client stream that reads the given bytes and records the bytes written
+    #[derive(Default)]
+    struct Client {
+        input: Cursor<Vec<u8>>,
+        output: Vec<u8>,
+    }
+
+    impl Read for Client {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.input.read(buf)
+        }
+    }
+
+    impl Write for Client {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.output.write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn startup_message(code: i32, parameters: &[u8]) -> Vec<u8> {
+        let length = i32::try_from(8 + parameters.len()).expect("length");
+        let mut message = length.to_be_bytes().to_vec();
+        message.extend(code.to_be_bytes());
+        message.extend(parameters);
+        message
+    }
+
+    fn message(tag: u8, body: &[u8]) -> Vec<u8> {
+        let length = i32::try_from(4 + body.len()).expect("length");
+        let mut message = vec![tag];
+        message.extend(length.to_be_bytes());
+        message.extend(body);
+        message
+    }
+
+    #[test]
+    fn test_intercept() -> Result<()> {
+        let startup = startup_message(196_608, b"user\0postgres\0\0");
+        let query = message(b'Q', b"SELECT 1\0");
+        let parse = message(b'P', b"s1\0SELECT $1\0\0\0");
+        let sync = message(b'S', b"");
+        let terminate = message(b'X', b"");
+        let mut input = startup_message(SSL_REQUEST_CODE, b"");
+        for bytes in [&startup, &query, &parse, &sync, &terminate] {
+            input.extend(bytes);
+        }
+        let mut client = Client {
+            input: Cursor::new(input),
+            ..Default::default()
+        };
+        let mut server = Vec::new();
+        let statements = Mutex::new(Vec::new());
+
+        intercept(7, &mut client, &mut server, &statements)?;
+
+        // The SSL request is declined and is not forwarded to the server
+        assert_eq!(b"N".to_vec(), client.output);
+        assert_eq!([startup, query, parse, sync, terminate].concat(), server);
+        let statements = statements.into_inner().expect("statements");
+        assert_eq!(
+            vec![(7, "SELECT 1"), (7, "SELECT $1")],
+            statements
+                .iter()
+                .map(|statement| (statement.connection, statement.sql.as_str()))
+                .collect::<Vec<_>>()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_intercept_invalid_startup_message() {
+        let mut client = Client {
+            input: Cursor::new(vec![0, 0, 0, 1]),
+            ..Default::default()
+        };
+        let statements = Mutex::new(Vec::new());
+        assert!(intercept(1, &mut client, &mut Vec::new(), &statements).is_err());
+    }
+
+    #[test]
+    fn test_listener_forgets_closed_connections() -> Result<()> {
+        // Server that echoes what it receives
+        let server = TcpListener::bind("127.0.0.1:0")?;
+        let settings = Settings {
+            host: "127.0.0.1".to_string(),
+            port: server.local_addr()?.port(),
+            ..Default::default()
+        };
+        std::thread::spawn(move || {
+            for stream in server.incoming() {
+                let Ok(mut stream) = stream else { break };
+                std::thread::spawn(move || {
+                    let mut reader = stream.try_clone().expect("stream");
+                    let _ = copy(&mut reader, &mut stream);
+                });
+            }
+        });
+        let listener = Listener::start(&settings, "test", |accepted| {
+            accepted.connect()?.forward(
+                |client, server| {
+                    let _ = copy(client, server);
+                },
+                |server, client| {
+                    let _ = copy(server, client);
+                },
+            )
+        })?;
+
+        for _ in 0..3 {
+            let mut client = TcpStream::connect(listener.address())?;
+            client.write_all(b"ping")?;
+            let mut reply = [0u8; 4];
+            client.read_exact(&mut reply)?;
+            assert_eq!(b"ping", &reply);
+            assert_eq!(1, listener.open_connections());
+            client.shutdown(Shutdown::Write)?;
+            let _ = client.read(&mut reply)?;
+
+            let start = std::time::Instant::now();
+            while listener.open_connections() > 0 {
+                assert!(start.elapsed() < std::time::Duration::from_secs(5));
+                std::thread::sleep(std::time::Duration::from_millis(10));
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_string() {
+        assert_eq!("SELECT 1", read_string(b"SELECT 1\0rest"));
+        assert_eq!("SELECT 1", read_string(b"SELECT 1"));
+    }
+}
@@ -0,0 +1,102 @@
+//! Data directory of a server that was stopped and has fully exited, for archiving or copying;
+//! see [stop_and_quiesce](crate::PostgreSQL::stop_and_quiesce)
+
+use crate::error::{Error::QuiesceError, Result};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::fs::File;
+use std::path::Path;
+use std::time::Duration;
+
+/// Maximum time to wait for the server processes to exit after the server was stopped
+pub(crate) const QUIESCE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Cluster state reported by pg_controldata after a clean shutdown
+pub(crate) const SHUT_DOWN: &str = "shut down";
+
+/// Query the process ids of the server processes other than the postmaster
+pub(crate) const SERVER_PIDS_QUERY: &str = "SELECT pid FROM pg_stat_activity";
+
+/// Data directory of a server that was cleanly stopped and whose processes have all exited.
+/// The instance stays borrowed while the handle exists, so the server cannot be started while
+/// the directory is read.
+#[derive(Debug)]
+pub struct QuiescedDataDir<'a> {
+    path: &'a Path,
+}
+
+impl<'a> QuiescedDataDir<'a> {
+    pub(crate) fn new(path: &'a Path) -> Self {
+        Self { path }
+    }
+
+    /// Get the path of the data directory
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        self.path
+    }
+
+    /// Write the data directory to a gzip compressed tar archive; the entries are relative to
+    /// the data directory. When extracting the archive, note that PostgreSQL requires the data
+    /// directory to be accessible by its owner only (mode `0700` or `0750`).
+    pub fn archive_to<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let archive = |path: &Path| -> std::io::Result<()> {
+            let encoder = GzEncoder::new(File::create(path)?, Compression::default());
+            let mut builder = tar::Builder::new(encoder);
+            builder.append_dir_all(".", self.path)?;
+            builder.into_inner()?.finish()?;
+            Ok(())
+        };
+        archive(path.as_ref()).map_err(|error| {
+            QuiesceError(anyhow::anyhow!(
+                "Failed to archive {} to {}: {error}",
+                self.path.to_string_lossy(),
+                path.as_ref().to_string_lossy()
+            ))
+        })
+    }
+}
+
+/// Get the cluster state from the output of pg_controldata; e.g. `shut down`
+pub(crate) fn parse_cluster_state(control_data: &str) -> Option<&str> {
+    control_data.lines().find_map(|line| {
+        let (label, value) = line.split_once(':')?;
+        (label.trim() == "Database cluster state").then(|| value.trim())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cluster_state() {
+        let control_data = "pg_control version number:            1300\n\
+            Database system identifier:           7358104279419582751\n\
+            Database cluster state:               shut down\n\
+            pg_control last modified:             Mon 15 Jan 2024 10:00:00 AM UTC\n";
+        assert_eq!(Some(SHUT_DOWN), parse_cluster_state(control_data));
+        assert_eq!(None, parse_cluster_state(""));
+    }
+
+    #[test]
+    fn test_archive_to() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let data_dir = temp_dir.path().join("data");
+        std::fs::create_dir_all(data_dir.join("base"))?;
+        std::fs::write(data_dir.join("PG_VERSION"), "16\n")?;
+        let archive = temp_dir.path().join("data.tar.gz");
+
+        QuiescedDataDir::new(&data_dir).archive_to(&archive)?;
+
+        let extract_dir = temp_dir.path().join("extract");
+        let decoder = flate2::read::GzDecoder::new(File::open(&archive)?);
+        tar::Archive::new(decoder).unpack(&extract_dir)?;
+        assert_eq!(
+            "16\n",
+            std::fs::read_to_string(extract_dir.join("PG_VERSION"))?
+        );
+        assert!(extract_dir.join("base").is_dir());
+        Ok(())
+    }
+}
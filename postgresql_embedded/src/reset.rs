@@ -0,0 +1,342 @@
+//! Removal of the data of all user tables while preserving the schema; see
+//! [reset_data](crate::PostgreSQL::reset_data)
+
+use crate::catalog::schema_condition;
+use crate::sql::quote_literal;
+use postgresql_commands::QualifiedName;
+use std::collections::HashSet;
+
+/// Options for resetting the data of a database with [reset_data](crate::PostgreSQL::reset_data)
+#[derive(Clone, Debug, PartialEq)]
+pub struct ResetOptions {
+    /// Tables whose data is preserved; a name without a schema matches the table in any schema.
+    /// Tables referenced by the foreign keys of preserved tables are emptied row by row with
+    /// the foreign key triggers disabled, rather than truncated, so that the truncation does not
+    /// cascade to the preserved tables.
+    pub exclude_tables: Vec<QualifiedName>,
+    /// Restart the sequences owned by the columns of the reset tables, e.g. identity and
+    /// serial columns; sequences owned by preserved tables are never restarted
+    pub restart_identities: bool,
+    /// Truncate with `CASCADE`, so that tables outside the reset tables that reference them,
+    /// e.g. in system schemas, are truncated as well; otherwise the reset fails on such tables
+    pub cascade: bool,
+    /// Reset the configuration tables of extensions, which are marked with
+    /// `pg_extension_config_dump`; they are preserved by default. Other tables that belong to
+    /// an extension are always preserved.
+    pub include_extension_config: bool,
+}
+
+/// Default implementation for [`ResetOptions`]
+impl Default for ResetOptions {
+    fn default() -> Self {
+        Self {
+            exclude_tables: Vec::new(),
+            restart_identities: true,
+            cascade: true,
+            include_extension_config: false,
+        }
+    }
+}
+
+/// Report of the tables reset by [reset_data](crate::PostgreSQL::reset_data)
+#[derive(Clone, Debug, PartialEq)]
+pub struct ResetReport {
+    /// Database that was reset
+    pub database: String,
+    /// Tables that were truncated
+    pub truncated: Vec<QualifiedName>,
+    /// Tables that were emptied row by row, because preserved tables reference them
+    pub deleted: Vec<QualifiedName>,
+    /// Tables whose data was preserved
+    pub preserved: Vec<QualifiedName>,
+}
+
+/// User table found by the [reset tables query](reset_tables_query)
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct ResetTable {
+    pub(crate) oid: u32,
+    pub(crate) name: QualifiedName,
+    /// Configuration table of an extension
+    pub(crate) extension_config: bool,
+    /// Table that belongs to an extension
+    pub(crate) extension_member: bool,
+    /// Tables with foreign keys referencing this table
+    pub(crate) referenced_by: Vec<u32>,
+}
+
+/// Query listing the user tables, whether they belong to an extension and the tables
+/// referencing them. Partitions are reset with their partitioned table and are not listed.
+pub(crate) fn reset_tables_query() -> String {
+    format!(
+        "SELECT c.oid, n.nspname, c.relname, \
+         EXISTS (SELECT 1 FROM pg_catalog.pg_extension e WHERE c.oid = ANY (e.extconfig)), \
+         EXISTS (SELECT 1 FROM pg_catalog.pg_depend d \
+         WHERE d.classid = 'pg_catalog.pg_class'::pg_catalog.regclass AND d.objid = c.oid \
+         AND d.deptype = 'e'), \
+         pg_catalog.array_to_string(ARRAY(SELECT DISTINCT f.conrelid \
+         FROM pg_catalog.pg_constraint f \
+         WHERE f.contype = 'f' AND f.confrelid = c.oid AND f.conrelid <> c.oid), ',') \
+         FROM pg_catalog.pg_class c JOIN pg_catalog.pg_namespace n ON n.oid = c.relnamespace \
+         WHERE c.relkind IN ('r', 'p') AND NOT c.relispartition AND {} ORDER BY 2, 3",
+        schema_condition(None)
+    )
+}
+
+/// Parse the rows of the [reset tables query](reset_tables_query)
+pub(crate) fn parse_reset_tables(rows: Vec<Vec<String>>) -> Vec<ResetTable> {
+    rows.into_iter()
+        .filter_map(|row| match row.as_slice() {
+            [oid, schema, name, extension_config, extension_member, referenced_by] => {
+                Some(ResetTable {
+                    oid: oid.parse().ok()?,
+                    name: QualifiedName::qualified(schema.as_str(), name.as_str()),
+                    extension_config: extension_config == "t",
+                    extension_member: extension_member == "t",
+                    referenced_by: referenced_by
+                        .split(',')
+                        .filter_map(|oid| oid.parse().ok())
+                        .collect(),
+                })
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Tables to truncate, delete from and preserve
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct ResetPlan {
+    pub(crate) truncate: Vec<QualifiedName>,
+    pub(crate) delete: Vec<QualifiedName>,
+    pub(crate) preserve: Vec<QualifiedName>,
+}
+
+/// Determine how each table is reset. A table that a preserved table references, directly or
+/// through other tables, cannot be truncated without the truncation cascading to the preserved
+/// table, and is emptied row by row instead.
+pub(crate) fn plan_reset(tables: &[ResetTable], options: &ResetOptions) -> ResetPlan {
+    let excluded = |name: &QualifiedName| {
+        options.exclude_tables.iter().any(|exclude| {
+            exclude.name == name.name && (exclude.schema.is_none() || exclude.schema == name.schema)
+        })
+    };
+    let preserved: HashSet<u32> = tables
+        .iter()
+        .filter(|table| {
+            excluded(&table.name)
+                || (table.extension_config && !options.include_extension_config)
+                || (table.extension_member && !table.extension_config)
+        })
+        .map(|table| table.oid)
+        .collect();
+
+    let mut deleted: HashSet<u32> = HashSet::new();
+    loop {
+        let referenced: Vec<u32> = tables
+            .iter()
+            .filter(|table| !preserved.contains(&table.oid) && !deleted.contains(&table.oid))
+            .filter(|table| {
+                table
+                    .referenced_by
+                    .iter()
+                    .any(|oid| preserved.contains(oid) || deleted.contains(oid))
+            })
+            .map(|table| table.oid)
+            .collect();
+        if referenced.is_empty() {
+            break;
+        }
+        deleted.extend(referenced);
+    }
+
+    let mut plan = ResetPlan::default();
+    for table in tables {
+        let name = table.name.clone();
+        if preserved.contains(&table.oid) {
+            plan.preserve.push(name);
+        } else if deleted.contains(&table.oid) {
+            plan.delete.push(name);
+        } else {
+            plan.truncate.push(name);
+        }
+    }
+    plan
+}
+
+/// Build the script resetting the tables of the plan in a single transaction. The foreign key
+/// triggers are disabled for the deletes by switching the session to the replica role, which
+/// also disables the user triggers of the tables.
+pub(crate) fn reset_statement(plan: &ResetPlan, options: &ResetOptions) -> String {
+    let mut statements = vec!["BEGIN".to_string()];
+    if !plan.truncate.is_empty() {
+        let tables: Vec<String> = plan.truncate.iter().map(ToString::to_string).collect();
+        let mut truncate = format!("TRUNCATE TABLE {}", tables.join(", "));
+        if options.restart_identities {
+            truncate.push_str(" RESTART IDENTITY");
+        }
+        if options.cascade {
+            truncate.push_str(" CASCADE");
+        }
+        statements.push(truncate);
+    }
+    if !plan.delete.is_empty() {
+        statements.push("SET LOCAL session_replication_role = replica".to_string());
+        for table in &plan.delete {
+            statements.push(format!("DELETE FROM {table}"));
+        }
+        if options.restart_identities {
+            let tables: Vec<String> = plan
+                .delete
+                .iter()
+                .map(|table| format!("{}::pg_catalog.regclass", quote_literal(table.to_string())))
+                .collect();
+            statements.push(format!(
+                "SELECT pg_catalog.setval(s.seqrelid, s.seqstart, false) \
+                 FROM pg_catalog.pg_sequence s JOIN pg_catalog.pg_depend d \
+                 ON d.classid = 'pg_catalog.pg_class'::pg_catalog.regclass \
+                 AND d.objid = s.seqrelid \
+                 AND d.refclassid = 'pg_catalog.pg_class'::pg_catalog.regclass \
+                 AND d.deptype IN ('a', 'i') \
+                 WHERE d.refobjid IN ({})",
+                tables.join(", ")
+            ));
+        }
+    }
+    statements.push("COMMIT".to_string());
+    statements.join(";\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(oid: u32, name: &str, referenced_by: &[u32]) -> ResetTable {
+        ResetTable {
+            oid,
+            name: QualifiedName::qualified("public", name),
+            extension_config: false,
+            extension_member: false,
+            referenced_by: referenced_by.to_vec(),
+        }
+    }
+
+    fn names(names: &[&str]) -> Vec<QualifiedName> {
+        names
+            .iter()
+            .map(|name| QualifiedName::qualified("public", *name))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_reset_tables() {
+        let rows = vec![
+            vec!["16384", "public", "accounts", "f", "f", "16390,16395"],
+            vec!["16400", "ext", "config", "t", "t", ""],
+            vec!["invalid", "public", "skipped", "f", "f", ""],
+        ]
+        .into_iter()
+        .map(|row| row.into_iter().map(str::to_string).collect())
+        .collect();
+        let tables = parse_reset_tables(rows);
+        assert_eq!(
+            vec![
+                ResetTable {
+                    oid: 16384,
+                    name: QualifiedName::qualified("public", "accounts"),
+                    extension_config: false,
+                    extension_member: false,
+                    referenced_by: vec![16390, 16395],
+                },
+                ResetTable {
+                    oid: 16400,
+                    name: QualifiedName::qualified("ext", "config"),
+                    extension_config: true,
+                    extension_member: true,
+                    referenced_by: vec![],
+                },
+            ],
+            tables
+        );
+    }
+
+    #[test]
+    fn test_plan_reset() {
+        // orders -> accounts -> regions; audit -> orders; audit is preserved
+        let mut config = table(5, "config", &[]);
+        config.extension_config = true;
+        config.extension_member = true;
+        let mut member = table(6, "member", &[]);
+        member.extension_member = true;
+        let tables = vec![
+            table(1, "accounts", &[2]),
+            table(2, "orders", &[4]),
+            table(3, "regions", &[1]),
+            table(4, "audit", &[]),
+            config,
+            member,
+            table(7, "unrelated", &[]),
+        ];
+        let options = ResetOptions {
+            exclude_tables: vec![QualifiedName::new("audit")],
+            ..Default::default()
+        };
+
+        let plan = plan_reset(&tables, &options);
+        assert_eq!(names(&["unrelated"]), plan.truncate);
+        assert_eq!(names(&["accounts", "orders", "regions"]), plan.delete);
+        assert_eq!(names(&["audit", "config", "member"]), plan.preserve);
+
+        let options = ResetOptions {
+            exclude_tables: vec![QualifiedName::qualified("other", "audit")],
+            include_extension_config: true,
+            ..Default::default()
+        };
+        let plan = plan_reset(&tables, &options);
+        assert_eq!(
+            names(&[
+                "accounts",
+                "orders",
+                "regions",
+                "audit",
+                "config",
+                "unrelated"
+            ]),
+            plan.truncate
+        );
+        assert!(plan.delete.is_empty());
+        assert_eq!(names(&["member"]), plan.preserve);
+    }
+
+    #[test]
+    fn test_reset_statement() {
+        let plan = ResetPlan {
+            truncate: names(&["accounts", "orders"]),
+            delete: names(&["regions"]),
+            preserve: names(&["audit"]),
+        };
+        let statement = reset_statement(&plan, &ResetOptions::default());
+        assert!(statement.starts_with(
+            "BEGIN;\n\
+             TRUNCATE TABLE \"public\".\"accounts\", \"public\".\"orders\" RESTART IDENTITY CASCADE;\n\
+             SET LOCAL session_replication_role = replica;\n\
+             DELETE FROM \"public\".\"regions\";\n\
+             SELECT pg_catalog.setval("
+        ));
+        assert!(statement.contains("IN ('\"public\".\"regions\"'::pg_catalog.regclass)"));
+        assert!(statement.ends_with(";\nCOMMIT"));
+
+        let options = ResetOptions {
+            restart_identities: false,
+            cascade: false,
+            ..Default::default()
+        };
+        let plan = ResetPlan {
+            truncate: names(&["accounts"]),
+            ..Default::default()
+        };
+        assert_eq!(
+            "BEGIN;\nTRUNCATE TABLE \"public\".\"accounts\";\nCOMMIT",
+            reset_statement(&plan, &options)
+        );
+    }
+}
@@ -0,0 +1,139 @@
+//! Normalization of schema-only dumps into deterministic snapshots; see
+//! [schema_snapshot](crate::PostgreSQL::schema_snapshot)
+
+/// Prefixes of the pg_dump lines that vary between runs or machines, such as the server and
+/// pg_dump versions or the random key of the `\restrict` meta-command
+const VOLATILE_PREFIXES: [&str; 8] = [
+    "-- Dumped from database version",
+    "-- Dumped by pg_dump version",
+    "-- Started on",
+    "-- Completed on",
+    "-- PostgreSQL database dump",
+    "-- TOC entry",
+    "\\restrict ",
+    "\\unrestrict ",
+];
+
+/// Prefix of the comment line that starts each object of a dump
+const ENTRY_PREFIX: &str = "-- Name: ";
+
+/// Normalize the plain text output of `pg_dump --schema-only`: volatile lines are removed and
+/// the objects are sorted by their `-- Name: ...; Type: ...; Schema: ...` header, so that the
+/// same schema always produces the same snapshot. The settings preceding the first object are
+/// kept in place. The snapshot is intended for comparison and is not necessarily restorable,
+/// since objects are no longer in dependency order.
+pub(crate) fn normalize(dump: &str) -> String {
+    let mut preamble = Vec::new();
+    let mut entries: Vec<Vec<&str>> = Vec::new();
+    for line in dump.lines() {
+        if VOLATILE_PREFIXES
+            .iter()
+            .any(|prefix| line.starts_with(prefix))
+        {
+            continue;
+        }
+        if line.starts_with(ENTRY_PREFIX) {
+            entries.push(vec![line]);
+        } else if let Some(entry) = entries.last_mut() {
+            entry.push(line);
+        } else {
+            preamble.push(line);
+        }
+    }
+
+    let mut entries: Vec<String> = entries.iter().map(|entry| trim_lines(entry)).collect();
+    entries.sort();
+
+    let mut snapshot = trim_lines(&preamble);
+    for entry in entries {
+        if !snapshot.is_empty() {
+            snapshot.push_str("\n\n");
+        }
+        snapshot.push_str("--\n");
+        snapshot.push_str(&entry);
+    }
+    snapshot.push('\n');
+    snapshot
+}
+
+/// Join the lines, without the leading and trailing blank or empty comment lines
+fn trim_lines(lines: &[&str]) -> String {
+    let is_blank = |line: &&str| line.trim().is_empty() || line.trim() == "--";
+    let start = lines.iter().position(|line| !is_blank(line));
+    let end = lines.iter().rposition(|line| !is_blank(line));
+    match (start, end) {
+        (Some(start), Some(end)) => lines[start..=end].join("\n"),
+        _ => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize() {
+        let dump = "--
+-- PostgreSQL database dump
+--
+
+\\restrict 3bRQ0cFXzYK1cNvKmSgNqY
+
+-- Dumped from database version 16.4
+-- Dumped by pg_dump version 16.4
+
+SET statement_timeout = 0;
+SET client_encoding = 'UTF8';
+
+--
+-- Name: users; Type: TABLE; Schema: public; Owner: -
+--
+
+CREATE TABLE public.users (
+    id integer NOT NULL
+);
+
+
+--
+-- Name: items; Type: TABLE; Schema: public; Owner: -
+--
+
+CREATE TABLE public.items (
+    id integer NOT NULL
+);
+
+
+--
+-- PostgreSQL database dump complete
+--
+
+\\unrestrict 3bRQ0cFXzYK1cNvKmSgNqY
+";
+        let expected = "SET statement_timeout = 0;
+SET client_encoding = 'UTF8';
+
+--
+-- Name: items; Type: TABLE; Schema: public; Owner: -
+--
+
+CREATE TABLE public.items (
+    id integer NOT NULL
+);
+
+--
+-- Name: users; Type: TABLE; Schema: public; Owner: -
+--
+
+CREATE TABLE public.users (
+    id integer NOT NULL
+);
+";
+        assert_eq!(expected, normalize(dump));
+        assert_eq!(expected, normalize(expected));
+    }
+
+    #[test]
+    fn test_normalize_empty() {
+        assert_eq!("\n", normalize(""));
+    }
+}
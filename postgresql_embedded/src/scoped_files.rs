@@ -0,0 +1,228 @@
+//! Short-lived files passed to the tools (e.g. the password file of initdb). The files are
+//! created in a private directory of the server, next to the data directory, rather than in the
+//! system temporary directory, and are only accessible by the current user since they may contain
+//! secrets.
+
+use crate::error::Result;
+use crate::settings::data_dir_sibling;
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use std::fs::{remove_dir, remove_file, DirBuilder, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tracing::{debug, warn};
+
+/// Get the private directory of the server with the given data directory; e.g. `/tmp/data.files`
+/// for `/tmp/data`
+pub(crate) fn files_dir(data_dir: &Path) -> PathBuf {
+    data_dir_sibling(data_dir, ".files")
+}
+
+/// Write a file that is only accessible by the current user; an existing file is truncated
+pub(crate) fn write_private(path: &Path, contents: &[u8]) -> Result<()> {
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+    }
+    file.write_all(contents)?;
+    file.flush()?;
+    Ok(())
+}
+
+/// Files created for the duration of a scope. The files are removed when the [ScopedFiles] is
+/// dropped, including while unwinding from a panic, unless the scope [failed](Self::fail) (or
+/// panicked) and the files are kept on failure for debugging.
+#[derive(Debug)]
+pub(crate) struct ScopedFiles {
+    directory: PathBuf,
+    files: Vec<PathBuf>,
+    keep_on_failure: bool,
+    failed: bool,
+}
+
+impl ScopedFiles {
+    /// Create a new scope for files in the private directory of the server with the given data
+    /// directory
+    pub(crate) fn new(data_dir: &Path, keep_on_failure: bool) -> Self {
+        Self {
+            directory: files_dir(data_dir),
+            files: Vec::new(),
+            keep_on_failure,
+            failed: false,
+        }
+    }
+
+    /// Create a file with the contents; the name of the file starts with the prefix and is
+    /// unique, so concurrent scopes do not collide
+    pub(crate) fn create(&mut self, prefix: &str, contents: &[u8]) -> Result<PathBuf> {
+        let mut builder = DirBuilder::new();
+        builder.recursive(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::DirBuilderExt;
+            builder.mode(0o700);
+        }
+        builder.create(&self.directory)?;
+
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let suffix: String = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(16)
+            .map(char::from)
+            .collect();
+        let path = self.directory.join(format!("{prefix}.{suffix}"));
+        let mut file = options.open(&path)?;
+        self.files.push(path.clone());
+        file.write_all(contents)?;
+        file.flush()?;
+        debug!("Created scoped file {}", path.to_string_lossy());
+        Ok(path)
+    }
+
+    /// Mark the scope as failed; the files are kept when the scope keeps files on failure
+    pub(crate) fn fail(&mut self) {
+        self.failed = true;
+    }
+}
+
+impl Drop for ScopedFiles {
+    fn drop(&mut self) {
+        if self.files.is_empty() {
+            return;
+        }
+        if self.keep_on_failure && (self.failed || std::thread::panicking()) {
+            warn!(
+                "Keeping files of failed operation in {}",
+                self.directory.to_string_lossy()
+            );
+            return;
+        }
+        for file in &self.files {
+            let _ = remove_file(file);
+        }
+        // Only succeeds once no other scope has files in the directory
+        let _ = remove_dir(&self.directory);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    #[test]
+    fn test_files_dir() {
+        assert_eq!(
+            PathBuf::from("/tmp/data.files"),
+            files_dir(Path::new("/tmp/data"))
+        );
+    }
+
+    #[test]
+    fn test_scoped_files() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let data_dir = temp_dir.path().join("data");
+        let (first, second) = {
+            let mut files = ScopedFiles::new(&data_dir, false);
+            let first = files.create("pwfile", b"password")?;
+            let second = files.create("pwfile", b"password")?;
+            assert_ne!(first, second);
+            assert!(first.starts_with(files_dir(&data_dir)));
+            assert_eq!("password", std::fs::read_to_string(&first)?);
+
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                let mode = std::fs::metadata(&first)?.permissions().mode();
+                assert_eq!(0o600, mode & 0o777);
+                let mode = std::fs::metadata(files_dir(&data_dir))?
+                    .permissions()
+                    .mode();
+                assert_eq!(0o700, mode & 0o777);
+            }
+            (first, second)
+        };
+
+        assert!(!first.exists());
+        assert!(!second.exists());
+        assert!(!files_dir(&data_dir).exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_scoped_files_panic() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let data_dir = temp_dir.path().join("data");
+        let mut path = PathBuf::new();
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            let mut files = ScopedFiles::new(&data_dir, false);
+            path = files.create("pwfile", b"password").expect("file created");
+            panic!("simulated failure");
+        }));
+
+        assert!(result.is_err());
+        assert!(!path.as_os_str().is_empty());
+        assert!(!path.exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_scoped_files_keep_on_failure() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let data_dir = temp_dir.path().join("data");
+        let succeeded = {
+            let mut files = ScopedFiles::new(&data_dir, true);
+            files.create("pwfile", b"password")?
+        };
+        let failed = {
+            let mut files = ScopedFiles::new(&data_dir, true);
+            let path = files.create("pwfile", b"password")?;
+            files.fail();
+            path
+        };
+        let mut panicked = PathBuf::new();
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            let mut files = ScopedFiles::new(&data_dir, true);
+            panicked = files.create("pwfile", b"password").expect("file created");
+            panic!("simulated failure");
+        }));
+
+        assert!(result.is_err());
+        assert!(!succeeded.exists());
+        assert!(failed.exists());
+        assert!(panicked.exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_private() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let path = temp_dir.path().join(".pgpass");
+        write_private(&path, b"first")?;
+        write_private(&path, b"second")?;
+        assert_eq!("second", std::fs::read_to_string(&path)?);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path)?.permissions().mode();
+            assert_eq!(0o600, mode & 0o777);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,99 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::PathBuf;
+
+/// Name of the file in the data directory that records the state of the [seed](Seed)
+pub(crate) const SEED_FILE: &str = "postgresql_embedded.seed";
+
+/// State of a seed that is yet to be applied
+pub(crate) const SEED_PENDING: &str = "pending";
+
+/// State of a seed that has been applied
+pub(crate) const SEED_APPLIED: &str = "applied";
+
+/// Magic bytes at the start of a pg_dump custom format archive
+const CUSTOM_ARCHIVE_MAGIC: &[u8] = b"PGDMP";
+
+/// Offset and magic bytes of the header of a tar archive, such as a pg_dump tar format archive
+const TAR_MAGIC_OFFSET: usize = 257;
+const TAR_MAGIC: &[u8] = b"ustar";
+
+/// Seed applied to the [maintenance database](crate::Settings::maintenance_database) once,
+/// when the server is started for the first time after the data directory was initialized; see
+/// [Settings::seed](crate::Settings::seed).
+///
+/// The seed is applied in a single transaction, so a seed that fails leaves no objects behind;
+/// statements that cannot run in a transaction block, such as `CREATE DATABASE`, are therefore
+/// not supported.
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+pub enum Seed {
+    /// SQL script file executed with psql
+    SqlFile(PathBuf),
+    /// SQL statements executed with psql
+    SqlString(String),
+    /// pg_dump archive (custom, directory or tar format) restored with pg_restore
+    DumpArchive(PathBuf),
+}
+
+impl Seed {
+    /// Create a seed from a dump produced by pg_dump, detecting its format from its contents:
+    /// directories and custom or tar format archives are restored with pg_restore, and any
+    /// other file is executed with psql as a plain SQL script.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the dump cannot be read.
+    pub fn from_dump<P: Into<PathBuf>>(path: P) -> std::io::Result<Self> {
+        let path = path.into();
+        if path.is_dir() {
+            return Ok(Seed::DumpArchive(path));
+        }
+
+        let mut header = Vec::with_capacity(TAR_MAGIC_OFFSET + TAR_MAGIC.len());
+        File::open(&path)?
+            .take((TAR_MAGIC_OFFSET + TAR_MAGIC.len()) as u64)
+            .read_to_end(&mut header)?;
+        if is_archive(&header) {
+            Ok(Seed::DumpArchive(path))
+        } else {
+            Ok(Seed::SqlFile(path))
+        }
+    }
+}
+
+/// Check whether the header of a file is the header of a custom or tar format archive
+fn is_archive(header: &[u8]) -> bool {
+    header.starts_with(CUSTOM_ARCHIVE_MAGIC)
+        || header.get(TAR_MAGIC_OFFSET..TAR_MAGIC_OFFSET + TAR_MAGIC.len()) == Some(TAR_MAGIC)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_dump() -> std::io::Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        assert_eq!(
+            Seed::DumpArchive(temp_dir.path().to_path_buf()),
+            Seed::from_dump(temp_dir.path())?
+        );
+
+        let custom = temp_dir.path().join("schema.dump");
+        std::fs::write(&custom, b"PGDMP\x01\x0e\x00")?;
+        assert_eq!(Seed::DumpArchive(custom.clone()), Seed::from_dump(&custom)?);
+
+        let mut header = vec![0; TAR_MAGIC_OFFSET];
+        header.extend_from_slice(b"ustar\x0000");
+        let tar = temp_dir.path().join("schema.tar");
+        std::fs::write(&tar, header)?;
+        assert_eq!(Seed::DumpArchive(tar.clone()), Seed::from_dump(&tar)?);
+
+        let sql = temp_dir.path().join("schema.sql");
+        std::fs::write(&sql, "CREATE TABLE items (id int);")?;
+        assert_eq!(Seed::SqlFile(sql.clone()), Seed::from_dump(&sql)?);
+
+        assert!(Seed::from_dump(temp_dir.path().join("missing.sql")).is_err());
+        Ok(())
+    }
+}
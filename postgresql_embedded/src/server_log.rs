@@ -0,0 +1,237 @@
+//! Forwarding of the server log to `tracing`; see
+//! [trace_server_logs](crate::Settings::trace_server_logs)
+
+use crate::telemetry;
+use std::fs::File;
+use std::io::{ErrorKind, Read, Seek, SeekFrom};
+use std::path::PathBuf;
+use std::sync::mpsc::{channel, RecvTimeoutError, Sender};
+use std::thread::JoinHandle;
+use std::time::Duration;
+use tracing::{debug, Dispatch, Level};
+
+/// Target of the events emitted for the lines of the server log
+pub(crate) const SERVER_LOG_TARGET: &str = "postgresql_embedded::server";
+
+/// Interval at which the [`ServerLogForwarder`] reads the lines appended to the server log
+const READ_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Severities of the server log and the level of the events emitted for them. Lines with the
+/// `DETAIL`, `HINT`, `CONTEXT`, `STATEMENT`, `QUERY` and `LOCATION` severities, and lines
+/// without a severity, belong to the preceding line and are emitted at its level.
+const SEVERITIES: [(&str, Level); 13] = [
+    ("PANIC", Level::ERROR),
+    ("FATAL", Level::ERROR),
+    ("ERROR", Level::ERROR),
+    ("WARNING", Level::WARN),
+    ("NOTICE", Level::INFO),
+    ("INFO", Level::INFO),
+    ("LOG", Level::INFO),
+    ("DEBUG1", Level::DEBUG),
+    ("DEBUG2", Level::DEBUG),
+    ("DEBUG3", Level::DEBUG),
+    ("DEBUG4", Level::DEBUG),
+    ("DEBUG5", Level::DEBUG),
+    ("DEBUG", Level::DEBUG),
+];
+
+/// Forwards the lines the server writes to stderr to `tracing` on a background thread. The
+/// server is started by pg_ctl, which redirects the stderr of the server to `start.log` in the
+/// data directory; the lines appended to it are emitted as events as they are written. The
+/// forwarder stops when dropped, after emitting the remaining lines.
+#[derive(Debug)]
+pub(crate) struct ServerLogForwarder {
+    stop: Option<Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl ServerLogForwarder {
+    /// Start forwarding the lines appended to the log after the offset. The events are
+    /// dispatched to the subscriber that is the default when the forwarder is started.
+    pub(crate) fn start(path: PathBuf, offset: u64) -> Self {
+        let (stop, stopped) = channel::<()>();
+        let dispatch = tracing::dispatcher::get_default(Dispatch::clone);
+        let span = telemetry::background_span("postgresql.server_log");
+        let thread = std::thread::spawn(move || {
+            tracing::dispatcher::with_default(&dispatch, || {
+                let _span = span.entered();
+                let mut reader = LogReader::new(path, offset);
+                loop {
+                    let stopping = !matches!(
+                        stopped.recv_timeout(READ_INTERVAL),
+                        Err(RecvTimeoutError::Timeout)
+                    );
+                    reader.read_lines();
+                    if stopping {
+                        reader.flush();
+                        break;
+                    }
+                }
+            });
+        });
+
+        Self {
+            stop: Some(stop),
+            thread: Some(thread),
+        }
+    }
+}
+
+impl Drop for ServerLogForwarder {
+    fn drop(&mut self) {
+        // Dropping the sender wakes the forwarder thread, which then exits
+        drop(self.stop.take());
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Reads the lines appended to the server log and emits them as events
+struct LogReader {
+    path: PathBuf,
+    offset: u64,
+    /// Incomplete last line of the log
+    partial: Vec<u8>,
+    /// Level of the last line with a severity
+    level: Level,
+}
+
+impl LogReader {
+    fn new(path: PathBuf, offset: u64) -> Self {
+        Self {
+            path,
+            offset,
+            partial: Vec::new(),
+            level: Level::INFO,
+        }
+    }
+
+    /// Emit the complete lines appended to the log since the last read
+    fn read_lines(&mut self) {
+        let mut file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(error) if error.kind() == ErrorKind::NotFound => return,
+            Err(error) => {
+                debug!("Unable to open the server log: {error}");
+                return;
+            }
+        };
+        let mut bytes = Vec::new();
+        if let Err(error) = file
+            .seek(SeekFrom::Start(self.offset))
+            .and_then(|_| file.read_to_end(&mut bytes))
+        {
+            debug!("Unable to read the server log: {error}");
+            return;
+        }
+        self.offset += bytes.len() as u64;
+        self.partial.extend(bytes);
+
+        let Some(end) = self.partial.iter().rposition(|byte| *byte == b'\n') else {
+            return;
+        };
+        let lines: Vec<u8> = self.partial.drain(..=end).collect();
+        for line in String::from_utf8_lossy(&lines).lines() {
+            self.emit(line);
+        }
+    }
+
+    /// Emit the incomplete last line of the log, if any
+    fn flush(&mut self) {
+        let line = String::from_utf8_lossy(&self.partial).into_owned();
+        self.partial.clear();
+        if !line.trim().is_empty() {
+            self.emit(&line);
+        }
+    }
+
+    fn emit(&mut self, line: &str) {
+        if line.trim().is_empty() {
+            return;
+        }
+        let (level, severity) = match parse_severity(line) {
+            Some((severity, Some(level))) => {
+                self.level = level;
+                (level, severity)
+            }
+            Some((severity, None)) => (self.level, severity),
+            None => (self.level, ""),
+        };
+        match level {
+            Level::ERROR => {
+                tracing::error!(target: SERVER_LOG_TARGET, severity, "{line}");
+            }
+            Level::WARN => tracing::warn!(target: SERVER_LOG_TARGET, severity, "{line}"),
+            Level::INFO => tracing::info!(target: SERVER_LOG_TARGET, severity, "{line}"),
+            Level::DEBUG => {
+                tracing::debug!(target: SERVER_LOG_TARGET, severity, "{line}");
+            }
+            _ => tracing::trace!(target: SERVER_LOG_TARGET, severity, "{line}"),
+        }
+    }
+}
+
+/// Parse the severity of a server log line, e.g. `WARNING` in
+/// `2024-01-01 00:00:00.000 UTC [42] WARNING:  message`; the severity follows the log line
+/// prefix and is terminated by a colon and two spaces. The level is `None` for the severities
+/// of lines that belong to the preceding line, e.g. `DETAIL`.
+pub(crate) fn parse_severity(line: &str) -> Option<(&str, Option<Level>)> {
+    let mut start = 0;
+    while let Some(position) = line[start..].find(":  ") {
+        let end = start + position;
+        let severity = line[..end]
+            .rsplit(|c: char| !c.is_ascii_alphanumeric())
+            .next()
+            .unwrap_or_default();
+        if let Some((name, level)) = SEVERITIES.iter().find(|(name, _)| *name == severity) {
+            return Some((*name, Some(*level)));
+        }
+        if let Some(severity) = [
+            "DETAIL",
+            "HINT",
+            "CONTEXT",
+            "STATEMENT",
+            "QUERY",
+            "LOCATION",
+        ]
+        .into_iter()
+        .find(|name| *name == severity)
+        {
+            return Some((severity, None));
+        }
+        start = end + 3;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_severity() {
+        assert_eq!(
+            Some(("WARNING", Some(Level::WARN))),
+            parse_severity("2024-01-01 00:00:00.000 UTC [42] WARNING:  careful: really")
+        );
+        assert_eq!(
+            Some(("FATAL", Some(Level::ERROR))),
+            parse_severity("FATAL:  database \"missing\" does not exist")
+        );
+        assert_eq!(
+            Some(("LOG", Some(Level::INFO))),
+            parse_severity("2024-01-01 00:00:00 UTC [42] app LOG:  checkpoint starting")
+        );
+        assert_eq!(
+            Some(("DEBUG2", Some(Level::DEBUG))),
+            parse_severity("[42] DEBUG2:  autovacuum: processing database")
+        );
+        assert_eq!(
+            Some(("DETAIL", None)),
+            parse_severity("[42] DETAIL:  Key (id)=(1) already exists.")
+        );
+        assert_eq!(None, parse_severity("\tcontinued line"));
+        assert_eq!(None, parse_severity("waiting for server to start...."));
+    }
+}
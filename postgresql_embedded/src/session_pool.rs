@@ -0,0 +1,606 @@
+//! Pool of long-lived psql sessions used by the SQL helpers of [PostgreSQL](crate::PostgreSQL)
+//! instead of starting a new psql process per statement; see
+//! [psql_sessions](crate::Settings::psql_sessions)
+
+use postgresql_commands::{CommandInvocation, PassFile};
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use std::fmt::{Debug, Formatter};
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Arc, Mutex, MutexGuard};
+use tracing::debug;
+
+/// Statements run after every statement to reset the session before it is reused: an open
+/// transaction is rolled back, and the settings, roles, temporary tables, prepared statements
+/// and advisory locks of the session are released. Rolling back outside of a transaction only
+/// warns, so warnings are suppressed until the settings are discarded.
+const RESET: &str =
+    "SET client_min_messages = error;\nROLLBACK;\nSELECT pg_advisory_unlock_all();\nDISCARD ALL;";
+/// Messages printed by psql when the connection of a session was closed by the server, e.g.
+/// because the server was restarted or the backend was terminated
+const CONNECTION_LOST: [&str; 3] = [
+    "server closed the connection unexpectedly",
+    "connection to the server was lost",
+    "no connection to the server",
+];
+
+/// Statistics of the psql session pool of a server; see
+/// [session_pool_stats](crate::PostgreSQL::session_pool_stats)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SessionPoolStats {
+    /// Number of psql sessions started by the pool
+    pub spawned: u64,
+    /// Number of statements run on a session reused from the pool
+    pub reused: u64,
+    /// Number of sessions waiting in the pool
+    pub idle: usize,
+    /// Number of sessions running a statement
+    pub in_use: usize,
+}
+
+/// Pool of psql sessions shared by the clones of a server
+#[derive(Debug, Default)]
+pub(crate) struct SessionPool {
+    state: Mutex<PoolState>,
+}
+
+#[derive(Debug, Default)]
+struct PoolState {
+    idle: Vec<Session>,
+    in_use: usize,
+    spawned: u64,
+    reused: u64,
+}
+
+/// Result of [checking out](SessionPool::checkout) a session
+#[derive(Debug)]
+pub(crate) enum Checkout {
+    /// An idle session connected to the database
+    Idle(Box<Session>),
+    /// A new session may be started; the slot is reserved until the session is
+    /// [checked in](SessionPool::checkin)
+    Spawn,
+    /// Every session is in use
+    Full,
+}
+
+impl SessionPool {
+    fn lock(&self) -> MutexGuard<'_, PoolState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Take an idle session connected to the database on the port, or reserve a slot for a new
+    /// session when fewer than `max_sessions` sessions exist. Idle sessions of other databases
+    /// are closed to make room, and sessions connected to another port or whose psql process
+    /// exited are discarded.
+    pub(crate) fn checkout(&self, database_name: &str, port: u16, max_sessions: usize) -> Checkout {
+        let mut state = self.lock();
+        state
+            .idle
+            .retain(|session| session.port == port && session.is_alive());
+        if let Some(index) = state
+            .idle
+            .iter()
+            .position(|session| session.database_name == database_name)
+        {
+            let session = state.idle.swap_remove(index);
+            state.in_use += 1;
+            state.reused += 1;
+            return Checkout::Idle(Box::new(session));
+        }
+        if state.idle.len() + state.in_use >= max_sessions {
+            if state.idle.is_empty() {
+                return Checkout::Full;
+            }
+            let session = state.idle.remove(0);
+            debug!(
+                "Closing idle psql session of database {}",
+                session.database_name
+            );
+        }
+        state.in_use += 1;
+        state.spawned += 1;
+        Checkout::Spawn
+    }
+
+    /// Return a checked out session to the pool; `None` releases the slot of a session that
+    /// must not be reused
+    pub(crate) fn checkin(&self, session: Option<Session>) {
+        let mut state = self.lock();
+        state.in_use = state.in_use.saturating_sub(1);
+        if let Some(session) = session {
+            state.idle.push(session);
+        }
+    }
+
+    /// Close the idle sessions, e.g. before a database they may be connected to is dropped
+    pub(crate) fn close_idle(&self) {
+        let idle = std::mem::take(&mut self.lock().idle);
+        if !idle.is_empty() {
+            debug!("Closing {} idle psql sessions", idle.len());
+        }
+    }
+
+    /// Get the statistics of the pool
+    pub(crate) fn stats(&self) -> SessionPoolStats {
+        let state = self.lock();
+        SessionPoolStats {
+            spawned: state.spawned,
+            reused: state.reused,
+            idle: state.idle.len(),
+            in_use: state.in_use,
+        }
+    }
+}
+
+/// Long-lived psql process reading statements from stdin. The output of every statement is
+/// followed by a random sentinel on stdout and stderr, which delimits the output of the
+/// statement from the output of the next one.
+pub(crate) struct Session {
+    database_name: String,
+    port: u16,
+    uses: u32,
+    sentinel: String,
+    invocation: CommandInvocation,
+    child: Arc<Mutex<Child>>,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    stderr: Receiver<String>,
+    _passfile: Option<PassFile>,
+}
+
+impl Debug for Session {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> std::fmt::Result {
+        formatter
+            .debug_struct("Session")
+            .field("database_name", &self.database_name)
+            .field("port", &self.port)
+            .field("uses", &self.uses)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Session {
+    /// Start a psql session with the command, which must read the statements from stdin. The
+    /// password file must be kept alive as long as the session.
+    pub(crate) fn spawn(
+        mut command: Command,
+        database_name: &str,
+        port: u16,
+        passfile: Option<PassFile>,
+    ) -> postgresql_commands::Result<Self> {
+        let invocation = CommandInvocation::from(&command);
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        let (Some(stdin), Some(stdout), Some(stderr)) =
+            (child.stdin.take(), child.stdout.take(), child.stderr.take())
+        else {
+            let _ = child.kill();
+            return Err(std::io::Error::other("psql session pipes are not available").into());
+        };
+
+        // stderr is read on its own thread, so that psql never blocks on a full stderr pipe
+        // while stdout is read
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            let mut stderr = BufReader::new(stderr);
+            let mut line = Vec::new();
+            while matches!(stderr.read_until(b'\n', &mut line), Ok(length) if length > 0) {
+                let text = String::from_utf8_lossy(&line);
+                let text = text.strip_suffix('\n').unwrap_or(&text).to_string();
+                if sender.send(text).is_err() {
+                    break;
+                }
+                line.clear();
+            }
+        });
+
+        let sentinel: String = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(32)
+            .map(char::from)
+            .collect();
+        debug!("Started psql session for database {database_name}");
+        Ok(Self {
+            database_name: database_name.to_string(),
+            port,
+            uses: 0,
+            sentinel: format!("postgresql_embedded_{sentinel}"),
+            invocation,
+            child: Arc::new(Mutex::new(child)),
+            stdin,
+            stdout: BufReader::new(stdout),
+            stderr: receiver,
+            _passfile: passfile,
+        })
+    }
+
+    /// Get the psql process, e.g. to [kill] it when a statement times out
+    pub(crate) fn child(&self) -> Arc<Mutex<Child>> {
+        self.child.clone()
+    }
+
+    /// Get the number of statements run on the session
+    pub(crate) fn uses(&self) -> u32 {
+        self.uses
+    }
+
+    /// Run the [script](script) and return the stdout and stderr of the statements, as psql
+    /// would for the statements passed with `--command`. When the statements fail, psql exits
+    /// because `ON_ERROR_STOP` is set, and the error is returned with the output of psql.
+    pub(crate) fn execute(
+        &mut self,
+        script: &str,
+    ) -> postgresql_commands::Result<(String, String)> {
+        self.uses += 1;
+        self.send(script)
+    }
+
+    /// Reset the session so that the next statement does not observe the effects of the
+    /// previous ones; returns `false` when the session cannot be reused
+    pub(crate) fn reset(&mut self) -> bool {
+        self.send(RESET).is_ok()
+    }
+
+    /// Send the statements followed by the sentinels, and read the output up to the sentinels
+    fn send(&mut self, statements: &str) -> postgresql_commands::Result<(String, String)> {
+        let input = format!(
+            "{statements}\n\\echo {sentinel}\n\\warn {sentinel}\n",
+            sentinel = self.sentinel
+        );
+        if self
+            .stdin
+            .write_all(input.as_bytes())
+            .and_then(|()| self.stdin.flush())
+            .is_err()
+        {
+            return Err(self.exited(String::new()));
+        }
+
+        let marker = format!("{}\n", self.sentinel);
+        let mut stdout = Vec::new();
+        loop {
+            match self.stdout.read_until(b'\n', &mut stdout) {
+                Ok(0) | Err(_) => {
+                    let stdout = String::from_utf8_lossy(&stdout).into_owned();
+                    return Err(self.exited(stdout));
+                }
+                Ok(_) if stdout.ends_with(marker.as_bytes()) => {
+                    stdout.truncate(stdout.len() - marker.len());
+                    break;
+                }
+                Ok(_) => {}
+            }
+        }
+
+        let mut stderr = String::new();
+        loop {
+            match self.stderr.recv() {
+                Ok(line) if line == self.sentinel => break,
+                Ok(line) => {
+                    stderr.push_str(&line);
+                    stderr.push('\n');
+                }
+                Err(_) => {
+                    let stdout = String::from_utf8_lossy(&stdout).into_owned();
+                    return Err(self.exited(stdout));
+                }
+            }
+        }
+        Ok((String::from_utf8_lossy(&stdout).into_owned(), stderr))
+    }
+
+    /// Wait for the psql process to exit and build the error with the remaining output
+    fn exited(&mut self, stdout: String) -> postgresql_commands::Error {
+        if let Ok(mut child) = self.child.lock() {
+            let _ = child.wait();
+        }
+        // The stderr thread ends once psql has exited
+        let stderr = self
+            .stderr
+            .iter()
+            .map(|line| format!("{line}\n"))
+            .collect::<String>();
+        postgresql_commands::Error::CommandError {
+            invocation: self.invocation.clone(),
+            stdout,
+            stderr,
+        }
+    }
+
+    /// Check that the psql process has not exited
+    fn is_alive(&self) -> bool {
+        match self.child.lock() {
+            Ok(mut child) => matches!(child.try_wait(), Ok(None)),
+            Err(_) => false,
+        }
+    }
+}
+
+/// Stop the psql process when the session is closed
+impl Drop for Session {
+    fn drop(&mut self) {
+        kill(&self.child);
+    }
+}
+
+/// Kill the psql process of a session and wait for it to exit
+pub(crate) fn kill(child: &Mutex<Child>) {
+    if let Ok(mut child) = child.lock() {
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+}
+
+/// Check whether the stderr of a failed session reports that the connection was lost before
+/// the statement could run
+pub(crate) fn connection_lost(stderr: &str) -> bool {
+    !stderr.contains("ERROR:")
+        && CONNECTION_LOST
+            .iter()
+            .any(|message| stderr.contains(message))
+}
+
+/// Statements prepared to be sent to a [Session]
+#[derive(Debug, PartialEq)]
+pub(crate) struct Script {
+    /// Statements to write to stdin
+    pub(crate) text: String,
+    /// Whether the statements mention `DATABASE`, e.g. `DROP DATABASE`; idle sessions are
+    /// closed first, as they would keep the database they are connected to in use
+    pub(crate) database_level: bool,
+}
+
+/// Prepare SQL passed as a single query string, as with `psql --command`, to be sent to a
+/// [Session] with the same semantics: the statements are separated with `\;` rather than `;`,
+/// so that psql sends them to the server in a single query that runs as one implicit
+/// transaction. Returns `None` when the SQL must run in a new psql process instead: when it
+/// contains backslashes or colons that psql would interpret as meta-commands or variables
+/// outside of literals, identifiers and comments, or when a literal, identifier, comment or
+/// parenthesis is not closed, as psql would wait for more input.
+pub(crate) fn script(sql: &str) -> Option<Script> {
+    let chars: Vec<char> = sql.chars().collect();
+    let mut text = String::with_capacity(sql.len() + 8);
+    let mut database_level = false;
+    let mut word = String::new();
+    let mut depth = 0usize;
+    let mut index = 0;
+    while index < chars.len() {
+        let c = chars[index];
+        let next = chars.get(index + 1).copied();
+        if is_identifier_char(c) || (c == '$' && !word.is_empty()) {
+            word.push(c);
+            text.push(c);
+            index += 1;
+            continue;
+        }
+        let escapes = word.eq_ignore_ascii_case("e");
+        if word.eq_ignore_ascii_case("database") {
+            database_level = true;
+        }
+        word.clear();
+
+        let end = match (c, next) {
+            ('\'', _) => quoted_end(&chars, index, '\'', escapes)?,
+            ('"', _) => quoted_end(&chars, index, '"', false)?,
+            ('-', Some('-')) => chars[index..]
+                .iter()
+                .position(|c| *c == '\n')
+                .map_or(chars.len(), |end| index + end),
+            ('/', Some('*')) => comment_end(&chars, index)?,
+            ('$', _) => match dollar_quote_end(&chars, index) {
+                Some(end) => end?,
+                None => index + 1,
+            },
+            ('\\', _) => return None,
+            (':', Some(':')) => index + 2,
+            (':', Some(next)) if is_identifier_start(next) || matches!(next, '\'' | '"' | '{') => {
+                return None
+            }
+            ('(', _) => {
+                depth += 1;
+                index + 1
+            }
+            (')', _) => {
+                depth = depth.saturating_sub(1);
+                index + 1
+            }
+            (';', _) if depth == 0 => {
+                text.push_str("\\;");
+                index += 1;
+                continue;
+            }
+            _ => index + 1,
+        };
+        text.extend(&chars[index..end]);
+        index = end;
+    }
+    if word.eq_ignore_ascii_case("database") {
+        database_level = true;
+    }
+    if depth > 0 {
+        return None;
+    }
+    text.push_str("\n;");
+    Some(Script {
+        text,
+        database_level,
+    })
+}
+
+fn is_identifier_start(c: char) -> bool {
+    c.is_alphabetic() || c == '_' || !c.is_ascii()
+}
+
+fn is_identifier_char(c: char) -> bool {
+    is_identifier_start(c) || c.is_ascii_digit()
+}
+
+/// Get the end of the literal or identifier starting at the quote; a doubled quote is part of
+/// it, as is a quote escaped with a backslash in an escape string literal (`E'...'`)
+fn quoted_end(chars: &[char], start: usize, quote: char, escapes: bool) -> Option<usize> {
+    let mut index = start + 1;
+    while index < chars.len() {
+        match chars[index] {
+            '\\' if escapes => index += 2,
+            c if c == quote => {
+                if chars.get(index + 1) == Some(&quote) {
+                    index += 2;
+                } else {
+                    return Some(index + 1);
+                }
+            }
+            _ => index += 1,
+        }
+    }
+    None
+}
+
+/// Get the end of the block comment starting at `/*`; block comments nest
+fn comment_end(chars: &[char], start: usize) -> Option<usize> {
+    let mut depth = 0;
+    let mut index = start;
+    while index + 1 < chars.len() {
+        match (chars[index], chars[index + 1]) {
+            ('/', '*') => {
+                depth += 1;
+                index += 2;
+            }
+            ('*', '/') => {
+                depth -= 1;
+                index += 2;
+                if depth == 0 {
+                    return Some(index);
+                }
+            }
+            _ => index += 1,
+        }
+    }
+    None
+}
+
+/// Get the end of the dollar-quoted string starting at the `$`, e.g. `$body$ ... $body$`; the
+/// outer `None` when the `$` does not start a dollar quote, e.g. for a parameter such as `$1`,
+/// and the inner `None` when the string is not closed
+fn dollar_quote_end(chars: &[char], start: usize) -> Option<Option<usize>> {
+    let tag_length = chars[start + 1..].iter().position(|c| *c == '$')?;
+    let tag = &chars[start + 1..start + 1 + tag_length];
+    if tag.first().is_some_and(|c| !is_identifier_start(*c))
+        || !tag.iter().all(|c| is_identifier_char(*c))
+    {
+        return None;
+    }
+    let delimiter = &chars[start..start + tag_length + 2];
+    let body = start + delimiter.len();
+    let end = chars[body..]
+        .windows(delimiter.len())
+        .position(|window| window == delimiter)
+        .map(|end| body + end + delimiter.len());
+    Some(end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(sql: &str) -> Option<String> {
+        script(sql).map(|script| script.text)
+    }
+
+    #[test]
+    fn test_script_separates_statements() {
+        assert_eq!(Some("SELECT 1\n;".to_string()), text("SELECT 1"));
+        assert_eq!(
+            Some("SELECT 1\\; SELECT 2\\;\n;".to_string()),
+            text("SELECT 1; SELECT 2;")
+        );
+        assert_eq!(
+            Some("SELECT 1 -- done; really\n;".to_string()),
+            text("SELECT 1 -- done; really")
+        );
+    }
+
+    #[test]
+    fn test_script_keeps_quoted_text() {
+        for sql in [
+            "SELECT ';', 'it''s; fine'",
+            r#"SELECT 1 AS "a;b""#,
+            r"SELECT E'\';\\', 'C:\path'",
+            "SELECT 1 /* a; /* nested; */ b; */",
+            "CREATE FUNCTION f() RETURNS int AS $$ SELECT 1; $$ LANGUAGE sql",
+            "DO $body$ BEGIN PERFORM 1; END $body$",
+            "SELECT ARRAY[1, 2][1:2], 1::text, $1",
+            "SELECT (SELECT 1; )",
+            "SELECT price$ FROM t",
+        ] {
+            let expected = format!("{sql}\n;");
+            assert_eq!(Some(expected), text(sql), "{sql}");
+        }
+    }
+
+    #[test]
+    fn test_script_requires_new_session() {
+        for sql in [
+            r"\copy t FROM 'data.csv'",
+            r"SELECT 1 \gset",
+            "SELECT :value",
+            "SELECT :'value'",
+            r#"SELECT :"value""#,
+            "SELECT 'unterminated",
+            r#"SELECT "unterminated"#,
+            "SELECT 1 /* unterminated",
+            "SELECT $$ unterminated",
+            "SELECT (1",
+        ] {
+            assert_eq!(None, text(sql), "{sql}");
+        }
+    }
+
+    #[test]
+    fn test_script_database_level() {
+        assert!(script("DROP DATABASE test").is_some_and(|script| script.database_level));
+        assert!(script("alter database test rename to other")
+            .is_some_and(|script| script.database_level));
+        assert!(script("SELECT 1; create database").is_some_and(|script| script.database_level));
+        assert!(!script("SELECT 'database'").is_some_and(|script| script.database_level));
+        assert!(!script("SELECT current_database()").is_some_and(|script| script.database_level));
+    }
+
+    #[test]
+    fn test_connection_lost() {
+        assert!(connection_lost(
+            "psql:<stdin>:1: server closed the connection unexpectedly\n"
+        ));
+        assert!(!connection_lost(
+            "psql:<stdin>:1: ERROR:  relation \"t\" does not exist\n"
+        ));
+    }
+
+    #[test]
+    fn test_session_pool_checkout() {
+        let pool = SessionPool::default();
+        assert!(matches!(pool.checkout("test", 5432, 1), Checkout::Spawn));
+        assert!(matches!(pool.checkout("test", 5432, 1), Checkout::Full));
+        pool.checkin(None);
+        assert!(matches!(pool.checkout("other", 5432, 1), Checkout::Spawn));
+        pool.checkin(None);
+        assert!(matches!(pool.checkout("test", 5432, 0), Checkout::Full));
+        assert_eq!(
+            SessionPoolStats {
+                spawned: 2,
+                reused: 0,
+                idle: 0,
+                in_use: 0,
+            },
+            pool.stats()
+        );
+    }
+}
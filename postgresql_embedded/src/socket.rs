@@ -0,0 +1,107 @@
+//! Unix-domain socket directories of the servers. By default every data directory gets a short
+//! socket directory of its own in the temporary directory, so that the socket and lock files
+//! left behind by unclean exits cannot collide with other servers using the same port.
+
+use crate::crash::process_exists;
+use crate::error::Result;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+/// Prefix of the names of the managed socket directories
+const MANAGED_DIR_PREFIX: &str = "pg-sock-";
+
+/// Number of bytes of the hash of the data directory used in the name of its managed socket
+/// directory; the name stays short so that the socket path is within the platform limit
+const MANAGED_DIR_HASH_LENGTH: usize = 6;
+
+/// Get the managed socket directory of the server with the given data directory; e.g.
+/// `/tmp/pg-sock-3fa8c2d91b0e`. The name is derived from the absolute data directory, so the
+/// server gets the same socket directory every time it is started.
+pub(crate) fn managed_dir(data_dir: &Path) -> PathBuf {
+    let data_dir = std::path::absolute(data_dir).unwrap_or_else(|_| data_dir.to_path_buf());
+    let digest = Sha256::digest(data_dir.as_os_str().as_encoded_bytes());
+    let hash = hex::encode(&digest[..MANAGED_DIR_HASH_LENGTH]);
+    std::env::temp_dir().join(format!("{MANAGED_DIR_PREFIX}{hash}"))
+}
+
+/// Get the path of the Unix-domain socket of the port in the socket directory
+pub(crate) fn socket_path(socket_dir: &Path, port: u16) -> PathBuf {
+    socket_dir.join(format!(".s.PGSQL.{port}"))
+}
+
+/// Get the path of the lock file of the Unix-domain socket of the port in the socket directory
+pub(crate) fn lock_file_path(socket_dir: &Path, port: u16) -> PathBuf {
+    socket_dir.join(format!(".s.PGSQL.{port}.lock"))
+}
+
+/// Remove the socket lock file of the port in the socket directory, and the socket it guards,
+/// when the process that created them is no longer running, e.g. after the server was killed.
+/// Lock files that cannot be parsed are kept, since they may belong to a server that is
+/// starting. Returns whether the files were removed.
+pub(crate) fn remove_stale_files(socket_dir: &Path, port: u16) -> Result<bool> {
+    let lock_file = lock_file_path(socket_dir, port);
+    let Ok(contents) = std::fs::read_to_string(&lock_file) else {
+        return Ok(false);
+    };
+    let Some(Ok(pid)) = contents.lines().next().map(|pid| pid.trim().parse::<u32>()) else {
+        return Ok(false);
+    };
+    if process_exists(pid) {
+        return Ok(false);
+    }
+
+    debug!(
+        "Removing stale socket lock file {} of process {pid}",
+        lock_file.to_string_lossy()
+    );
+    let socket = socket_path(socket_dir, port);
+    if let Err(error) = std::fs::remove_file(&socket) {
+        if error.kind() != std::io::ErrorKind::NotFound {
+            return Err(error.into());
+        }
+    }
+    std::fs::remove_file(&lock_file)?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_managed_dir() {
+        let directory = managed_dir(Path::new("/tmp/data"));
+        assert_eq!(directory, managed_dir(Path::new("/tmp/data")));
+        assert_ne!(directory, managed_dir(Path::new("/tmp/other")));
+        assert_eq!(Some(std::env::temp_dir().as_path()), directory.parent());
+        let name = directory.file_name().expect("name").to_string_lossy();
+        assert!(name.starts_with(MANAGED_DIR_PREFIX));
+        assert_eq!(
+            MANAGED_DIR_PREFIX.len() + 2 * MANAGED_DIR_HASH_LENGTH,
+            name.len()
+        );
+    }
+
+    #[test]
+    fn test_remove_stale_files() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let socket_dir = temp_dir.path();
+        assert!(!remove_stale_files(socket_dir, 5432)?);
+
+        // No process has the largest process id
+        let lock_file = lock_file_path(socket_dir, 5432);
+        std::fs::write(&lock_file, format!("{}\n/tmp/data\n", u32::MAX))?;
+        std::fs::write(socket_path(socket_dir, 5432), "")?;
+        assert!(remove_stale_files(socket_dir, 5432)?);
+        assert!(!lock_file.exists());
+        assert!(!socket_path(socket_dir, 5432).exists());
+
+        std::fs::write(&lock_file, format!("{}\n", std::process::id()))?;
+        assert!(!remove_stale_files(socket_dir, 5432)?);
+        std::fs::write(&lock_file, "")?;
+        assert!(!remove_stale_files(socket_dir, 5432)?);
+        assert!(lock_file.exists());
+        Ok(())
+    }
+}
@@ -0,0 +1,28 @@
+//! SQL helpers for building statements executed by the crate
+
+/// Quote an identifier (e.g. a database, role or table name) for use in a SQL statement
+pub(crate) fn quote_identifier<S: AsRef<str>>(identifier: S) -> String {
+    format!("\"{}\"", identifier.as_ref().replace('"', "\"\""))
+}
+
+/// Quote a string literal for use in a SQL statement
+pub(crate) fn quote_literal<S: AsRef<str>>(literal: S) -> String {
+    format!("'{}'", literal.as_ref().replace('\'', "''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quote_identifier() {
+        assert_eq!("\"test\"", quote_identifier("test"));
+        assert_eq!("\"My \"\"Table\"\"\"", quote_identifier("My \"Table\""));
+    }
+
+    #[test]
+    fn test_quote_literal() {
+        assert_eq!("'test'", quote_literal("test"));
+        assert_eq!("'it''s'", quote_literal("it's"));
+    }
+}
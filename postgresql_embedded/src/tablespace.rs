@@ -0,0 +1,120 @@
+//! Tablespaces of a server. Tablespaces created without a location are placed in a directory
+//! managed by the crate, next to the data directory, which is removed along with the data
+//! directory of temporary servers.
+
+use crate::error::Result;
+use crate::settings::data_dir_sibling;
+use std::fs::create_dir_all;
+use std::path::{Path, PathBuf};
+
+/// Tablespace; see [list_tablespaces](crate::PostgreSQL::list_tablespaces)
+#[derive(Clone, Debug, PartialEq)]
+pub struct TablespaceInfo {
+    /// Name of the tablespace
+    pub name: String,
+    /// Directory of the tablespace; `None` for the built-in `pg_default` and `pg_global`
+    /// tablespaces, which are stored in the data directory
+    pub location: Option<PathBuf>,
+    /// Whether the directory of the tablespace is managed by the crate, i.e. the tablespace was
+    /// created without a location
+    pub managed: bool,
+}
+
+/// Get the directory containing the managed tablespace directories of the server with the given
+/// data directory; e.g. `/tmp/data.tablespaces` for `/tmp/data`
+pub(crate) fn managed_dir(data_dir: &Path) -> PathBuf {
+    data_dir_sibling(data_dir, ".tablespaces")
+}
+
+/// Check if the tablespace name can be used as the name of a managed tablespace directory
+pub(crate) fn is_valid_directory_name(name: &str) -> bool {
+    !name.is_empty() && name != "." && name != ".." && !name.contains(['/', '\\', ':', '\0'])
+}
+
+/// Create the directory of a managed tablespace. The server requires the directory to be owned
+/// by the user running the server, and restricts the permissions to that user on Unix; on
+/// Windows the directory inherits the permissions of the managed directory, and the server
+/// links to it with a junction rather than a symbolic link.
+pub(crate) fn create_directory(location: &Path) -> Result<()> {
+    create_dir_all(location)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(location, std::fs::Permissions::from_mode(0o700))?;
+    }
+    Ok(())
+}
+
+/// Parse the rows of tablespace names and locations
+pub(crate) fn parse_tablespaces(rows: Vec<Vec<String>>, managed_dir: &Path) -> Vec<TablespaceInfo> {
+    rows.into_iter()
+        .filter_map(|row| match row.as_slice() {
+            [name, location] => {
+                let location = (!location.is_empty()).then(|| PathBuf::from(location));
+                Some(TablespaceInfo {
+                    name: name.clone(),
+                    managed: location
+                        .as_ref()
+                        .is_some_and(|location| location.starts_with(managed_dir)),
+                    location,
+                })
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_managed_dir() {
+        assert_eq!(
+            PathBuf::from("/tmp/data.tablespaces"),
+            managed_dir(Path::new("/tmp/data"))
+        );
+    }
+
+    #[test]
+    fn test_is_valid_directory_name() {
+        assert!(is_valid_directory_name("fast_space"));
+        assert!(is_valid_directory_name("Fast Space"));
+        assert!(!is_valid_directory_name(""));
+        assert!(!is_valid_directory_name(".."));
+        assert!(!is_valid_directory_name("../space"));
+        assert!(!is_valid_directory_name("c:\\space"));
+    }
+
+    #[test]
+    fn test_parse_tablespaces() {
+        let rows = vec![
+            vec![
+                "fast_space".to_string(),
+                "/tmp/data.tablespaces/fast_space".to_string(),
+            ],
+            vec!["pg_default".to_string(), String::new()],
+            vec!["ssd".to_string(), "/mnt/ssd".to_string()],
+        ];
+        assert_eq!(
+            vec![
+                TablespaceInfo {
+                    name: "fast_space".to_string(),
+                    location: Some(PathBuf::from("/tmp/data.tablespaces/fast_space")),
+                    managed: true,
+                },
+                TablespaceInfo {
+                    name: "pg_default".to_string(),
+                    location: None,
+                    managed: false,
+                },
+                TablespaceInfo {
+                    name: "ssd".to_string(),
+                    location: Some(PathBuf::from("/mnt/ssd")),
+                    managed: false,
+                },
+            ],
+            parse_tablespaces(rows, Path::new("/tmp/data.tablespaces"))
+        );
+    }
+}
@@ -0,0 +1,59 @@
+//! Fast teardown of a server at the end of a test; see
+//! [teardown](crate::PostgreSQL::teardown)
+
+use std::time::Duration;
+
+/// Mode used to stop the server with [teardown](crate::PostgreSQL::teardown). When no mode is
+/// given, [Immediate](TeardownMode::Immediate) is used for
+/// [temporary](crate::Settings::temporary) instances, whose data directory is removed anyway,
+/// and [Fast](TeardownMode::Fast) otherwise.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TeardownMode {
+    /// Stop the server in fast mode, which writes a shutdown checkpoint. The checkpoint is
+    /// started beforehand when [checkpoint_before_stop](crate::Settings::checkpoint_before_stop)
+    /// is set.
+    Fast,
+    /// Stop the server in immediate mode, skipping the shutdown checkpoint; the server performs
+    /// crash recovery when the data directory is started again
+    Immediate,
+}
+
+/// Report of a [teardown](crate::PostgreSQL::teardown)
+#[derive(Clone, Debug, PartialEq)]
+pub struct TeardownReport {
+    /// Mode used to stop the server
+    pub mode: TeardownMode,
+    /// Time taken by the checkpoint issued before stopping the server; `None` if no checkpoint
+    /// was issued
+    pub checkpoint: Option<Duration>,
+    /// Time taken to stop the server
+    pub shutdown: Duration,
+    /// Whether the data directory was removed, i.e. the instance is temporary
+    pub removed: bool,
+}
+
+/// Build the statement issuing a checkpoint that is cancelled after the timeout
+pub(crate) fn checkpoint_statement(timeout: Duration) -> String {
+    format!(
+        "SET statement_timeout = {}; CHECKPOINT",
+        timeout.as_millis().max(1)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checkpoint_statement() {
+        assert_eq!(
+            "SET statement_timeout = 2500; CHECKPOINT",
+            checkpoint_statement(Duration::from_millis(2500))
+        );
+        // A timeout of 0 would disable the timeout
+        assert_eq!(
+            "SET statement_timeout = 1; CHECKPOINT",
+            checkpoint_statement(Duration::ZERO)
+        );
+    }
+}
@@ -0,0 +1,44 @@
+//! OpenTelemetry-compatible span attributes and events of the server lifecycle; see the
+//! [span hierarchy](crate#opentelemetry). The helpers do nothing without the `otel` feature.
+
+use std::time::Instant;
+use tracing::Span;
+
+/// Value of the `db.system` attribute
+pub(crate) const DB_SYSTEM: &str = "postgresql";
+
+/// Record the attribute on the current span; the attribute must be declared by the span
+pub(crate) fn record<V: tracing::Value>(name: &str, value: V) {
+    #[cfg(feature = "otel")]
+    Span::current().record(name, value);
+    #[cfg(not(feature = "otel"))]
+    let _ = (name, value);
+}
+
+/// Record the completion of a phase, e.g. `download`, as an event of the current span with
+/// the duration of the phase
+pub(crate) fn phase_completed(phase: &'static str, started: Instant) {
+    #[cfg(feature = "otel")]
+    tracing::info!(
+        postgresql.phase = phase,
+        postgresql.duration_ms = started.elapsed().as_millis() as u64,
+        "phase completed"
+    );
+    #[cfg(not(feature = "otel"))]
+    let _ = (phase, started);
+}
+
+/// Create the span of a background task, e.g. `postgresql.low_disk_watcher`, as a child of
+/// the current span; entering it on the thread of the task parents the spans of the task to
+/// the operation that started it.
+pub(crate) fn background_span(name: &'static str) -> Span {
+    #[cfg(feature = "otel")]
+    {
+        tracing::info_span!("postgresql.background", otel.name = name)
+    }
+    #[cfg(not(feature = "otel"))]
+    {
+        let _ = name;
+        Span::none()
+    }
+}
@@ -0,0 +1,306 @@
+use crate::error::Error::TemplateError;
+use crate::error::Result;
+use crate::postgresql::PostgreSQL;
+use crate::settings::BOOTSTRAP_SUPERUSER;
+use crate::sql::quote_identifier;
+use postgresql_archive::{acquire_lock, LockFile};
+use postgresql_commands::psql::PsqlBuilder;
+use sha2::{Digest, Sha256};
+use std::fs::{read, read_dir};
+use std::path::{Path, PathBuf};
+use tracing::{debug, instrument};
+
+/// Default prefix used for the names of migrated template databases
+pub const DEFAULT_TEMPLATE_PREFIX: &str = "migrated_template_";
+
+/// Number of hexadecimal characters of the migration hash used in the template name
+const HASH_LENGTH: usize = 16;
+
+/// Maintains a template database built from a directory of SQL migrations. The name of the
+/// template database encodes a hash of the migration files, so changing, adding, or removing a
+/// migration automatically results in a new template being built the next time
+/// [setup](MigratedTemplate::setup) is called. Templates built from stale migration sets are
+/// dropped during setup.
+///
+/// Migrations are the `*.sql` files in the migrations directory, applied in file name order,
+/// each in its own transaction.
+#[derive(Clone, Debug)]
+pub struct MigratedTemplate {
+    migrations_dir: PathBuf,
+    prefix: String,
+    analyze: Option<bool>,
+}
+
+impl MigratedTemplate {
+    /// Create a new [`MigratedTemplate`] for the given migrations directory
+    pub fn new<P: Into<PathBuf>>(migrations_dir: P) -> Self {
+        Self {
+            migrations_dir: migrations_dir.into(),
+            prefix: DEFAULT_TEMPLATE_PREFIX.to_string(),
+            analyze: None,
+        }
+    }
+
+    /// Set the prefix used for the template database names. Every database whose name starts
+    /// with this prefix is considered to be managed by this template and is subject to removal
+    /// when it does not match the current migrations.
+    pub fn prefix<S: AsRef<str>>(mut self, prefix: S) -> Self {
+        self.prefix = prefix.as_ref().to_string();
+        self
+    }
+
+    /// Analyze the template database once the migrations are applied, before it is marked as a
+    /// template, so that databases cloned from it start with statistics for the tables the
+    /// migrations populate. Defaults to
+    /// [analyze_after_load](crate::Settings::analyze_after_load).
+    pub fn analyze(mut self, analyze: bool) -> Self {
+        self.analyze = Some(analyze);
+        self
+    }
+
+    /// Get the migrations directory
+    pub fn migrations_dir(&self) -> &Path {
+        &self.migrations_dir
+    }
+
+    /// Get the migration files, sorted by file name
+    pub fn migrations(&self) -> Result<Vec<PathBuf>> {
+        let mut migrations = Vec::new();
+        for entry in read_dir(&self.migrations_dir)? {
+            let path = entry?.path();
+            if path.is_file() && path.extension().is_some_and(|extension| extension == "sql") {
+                migrations.push(path);
+            }
+        }
+        migrations.sort();
+        Ok(migrations)
+    }
+
+    /// Compute the hash of the migration set; the hash covers the file names and contents of
+    /// every migration.
+    pub fn hash(&self) -> Result<String> {
+        let mut hasher = Sha256::new();
+        for migration in self.migrations()? {
+            let file_name = migration.file_name().unwrap_or_default();
+            hasher.update(file_name.to_string_lossy().as_bytes());
+            hasher.update([0]);
+            hasher.update(read(&migration)?);
+            hasher.update([0]);
+        }
+        Ok(hex::encode(hasher.finalize()))
+    }
+
+    /// Get the name of the template database for the current migration set
+    pub fn template_name(&self) -> Result<String> {
+        let hash = self.hash()?;
+        Ok(format!("{}{}", self.prefix, &hash[..HASH_LENGTH]))
+    }
+
+    /// Ensure that the template database for the current migration set exists, building it if
+    /// necessary, and drop the templates of stale migration sets. A lock file in the data
+    /// directory ensures that only one process builds the template at a time.
+    ///
+    /// Returns the name of the template database.
+    #[instrument(skip(postgresql))]
+    pub async fn setup(&self, postgresql: &PostgreSQL) -> Result<String> {
+        let template_name = self.template_name()?;
+        let lock_file = postgresql
+            .settings()
+            .data_dir
+            .join("migrated_template.lock");
+        let _lock = acquire_template_lock(lock_file).await?;
+
+        if postgresql.database_exists(&template_name).await? {
+            debug!("Template database {template_name} already exists");
+        } else {
+            self.build(postgresql, &template_name).await?;
+        }
+
+        for database_name in self.template_databases(postgresql).await? {
+            if database_name != template_name {
+                debug!("Dropping stale template database {database_name}");
+                drop_template(postgresql, &database_name).await?;
+            }
+        }
+
+        Ok(template_name)
+    }
+
+    /// Create a new database with the given name as a clone of the template database. The
+    /// template is set up first if it does not already exist.
+    #[instrument(skip(postgresql, database_name))]
+    pub async fn create_database<S: AsRef<str>>(
+        &self,
+        postgresql: &PostgreSQL,
+        database_name: S,
+    ) -> Result<()> {
+        let template_name = self.setup(postgresql).await?;
+        let sql = format!(
+            "CREATE DATABASE {} TEMPLATE {}",
+            quote_identifier(database_name),
+            quote_identifier(template_name)
+        );
+        match postgresql.maintenance_query(sql).await {
+            Ok(_) => Ok(()),
+            Err(error) => Err(TemplateError(error.into())),
+        }
+    }
+
+    /// Build the template database by applying the migrations to a new database. The migrations
+    /// are applied to a staging database that is renamed once all migrations succeed, so that a
+    /// failed build never leaves a partially migrated template behind.
+    async fn build(&self, postgresql: &PostgreSQL, template_name: &str) -> Result<()> {
+        let staging_name = format!("{template_name}_staging");
+        debug!("Building template database {template_name}");
+
+        drop_template(postgresql, &staging_name).await?;
+        postgresql.create_database(&staging_name).await?;
+
+        for migration in self.migrations()? {
+            debug!("Applying migration {}", migration.to_string_lossy());
+            let psql = PsqlBuilder::from(postgresql.settings())
+                .file(&migration)
+                .dbname(&staging_name)
+                .username(BOOTSTRAP_SUPERUSER)
+                .variable(("ON_ERROR_STOP", "1"))
+                .single_transaction()
+                .no_psqlrc()
+                .quiet();
+
+            if let Err(error) = postgresql.execute_command(psql).await {
+                drop_template(postgresql, &staging_name).await?;
+                return Err(TemplateError(error.into()));
+            }
+        }
+
+        if self
+            .analyze
+            .unwrap_or(postgresql.settings().analyze_after_load)
+        {
+            if let Err(error) = postgresql.analyze(&staging_name, &[]).await {
+                drop_template(postgresql, &staging_name).await?;
+                return Err(error);
+            }
+        }
+
+        for sql in [
+            format!(
+                "ALTER DATABASE {} RENAME TO {}",
+                quote_identifier(&staging_name),
+                quote_identifier(template_name)
+            ),
+            format!(
+                "ALTER DATABASE {} WITH IS_TEMPLATE true",
+                quote_identifier(template_name)
+            ),
+        ] {
+            if let Err(error) = postgresql.maintenance_query(sql).await {
+                return Err(TemplateError(error.into()));
+            }
+        }
+
+        debug!("Built template database {template_name}");
+        Ok(())
+    }
+
+    /// Get the names of all databases managed by this template
+    async fn template_databases(&self, postgresql: &PostgreSQL) -> Result<Vec<String>> {
+        let rows = match postgresql
+            .maintenance_query("SELECT datname FROM pg_database")
+            .await
+        {
+            Ok(rows) => rows,
+            Err(error) => return Err(TemplateError(error.into())),
+        };
+
+        Ok(rows
+            .into_iter()
+            .filter_map(|row| row.into_iter().next())
+            .filter(|database_name| database_name.starts_with(&self.prefix))
+            .collect())
+    }
+}
+
+/// Drop a template database; the database must be unmarked as a template before it can be dropped.
+async fn drop_template(postgresql: &PostgreSQL, database_name: &str) -> Result<()> {
+    if !postgresql.database_exists(database_name).await? {
+        return Ok(());
+    }
+
+    let sql = format!(
+        "ALTER DATABASE {} WITH IS_TEMPLATE false",
+        quote_identifier(database_name)
+    );
+    if let Err(error) = postgresql.maintenance_query(sql).await {
+        return Err(TemplateError(error.into()));
+    }
+    postgresql.drop_database(database_name).await
+}
+
+/// Acquire the lock file that prevents multiple processes from building a template at the same
+/// time; the lock is released when the value is dropped. Waiting for another process sleeps the
+/// thread, so with the `tokio` feature the wait runs on the blocking thread pool.
+async fn acquire_template_lock(lock_file: PathBuf) -> Result<LockFile> {
+    #[cfg(feature = "tokio")]
+    {
+        match tokio::task::spawn_blocking(move || acquire_lock(&lock_file)).await {
+            Ok(lock) => Ok(lock?),
+            Err(error) => Err(TemplateError(error.into())),
+        }
+    }
+    #[cfg(not(feature = "tokio"))]
+    {
+        Ok(acquire_lock(&lock_file)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::write;
+    use test_log::test;
+
+    #[test]
+    fn test_migrations_sorted() -> Result<()> {
+        let migrations_dir = tempfile::tempdir()?;
+        write(migrations_dir.path().join("002_second.sql"), "SELECT 2;")?;
+        write(migrations_dir.path().join("001_first.sql"), "SELECT 1;")?;
+        write(migrations_dir.path().join("README.md"), "not a migration")?;
+
+        let template = MigratedTemplate::new(migrations_dir.path());
+        let migrations = template.migrations()?;
+
+        assert_eq!(
+            vec![
+                migrations_dir.path().join("001_first.sql"),
+                migrations_dir.path().join("002_second.sql")
+            ],
+            migrations
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_template_name_changes_with_migrations() -> Result<()> {
+        let migrations_dir = tempfile::tempdir()?;
+        let migration = migrations_dir.path().join("001_create.sql");
+        write(&migration, "CREATE TABLE foo (id INTEGER);")?;
+
+        let template = MigratedTemplate::new(migrations_dir.path()).prefix("test_");
+        let template_name = template.template_name()?;
+        assert!(template_name.starts_with("test_"));
+        assert_eq!("test_".len() + HASH_LENGTH, template_name.len());
+        assert_eq!(template_name, template.template_name()?);
+
+        write(&migration, "CREATE TABLE bar (id INTEGER);")?;
+        let changed_template_name = template.template_name()?;
+        assert_ne!(template_name, changed_template_name);
+
+        write(
+            migrations_dir.path().join("002_alter.sql"),
+            "ALTER TABLE bar ADD COLUMN name TEXT;",
+        )?;
+        assert_ne!(changed_template_name, template.template_name()?);
+        Ok(())
+    }
+}
@@ -0,0 +1,446 @@
+//! Inventory of the programs shipped in an installation; see
+//! [inventory](crate::PostgreSQL::inventory)
+
+use std::collections::HashMap;
+use std::env::consts::EXE_SUFFIX;
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{Mutex, OnceLock};
+use tracing::debug;
+
+/// Program shipped in a PostgreSQL installation. Some archive builds omit programs, such as
+/// the contrib programs; check the [inventory](crate::PostgreSQL::inventory) before relying on
+/// one.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Tool {
+    /// `clusterdb`
+    ClusterDb,
+    /// `createdb`
+    CreateDb,
+    /// `createuser`
+    CreateUser,
+    /// `dropdb`
+    DropDb,
+    /// `dropuser`
+    DropUser,
+    /// `ecpg`
+    Ecpg,
+    /// `initdb`
+    InitDb,
+    /// `oid2name`
+    Oid2Name,
+    /// `pg_amcheck`
+    PgAmcheck,
+    /// `pg_archivecleanup`
+    PgArchiveCleanup,
+    /// `pg_basebackup`
+    PgBaseBackup,
+    /// `pgbench`
+    PgBench,
+    /// `pg_checksums`
+    PgChecksums,
+    /// `pg_config`
+    PgConfig,
+    /// `pg_controldata`
+    PgControlData,
+    /// `pg_ctl`
+    PgCtl,
+    /// `pg_dump`
+    PgDump,
+    /// `pg_dumpall`
+    PgDumpAll,
+    /// `pg_isready`
+    PgIsReady,
+    /// `pg_receivewal`
+    PgReceiveWal,
+    /// `pg_recvlogical`
+    PgRecvLogical,
+    /// `pg_resetwal`
+    PgResetWal,
+    /// `pg_restore`
+    PgRestore,
+    /// `pg_rewind`
+    PgRewind,
+    /// `pg_test_fsync`
+    PgTestFsync,
+    /// `pg_test_timing`
+    PgTestTiming,
+    /// `pg_upgrade`
+    PgUpgrade,
+    /// `pg_verifybackup`
+    PgVerifyBackup,
+    /// `pg_waldump`
+    PgWalDump,
+    /// `postgres`
+    Postgres,
+    /// `psql`
+    Psql,
+    /// `reindexdb`
+    ReindexDb,
+    /// `vacuumdb`
+    VacuumDb,
+    /// `vacuumlo`
+    VacuumLo,
+}
+
+impl Tool {
+    /// Every known program
+    pub const ALL: [Tool; 34] = [
+        Tool::ClusterDb,
+        Tool::CreateDb,
+        Tool::CreateUser,
+        Tool::DropDb,
+        Tool::DropUser,
+        Tool::Ecpg,
+        Tool::InitDb,
+        Tool::Oid2Name,
+        Tool::PgAmcheck,
+        Tool::PgArchiveCleanup,
+        Tool::PgBaseBackup,
+        Tool::PgBench,
+        Tool::PgChecksums,
+        Tool::PgConfig,
+        Tool::PgControlData,
+        Tool::PgCtl,
+        Tool::PgDump,
+        Tool::PgDumpAll,
+        Tool::PgIsReady,
+        Tool::PgReceiveWal,
+        Tool::PgRecvLogical,
+        Tool::PgResetWal,
+        Tool::PgRestore,
+        Tool::PgRewind,
+        Tool::PgTestFsync,
+        Tool::PgTestTiming,
+        Tool::PgUpgrade,
+        Tool::PgVerifyBackup,
+        Tool::PgWalDump,
+        Tool::Postgres,
+        Tool::Psql,
+        Tool::ReindexDb,
+        Tool::VacuumDb,
+        Tool::VacuumLo,
+    ];
+
+    /// Get the name of the program, without the platform executable suffix; e.g. `pg_dump`
+    #[must_use]
+    pub fn program(&self) -> &'static str {
+        match self {
+            Tool::ClusterDb => "clusterdb",
+            Tool::CreateDb => "createdb",
+            Tool::CreateUser => "createuser",
+            Tool::DropDb => "dropdb",
+            Tool::DropUser => "dropuser",
+            Tool::Ecpg => "ecpg",
+            Tool::InitDb => "initdb",
+            Tool::Oid2Name => "oid2name",
+            Tool::PgAmcheck => "pg_amcheck",
+            Tool::PgArchiveCleanup => "pg_archivecleanup",
+            Tool::PgBaseBackup => "pg_basebackup",
+            Tool::PgBench => "pgbench",
+            Tool::PgChecksums => "pg_checksums",
+            Tool::PgConfig => "pg_config",
+            Tool::PgControlData => "pg_controldata",
+            Tool::PgCtl => "pg_ctl",
+            Tool::PgDump => "pg_dump",
+            Tool::PgDumpAll => "pg_dumpall",
+            Tool::PgIsReady => "pg_isready",
+            Tool::PgReceiveWal => "pg_receivewal",
+            Tool::PgRecvLogical => "pg_recvlogical",
+            Tool::PgResetWal => "pg_resetwal",
+            Tool::PgRestore => "pg_restore",
+            Tool::PgRewind => "pg_rewind",
+            Tool::PgTestFsync => "pg_test_fsync",
+            Tool::PgTestTiming => "pg_test_timing",
+            Tool::PgUpgrade => "pg_upgrade",
+            Tool::PgVerifyBackup => "pg_verifybackup",
+            Tool::PgWalDump => "pg_waldump",
+            Tool::Postgres => "postgres",
+            Tool::Psql => "psql",
+            Tool::ReindexDb => "reindexdb",
+            Tool::VacuumDb => "vacuumdb",
+            Tool::VacuumLo => "vacuumlo",
+        }
+    }
+
+    /// Get the tool for the name of a program; e.g. [Tool::PgDump] for `pg_dump`
+    #[must_use]
+    pub fn from_program(program: &str) -> Option<Tool> {
+        let program = program.strip_suffix(EXE_SUFFIX).unwrap_or(program);
+        Tool::ALL.into_iter().find(|tool| tool.program() == program)
+    }
+}
+
+impl Display for Tool {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> std::fmt::Result {
+        formatter.write_str(self.program())
+    }
+}
+
+/// Version reported by a program with `--version`; e.g. `pg_dump (PostgreSQL) 16.4`
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ToolVersion {
+    /// Major version; e.g. `16`
+    pub major: u64,
+    /// Minor version; e.g. `4`. Not set for development and pre-release builds.
+    pub minor: Option<u64>,
+    /// Pre-release label; e.g. `beta2` for `17beta2`
+    pub pre_release: Option<String>,
+    /// Vendor suffix following the version, without parentheses; e.g.
+    /// `Ubuntu 16.4-1.pgdg22.04+1`
+    pub vendor: Option<String>,
+}
+
+impl ToolVersion {
+    /// Parse the first line of the `--version` output of a program; e.g.
+    /// `psql (PostgreSQL) 16.4 (Ubuntu 16.4-1.pgdg22.04+1)` or `PostgreSQL 17beta2`
+    #[must_use]
+    pub fn parse(output: &str) -> Option<ToolVersion> {
+        let mut tokens = output
+            .lines()
+            .next()?
+            .split_whitespace()
+            .skip_while(|token| !token.starts_with(|c: char| c.is_ascii_digit()));
+        let token = tokens.next()?;
+        let vendor = tokens.collect::<Vec<_>>().join(" ");
+
+        let numbers_end = token
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(token.len());
+        let (numbers, pre_release) = token.split_at(numbers_end);
+        let mut numbers = numbers.split('.');
+        let major = numbers.next()?.parse().ok()?;
+        let minor = numbers.next().and_then(|minor| minor.parse().ok());
+        let vendor = vendor
+            .trim()
+            .trim_start_matches('(')
+            .trim_end_matches(')')
+            .trim();
+
+        Some(ToolVersion {
+            major,
+            minor,
+            pre_release: (!pre_release.is_empty()).then(|| pre_release.to_string()),
+            vendor: (!vendor.is_empty()).then(|| vendor.to_string()),
+        })
+    }
+}
+
+impl Display for ToolVersion {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(formatter, "{}", self.major)?;
+        if let Some(minor) = self.minor {
+            write!(formatter, ".{minor}")?;
+        }
+        if let Some(pre_release) = &self.pre_release {
+            formatter.write_str(pre_release)?;
+        }
+        Ok(())
+    }
+}
+
+/// Presence, location and version of a program in an installation
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ToolInfo {
+    /// The program
+    pub tool: Tool,
+    /// Absolute path of the program in the binary directory, whether it is present or not
+    pub path: PathBuf,
+    /// Whether the program is present in the installation
+    pub present: bool,
+    /// Version reported by the program; not set when the program is missing or its output
+    /// could not be parsed
+    pub version: Option<ToolVersion>,
+}
+
+/// Programs found in the binary directory of an installation, built by
+/// [setup](crate::PostgreSQL::setup)
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ToolInventory {
+    binary_dir: PathBuf,
+    tools: Vec<ToolInfo>,
+}
+
+impl ToolInventory {
+    /// Check each [known program](Tool::ALL) in the binary directory and run the programs that
+    /// are present with `--version`. The inventory of each directory is cached for the life of
+    /// the process.
+    #[must_use]
+    pub fn scan(binary_dir: &Path) -> ToolInventory {
+        static INVENTORIES: OnceLock<Mutex<HashMap<PathBuf, ToolInventory>>> = OnceLock::new();
+        let inventories = INVENTORIES.get_or_init(Mutex::default);
+        if let Some(inventory) = inventories
+            .lock()
+            .ok()
+            .and_then(|inventories| inventories.get(binary_dir).cloned())
+        {
+            return inventory;
+        }
+
+        let absolute_dir = std::path::absolute(binary_dir).unwrap_or(binary_dir.to_path_buf());
+        let tools = Tool::ALL
+            .into_iter()
+            .map(|tool| {
+                let path = absolute_dir.join(format!("{}{EXE_SUFFIX}", tool.program()));
+                let present = path.is_file();
+                let version = if present {
+                    Command::new(&path)
+                        .arg("--version")
+                        .output()
+                        .ok()
+                        .and_then(|output| {
+                            ToolVersion::parse(&String::from_utf8_lossy(&output.stdout))
+                        })
+                } else {
+                    None
+                };
+                ToolInfo {
+                    tool,
+                    path,
+                    present,
+                    version,
+                }
+            })
+            .collect::<Vec<_>>();
+        debug!(
+            "Found {} of {} programs in {}",
+            tools.iter().filter(|info| info.present).count(),
+            tools.len(),
+            binary_dir.to_string_lossy()
+        );
+
+        let inventory = ToolInventory {
+            binary_dir: binary_dir.to_path_buf(),
+            tools,
+        };
+        if let Ok(mut inventories) = inventories.lock() {
+            inventories.insert(binary_dir.to_path_buf(), inventory.clone());
+        }
+        inventory
+    }
+
+    /// Get the binary directory that was scanned
+    #[must_use]
+    pub fn binary_dir(&self) -> &Path {
+        &self.binary_dir
+    }
+
+    /// Get the programs of the inventory, present or not; empty until the installation is
+    /// scanned
+    #[must_use]
+    pub fn tools(&self) -> &[ToolInfo] {
+        &self.tools
+    }
+
+    /// Get the information of the program; `None` until the installation is scanned
+    #[must_use]
+    pub fn get(&self, tool: Tool) -> Option<&ToolInfo> {
+        self.tools.iter().find(|info| info.tool == tool)
+    }
+
+    /// Check whether the installation was scanned
+    #[must_use]
+    pub fn is_scanned(&self) -> bool {
+        !self.tools.is_empty()
+    }
+
+    /// Check whether the program is present; programs are assumed present until the
+    /// installation is scanned
+    #[must_use]
+    pub fn is_present(&self, tool: Tool) -> bool {
+        self.get(tool).is_none_or(|info| info.present)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_program() {
+        assert_eq!(Some(Tool::PgDump), Tool::from_program("pg_dump"));
+        assert_eq!(
+            Some(Tool::VacuumLo),
+            Tool::from_program(&format!("vacuumlo{EXE_SUFFIX}"))
+        );
+        assert_eq!(None, Tool::from_program("pg_dump_custom"));
+        for tool in Tool::ALL {
+            assert_eq!(Some(tool), Tool::from_program(tool.program()));
+        }
+    }
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(
+            Some(ToolVersion {
+                major: 16,
+                minor: Some(4),
+                pre_release: None,
+                vendor: None,
+            }),
+            ToolVersion::parse("pg_dump (PostgreSQL) 16.4\n")
+        );
+        assert_eq!(
+            Some(ToolVersion {
+                major: 16,
+                minor: Some(4),
+                pre_release: None,
+                vendor: Some("Ubuntu 16.4-1.pgdg22.04+1".to_string()),
+            }),
+            ToolVersion::parse("psql (PostgreSQL) 16.4 (Ubuntu 16.4-1.pgdg22.04+1)")
+        );
+        assert_eq!(
+            Some(ToolVersion {
+                major: 17,
+                minor: None,
+                pre_release: Some("beta2".to_string()),
+                vendor: None,
+            }),
+            ToolVersion::parse("postgres (PostgreSQL) 17beta2")
+        );
+        assert_eq!(
+            Some(ToolVersion {
+                major: 9,
+                minor: Some(6),
+                pre_release: None,
+                vendor: Some("Homebrew".to_string()),
+            }),
+            ToolVersion::parse("PostgreSQL 9.6.24 (Homebrew)")
+        );
+        assert_eq!(
+            "17beta2",
+            ToolVersion::parse("PostgreSQL 17beta2")
+                .unwrap()
+                .to_string()
+        );
+        assert_eq!(None, ToolVersion::parse("vacuumlo: invalid option"));
+        assert_eq!(None, ToolVersion::parse(""));
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn test_scan() -> std::io::Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = tempfile::tempdir()?;
+        let pg_dump = temp_dir.path().join("pg_dump");
+        std::fs::write(&pg_dump, "#!/bin/sh\necho 'pg_dump (PostgreSQL) 16.4'\n")?;
+        std::fs::set_permissions(&pg_dump, std::fs::Permissions::from_mode(0o755))?;
+
+        let inventory = ToolInventory::scan(temp_dir.path());
+        assert!(inventory.is_scanned());
+        assert_eq!(Tool::ALL.len(), inventory.tools().len());
+        let info = inventory.get(Tool::PgDump).expect("pg_dump");
+        assert!(info.present);
+        assert_eq!(pg_dump, info.path);
+        assert_eq!(Some(16), info.version.as_ref().map(|version| version.major));
+        let info = inventory.get(Tool::VacuumLo).expect("vacuumlo");
+        assert!(!info.present);
+        assert_eq!(None, info.version);
+        assert!(!inventory.is_present(Tool::VacuumLo));
+
+        assert!(ToolInventory::default().is_present(Tool::VacuumLo));
+        Ok(())
+    }
+}
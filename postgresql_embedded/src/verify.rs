@@ -0,0 +1,148 @@
+use crate::error::Error::VerifyError;
+use crate::error::Result;
+use crate::postgresql::FIELD_SEPARATOR;
+use postgresql_archive::Version;
+use postgresql_commands::psql::PsqlBuilder;
+#[cfg(feature = "tokio")]
+use postgresql_commands::AsyncCommandExecutor;
+use postgresql_commands::CommandBuilder;
+#[cfg(not(feature = "tokio"))]
+use postgresql_commands::CommandExecutor;
+use postgresql_commands::Settings;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::{debug, instrument};
+
+/// Information about a PostgreSQL server, reported by [`verify_connection`]
+#[derive(Clone, Debug, PartialEq)]
+pub struct ServerInfo {
+    /// Version of the server
+    pub version: Version,
+    /// Name of the database connected to
+    pub database: String,
+    /// Time the server was started
+    pub start_time: SystemTime,
+}
+
+/// Verify that a connection can be established with the given settings by authenticating and
+/// running a query in the [database](Settings::get_database) of the settings, which checks
+/// reachability, authentication and the existence of the database. The query is timed out after
+/// the [timeout](Settings::get_timeout) of the settings (with the `tokio` feature). Returns
+/// information about the server.
+#[instrument(skip(settings))]
+pub async fn verify_connection(settings: &dyn Settings) -> Result<ServerInfo> {
+    let psql = verify_command(settings);
+
+    #[cfg(not(feature = "tokio"))]
+    let result = psql.build().execute();
+    #[cfg(feature = "tokio")]
+    let result = psql.build_tokio().execute(settings.get_timeout()).await;
+
+    let stdout = match result {
+        Ok((stdout, _stderr)) => stdout,
+        Err(error) => return Err(VerifyError(error.into())),
+    };
+    let server_info = parse_server_info(&stdout)?;
+    debug!(
+        "Verified connection to database {} on PostgreSQL {}",
+        server_info.database, server_info.version
+    );
+    Ok(server_info)
+}
+
+/// Build the psql command running the verification query
+fn verify_command(settings: &dyn Settings) -> PsqlBuilder {
+    PsqlBuilder::from(settings)
+        .command("SELECT current_setting('server_version_num'), current_database(), extract(epoch FROM pg_postmaster_start_time())")
+        .dbname(settings.get_database())
+        .no_psqlrc()
+        .tuples_only()
+        .no_align()
+        .field_separator(FIELD_SEPARATOR.to_string())
+}
+
+/// Parse the output of the verification query
+fn parse_server_info(output: &str) -> Result<ServerInfo> {
+    let fields: Vec<&str> = output
+        .trim_end_matches('\n')
+        .split(FIELD_SEPARATOR)
+        .collect();
+    let [version_num, database, start_time] = fields[..] else {
+        return Err(VerifyError(anyhow::anyhow!(
+            "Unexpected verification output: {output}"
+        )));
+    };
+
+    let version = match version_num.trim().parse::<u64>() {
+        Ok(version_num) => parse_version_num(version_num),
+        Err(error) => return Err(VerifyError(error.into())),
+    };
+    let start_time = match start_time.trim().parse::<f64>() {
+        Ok(seconds) => UNIX_EPOCH + Duration::from_secs_f64(seconds),
+        Err(error) => return Err(VerifyError(error.into())),
+    };
+
+    Ok(ServerInfo {
+        version,
+        database: database.to_string(),
+        start_time,
+    })
+}
+
+/// Convert a `server_version_num` value into a [`Version`]; e.g. `160002` is `16.2` and
+/// `90624` is `9.6.24`.
+fn parse_version_num(version_num: u64) -> Version {
+    if version_num >= 100_000 {
+        Version::new(version_num / 10_000, Some(version_num % 10_000), None)
+    } else {
+        Version::new(
+            version_num / 10_000,
+            Some(version_num / 100 % 100),
+            Some(version_num % 100),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verify_command() {
+        let settings = crate::Settings {
+            maintenance_database: "template1".to_string(),
+            ..Default::default()
+        };
+        let args = verify_command(&settings).get_args();
+        assert!(args
+            .windows(2)
+            .any(|args| args[0] == "--dbname" && args[1] == "template1"));
+    }
+
+    #[test]
+    fn test_parse_version_num() {
+        assert_eq!(Version::new(16, Some(2), None), parse_version_num(160_002));
+        assert_eq!(
+            Version::new(9, Some(6), Some(24)),
+            parse_version_num(90_624)
+        );
+    }
+
+    #[test]
+    fn test_parse_server_info() -> Result<()> {
+        let output = "150018\u{1f}postgres\u{1f}1700000000.5\n";
+        let server_info = parse_server_info(output)?;
+        assert_eq!(Version::new(15, Some(18), None), server_info.version);
+        assert_eq!("postgres", server_info.database);
+        assert_eq!(
+            UNIX_EPOCH + Duration::from_millis(1_700_000_000_500),
+            server_info.start_time
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_server_info_invalid() {
+        assert!(parse_server_info("").is_err());
+        assert!(parse_server_info("version\u{1f}postgres\u{1f}0\n").is_err());
+    }
+}
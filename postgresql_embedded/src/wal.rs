@@ -0,0 +1,102 @@
+use crate::error::Result;
+use std::fs::{metadata, read_dir};
+use std::io::ErrorKind;
+use std::path::Path;
+use std::time::Duration;
+
+/// Write-ahead log (WAL) retained by a server; see [wal_usage](crate::PostgreSQL::wal_usage)
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct WalUsage {
+    /// Size of the write-ahead log directory (`pg_wal`) in bytes
+    pub wal_bytes: u64,
+    /// Name of the oldest WAL segment file in `pg_wal`; e.g. `000000010000000000000001`
+    pub oldest_segment: Option<String>,
+    /// Replication slots that pin WAL, retaining the most WAL first
+    pub slots: Vec<SlotRetention>,
+}
+
+/// WAL retained by a replication slot
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SlotRetention {
+    /// Name of the replication slot
+    pub slot_name: String,
+    /// Whether a client is currently streaming from the slot
+    pub active: bool,
+    /// WAL retained for the slot in bytes, i.e. the distance from the oldest WAL location still
+    /// required by the slot to the current WAL location
+    pub retained_bytes: u64,
+}
+
+/// Get the name of the oldest WAL segment file in the WAL directory
+pub(crate) fn oldest_segment(wal_dir: &Path) -> Result<Option<String>> {
+    let entries = match read_dir(wal_dir) {
+        Ok(entries) => entries,
+        Err(error) if error.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(error) => return Err(error.into()),
+    };
+
+    let mut oldest_segment: Option<String> = None;
+    for entry in entries {
+        let name = entry?.file_name().to_string_lossy().to_string();
+        // Segment names have the same length, so they are ordered by position in the WAL
+        if is_segment_name(&name) && oldest_segment.as_ref().is_none_or(|oldest| name < *oldest) {
+            oldest_segment = Some(name);
+        }
+    }
+    Ok(oldest_segment)
+}
+
+/// Check if the file name is a WAL segment file name, consisting of the timeline, log, and
+/// segment numbers as 24 hexadecimal digits
+fn is_segment_name(name: &str) -> bool {
+    name.len() == 24 && name.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Get the time since the state of the replication slot was last saved. The state file of a
+/// slot is written when the slot is created and when it advances, so for an inactive slot this
+/// is the time since the slot was last used.
+pub(crate) fn slot_inactive_for(data_dir: &Path, slot_name: &str) -> Option<Duration> {
+    let state = data_dir.join("pg_replslot").join(slot_name).join("state");
+    metadata(state).ok()?.modified().ok()?.elapsed().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{create_dir_all, write};
+
+    #[test]
+    fn test_oldest_segment() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        assert_eq!(None, oldest_segment(temp_dir.path())?);
+        assert_eq!(None, oldest_segment(&temp_dir.path().join("missing"))?);
+
+        create_dir_all(temp_dir.path().join("archive_status"))?;
+        for name in [
+            "000000010000000000000003",
+            "000000010000000000000002",
+            "000000010000000000000002.partial",
+            "00000002.history",
+            "000000010000000000000004",
+        ] {
+            write(temp_dir.path().join(name), [])?;
+        }
+        assert_eq!(
+            Some("000000010000000000000002".to_string()),
+            oldest_segment(temp_dir.path())?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_slot_inactive_for() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        assert_eq!(None, slot_inactive_for(temp_dir.path(), "slot"));
+
+        let slot_dir = temp_dir.path().join("pg_replslot").join("slot");
+        create_dir_all(&slot_dir)?;
+        write(slot_dir.join("state"), [])?;
+        assert!(slot_inactive_for(temp_dir.path(), "slot").is_some());
+        Ok(())
+    }
+}
@@ -0,0 +1,152 @@
+use postgresql_archive::LATEST;
+use postgresql_embedded::{
+    CancellationToken, MigratedTemplate, PostgreSQL, QualifiedName, Result, Seed, Settings,
+};
+use std::fs::write;
+use test_log::test;
+
+const ITEMS: &str = "CREATE TABLE items (id integer PRIMARY KEY, name text);
+    INSERT INTO items SELECT id, 'item ' || id FROM generate_series(1, 1000) AS id;";
+
+/// Count the rows in pg_stats for the items table
+async fn item_stats(postgresql: &PostgreSQL, database_name: &str) -> Result<String> {
+    let rows = postgresql
+        .run_sql(
+            database_name,
+            "SELECT count(*) FROM pg_stats WHERE schemaname = 'public' AND tablename = 'items'",
+        )
+        .await?
+        .rows;
+    Ok(rows[0][0].clone())
+}
+
+/// Check whether the items table was analyzed manually rather than by autovacuum
+async fn items_analyzed(postgresql: &PostgreSQL, database_name: &str) -> Result<bool> {
+    let rows = postgresql
+        .run_sql(
+            database_name,
+            "SELECT last_analyze IS NOT NULL FROM pg_stat_user_tables WHERE relname = 'items'",
+        )
+        .await?
+        .rows;
+    Ok(rows[0][0] == "t")
+}
+
+async fn dump_items(postgresql: &PostgreSQL, path: &std::path::Path) -> Result<()> {
+    postgresql.create_database("source").await?;
+    postgresql.run_sql("source", ITEMS).await?;
+    postgresql.backup("source", path).await
+}
+
+#[test(tokio::test)]
+async fn test_restore_analyze() -> Result<()> {
+    let mut postgresql = PostgreSQL::default();
+    postgresql.setup().await?;
+    postgresql.start().await?;
+    let temp_dir = tempfile::tempdir()?;
+    let archive = temp_dir.path().join("source.dump");
+    dump_items(&postgresql, &archive).await?;
+
+    // Not analyzed unless requested
+    postgresql.create_database("skipped").await?;
+    let report = postgresql
+        .restore("skipped", &archive, None, |_| {}, &CancellationToken::new())
+        .await?;
+    assert_eq!(None, report);
+    assert!(!items_analyzed(&postgresql, "skipped").await?);
+
+    postgresql.create_database("analyzed").await?;
+    let report = postgresql
+        .restore(
+            "analyzed",
+            &archive,
+            Some(true),
+            |_| {},
+            &CancellationToken::new(),
+        )
+        .await?
+        .expect("analyze report");
+    assert_eq!("analyzed", report.database);
+    assert_eq!(
+        vec![QualifiedName::qualified("public", "items")],
+        report.tables
+    );
+    assert_eq!("2", item_stats(&postgresql, "analyzed").await?);
+
+    postgresql.stop().await?;
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_restore_table_analyze_after_load() -> Result<()> {
+    let settings = Settings {
+        analyze_after_load: true,
+        ..Default::default()
+    };
+    let mut postgresql = PostgreSQL::new(LATEST, settings);
+    postgresql.setup().await?;
+    postgresql.start().await?;
+    let temp_dir = tempfile::tempdir()?;
+    let archive = temp_dir.path().join("source.dump");
+    dump_items(&postgresql, &archive).await?;
+    let table = QualifiedName::qualified("public", "items");
+
+    postgresql.create_database("analyzed").await?;
+    let report = postgresql
+        .restore_table("analyzed", &table, &archive, None)
+        .await?
+        .expect("analyze report");
+    assert_eq!(vec![table.clone()], report.tables);
+    assert_eq!("2", item_stats(&postgresql, "analyzed").await?);
+
+    // The setting is overridden per call
+    postgresql.create_database("skipped").await?;
+    let report = postgresql
+        .restore_table("skipped", &table, &archive, Some(false))
+        .await?;
+    assert_eq!(None, report);
+    assert!(!items_analyzed(&postgresql, "skipped").await?);
+
+    postgresql.stop().await?;
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_seed_analyze_after_load() -> Result<()> {
+    let settings = Settings {
+        seed: Some(Seed::SqlString(ITEMS.to_string())),
+        analyze_after_load: true,
+        ..Default::default()
+    };
+    let mut postgresql = PostgreSQL::new(LATEST, settings);
+    postgresql.setup().await?;
+    postgresql.start().await?;
+
+    assert_eq!("2", item_stats(&postgresql, "postgres").await?);
+
+    postgresql.stop().await?;
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_migrated_template_analyze() -> Result<()> {
+    let mut postgresql = PostgreSQL::default();
+    postgresql.setup().await?;
+    postgresql.start().await?;
+    let migrations_dir = tempfile::tempdir()?;
+    write(migrations_dir.path().join("001_items.sql"), ITEMS)?;
+
+    // Statistics are copied from the template to its clones
+    let template = MigratedTemplate::new(migrations_dir.path()).analyze(true);
+    template.create_database(&postgresql, "analyzed").await?;
+    assert_eq!("2", item_stats(&postgresql, "analyzed").await?);
+
+    let template = MigratedTemplate::new(migrations_dir.path())
+        .prefix("unanalyzed_")
+        .analyze(false);
+    template.create_database(&postgresql, "skipped").await?;
+    assert_eq!("0", item_stats(&postgresql, "skipped").await?);
+
+    postgresql.stop().await?;
+    Ok(())
+}
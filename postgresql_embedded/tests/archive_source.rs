@@ -0,0 +1,119 @@
+#![cfg(not(target_os = "windows"))]
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use postgresql_archive::{ArchiveSource, Version, TARGET};
+use postgresql_embedded::{Error, PostgreSQL, Result, Settings, Status};
+use std::sync::{Arc, Mutex};
+use test_log::test;
+
+/// Version provided by the in-memory source; not a real release, so nothing can be downloaded
+const VERSION: Version = Version::new(15, Some(99), Some(0));
+
+/// Fake initdb that only creates the configuration file of the data directory
+const INITDB: &str = r#"#!/bin/sh
+while [ $# -gt 0 ]; do
+    if [ "$1" = "--pgdata" ]; then
+        mkdir -p "$2" && touch "$2/postgresql.conf"
+    fi
+    shift
+done
+"#;
+
+/// Archive source serving a single archive from memory and recording the archives fetched
+#[derive(Debug, Default)]
+struct MemorySource {
+    fetched: Mutex<Vec<(Version, String)>>,
+}
+
+#[async_trait]
+impl ArchiveSource for MemorySource {
+    async fn get_version(&self, version: &Version) -> postgresql_archive::Result<Version> {
+        if version.major == VERSION.major {
+            Ok(VERSION)
+        } else {
+            Err(postgresql_archive::Error::ReleaseNotFound(
+                version.to_string(),
+            ))
+        }
+    }
+
+    async fn fetch(&self, version: &Version, target: &str) -> postgresql_archive::Result<Bytes> {
+        self.fetched
+            .lock()
+            .unwrap()
+            .push((*version, target.to_string()));
+        Ok(archive()?)
+    }
+}
+
+/// Build an installation archive containing the fake initdb
+fn archive() -> std::io::Result<Bytes> {
+    let mut builder = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+    let prefix = format!("postgresql-{VERSION}-{TARGET}");
+    let mut header = tar::Header::new_gnu();
+    header.set_entry_type(tar::EntryType::Directory);
+    header.set_size(0);
+    header.set_mode(0o755);
+    header.set_cksum();
+    builder.append_data(&mut header, format!("{prefix}/bin"), std::io::empty())?;
+
+    let mut header = tar::Header::new_gnu();
+    header.set_size(INITDB.len() as u64);
+    header.set_mode(0o755);
+    header.set_cksum();
+    builder.append_data(
+        &mut header,
+        format!("{prefix}/bin/initdb"),
+        INITDB.as_bytes(),
+    )?;
+    Ok(Bytes::from(builder.into_inner()?.finish()?))
+}
+
+fn settings(temp_dir: &tempfile::TempDir) -> Settings {
+    Settings {
+        installation_dir: temp_dir.path().join("installation"),
+        data_dir: temp_dir.path().join("data"),
+        password_file: temp_dir.path().join(".pgpass"),
+        ..Default::default()
+    }
+}
+
+#[test(tokio::test)]
+async fn test_setup_with_archive_source() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let source = Arc::new(MemorySource::default());
+    let mut postgresql = PostgreSQL::new(Version::new(15, None, None), settings(&temp_dir))
+        .with_archive_source(source.clone());
+    assert_eq!(Status::NotInstalled, postgresql.status());
+
+    postgresql.setup().await?;
+
+    assert_eq!(&VERSION, postgresql.version());
+    assert_eq!(Status::Stopped, postgresql.status());
+    assert!(postgresql
+        .settings()
+        .installation_dir
+        .ends_with(VERSION.to_string()));
+    assert_eq!(
+        vec![(VERSION, TARGET.to_string())],
+        *source.fetched.lock().unwrap()
+    );
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_setup_with_archive_source_version_not_found() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let source = Arc::new(MemorySource::default());
+    let mut postgresql = PostgreSQL::new(Version::new(14, None, None), settings(&temp_dir))
+        .with_archive_source(source.clone());
+
+    let error = postgresql.setup().await.expect_err("version not found");
+
+    assert!(matches!(error, Error::ArchiveError(_)));
+    assert!(source.fetched.lock().unwrap().is_empty());
+    Ok(())
+}
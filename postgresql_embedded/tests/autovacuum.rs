@@ -0,0 +1,80 @@
+use postgresql_embedded::{PostgreSQL, Result};
+use test_log::test;
+
+#[test(tokio::test)]
+async fn test_with_autovacuum_paused() -> Result<()> {
+    let mut postgresql = PostgreSQL::default();
+    postgresql.setup().await?;
+    postgresql.start().await?;
+    assert_eq!("on", postgresql.get_guc("autovacuum").await?);
+
+    let inside = postgresql
+        .with_autovacuum_paused(|postgresql| async move {
+            postgresql.create_database("test").await?;
+            postgresql
+                .run_sql(
+                    "test",
+                    "CREATE TABLE items AS SELECT generate_series(1, 1000) AS id",
+                )
+                .await?;
+            postgresql.get_guc("autovacuum").await
+        })
+        .await??;
+    assert_eq!("off", inside);
+    assert_eq!("on", postgresql.get_guc("autovacuum").await?);
+
+    // The prior setting is restored rather than overridden with ALTER SYSTEM
+    let rows = postgresql
+        .run_sql(
+            "postgres",
+            "SELECT count(*) FROM pg_file_settings WHERE name = 'autovacuum'",
+        )
+        .await?
+        .rows;
+    assert_eq!(vec![vec!["0".to_string()]], rows);
+
+    postgresql.stop().await?;
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_wait_for_vacuum() -> Result<()> {
+    let mut postgresql = PostgreSQL::default();
+    postgresql.setup().await?;
+    postgresql.start().await?;
+
+    let database_name = "vacuum_test";
+    postgresql.create_database(database_name).await?;
+    postgresql
+        .run_sql(
+            database_name,
+            "CREATE TABLE vacuum_table AS SELECT i FROM generate_series(1, 1000) AS i; \
+             DELETE FROM vacuum_table WHERE i % 2 = 0",
+        )
+        .await?;
+    let last_vacuum_sql =
+        "SELECT coalesce(last_vacuum::text, 'never') FROM pg_stat_all_tables WHERE relname = 'vacuum_table'";
+    let last_vacuum = postgresql.run_sql(database_name, last_vacuum_sql).await?;
+    assert_eq!(vec![vec!["never".to_string()]], last_vacuum.rows);
+
+    let timeout = std::time::Duration::from_secs(30);
+    postgresql
+        .wait_for_vacuum(database_name, "public.vacuum_table", timeout)
+        .await?;
+    let first_vacuum = postgresql.run_sql(database_name, last_vacuum_sql).await?;
+    assert_ne!(last_vacuum, first_vacuum);
+
+    postgresql
+        .wait_for_vacuum(database_name, "vacuum_table", timeout)
+        .await?;
+    let second_vacuum = postgresql.run_sql(database_name, last_vacuum_sql).await?;
+    assert_ne!(first_vacuum, second_vacuum);
+
+    assert!(postgresql
+        .wait_for_vacuum(database_name, "missing_table", timeout)
+        .await
+        .is_err());
+
+    postgresql.stop().await?;
+    Ok(())
+}
@@ -0,0 +1,126 @@
+use postgresql_archive::LATEST;
+use postgresql_embedded::{Error, PostgreSQL, QuiesceOptions, Result, Settings};
+use std::time::Duration;
+use test_log::test;
+
+async fn autovacuum_setting(postgresql: &PostgreSQL) -> Result<String> {
+    let rows = postgresql
+        .run_sql(
+            "postgres",
+            "SELECT setting FROM pg_settings WHERE name = 'autovacuum'",
+        )
+        .await?
+        .rows;
+    Ok(rows[0][0].clone())
+}
+
+#[test(tokio::test)]
+async fn test_quiesce_background_activity() -> Result<()> {
+    let mut postgresql = PostgreSQL::default();
+    postgresql.setup().await?;
+    postgresql.start().await?;
+    assert_eq!("on", autovacuum_setting(&postgresql).await?);
+
+    postgresql
+        .quiesce_background_activity(&QuiesceOptions::default())
+        .await?;
+    assert_eq!("off", autovacuum_setting(&postgresql).await?);
+    let rows = postgresql
+        .run_sql(
+            "postgres",
+            "SELECT count(*) FROM pg_stat_activity WHERE backend_type = 'autovacuum worker'",
+        )
+        .await?
+        .rows;
+    assert_eq!(vec![vec!["0".to_string()]], rows);
+
+    // Quiescing again keeps the setting to restore
+    postgresql
+        .quiesce_background_activity(&QuiesceOptions::default())
+        .await?;
+    postgresql.restore_background_activity().await?;
+    assert_eq!("on", autovacuum_setting(&postgresql).await?);
+    postgresql.restore_background_activity().await?;
+
+    postgresql.stop().await?;
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_quiesce_background_activity_persistent() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let settings = Settings {
+        data_dir: temp_dir.path().join("data"),
+        password_file: temp_dir.path().join(".pgpass"),
+        temporary: false,
+        ..Default::default()
+    };
+    let mut postgresql = PostgreSQL::new(LATEST, settings);
+    postgresql.setup().await?;
+    postgresql.start().await?;
+
+    let result = postgresql
+        .quiesce_background_activity(&QuiesceOptions::default())
+        .await;
+    assert!(matches!(result, Err(Error::BackgroundActivityError(_))));
+    assert_eq!("on", autovacuum_setting(&postgresql).await?);
+
+    let options = QuiesceOptions {
+        force: true,
+        ..Default::default()
+    };
+    postgresql.quiesce_background_activity(&options).await?;
+    assert_eq!("off", autovacuum_setting(&postgresql).await?);
+    postgresql.restore_background_activity().await?;
+    assert_eq!("on", autovacuum_setting(&postgresql).await?);
+
+    postgresql.stop().await?;
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_quiesce_background_activity_timeout() -> Result<()> {
+    let mut postgresql = PostgreSQL::default();
+    postgresql.setup().await?;
+    postgresql.start().await?;
+    postgresql.create_database("test").await?;
+    postgresql
+        .run_sql(
+            "test",
+            "CREATE TABLE items AS SELECT generate_series(1, 100000) AS id",
+        )
+        .await?;
+    // Throttle vacuums in new sessions so that the vacuum below runs for a long time
+    postgresql
+        .run_sql(
+            "test",
+            "ALTER DATABASE test SET vacuum_cost_delay = 100; \
+             ALTER DATABASE test SET vacuum_cost_limit = 1",
+        )
+        .await?;
+
+    let vacuum = postgresql.run_sql("test", "VACUUM (DISABLE_PAGE_SKIPPING) items");
+    let quiesce = async {
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        let options = QuiesceOptions {
+            disable_autovacuum: false,
+            force_checkpoint: false,
+            timeout: Duration::from_secs(1),
+            ..Default::default()
+        };
+        let result = postgresql.quiesce_background_activity(&options).await;
+        postgresql
+            .run_sql(
+                "postgres",
+                "SELECT pg_cancel_backend(pid) FROM pg_stat_activity WHERE query LIKE 'VACUUM%'",
+            )
+            .await?;
+        Ok::<_, Error>(result)
+    };
+    let (vacuum, quiesce) = tokio::join!(vacuum, quiesce);
+    assert!(vacuum.is_err());
+    assert!(matches!(quiesce?, Err(Error::BackgroundActivityError(_))));
+
+    postgresql.stop().await?;
+    Ok(())
+}
@@ -0,0 +1,122 @@
+use postgresql_embedded::{BatchOptions, PostgreSQL, Result};
+use test_log::test;
+
+#[test(tokio::test)]
+async fn test_run_sql_batch() -> Result<()> {
+    let mut postgresql = PostgreSQL::default();
+    postgresql.setup().await?;
+    postgresql.start().await?;
+
+    let failing_index = 50;
+    let statements: Vec<String> = (0..100)
+        .map(|index| {
+            if index == failing_index {
+                "SELECT * FROM missing_table".to_string()
+            } else {
+                format!("SELECT {index}")
+            }
+        })
+        .collect();
+
+    let options = BatchOptions {
+        max_concurrency: 8,
+        ..Default::default()
+    };
+    let results = postgresql
+        .run_sql_batch("postgres", statements.clone(), options)
+        .await;
+    assert_eq!(statements.len(), results.len());
+    for (index, result) in results.iter().enumerate() {
+        match result {
+            Ok(query_result) => {
+                assert_ne!(failing_index, index);
+                assert_eq!(vec![vec![index.to_string()]], query_result.rows);
+            }
+            Err(error) => {
+                assert_eq!(failing_index, index);
+                assert!(error.to_string().starts_with("Statement 50 failed"));
+            }
+        }
+    }
+
+    let options = BatchOptions {
+        max_concurrency: 8,
+        stop_on_first_error: true,
+        ..Default::default()
+    };
+    let results = postgresql
+        .run_sql_batch("postgres", statements, options)
+        .await;
+    assert_eq!(100, results.len());
+    assert!(results[..failing_index].iter().all(|result| result.is_ok()));
+    assert!(results[failing_index].is_err());
+    assert!(results[failing_index + 1..].iter().any(|result| result
+        .as_ref()
+        .is_err_and(|error| error.to_string().contains("skipped"))));
+
+    postgresql.stop().await
+}
+
+#[cfg(feature = "tokio")]
+#[test(tokio::test)]
+async fn test_run_sql_batch_concurrency_and_timeout() -> Result<()> {
+    use std::time::{Duration, Instant};
+
+    let mut postgresql = PostgreSQL::default();
+    postgresql.setup().await?;
+    postgresql.start().await?;
+
+    // Run serially, the statements would take at least four seconds
+    let statements = vec!["SELECT pg_sleep(1)".to_string(); 4];
+    let options = BatchOptions {
+        max_concurrency: 4,
+        ..Default::default()
+    };
+    let start = Instant::now();
+    let results = postgresql
+        .run_sql_batch("postgres", statements, options)
+        .await;
+    assert!(results.iter().all(|result| result.is_ok()));
+    assert!(
+        start.elapsed() < Duration::from_secs(3),
+        "statements ran serially: {:?}",
+        start.elapsed()
+    );
+
+    let options = BatchOptions {
+        per_statement_timeout: Some(Duration::from_secs(1)),
+        ..Default::default()
+    };
+    let start = Instant::now();
+    let results = postgresql
+        .run_sql_batch("postgres", vec!["SELECT pg_sleep(30)".to_string()], options)
+        .await;
+    assert!(results[0].is_err());
+    assert!(start.elapsed() < Duration::from_secs(10));
+
+    postgresql.stop().await
+}
+
+#[test(tokio::test(flavor = "multi_thread"))]
+async fn test_run_sql_batch_spawned() -> Result<()> {
+    let mut postgresql = PostgreSQL::default();
+    postgresql.setup().await?;
+    postgresql.start().await?;
+
+    let postgresql = std::sync::Arc::new(postgresql);
+    let statements = (0..10).map(|index| format!("SELECT {index}")).collect();
+    let batch = {
+        let postgresql = postgresql.clone();
+        tokio::spawn(async move {
+            postgresql
+                .run_sql_batch("postgres", statements, BatchOptions::default())
+                .await
+        })
+    };
+    let results = batch.await.expect("batch task");
+    for (index, result) in results.into_iter().enumerate() {
+        assert_eq!(vec![vec![index.to_string()]], result?.rows);
+    }
+
+    postgresql.stop().await
+}
@@ -0,0 +1,21 @@
+use postgresql_embedded::{BenchOptions, PostgreSQL, Result};
+use test_log::test;
+
+#[test(tokio::test)]
+async fn test_benchmark() -> Result<()> {
+    let mut postgresql = PostgreSQL::default();
+    postgresql.setup().await?;
+    postgresql.start().await?;
+
+    let options = BenchOptions {
+        clients: 2,
+        duration: std::time::Duration::from_secs(1),
+        ..Default::default()
+    };
+    let result = postgresql.benchmark(options).await?;
+    assert!(result.tps > 0.0);
+    assert!(result.transactions > 0);
+    assert!(!result.latency_avg.is_zero());
+
+    postgresql.stop().await
+}
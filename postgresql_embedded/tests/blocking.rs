@@ -35,3 +35,33 @@ fn test_lifecycle() -> Result<()> {
 
     Ok(())
 }
+
+#[cfg(feature = "blocking")]
+#[test]
+fn test_with_autovacuum_paused_restores_on_panic() -> Result<()> {
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+    use std::time::{Duration, Instant};
+
+    let mut postgresql = PostgreSQL::default();
+    postgresql.setup()?;
+    postgresql.start()?;
+
+    let inside =
+        postgresql.with_autovacuum_paused(|postgresql| postgresql.get_guc("autovacuum"))??;
+    assert_eq!("off", inside);
+    assert_eq!("on", postgresql.get_guc("autovacuum")?);
+
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        postgresql.with_autovacuum_paused(|_| panic!("operation failed"))
+    }));
+    assert!(result.is_err());
+    // The configuration is reloaded asynchronously after a panic
+    let start = Instant::now();
+    while postgresql.get_guc("autovacuum")? != "on" {
+        assert!(start.elapsed() < Duration::from_secs(10));
+        std::thread::sleep(Duration::from_millis(50));
+    }
+
+    postgresql.stop()?;
+    Ok(())
+}
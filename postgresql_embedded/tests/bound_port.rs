@@ -0,0 +1,96 @@
+use postgresql_archive::LATEST;
+use postgresql_commands::psql::PsqlBuilder;
+use postgresql_commands::CommandBuilder;
+use postgresql_embedded::{BoundAddress, EnvFile, EnvFileFormat, PostgreSQL, Result, Settings};
+use std::fs::{read_to_string, remove_file, write};
+use std::net::TcpStream;
+use std::process::Command;
+use test_log::test;
+
+#[cfg(not(target_os = "windows"))]
+#[test(tokio::test)]
+async fn test_start_retries_port_in_use() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let data_dir = temp_dir.path().join("data");
+    let env_file = temp_dir.path().join("connection.env");
+    let settings = Settings {
+        data_dir: data_dir.clone(),
+        password_file: temp_dir.path().join(".pgpass"),
+        temporary: false,
+        env_files: vec![EnvFile::new(&env_file, EnvFileFormat::DotEnv)],
+        ..Default::default()
+    };
+    let mut postgresql = PostgreSQL::new(LATEST, settings);
+    postgresql.setup().await?;
+    postgresql.start().await?;
+    let first_port = postgresql.settings().port;
+    let socket_dir = postgresql
+        .bound_addresses()
+        .await?
+        .into_iter()
+        .find_map(|address| match address {
+            BoundAddress::Unix(path) => path.parent().map(|path| path.to_path_buf()),
+            BoundAddress::Tcp(_) => None,
+        })
+        .expect("unix socket");
+    postgresql.stop().await?;
+
+    // Hold the socket lock file of the persisted port for a live process of the same user, so
+    // that the server fails to start on it even though the TCP/IP port is available when it is
+    // selected
+    let mut holder = Command::new("sleep").arg("30").spawn()?;
+    let lock_file = socket_dir.join(format!(".s.PGSQL.{first_port}.lock"));
+    write(&lock_file, format!("{}\n", holder.id()))?;
+    let result = postgresql.start().await;
+    let _ = remove_file(&lock_file);
+    let _ = holder.kill();
+    let _ = holder.wait();
+    result?;
+
+    let port = postgresql.settings().port;
+    assert_ne!(first_port, port);
+    let postmaster_pid = read_to_string(data_dir.join("postmaster.pid"))?;
+    assert_eq!(
+        Some(port.to_string().as_str()),
+        postmaster_pid.lines().nth(3)
+    );
+    assert!(postgresql
+        .settings()
+        .url("postgres")
+        .ends_with(&format!(":{port}/postgres")));
+    assert!(read_to_string(&env_file)?.contains(&format!("PGPORT=\"{port}\"")));
+    let result = postgresql.run_sql("postgres", "SHOW port").await?;
+    assert_eq!(vec![vec![port.to_string()]], result.rows);
+    let output = PsqlBuilder::from(postgresql.settings())
+        .command("SHOW port")
+        .no_psqlrc()
+        .tuples_only()
+        .no_align()
+        .build()
+        .output()?;
+    assert_eq!(
+        port.to_string(),
+        String::from_utf8_lossy(&output.stdout).trim()
+    );
+
+    let addresses = postgresql.bound_addresses().await?;
+    assert!(addresses.contains(&BoundAddress::Unix(
+        socket_dir.join(format!(".s.PGSQL.{port}"))
+    )));
+    for address in &addresses {
+        if let BoundAddress::Tcp(address) = address {
+            assert_eq!(port, address.port());
+        }
+    }
+    let tcp = addresses
+        .iter()
+        .find_map(|address| match address {
+            BoundAddress::Tcp(address) => Some(*address),
+            BoundAddress::Unix(_) => None,
+        })
+        .expect("tcp address");
+    assert!(TcpStream::connect(tcp).is_ok());
+
+    postgresql.stop().await?;
+    Ok(())
+}
@@ -0,0 +1,86 @@
+#![cfg(feature = "blocking")]
+
+use postgresql_embedded::blocking::{setup_for_build, BuildSetupOptions};
+use postgresql_embedded::{Result, Status};
+use std::time::SystemTime;
+use test_log::test;
+
+/// Get the time the directory was last modified
+fn modified(path: &std::path::Path) -> Result<SystemTime> {
+    Ok(std::fs::metadata(path)?.modified()?)
+}
+
+#[test]
+fn test_setup_for_build() -> Result<()> {
+    let run_dir = tempfile::tempdir()?;
+    let options = BuildSetupOptions {
+        database_name: "codegen".to_string(),
+        run_dir: Some(run_dir.path().to_path_buf()),
+        emit_warnings: false,
+        ..Default::default()
+    };
+
+    let database = setup_for_build(options.clone())?;
+    let postgresql = database.postgresql();
+    assert_eq!(Status::Started, postgresql.status());
+    assert!(database.url().starts_with("postgresql://postgres:build@"));
+    assert!(database.url().ends_with("/codegen"));
+    let result = postgresql.run_sql(database.database_name(), "SELECT current_database()")?;
+    assert_eq!(vec![vec!["codegen".to_string()]], result.rows);
+    let installation_dir = postgresql.settings().installation_dir.clone();
+    let installed_at = modified(&installation_dir)?;
+    let data_dir = postgresql.settings().data_dir.clone();
+    assert!(data_dir.starts_with(run_dir.path()));
+
+    drop(database);
+    assert!(!data_dir.exists());
+    assert_eq!(0, std::fs::read_dir(run_dir.path())?.count());
+
+    // The installation is reused by the next build
+    let database = setup_for_build(options)?;
+    let settings = database.postgresql().settings();
+    assert_eq!(installation_dir, settings.installation_dir);
+    assert_eq!(installed_at, modified(&settings.installation_dir)?);
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_setup_for_build_removes_orphans() -> Result<()> {
+    let run_dir = tempfile::tempdir()?;
+    let options = BuildSetupOptions {
+        run_dir: Some(run_dir.path().to_path_buf()),
+        emit_warnings: false,
+        ..Default::default()
+    };
+
+    // Simulate a build script killed while its server was running, by leaking the database and
+    // naming its directory after a process that does not exist
+    let database = setup_for_build(options.clone())?;
+    let directory = database
+        .postgresql()
+        .settings()
+        .data_dir
+        .parent()
+        .expect("directory")
+        .to_path_buf();
+    let postmaster_pid = std::fs::read_to_string(directory.join("data/postmaster.pid"))?
+        .lines()
+        .next()
+        .expect("pid")
+        .to_string();
+    std::mem::forget(database);
+    let orphan = run_dir.path().join(format!("{}-orphan", u32::MAX));
+    std::fs::rename(&directory, &orphan)?;
+
+    let database = setup_for_build(options)?;
+    assert!(!orphan.exists());
+    let started = std::time::Instant::now();
+    let proc_dir = std::path::PathBuf::from(format!("/proc/{postmaster_pid}"));
+    while proc_dir.exists() && started.elapsed() < std::time::Duration::from_secs(5) {
+        std::thread::sleep(std::time::Duration::from_millis(50));
+    }
+    assert!(!proc_dir.exists());
+    drop(database);
+    Ok(())
+}
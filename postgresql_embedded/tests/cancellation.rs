@@ -0,0 +1,68 @@
+#![cfg(target_os = "linux")]
+
+use postgresql_embedded::{PostgreSQL, Result, Settings};
+use std::path::Path;
+use std::time::{Duration, Instant};
+use test_log::test;
+
+/// Get the ids of the processes whose command line or environment refers to the path; initdb
+/// gets the data directory as an argument and passes it to the processes it starts in `PGDATA`
+fn processes_using(path: &Path) -> Vec<String> {
+    let path = path.to_string_lossy().to_string();
+    let Ok(entries) = std::fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter(|entry| {
+            let pid = entry.file_name().to_string_lossy().to_string();
+            pid.chars().all(|c| c.is_ascii_digit())
+                && pid != std::process::id().to_string()
+                && ["cmdline", "environ"].into_iter().any(|file| {
+                    std::fs::read(entry.path().join(file))
+                        .map(|bytes| String::from_utf8_lossy(&bytes).contains(&path))
+                        .unwrap_or(false)
+                })
+        })
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .collect()
+}
+
+#[test(tokio::test)]
+async fn test_setup_cancelled_during_initdb() -> Result<()> {
+    // Install first, so that the setup below is cancelled while initdb runs
+    let mut installed = PostgreSQL::default();
+    installed.setup().await?;
+
+    let settings = installed.settings().clone();
+    let temp_dir = tempfile::tempdir()?;
+    let data_dir = temp_dir.path().join("data");
+    let mut postgresql = PostgreSQL::from(Settings {
+        data_dir: data_dir.clone(),
+        installation_dir: settings.installation_dir.clone(),
+        ..Default::default()
+    });
+
+    let initdb_started = async {
+        while !data_dir.exists() {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+    };
+    tokio::select! {
+        result = postgresql.setup() => panic!("setup completed before initdb started: {result:?}"),
+        () = initdb_started => {}
+    }
+
+    // Dropping the setup future kills initdb and removes the partial data directory
+    assert!(!data_dir.exists());
+    let started = Instant::now();
+    while !processes_using(&data_dir).is_empty() && started.elapsed() < Duration::from_secs(5) {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    assert_eq!(Vec::<String>::new(), processes_using(&data_dir));
+
+    // Setup can be run again
+    postgresql.setup().await?;
+    postgresql.start().await?;
+    postgresql.stop().await
+}
@@ -0,0 +1,213 @@
+use postgresql_embedded::{
+    ColumnInfo, ConstraintInfo, ConstraintKind, ForeignKeyInfo, FunctionInfo, FunctionKind,
+    IndexInfo, Persistence, PostgreSQL, Result,
+};
+use test_log::test;
+
+fn column(name: &str, data_type: &str, nullable: bool, default: Option<&str>) -> ColumnInfo {
+    ColumnInfo {
+        name: name.to_string(),
+        data_type: data_type.to_string(),
+        nullable,
+        default: default.map(str::to_string),
+    }
+}
+
+fn constraint(name: &str, kind: ConstraintKind, definition: &str) -> ConstraintInfo {
+    ConstraintInfo {
+        name: name.to_string(),
+        kind,
+        definition: definition.to_string(),
+    }
+}
+
+#[test(tokio::test)]
+async fn test_describe_objects() -> Result<()> {
+    let mut postgresql = PostgreSQL::default();
+    postgresql.setup().await?;
+    postgresql.start().await?;
+
+    let database_name = "catalog_test";
+    postgresql.create_database(database_name).await?;
+    postgresql
+        .run_sql(
+            database_name,
+            r#"CREATE SCHEMA sales;
+            CREATE TABLE sales.customers (id integer PRIMARY KEY, region text NOT NULL, UNIQUE (id, region));
+            CREATE TABLE sales."Order Items" (
+                id bigserial PRIMARY KEY,
+                customer_id integer NOT NULL,
+                region text NOT NULL,
+                sku varchar(20) NOT NULL,
+                price numeric(10, 2) CHECK (price > 0),
+                active boolean NOT NULL DEFAULT true,
+                tags text[],
+                CONSTRAINT order_customer_fkey FOREIGN KEY (customer_id, region)
+                    REFERENCES sales.customers (id, region) ON DELETE CASCADE
+            );
+            CREATE INDEX order_items_active_sku ON sales."Order Items" (lower(sku), region) WHERE active;
+            CREATE UNLOGGED TABLE public.scratch (value text);
+            CREATE FUNCTION sales.total(a numeric, b numeric) RETURNS numeric LANGUAGE sql AS 'SELECT a + b';
+            CREATE PROCEDURE sales.noop() LANGUAGE sql AS 'SELECT 1';"#,
+        )
+        .await?;
+
+    let tables = postgresql.list_tables(database_name, None).await?;
+    let names: Vec<(&str, &str)> = tables
+        .iter()
+        .map(|table| (table.schema.as_str(), table.name.as_str()))
+        .collect();
+    assert_eq!(
+        vec![
+            ("public", "scratch"),
+            ("sales", "Order Items"),
+            ("sales", "customers")
+        ],
+        names
+    );
+    assert_eq!(Persistence::Unlogged, tables[0].persistence);
+    assert_eq!(Persistence::Permanent, tables[1].persistence);
+    assert_eq!("postgres", tables[1].owner);
+    assert_eq!(
+        2,
+        postgresql
+            .list_tables(database_name, Some("sales"))
+            .await?
+            .len()
+    );
+
+    postgresql
+        .run_sql(
+            database_name,
+            "INSERT INTO sales.customers VALUES (1, 'eu'), (2, 'us'); ANALYZE sales.customers",
+        )
+        .await?;
+    let tables = postgresql.list_tables(database_name, Some("sales")).await?;
+    assert_eq!(Some(2), tables[1].approximate_rows);
+
+    let description = postgresql
+        .describe_table(database_name, r#"sales."Order Items""#)
+        .await?;
+    assert_eq!("sales", description.schema);
+    assert_eq!("Order Items", description.name);
+    assert_eq!(
+        vec![
+            column(
+                "id",
+                "bigint",
+                false,
+                Some(r#"nextval('sales."Order Items_id_seq"'::regclass)"#)
+            ),
+            column("customer_id", "integer", false, None),
+            column("region", "text", false, None),
+            column("sku", "character varying(20)", false, None),
+            column("price", "numeric(10,2)", true, None),
+            column("active", "boolean", false, Some("true")),
+            column("tags", "text[]", true, None),
+        ],
+        description.columns
+    );
+    assert_eq!(
+        vec![
+            IndexInfo {
+                schema: "sales".to_string(),
+                table: "Order Items".to_string(),
+                name: "Order Items_pkey".to_string(),
+                columns: vec!["id".to_string()],
+                unique: true,
+                primary: true,
+                predicate: None,
+                definition: r#"CREATE UNIQUE INDEX "Order Items_pkey" ON sales."Order Items" USING btree (id)"#.to_string(),
+            },
+            IndexInfo {
+                schema: "sales".to_string(),
+                table: "Order Items".to_string(),
+                name: "order_items_active_sku".to_string(),
+                columns: vec!["lower(sku::text)".to_string(), "region".to_string()],
+                unique: false,
+                primary: false,
+                predicate: Some("active".to_string()),
+                definition: r#"CREATE INDEX order_items_active_sku ON sales."Order Items" USING btree (lower((sku)::text), region) WHERE active"#.to_string(),
+            },
+        ],
+        description.indexes
+    );
+    assert_eq!(
+        vec![
+            constraint(
+                "Order Items_pkey",
+                ConstraintKind::PrimaryKey,
+                "PRIMARY KEY (id)"
+            ),
+            constraint(
+                "Order Items_price_check",
+                ConstraintKind::Check,
+                "CHECK (price > 0::numeric)"
+            ),
+            constraint(
+                "order_customer_fkey",
+                ConstraintKind::ForeignKey,
+                "FOREIGN KEY (customer_id, region) REFERENCES sales.customers(id, region) ON DELETE CASCADE"
+            ),
+        ],
+        description.constraints
+    );
+    assert_eq!(
+        vec![ForeignKeyInfo {
+            name: "order_customer_fkey".to_string(),
+            columns: vec!["customer_id".to_string(), "region".to_string()],
+            referenced_schema: "sales".to_string(),
+            referenced_table: "customers".to_string(),
+            referenced_columns: vec!["id".to_string(), "region".to_string()],
+            on_update: "NO ACTION".to_string(),
+            on_delete: "CASCADE".to_string(),
+        }],
+        description.foreign_keys
+    );
+
+    let indexes = postgresql
+        .list_indexes(database_name, Some("sales"))
+        .await?;
+    let names: Vec<&str> = indexes.iter().map(|index| index.name.as_str()).collect();
+    assert_eq!(
+        vec![
+            "Order Items_pkey",
+            "order_items_active_sku",
+            "customers_id_region_key",
+            "customers_pkey"
+        ],
+        names
+    );
+
+    let functions = postgresql.list_functions(database_name, None).await?;
+    assert_eq!(
+        vec![
+            FunctionInfo {
+                schema: "sales".to_string(),
+                name: "noop".to_string(),
+                arguments: String::new(),
+                result_type: None,
+                kind: FunctionKind::Procedure,
+                language: "sql".to_string(),
+            },
+            FunctionInfo {
+                schema: "sales".to_string(),
+                name: "total".to_string(),
+                arguments: "a numeric, b numeric".to_string(),
+                result_type: Some("numeric".to_string()),
+                kind: FunctionKind::Function,
+                language: "sql".to_string(),
+            },
+        ],
+        functions
+    );
+
+    let error = postgresql
+        .describe_table(database_name, "sales.missing")
+        .await
+        .expect_err("table does not exist");
+    assert!(error.to_string().contains("does not exist"));
+
+    postgresql.stop().await?;
+    Ok(())
+}
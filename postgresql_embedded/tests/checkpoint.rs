@@ -0,0 +1,32 @@
+use postgresql_embedded::{Lsn, PostgreSQL, Result};
+use std::str::FromStr;
+use test_log::test;
+
+/// Get the redo location of the last checkpoint
+async fn redo_lsn(postgresql: &PostgreSQL) -> Result<Lsn> {
+    let rows = postgresql
+        .run_sql("postgres", "SELECT redo_lsn FROM pg_control_checkpoint()")
+        .await?
+        .rows;
+    Ok(Lsn::from_str(&rows[0][0]).expect("redo location"))
+}
+
+#[test(tokio::test)]
+async fn test_checkpoint() -> Result<()> {
+    let mut postgresql = PostgreSQL::default();
+    postgresql.setup().await?;
+    postgresql.start().await?;
+    let before = redo_lsn(&postgresql).await?;
+
+    postgresql
+        .run_sql(
+            "postgres",
+            "CREATE TABLE checkpointed AS SELECT generate_series(1, 10000) AS id",
+        )
+        .await?;
+    postgresql.checkpoint().await?;
+
+    let after = redo_lsn(&postgresql).await?;
+    assert!(after > before, "{after} > {before}");
+    postgresql.stop().await
+}
@@ -0,0 +1,20 @@
+use postgresql_embedded::{PostgreSQL, Result};
+use test_log::test;
+
+#[test(tokio::test)]
+async fn test_cluster_params() -> Result<()> {
+    let mut postgresql = PostgreSQL::default();
+    postgresql.setup().await?;
+
+    let stopped = postgresql.cluster_params().await?;
+    assert_eq!(8192, stopped.block_size);
+    assert_eq!(8192, stopped.wal_block_size);
+    assert_eq!(16 * 1024 * 1024, stopped.wal_segment_size);
+    assert_eq!(63, stopped.max_identifier_length);
+
+    postgresql.start().await?;
+    assert_eq!(stopped, postgresql.cluster_params().await?);
+
+    postgresql.stop().await?;
+    Ok(())
+}
@@ -0,0 +1,96 @@
+use postgresql_archive::LATEST;
+use postgresql_commands::psql::PsqlBuilder;
+use postgresql_commands::CommandBuilder;
+use postgresql_embedded::{PostgreSQL, Result, Settings};
+use std::fs::{remove_dir_all, remove_file};
+use test_log::test;
+
+#[test(tokio::test)]
+async fn test_keyword_value_string() -> Result<()> {
+    let settings = Settings {
+        password: "pass word='1'".to_string(),
+        ..Default::default()
+    };
+    let mut postgresql = PostgreSQL::new(LATEST, settings);
+    postgresql.setup().await?;
+    postgresql.start().await?;
+
+    let connection_string = postgresql.settings().keyword_value_string("postgres");
+    let mut psql = PsqlBuilder::new()
+        .program_dir(postgresql.settings().binary_dir())
+        .dbname(connection_string)
+        .command("SELECT current_user")
+        .no_password()
+        .no_psqlrc()
+        .tuples_only()
+        .no_align()
+        .build();
+    let output = psql.output()?;
+    assert!(output.status.success());
+    assert_eq!("postgres", String::from_utf8_lossy(&output.stdout).trim());
+
+    postgresql.stop().await?;
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_persistent_port_reused_on_restart() -> Result<()> {
+    let mut settings = Settings::default();
+    let data_dir = settings.data_dir.clone();
+    let password_file = settings.password_file.clone();
+    settings.temporary = false;
+
+    let settings = {
+        let mut postgresql = PostgreSQL::new(LATEST, settings);
+        postgresql.setup().await?;
+        postgresql.start().await?;
+        postgresql.stop().await?;
+        postgresql.settings().clone()
+    };
+
+    // A new instance with a dynamic port reuses the port persisted by the previous start
+    let restart_settings = Settings {
+        data_dir: data_dir.clone(),
+        password: settings.password.clone(),
+        password_file: password_file.clone(),
+        port: 0,
+        temporary: false,
+        ..Default::default()
+    };
+    {
+        let mut postgresql = PostgreSQL::new(LATEST, restart_settings);
+        postgresql.setup().await?;
+        postgresql.start().await?;
+        assert_eq!(settings.port, postgresql.settings().port);
+        postgresql.stop().await?;
+    }
+
+    let _ = remove_dir_all(&data_dir);
+    let _ = remove_file(&password_file);
+    Ok(())
+}
+
+#[cfg(feature = "tokio")]
+#[test(tokio::test)]
+async fn test_connection_info_handle_observes_new_port() -> Result<()> {
+    let mut postgresql = PostgreSQL::default();
+    postgresql.setup().await?;
+    postgresql.start().await?;
+    postgresql.stop().await?;
+
+    let mut handle = postgresql.connection_info_handle();
+    let port = handle.current().port;
+    assert_eq!(postgresql.settings().port, port);
+
+    // Occupy the previous port so that the restart falls back to a new port
+    let _listener = std::net::TcpListener::bind(("0.0.0.0", port))?;
+    postgresql.start().await?;
+
+    let connection_info = handle.changed().await;
+    assert_eq!(Some(postgresql.connection_info()), connection_info);
+    assert_ne!(port, postgresql.settings().port);
+    assert_eq!(Some(port), postgresql.connection_info().previous_port);
+    assert_eq!(postgresql.settings().url("test"), handle.url("test"));
+    postgresql.stop().await?;
+    Ok(())
+}
@@ -0,0 +1,93 @@
+use postgresql_archive::LATEST;
+use postgresql_commands::psql::PsqlBuilder;
+use postgresql_commands::CommandBuilder;
+use postgresql_embedded::{CrashMode, PostgreSQL, Result};
+use std::io::Write;
+use std::process::{Child, Stdio};
+use std::thread::sleep;
+use std::time::Duration;
+use test_log::test;
+
+fn query(postgresql: &PostgreSQL, sql: &str) -> Result<String> {
+    let mut psql = PsqlBuilder::from(postgresql.settings())
+        .command(sql)
+        .no_psqlrc()
+        .tuples_only()
+        .no_align()
+        .build();
+    let output = psql.output()?;
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Start a psql session with an open transaction that inserts a row and is never committed
+fn uncommitted_transaction(postgresql: &PostgreSQL) -> Result<Child> {
+    let mut child = PsqlBuilder::from(postgresql.settings())
+        .no_psqlrc()
+        .build()
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    if let Some(stdin) = child.stdin.as_mut() {
+        stdin.write_all(b"BEGIN;\nINSERT INTO crash_test VALUES ('uncommitted');\n")?;
+        stdin.flush()?;
+    }
+
+    for _ in 0..100 {
+        let sql = "SELECT count(*) FROM pg_stat_activity WHERE state = 'idle in transaction'";
+        if query(postgresql, sql)? == "1" {
+            return Ok(child);
+        }
+        sleep(Duration::from_millis(100));
+    }
+    panic!("transaction was not started");
+}
+
+async fn crash_and_recover(mode: CrashMode) -> Result<()> {
+    let mut postgresql = PostgreSQL::default();
+    postgresql.setup().await?;
+    postgresql.start().await?;
+
+    query(&postgresql, "CREATE TABLE crash_test (value TEXT)")?;
+    query(&postgresql, "INSERT INTO crash_test VALUES ('committed')")?;
+    let mut transaction = uncommitted_transaction(&postgresql)?;
+
+    postgresql.crash(mode).await?;
+    let recovery_info = postgresql.recover().await?;
+    assert!(recovery_info.redo_start_lsn.is_some());
+    assert!(recovery_info.redo_end_lsn.is_some());
+
+    assert_eq!(
+        "committed",
+        query(&postgresql, "SELECT string_agg(value, ',') FROM crash_test")?
+    );
+
+    let _ = transaction.kill();
+    let _ = transaction.wait();
+    postgresql.stop().await
+}
+
+#[test(tokio::test)]
+async fn test_crash_immediate_shutdown() -> Result<()> {
+    crash_and_recover(CrashMode::ImmediateShutdown).await
+}
+
+#[test(tokio::test)]
+async fn test_crash_kill_postmaster() -> Result<()> {
+    crash_and_recover(CrashMode::KillPostmaster).await
+}
+
+#[test(tokio::test)]
+async fn test_crash_kill_random_backend() -> Result<()> {
+    crash_and_recover(CrashMode::KillRandomBackend).await
+}
+
+#[test(tokio::test)]
+async fn test_crash_requires_started_server() -> Result<()> {
+    let mut postgresql = PostgreSQL::new(LATEST, Default::default());
+    assert!(postgresql
+        .crash(CrashMode::ImmediateShutdown)
+        .await
+        .is_err());
+    Ok(())
+}
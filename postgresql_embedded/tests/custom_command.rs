@@ -0,0 +1,73 @@
+use postgresql_embedded::{CommandBuilder, Error, PostgreSQL, Result};
+use std::ffi::{OsStr, OsString};
+use std::path::PathBuf;
+use test_log::test;
+
+/// Builder for a program that is not known to the crate; the connection settings are applied
+/// by the server through the libpq environment variables
+#[derive(Debug)]
+struct QueryBuilder {
+    program_dir: Option<PathBuf>,
+    query: String,
+}
+
+impl QueryBuilder {
+    fn new<S: Into<String>>(query: S) -> Self {
+        Self {
+            program_dir: None,
+            query: query.into(),
+        }
+    }
+}
+
+impl CommandBuilder for QueryBuilder {
+    fn get_program(&self) -> &'static OsStr {
+        "psql".as_ref()
+    }
+
+    fn get_program_dir(&self) -> &Option<PathBuf> {
+        &self.program_dir
+    }
+
+    fn get_args(&self) -> Vec<OsString> {
+        vec![
+            "--no-psqlrc".into(),
+            "--tuples-only".into(),
+            "--no-align".into(),
+            "--dbname".into(),
+            "postgres".into(),
+            "--command".into(),
+            self.query.clone().into(),
+        ]
+    }
+}
+
+#[test(tokio::test)]
+async fn test_run_command() -> Result<()> {
+    let mut postgresql = PostgreSQL::default();
+    postgresql.setup().await?;
+    postgresql.start().await?;
+
+    let (stdout, _stderr) = postgresql
+        .run_command(QueryBuilder::new(
+            "SELECT current_user || ' ' || current_setting('port')",
+        ))
+        .await?;
+    let expected = format!(
+        "{} {}",
+        postgresql.settings().username,
+        postgresql.settings().port
+    );
+    assert_eq!(expected, stdout.trim());
+
+    let error = postgresql
+        .run_command(QueryBuilder::new("SELECT 1/0"))
+        .await
+        .expect_err("division by zero");
+    assert!(
+        matches!(error, Error::CommandError { stderr, .. } if stderr.contains("division by zero"))
+    );
+
+    postgresql.stop().await?;
+    Ok(())
+}
@@ -0,0 +1,15 @@
+use postgresql_archive::LATEST;
+use postgresql_embedded::{PostgreSQL, Result, Settings};
+use test_log::test;
+
+#[test(tokio::test)]
+async fn test_data_dir_prefix() -> Result<()> {
+    let settings = Settings::default().data_dir_prefix("test_data_dir_prefix");
+    let mut postgresql = PostgreSQL::new(LATEST, settings);
+    postgresql.setup().await?;
+
+    let data_dir = postgresql.settings().data_dir.clone();
+    assert!(data_dir.to_string_lossy().contains("test_data_dir_prefix-"));
+    assert!(data_dir.join("PG_VERSION").exists());
+    Ok(())
+}
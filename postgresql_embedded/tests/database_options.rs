@@ -0,0 +1,114 @@
+use postgresql_embedded::{
+    CreateDatabaseOptions, DefaultPrivileges, PostgreSQL, Result, Revocation, SchemaSpec,
+};
+use test_log::test;
+
+fn options(owner: &str) -> CreateDatabaseOptions {
+    CreateDatabaseOptions {
+        revocations: vec![Revocation {
+            privileges: vec!["CREATE".to_string()],
+            object_type: "SCHEMA".to_string(),
+            object: "public".to_string(),
+            role: "PUBLIC".to_string(),
+        }],
+        schemas: vec![SchemaSpec {
+            name: "app".to_string(),
+            owner: Some(owner.to_string()),
+        }],
+        search_path: vec!["app".to_string(), "public".to_string()],
+        settings: vec![("statement_timeout".to_string(), "5s".to_string())],
+        default_privileges: vec![DefaultPrivileges {
+            for_role: Some(owner.to_string()),
+            schema: Some("app".to_string()),
+            privileges: vec!["SELECT".to_string()],
+            object_type: "TABLES".to_string(),
+            grantee: "reader".to_string(),
+        }],
+    }
+}
+
+async fn single_value(postgresql: &PostgreSQL, database_name: &str, sql: &str) -> Result<String> {
+    Ok(postgresql.run_sql(database_name, sql).await?.rows[0][0].clone())
+}
+
+#[test(tokio::test)]
+async fn test_create_database_with_options() -> Result<()> {
+    let mut postgresql = PostgreSQL::default();
+    postgresql.setup().await?;
+    postgresql.start().await?;
+    postgresql
+        .run_sql("postgres", "CREATE ROLE app; CREATE ROLE reader")
+        .await?;
+
+    postgresql
+        .create_database_with_options("provisioned", &options("app"))
+        .await?;
+
+    assert_eq!(
+        "app",
+        single_value(
+            &postgresql,
+            "provisioned",
+            "SELECT nspowner::regrole::text FROM pg_namespace WHERE nspname = 'app'"
+        )
+        .await?
+    );
+    assert_eq!(
+        "app, public",
+        single_value(&postgresql, "provisioned", "SHOW search_path").await?
+    );
+    assert_eq!(
+        "5s",
+        single_value(&postgresql, "provisioned", "SHOW statement_timeout").await?
+    );
+
+    // The app role can create tables in its schema only
+    postgresql
+        .run_sql("provisioned", "SET ROLE app; CREATE TABLE items (id int)")
+        .await?;
+    assert_eq!(
+        "app",
+        single_value(
+            &postgresql,
+            "provisioned",
+            "SELECT schemaname FROM pg_tables WHERE tablename = 'items'"
+        )
+        .await?
+    );
+    assert!(postgresql
+        .run_sql(
+            "provisioned",
+            "SET ROLE app; CREATE TABLE public.items (id int)",
+        )
+        .await
+        .is_err());
+    assert_eq!(
+        "t",
+        single_value(
+            &postgresql,
+            "provisioned",
+            "SELECT has_table_privilege('reader', 'app.items', 'SELECT')"
+        )
+        .await?
+    );
+
+    postgresql.stop().await?;
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_create_database_with_options_failure() -> Result<()> {
+    let mut postgresql = PostgreSQL::default();
+    postgresql.setup().await?;
+    postgresql.start().await?;
+
+    // The owner role does not exist, so the database is dropped again
+    let result = postgresql
+        .create_database_with_options("provisioned", &options("missing"))
+        .await;
+    assert!(result.is_err());
+    assert!(!postgresql.database_exists("provisioned").await?);
+
+    postgresql.stop().await?;
+    Ok(())
+}
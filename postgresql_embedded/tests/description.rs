@@ -0,0 +1,70 @@
+#![cfg(feature = "serde")]
+
+use postgresql_archive::LATEST;
+use postgresql_embedded::{
+    InstanceDescription, PostgreSQL, Result, Settings, Status, DESCRIPTION_SCHEMA_VERSION,
+};
+use std::fs::read_to_string;
+use std::path::Path;
+use test_log::test;
+
+fn read_description(path: &Path) -> anyhow::Result<InstanceDescription> {
+    Ok(serde_json::from_str(&read_to_string(path)?)?)
+}
+
+#[test(tokio::test)]
+async fn test_description_lifecycle() -> anyhow::Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let settings = Settings {
+        data_dir: temp_dir.path().join("data"),
+        password_file: temp_dir.path().join(".pgpass"),
+        ..Default::default()
+    };
+    let mut postgresql = PostgreSQL::new(LATEST, settings);
+    let path = postgresql.description_path();
+    assert_eq!(temp_dir.path().join("data.description.json"), path);
+
+    postgresql.setup().await?;
+    let description = read_description(&path)?;
+    assert_eq!(DESCRIPTION_SCHEMA_VERSION, description.schema_version);
+    assert_eq!(Status::Stopped, description.status);
+    assert_eq!(None, description.pid);
+    assert_eq!(None, description.password);
+
+    postgresql.start().await?;
+    let started = read_description(&path)?;
+    assert_eq!(Status::Started, started.status);
+    assert_eq!(postgresql.settings().port, started.port);
+    assert!(started.pid.is_some());
+    assert!(started.started_at.is_some());
+    assert!(started.updated_at >= description.updated_at);
+    assert_eq!(None, started.last_backup_at);
+
+    postgresql.stop().await?;
+    let stopped = read_description(&path)?;
+    assert_eq!(Status::Stopped, stopped.status);
+    assert_eq!(None, stopped.pid);
+    assert_eq!(None, stopped.started_at);
+    assert!(stopped.updated_at >= started.updated_at);
+
+    drop(postgresql);
+    assert!(!path.exists());
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_describe_secrets() -> Result<()> {
+    let postgresql = PostgreSQL::new(LATEST, Settings::default());
+    assert_eq!(None, postgresql.describe().password);
+
+    let settings = Settings {
+        describe_secrets: true,
+        ..Default::default()
+    };
+    let postgresql = PostgreSQL::new(LATEST, settings);
+    assert_eq!(
+        Some(postgresql.settings().password.clone()),
+        postgresql.describe().password
+    );
+    Ok(())
+}
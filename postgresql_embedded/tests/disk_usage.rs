@@ -0,0 +1,53 @@
+use postgresql_embedded::{DiskUsage, PostgreSQL};
+use test_log::test;
+
+#[test(tokio::test)]
+async fn test_disk_usage() -> anyhow::Result<()> {
+    let mut postgresql = PostgreSQL::default();
+    postgresql.setup().await?;
+    postgresql.start().await?;
+
+    let database_name = "disk_usage_test";
+    postgresql.create_database(database_name).await?;
+    let database_size = |disk_usage: &DiskUsage| {
+        disk_usage
+            .per_database
+            .iter()
+            .find(|(name, _)| name == database_name)
+            .map(|(_, bytes)| *bytes)
+    };
+
+    let disk_usage = postgresql.disk_usage().await?;
+    assert!(disk_usage.installation_bytes > 0);
+    assert!(disk_usage.data_dir_bytes > 0);
+    assert!(disk_usage.wal_bytes > 0);
+    let initial_size = database_size(&disk_usage).unwrap_or_default();
+    assert!(initial_size > 0);
+
+    postgresql
+        .run_sql(
+            database_name,
+            "CREATE TABLE large_table AS SELECT i, repeat('x', 100) AS value FROM generate_series(1, 50000) AS i",
+        )
+        .await?;
+
+    let disk_usage = postgresql.disk_usage().await?;
+    assert!(database_size(&disk_usage).unwrap_or_default() > initial_size);
+    let largest_relation = &disk_usage.largest_relations[0];
+    assert_eq!(database_name, largest_relation.database);
+    assert_eq!("public", largest_relation.schema);
+    assert_eq!("large_table", largest_relation.name);
+    assert!(largest_relation.bytes > 0);
+
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let watcher = postgresql.low_disk_watcher(u64::MAX, move |event| {
+        let _ = sender.send(event);
+    });
+    let event = receiver.recv_timeout(std::time::Duration::from_secs(30))?;
+    assert_eq!(&postgresql.settings().data_dir, &event.path);
+    assert!(event.available_bytes < event.threshold_bytes);
+    drop(watcher);
+
+    postgresql.stop().await?;
+    Ok(())
+}
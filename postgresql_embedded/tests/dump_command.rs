@@ -40,3 +40,48 @@ async fn dump_command() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test(tokio::test)]
+async fn list_objects() -> anyhow::Result<()> {
+    let mut postgresql = PostgreSQL::default();
+
+    postgresql.setup().await?;
+    postgresql.start().await?;
+    let settings = postgresql.settings();
+
+    let database_name = "test";
+    postgresql.create_database(database_name).await?;
+    postgresql
+        .run_sql(
+            database_name,
+            "CREATE EXTENSION IF NOT EXISTS plpgsql;
+            CREATE TABLE person (id INTEGER PRIMARY KEY, name TEXT);
+            INSERT INTO person SELECT id, 'person ' || id FROM generate_series(1, 100) AS id;
+            ANALYZE person;
+            CREATE VIEW person_names AS SELECT name FROM person;
+            CREATE FUNCTION person_count(min_id INTEGER) RETURNS BIGINT
+                AS 'SELECT count(*) FROM person WHERE id >= min_id' LANGUAGE SQL;",
+        )
+        .await?;
+
+    let objects = PgDumpBuilder::from(settings)
+        .dbname(database_name)
+        .list_objects()?;
+
+    assert_eq!(1, objects.tables.len());
+    let table = &objects.tables[0];
+    assert_eq!(
+        ("public", "person"),
+        (table.schema.as_str(), table.name.as_str())
+    );
+    assert_eq!(100, table.estimated_rows);
+    assert!(table.total_bytes > 0);
+    assert_eq!(1, objects.views.len());
+    assert_eq!("person_names", objects.views[0].name);
+    assert!(!objects.views[0].materialized);
+    assert_eq!(1, objects.functions.len());
+    assert_eq!("person_count", objects.functions[0].name);
+    assert_eq!("min_id integer", objects.functions[0].arguments);
+
+    Ok(())
+}
@@ -0,0 +1,30 @@
+use postgresql_embedded::{EnvFileFormat, PostgreSQL, Result};
+use std::fs::read_to_string;
+use test_log::test;
+
+#[test(tokio::test)]
+async fn test_env_file_rewritten_on_start() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let env_file_path = temp_dir.path().join("postgresql.env");
+    let mut postgresql = PostgreSQL::default();
+    postgresql.setup().await?;
+    postgresql.start().await?;
+    postgresql.write_env_file(&env_file_path, EnvFileFormat::Shell)?;
+
+    let contents = read_to_string(&env_file_path)?;
+    let port = postgresql.settings().port;
+    assert!(contents.contains(&format!("export PGPORT='{port}'")));
+    assert!(!contents.contains("PGPASSWORD"));
+    postgresql.stop().await?;
+
+    // Occupy the previous port so that the restart falls back to a new port; the env file is
+    // rewritten with the new port
+    let _listener = std::net::TcpListener::bind(("0.0.0.0", port))?;
+    postgresql.start().await?;
+    let new_port = postgresql.settings().port;
+    assert_ne!(port, new_port);
+    let contents = read_to_string(&env_file_path)?;
+    assert!(contents.contains(&format!("export PGPORT='{new_port}'")));
+    postgresql.stop().await?;
+    Ok(())
+}
@@ -0,0 +1,49 @@
+use postgresql_embedded::{ephemeral, ephemeral_with, Result, Status, EPHEMERAL_DATABASE};
+use std::net::TcpStream;
+use test_log::test;
+
+#[test(tokio::test)]
+async fn test_ephemeral_removed_on_drop() -> Result<()> {
+    let db = ephemeral().await?;
+    assert_eq!(EPHEMERAL_DATABASE, db.database_name());
+    assert!(db.url().ends_with(&format!("/{EPHEMERAL_DATABASE}")));
+    let result = db.run_sql("SELECT current_database()").await?;
+    assert_eq!(vec![vec![EPHEMERAL_DATABASE.to_string()]], result.rows);
+    let result = db.run_sql("SHOW fsync").await?;
+    assert_eq!(vec![vec!["off".to_string()]], result.rows);
+
+    let settings = db.postgresql().settings().clone();
+    assert_eq!(Status::Started, db.postgresql().status());
+    assert_ne!(0, settings.port);
+    assert!(settings.data_dir.exists());
+
+    drop(db);
+    assert!(!settings.data_dir.exists());
+    assert!(TcpStream::connect(("127.0.0.1", settings.port)).is_err());
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_ephemeral_with_close() -> Result<()> {
+    let db = ephemeral_with(|settings| {
+        settings.max_connections = Some(20);
+    })
+    .await?;
+    let result = db.run_sql("SHOW max_connections").await?;
+    assert_eq!(vec![vec!["20".to_string()]], result.rows);
+
+    let data_dir = db.postgresql().settings().data_dir.clone();
+    db.close().await?;
+    assert!(!data_dir.exists());
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_ephemeral_databases_are_independent() -> Result<()> {
+    let first = ephemeral().await?;
+    let second = ephemeral().await?;
+    assert_ne!(first.url(), second.url());
+    first.run_sql("CREATE TABLE only_first (id int)").await?;
+    assert!(second.run_sql("SELECT * FROM only_first").await.is_err());
+    Ok(())
+}
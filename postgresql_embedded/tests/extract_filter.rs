@@ -0,0 +1,48 @@
+use postgresql_archive::ExtractFilter;
+use postgresql_embedded::{PostgreSQL, Result, Settings};
+use std::path::Path;
+use test_log::test;
+
+/// Get the total size of the files under the path
+fn directory_size(path: &Path) -> Result<u64> {
+    let mut size = 0;
+    for entry in std::fs::read_dir(path)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        size += if metadata.is_dir() {
+            directory_size(&entry.path())?
+        } else {
+            metadata.len()
+        };
+    }
+    Ok(size)
+}
+
+#[test(tokio::test)]
+async fn test_setup_with_extract_filter() -> Result<()> {
+    let mut full = PostgreSQL::default();
+    full.setup().await?;
+    let full_size = directory_size(&full.settings().installation_dir)?;
+
+    // The filtered installation needs its own directory, since installations are reused
+    let temp_dir = tempfile::tempdir()?;
+    let settings = Settings {
+        installation_dir: temp_dir.path().join("installation"),
+        ..Default::default()
+    };
+    let mut postgresql =
+        PostgreSQL::new(*full.version(), settings).with_extract_filter(ExtractFilter::runtime());
+    postgresql.setup().await?;
+
+    let installation_dir = postgresql.settings().installation_dir.clone();
+    for excluded in ["include", "share/doc", "share/locale", "share/man"] {
+        assert!(!installation_dir.join(excluded).exists(), "{excluded}");
+    }
+    assert!(directory_size(&installation_dir)? < full_size);
+
+    postgresql.start().await?;
+    postgresql.create_database("filtered").await?;
+    let result = postgresql.run_sql("filtered", "SELECT 1").await?;
+    assert_eq!(vec![vec!["1".to_string()]], result.rows);
+    postgresql.stop().await
+}
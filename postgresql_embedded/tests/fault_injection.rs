@@ -0,0 +1,94 @@
+#![cfg(feature = "fault-injection")]
+
+use postgresql_commands::psql::PsqlBuilder;
+use postgresql_commands::CommandBuilder;
+use postgresql_embedded::{FaultInjectingProxy, PostgreSQL, Result};
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+use test_log::test;
+
+fn psql(postgresql: &PostgreSQL, proxy: &FaultInjectingProxy) -> Command {
+    PsqlBuilder::new()
+        .program_dir(postgresql.settings().binary_dir())
+        .dbname(proxy.url("postgres"))
+        .no_psqlrc()
+        .tuples_only()
+        .no_align()
+        .variable(("ON_ERROR_STOP", "1"))
+        .build()
+}
+
+/// Run a query through the proxy and return the time it took
+fn timed_query(postgresql: &PostgreSQL, proxy: &FaultInjectingProxy) -> Result<Duration> {
+    let start = Instant::now();
+    let output = psql(postgresql, proxy).arg("--command=SELECT 1").output()?;
+    assert!(output.status.success(), "{output:?}");
+    Ok(start.elapsed())
+}
+
+#[test(tokio::test)]
+async fn test_fault_injecting_proxy() -> Result<()> {
+    let mut postgresql = PostgreSQL::default();
+    postgresql.setup().await?;
+    postgresql.start().await?;
+
+    let proxy = postgresql.fault_injecting_proxy()?;
+    assert_ne!(postgresql.settings().port, proxy.connection_info().port);
+    assert_eq!(proxy.connection_info().port, proxy.settings().port);
+    assert_eq!(proxy.url("postgres"), proxy.settings().url("postgres"));
+
+    // Connecting and querying takes several round trips, each delayed in both directions
+    let latency = Duration::from_millis(100);
+    let baseline = timed_query(&postgresql, &proxy)?;
+    proxy.set_latency(latency, Duration::ZERO);
+    let delayed = timed_query(&postgresql, &proxy)?;
+    assert!(
+        delayed >= baseline + latency * 2,
+        "{baseline:?} {delayed:?}"
+    );
+    proxy.set_latency(Duration::ZERO, Duration::ZERO);
+
+    proxy.drop_new_connections(true);
+    let output = psql(&postgresql, &proxy)
+        .arg("--command=SELECT 1")
+        .output()?;
+    assert!(!output.status.success());
+    proxy.drop_new_connections(false);
+
+    // Reset a connection in the middle of a transaction
+    let mut client = psql(&postgresql, &proxy)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let mut stdin = client.stdin.take().expect("stdin");
+    stdin.write_all(b"BEGIN;\nSELECT pg_sleep(0.5);\n")?;
+    stdin.flush()?;
+    let start = Instant::now();
+    while postgresql
+        .run_sql(
+            "postgres",
+            "SELECT count(*) FROM pg_stat_activity WHERE state = 'active' AND query LIKE 'SELECT pg_sleep%'",
+        )
+        .await?
+        .rows
+        != vec![vec!["1".to_string()]]
+    {
+        assert!(start.elapsed() < Duration::from_secs(10));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    proxy.reset_existing_connections();
+    drop(stdin);
+    let output = client.wait_with_output()?;
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("connection"), "{stderr}");
+
+    // New connections are accepted after a reset
+    timed_query(&postgresql, &proxy)?;
+
+    drop(proxy);
+    postgresql.stop().await?;
+    Ok(())
+}
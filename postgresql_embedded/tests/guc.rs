@@ -0,0 +1,29 @@
+use postgresql_embedded::{Error, PostgreSQL, Result};
+use test_log::test;
+
+#[test(tokio::test)]
+async fn test_get_guc() -> Result<()> {
+    let mut postgresql = PostgreSQL::default();
+    postgresql.setup().await?;
+    postgresql.start().await?;
+
+    let port = postgresql.settings().port.to_string();
+    assert_eq!(port, postgresql.get_guc("port").await?);
+    postgresql.assert_guc("port", &port).await?;
+
+    let error = postgresql
+        .assert_guc("port", "1")
+        .await
+        .expect_err("mismatch");
+    assert!(matches!(error, Error::GucError(_)));
+    assert!(error.to_string().contains(&format!("{port:?}")));
+
+    let error = postgresql
+        .get_guc("no_such_parameter")
+        .await
+        .expect_err("unknown parameter");
+    assert!(matches!(error, Error::GucError(_)));
+
+    postgresql.stop().await?;
+    Ok(())
+}
@@ -0,0 +1,81 @@
+#![cfg(feature = "tokio")]
+
+use anyhow::bail;
+use postgresql_commands::psql::PsqlBuilder;
+use postgresql_commands::CommandBuilder;
+use postgresql_embedded::PostgreSQL;
+use test_log::test;
+
+#[test(tokio::test)]
+async fn test_terminate_idle_in_transaction() -> anyhow::Result<()> {
+    use std::process::Stdio;
+    use tokio::io::AsyncWriteExt;
+
+    let mut postgresql = PostgreSQL::default();
+    postgresql.setup().await?;
+    postgresql.start().await?;
+
+    // Sessions are killed when dropped, so they do not outlive a failed test
+    let mut idle_command = PsqlBuilder::from(postgresql.settings())
+        .dbname("postgres")
+        .build_tokio();
+    let mut idle = idle_command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()?;
+    let mut stdin = idle.stdin.take().expect("stdin");
+    stdin
+        .write_all(b"BEGIN; CREATE TABLE idle_test (id int);\n")
+        .await?;
+    stdin.flush().await?;
+    let mut active_command = PsqlBuilder::from(postgresql.settings())
+        .dbname("postgres")
+        .command("SELECT pg_sleep(60)")
+        .build_tokio();
+    let active = active_command
+        .stdout(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()?;
+
+    let state_sql = "SELECT pid FROM pg_stat_activity WHERE state = 'idle in transaction' \
+        UNION ALL SELECT pid FROM pg_stat_activity WHERE query = 'SELECT pg_sleep(60)'";
+    let mut pids = Vec::new();
+    for _ in 0..100 {
+        pids = postgresql.run_sql("postgres", state_sql).await?.rows;
+        if pids.len() == 2 {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    let [idle_pid, active_pid] = pids.as_slice() else {
+        bail!("sessions did not start: {pids:?}");
+    };
+
+    let terminated = postgresql
+        .terminate_idle_in_transaction(std::time::Duration::from_secs(3600))
+        .await?;
+    assert!(terminated.is_empty());
+
+    let terminated = postgresql
+        .terminate_idle_in_transaction(std::time::Duration::ZERO)
+        .await?;
+    assert_eq!(vec![idle_pid[0].parse::<u32>()?], terminated);
+
+    // The lock held by the terminated session is released
+    drop(stdin);
+    idle.wait().await?;
+    postgresql
+        .run_sql("postgres", "CREATE TABLE idle_test (id int)")
+        .await?;
+    let sql = format!(
+        "SELECT state FROM pg_stat_activity WHERE pid = {}",
+        active_pid[0]
+    );
+    let state = postgresql.run_sql("postgres", sql).await?;
+    assert_eq!(vec![vec!["active".to_string()]], state.rows);
+
+    drop(active);
+    postgresql.stop().await?;
+    Ok(())
+}
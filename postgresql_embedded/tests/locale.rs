@@ -0,0 +1,78 @@
+use postgresql_archive::LATEST;
+use postgresql_embedded::{PostgreSQL, Result, Settings};
+use std::sync::Once;
+use test_log::test;
+
+/// Set a locale and time zone environment that differs from the cluster settings; the
+/// environment is shared by all tests in this file
+fn set_hostile_environment() {
+    static ENVIRONMENT: Once = Once::new();
+    ENVIRONMENT.call_once(|| {
+        std::env::set_var("LC_ALL", "de_DE.UTF-8");
+        std::env::set_var("LANG", "tr_TR.UTF-8");
+        std::env::set_var("LC_MESSAGES", "de_DE.UTF-8");
+        std::env::set_var("TZ", "Pacific/Chatham");
+    });
+}
+
+#[test(tokio::test)]
+async fn test_locale_ignores_process_environment() -> Result<()> {
+    set_hostile_environment();
+
+    let mut postgresql = PostgreSQL::default();
+    postgresql.setup().await?;
+    postgresql.start().await?;
+
+    let locale = postgresql.settings().locale.clone();
+    let result = postgresql
+        .run_sql(
+            "postgres",
+            "SELECT datcollate, datctype, current_setting('lc_messages') FROM pg_database WHERE datname = current_database()",
+        )
+        .await?;
+    assert_eq!(
+        vec![vec![locale.clone(), locale, "C".to_string()]],
+        result.rows
+    );
+
+    let database_name = "locale_test";
+    postgresql.create_database(database_name).await?;
+    assert!(postgresql.database_exists(database_name).await?);
+    let error = postgresql
+        .run_sql(database_name, "SELECT 1/0")
+        .await
+        .expect_err("division by zero");
+    assert!(error.to_string().contains("division by zero"));
+
+    postgresql.stop().await?;
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test(tokio::test)]
+async fn test_initdb_pinned_locale() -> Result<()> {
+    set_hostile_environment();
+
+    let settings = Settings {
+        locale: "C.UTF-8".to_string(),
+        ..Default::default()
+    };
+    let mut postgresql = PostgreSQL::new(LATEST, settings);
+    postgresql.setup().await?;
+    postgresql.start().await?;
+
+    let result = postgresql
+        .run_sql(
+            "postgres",
+            "SELECT datcollate, datctype, current_setting('lc_monetary') FROM pg_database WHERE datname = current_database()",
+        )
+        .await?;
+    let expected: Vec<String> = ["C.UTF-8", "C.UTF-8", "C.UTF-8"]
+        .into_iter()
+        .map(str::to_string)
+        .collect();
+    assert_eq!(vec![expected], result.rows);
+
+    postgresql.stop().await?;
+    Ok(())
+}
@@ -0,0 +1,27 @@
+use postgresql_archive::LATEST;
+use postgresql_embedded::{PostgreSQL, Result, Settings};
+use std::fs::read_to_string;
+use test_log::test;
+
+#[test(tokio::test)]
+async fn test_log_line_prefix() -> Result<()> {
+    let settings = Settings {
+        log_line_prefix: Some("[embedded-test] ".to_string()),
+        ..Default::default()
+    };
+    let mut postgresql = PostgreSQL::new(LATEST, settings);
+    postgresql.setup().await?;
+    postgresql.start().await?;
+    postgresql.stop().await?;
+
+    let start_log = read_to_string(postgresql.settings().data_dir.join("start.log"))?;
+    let log_lines: Vec<&str> = start_log
+        .lines()
+        .filter(|line| line.contains("LOG:"))
+        .collect();
+    assert!(!log_lines.is_empty());
+    for line in log_lines {
+        assert!(line.starts_with("[embedded-test] "), "{line}");
+    }
+    Ok(())
+}
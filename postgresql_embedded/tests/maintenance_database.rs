@@ -0,0 +1,29 @@
+use postgresql_commands::psql::PsqlBuilder;
+use postgresql_commands::CommandBuilder;
+use postgresql_embedded::{PostgreSQL, Result};
+use test_log::test;
+
+#[test(tokio::test)]
+async fn test_maintenance_database_fallback() -> Result<()> {
+    let mut postgresql = PostgreSQL::default();
+    postgresql.setup().await?;
+    postgresql.start().await?;
+
+    let mut psql = PsqlBuilder::from(postgresql.settings())
+        .command("DROP DATABASE postgres")
+        .dbname("template1")
+        .no_psqlrc()
+        .build();
+    let output = psql.output()?;
+    assert!(output.status.success());
+
+    let database_name = "test";
+    assert!(!postgresql.database_exists("postgres").await?);
+    postgresql.create_database(database_name).await?;
+    assert!(postgresql.database_exists(database_name).await?);
+    postgresql.reset_roles(&[]).await?;
+    postgresql.drop_database(database_name).await?;
+    assert!(!postgresql.database_exists(database_name).await?);
+
+    postgresql.stop().await
+}
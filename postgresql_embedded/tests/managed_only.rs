@@ -0,0 +1,68 @@
+use postgresql_archive::LATEST;
+use postgresql_embedded::{Error, PostgreSQL, Result, Settings};
+use std::time::Duration;
+use test_log::test;
+
+/// Get a managed-only view of the server that connects to the port; the view does not own the
+/// data directory
+fn managed_view(postgresql: &PostgreSQL, port: u16) -> PostgreSQL {
+    let settings = Settings {
+        port,
+        managed_only: true,
+        temporary: false,
+        ..postgresql.settings().clone()
+    };
+    PostgreSQL::new(*postgresql.version(), settings)
+}
+
+fn assert_refused<T: std::fmt::Debug>(result: Result<T>) {
+    assert!(
+        matches!(result, Err(Error::UnmanagedServerError(_))),
+        "{result:?}"
+    );
+}
+
+#[test(tokio::test)]
+async fn test_managed_only() -> Result<()> {
+    let mut managed = PostgreSQL::new(LATEST, Settings::default());
+    managed.setup().await?;
+    managed.start().await?;
+    // The other server accepts the same credentials, so only the system identifier differs
+    let other_settings = Settings {
+        password: managed.settings().password.clone(),
+        ..Default::default()
+    };
+    let mut other = PostgreSQL::new(LATEST, other_settings);
+    other.setup().await?;
+    other.start().await?;
+    other.create_database("victim").await?;
+
+    let misdirected = managed_view(&managed, other.settings().port);
+    assert_refused(misdirected.drop_database("victim").await);
+    assert_refused(misdirected.drop_slot("slot").await);
+    assert_refused(misdirected.reset_roles(&[]).await);
+    assert_refused(
+        misdirected
+            .prune_inactive_replication_slots(Duration::ZERO)
+            .await,
+    );
+    assert_refused(
+        misdirected
+            .terminate_idle_in_transaction(Duration::ZERO)
+            .await,
+    );
+    assert_refused(misdirected.drop_tablespace("tablespace", None).await);
+    assert!(other.database_exists("victim").await?);
+
+    // Non-destructive helpers are unaffected
+    assert!(misdirected.database_exists("victim").await?);
+
+    let managed_view = managed_view(&managed, managed.settings().port);
+    managed.create_database("test").await?;
+    managed_view.drop_database("test").await?;
+    assert!(!managed.database_exists("test").await?);
+
+    other.stop().await?;
+    managed.stop().await?;
+    Ok(())
+}
@@ -0,0 +1,78 @@
+use async_trait::async_trait;
+use bytes::Bytes;
+use postgresql_archive::{ArchiveSource, Version, V15, V16};
+use postgresql_embedded::{MatrixOutcome, MatrixRunner, Result, Settings};
+use std::sync::Arc;
+use test_log::test;
+
+/// Archive source without any release, as for a platform no archive is built for
+#[derive(Debug)]
+struct EmptySource;
+
+#[async_trait]
+impl ArchiveSource for EmptySource {
+    async fn get_version(&self, version: &Version) -> postgresql_archive::Result<Version> {
+        Err(postgresql_archive::Error::ReleaseNotFound(
+            version.to_string(),
+        ))
+    }
+
+    async fn fetch(&self, version: &Version, _target: &str) -> postgresql_archive::Result<Bytes> {
+        Err(postgresql_archive::Error::ReleaseNotFound(
+            version.to_string(),
+        ))
+    }
+}
+
+#[test(tokio::test)]
+async fn test_matrix_runner() -> Result<()> {
+    let runner = MatrixRunner::new(vec![V15, V16]).max_concurrency(2);
+
+    let report = runner
+        .run(|postgresql| async move {
+            let server_version_num = postgresql.get_guc("server_version_num").await?;
+            Ok((postgresql.settings().port, server_version_num))
+        })
+        .await;
+
+    assert!(report.is_success(), "{:?}", report.failed());
+    assert!(report.skipped().is_empty());
+    let completed = report.completed();
+    assert_eq!(2, completed.len());
+    let (version, (port, server_version_num)) = completed[0];
+    assert_eq!(15, version.major);
+    assert!(server_version_num.starts_with("15"));
+    let (other_version, (other_port, other_server_version_num)) = completed[1];
+    assert_eq!(16, other_version.major);
+    assert!(other_server_version_num.starts_with("16"));
+    assert_ne!(port, other_port);
+
+    let installed = Settings::default().installed_versions()?;
+    assert!(installed.contains(&version));
+    assert!(installed.contains(&other_version));
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_matrix_runner_skips_missing_versions() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let settings = Settings {
+        installation_dir: temp_dir.path().to_path_buf(),
+        ..Default::default()
+    };
+    let runner = MatrixRunner::new(vec![Version::new(14, None, None), V16])
+        .settings(settings)
+        .with_archive_source(Arc::new(EmptySource));
+
+    let report = runner.run(|_postgresql| async move { Ok(()) }).await;
+
+    assert!(report.is_success());
+    assert!(report.completed().is_empty());
+    assert_eq!(vec![Version::new(14, None, None), V16], report.skipped());
+    assert!(report
+        .results
+        .iter()
+        .all(|result| result.version.is_none()
+            && matches!(result.outcome, MatrixOutcome::Skipped(_))));
+    Ok(())
+}
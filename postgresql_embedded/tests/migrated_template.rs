@@ -0,0 +1,63 @@
+use postgresql_embedded::{MigratedTemplate, PostgreSQL, Result};
+use std::fs;
+use test_log::test;
+
+#[test(tokio::test)]
+async fn test_migrated_template() -> Result<()> {
+    let mut postgresql = PostgreSQL::default();
+    postgresql.setup().await?;
+    postgresql.start().await?;
+
+    let migrations_dir = tempfile::tempdir()?;
+    let migration = migrations_dir.path().join("001_create_person.sql");
+    fs::write(&migration, "CREATE TABLE person (id INTEGER, name TEXT);")?;
+
+    let template = MigratedTemplate::new(migrations_dir.path());
+    let template_name = template.setup(&postgresql).await?;
+    assert!(postgresql.database_exists(&template_name).await?);
+
+    // Setting up again with unchanged migrations reuses the existing template
+    assert_eq!(template_name, template.setup(&postgresql).await?);
+
+    template.create_database(&postgresql, "clone1").await?;
+    assert!(postgresql.database_exists("clone1").await?);
+
+    fs::write(
+        &migration,
+        "CREATE TABLE person (id INTEGER, name TEXT, email TEXT);",
+    )?;
+    let changed_template_name = template.setup(&postgresql).await?;
+    assert_ne!(template_name, changed_template_name);
+    assert!(postgresql.database_exists(&changed_template_name).await?);
+    assert!(!postgresql.database_exists(&template_name).await?);
+
+    template.create_database(&postgresql, "clone2").await?;
+    assert!(postgresql.database_exists("clone2").await?);
+
+    postgresql.stop().await
+}
+
+#[test(tokio::test)]
+async fn test_migrated_template_invalid_migration() -> Result<()> {
+    let mut postgresql = PostgreSQL::default();
+    postgresql.setup().await?;
+    postgresql.start().await?;
+
+    let migrations_dir = tempfile::tempdir()?;
+    fs::write(
+        migrations_dir.path().join("001_invalid.sql"),
+        "CREATE TABLE person (id INTEGER;",
+    )?;
+
+    let template = MigratedTemplate::new(migrations_dir.path());
+    assert!(template.setup(&postgresql).await.is_err());
+    let template_name = template.template_name()?;
+    assert!(!postgresql.database_exists(&template_name).await?);
+    assert!(
+        !postgresql
+            .database_exists(format!("{template_name}_staging"))
+            .await?
+    );
+
+    postgresql.stop().await
+}
@@ -0,0 +1,37 @@
+use postgresql_embedded::{PostgreSQL, Result};
+use test_log::test;
+
+const FUNCTION: &str = "CREATE FUNCTION noisy() RETURNS integer LANGUAGE plpgsql AS $$
+BEGIN
+    RAISE NOTICE 'noisy called with % rows', 42;
+    RAISE WARNING 'this is a warning';
+    RETURN 1;
+END
+$$";
+
+#[test(tokio::test)]
+async fn test_query_with_notices() -> Result<()> {
+    let mut postgresql = PostgreSQL::default();
+    postgresql.setup().await?;
+    postgresql.start().await?;
+    postgresql.run_sql("postgres", FUNCTION).await?;
+
+    let result = postgresql
+        .query_with_notices("postgres", "SELECT noisy()")
+        .await?;
+
+    assert_eq!(vec![vec!["1".to_string()]], result.rows);
+    assert_eq!(
+        vec![
+            "noisy called with 42 rows".to_string(),
+            "this is a warning".to_string()
+        ],
+        result.notices
+    );
+    assert!(postgresql
+        .run_sql("postgres", "SELECT noisy()")
+        .await?
+        .notices
+        .is_empty());
+    postgresql.stop().await
+}
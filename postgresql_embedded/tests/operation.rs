@@ -0,0 +1,127 @@
+use postgresql_embedded::{
+    Operation, OperationOutcome, OperationOutput, PostgreSQL, Result, Status,
+};
+use test_log::test;
+
+async fn apply(postgresql: &mut PostgreSQL, operation: Operation) -> OperationOutput {
+    let outcome: OperationOutcome = postgresql.apply(operation.clone()).await;
+    assert_eq!(operation, outcome.operation);
+    match outcome.output {
+        Ok(output) => output,
+        Err(error) => panic!("{operation:?} failed: {error}"),
+    }
+}
+
+#[test(tokio::test)]
+async fn test_lifecycle_through_apply() -> Result<()> {
+    let mut postgresql = PostgreSQL::default();
+    let database_name = "operation_test".to_string();
+    let backup_dir = tempfile::tempdir()?;
+    let backup_path = backup_dir.path().join("operation_test.dump");
+
+    assert_eq!(
+        OperationOutput::Completed,
+        apply(&mut postgresql, Operation::Setup).await
+    );
+    assert_eq!(Status::Stopped, postgresql.status());
+    assert_eq!(
+        OperationOutput::Completed,
+        apply(&mut postgresql, Operation::Start).await
+    );
+    assert_eq!(Status::Started, postgresql.status());
+
+    apply(
+        &mut postgresql,
+        Operation::CreateDatabase {
+            database_name: database_name.clone(),
+        },
+    )
+    .await;
+    let exists = apply(
+        &mut postgresql,
+        Operation::DatabaseExists {
+            database_name: database_name.clone(),
+        },
+    )
+    .await;
+    assert_eq!(
+        OperationOutput::Exists(postgresql.database_exists(&database_name).await?),
+        exists
+    );
+    assert_eq!(OperationOutput::Exists(true), exists);
+
+    let sql = "CREATE TABLE t AS SELECT 1 AS id; SELECT id FROM t".to_string();
+    let rows = apply(
+        &mut postgresql,
+        Operation::RunSql {
+            database_name: database_name.clone(),
+            sql,
+        },
+    )
+    .await;
+    let result = postgresql
+        .run_sql(&database_name, "SELECT id FROM t")
+        .await?;
+    assert_eq!(OperationOutput::Rows(result.rows), rows);
+
+    apply(
+        &mut postgresql,
+        Operation::Backup {
+            database_name: database_name.clone(),
+            path: backup_path.clone(),
+        },
+    )
+    .await;
+    assert!(backup_path.exists());
+
+    apply(
+        &mut postgresql,
+        Operation::CreatePhysicalSlot {
+            slot_name: "operation_slot".to_string(),
+        },
+    )
+    .await;
+    apply(
+        &mut postgresql,
+        Operation::DropSlot {
+            slot_name: "operation_slot".to_string(),
+        },
+    )
+    .await;
+
+    postgresql
+        .run_sql("postgres", "CREATE ROLE operation_role")
+        .await?;
+    let roles = apply(&mut postgresql, Operation::ResetRoles { keep: vec![] }).await;
+    assert_eq!(
+        OperationOutput::Roles(vec!["operation_role".to_string()]),
+        roles
+    );
+    assert_eq!(Vec::<String>::new(), postgresql.reset_roles(&[]).await?);
+
+    apply(
+        &mut postgresql,
+        Operation::DropDatabase {
+            database_name: database_name.clone(),
+        },
+    )
+    .await;
+    assert!(!postgresql.database_exists(&database_name).await?);
+
+    let outcome = postgresql
+        .apply(Operation::RunSql {
+            database_name: database_name.clone(),
+            sql: "SELECT 1".to_string(),
+        })
+        .await;
+    assert!(!outcome.is_success());
+    let error = postgresql
+        .run_sql(&database_name, "SELECT 1")
+        .await
+        .expect_err("database was dropped");
+    assert_eq!(Err(error.to_string()), outcome.output);
+
+    apply(&mut postgresql, Operation::Stop).await;
+    assert_eq!(Status::Stopped, postgresql.status());
+    Ok(())
+}
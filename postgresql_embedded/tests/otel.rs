@@ -0,0 +1,207 @@
+#![cfg(feature = "otel")]
+
+use postgresql_archive::LATEST;
+use postgresql_embedded::{PostgreSQL, Result, Settings};
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Instrument, Subscriber};
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::{Layer, Registry};
+
+/// Span recorded by the [Exporter]
+#[derive(Clone, Debug, Default)]
+struct ExportedSpan {
+    id: u64,
+    name: String,
+    parent: Option<String>,
+    attributes: HashMap<String, String>,
+    events: Vec<HashMap<String, String>>,
+}
+
+/// In-memory span exporter recording the spans, their attributes and their events
+#[derive(Clone, Debug, Default)]
+struct Exporter {
+    spans: Arc<Mutex<Vec<ExportedSpan>>>,
+}
+
+impl Exporter {
+    /// Get the spans with the name, in the order they were created
+    fn spans(&self, name: &str) -> Vec<ExportedSpan> {
+        self.spans
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|span| span.name == name)
+            .cloned()
+            .collect()
+    }
+
+    fn clear(&self) {
+        self.spans.lock().unwrap().clear();
+    }
+}
+
+/// Visitor collecting the fields of a span or event
+#[derive(Default)]
+struct Fields(HashMap<String, String>);
+
+impl Visit for Fields {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), value.to_string());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.0
+            .insert(field.name().to_string(), format!("{value:?}"));
+    }
+}
+
+impl<S: Subscriber + for<'a> LookupSpan<'a>> Layer<S> for Exporter {
+    fn on_new_span(&self, attributes: &Attributes<'_>, id: &Id, context: Context<'_, S>) {
+        let parent = context
+            .span(id)
+            .and_then(|span| span.parent())
+            .map(|parent| parent.name().to_string());
+        let mut fields = Fields::default();
+        attributes.record(&mut fields);
+        self.spans.lock().unwrap().push(ExportedSpan {
+            id: id.into_u64(),
+            name: attributes.metadata().name().to_string(),
+            parent,
+            attributes: fields.0,
+            events: Vec::new(),
+        });
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, _context: Context<'_, S>) {
+        let mut fields = Fields::default();
+        values.record(&mut fields);
+        let mut spans = self.spans.lock().unwrap();
+        if let Some(span) = spans.iter_mut().rev().find(|span| span.id == id.into_u64()) {
+            span.attributes.extend(fields.0);
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, context: Context<'_, S>) {
+        let Some(current) = context.event_span(event) else {
+            return;
+        };
+        let mut fields = Fields::default();
+        event.record(&mut fields);
+        let mut spans = self.spans.lock().unwrap();
+        if let Some(span) = spans
+            .iter_mut()
+            .rev()
+            .find(|span| span.id == current.id().into_u64())
+        {
+            span.events.push(fields.0);
+        }
+    }
+}
+
+/// Get the phases recorded as events of the span
+fn phases(span: &ExportedSpan) -> Vec<String> {
+    span.events
+        .iter()
+        .filter_map(|event| event.get("postgresql.phase").cloned())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_otel_setup_spans() -> Result<()> {
+    let exporter = Exporter::default();
+    let _guard = tracing::subscriber::set_default(Registry::default().with(exporter.clone()));
+    let temp_dir = tempfile::tempdir()?;
+    let settings = |data_dir: &str| Settings {
+        installation_dir: temp_dir.path().join("installation"),
+        data_dir: temp_dir.path().join(data_dir),
+        ..Default::default()
+    };
+
+    // Uncached setup: the archive is downloaded and extracted
+    let mut postgresql = PostgreSQL::new(LATEST, settings("uncached"));
+    postgresql
+        .setup()
+        .instrument(tracing::info_span!("test"))
+        .await?;
+
+    let setup = exporter.spans("postgresql.setup");
+    assert_eq!(1, setup.len());
+    let setup = &setup[0];
+    assert_eq!(Some("test".to_string()), setup.parent);
+    assert_eq!("postgresql", setup.attributes["db.system"]);
+    assert_eq!("false", setup.attributes["postgresql.cache_hit"]);
+    assert_eq!(
+        postgresql.version().to_string(),
+        setup.attributes["postgresql.version"]
+    );
+    assert!(setup.attributes["postgresql.data_dir"].ends_with("uncached"));
+
+    let install = &exporter.spans("postgresql.install")[0];
+    assert_eq!(Some("postgresql.setup".to_string()), install.parent);
+    assert_eq!("false", install.attributes["postgresql.cache_hit"]);
+    let bytes: u64 = install.attributes["postgresql.download.bytes"]
+        .parse()
+        .expect("downloaded bytes");
+    assert!(bytes > 0);
+    assert_eq!(vec!["download", "extract"], phases(install));
+
+    let initialize = &exporter.spans("postgresql.initialize")[0];
+    assert_eq!(Some("postgresql.setup".to_string()), initialize.parent);
+    assert_eq!(vec!["initialize"], phases(initialize));
+
+    postgresql
+        .start()
+        .instrument(tracing::info_span!("test"))
+        .await?;
+    let start = &exporter.spans("postgresql.start")[0];
+    assert_eq!(Some("test".to_string()), start.parent);
+    assert_eq!(
+        postgresql.settings().port.to_string(),
+        start.attributes["server.port"]
+    );
+    assert_eq!(vec!["start"], phases(start));
+    postgresql.stop().await?;
+    assert_eq!(vec!["stop"], phases(&exporter.spans("postgresql.stop")[0]));
+
+    // Cached setup: the installation directory is reused
+    exporter.clear();
+    let mut postgresql = PostgreSQL::new(LATEST, settings("cached"));
+    postgresql
+        .setup()
+        .instrument(tracing::info_span!("test"))
+        .await?;
+
+    let setup = &exporter.spans("postgresql.setup")[0];
+    assert_eq!(Some("test".to_string()), setup.parent);
+    assert_eq!("true", setup.attributes["postgresql.cache_hit"]);
+    let install = &exporter.spans("postgresql.install")[0];
+    assert_eq!(Some("postgresql.setup".to_string()), install.parent);
+    assert_eq!("true", install.attributes["postgresql.cache_hit"]);
+    assert!(!install.attributes.contains_key("postgresql.download.bytes"));
+    assert!(phases(install).is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_otel_background_span() -> Result<()> {
+    let exporter = Exporter::default();
+    let _guard = tracing::subscriber::set_default(Registry::default().with(exporter.clone()));
+    let postgresql = PostgreSQL::default();
+
+    let watcher =
+        tracing::info_span!("test").in_scope(|| postgresql.low_disk_watcher(0, |_event| {}));
+    drop(watcher);
+
+    let background = &exporter.spans("postgresql.background")[0];
+    assert_eq!(Some("test".to_string()), background.parent);
+    assert_eq!(
+        "postgresql.low_disk_watcher",
+        background.attributes["otel.name"]
+    );
+    Ok(())
+}
@@ -0,0 +1,69 @@
+use postgresql_embedded::{
+    CompressionMethod, CompressionSpec, PhysicalBackupWorkflow, PostgreSQL, Result,
+};
+use test_log::test;
+
+#[test(tokio::test)]
+async fn test_physical_backup_workflow() -> Result<()> {
+    let mut postgresql = PostgreSQL::default();
+    postgresql.setup().await?;
+    postgresql.start().await?;
+
+    let temp_dir = tempfile::tempdir()?;
+    let directory = temp_dir.path().join("backup");
+    let report = PhysicalBackupWorkflow::new(postgresql.settings())
+        .backup_to(&directory)
+        .verify_after(true)
+        .execute()?;
+
+    assert_eq!(directory, report.path);
+    assert!(directory.join("PG_VERSION").exists());
+    let verify_result = report.verify_result.expect("verify result");
+    assert!(verify_result.success);
+    assert!(verify_result.errors.is_empty());
+
+    postgresql.stop().await?;
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_physical_backup_workflow_server_compression() -> Result<()> {
+    let mut postgresql = PostgreSQL::default();
+    postgresql.setup().await?;
+    postgresql.start().await?;
+
+    let temp_dir = tempfile::tempdir()?;
+    let directory = temp_dir.path().join("backup");
+    let report = PhysicalBackupWorkflow::new(postgresql.settings())
+        .backup_to(&directory)
+        .compress(CompressionSpec::server(CompressionMethod::Gzip).level(1))
+        .verify_after(true)
+        .execute()?;
+
+    assert!(directory.join("PG_VERSION").exists());
+    assert_eq!(
+        Some(true),
+        report.verify_result.map(|result| result.success)
+    );
+
+    postgresql.stop().await?;
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_physical_backup_workflow_without_verify() -> Result<()> {
+    let mut postgresql = PostgreSQL::default();
+    postgresql.setup().await?;
+    postgresql.start().await?;
+
+    let temp_dir = tempfile::tempdir()?;
+    let directory = temp_dir.path().join("backup");
+    let report = PhysicalBackupWorkflow::new(postgresql.settings())
+        .backup_to(&directory)
+        .execute()?;
+
+    assert_eq!(None, report.verify_result);
+
+    postgresql.stop().await?;
+    Ok(())
+}
@@ -0,0 +1,34 @@
+use postgresql_archive::LATEST;
+use postgresql_embedded::{PostgreSQL, Result, Settings};
+use test_log::test;
+
+#[test(tokio::test)]
+async fn test_preload_libraries() -> Result<()> {
+    let settings = Settings {
+        preload_libraries: vec!["pg_stat_statements".to_string()],
+        ..Default::default()
+    };
+    let mut postgresql = PostgreSQL::new(LATEST, settings);
+    postgresql.setup().await?;
+    postgresql.start().await?;
+
+    let result = postgresql
+        .run_sql("postgres", "SHOW shared_preload_libraries")
+        .await?;
+    assert_eq!(vec![vec!["pg_stat_statements".to_string()]], result.rows);
+
+    let result = postgresql
+        .run_sql("postgres", "SELECT count(*) > 0 FROM pg_stat_statements")
+        .await?;
+    assert_eq!(vec![vec!["t".to_string()]], result.rows);
+
+    // Restarting the server keeps a single entry and the existing extension
+    postgresql.stop().await?;
+    postgresql.start().await?;
+    let result = postgresql
+        .run_sql("postgres", "SHOW shared_preload_libraries")
+        .await?;
+    assert_eq!(vec![vec!["pg_stat_statements".to_string()]], result.rows);
+
+    postgresql.stop().await
+}
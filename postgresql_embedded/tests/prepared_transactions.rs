@@ -0,0 +1,85 @@
+use postgresql_archive::LATEST;
+use postgresql_embedded::{PostgreSQL, Result, Settings};
+use test_log::test;
+
+const PREPARE: &str = "BEGIN; CREATE TABLE prepared (id int); PREPARE TRANSACTION 'prepared';";
+
+#[test(tokio::test)]
+async fn test_max_prepared_transactions() -> Result<()> {
+    let settings = Settings {
+        max_prepared_transactions: Some(10),
+        max_connections: Some(50),
+        max_locks_per_transaction: Some(128),
+        ..Default::default()
+    };
+    let mut postgresql = PostgreSQL::new(LATEST, settings);
+    postgresql.setup().await?;
+    postgresql.start().await?;
+    assert_eq!("10", postgresql.get_guc("max_prepared_transactions").await?);
+    assert_eq!("50", postgresql.get_guc("max_connections").await?);
+    assert_eq!(
+        "128",
+        postgresql.get_guc("max_locks_per_transaction").await?
+    );
+
+    postgresql.run_sql("postgres", PREPARE).await?;
+    let rows = postgresql
+        .run_sql("postgres", "SELECT gid FROM pg_prepared_xacts")
+        .await?
+        .rows;
+    assert_eq!(vec![vec!["prepared".to_string()]], rows);
+    postgresql
+        .run_sql("postgres", "COMMIT PREPARED 'prepared'")
+        .await?;
+
+    postgresql.stop().await?;
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_prepared_transactions_disabled() -> Result<()> {
+    let mut postgresql = PostgreSQL::default();
+    postgresql.setup().await?;
+    postgresql.start().await?;
+
+    assert!(postgresql.run_sql("postgres", PREPARE).await.is_err());
+
+    postgresql.stop().await?;
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_rollback_all_prepared() -> Result<()> {
+    let settings = Settings {
+        max_prepared_transactions: Some(10),
+        ..Default::default()
+    };
+    let mut postgresql = PostgreSQL::new(LATEST, settings);
+    postgresql.setup().await?;
+    postgresql.start().await?;
+    assert!(postgresql
+        .orphaned_prepared_transactions()
+        .await?
+        .is_empty());
+
+    postgresql.create_database("app").await?;
+    postgresql.run_sql("app", PREPARE).await?;
+    let transactions = postgresql.orphaned_prepared_transactions().await?;
+    assert_eq!(1, transactions.len());
+    assert_eq!("prepared", transactions[0].gid);
+    assert_eq!("app", transactions[0].database);
+
+    // Rolling back discards the table created by the prepared transaction
+    assert_eq!(transactions, postgresql.rollback_all_prepared().await?);
+    assert!(postgresql
+        .orphaned_prepared_transactions()
+        .await?
+        .is_empty());
+    let tables = postgresql
+        .run_sql("app", "SELECT to_regclass('prepared') IS NULL")
+        .await?;
+    assert_eq!(vec![vec!["t".to_string()]], tables.rows);
+
+    postgresql.stop().await?;
+    Ok(())
+}
@@ -0,0 +1,84 @@
+use postgresql_archive::LATEST;
+use postgresql_embedded::{
+    CancellationToken, PostgreSQL, Readiness, ReadinessProbe, Result, Settings,
+};
+use std::fs::{write, OpenOptions};
+use std::io::Write;
+use std::net::TcpListener;
+use test_log::test;
+
+/// Get a view of the server that uses the readiness probe; the view does not own the data
+/// directory
+fn with_probe(postgresql: &PostgreSQL, readiness_probe: ReadinessProbe) -> PostgreSQL {
+    let settings = Settings {
+        readiness_probe,
+        temporary: false,
+        ..postgresql.settings().clone()
+    };
+    PostgreSQL::new(*postgresql.version(), settings)
+}
+
+#[test(tokio::test)]
+async fn test_ping_accepting() -> Result<()> {
+    let mut postgresql = PostgreSQL::new(LATEST, Settings::default());
+    postgresql.setup().await?;
+    postgresql.start().await?;
+
+    assert_eq!(Readiness::Accepting, postgresql.ping().await?);
+    let process = with_probe(&postgresql, ReadinessProbe::Process);
+    assert_eq!(Readiness::Accepting, process.ping().await?);
+
+    postgresql.stop().await?;
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_ping_absent() -> Result<()> {
+    let mut postgresql = PostgreSQL::new(LATEST, Settings::default());
+    postgresql.setup().await?;
+    let port = TcpListener::bind(("127.0.0.1", 0))?.local_addr()?.port();
+    let settings = Settings {
+        port,
+        ..postgresql.settings().clone()
+    };
+    let postgresql = PostgreSQL::new(*postgresql.version(), settings);
+
+    assert_eq!(Readiness::Absent, postgresql.ping().await?);
+    let process = with_probe(&postgresql, ReadinessProbe::Process);
+    assert_eq!(Readiness::Absent, process.ping().await?);
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_ping_rejecting() -> Result<()> {
+    let mut primary = PostgreSQL::new(LATEST, Settings::default());
+    primary.setup().await?;
+    primary.start().await?;
+
+    // A standby without hot standby stays in recovery and rejects connections with 57P03
+    let temp_dir = tempfile::tempdir()?;
+    let data_dir = temp_dir.path().join("standby");
+    primary
+        .base_backup(&data_dir, |_| {}, &CancellationToken::new())
+        .await?;
+    write(data_dir.join("standby.signal"), "")?;
+    let mut auto_conf = OpenOptions::new()
+        .append(true)
+        .open(data_dir.join("postgresql.auto.conf"))?;
+    writeln!(auto_conf, "hot_standby = off")?;
+    let settings = Settings {
+        data_dir,
+        port: 0,
+        ..primary.settings().clone()
+    };
+    let mut standby = PostgreSQL::new(*primary.version(), settings);
+    standby.start().await?;
+
+    assert_eq!(Readiness::Rejecting, standby.ping().await?);
+    let process = with_probe(&standby, ReadinessProbe::Process);
+    assert_eq!(Readiness::Rejecting, process.ping().await?);
+
+    standby.stop().await?;
+    primary.stop().await?;
+    Ok(())
+}
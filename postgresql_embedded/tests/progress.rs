@@ -0,0 +1,149 @@
+use postgresql_embedded::{
+    BaseBackupProgress, CancellationToken, DumpProgress, Error, PostgreSQL, ProgressEvent,
+    RestoreProgress, RestoreProgressEvent, Result,
+};
+use std::sync::{Arc, Mutex};
+use test_log::test;
+
+#[test(tokio::test)]
+async fn test_restore_with_progress() -> Result<()> {
+    let mut postgresql = PostgreSQL::default();
+    postgresql.setup().await?;
+    postgresql.start().await?;
+
+    let temp_dir = tempfile::tempdir()?;
+    let archive = temp_dir.path().join("source.dump");
+    postgresql.create_database("source").await?;
+    postgresql
+        .run_sql(
+            "source",
+            "CREATE TABLE items (id integer PRIMARY KEY, name text);
+            INSERT INTO items SELECT id, 'item ' || id FROM generate_series(1, 100) AS id;
+            CREATE INDEX items_name ON items (name);",
+        )
+        .await?;
+    let dump_events = Arc::new(Mutex::new(Vec::new()));
+    let dump_sink = dump_events.clone();
+    postgresql
+        .dump(
+            "source",
+            &archive,
+            move |event| dump_sink.lock().unwrap().push(event),
+            &CancellationToken::new(),
+        )
+        .await?;
+    let dumped: Vec<DumpProgress> = dump_events
+        .lock()
+        .unwrap()
+        .iter()
+        .filter_map(|event| match event {
+            ProgressEvent::Dump(progress) => Some(progress.clone()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        vec![DumpProgress {
+            tables_dumped: 1,
+            table: Some("public.items".to_string()),
+        }],
+        dumped
+    );
+
+    postgresql.create_database("target").await?;
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let sink = events.clone();
+    postgresql
+        .restore(
+            "target",
+            &archive,
+            None,
+            move |event| sink.lock().unwrap().push(event),
+            &CancellationToken::new(),
+        )
+        .await?;
+
+    let progress: Vec<RestoreProgress> = events
+        .lock()
+        .unwrap()
+        .iter()
+        .filter_map(|event| match event {
+            ProgressEvent::Restore(progress) => Some(progress.clone()),
+            _ => None,
+        })
+        .collect();
+    let last = progress.last().expect("restore progress");
+    assert_eq!(Some(last.processed), last.total);
+    assert_eq!(Some(100), last.percentage());
+    assert_eq!(
+        Some(RestoreProgressEvent::CreatingObject {
+            type_: "INDEX".to_string(),
+            schema: "public".to_string(),
+            name: "items_name".to_string(),
+        }),
+        last.event
+    );
+
+    let result = postgresql
+        .run_sql("target", "SELECT count(*) FROM items")
+        .await?;
+    assert_eq!(vec![vec!["100".to_string()]], result.rows);
+
+    postgresql.stop().await?;
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_base_backup_with_progress() -> Result<()> {
+    let mut postgresql = PostgreSQL::default();
+    postgresql.setup().await?;
+    postgresql.start().await?;
+
+    let temp_dir = tempfile::tempdir()?;
+    let directory = temp_dir.path().join("backup");
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let sink = events.clone();
+    postgresql
+        .base_backup(
+            &directory,
+            move |event| sink.lock().unwrap().push(event),
+            &CancellationToken::new(),
+        )
+        .await?;
+
+    let progress: Vec<BaseBackupProgress> = events
+        .lock()
+        .unwrap()
+        .iter()
+        .filter_map(|event| match event {
+            ProgressEvent::BaseBackup(progress) => Some(*progress),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(Some(100), progress.last().map(|value| value.percentage));
+    assert!(directory.join("PG_VERSION").exists());
+
+    postgresql.stop().await?;
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_base_backup_cancelled() -> Result<()> {
+    let mut postgresql = PostgreSQL::default();
+    postgresql.setup().await?;
+    postgresql.start().await?;
+
+    let temp_dir = tempfile::tempdir()?;
+    let directory = temp_dir.path().join("backup");
+    let cancel = CancellationToken::new();
+    let token = cancel.clone();
+    let error = postgresql
+        .base_backup(&directory, move |_| token.cancel(), &cancel)
+        .await
+        .expect_err("backup cancelled");
+
+    assert!(matches!(error, Error::CancelledError(_)));
+    assert!(!directory.exists());
+
+    postgresql.stop().await?;
+    Ok(())
+}
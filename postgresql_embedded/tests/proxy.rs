@@ -0,0 +1,41 @@
+use postgresql_commands::psql::PsqlBuilder;
+use postgresql_commands::CommandBuilder;
+use postgresql_embedded::{PostgreSQL, Result};
+use test_log::test;
+
+#[test(tokio::test)]
+async fn test_statement_logging_proxy() -> Result<()> {
+    let mut postgresql = PostgreSQL::default();
+    postgresql.setup().await?;
+    postgresql.start().await?;
+
+    let proxy = postgresql.statement_logging_proxy()?;
+    assert_ne!(postgresql.settings().port, proxy.connection_info().port);
+    for sql in ["SELECT 1", "SELECT 'proxy'"] {
+        let mut psql = PsqlBuilder::new()
+            .program_dir(postgresql.settings().binary_dir())
+            .dbname(proxy.url("postgres"))
+            .command(sql)
+            .no_psqlrc()
+            .tuples_only()
+            .no_align()
+            .build();
+        let output = psql.output()?;
+        assert!(output.status.success());
+    }
+
+    let statements = proxy.statements();
+    assert_eq!(
+        vec![(1, "SELECT 1"), (2, "SELECT 'proxy'")],
+        statements
+            .iter()
+            .map(|statement| (statement.connection, statement.sql.as_str()))
+            .collect::<Vec<_>>()
+    );
+    assert!(statements[0].timestamp <= statements[1].timestamp);
+    assert!(proxy.diagnostics().contains("connection 2: SELECT 'proxy'"));
+    drop(proxy);
+
+    postgresql.stop().await?;
+    Ok(())
+}
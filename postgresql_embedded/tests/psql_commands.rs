@@ -0,0 +1,55 @@
+use postgresql_commands::psql::PsqlBuilder;
+use postgresql_commands::CommandBuilder;
+use postgresql_embedded::{PostgreSQL, Result};
+use test_log::test;
+
+#[test(tokio::test)]
+async fn test_psql_commands_single_transaction() -> Result<()> {
+    let mut postgresql = PostgreSQL::default();
+    postgresql.setup().await?;
+    postgresql.start().await?;
+    postgresql
+        .run_sql(
+            "postgres",
+            "CREATE TABLE session_test (id integer PRIMARY KEY)",
+        )
+        .await?;
+
+    let mut psql = PsqlBuilder::from(postgresql.settings())
+        .commands([
+            "INSERT INTO session_test VALUES (1)",
+            "INSERT INTO session_test VALUES (2)",
+            "INSERT INTO session_test VALUES (1)",
+        ])
+        .variable(("ON_ERROR_STOP", "1"))
+        .single_transaction()
+        .no_psqlrc()
+        .build();
+    let output = psql.output()?;
+    assert!(!output.status.success());
+
+    let result = postgresql
+        .run_sql("postgres", "SELECT count(*) FROM session_test")
+        .await?;
+    assert_eq!(vec![vec!["0".to_string()]], result.rows);
+
+    let mut psql = PsqlBuilder::from(postgresql.settings())
+        .command(r"\set ON_ERROR_STOP on")
+        .command("INSERT INTO session_test VALUES (3)")
+        .command("INSERT INTO session_test VALUES (4)")
+        .single_transaction()
+        .no_psqlrc()
+        .build();
+    assert!(psql.output()?.status.success());
+
+    let result = postgresql
+        .run_sql("postgres", "SELECT id FROM session_test ORDER BY id")
+        .await?;
+    assert_eq!(
+        vec![vec!["3".to_string()], vec!["4".to_string()]],
+        result.rows
+    );
+
+    postgresql.stop().await?;
+    Ok(())
+}
@@ -0,0 +1,143 @@
+use postgresql_commands::pg_restore::PgRestoreBuilder;
+use postgresql_commands::CommandBuilder;
+use postgresql_embedded::{PostgreSQL, QualifiedName, Result};
+use test_log::test;
+
+#[test(tokio::test)]
+async fn test_backup_and_restore_qualified_table() -> Result<()> {
+    let mut postgresql = PostgreSQL::default();
+    postgresql.setup().await?;
+    postgresql.start().await?;
+
+    let table = QualifiedName::qualified("my schema", "Weird.Table");
+    postgresql.create_database("source").await?;
+    postgresql
+        .run_sql(
+            "source",
+            r#"CREATE SCHEMA "my schema";
+            CREATE TABLE "my schema"."Weird.Table" (id integer PRIMARY KEY, "Value" text);
+            CREATE TABLE "my schema".weird (id integer);
+            INSERT INTO "my schema"."Weird.Table" VALUES (1, 'one'), (2, 'two');"#,
+        )
+        .await?;
+
+    let temp_dir = tempfile::tempdir()?;
+    let archive = temp_dir.path().join("table.dump");
+    postgresql.backup_table("source", &table, &archive).await?;
+
+    postgresql.create_database("target").await?;
+    postgresql
+        .run_sql("target", r#"CREATE SCHEMA "my schema""#)
+        .await?;
+    postgresql
+        .restore_table("target", &table, &archive, None)
+        .await?;
+
+    let result = postgresql
+        .run_sql(
+            "target",
+            r#"SELECT id, "Value" FROM "my schema"."Weird.Table" ORDER BY id"#,
+        )
+        .await?;
+    assert_eq!(
+        vec![
+            vec!["1".to_string(), "one".to_string()],
+            vec!["2".to_string(), "two".to_string()]
+        ],
+        result.rows
+    );
+    let tables = postgresql.list_tables("target", Some("my schema")).await?;
+    assert_eq!(1, tables.len());
+
+    let missing = QualifiedName::qualified("my schema", "weird.table");
+    let result = postgresql
+        .backup_table("source", &missing, temp_dir.path().join("missing.dump"))
+        .await;
+    assert!(result.is_err());
+    let result = postgresql
+        .restore_table("target", &missing, &archive, None)
+        .await;
+    assert!(result.is_err());
+
+    postgresql.stop().await?;
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_backup_tables_from_query() -> Result<()> {
+    let mut postgresql = PostgreSQL::default();
+    postgresql.setup().await?;
+    postgresql.start().await?;
+
+    postgresql.create_database("source").await?;
+    postgresql
+        .run_sql(
+            "source",
+            "CREATE SCHEMA sales;
+            CREATE TABLE sales.orders (id integer);
+            CREATE TABLE sales.items (id integer);
+            CREATE TABLE sales.archived_orders (id integer);
+            CREATE TABLE public.orders (id integer);",
+        )
+        .await?;
+
+    let temp_dir = tempfile::tempdir()?;
+    let archive = temp_dir.path().join("tables.dump");
+    let tables = postgresql
+        .backup_tables_from_query(
+            "source",
+            "SELECT table_schema, table_name FROM information_schema.tables \
+            WHERE table_schema = 'sales' AND table_name NOT LIKE 'archived%' \
+            ORDER BY table_name",
+            &archive,
+        )
+        .await?;
+    assert_eq!(
+        vec![
+            QualifiedName::qualified("sales", "items"),
+            QualifiedName::qualified("sales", "orders"),
+        ],
+        tables
+    );
+
+    let output = PgRestoreBuilder::from(postgresql.settings())
+        .list()
+        .archive(&archive)
+        .build()
+        .output()?;
+    let list = String::from_utf8_lossy(&output.stdout);
+    let mut archived: Vec<(String, String)> = list
+        .lines()
+        .filter(|line| !line.starts_with(';'))
+        .filter_map(|line| {
+            let mut fields = line
+                .split_whitespace()
+                .skip_while(|field| *field != "TABLE");
+            fields.next()?;
+            let schema = fields.next()?;
+            let name = fields.next()?;
+            (schema != "DATA").then(|| (schema.to_string(), name.to_string()))
+        })
+        .collect();
+    archived.sort();
+    assert_eq!(
+        vec![
+            ("sales".to_string(), "items".to_string()),
+            ("sales".to_string(), "orders".to_string()),
+        ],
+        archived
+    );
+
+    let result = postgresql
+        .backup_tables_from_query(
+            "source",
+            "SELECT table_schema, table_name FROM information_schema.tables WHERE false",
+            temp_dir.path().join("empty.dump"),
+        )
+        .await;
+    assert!(result.is_err());
+    assert!(!temp_dir.path().join("empty.dump").exists());
+
+    postgresql.stop().await?;
+    Ok(())
+}
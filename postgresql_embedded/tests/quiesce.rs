@@ -0,0 +1,58 @@
+use flate2::read::GzDecoder;
+use postgresql_archive::LATEST;
+use postgresql_embedded::{PostgreSQL, Result, Settings, Status};
+use std::fs::File;
+use test_log::test;
+
+#[test(tokio::test)]
+async fn test_stop_and_quiesce() -> Result<()> {
+    let mut postgresql = PostgreSQL::new(LATEST, Settings::default());
+    postgresql.setup().await?;
+    postgresql.start().await?;
+    postgresql.create_database("test").await?;
+    postgresql
+        .run_sql(
+            "test",
+            "CREATE TABLE items AS SELECT generate_series(1, 100) AS id",
+        )
+        .await?;
+
+    let temp_dir = tempfile::tempdir()?;
+    let archive = temp_dir.path().join("data.tar.gz");
+    let expected_data_dir = postgresql.settings().data_dir.clone();
+    {
+        let data_dir = postgresql.stop_and_quiesce().await?;
+        assert_eq!(expected_data_dir, data_dir.path());
+        assert!(!data_dir.path().join("postmaster.pid").exists());
+        data_dir.archive_to(&archive)?;
+    }
+    assert_eq!(Status::Stopped, postgresql.status());
+
+    // Quiescing a stopped server only checks it
+    let _ = postgresql.stop_and_quiesce().await?;
+
+    let restored_dir = temp_dir.path().join("restored");
+    tar::Archive::new(GzDecoder::new(File::open(&archive)?)).unpack(&restored_dir)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&restored_dir, std::fs::Permissions::from_mode(0o700))?;
+    }
+    let settings = Settings {
+        data_dir: restored_dir,
+        password: postgresql.settings().password.clone(),
+        password_file: temp_dir.path().join(".pgpass"),
+        temporary: false,
+        ..Default::default()
+    };
+    let mut restored = PostgreSQL::new(*postgresql.version(), settings);
+    restored.setup().await?;
+    restored.start().await?;
+    let rows = restored
+        .run_sql("test", "SELECT count(*) FROM items")
+        .await?
+        .rows;
+    assert_eq!(vec![vec!["100".to_string()]], rows);
+    restored.stop().await?;
+    Ok(())
+}
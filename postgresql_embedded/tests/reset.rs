@@ -0,0 +1,169 @@
+use postgresql_commands::psql::PsqlBuilder;
+use postgresql_commands::CommandBuilder;
+use postgresql_embedded::{PostgreSQL, QualifiedName, ResetOptions, Result};
+use test_log::test;
+
+/// Install an extension with a configuration table marked with `pg_extension_config_dump`
+fn install_config_extension(postgresql: &PostgreSQL) -> Result<()> {
+    let binary_dir = postgresql.settings().binary_dir();
+    let extension_dir = binary_dir
+        .parent()
+        .expect("installation directory")
+        .join("share")
+        .join("extension");
+    std::fs::write(
+        extension_dir.join("reset_data_test.control"),
+        "default_version = '1.0'\nrelocatable = true\n",
+    )?;
+    std::fs::write(
+        extension_dir.join("reset_data_test--1.0.sql"),
+        "CREATE TABLE reset_data_test_config (key text PRIMARY KEY, value text);
+        SELECT pg_catalog.pg_extension_config_dump('reset_data_test_config', '');",
+    )?;
+    Ok(())
+}
+
+async fn count(postgresql: &PostgreSQL, table: &str) -> Result<String> {
+    let result = postgresql
+        .run_sql("app", format!("SELECT count(*) FROM {table}"))
+        .await?;
+    Ok(result.rows[0][0].clone())
+}
+
+#[test(tokio::test)]
+async fn test_reset_data() -> Result<()> {
+    let mut postgresql = PostgreSQL::default();
+    postgresql.setup().await?;
+    postgresql.start().await?;
+    install_config_extension(&postgresql)?;
+    postgresql.create_database("app").await?;
+    postgresql
+        .run_sql(
+            "app",
+            "CREATE EXTENSION reset_data_test;
+            INSERT INTO reset_data_test_config VALUES ('theme', 'dark');
+            CREATE TABLE regions (id integer GENERATED ALWAYS AS IDENTITY PRIMARY KEY, name text);
+            CREATE TABLE accounts (id serial PRIMARY KEY, region_id integer REFERENCES regions);
+            CREATE TABLE orders (id bigserial PRIMARY KEY, account_id integer REFERENCES accounts);
+            CREATE TABLE audit (id serial PRIMARY KEY, account_id integer REFERENCES accounts);
+            CREATE TABLE events (id integer GENERATED ALWAYS AS IDENTITY PRIMARY KEY);
+            INSERT INTO regions (name) SELECT 'region ' || id FROM generate_series(1, 3) AS id;
+            INSERT INTO accounts (region_id) SELECT id % 3 + 1 FROM generate_series(1, 10) AS id;
+            INSERT INTO orders (account_id) SELECT id % 10 + 1 FROM generate_series(1, 50) AS id;
+            INSERT INTO audit (account_id) SELECT id FROM generate_series(1, 5) AS id;
+            INSERT INTO events SELECT FROM generate_series(1, 7);",
+        )
+        .await?;
+
+    let options = ResetOptions {
+        exclude_tables: vec![QualifiedName::new("audit")],
+        ..Default::default()
+    };
+    let report = postgresql.reset_data("app", &options).await?;
+
+    let qualified = |names: &[&str]| -> Vec<QualifiedName> {
+        names
+            .iter()
+            .map(|name| QualifiedName::qualified("public", *name))
+            .collect()
+    };
+    assert_eq!(qualified(&["events", "orders"]), report.truncated);
+    // The audit table references the accounts, which reference the regions
+    assert_eq!(qualified(&["accounts", "regions"]), report.deleted);
+    assert_eq!(
+        qualified(&["audit", "reset_data_test_config"]),
+        report.preserved
+    );
+    for table in ["regions", "accounts", "orders", "events"] {
+        assert_eq!("0", count(&postgresql, table).await?, "{table}");
+    }
+    assert_eq!("5", count(&postgresql, "audit").await?);
+    assert_eq!("1", count(&postgresql, "reset_data_test_config").await?);
+
+    // The identities of the reset tables are restarted, unlike those of the preserved tables
+    let ids = postgresql
+        .run_sql(
+            "app",
+            "INSERT INTO regions (name) VALUES ('new');
+            INSERT INTO accounts (region_id) VALUES (1);
+            INSERT INTO orders (account_id) VALUES (1);
+            INSERT INTO events DEFAULT VALUES;
+            INSERT INTO audit (account_id) VALUES (1);
+            SELECT (SELECT max(id) FROM regions), (SELECT max(id) FROM accounts),
+                (SELECT max(id) FROM orders), (SELECT max(id) FROM events),
+                (SELECT max(id) FROM audit)",
+        )
+        .await?;
+    assert_eq!(
+        vec![vec!["1", "1", "1", "1", "6"]
+            .into_iter()
+            .map(str::to_string)
+            .collect::<Vec<_>>()],
+        ids.rows
+    );
+
+    // Extension configuration tables are reset when included
+    let options = ResetOptions {
+        include_extension_config: true,
+        restart_identities: false,
+        ..Default::default()
+    };
+    let report = postgresql.reset_data("app", &options).await?;
+    assert!(report.deleted.is_empty());
+    assert_eq!("0", count(&postgresql, "audit").await?);
+    assert_eq!("0", count(&postgresql, "reset_data_test_config").await?);
+    postgresql.stop().await
+}
+
+#[test(tokio::test)]
+async fn test_reset_roles() -> Result<()> {
+    let mut postgresql = PostgreSQL::default();
+    postgresql.setup().await?;
+    postgresql.start().await?;
+
+    let database_name = "test";
+    postgresql.create_database(database_name).await?;
+    let statements = [
+        "CREATE ROLE owner1 LOGIN",
+        "CREATE ROLE owner2",
+        "CREATE ROLE keeper",
+        "CREATE TABLE owned1 (id INTEGER)",
+        "ALTER TABLE owned1 OWNER TO owner1",
+        "CREATE SCHEMA owned2 AUTHORIZATION owner2",
+        "GRANT SELECT ON owned1 TO owner2",
+    ];
+    for statement in statements {
+        let mut psql = PsqlBuilder::from(postgresql.settings())
+            .command(statement)
+            .dbname(database_name)
+            .no_psqlrc()
+            .build();
+        let output = psql.output()?;
+        assert!(output.status.success());
+    }
+
+    let dropped_roles = postgresql.reset_roles(&["keeper"]).await?;
+    assert_eq!(
+        vec!["owner1".to_string(), "owner2".to_string()],
+        dropped_roles
+    );
+
+    let mut psql = PsqlBuilder::from(postgresql.settings())
+        .command("SELECT string_agg(rolname, ',' ORDER BY rolname) FROM pg_roles WHERE rolname NOT LIKE 'pg\\_%'")
+        .no_psqlrc()
+        .tuples_only()
+        .no_align()
+        .build();
+    let output = psql.output()?;
+    assert_eq!(
+        "keeper,postgres",
+        String::from_utf8_lossy(&output.stdout).trim()
+    );
+
+    assert!(postgresql
+        .reset_roles(&[])
+        .await?
+        .contains(&"keeper".to_string()));
+    assert!(postgresql.reset_roles(&[]).await?.is_empty());
+    Ok(())
+}
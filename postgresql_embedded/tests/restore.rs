@@ -0,0 +1,180 @@
+use postgresql_commands::toc::reorder;
+use postgresql_embedded::{PostgreSQL, QualifiedName, ReorderStrategy, Result, Toc};
+use std::path::Path;
+use test_log::test;
+
+const ITEMS: &str = "CREATE TABLE items (id integer PRIMARY KEY, name text);
+    CREATE VIEW item_names AS SELECT name FROM items;
+    INSERT INTO items SELECT id, 'item ' || id FROM generate_series(1, 10) AS id;";
+
+/// Back up a database with the items table and view to the path
+async fn backup_items(postgresql: &PostgreSQL, path: &Path) -> Result<()> {
+    postgresql.create_database("source").await?;
+    postgresql.run_sql("source", ITEMS).await?;
+    postgresql.backup("source", path).await
+}
+
+/// Get the number of items and the number of item names in the database
+async fn item_counts(postgresql: &PostgreSQL, database_name: &str) -> Result<Vec<Vec<String>>> {
+    Ok(postgresql
+        .run_sql(
+            database_name,
+            "SELECT (SELECT count(*) FROM items), (SELECT count(*) FROM item_names)",
+        )
+        .await?
+        .rows)
+}
+
+#[test(tokio::test)]
+async fn test_restore_database_absent() -> Result<()> {
+    let mut postgresql = PostgreSQL::default();
+    postgresql.setup().await?;
+    postgresql.start().await?;
+    let temp_dir = tempfile::tempdir()?;
+    let archive = temp_dir.path().join("source.dump");
+    backup_items(&postgresql, &archive).await?;
+
+    assert!(!postgresql.database_exists("restored").await?);
+    let report = postgresql
+        .restore_database("restored", &archive, None)
+        .await?;
+
+    assert_eq!(None, report);
+    assert!(postgresql.database_exists("restored").await?);
+    assert_eq!(
+        vec![vec!["10".to_string(), "10".to_string()]],
+        item_counts(&postgresql, "restored").await?
+    );
+
+    // A failed restore does not leave the new database behind
+    let missing = temp_dir.path().join("missing.dump");
+    assert!(postgresql
+        .restore_database("failed", &missing, None)
+        .await
+        .is_err());
+    assert!(!postgresql.database_exists("failed").await?);
+    postgresql.stop().await
+}
+
+#[test(tokio::test)]
+async fn test_restore_database_exists() -> Result<()> {
+    let mut postgresql = PostgreSQL::default();
+    postgresql.setup().await?;
+    postgresql.start().await?;
+    let temp_dir = tempfile::tempdir()?;
+    let archive = temp_dir.path().join("source.dump");
+    backup_items(&postgresql, &archive).await?;
+
+    // The existing objects of the archive are replaced; other objects are kept
+    postgresql.create_database("restored").await?;
+    postgresql
+        .run_sql(
+            "restored",
+            "CREATE TABLE items (id integer PRIMARY KEY, name text);
+            CREATE VIEW item_names AS SELECT name FROM items;
+            INSERT INTO items VALUES (100, 'stale');
+            CREATE TABLE unrelated (id integer)",
+        )
+        .await?;
+    postgresql
+        .restore_database("restored", &archive, None)
+        .await?;
+
+    assert_eq!(
+        vec![vec!["10".to_string(), "10".to_string()]],
+        item_counts(&postgresql, "restored").await?
+    );
+    let stale = postgresql
+        .run_sql("restored", "SELECT count(*) FROM items WHERE id = 100")
+        .await?;
+    assert_eq!(vec![vec!["0".to_string()]], stale.rows);
+    let unrelated = postgresql
+        .run_sql("restored", "SELECT to_regclass('unrelated') IS NOT NULL")
+        .await?;
+    assert_eq!(vec![vec!["t".to_string()]], unrelated.rows);
+
+    // Restoring again into the restored database is idempotent
+    postgresql
+        .restore_database("restored", &archive, None)
+        .await?;
+    assert_eq!(
+        vec![vec!["10".to_string(), "10".to_string()]],
+        item_counts(&postgresql, "restored").await?
+    );
+    postgresql.stop().await
+}
+
+#[test(tokio::test)]
+async fn test_restore_database_ordered() -> Result<()> {
+    let mut postgresql = PostgreSQL::default();
+    postgresql.setup().await?;
+    postgresql.start().await?;
+    postgresql.create_database("source").await?;
+    postgresql
+        .run_sql(
+            "source",
+            "CREATE TABLE accounts (id integer PRIMARY KEY);
+            CREATE TABLE z_events (id integer PRIMARY KEY, account_id integer REFERENCES accounts, payload text);
+            INSERT INTO accounts SELECT generate_series(1, 10);
+            INSERT INTO z_events SELECT id, id % 10 + 1, repeat('x', 100) FROM generate_series(1, 10000) AS id;",
+        )
+        .await?;
+    let temp_dir = tempfile::tempdir()?;
+    let archive = temp_dir.path().join("source.dump");
+    postgresql.backup("source", &archive).await?;
+
+    let toc = postgresql.list_archive(&archive).await?;
+    let data_tables = |toc: &Toc| -> Vec<String> {
+        toc.entries
+            .iter()
+            .filter_map(|entry| entry.data_table().map(|table| table.name))
+            .collect()
+    };
+    assert_eq!(vec!["accounts", "z_events"], data_tables(&toc));
+    let foreign_key = toc
+        .entries
+        .iter()
+        .find(|entry| entry.object_type == "FK CONSTRAINT")
+        .expect("foreign key");
+    assert!(!foreign_key.dependencies.is_empty());
+
+    let sizes = postgresql.table_sizes("source").await?;
+    assert!(
+        sizes[&QualifiedName::qualified("public", "z_events")]
+            > sizes[&QualifiedName::qualified("public", "accounts")]
+    );
+    let strategy = ReorderStrategy::LargestDataFirst(sizes);
+    let reordered = reorder(&toc, &strategy).expect("reordered");
+    assert_eq!(vec!["z_events", "accounts"], data_tables(&reordered));
+    // No entry is placed before an entry it depends on
+    for (position, entry) in reordered.entries.iter().enumerate() {
+        for dependency in &entry.dependencies {
+            if let Some(dependency_position) = reordered
+                .entries
+                .iter()
+                .position(|entry| entry.dump_id == *dependency)
+            {
+                assert!(dependency_position < position);
+            }
+        }
+    }
+
+    postgresql
+        .restore_database_ordered("restored", &archive, &strategy, None)
+        .await?;
+    // The use-list file is created in the private files directory and removed after the restore
+    let mut files_dir = postgresql.settings().data_dir.clone().into_os_string();
+    files_dir.push(".files");
+    assert!(!std::path::Path::new(&files_dir).exists());
+    let counts = postgresql
+        .run_sql(
+            "restored",
+            "SELECT (SELECT count(*) FROM accounts), (SELECT count(*) FROM z_events)",
+        )
+        .await?;
+    assert_eq!(
+        vec![vec!["10".to_string(), "10000".to_string()]],
+        counts.rows
+    );
+    postgresql.stop().await
+}
@@ -0,0 +1,103 @@
+use postgresql_archive::LATEST;
+use postgresql_commands::pg_basebackup::PgBaseBackupBuilder;
+use postgresql_commands::{CommandBuilder, CommandExecutor};
+use postgresql_embedded::{PostgreSQL, Result, Settings, Status};
+use std::thread::sleep;
+use std::time::Duration;
+use test_log::test;
+
+async fn values(postgresql: &PostgreSQL) -> Result<Vec<String>> {
+    let result = postgresql
+        .run_sql("postgres", "SELECT value FROM rewind_test ORDER BY value")
+        .await?;
+    Ok(result.rows.into_iter().flatten().collect())
+}
+
+#[test(tokio::test)]
+async fn test_rewind_from() -> anyhow::Result<()> {
+    // The target must retain the WAL since the last checkpoint before the servers diverged
+    let settings = Settings {
+        wal_keep_size: Some("128MB".to_string()),
+        ..Default::default()
+    };
+    let mut target = PostgreSQL::new(LATEST, settings);
+    target.setup().await?;
+    target.start().await?;
+    target
+        .run_sql("postgres", "ALTER SYSTEM SET wal_log_hints = on")
+        .await?;
+    target.stop().await?;
+    target.start().await?;
+    target
+        .run_sql(
+            "postgres",
+            "CREATE TABLE rewind_test (value text); INSERT INTO rewind_test VALUES ('common')",
+        )
+        .await?;
+
+    // Create the source as a standby of the target and promote it
+    let source_dir = tempfile::tempdir()?;
+    let source_data_dir = source_dir.path().join("data");
+    PgBaseBackupBuilder::from(target.settings())
+        .username("postgres")
+        .pgdata(&source_data_dir)
+        .write_recovery_conf()
+        .build()
+        .execute()?;
+    let settings = Settings {
+        data_dir: source_data_dir,
+        password: target.settings().password.clone(),
+        ..Default::default()
+    };
+    let mut source = PostgreSQL::new(LATEST, settings);
+    source.setup().await?;
+    source.start().await?;
+    source.run_sql("postgres", "SELECT pg_promote()").await?;
+
+    // Diverge the servers
+    source
+        .run_sql("postgres", "INSERT INTO rewind_test VALUES ('source')")
+        .await?;
+    target
+        .run_sql("postgres", "INSERT INTO rewind_test VALUES ('diverged')")
+        .await?;
+
+    target.rewind_from(&source).await?;
+    assert_eq!(Status::Stopped, target.status());
+    target.start().await?;
+
+    let expected = vec!["common".to_string(), "source".to_string()];
+    for _ in 0..100 {
+        if values(&target).await? == expected {
+            break;
+        }
+        sleep(Duration::from_millis(100));
+    }
+    assert_eq!(expected, values(&target).await?);
+    let in_recovery = target
+        .run_sql("postgres", "SELECT pg_is_in_recovery()")
+        .await?;
+    assert_eq!(vec![vec!["t".to_string()]], in_recovery.rows);
+
+    target.stop().await?;
+    source.stop().await?;
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_rewind_from_unrelated_server() -> anyhow::Result<()> {
+    let mut target = PostgreSQL::default();
+    target.setup().await?;
+    let mut source = PostgreSQL::default();
+    source.setup().await?;
+    source.start().await?;
+
+    let error = target
+        .rewind_from(&source)
+        .await
+        .expect_err("servers are unrelated");
+    assert!(error.to_string().contains("incompatible"));
+
+    source.stop().await?;
+    Ok(())
+}
@@ -0,0 +1,33 @@
+use postgresql_embedded::{PostgreSQL, Result};
+use test_log::test;
+
+const SCHEMA: &str = "CREATE SCHEMA inventory;
+    CREATE TABLE inventory.items (id integer PRIMARY KEY, name text NOT NULL);
+    CREATE INDEX items_name ON inventory.items (name);
+    CREATE VIEW inventory.item_names AS SELECT name FROM inventory.items;
+    COMMENT ON TABLE inventory.items IS 'Items in stock';";
+
+/// Take a snapshot of the schema in a new instance
+async fn snapshot() -> Result<String> {
+    let mut postgresql = PostgreSQL::default();
+    postgresql.setup().await?;
+    postgresql.start().await?;
+    postgresql.create_database("test").await?;
+    postgresql.run_sql("test", SCHEMA).await?;
+    let snapshot = postgresql.schema_snapshot("test").await?;
+    postgresql.stop().await?;
+    Ok(snapshot)
+}
+
+#[test(tokio::test)]
+async fn test_schema_snapshot() -> Result<()> {
+    let first = snapshot().await?;
+    let second = snapshot().await?;
+    assert_eq!(first, second);
+
+    assert!(first.contains("CREATE TABLE inventory.items"));
+    assert!(first.contains("COMMENT ON TABLE inventory.items IS 'Items in stock';"));
+    assert!(!first.contains("Dumped from database version"));
+    assert!(!first.contains("OWNER TO"));
+    Ok(())
+}
@@ -0,0 +1,219 @@
+use postgresql_archive::LATEST;
+use postgresql_embedded::{Error, PostgreSQL, Result, Seed, Settings, Status};
+use std::fs::write;
+use std::path::Path;
+use test_log::test;
+
+fn settings(temp_dir: &Path, seed: Seed) -> Settings {
+    Settings {
+        data_dir: temp_dir.join("data"),
+        password_file: temp_dir.join(".pgpass"),
+        temporary: false,
+        seed: Some(seed),
+        ..Default::default()
+    }
+}
+
+async fn count_seeded(postgresql: &PostgreSQL) -> Result<Vec<Vec<String>>> {
+    Ok(postgresql
+        .run_sql("postgres", "SELECT count(*) FROM seeded")
+        .await?
+        .rows)
+}
+
+#[test(tokio::test)]
+async fn test_seed_sql_string() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let seed = Seed::SqlString(
+        "CREATE TABLE seeded (id int); INSERT INTO seeded VALUES (1), (2);".to_string(),
+    );
+    let mut postgresql = PostgreSQL::new(LATEST, settings(temp_dir.path(), seed));
+    postgresql.setup().await?;
+    postgresql.start().await?;
+    assert_eq!(
+        vec![vec!["2".to_string()]],
+        count_seeded(&postgresql).await?
+    );
+
+    // The seed is applied once; applying it again would fail to create the table
+    postgresql.stop().await?;
+    postgresql.start().await?;
+    assert_eq!(
+        vec![vec!["2".to_string()]],
+        count_seeded(&postgresql).await?
+    );
+
+    postgresql.stop().await?;
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_seed_sql_file() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let seed_file = temp_dir.path().join("seed.sql");
+    write(
+        &seed_file,
+        "CREATE TABLE seeded (id int);\nINSERT INTO seeded VALUES (1);\n",
+    )?;
+    let mut postgresql =
+        PostgreSQL::new(LATEST, settings(temp_dir.path(), Seed::SqlFile(seed_file)));
+    postgresql.setup().await?;
+    postgresql.start().await?;
+    assert_eq!(
+        vec![vec!["1".to_string()]],
+        count_seeded(&postgresql).await?
+    );
+
+    postgresql.stop().await?;
+    postgresql.start().await?;
+    assert_eq!(
+        vec![vec!["1".to_string()]],
+        count_seeded(&postgresql).await?
+    );
+
+    postgresql.stop().await?;
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_seed_dump_archive() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let archive = temp_dir.path().join("seed.dump");
+    {
+        let mut source = PostgreSQL::new(LATEST, Settings::default());
+        source.setup().await?;
+        source.start().await?;
+        source.create_database("source").await?;
+        source
+            .run_sql(
+                "source",
+                "CREATE TABLE seeded (id int); INSERT INTO seeded VALUES (1), (2), (3);",
+            )
+            .await?;
+        source.backup("source", &archive).await?;
+        source.stop().await?;
+    }
+
+    let mut postgresql = PostgreSQL::new(
+        LATEST,
+        settings(temp_dir.path(), Seed::DumpArchive(archive)),
+    );
+    postgresql.setup().await?;
+    postgresql.start().await?;
+    assert_eq!(
+        vec![vec!["3".to_string()]],
+        count_seeded(&postgresql).await?
+    );
+
+    postgresql.stop().await?;
+    postgresql.start().await?;
+    assert_eq!(
+        vec![vec!["3".to_string()]],
+        count_seeded(&postgresql).await?
+    );
+
+    postgresql.stop().await?;
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_seed_failure() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let seed = Seed::SqlString("CREATE TABLE seeded (id int); SELECT 1 / 0;".to_string());
+    let settings = settings(temp_dir.path(), seed);
+    let mut postgresql = PostgreSQL::new(LATEST, settings.clone());
+    postgresql.setup().await?;
+    let error = postgresql.start().await.expect_err("seed failure");
+    assert!(matches!(error, Error::SeedError(_)));
+    assert_eq!(Status::Stopped, postgresql.status());
+
+    // The seed is applied in a single transaction, so nothing of it is left behind
+    let settings = Settings {
+        seed: None,
+        temporary: true,
+        ..settings
+    };
+    let mut postgresql = PostgreSQL::new(LATEST, settings);
+    postgresql.setup().await?;
+    postgresql.start().await?;
+    let rows = postgresql
+        .run_sql(
+            "postgres",
+            "SELECT count(*) FROM pg_tables WHERE tablename = 'seeded'",
+        )
+        .await?
+        .rows;
+    assert_eq!(vec![vec!["0".to_string()]], rows);
+
+    postgresql.stop().await?;
+    Ok(())
+}
+
+/// Check that the schema of the dump was applied to the maintenance database
+async fn assert_dump_schema(postgresql: &PostgreSQL) -> Result<()> {
+    let columns: Vec<String> = postgresql
+        .describe_table("postgres", "public.items")
+        .await?
+        .columns
+        .into_iter()
+        .map(|column| column.name)
+        .collect();
+    assert_eq!(vec!["id", "name"], columns);
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_with_dump_sql() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let schema = temp_dir.path().join("schema.sql");
+    write(
+        &schema,
+        "CREATE TABLE items (id int PRIMARY KEY, name text);\n",
+    )?;
+
+    let mut postgresql = PostgreSQL::default().with_dump(&schema)?;
+    assert_eq!(Some(Seed::SqlFile(schema)), postgresql.settings().seed);
+    postgresql.setup().await?;
+    postgresql.start().await?;
+    assert_dump_schema(&postgresql).await?;
+
+    postgresql.stop().await?;
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_with_dump_archive() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    // The format is detected from the contents rather than the extension
+    let archive = temp_dir.path().join("schema.sql");
+    {
+        let mut source = PostgreSQL::default();
+        source.setup().await?;
+        source.start().await?;
+        source.create_database("source").await?;
+        source
+            .run_sql(
+                "source",
+                "CREATE TABLE items (id int PRIMARY KEY, name text);",
+            )
+            .await?;
+        source.backup("source", &archive).await?;
+        source.stop().await?;
+    }
+
+    let mut postgresql = PostgreSQL::default().with_dump(&archive)?;
+    assert_eq!(Some(Seed::DumpArchive(archive)), postgresql.settings().seed);
+    postgresql.setup().await?;
+    postgresql.start().await?;
+    assert_dump_schema(&postgresql).await?;
+
+    postgresql.stop().await?;
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_with_dump_missing() {
+    let temp_dir = tempfile::tempdir().expect("temp dir");
+    let result = PostgreSQL::default().with_dump(temp_dir.path().join("missing.sql"));
+    assert!(matches!(result, Err(Error::IoError(_))));
+}
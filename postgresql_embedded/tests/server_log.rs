@@ -0,0 +1,110 @@
+use postgresql_embedded::{PostgreSQL, Result, Settings};
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::{Layer, Registry};
+
+/// Event recorded by the [Recorder]
+#[derive(Clone, Debug)]
+struct RecordedEvent {
+    level: Level,
+    severity: String,
+    message: String,
+}
+
+/// Layer recording the events of the server log
+#[derive(Clone, Debug, Default)]
+struct Recorder {
+    events: Arc<Mutex<Vec<RecordedEvent>>>,
+}
+
+/// Visitor collecting the severity and message of an event
+#[derive(Default)]
+struct Fields {
+    severity: String,
+    message: String,
+}
+
+impl Visit for Fields {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "severity" {
+            self.severity = value.to_string();
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        if field.name() == "message" {
+            self.message = format!("{value:?}");
+        }
+    }
+}
+
+impl<S: Subscriber> Layer<S> for Recorder {
+    fn on_event(&self, event: &Event<'_>, _context: Context<'_, S>) {
+        if event.metadata().target() != "postgresql_embedded::server" {
+            return;
+        }
+        let mut fields = Fields::default();
+        event.record(&mut fields);
+        self.events.lock().unwrap().push(RecordedEvent {
+            level: *event.metadata().level(),
+            severity: fields.severity,
+            message: fields.message,
+        });
+    }
+}
+
+#[tokio::test]
+async fn test_trace_server_logs() -> Result<()> {
+    let recorder = Recorder::default();
+    let _guard = tracing::subscriber::set_default(Registry::default().with(recorder.clone()));
+    let settings = Settings {
+        trace_server_logs: true,
+        ..Default::default()
+    };
+    let mut postgresql = PostgreSQL::from(settings);
+    postgresql.setup().await?;
+    postgresql.start().await?;
+
+    postgresql
+        .run_sql(
+            "postgres",
+            "DO $$ BEGIN RAISE WARNING 'forwarded to tracing'; END $$",
+        )
+        .await?;
+    postgresql.stop().await?;
+
+    let events = recorder.events.lock().unwrap().clone();
+    let warning = events
+        .iter()
+        .find(|event| event.message.contains("forwarded to tracing"))
+        .expect("warning event");
+    assert_eq!(Level::WARN, warning.level);
+    assert_eq!("WARNING", warning.severity);
+    // The server logs its startup and shutdown at the LOG severity
+    assert!(events
+        .iter()
+        .any(|event| event.level == Level::INFO && event.severity == "LOG"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_server_logs_not_traced_by_default() -> Result<()> {
+    let recorder = Recorder::default();
+    let _guard = tracing::subscriber::set_default(Registry::default().with(recorder.clone()));
+    let mut postgresql = PostgreSQL::default();
+    postgresql.setup().await?;
+    postgresql.start().await?;
+    postgresql
+        .run_sql(
+            "postgres",
+            "DO $$ BEGIN RAISE WARNING 'not forwarded'; END $$",
+        )
+        .await?;
+    postgresql.stop().await?;
+
+    assert!(recorder.events.lock().unwrap().is_empty());
+    Ok(())
+}
@@ -0,0 +1,170 @@
+use postgresql_embedded::{BatchOptions, PostgreSQL, Result, Settings};
+use test_log::test;
+
+#[test(tokio::test)]
+async fn test_session_pool_reuses_sessions() -> Result<()> {
+    let mut postgresql = PostgreSQL::default();
+    postgresql.setup().await?;
+    postgresql.start().await?;
+    let before = postgresql.session_pool_stats();
+
+    for value in 0..20 {
+        let result = postgresql
+            .run_sql("postgres", format!("SELECT {value}, 'row;{value}'"))
+            .await?;
+        assert_eq!(
+            vec![vec![value.to_string(), format!("row;{value}")]],
+            result.rows
+        );
+    }
+
+    let stats = postgresql.session_pool_stats();
+    assert!(stats.spawned <= postgresql.settings().psql_sessions as u64);
+    assert!(stats.reused >= before.reused + 19);
+    assert_eq!(0, stats.in_use);
+
+    // Statements on a reused session run on the same backend, unless opted out
+    let pid = postgresql
+        .run_sql("postgres", "SELECT pg_backend_pid()")
+        .await?
+        .rows;
+    assert_eq!(
+        pid,
+        postgresql
+            .run_sql("postgres", "SELECT pg_backend_pid()")
+            .await?
+            .rows
+    );
+    assert_ne!(
+        pid,
+        postgresql
+            .run_sql_in_new_session("postgres", "SELECT pg_backend_pid()")
+            .await?
+            .rows
+    );
+    postgresql.stop().await
+}
+
+#[test(tokio::test)]
+async fn test_session_pool_interleaved_calls() -> Result<()> {
+    let mut postgresql = PostgreSQL::default();
+    postgresql.setup().await?;
+    postgresql.start().await?;
+
+    // Results of varying sizes and durations, so that the statements interleave
+    let statements: Vec<String> = (0..32)
+        .map(|index| {
+            format!(
+                "SELECT {index}, repeat('x', {index} * 1000) FROM pg_sleep({} / 100.0)",
+                index % 3
+            )
+        })
+        .collect();
+    let options = BatchOptions {
+        max_concurrency: 8,
+        ..Default::default()
+    };
+    let batch = postgresql.run_sql_batch("postgres", statements, options);
+    let single = async {
+        let mut results = Vec::new();
+        for value in 0..8 {
+            results.push(
+                postgresql
+                    .query_with_notices(
+                        "postgres",
+                        format!(
+                            "DO $$ BEGIN RAISE NOTICE 'notice {value}'; END $$; SELECT {value}"
+                        ),
+                    )
+                    .await,
+            );
+        }
+        results
+    };
+    let (batch_results, single_results) = tokio::join!(batch, single);
+
+    for (index, result) in batch_results.into_iter().enumerate() {
+        assert_eq!(
+            vec![vec![index.to_string(), "x".repeat(index * 1000)]],
+            result?.rows
+        );
+    }
+    for (value, result) in single_results.into_iter().enumerate() {
+        let result = result?;
+        assert_eq!(vec![vec![value.to_string()]], result.rows);
+        assert_eq!(vec![format!("notice {value}")], result.notices);
+    }
+    let stats = postgresql.session_pool_stats();
+    assert!(stats.spawned <= postgresql.settings().psql_sessions as u64);
+    assert!(stats.reused > 0);
+    postgresql.stop().await
+}
+
+#[test(tokio::test)]
+async fn test_session_pool_resets_sessions() -> Result<()> {
+    let settings = Settings {
+        psql_sessions: 1,
+        ..Default::default()
+    };
+    let mut postgresql = PostgreSQL::from(settings);
+    postgresql.setup().await?;
+    postgresql.start().await?;
+
+    postgresql
+        .run_sql(
+            "postgres",
+            "SET application_name = 'leaked'; BEGIN; CREATE TABLE uncommitted (id int)",
+        )
+        .await?;
+    let application_name = postgresql
+        .run_sql("postgres", "SHOW application_name")
+        .await?;
+    assert_ne!(vec![vec!["leaked".to_string()]], application_name.rows);
+    let tables = postgresql
+        .run_sql("postgres", "SELECT to_regclass('uncommitted') IS NULL")
+        .await?;
+    assert_eq!(vec![vec!["t".to_string()]], tables.rows);
+
+    // A failed statement closes the session, and the next statement uses a new one
+    assert!(postgresql.run_sql("postgres", "SELECT 1/0").await.is_err());
+    assert_eq!(
+        vec![vec!["1".to_string()]],
+        postgresql.run_sql("postgres", "SELECT 1").await?.rows
+    );
+    assert_eq!(1, postgresql.session_pool_stats().idle);
+
+    // Idle sessions do not prevent dropping the database they are connected to
+    postgresql.create_database("pooled").await?;
+    postgresql.run_sql("pooled", "SELECT 1").await?;
+    postgresql.drop_database("pooled").await?;
+    assert!(!postgresql.database_exists("pooled").await?);
+    postgresql.stop().await
+}
+
+#[test(tokio::test)]
+async fn test_session_pool_limits() -> Result<()> {
+    let settings = Settings {
+        psql_session_max_uses: 2,
+        ..Default::default()
+    };
+    let mut postgresql = PostgreSQL::from(settings);
+    postgresql.setup().await?;
+    postgresql.start().await?;
+    let before = postgresql.session_pool_stats();
+    for _ in 0..6 {
+        postgresql.run_sql("postgres", "SELECT 1").await?;
+    }
+    assert_eq!(before.spawned + 3, postgresql.session_pool_stats().spawned);
+    postgresql.stop().await?;
+
+    let settings = Settings {
+        psql_sessions: 0,
+        ..Default::default()
+    };
+    let mut postgresql = PostgreSQL::from(settings);
+    postgresql.setup().await?;
+    postgresql.start().await?;
+    postgresql.run_sql("postgres", "SELECT 1").await?;
+    assert_eq!(0, postgresql.session_pool_stats().spawned);
+    postgresql.stop().await
+}
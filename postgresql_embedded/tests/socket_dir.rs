@@ -0,0 +1,78 @@
+#![cfg(not(target_os = "windows"))]
+
+use postgresql_embedded::{Error, PostgreSQL, Result, Settings};
+use std::fs::{read_to_string, write};
+use std::net::TcpListener;
+use std::process::Command;
+use test_log::test;
+
+/// Get a TCP/IP port that is not in use
+fn free_port() -> Result<u16> {
+    Ok(TcpListener::bind(("127.0.0.1", 0))?.local_addr()?.port())
+}
+
+#[test(tokio::test)]
+async fn test_start_removes_stale_socket_lock_file() -> Result<()> {
+    let port = free_port()?;
+    let settings = Settings {
+        port,
+        ..Default::default()
+    };
+    let mut postgresql = PostgreSQL::new(postgresql_archive::LATEST, settings);
+    postgresql.setup().await?;
+    let socket_path = postgresql.socket_path().expect("socket path");
+    let socket_dir = socket_path.parent().expect("socket directory");
+    assert_ne!(std::env::temp_dir(), socket_dir);
+
+    // Leave the socket lock file of a server that exited uncleanly, owned by a process that is
+    // no longer running
+    let mut exited = Command::new("true").spawn()?;
+    exited.wait()?;
+    std::fs::create_dir_all(socket_dir)?;
+    let lock_file = socket_dir.join(format!(".s.PGSQL.{port}.lock"));
+    write(&lock_file, format!("{}\n/tmp/data\n", exited.id()))?;
+    write(&socket_path, "")?;
+
+    postgresql.start().await?;
+    let postmaster_pid = read_to_string(postgresql.settings().data_dir.join("postmaster.pid"))?;
+    let lock_owner = read_to_string(&lock_file)?;
+    assert_eq!(postmaster_pid.lines().next(), lock_owner.lines().next());
+    let result = postgresql
+        .run_sql("postgres", "SHOW unix_socket_directories")
+        .await?;
+    assert_eq!(
+        vec![vec![socket_dir.to_string_lossy().to_string()]],
+        result.rows
+    );
+    postgresql.stop().await
+}
+
+#[test(tokio::test)]
+async fn test_start_tcp_port_conflict_with_different_socket_dirs() -> Result<()> {
+    let port = free_port()?;
+    let settings = Settings {
+        port,
+        ..Default::default()
+    };
+    let mut first = PostgreSQL::new(postgresql_archive::LATEST, settings);
+    first.setup().await?;
+    first.start().await?;
+
+    let mut second = PostgreSQL::new(
+        postgresql_archive::LATEST,
+        Settings {
+            port,
+            ..Default::default()
+        },
+    );
+    second.setup().await?;
+    assert_ne!(first.socket_path(), second.socket_path());
+    let error = second.start().await.expect_err("port in use");
+    assert!(
+        matches!(error, Error::TcpPortConflictError(_)),
+        "unexpected error: {error:?}"
+    );
+    assert!(error.to_string().contains(&port.to_string()));
+
+    first.stop().await
+}
@@ -0,0 +1,67 @@
+use postgresql_embedded::{Error, PostgreSQL, Result};
+use test_log::test;
+
+#[test(tokio::test)]
+async fn test_managed_tablespace() -> Result<()> {
+    let mut postgresql = PostgreSQL::default();
+    postgresql.setup().await?;
+    postgresql.start().await?;
+
+    let location = postgresql.create_tablespace("fast_space", None).await?;
+    assert!(location.is_dir());
+    let tablespaces = postgresql.list_tablespaces().await?;
+    let tablespace = tablespaces
+        .iter()
+        .find(|tablespace| tablespace.name == "fast_space")
+        .expect("tablespace listed");
+    assert_eq!(Some(&location), tablespace.location.as_ref());
+    assert!(tablespace.managed);
+
+    postgresql.create_database("test").await?;
+    postgresql
+        .run_sql(
+            "test",
+            "CREATE TABLE items (id integer PRIMARY KEY USING INDEX TABLESPACE fast_space) TABLESPACE fast_space;
+            INSERT INTO items SELECT generate_series(1, 10);",
+        )
+        .await?;
+
+    let error = postgresql
+        .drop_tablespace("fast_space", None)
+        .await
+        .expect_err("tablespace not empty");
+    assert!(matches!(error, Error::TablespaceError(_)));
+    assert!(location.is_dir());
+
+    postgresql
+        .drop_tablespace("fast_space", Some("pg_default"))
+        .await?;
+    assert!(!location.exists());
+    let result = postgresql
+        .run_sql("test", "SELECT count(*) FROM items")
+        .await?;
+    assert_eq!(vec![vec!["10".to_string()]], result.rows);
+    assert!(!postgresql
+        .list_tablespaces()
+        .await?
+        .iter()
+        .any(|tablespace| tablespace.name == "fast_space"));
+
+    postgresql.stop().await?;
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_managed_tablespace_removed_on_drop() -> Result<()> {
+    let mut postgresql = PostgreSQL::default();
+    postgresql.setup().await?;
+    postgresql.start().await?;
+
+    let location = postgresql.create_tablespace("fast_space", None).await?;
+    let managed_dir = location.parent().expect("managed directory").to_path_buf();
+    assert!(managed_dir.is_dir());
+
+    drop(postgresql);
+    assert!(!managed_dir.exists());
+    Ok(())
+}
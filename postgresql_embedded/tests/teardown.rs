@@ -0,0 +1,112 @@
+use postgresql_archive::LATEST;
+use postgresql_embedded::{PostgreSQL, Result, Settings, TeardownMode};
+use std::path::Path;
+use std::time::Duration;
+use test_log::test;
+
+const ITEMS: &str = "CREATE TABLE items AS SELECT id, md5(id::text) AS hash
+    FROM generate_series(1, 10000) AS id";
+
+fn persistent_settings(temp_dir: &Path) -> Settings {
+    Settings {
+        data_dir: temp_dir.join("data"),
+        password_file: temp_dir.join(".pgpass"),
+        temporary: false,
+        ..Default::default()
+    }
+}
+
+async fn count_items(settings: Settings) -> Result<String> {
+    let mut postgresql = PostgreSQL::new(LATEST, settings);
+    postgresql.setup().await?;
+    postgresql.start().await?;
+    let rows = postgresql
+        .run_sql("postgres", "SELECT count(*) FROM items")
+        .await?
+        .rows;
+    postgresql.stop().await?;
+    Ok(rows[0][0].clone())
+}
+
+#[test(tokio::test)]
+async fn test_teardown_temporary() -> Result<()> {
+    let mut postgresql = PostgreSQL::default();
+    postgresql.setup().await?;
+    postgresql.start().await?;
+    postgresql.run_sql("postgres", ITEMS).await?;
+    let data_dir = postgresql.settings().data_dir.clone();
+    let password_file = postgresql.settings().password_file.clone();
+
+    let report = postgresql.teardown(None).await?;
+    assert_eq!(TeardownMode::Immediate, report.mode);
+    assert_eq!(None, report.checkpoint);
+    assert!(report.removed);
+    assert!(!data_dir.exists());
+    assert!(!password_file.exists());
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_teardown_temporary_fast() -> Result<()> {
+    let settings = Settings {
+        checkpoint_before_stop: Some(Duration::from_secs(10)),
+        ..Default::default()
+    };
+    let mut postgresql = PostgreSQL::new(LATEST, settings);
+    postgresql.setup().await?;
+    postgresql.start().await?;
+    let data_dir = postgresql.settings().data_dir.clone();
+
+    let report = postgresql.teardown(Some(TeardownMode::Fast)).await?;
+    assert_eq!(TeardownMode::Fast, report.mode);
+    assert!(report.checkpoint.is_some());
+    assert!(report.removed);
+    assert!(!data_dir.exists());
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_teardown_persistent() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let settings = Settings {
+        checkpoint_before_stop: Some(Duration::from_secs(10)),
+        ..persistent_settings(temp_dir.path())
+    };
+    let mut postgresql = PostgreSQL::new(LATEST, settings.clone());
+    postgresql.setup().await?;
+    postgresql.start().await?;
+    postgresql.run_sql("postgres", ITEMS).await?;
+
+    let report = postgresql.teardown(None).await?;
+    assert_eq!(TeardownMode::Fast, report.mode);
+    assert!(report.checkpoint.is_some());
+    assert!(!report.removed);
+    assert!(settings.data_dir.exists());
+    assert!(!settings.data_dir.join("postmaster.pid").exists());
+
+    assert_eq!("10000", count_items(settings).await?);
+    Ok(())
+}
+
+#[test(tokio::test)]
+async fn test_teardown_persistent_immediate() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let settings = Settings {
+        checkpoint_before_stop: Some(Duration::from_secs(10)),
+        ..persistent_settings(temp_dir.path())
+    };
+    let mut postgresql = PostgreSQL::new(LATEST, settings.clone());
+    postgresql.setup().await?;
+    postgresql.start().await?;
+    postgresql.run_sql("postgres", ITEMS).await?;
+
+    // No checkpoint is issued before an immediate shutdown
+    let report = postgresql.teardown(Some(TeardownMode::Immediate)).await?;
+    assert_eq!(TeardownMode::Immediate, report.mode);
+    assert_eq!(None, report.checkpoint);
+    assert!(!report.removed);
+
+    // Committed data survives the crash recovery
+    assert_eq!("10000", count_items(settings).await?);
+    Ok(())
+}
@@ -0,0 +1,35 @@
+use postgresql_archive::LATEST;
+use postgresql_commands::psql::PsqlBuilder;
+use postgresql_commands::CommandBuilder;
+use postgresql_embedded::{PostgreSQL, Result, Settings};
+use test_log::test;
+
+#[test(tokio::test)]
+async fn test_default_text_search_config() -> Result<()> {
+    let settings = Settings {
+        default_text_search_config: Some("pg_catalog.simple".to_string()),
+        ..Default::default()
+    };
+    let mut postgresql = PostgreSQL::new(LATEST, settings);
+    postgresql.setup().await?;
+    postgresql.start().await?;
+
+    // The configuration is set on the existing databases after the start and on new databases
+    postgresql.create_database("test").await?;
+    for database_name in ["postgres", "test"] {
+        let mut psql = PsqlBuilder::from(postgresql.settings())
+            .command("SHOW default_text_search_config")
+            .dbname(database_name)
+            .no_psqlrc()
+            .tuples_only()
+            .no_align()
+            .build();
+        let output = psql.output()?;
+        assert_eq!(
+            "pg_catalog.simple",
+            String::from_utf8_lossy(&output.stdout).trim()
+        );
+    }
+
+    postgresql.stop().await
+}
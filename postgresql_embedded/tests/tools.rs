@@ -0,0 +1,78 @@
+use postgresql_embedded::{Error, PostgreSQL, Result, Settings, Tool};
+use test_log::test;
+
+#[test(tokio::test)]
+async fn test_inventory() -> Result<()> {
+    let mut postgresql = PostgreSQL::default();
+    assert!(!postgresql.inventory().is_scanned());
+    assert!(matches!(
+        postgresql.require_tool(Tool::PgDump),
+        Err(Error::ToolNotFoundError(_))
+    ));
+    postgresql.setup().await?;
+
+    let inventory = postgresql.inventory();
+    assert!(inventory.is_scanned());
+    let info = postgresql.require_tool(Tool::PgDump)?;
+    assert!(info.path.is_absolute());
+    assert!(info.path.starts_with(postgresql.settings().binary_dir()));
+    let version = info.version.as_ref().expect("pg_dump version");
+    assert_eq!(postgresql.version().major, version.major);
+    Ok(())
+}
+
+/// Simulate an installation without vacuumdb by linking every other file of a real installation
+#[cfg(unix)]
+#[test(tokio::test)]
+async fn test_missing_tool() -> Result<()> {
+    use std::os::unix::fs::symlink;
+
+    let mut installed = PostgreSQL::default();
+    installed.setup().await?;
+    let installation_dir = installed.settings().installation_dir.clone();
+
+    let temp_dir = tempfile::tempdir()?;
+    let partial_dir = temp_dir.path().join(installed.version().to_string());
+    std::fs::create_dir_all(partial_dir.join("bin"))?;
+    for entry in std::fs::read_dir(&installation_dir)? {
+        let entry = entry?;
+        if entry.file_name() != "bin" {
+            symlink(entry.path(), partial_dir.join(entry.file_name()))?;
+        }
+    }
+    for entry in std::fs::read_dir(installation_dir.join("bin"))? {
+        let entry = entry?;
+        if entry.file_name() != Tool::VacuumDb.program() {
+            symlink(
+                entry.path(),
+                partial_dir.join("bin").join(entry.file_name()),
+            )?;
+        }
+    }
+
+    let settings = Settings {
+        installation_dir: partial_dir,
+        ..Default::default()
+    };
+    let mut postgresql = PostgreSQL::new(*installed.version(), settings);
+    postgresql.setup().await?;
+    postgresql.start().await?;
+
+    assert!(postgresql.require_tool(Tool::Psql).is_ok());
+    let error = postgresql
+        .require_tool(Tool::VacuumDb)
+        .expect_err("vacuumdb is missing");
+    assert!(matches!(error, Error::ToolNotFoundError(_)));
+
+    // Helpers that run the missing program fail before spawning it
+    let error = postgresql
+        .analyze("postgres", &[])
+        .await
+        .expect_err("vacuumdb is missing");
+    assert!(error
+        .to_string()
+        .contains("vacuumdb is not present in this installation"));
+
+    postgresql.stop().await?;
+    Ok(())
+}
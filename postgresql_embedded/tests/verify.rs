@@ -0,0 +1,19 @@
+use postgresql_embedded::{verify_connection, PostgreSQL, Result};
+use test_log::test;
+
+#[test(tokio::test)]
+async fn test_verify_connection() -> Result<()> {
+    let mut postgresql = PostgreSQL::default();
+    postgresql.setup().await?;
+    postgresql.start().await?;
+
+    let server_info = verify_connection(postgresql.settings()).await?;
+    assert_eq!("postgres", server_info.database);
+    assert!(server_info.start_time <= std::time::SystemTime::now());
+
+    let mut settings = postgresql.settings().clone();
+    settings.password = "invalid".to_string();
+    assert!(verify_connection(&settings).await.is_err());
+
+    postgresql.stop().await
+}
@@ -0,0 +1,95 @@
+use postgresql_archive::LATEST;
+use postgresql_commands::psql::PsqlBuilder;
+use postgresql_commands::CommandBuilder;
+use postgresql_embedded::{PostgreSQL, Result, Settings};
+use test_log::test;
+
+#[test(tokio::test)]
+async fn test_replication_slots() -> Result<()> {
+    let mut postgresql = PostgreSQL::default();
+    postgresql.setup().await?;
+    postgresql.start().await?;
+
+    let slot_exists = |postgresql: &PostgreSQL| -> Result<bool> {
+        let mut psql = PsqlBuilder::from(postgresql.settings())
+            .command("SELECT count(*) FROM pg_replication_slots WHERE slot_name = 'test_slot'")
+            .no_psqlrc()
+            .tuples_only()
+            .no_align()
+            .build();
+        let output = psql.output()?;
+        Ok(String::from_utf8_lossy(&output.stdout).trim() == "1")
+    };
+
+    postgresql.create_physical_slot("test_slot").await?;
+    assert!(slot_exists(&postgresql)?);
+    // Creating an existing slot is a no-op
+    postgresql.create_physical_slot("test_slot").await?;
+    assert!(slot_exists(&postgresql)?);
+
+    postgresql.drop_slot("test_slot").await?;
+    assert!(!slot_exists(&postgresql)?);
+    // Dropping a missing slot is a no-op
+    postgresql.drop_slot("test_slot").await?;
+
+    postgresql.stop().await
+}
+
+#[test(tokio::test)]
+async fn test_wal_housekeeping() -> Result<()> {
+    let settings = Settings {
+        max_wal_size: Some("64MB".to_string()),
+        min_wal_size: Some("32MB".to_string()),
+        wal_keep_size: Some("0".to_string()),
+        ..Default::default()
+    };
+    let mut postgresql = PostgreSQL::new(LATEST, settings);
+    postgresql.setup().await?;
+    postgresql.start().await?;
+
+    let max_wal_size = postgresql.run_sql("postgres", "SHOW max_wal_size").await?;
+    assert_eq!(vec![vec!["64MB".to_string()]], max_wal_size.rows);
+    postgresql
+        .run_sql(
+            "postgres",
+            "SELECT pg_create_physical_replication_slot('wal_slot', true)",
+        )
+        .await?;
+
+    for _ in 0..4 {
+        postgresql
+            .run_sql(
+                "postgres",
+                "CREATE TABLE IF NOT EXISTS wal_table (i int); \
+                 INSERT INTO wal_table SELECT generate_series(1, 1000); \
+                 SELECT pg_switch_wal()",
+            )
+            .await?;
+    }
+    postgresql.run_sql("postgres", "CHECKPOINT").await?;
+
+    let retained = postgresql.wal_usage().await?;
+    assert!(retained.wal_bytes > 0);
+    let slot = &retained.slots[0];
+    assert_eq!("wal_slot", slot.slot_name);
+    assert!(!slot.active);
+    assert!(slot.retained_bytes > 3 * 16 * 1024 * 1024);
+    let retained_segment = retained.oldest_segment.expect("oldest segment");
+
+    let dropped = postgresql
+        .prune_inactive_replication_slots(std::time::Duration::from_secs(3600))
+        .await?;
+    assert!(dropped.is_empty());
+    let dropped = postgresql
+        .prune_inactive_replication_slots(std::time::Duration::ZERO)
+        .await?;
+    assert_eq!(vec!["wal_slot".to_string()], dropped);
+
+    postgresql.run_sql("postgres", "CHECKPOINT").await?;
+    let recycled = postgresql.wal_usage().await?;
+    assert!(recycled.slots.is_empty());
+    assert!(recycled.oldest_segment.expect("oldest segment") > retained_segment);
+
+    postgresql.stop().await?;
+    Ok(())
+}
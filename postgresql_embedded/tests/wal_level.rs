@@ -0,0 +1,69 @@
+use postgresql_archive::LATEST;
+use postgresql_embedded::{Error, PostgreSQL, Result, Settings, WalLevel};
+use test_log::test;
+
+#[test(tokio::test)]
+async fn test_wal_level_logical() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let settings = Settings {
+        data_dir: temp_dir.path().join("data"),
+        password_file: temp_dir.path().join(".pgpass"),
+        temporary: false,
+        ..Default::default()
+    };
+    let mut postgresql = PostgreSQL::new(LATEST, settings.clone());
+    postgresql.setup().await?;
+    postgresql.start().await?;
+    postgresql.create_database("app").await?;
+
+    // Logical replication slots cannot be created at the default replica level
+    assert_eq!("replica", postgresql.get_guc("wal_level").await?);
+    let error = postgresql
+        .create_logical_slot("app", "app_slot", "pgoutput")
+        .await
+        .expect_err("wal_level = replica");
+    assert!(matches!(error, Error::ReplicationSlotError(_)));
+    assert!(error.to_string().contains("wal_level = logical"));
+    assert!(postgresql
+        .run_sql(
+            "app",
+            "SELECT pg_create_logical_replication_slot('app_slot', 'pgoutput')"
+        )
+        .await
+        .is_err());
+    postgresql.stop().await?;
+
+    // The level is written to postgresql.conf before the server is started again
+    let settings = Settings {
+        wal_level: Some(WalLevel::Logical),
+        ..settings
+    };
+    let mut postgresql = PostgreSQL::new(LATEST, settings);
+    postgresql.setup().await?;
+    postgresql.start().await?;
+    assert_eq!("logical", postgresql.get_guc("wal_level").await?);
+    postgresql
+        .create_logical_slot("app", "app_slot", "pgoutput")
+        .await?;
+    // Creating an existing slot does nothing
+    postgresql
+        .create_logical_slot("app", "app_slot", "pgoutput")
+        .await?;
+    let result = postgresql
+        .run_sql(
+            "app",
+            "SELECT slot_type, database, plugin FROM pg_replication_slots \
+             WHERE slot_name = 'app_slot'",
+        )
+        .await?;
+    assert_eq!(
+        vec![vec![
+            "logical".to_string(),
+            "app".to_string(),
+            "pgoutput".to_string()
+        ]],
+        result.rows
+    );
+    postgresql.drop_slot("app_slot").await?;
+    postgresql.stop().await
+}